@@ -1,8 +1,27 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Mint, Transfer};
+use anchor_spl::token_interface::{self, TokenInterface, TokenAccount, Mint, TransferChecked, Revoke, CloseAccount, SyncNative};
+use anchor_spl::token_interface::spl_token_2022::{
+    self,
+    extension::{
+        transfer_fee::{TransferFeeAmount, TransferFeeConfig},
+        BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+    },
+};
+use anchor_spl::memo::{self, BuildMemo, Memo};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_lang::system_program;
 use std::str::FromStr;
 
-declare_id!("CVault111111111111111111111111111111111111111");
+// Account layouts, events, errors, seeds and the program id live in the shared
+// types crate so the backend deserializes exactly what the program writes
+pub use collateral_vault_types::{ID, id, check_id, error::VaultError, events::*, seeds::*, state::*};
+
+/// Crate version of this build; off-chain clients report it alongside the IDL they serve
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Longest memo `withdraw_with_memo` and `transfer_collateral_with_memo`
+/// forward, in bytes
+pub const MAX_MEMO_LEN: usize = 256;
 
 #[program]
 pub mod collateral_vault {
@@ -15,24 +34,50 @@ pub mod collateral_vault {
     /// - Vault PDA derived from user pubkey + constant seed
     /// - Token account owned by vault PDA (not user)
     /// - Initial balances set to zero
-    pub fn initialize_vault(ctx: Context<InitializeVault>, bump: u8) -> Result<()> {
+    /// - The canonical bump found by the seeds constraint is stored, so the
+    ///   vault's signer seeds always derive its address
+    /// 
+    /// `payer` funds the rent of both accounts and may be the user or an
+    /// operator sponsoring the user's onboarding; the user signs either way.
+    /// Closing the vault refunds the rent to the `rent_destination` the user
+    /// names.
+    /// 
+    /// A user may hold several isolated vaults, one per `sub_account_id`, at
+    /// `[VAULT_SEED, user, sub_account_id]`. Each has its own token account,
+    /// balances and authorities, so a loss in one never reaches another.
+    /// Sub-account 0 omits the id from its seeds and is the vault every user
+    /// had before sub-accounts existed.
+    pub fn initialize_vault(ctx: Context<InitializeVault>, sub_account_id: u16) -> Result<()> {
+        // The mint may have gained a fee since it was configured, or the policy tightened
+        check_mint_safety(&ctx.accounts.usdt_mint, &ctx.accounts.config)?;
+        
         let vault = &mut ctx.accounts.vault;
         let clock = Clock::get()?;
         
         vault.user = ctx.accounts.user.key();
         vault.token_account = ctx.accounts.vault_token_account.key();
-        vault.bump = bump;
+        vault.bump = ctx.bumps.vault;
         vault.total_balance = 0;
         vault.locked_balance = 0;
         vault.available_balance = 0;
         vault.last_updated = clock.unix_timestamp;
         vault.is_active = true;
         vault.authority = ctx.accounts.authority.key();
+        vault.created_at = clock.unix_timestamp;
+        vault.counters_since = clock.unix_timestamp;
+        vault.deposit_count = 0;
+        vault.withdraw_count = 0;
+        vault.total_deposited = 0;
+        vault.total_withdrawn = 0;
+        vault.authorized_authorities = Vec::new();
+        vault.sub_account_id = sub_account_id;
         
         emit!(VaultInitialized {
             user: vault.user,
             vault: vault.key(),
             token_account: vault.token_account,
+            sub_account_id,
+            sequence: vault.next_sequence()?,
             timestamp: clock.unix_timestamp,
         });
         
@@ -46,41 +91,89 @@ pub mod collateral_vault {
     /// - User must sign
     /// - Overflow protection on balance updates
     /// - SPL token transfer verification
+    /// 
+    /// Only what reaches the vault token account is credited: with a Token-2022
+    /// transfer fee that is `amount` less the fee, and the event carries the
+    /// credited amount.
     pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        deposit_into_vault(
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.vault_token_account,
+            &ctx.accounts.user_token_account,
+            &ctx.accounts.user,
+            &ctx.accounts.config,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            amount,
+        )
+    }
+
+    /// Deposit into someone else's vault from the signer's own token account
+    /// 
+    /// For exchanges, employers or sponsors funding a user's vault: the
+    /// depositor signs for its tokens and the vault owner does not sign. The
+    /// deposit is credited to the owner's available balance exactly as their
+    /// own would be, and `DepositEvent::depositor` names who paid.
+    pub fn deposit_for(ctx: Context<DepositFor>, amount: u64) -> Result<()> {
+        deposit_into_vault(
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.vault_token_account,
+            &ctx.accounts.depositor_token_account,
+            &ctx.accounts.depositor,
+            &ctx.accounts.config,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            amount,
+        )
+    }
+
+    /// Deposit from the user's associated token account of the vault's mint
+    /// 
+    /// Same as `deposit`, except the source is derived from the user, mint and
+    /// token program rather than chosen by the caller.
+    pub fn deposit_from_associated(ctx: Context<DepositFromAssociated>, amount: u64) -> Result<()> {
+        deposit_into_vault(
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.vault_token_account,
+            &ctx.accounts.user_token_account,
+            &ctx.accounts.user,
+            &ctx.accounts.config,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            amount,
+        )
+    }
+
+    /// Deposit native SOL into a wrapped SOL vault
+    /// 
+    /// The lamports are wrapped in a temporary wSOL account of the user's,
+    /// moved into the vault like any deposit and the emptied account closed
+    /// back to the user, so the user never handles wSOL themselves.
+    pub fn deposit_sol(ctx: Context<DepositSol>, amount: u64) -> Result<()> {
         require!(amount > 0, VaultError::InvalidAmount);
-        require!(ctx.accounts.vault.is_active, VaultError::VaultInactive);
-        
-        let vault = &mut ctx.accounts.vault;
-        let clock = Clock::get()?;
-        
-        // Update vault balances with overflow protection
-        vault.total_balance = vault.total_balance.checked_add(amount)
-            .ok_or(VaultError::Overflow)?;
-        vault.available_balance = vault.available_balance.checked_add(amount)
-            .ok_or(VaultError::Overflow)?;
-        vault.last_updated = clock.unix_timestamp;
         
-        // Perform SPL token transfer from user to vault
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.user_token_account.to_account_info(),
-            to: ctx.accounts.vault_token_account.to_account_info(),
-            authority: ctx.accounts.user.to_account_info(),
+        let cpi_accounts = system_program::Transfer {
+            from: ctx.accounts.user.to_account_info(),
+            to: ctx.accounts.wrapped_sol_account.to_account_info(),
         };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        
-        token::transfer(cpi_ctx, amount)?;
+        system_program::transfer(CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts), amount)?;
+        let cpi_accounts = SyncNative {
+            account: ctx.accounts.wrapped_sol_account.to_account_info(),
+        };
+        token_interface::sync_native(CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts))?;
         
-        emit!(DepositEvent {
-            user: vault.user,
-            vault: vault.key(),
+        deposit_into_vault(
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.vault_token_account,
+            &ctx.accounts.wrapped_sol_account,
+            &ctx.accounts.user,
+            &ctx.accounts.config,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
             amount,
-            new_total_balance: vault.total_balance,
-            new_available_balance: vault.available_balance,
-            timestamp: clock.unix_timestamp,
-        });
+        )?;
         
-        Ok(())
+        close_wrapped_sol(&ctx.accounts.token_program, &ctx.accounts.wrapped_sol_account, &ctx.accounts.user)
     }
 
     /// Withdraw available balance from vault
@@ -91,289 +184,4238 @@ pub mod collateral_vault {
     /// - Amount must be <= available_balance
     /// - Vault remains solvent after withdrawal
     pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        require!(amount <= ctx.accounts.config.max_transaction_amount, VaultError::AmountExceedsLimit);
+        require!(!ctx.accounts.vault.has_withdrawal_cooldown(), VaultError::WithdrawalCooldownActive);
+        
+        withdraw_from_vault(
+            &mut ctx.accounts.vault,
+            &ctx.accounts.vault_token_account,
+            &mut ctx.accounts.user_token_account,
+            ctx.accounts.treasury_token_account.as_mut(),
+            &ctx.accounts.config,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            amount,
+        )
+    }
+
+    /// Withdraw with a memo, such as an exchange deposit reference
+    /// 
+    /// Same as `withdraw`; the memo is forwarded to the SPL Memo program in
+    /// the same transaction and needs `memo_program`.
+    pub fn withdraw_with_memo(ctx: Context<Withdraw>, amount: u64, memo: String) -> Result<()> {
+        let memo_program = ctx.accounts.memo_program.clone();
+        withdraw(ctx, amount)?;
+        
+        forward_memo(memo_program.as_ref(), memo)
+    }
+
+    /// Withdraw the whole available balance, whatever it is when the
+    /// instruction runs
+    /// 
+    /// Saves clients reading `available_balance` and racing a deposit, unlock
+    /// or settlement that changes it before their fixed-amount `withdraw`
+    /// lands. Same checks as `withdraw`: a balance above the per-transaction
+    /// cap is refused rather than partly withdrawn.
+    pub fn withdraw_all(ctx: Context<Withdraw>) -> Result<()> {
+        let amount = ctx.accounts.vault.available_balance;
+        require!(amount <= ctx.accounts.config.max_transaction_amount, VaultError::AmountExceedsLimit);
+        require!(!ctx.accounts.vault.has_withdrawal_cooldown(), VaultError::WithdrawalCooldownActive);
+        
+        withdraw_from_vault(
+            &mut ctx.accounts.vault,
+            &ctx.accounts.vault_token_account,
+            &mut ctx.accounts.user_token_account,
+            ctx.accounts.treasury_token_account.as_mut(),
+            &ctx.accounts.config,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            amount,
+        )
+    }
+
+    /// Withdraw available balance from a wrapped SOL vault as native SOL
+    /// 
+    /// Same checks and fee as `withdraw`. The tokens land in a temporary wSOL
+    /// account of the user's, which is closed to the user, unwrapping them
+    /// into lamports along with the account's rent.
+    pub fn withdraw_sol(ctx: Context<WithdrawSol>, amount: u64) -> Result<()> {
+        require!(amount <= ctx.accounts.config.max_transaction_amount, VaultError::AmountExceedsLimit);
+        require!(!ctx.accounts.vault.has_withdrawal_cooldown(), VaultError::WithdrawalCooldownActive);
+        
+        withdraw_from_vault(
+            &mut ctx.accounts.vault,
+            &ctx.accounts.vault_token_account,
+            &mut ctx.accounts.wrapped_sol_account,
+            ctx.accounts.treasury_token_account.as_mut(),
+            &ctx.accounts.config,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        
+        close_wrapped_sol(&ctx.accounts.token_program, &ctx.accounts.wrapped_sol_account, &ctx.accounts.user)
+    }
+
+    /// Withdraw available balance into the user's associated token account,
+    /// creating it at the user's expense when it does not exist yet
+    /// 
+    /// Same checks and fee as `withdraw`, so a wallet can withdraw without
+    /// creating a token account first.
+    pub fn withdraw_to_associated(ctx: Context<WithdrawToAssociated>, amount: u64) -> Result<()> {
+        require!(amount <= ctx.accounts.config.max_transaction_amount, VaultError::AmountExceedsLimit);
+        require!(!ctx.accounts.vault.has_withdrawal_cooldown(), VaultError::WithdrawalCooldownActive);
+        
+        withdraw_from_vault(
+            &mut ctx.accounts.vault,
+            &ctx.accounts.vault_token_account,
+            &mut ctx.accounts.user_token_account,
+            ctx.accounts.treasury_token_account.as_mut(),
+            &ctx.accounts.config,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            amount,
+        )
+    }
+
+    /// Withdraw above the per-transaction cap, co-signed by the config admin
+    /// 
+    /// Same checks as `withdraw` except the cap; exists so large legitimate
+    /// movements don't require raising the global limit.
+    pub fn withdraw_with_admin_approval(ctx: Context<WithdrawWithAdminApproval>, amount: u64) -> Result<()> {
+        require!(!ctx.accounts.vault.has_withdrawal_cooldown(), VaultError::WithdrawalCooldownActive);
+        
+        withdraw_from_vault(
+            &mut ctx.accounts.vault,
+            &ctx.accounts.vault_token_account,
+            &mut ctx.accounts.user_token_account,
+            ctx.accounts.treasury_token_account.as_mut(),
+            &ctx.accounts.config,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        
+        emit!(LimitOverrideApproved {
+            admin: ctx.accounts.admin.key(),
+            vault: ctx.accounts.vault.key(),
+            amount,
+            max_transaction_amount: ctx.accounts.config.max_transaction_amount,
+            sequence: ctx.accounts.vault.next_sequence()?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Raise the vault's withdrawal cooldown, or set one for the first time
+    /// 
+    /// Once set, `withdraw` is refused: withdrawals go through
+    /// `request_withdrawal` and `execute_withdrawal`, so a stolen owner key
+    /// cannot drain the vault before the request is noticed and cancelled.
+    /// Lowering the cooldown would undo that, so it takes
+    /// `set_withdrawal_cooldown_with_admin_approval`.
+    pub fn set_withdrawal_cooldown(ctx: Context<SetWithdrawalCooldown>, cooldown_seconds: u32) -> Result<()> {
+        require!(cooldown_seconds >= ctx.accounts.vault.withdrawal_cooldown, 
+                 VaultError::CooldownDecreaseNeedsApproval);
+        
+        update_withdrawal_cooldown(&mut ctx.accounts.vault, cooldown_seconds, None)
+    }
+
+    /// Set the vault's withdrawal cooldown to any value, 0 to remove it,
+    /// co-signed by the config admin
+    pub fn set_withdrawal_cooldown_with_admin_approval(
+        ctx: Context<SetWithdrawalCooldownWithAdminApproval>,
+        cooldown_seconds: u32,
+    ) -> Result<()> {
+        let admin = ctx.accounts.admin.key();
+        update_withdrawal_cooldown(&mut ctx.accounts.vault, cooldown_seconds, Some(admin))
+    }
+
+    /// Start a withdrawal that `execute_withdrawal` completes once the vault's
+    /// cooldown has elapsed
+    /// 
+    /// A vault holds one request at a time; a new one replaces it and restarts
+    /// the cooldown. The amount stays available, and so lockable, until the
+    /// withdrawal executes.
+    pub fn request_withdrawal(ctx: Context<RequestWithdrawal>, amount: u64) -> Result<()> {
         require!(amount > 0, VaultError::InvalidAmount);
-        require!(ctx.accounts.vault.is_active, VaultError::VaultInactive);
+        require!(amount <= ctx.accounts.config.max_transaction_amount, VaultError::AmountExceedsLimit);
         
         let vault = &mut ctx.accounts.vault;
-        let clock = Clock::get()?;
-        
-        // Ensure sufficient available balance
         require!(vault.available_balance >= amount, VaultError::InsufficientAvailableBalance);
         
-        // Update balances with underflow protection
-        vault.total_balance = vault.total_balance.checked_sub(amount)
-            .ok_or(VaultError::Underflow)?;
-        vault.available_balance = vault.available_balance.checked_sub(amount)
-            .ok_or(VaultError::Underflow)?;
+        let clock = Clock::get()?;
+        let unlocks_at = clock.unix_timestamp.checked_add(vault.withdrawal_cooldown as i64)
+            .ok_or(VaultError::Overflow)?;
+        vault.pending_withdrawal = amount;
+        vault.withdrawal_unlocks_at = unlocks_at;
         vault.last_updated = clock.unix_timestamp;
         
-        // Transfer tokens from vault to user
-        let vault_key = vault.key();
-        let bump = vault.bump;
-        let signer_seeds = &[
-            b"vault",
-            vault.user.as_ref(),
-            &[bump],
-        ];
-        let signer = &[&signer_seeds[..]];
+        emit!(WithdrawalRequested {
+            user: vault.user,
+            vault: vault.key(),
+            amount,
+            unlocks_at,
+            sequence: vault.next_sequence()?,
+            timestamp: clock.unix_timestamp,
+        });
         
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.vault_token_account.to_account_info(),
-            to: ctx.accounts.user_token_account.to_account_info(),
-            authority: ctx.accounts.vault.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        Ok(())
+    }
+
+    /// Drop the vault's pending withdrawal
+    /// 
+    /// Security: the owner or the config admin may cancel, so a request the
+    /// owner did not make can be stopped before it unlocks
+    pub fn cancel_withdrawal(ctx: Context<CancelWithdrawal>) -> Result<()> {
+        let canceller = ctx.accounts.canceller.key();
+        let vault = &mut ctx.accounts.vault;
+        require!(canceller == vault.user || canceller == ctx.accounts.config.admin, 
+                 VaultError::UnauthorizedCaller);
+        require!(vault.pending_withdrawal > 0, VaultError::NoPendingWithdrawal);
         
-        token::transfer(cpi_ctx, amount)?;
+        let clock = Clock::get()?;
+        let amount = vault.pending_withdrawal;
+        vault.pending_withdrawal = 0;
+        vault.withdrawal_unlocks_at = 0;
+        vault.last_updated = clock.unix_timestamp;
         
-        emit!(WithdrawEvent {
+        emit!(WithdrawalCancelled {
             user: vault.user,
             vault: vault.key(),
             amount,
-            new_total_balance: vault.total_balance,
-            new_available_balance: vault.available_balance,
+            cancelled_by: canceller,
+            sequence: vault.next_sequence()?,
             timestamp: clock.unix_timestamp,
         });
         
         Ok(())
     }
 
-    /// Lock collateral for trading positions (CPI-only)
+    /// Complete the vault's pending withdrawal once its cooldown has elapsed
     /// 
-    /// Security: Only authorized trading program can call this
-    /// Prevents double-spending of collateral
-    pub fn lock_collateral(ctx: Context<LockCollateral>, amount: u64) -> Result<()> {
-        require!(amount > 0, VaultError::InvalidAmount);
-        require!(ctx.accounts.vault.is_active, VaultError::VaultInactive);
+    /// Same checks as `withdraw` on the requested amount, which must still be
+    /// available.
+    pub fn execute_withdrawal(ctx: Context<Withdraw>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        require!(vault.pending_withdrawal > 0, VaultError::NoPendingWithdrawal);
+        require!(vault.withdrawal_unlocked(Clock::get()?.unix_timestamp), 
+                 VaultError::WithdrawalStillCoolingDown);
         
-        // Verify caller is authorized trading program
-        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, 
-                 VaultError::UnauthorizedCaller);
+        let amount = vault.pending_withdrawal;
+        require!(amount <= ctx.accounts.config.max_transaction_amount, VaultError::AmountExceedsLimit);
+        vault.pending_withdrawal = 0;
+        vault.withdrawal_unlocks_at = 0;
         
+        withdraw_from_vault(
+            vault,
+            &ctx.accounts.vault_token_account,
+            &mut ctx.accounts.user_token_account,
+            ctx.accounts.treasury_token_account.as_mut(),
+            &ctx.accounts.config,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            amount,
+        )
+    }
+
+    /// Freeze the vault as a panic button when the owner suspects their key
+    /// is compromised
+    /// 
+    /// A frozen vault refuses withdrawals, transfers of its available balance
+    /// and new locks; deposits, unlocks and settlements go on. Freezing again cancels a pending unfreeze request, so
+    /// an attacker holding the key cannot outwait an owner who is watching.
+    pub fn freeze_vault(ctx: Context<SetVaultFreeze>) -> Result<()> {
+        let clock = Clock::get()?;
         let vault = &mut ctx.accounts.vault;
+        vault.frozen = true;
+        vault.unfreeze_requested_at = 0;
+        vault.last_updated = clock.unix_timestamp;
+        
+        emit!(VaultFrozen {
+            user: vault.user,
+            vault: vault.key(),
+            sequence: vault.next_sequence()?,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Ask to unfreeze the vault; `unfreeze_vault` completes it once
+    /// `Vault::UNFREEZE_DELAY` has passed
+    pub fn request_unfreeze(ctx: Context<SetVaultFreeze>) -> Result<()> {
         let clock = Clock::get()?;
+        let vault = &mut ctx.accounts.vault;
+        require!(vault.frozen, VaultError::VaultNotFrozen);
         
-        // Ensure sufficient available balance
-        require!(vault.available_balance >= amount, VaultError::InsufficientAvailableBalance);
+        vault.unfreeze_requested_at = clock.unix_timestamp;
+        vault.last_updated = clock.unix_timestamp;
         
-        // Atomically update balances
-        vault.available_balance = vault.available_balance.checked_sub(amount)
-            .ok_or(VaultError::Underflow)?;
-        vault.locked_balance = vault.locked_balance.checked_add(amount)
-            .ok_or(VaultError::Overflow)?;
+        emit!(UnfreezeRequested {
+            user: vault.user,
+            vault: vault.key(),
+            unfreezes_at: clock.unix_timestamp.saturating_add(Vault::UNFREEZE_DELAY),
+            sequence: vault.next_sequence()?,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Unfreeze the vault once a requested unfreeze's delay has passed
+    pub fn unfreeze_vault(ctx: Context<SetVaultFreeze>) -> Result<()> {
+        let clock = Clock::get()?;
+        let vault = &mut ctx.accounts.vault;
+        require!(vault.frozen, VaultError::VaultNotFrozen);
+        require!(vault.unfreeze_due(clock.unix_timestamp), VaultError::UnfreezeNotDue);
+        
+        vault.frozen = false;
+        vault.unfreeze_requested_at = 0;
         vault.last_updated = clock.unix_timestamp;
         
-        emit!(CollateralLocked {
+        emit!(VaultUnfrozen {
             user: vault.user,
             vault: vault.key(),
-            amount,
-            new_available_balance: vault.available_balance,
-            new_locked_balance: vault.locked_balance,
+            sequence: vault.next_sequence()?,
             timestamp: clock.unix_timestamp,
         });
         
         Ok(())
     }
 
+    /// Lock collateral for trading positions (CPI-only)
+    /// 
+    /// Security: Only authorized trading program can call this
+    /// Prevents double-spending of collateral
+    /// 
+    /// Locks that would leave more than the config's `max_lock_ratio_bps` of the
+    /// vault's total balance locked, more than the balance is worth after its
+    /// mint's haircut, or less than the config's `min_free_balance`
+    /// available, are rejected, whatever margin the calling program computed.
+    /// Together they keep a vault healthy: a trading program cannot lock it
+    /// down to no free collateral.
+    pub fn lock_collateral(ctx: Context<LockCollateral>, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        require!(ctx.accounts.vault.is_active, VaultError::VaultInactive);
+        
+        // Verify caller is authorized trading program
+        require!(ctx.accounts.vault.is_authorized(&ctx.accounts.authority.key()), 
+                 VaultError::UnauthorizedCaller);
+        
+        lock_available(&mut ctx.accounts.vault, &ctx.accounts.config, &ctx.accounts.asset_config, amount)
+    }
+
     /// Unlock collateral when positions are closed (CPI-only)
     /// 
     /// Security: Only authorized trading program can unlock
-    /// Amount must not exceed locked balance
+    /// Amount must not exceed the locked balance no lock record holds;
+    /// recorded collateral is unlocked through `release_lock`
     pub fn unlock_collateral(ctx: Context<UnlockCollateral>, amount: u64) -> Result<()> {
         require!(amount > 0, VaultError::InvalidAmount);
         require!(ctx.accounts.vault.is_active, VaultError::VaultInactive);
         
         // Verify caller is authorized trading program
-        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, 
+        require!(ctx.accounts.vault.is_authorized(&ctx.accounts.authority.key()), 
                  VaultError::UnauthorizedCaller);
+        ctx.accounts.vault.check_unrecorded_locked(amount)?;
         
-        let vault = &mut ctx.accounts.vault;
-        let clock = Clock::get()?;
+        unlock_locked(&mut ctx.accounts.vault, amount)
+    }
+
+    /// Transfer collateral between vaults (authorized internal settlement)
+    /// 
+    /// Security: Only authorized programs can transfer
+    /// Both vaults must be active
+    /// Source must have sufficient locked balance no lock record holds;
+    /// recorded collateral moves through `transfer_from_lock`
+    pub fn transfer_collateral(ctx: Context<TransferCollateral>, amount: u64) -> Result<()> {
+        require!(amount <= ctx.accounts.config.max_transaction_amount, VaultError::AmountExceedsLimit);
         
-        // Ensure sufficient locked balance
-        require!(vault.locked_balance >= amount, VaultError::InsufficientLockedBalance);
+        // Verify caller is authorized
+        require!(ctx.accounts.source_vault.is_authorized(&ctx.accounts.authority.key()), 
+                 VaultError::UnauthorizedCaller);
+        ctx.accounts.source_vault.check_unrecorded_locked(amount)?;
         
-        // Atomically update balances
-        vault.locked_balance = vault.locked_balance.checked_sub(amount)
-            .ok_or(VaultError::Underflow)?;
-        vault.available_balance = vault.available_balance.checked_add(amount)
-            .ok_or(VaultError::Overflow)?;
-        vault.last_updated = clock.unix_timestamp;
+        transfer_between_vaults(
+            &mut ctx.accounts.source_vault,
+            &mut ctx.accounts.destination_vault,
+            &ctx.accounts.source_token_account,
+            &mut ctx.accounts.destination_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            amount,
+            true,
+        )
+    }
+
+    /// Transfer collateral between vaults with a memo, forwarded as for
+    /// `withdraw_with_memo`
+    pub fn transfer_collateral_with_memo(ctx: Context<TransferCollateral>, amount: u64, memo: String) -> Result<()> {
+        let memo_program = ctx.accounts.memo_program.clone();
+        transfer_collateral(ctx, amount)?;
         
-        emit!(CollateralUnlocked {
-            user: vault.user,
-            vault: vault.key(),
+        forward_memo(memo_program.as_ref(), memo)
+    }
+
+    /// Transfer collateral from the owner's available balance to another vault
+    /// 
+    /// The peer-to-peer counterpart of `transfer_collateral`, which moves only
+    /// locked collateral at an authority's request. Security checks:
+    /// - The source vault's owner must sign
+    /// - Both vaults must be active and the per-transaction cap applies
+    /// - Like `withdraw`, refused while the source has a withdrawal cooldown
+    ///   or is frozen
+    pub fn transfer_available_collateral(ctx: Context<TransferAvailableCollateral>, amount: u64) -> Result<()> {
+        require!(amount <= ctx.accounts.config.max_transaction_amount, VaultError::AmountExceedsLimit);
+        require!(!ctx.accounts.source_vault.has_withdrawal_cooldown(), VaultError::WithdrawalCooldownActive);
+        
+        transfer_between_vaults(
+            &mut ctx.accounts.source_vault,
+            &mut ctx.accounts.destination_vault,
+            &ctx.accounts.source_token_account,
+            &mut ctx.accounts.destination_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
             amount,
-            new_available_balance: vault.available_balance,
-            new_locked_balance: vault.locked_balance,
-            timestamp: clock.unix_timestamp,
+            false,
+        )
+    }
+
+    /// Transfer above the per-transaction cap, co-signed by the config admin
+    pub fn transfer_collateral_with_admin_approval(
+        ctx: Context<TransferCollateralWithAdminApproval>,
+        amount: u64,
+    ) -> Result<()> {
+        // Verify caller is authorized
+        require!(ctx.accounts.source_vault.is_authorized(&ctx.accounts.authority.key()), 
+                 VaultError::UnauthorizedCaller);
+        ctx.accounts.source_vault.check_unrecorded_locked(amount)?;
+        
+        transfer_between_vaults(
+            &mut ctx.accounts.source_vault,
+            &mut ctx.accounts.destination_vault,
+            &ctx.accounts.source_token_account,
+            &mut ctx.accounts.destination_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            amount,
+            true,
+        )?;
+        
+        emit!(LimitOverrideApproved {
+            admin: ctx.accounts.admin.key(),
+            vault: ctx.accounts.source_vault.key(),
+            amount,
+            max_transaction_amount: ctx.accounts.config.max_transaction_amount,
+            sequence: ctx.accounts.source_vault.next_sequence()?,
+            timestamp: Clock::get()?.unix_timestamp,
         });
         
         Ok(())
     }
 
-    /// Transfer collateral between vaults (authorized internal settlement)
+    /// Move locked collateral into escrow for another vault to accept (CPI-only)
     /// 
-    /// Security: Only authorized programs can transfer
-    /// Both vaults must be active
-    /// Source must have sufficient locked balance
-    pub fn transfer_collateral(ctx: Context<TransferCollateral>, amount: u64) -> Result<()> {
+    /// The two-step form of `transfer_collateral` for settlements the
+    /// destination must confirm. Same checks, and the source is debited at
+    /// once, but the funds land in an escrow token account rather than the
+    /// destination vault. The destination's owner takes them with
+    /// `accept_transfer` before `expires_at`; until then the initiating
+    /// authority may `cancel_transfer`, and after it anyone may.
+    pub fn initiate_transfer(
+        ctx: Context<InitiateTransfer>,
+        transfer_id: u64,
+        amount: u64,
+        expires_at: i64,
+    ) -> Result<()> {
         require!(amount > 0, VaultError::InvalidAmount);
-        require!(ctx.accounts.source_vault.is_active, VaultError::VaultInactive);
-        require!(ctx.accounts.destination_vault.is_active, VaultError::VaultInactive);
-        
-        // Verify caller is authorized
-        require!(ctx.accounts.authority.key() == ctx.accounts.source_vault.authority, 
+        require!(amount <= ctx.accounts.config.max_transaction_amount, VaultError::AmountExceedsLimit);
+        require!(ctx.accounts.source_vault.is_authorized(&ctx.accounts.authority.key()), 
                  VaultError::UnauthorizedCaller);
         
-        let source_vault = &mut ctx.accounts.source_vault;
-        let destination_vault = &mut ctx.accounts.destination_vault;
         let clock = Clock::get()?;
+        require!(expires_at > clock.unix_timestamp, VaultError::InvalidEscrowExpiry);
         
-        // Ensure source has sufficient locked balance for transfer
-        require!(source_vault.locked_balance >= amount, VaultError::InsufficientLockedBalance);
+        let escrowed = pay_out_locked(
+            &mut ctx.accounts.source_vault,
+            &ctx.accounts.source_token_account,
+            &mut ctx.accounts.escrow_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
         
-        // Update source vault (reduce locked, don't affect available)
-        source_vault.locked_balance = source_vault.locked_balance.checked_sub(amount)
-            .ok_or(VaultError::Underflow)?;
-        source_vault.total_balance = source_vault.total_balance.checked_sub(amount)
-            .ok_or(VaultError::Underflow)?;
-        source_vault.last_updated = clock.unix_timestamp;
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.source_vault = ctx.accounts.source_vault.key();
+        escrow.destination_vault = ctx.accounts.destination_vault.key();
+        escrow.transfer_id = transfer_id;
+        escrow.authority = ctx.accounts.authority.key();
+        escrow.mint = ctx.accounts.mint.key();
+        escrow.amount = escrowed;
+        escrow.created_at = clock.unix_timestamp;
+        escrow.expires_at = expires_at;
+        escrow.payer = ctx.accounts.payer.key();
+        escrow.bump = ctx.bumps.escrow;
+        ctx.accounts.source_vault.count_record_opened()?;
+        
+        emit!(TransferInitiated {
+            source_user: ctx.accounts.source_vault.user,
+            source_vault: escrow.source_vault,
+            destination_vault: escrow.destination_vault,
+            escrow: escrow.key(),
+            transfer_id,
+            authority: escrow.authority,
+            amount,
+            escrowed,
+            expires_at,
+            sequence: ctx.accounts.source_vault.next_sequence()?,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Take an escrowed transfer into the destination vault (destination owner only)
+    /// 
+    /// Must come before the escrow expires. The vault is credited what
+    /// reaches its token account, net of any Token-2022 transfer fee.
+    pub fn accept_transfer(ctx: Context<AcceptTransfer>) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(!ctx.accounts.escrow.is_expired(clock.unix_timestamp), VaultError::EscrowExpired);
+        
+        let received = pay_out_escrow(
+            &ctx.accounts.escrow,
+            &ctx.accounts.escrow_token_account,
+            &mut ctx.accounts.destination_token_account,
+            &ctx.accounts.payer,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+        )?;
+        ctx.accounts.source_vault.count_record_closed();
         
-        // Update destination vault (increase available and total)
-        destination_vault.total_balance = destination_vault.total_balance.checked_add(amount)
+        let vault = &mut ctx.accounts.destination_vault;
+        vault.total_balance = vault.total_balance.checked_add(received)
             .ok_or(VaultError::Overflow)?;
-        destination_vault.available_balance = destination_vault.available_balance.checked_add(amount)
+        vault.available_balance = vault.available_balance.checked_add(received)
             .ok_or(VaultError::Overflow)?;
-        destination_vault.last_updated = clock.unix_timestamp;
-        
-        // Perform actual token transfer
-        let source_key = source_vault.key();
-        let source_bump = source_vault.bump;
-        let source_seeds = &[
-            b"vault",
-            source_vault.user.as_ref(),
-            &[source_bump],
-        ];
-        let signer = &[&source_seeds[..]];
+        vault.last_updated = clock.unix_timestamp;
+        vault.validate_invariant()?;
         
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.source_token_account.to_account_info(),
-            to: ctx.accounts.destination_token_account.to_account_info(),
-            authority: source_vault.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        let escrow = &ctx.accounts.escrow;
+        emit!(TransferAccepted {
+            destination_user: vault.user,
+            source_vault: escrow.source_vault,
+            destination_vault: vault.key(),
+            escrow: escrow.key(),
+            transfer_id: escrow.transfer_id,
+            amount: received,
+            sequence: vault.next_sequence()?,
+            timestamp: clock.unix_timestamp,
+        });
         
-        token::transfer(cpi_ctx, amount)?;
+        Ok(())
+    }
+
+    /// Return an escrowed transfer to the source vault's available balance
+    /// 
+    /// Before expiry only the initiating authority, or the source vault's
+    /// `authority`, may cancel; once expired anyone may, so an unaccepted
+    /// transfer never strands the funds.
+    pub fn cancel_transfer(ctx: Context<CancelTransfer>) -> Result<()> {
+        let clock = Clock::get()?;
+        let escrow = &ctx.accounts.escrow;
+        let expired = escrow.is_expired(clock.unix_timestamp);
+        require!(expired || escrow.can_cancel(&ctx.accounts.source_vault, &ctx.accounts.caller.key()),
+                 VaultError::UnauthorizedCaller);
         
-        emit!(CollateralTransferred {
-            source_user: source_vault.user,
-            destination_user: destination_vault.user,
-            source_vault: source_vault.key(),
-            destination_vault: destination_vault.key(),
-            amount,
+        let received = pay_out_escrow(
+            escrow,
+            &ctx.accounts.escrow_token_account,
+            &mut ctx.accounts.source_token_account,
+            &ctx.accounts.payer,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+        )?;
+        
+        let vault = &mut ctx.accounts.source_vault;
+        vault.total_balance = vault.total_balance.checked_add(received)
+            .ok_or(VaultError::Overflow)?;
+        vault.available_balance = vault.available_balance.checked_add(received)
+            .ok_or(VaultError::Overflow)?;
+        vault.last_updated = clock.unix_timestamp;
+        vault.validate_invariant()?;
+        vault.count_record_closed();
+        
+        emit!(TransferCancelled {
+            source_user: vault.user,
+            source_vault: vault.key(),
+            destination_vault: escrow.destination_vault,
+            escrow: escrow.key(),
+            transfer_id: escrow.transfer_id,
+            amount: received,
+            cancelled_by: ctx.accounts.caller.key(),
+            expired,
+            sequence: vault.next_sequence()?,
             timestamp: clock.unix_timestamp,
         });
         
         Ok(())
     }
-}
-
-#[account]
-#[derive(Debug)]
-pub struct Vault {
-    pub user: Pubkey,                    // Owner of the vault
-    pub token_account: Pubkey,          // Associated token account
-    pub bump: u8,                       // PDA bump seed
-    pub total_balance: u64,            // Total USDT balance
-    pub locked_balance: u64,           // Locked for positions
-    pub available_balance: u64,        // Available for withdrawal
-    pub last_updated: i64,             // Last update timestamp
-    pub is_active: bool,              // Vault status
-    pub authority: Pubkey,             // Authorized programs for CPI calls
-}
 
-impl Vault {
-    pub const SIZE: usize = 32 + 32 + 1 + 8 + 8 + 8 + 8 + 1 + 32 + 32; // Account size
-    
-    /// Critical invariant: available_balance + locked_balance == total_balance
-    pub fn validate_invariant(&self) -> Result<()> {
-        let calculated_total = self.available_balance.checked_add(self.locked_balance)
-            .ok_or(VaultError::Overflow)?;
-        require!(calculated_total == self.total_balance, VaultError::InvariantViolated);
+    /// Lock collateral under a lock record of its own (CPI-only)
+    /// 
+    /// Same checks as `lock_collateral`; the record at `[LOCK_SEED, vault,
+    /// lock_id]` keeps the amount, the locking authority and `purpose`, so
+    /// `release_lock` and `transfer_from_lock` act on this lock alone.
+    /// `payer` funds the record's rent and gets it back when it closes.
+    /// 
+    /// A nonzero `expires_at` lets anyone return the lock to the available
+    /// balance from then on through `expire_lock`, so collateral is not
+    /// stranded by a program that never unlocks.
+    pub fn open_lock(
+        ctx: Context<OpenLock>,
+        lock_id: u64,
+        amount: u64,
+        purpose: [u8; 32],
+        expires_at: i64,
+    ) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        require!(ctx.accounts.vault.is_authorized(&ctx.accounts.authority.key()), 
+                 VaultError::UnauthorizedCaller);
+        
+        let clock = Clock::get()?;
+        require!(expires_at == 0 || expires_at > clock.unix_timestamp, VaultError::InvalidLockExpiry);
+        
+        lock_available(&mut ctx.accounts.vault, &ctx.accounts.config, &ctx.accounts.asset_config, amount)?;
+        ctx.accounts.vault.record_lock(amount)?;
+        ctx.accounts.vault.count_record_opened()?;
+        
+        let lock_record = &mut ctx.accounts.lock_record;
+        lock_record.vault = ctx.accounts.vault.key();
+        lock_record.lock_id = lock_id;
+        lock_record.authority = ctx.accounts.authority.key();
+        lock_record.purpose = purpose;
+        lock_record.amount = amount;
+        lock_record.created_at = clock.unix_timestamp;
+        lock_record.payer = ctx.accounts.payer.key();
+        lock_record.bump = ctx.bumps.lock_record;
+        lock_record.expires_at = expires_at;
+        
+        emit!(LockOpened {
+            user: ctx.accounts.vault.user,
+            vault: lock_record.vault,
+            lock: lock_record.key(),
+            lock_id,
+            authority: lock_record.authority,
+            purpose,
+            amount,
+            expires_at,
+            sequence: ctx.accounts.vault.next_sequence()?,
+            timestamp: clock.unix_timestamp,
+        });
+        
         Ok(())
     }
-}
 
-#[derive(Accounts)]
+    /// Unlock part or all of a lock record's collateral (CPI-only)
+    /// 
+    /// Security: only the authority that opened the lock may release it, or
+    /// the vault's `authority` once that key is no longer authorized
+    pub fn release_lock(ctx: Context<ReleaseLock>, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        require!(ctx.accounts.lock_record.can_release(&ctx.accounts.vault, &ctx.accounts.authority.key()), 
+                 VaultError::UnauthorizedCaller);
+        
+        ctx.accounts.vault.release_recorded_lock(amount)?;
+        unlock_locked(&mut ctx.accounts.vault, amount)?;
+        release_lock_record(
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.lock_record,
+            &ctx.accounts.payer,
+            ctx.accounts.authority.key(),
+            amount,
+            None,
+        )
+    }
+
+    /// Return an expired lock to the vault's available balance and close its
+    /// record (permissionless)
+    /// 
+    /// Liquidation and loss settlement never reach recorded collateral, but a
+    /// force unlock may have returned part of it already; only what the
+    /// vault still has recorded returns, so collateral backing other locks
+    /// stays locked.
+    pub fn expire_lock(ctx: Context<ExpireLock>) -> Result<()> {
+        let clock = Clock::get()?;
+        let lock_record = &ctx.accounts.lock_record;
+        require!(lock_record.is_expired(clock.unix_timestamp), VaultError::LockNotExpired);
+        
+        let amount = lock_record.amount.min(ctx.accounts.vault.recorded_locked_balance);
+        if amount > 0 {
+            ctx.accounts.vault.release_recorded_lock(amount)?;
+            unlock_locked(&mut ctx.accounts.vault, amount)?;
+        }
+        ctx.accounts.vault.count_record_closed();
+        
+        emit!(LockExpired {
+            user: ctx.accounts.vault.user,
+            vault: ctx.accounts.vault.key(),
+            lock: lock_record.key(),
+            lock_id: lock_record.lock_id,
+            amount,
+            expires_at: lock_record.expires_at,
+            expired_by: ctx.accounts.caller.key(),
+            sequence: ctx.accounts.vault.next_sequence()?,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Announce a force unlock of the vault's locked collateral (admin only)
+    /// 
+    /// Escape hatch for when the trading program is bricked or its authority
+    /// key is lost and the collateral would otherwise stay locked forever.
+    /// `execute_force_unlock` returns it to available once
+    /// `ForceUnlock::DELAY` has passed; the announcement is public, and any of
+    /// the vault's authorities may cancel it in the meantime.
+    pub fn announce_force_unlock(ctx: Context<AnnounceForceUnlock>, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        require!(ctx.accounts.vault.locked_balance >= amount, VaultError::InsufficientLockedBalance);
+        
+        let clock = Clock::get()?;
+        let executable_at = clock.unix_timestamp.checked_add(ForceUnlock::DELAY)
+            .ok_or(VaultError::Overflow)?;
+        let force_unlock = &mut ctx.accounts.force_unlock;
+        force_unlock.vault = ctx.accounts.vault.key();
+        force_unlock.admin = ctx.accounts.admin.key();
+        force_unlock.amount = amount;
+        force_unlock.announced_at = clock.unix_timestamp;
+        force_unlock.executable_at = executable_at;
+        force_unlock.bump = ctx.bumps.force_unlock;
+        
+        let vault = &mut ctx.accounts.vault;
+        vault.count_record_opened()?;
+        emit!(ForceUnlockAnnounced {
+            user: vault.user,
+            vault: vault.key(),
+            admin: ctx.accounts.admin.key(),
+            amount,
+            executable_at,
+            sequence: vault.next_sequence()?,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Return an announced force unlock's collateral to available once its
+    /// delay has passed (admin only)
+    /// 
+    /// Only what is still locked returns, at most the announced amount.
+    /// Collateral no lock record holds returns first; the rest comes out of
+    /// what records hold. The records are left as they are, and their holders
+    /// can no longer release more than the vault still has recorded.
+    pub fn execute_force_unlock(ctx: Context<ExecuteForceUnlock>) -> Result<()> {
+        let clock = Clock::get()?;
+        let force_unlock = &ctx.accounts.force_unlock;
+        require!(force_unlock.is_executable(clock.unix_timestamp), VaultError::ForceUnlockNotDue);
+        
+        let amount = force_unlock.amount.min(ctx.accounts.vault.locked_balance);
+        if amount > 0 {
+            let recorded = amount.saturating_sub(ctx.accounts.vault.unrecorded_locked_balance());
+            ctx.accounts.vault.release_recorded_lock(recorded)?;
+            unlock_locked(&mut ctx.accounts.vault, amount)?;
+        }
+        
+        let vault = &mut ctx.accounts.vault;
+        vault.count_record_closed();
+        emit!(ForceUnlockExecuted {
+            user: vault.user,
+            vault: vault.key(),
+            admin: ctx.accounts.admin.key(),
+            amount,
+            sequence: vault.next_sequence()?,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Drop an announced force unlock
+    /// 
+    /// Security: the config admin or any of the vault's authorities may
+    /// cancel; an authority cancelling shows the trading program is still in
+    /// control of its locks.
+    pub fn cancel_force_unlock(ctx: Context<CancelForceUnlock>) -> Result<()> {
+        let canceller = ctx.accounts.canceller.key();
+        require!(canceller == ctx.accounts.config.admin || ctx.accounts.vault.is_authorized(&canceller),
+                 VaultError::UnauthorizedCaller);
+        
+        let amount = ctx.accounts.force_unlock.amount;
+        let vault = &mut ctx.accounts.vault;
+        vault.count_record_closed();
+        emit!(ForceUnlockCancelled {
+            user: vault.user,
+            vault: vault.key(),
+            cancelled_by: canceller,
+            amount,
+            sequence: vault.next_sequence()?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Transfer part or all of a lock record's collateral to another vault (CPI-only)
+    /// 
+    /// Same checks as `transfer_collateral`, with the lock's release rights in
+    /// place of the vault's authority check.
+    pub fn transfer_from_lock(ctx: Context<TransferFromLock>, amount: u64) -> Result<()> {
+        require!(amount <= ctx.accounts.config.max_transaction_amount, VaultError::AmountExceedsLimit);
+        require!(ctx.accounts.lock_record.can_release(&ctx.accounts.source_vault, &ctx.accounts.authority.key()), 
+                 VaultError::UnauthorizedCaller);
+        
+        ctx.accounts.source_vault.release_recorded_lock(amount)?;
+        transfer_between_vaults(
+            &mut ctx.accounts.source_vault,
+            &mut ctx.accounts.destination_vault,
+            &ctx.accounts.source_token_account,
+            &mut ctx.accounts.destination_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            amount,
+            true,
+        )?;
+        release_lock_record(
+            &mut ctx.accounts.source_vault,
+            &mut ctx.accounts.lock_record,
+            &ctx.accounts.payer,
+            ctx.accounts.authority.key(),
+            amount,
+            Some(ctx.accounts.destination_vault.key()),
+        )
+    }
+
+    /// Seize part of an undercollateralized vault's locked collateral (CPI-only)
+    /// 
+    /// Security checks:
+    /// - The liquidator must be authorized by the vault, as for lock and unlock;
+    ///   judging the vault undercollateralized is the calling program's job
+    /// - Only locked collateral no lock record holds is seized; the available
+    ///   balance and recorded locks are untouched
+    /// - The config's liquidation penalty is seized on top of `amount`, capped
+    ///   at what remains of that
+    /// 
+    /// Everything seized goes to `destination_token_account`, which may be any
+    /// token account of the vault's mint. When `amount` exceeds the unrecorded
+    /// locked balance the liquidation fails, unless the insurance fund is passed, in
+    /// which case it pays the shortfall.
+    /// 
+    /// When the calling program passes `keeper_token_account`, the config's
+    /// `keeper_reward_bps` of the collateral seized from the vault goes there
+    /// instead, so third-party bots that trigger liquidations are paid for
+    /// keeping the system solvent. Any part the insurance fund covers earns
+    /// no reward.
+    pub fn liquidate(ctx: Context<Liquidate>, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        require!(ctx.accounts.vault.is_authorized(&ctx.accounts.liquidator.key()), 
+                 VaultError::UnauthorizedCaller);
+        
+        let vault = &mut ctx.accounts.vault;
+        let clock = Clock::get()?;
+        
+        let penalty = ctx.accounts.config.liquidation_penalty(amount)
+            .min(vault.unrecorded_locked_balance().saturating_sub(amount));
+        let seized = amount.checked_add(penalty)
+            .ok_or(VaultError::Overflow)?;
+        
+        // The reward is a share of what the vault itself gives up, so the
+        // insurance fund never pays the keeper
+        let mut keeper_reward = 0;
+        let mut reward = 0;
+        if let Some(keeper_token_account) = ctx.accounts.keeper_token_account.as_mut() {
+            reward = ctx.accounts.config.keeper_reward(vault.unrecorded_locked_balance().min(seized));
+            if reward > 0 {
+                keeper_reward = pay_out_locked(
+                    vault,
+                    &ctx.accounts.vault_token_account,
+                    keeper_token_account,
+                    &ctx.accounts.mint,
+                    &ctx.accounts.token_program,
+                    reward,
+                )?;
+            }
+        }
+        
+        let received = pay_out_locked_or_insured(
+            vault,
+            &ctx.accounts.vault_token_account,
+            &mut ctx.accounts.destination_token_account,
+            ctx.accounts.insurance_fund.as_ref(),
+            &ctx.accounts.config,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            seized - reward,
+        )?;
+        
+        emit!(VaultLiquidated {
+            user: vault.user,
+            vault: vault.key(),
+            liquidator: ctx.accounts.liquidator.key(),
+            destination: ctx.accounts.destination_token_account.key(),
+            amount,
+            penalty,
+            received,
+            keeper: ctx.accounts.keeper_token_account.as_ref().map(|account| account.key()),
+            keeper_reward,
+            new_total_balance: vault.total_balance,
+            new_locked_balance: vault.locked_balance,
+            sequence: vault.next_sequence()?,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Pay a realized trading loss out of a vault's locked collateral (CPI-only)
+    /// 
+    /// Security checks:
+    /// - The caller must be authorized by the vault, as for transfers
+    /// - The per-transaction cap applies; larger losses settle in parts
+    /// - Only locked collateral no lock record holds is paid out; the
+    ///   available balance and recorded locks are untouched
+    /// 
+    /// The loss goes to `counterparty_token_account`, a counterparty's token
+    /// account of the vault's mint. A loss beyond that balance fails,
+    /// unless the insurance fund is passed, in which case it pays the shortfall
+    /// rather than leaving it to the counterparty.
+    pub fn settle_loss(ctx: Context<SettleLoss>, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        require!(amount <= ctx.accounts.config.max_transaction_amount, VaultError::AmountExceedsLimit);
+        require!(ctx.accounts.vault.is_authorized(&ctx.accounts.authority.key()), 
+                 VaultError::UnauthorizedCaller);
+        
+        let vault = &mut ctx.accounts.vault;
+        let clock = Clock::get()?;
+        
+        let received = pay_out_locked_or_insured(
+            vault,
+            &ctx.accounts.vault_token_account,
+            &mut ctx.accounts.counterparty_token_account,
+            ctx.accounts.insurance_fund.as_ref(),
+            &ctx.accounts.config,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        
+        emit!(LossSettled {
+            user: vault.user,
+            vault: vault.key(),
+            authority: ctx.accounts.authority.key(),
+            counterparty: ctx.accounts.counterparty_token_account.key(),
+            amount,
+            received,
+            new_total_balance: vault.total_balance,
+            new_locked_balance: vault.locked_balance,
+            sequence: vault.next_sequence()?,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Credit tokens that reached the vault token account without a deposit
+    /// 
+    /// Transfers made straight to the token account (donations, airdrops,
+    /// mistaken sends) are not in the vault's books. Anyone may sync them: the
+    /// surplus of the token account over `total_balance` is credited to the
+    /// available balance. Deposit counters are left alone.
+    pub fn sync_balance(ctx: Context<SyncBalance>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let surplus = ctx.accounts.vault_token_account.amount.saturating_sub(vault.total_balance);
+        require!(surplus > 0, VaultError::NoBalanceSurplus);
+        
+        let clock = Clock::get()?;
+        vault.total_balance = vault.total_balance.checked_add(surplus)
+            .ok_or(VaultError::Overflow)?;
+        vault.available_balance = vault.available_balance.checked_add(surplus)
+            .ok_or(VaultError::Overflow)?;
+        vault.last_updated = clock.unix_timestamp;
+        vault.validate_invariant()?;
+        
+        emit!(BalanceSynced {
+            user: vault.user,
+            vault: vault.key(),
+            caller: ctx.accounts.caller.key(),
+            surplus,
+            new_total_balance: vault.total_balance,
+            new_available_balance: vault.available_balance,
+            sequence: vault.next_sequence()?,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Store a vault's canonical bump in place of the one it was created with
+    /// 
+    /// Vaults created while `initialize_vault` took the bump from the caller
+    /// may hold one that does not derive their address, which leaves every
+    /// instruction signing as the vault unable to run. The address itself was
+    /// always the canonical PDA; the vault's primary authority applies the
+    /// fix, not keys authorized alongside it.
+    pub fn repair_vault_bump(ctx: Context<RepairVaultBump>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let canonical_bump = ctx.bumps.vault;
+        require!(vault.bump != canonical_bump, VaultError::VaultBumpAlreadyCanonical);
+        
+        let previous_bump = vault.bump;
+        vault.bump = canonical_bump;
+        
+        emit!(VaultBumpRepaired {
+            vault: vault.key(),
+            previous_bump,
+            bump: canonical_bump,
+            sequence: vault.next_sequence()?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Create the global program config
+    /// 
+    /// The signer becomes the admin who can change limits and co-sign
+    /// over-limit movements. `collateral_mint` becomes the only mint vaults,
+    /// treasuries and insurance funds may be opened for; it cannot be changed
+    /// afterwards, and must pass the mint policy given alongside it.
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        max_transaction_amount: u64,
+        allow_mint_freeze_authority: bool,
+        max_mint_transfer_fee_bps: u16,
+    ) -> Result<()> {
+        require!(max_transaction_amount > 0, VaultError::InvalidAmount);
+        require!(max_mint_transfer_fee_bps <= ProgramConfig::MAX_MINT_TRANSFER_FEE_BPS,
+                 VaultError::InvalidMintPolicy);
+        
+        let config = &mut ctx.accounts.config;
+        let clock = Clock::get()?;
+        config.admin = ctx.accounts.admin.key();
+        config.max_transaction_amount = max_transaction_amount;
+        config.bump = ctx.bumps.config;
+        config.allow_mint_freeze_authority = allow_mint_freeze_authority;
+        config.max_mint_transfer_fee_bps = max_mint_transfer_fee_bps;
+        check_mint_safety(&ctx.accounts.collateral_mint, config)?;
+        config.collateral_mint = ctx.accounts.collateral_mint.key();
+        config.collateral_decimals = ctx.accounts.collateral_mint.decimals;
+        
+        emit!(ConfigUpdated {
+            admin: config.admin,
+            max_transaction_amount,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        emit!(CollateralMintSet {
+            admin: config.admin,
+            mint: config.collateral_mint,
+            decimals: config.collateral_decimals,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Set the collateral mint of a config created before it existed (admin only)
+    /// 
+    /// Such configs come out of `migrate_config_layout` without one, and no
+    /// vault can be opened until it is set. It is set once: vaults already
+    /// opened hold the mint, so it is never changed under them. The mint
+    /// must pass the mint policy, which `update_mint_policy` sets beforehand.
+    pub fn set_collateral_mint(ctx: Context<SetCollateralMint>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(!config.has_collateral_mint(), VaultError::CollateralMintAlreadySet);
+        check_mint_safety(&ctx.accounts.collateral_mint, config)?;
+        
+        config.collateral_mint = ctx.accounts.collateral_mint.key();
+        config.collateral_decimals = ctx.accounts.collateral_mint.decimals;
+        
+        emit!(CollateralMintSet {
+            admin: config.admin,
+            mint: config.collateral_mint,
+            decimals: config.collateral_decimals,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Set which collateral mints are accepted (admin only)
+    /// 
+    /// A mint with a freeze authority can have vault token accounts frozen,
+    /// so it is refused unless `allow_mint_freeze_authority` is set, as it
+    /// must be for USDT. A mint whose Token-2022 transfer fee exceeds
+    /// `max_mint_transfer_fee_bps` is refused; 0 refuses any fee. The policy
+    /// is checked whenever the collateral mint is set and on every vault
+    /// opened, so tightening it stops new vaults of a mint that no longer
+    /// passes.
+    pub fn update_mint_policy(
+        ctx: Context<UpdateConfig>,
+        allow_mint_freeze_authority: bool,
+        max_mint_transfer_fee_bps: u16,
+    ) -> Result<()> {
+        require!(max_mint_transfer_fee_bps <= ProgramConfig::MAX_MINT_TRANSFER_FEE_BPS,
+                 VaultError::InvalidMintPolicy);
+        
+        let config = &mut ctx.accounts.config;
+        config.allow_mint_freeze_authority = allow_mint_freeze_authority;
+        config.max_mint_transfer_fee_bps = max_mint_transfer_fee_bps;
+        
+        emit!(MintPolicyUpdated {
+            admin: config.admin,
+            allow_mint_freeze_authority,
+            max_mint_transfer_fee_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Change the per-transaction cap (admin only)
+    pub fn update_max_transaction_amount(ctx: Context<UpdateConfig>, max_transaction_amount: u64) -> Result<()> {
+        require!(max_transaction_amount > 0, VaultError::InvalidAmount);
+        
+        let config = &mut ctx.accounts.config;
+        config.max_transaction_amount = max_transaction_amount;
+        
+        emit!(ConfigUpdated {
+            admin: config.admin,
+            max_transaction_amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Cap the share of a vault's balance that may be locked (admin only)
+    /// 
+    /// `max_lock_ratio_bps` is in basis points of the total balance; 0 removes
+    /// the cap. Collateral already locked is never released by a lower cap,
+    /// only further locks are refused.
+    pub fn update_max_lock_ratio(ctx: Context<UpdateConfig>, max_lock_ratio_bps: u16) -> Result<()> {
+        require!(max_lock_ratio_bps <= ProgramConfig::MAX_LOCK_RATIO_BPS, VaultError::InvalidLockRatio);
+        
+        let config = &mut ctx.accounts.config;
+        config.max_lock_ratio_bps = max_lock_ratio_bps;
+        
+        emit!(LockRatioUpdated {
+            admin: config.admin,
+            max_lock_ratio_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Set the available balance every lock must leave a vault (admin only)
+    /// 
+    /// `min_free_balance` is in base units of the collateral mint; 0 removes
+    /// the floor. Like the lock ratio cap it only refuses further locks and
+    /// never releases collateral already locked.
+    pub fn update_min_free_balance(ctx: Context<UpdateConfig>, min_free_balance: u64) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.min_free_balance = min_free_balance;
+        
+        emit!(MinFreeBalanceUpdated {
+            admin: config.admin,
+            min_free_balance,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Create the collateral settings of a mint with its haircut (admin only)
+    /// 
+    /// `haircut_bps` discounts the mint's balances when locks are checked, at
+    /// most `AssetConfig::MAX_HAIRCUT_BPS`; 0 counts them in full. Vaults of
+    /// the mint cannot lock collateral until this exists.
+    pub fn initialize_asset_config(ctx: Context<InitializeAssetConfig>, haircut_bps: u16) -> Result<()> {
+        require!(haircut_bps <= AssetConfig::MAX_HAIRCUT_BPS, VaultError::InvalidHaircut);
+        
+        let asset_config = &mut ctx.accounts.asset_config;
+        asset_config.mint = ctx.accounts.mint.key();
+        asset_config.haircut_bps = haircut_bps;
+        asset_config.bump = ctx.bumps.asset_config;
+        
+        emit!(HaircutUpdated {
+            admin: ctx.accounts.admin.key(),
+            mint: asset_config.mint,
+            haircut_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Change the haircut of a mint (admin only)
+    /// 
+    /// As with the lock ratio cap, a higher haircut only refuses further
+    /// locks and never releases collateral already locked.
+    pub fn update_haircut(ctx: Context<UpdateAssetConfig>, haircut_bps: u16) -> Result<()> {
+        require!(haircut_bps <= AssetConfig::MAX_HAIRCUT_BPS, VaultError::InvalidHaircut);
+        
+        let asset_config = &mut ctx.accounts.asset_config;
+        asset_config.haircut_bps = haircut_bps;
+        
+        emit!(HaircutUpdated {
+            admin: ctx.accounts.admin.key(),
+            mint: asset_config.mint,
+            haircut_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Set the smallest deposit and withdrawal accepted (admin only)
+    /// 
+    /// Both are in base units of the collateral mint; 0 removes a minimum.
+    /// Dust operations only bloat event history and make accounting noisy. A
+    /// withdrawal taking the whole available balance is accepted whatever its
+    /// size, so no vault is left holding dust it cannot get out.
+    pub fn update_minimum_amounts(ctx: Context<UpdateConfig>, min_deposit: u64, min_withdrawal: u64) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.min_deposit = min_deposit;
+        config.min_withdrawal = min_withdrawal;
+        
+        emit!(MinimumAmountsUpdated {
+            admin: config.admin,
+            min_deposit,
+            min_withdrawal,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Set the penalty seized on top of each liquidated amount (admin only)
+    /// 
+    /// `liquidation_penalty_bps` is in basis points of the liquidated amount,
+    /// at most `ProgramConfig::MAX_LIQUIDATION_PENALTY_BPS`; 0 charges none.
+    pub fn update_liquidation_penalty(ctx: Context<UpdateConfig>, liquidation_penalty_bps: u16) -> Result<()> {
+        require!(liquidation_penalty_bps <= ProgramConfig::MAX_LIQUIDATION_PENALTY_BPS,
+                 VaultError::InvalidLiquidationPenalty);
+        
+        let config = &mut ctx.accounts.config;
+        config.liquidation_penalty_bps = liquidation_penalty_bps;
+        
+        emit!(LiquidationPenaltyUpdated {
+            admin: config.admin,
+            liquidation_penalty_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Set the reward paid to liquidation keepers (admin only)
+    /// 
+    /// `keeper_reward_bps` is in basis points of the collateral a liquidation
+    /// seizes, at most `ProgramConfig::MAX_KEEPER_REWARD_BPS`; 0 pays none. The
+    /// reward comes out of the seized amount, not on top of it.
+    pub fn update_keeper_reward(ctx: Context<UpdateConfig>, keeper_reward_bps: u16) -> Result<()> {
+        require!(keeper_reward_bps <= ProgramConfig::MAX_KEEPER_REWARD_BPS, VaultError::InvalidKeeperReward);
+        
+        let config = &mut ctx.accounts.config;
+        config.keeper_reward_bps = keeper_reward_bps;
+        
+        emit!(KeeperRewardUpdated {
+            admin: config.admin,
+            keeper_reward_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Set the protocol fee kept from each withdrawal (admin only)
+    /// 
+    /// `withdrawal_fee_bps` is in basis points of the withdrawn amount, at most
+    /// `ProgramConfig::MAX_WITHDRAWAL_FEE_BPS`; 0 charges none. Fees go to the
+    /// mint's treasury token account, which `initialize_treasury` creates.
+    pub fn update_withdrawal_fee(ctx: Context<UpdateConfig>, withdrawal_fee_bps: u16) -> Result<()> {
+        require!(withdrawal_fee_bps <= ProgramConfig::MAX_WITHDRAWAL_FEE_BPS,
+                 VaultError::InvalidWithdrawalFee);
+        
+        let config = &mut ctx.accounts.config;
+        config.withdrawal_fee_bps = withdrawal_fee_bps;
+        
+        emit!(WithdrawalFeeUpdated {
+            admin: config.admin,
+            withdrawal_fee_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Create the treasury token account collecting withdrawal fees of a mint
+    /// (admin only)
+    /// 
+    /// The account is a PDA of `[TREASURY_SEED, mint]` whose token authority is
+    /// the config PDA, so only `withdraw_treasury` can move what it holds.
+    /// Withdrawals of the mint need it to exist while a fee is charged, so it
+    /// can be created for any mint, including one vaults no longer take.
+    pub fn initialize_treasury(_ctx: Context<InitializeTreasury>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Move collected fees out of a mint's treasury (admin only)
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        
+        transfer_from_protocol_account(
+            &ctx.accounts.config,
+            &ctx.accounts.treasury_token_account,
+            &mut ctx.accounts.destination_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        
+        emit!(TreasuryWithdrawn {
+            admin: ctx.accounts.admin.key(),
+            treasury: ctx.accounts.treasury_token_account.key(),
+            destination: ctx.accounts.destination_token_account.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Create the insurance fund's token account for a mint (admin only)
+    /// 
+    /// Like the treasury, the account is a PDA of `[INSURANCE_SEED, mint]` whose
+    /// token authority is the config PDA. Settlements and liquidations of the
+    /// mint may then draw on it for shortfalls.
+    pub fn initialize_insurance_fund(_ctx: Context<InitializeInsuranceFund>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Pay into the insurance fund of a mint; anyone may fund it
+    pub fn fund_insurance(ctx: Context<FundInsurance>, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        
+        let received = transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.funder_token_account,
+            &mut ctx.accounts.insurance_fund,
+            ctx.accounts.funder.to_account_info(),
+            &[],
+            amount,
+        )?;
+        
+        emit!(InsuranceFunded {
+            funder: ctx.accounts.funder.key(),
+            fund: ctx.accounts.insurance_fund.key(),
+            amount,
+            received,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Pay out of the insurance fund outside of a settlement (admin only)
+    /// 
+    /// `reason` is a caller-chosen tag, e.g. an incident reference, recorded in
+    /// the event; it must not be all zeroes.
+    pub fn draw_insurance(ctx: Context<DrawInsurance>, amount: u64, reason: [u8; 32]) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        require!(reason != [0u8; 32], VaultError::MissingInsuranceDrawReason);
+        
+        transfer_from_protocol_account(
+            &ctx.accounts.config,
+            &ctx.accounts.insurance_fund,
+            &mut ctx.accounts.destination_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        
+        emit!(InsuranceDrawn {
+            admin: ctx.accounts.admin.key(),
+            fund: ctx.accounts.insurance_fund.key(),
+            destination: ctx.accounts.destination_token_account.key(),
+            amount,
+            reason,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Halt or resume every balance-moving instruction (admin only)
+    /// 
+    /// While paused, every instruction that moves a vault's funds fails with
+    /// `ProgramPaused` before touching any account, whatever the vault's own
+    /// state: deposits, withdrawals, requested withdrawals, locks and lock
+    /// records, unlocks, transfers and escrows, liquidations, loss settlements,
+    /// balance syncs, force unlocks, dormant sweeps and reclaims. Admin moves
+    /// of protocol accounts (treasury, insurance fund, foreign token recovery)
+    /// and vault settings stay available for handling the incident. Vaults
+    /// keep their `is_active` flag, so resuming needs no per-vault action.
+    pub fn set_pause(ctx: Context<UpdateConfig>, paused: bool) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.paused = paused;
+        
+        emit!(PauseUpdated {
+            admin: config.admin,
+            paused,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Grow a config created under an earlier, smaller layout to the current one
+    /// 
+    /// Until this runs, instructions taking the config fail to decode it.
+    /// Every existing field is left as it is and the new bytes are zeroed, so
+    /// locks stay uncapped, no collateral mint is set and anyone may pay for
+    /// the migration. The admin then sets the mint with `set_collateral_mint`.
+    pub fn migrate_config_layout(ctx: Context<MigrateConfigLayout>) -> Result<()> {
+        let config_info = ctx.accounts.config.to_account_info();
+        let old_size = config_info.data_len();
+        require!(old_size < ProgramConfig::SIZE, VaultError::ConfigLayoutCurrent);
+        
+        // Validates the discriminator before anything changes
+        let config = ProgramConfig::from_account_data(&config_info.try_borrow_data()?)?;
+        
+        let rent_due = Rent::get()?.minimum_balance(ProgramConfig::SIZE).saturating_sub(config_info.lamports());
+        if rent_due > 0 {
+            let cpi_accounts = system_program::Transfer {
+                from: ctx.accounts.payer.to_account_info(),
+                to: config_info.clone(),
+            };
+            system_program::transfer(CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts), rent_due)?;
+        }
+        config_info.realloc(ProgramConfig::SIZE, true)?;
+        config.try_serialize(&mut &mut config_info.try_borrow_mut_data()?[..])?;
+        
+        emit!(ConfigLayoutMigrated {
+            config: config_info.key(),
+            payer: ctx.accounts.payer.key(),
+            old_size: old_size as u64,
+            new_size: ProgramConfig::SIZE as u64,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Sweep tokens of a non-collateral mint out of a vault-owned token account
+    /// 
+    /// Security checks:
+    /// - Only the config admin can recover
+    /// - Source must be owned by the vault PDA and hold a different mint than
+    ///   the vault's collateral token account
+    /// - Vault balances are never touched
+    pub fn recover_foreign_tokens(ctx: Context<RecoverForeignTokens>) -> Result<()> {
+        let amount = ctx.accounts.foreign_token_account.amount;
+        require!(amount > 0, VaultError::InvalidAmount);
+        
+        let vault = &mut ctx.accounts.vault;
+        let sub_account_seed = Vault::sub_account_seed(vault.sub_account_id);
+        let signer_seeds = &[
+            VAULT_SEED,
+            vault.user.as_ref(),
+            sub_account_seed.as_slice(),
+            &[vault.bump],
+        ];
+        let signer = &[&signer_seeds[..]];
+        
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.foreign_mint,
+            &ctx.accounts.foreign_token_account,
+            &mut ctx.accounts.recovery_token_account,
+            vault.to_account_info(),
+            signer,
+            amount,
+        )?;
+        
+        emit!(ForeignTokensRecovered {
+            admin: ctx.accounts.admin.key(),
+            vault: vault.key(),
+            mint: ctx.accounts.foreign_token_account.mint,
+            source: ctx.accounts.foreign_token_account.key(),
+            destination: ctx.accounts.recovery_token_account.key(),
+            amount,
+            sequence: vault.next_sequence()?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Remove an SPL delegate from a vault's collateral token account
+    /// 
+    /// Security checks:
+    /// - Only the config admin can revoke
+    /// - The token account must be the vault's own collateral account
+    /// 
+    /// The program never approves delegates, so one being set means collateral
+    /// can move outside the vault's accounting. A close authority cannot be
+    /// cleared the same way: only the close authority itself may change it.
+    pub fn revoke_token_delegate(ctx: Context<RevokeTokenDelegate>) -> Result<()> {
+        let token_account = &ctx.accounts.vault_token_account;
+        let delegate = Option::<Pubkey>::from(token_account.delegate)
+            .ok_or(VaultError::NoDelegateToRevoke)?;
+        let delegated_amount = token_account.delegated_amount;
+        
+        let vault = &mut ctx.accounts.vault;
+        let sub_account_seed = Vault::sub_account_seed(vault.sub_account_id);
+        let signer_seeds = &[
+            VAULT_SEED,
+            vault.user.as_ref(),
+            sub_account_seed.as_slice(),
+            &[vault.bump],
+        ];
+        let signer = &[&signer_seeds[..]];
+        
+        let cpi_accounts = Revoke {
+            source: token_account.to_account_info(),
+            authority: vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token_interface::revoke(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer))?;
+        
+        emit!(TokenDelegateRevoked {
+            admin: ctx.accounts.admin.key(),
+            vault: vault.key(),
+            token_account: token_account.key(),
+            delegate,
+            delegated_amount,
+            sequence: vault.next_sequence()?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Hand CPI authority over a vault to a new key
+    /// 
+    /// Security checks:
+    /// - The current authority must sign
+    /// - The new authority must sign, proving the key is held before it takes over
+    /// - Rotating to the same key is rejected
+    /// - So is rotating to a key already authorized alongside it, which would
+    ///   leave that key listed twice
+    /// 
+    /// Rolling back is the same instruction with the keys swapped.
+    pub fn rotate_authority(ctx: Context<RotateAuthority>) -> Result<()> {
+        let new_authority = ctx.accounts.new_authority.key();
+        let vault = &mut ctx.accounts.vault;
+        let old_authority = vault.authority;
+        require!(new_authority != old_authority, VaultError::AuthorityUnchanged);
+        require!(!vault.authorized_authorities.contains(&new_authority),
+                 VaultError::AuthorityAlreadyAuthorized);
+        
+        let clock = Clock::get()?;
+        vault.authority = new_authority;
+        vault.last_updated = clock.unix_timestamp;
+        
+        emit!(AuthorityRotated {
+            user: vault.user,
+            vault: vault.key(),
+            old_authority,
+            new_authority,
+            sequence: vault.next_sequence()?,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Give another key the vault's CPI rights alongside `authority`
+    /// 
+    /// Security checks:
+    /// - The vault's `authority` must sign; authorized keys cannot authorize others
+    /// - The new key must sign, proving it is held, as in `rotate_authority`
+    /// - At most `Vault::MAX_AUTHORIZED_AUTHORITIES` keys besides `authority`
+    /// 
+    /// The vault grows by one key, `payer` funding the extra rent.
+    pub fn add_authorized_authority(ctx: Context<AddAuthorizedAuthority>) -> Result<()> {
+        let new_authority = ctx.accounts.new_authority.key();
+        let vault = &mut ctx.accounts.vault;
+        require!(!vault.is_authorized(&new_authority), VaultError::AuthorityAlreadyAuthorized);
+        require!(vault.authorized_authorities.len() < Vault::MAX_AUTHORIZED_AUTHORITIES,
+                 VaultError::TooManyAuthorities);
+        
+        let clock = Clock::get()?;
+        vault.authorized_authorities.push(new_authority);
+        vault.last_updated = clock.unix_timestamp;
+        
+        emit!(AuthorityAuthorized {
+            user: vault.user,
+            vault: vault.key(),
+            authority: new_authority,
+            authorized_by: ctx.accounts.authority.key(),
+            sequence: vault.next_sequence()?,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Withdraw a key's CPI rights over the vault
+    /// 
+    /// Security checks:
+    /// - The vault's `authority` must sign
+    /// - `authority` itself cannot be removed, only rotated
+    /// 
+    /// The vault shrinks by one key and its rent goes to `payer`.
+    pub fn remove_authorized_authority(ctx: Context<RemoveAuthorizedAuthority>, removed_authority: Pubkey) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let position = vault.authorized_authorities
+            .iter()
+            .position(|key| *key == removed_authority)
+            .ok_or(VaultError::AuthorityNotAuthorized)?;
+        
+        let clock = Clock::get()?;
+        vault.authorized_authorities.remove(position);
+        vault.last_updated = clock.unix_timestamp;
+        
+        emit!(AuthorityDeauthorized {
+            user: vault.user,
+            vault: vault.key(),
+            authority: removed_authority,
+            deauthorized_by: ctx.accounts.authority.key(),
+            sequence: vault.next_sequence()?,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Grow a vault allocated for an older layout to the current one
+    /// 
+    /// Security checks:
+    /// - The account must be this program's vault PDA for `user` and
+    ///   `sub_account_id` with the vault discriminator, smaller than its
+    ///   layout needs
+    /// - Balances and every existing field are left as they are; only the new
+    ///   bytes are zeroed, so anyone may pay for the migration
+    /// 
+    /// For a vault created before the counters, `created_at` stays 0 because
+    /// the creation time is unknown; the counters count from the migration,
+    /// recorded in `counters_since`. Event sequences start from the migration.
+    pub fn migrate_vault_layout(ctx: Context<MigrateVaultLayout>, _sub_account_id: u16) -> Result<()> {
+        let vault_info = ctx.accounts.vault.to_account_info();
+        let old_size = vault_info.data_len();
+        
+        // Validates the discriminator before anything changes
+        let mut vault = Vault::from_account_data(&vault_info.try_borrow_data()?)?;
+        let new_size = vault.required_size();
+        require!(old_size < new_size, VaultError::VaultLayoutCurrent);
+        
+        let rent_due = Rent::get()?.minimum_balance(new_size).saturating_sub(vault_info.lamports());
+        if rent_due > 0 {
+            let cpi_accounts = system_program::Transfer {
+                from: ctx.accounts.payer.to_account_info(),
+                to: vault_info.clone(),
+            };
+            system_program::transfer(CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts), rent_due)?;
+        }
+        vault_info.realloc(new_size, true)?;
+        
+        let clock = Clock::get()?;
+        if old_size == Vault::LEGACY_SIZE {
+            vault.counters_since = clock.unix_timestamp;
+        }
+        let sequence = vault.next_sequence()?;
+        vault.try_serialize(&mut &mut vault_info.try_borrow_mut_data()?[..])?;
+        
+        emit!(VaultLayoutMigrated {
+            vault: vault_info.key(),
+            payer: ctx.accounts.payer.key(),
+            old_size: old_size as u64,
+            new_size: new_size as u64,
+            sequence,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Move a dormant vault's funds into a segregated dormant-funds account
+    /// 
+    /// Security checks:
+    /// - Only the config admin can sweep
+    /// - The vault must be active, untouched for `DormantFunds::MIN_DORMANCY_SECONDS`
+    ///   and hold no locked collateral
+    /// - Only the accounted balance moves; what reaches the dormant token
+    ///   account, net of any Token-2022 transfer fee, is recorded on the
+    ///   `DormantFunds` account so the owner can reclaim exactly that amount
+    /// 
+    /// The vault is left inactive with zero balances until it is reclaimed.
+    pub fn sweep_dormant_vault(ctx: Context<SweepDormantVault>) -> Result<()> {
+        let clock = Clock::get()?;
+        let vault = &mut ctx.accounts.vault;
+        require!(vault.is_active, VaultError::VaultInactive);
+        require!(DormantFunds::is_dormant(vault, clock.unix_timestamp), VaultError::VaultNotDormant);
+        require!(vault.locked_balance == 0, VaultError::VaultHasLockedCollateral);
+        vault.validate_invariant()?;
+        let amount = vault.total_balance;
+        require!(amount > 0, VaultError::InvalidAmount);
+        
+        let user = vault.user;
+        let sub_account_seed = Vault::sub_account_seed(vault.sub_account_id);
+        let signer_seeds = &[
+            VAULT_SEED,
+            user.as_ref(),
+            sub_account_seed.as_slice(),
+            &[vault.bump],
+        ];
+        let signer = &[&signer_seeds[..]];
+        
+        let swept = transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.vault_token_account,
+            &mut ctx.accounts.dormant_token_account,
+            vault.to_account_info(),
+            signer,
+            amount,
+        )?;
+        
+        let inactive_since = vault.last_updated;
+        vault.total_balance = 0;
+        vault.available_balance = 0;
+        vault.pending_withdrawal = 0;
+        vault.withdrawal_unlocks_at = 0;
+        vault.is_active = false;
+        vault.last_updated = clock.unix_timestamp;
+        
+        let dormant_funds = &mut ctx.accounts.dormant_funds;
+        dormant_funds.vault = vault.key();
+        dormant_funds.user = user;
+        dormant_funds.mint = ctx.accounts.mint.key();
+        dormant_funds.amount = swept;
+        dormant_funds.inactive_since = inactive_since;
+        dormant_funds.swept_at = clock.unix_timestamp;
+        dormant_funds.payer = ctx.accounts.admin.key();
+        dormant_funds.bump = ctx.bumps.dormant_funds;
+        
+        emit!(VaultSweptDormant {
+            admin: ctx.accounts.admin.key(),
+            user,
+            vault: vault.key(),
+            dormant_funds: dormant_funds.key(),
+            amount: swept,
+            inactive_since,
+            sequence: vault.next_sequence()?,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Return swept funds to their vault and reactivate it (owner only)
+    /// 
+    /// Security checks:
+    /// - The vault owner must sign
+    /// - The dormant-funds account must be the one derived for this vault
+    /// - The vault token account must have no delegate or close authority
+    /// 
+    /// The vault is credited what reaches its token account, net of any
+    /// Token-2022 transfer fee. Fees withheld in the dormant token account are
+    /// harvested to the mint so it can close; the dormant token account and
+    /// the record are closed, refunding their rent to whoever paid for the sweep.
+    pub fn reclaim_dormant_funds(ctx: Context<ReclaimDormantFunds>) -> Result<()> {
+        let clock = Clock::get()?;
+        let dormant_funds = &ctx.accounts.dormant_funds;
+        let amount = dormant_funds.amount;
+        
+        let vault_key = ctx.accounts.vault.key();
+        let signer_seeds = &[
+            DORMANT_SEED,
+            vault_key.as_ref(),
+            &[dormant_funds.bump],
+        ];
+        let signer = &[&signer_seeds[..]];
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        
+        let received = transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.dormant_token_account,
+            &mut ctx.accounts.vault_token_account,
+            dormant_funds.to_account_info(),
+            signer,
+            amount,
+        )?;
+        
+        harvest_withheld_fees(&ctx.accounts.token_program, &ctx.accounts.mint, &ctx.accounts.dormant_token_account)?;
+        let cpi_accounts = CloseAccount {
+            account: ctx.accounts.dormant_token_account.to_account_info(),
+            destination: ctx.accounts.payer.to_account_info(),
+            authority: dormant_funds.to_account_info(),
+        };
+        token_interface::close_account(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer))?;
+        
+        let vault = &mut ctx.accounts.vault;
+        vault.total_balance = vault.total_balance.checked_add(received)
+            .ok_or(VaultError::Overflow)?;
+        vault.available_balance = vault.available_balance.checked_add(received)
+            .ok_or(VaultError::Overflow)?;
+        vault.is_active = true;
+        vault.last_updated = clock.unix_timestamp;
+        vault.validate_invariant()?;
+        
+        emit!(DormantFundsReclaimed {
+            user: vault.user,
+            vault: vault_key,
+            dormant_funds: dormant_funds.key(),
+            amount: received,
+            swept_at: dormant_funds.swept_at,
+            sequence: vault.next_sequence()?,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Close an empty vault and its token account (owner only)
+    /// 
+    /// Security checks:
+    /// - The vault owner must sign
+    /// - The vault must be active: a swept vault is still needed to reclaim its funds
+    /// - Both the accounted balance and the token account must be empty
+    /// - No lock record, escrowed transfer or force unlock may still draw on
+    ///   it, so a vault reopened at the same address inherits none of them
+    /// 
+    /// Fees withheld in the token account are harvested to the mint so it can
+    /// close. The rent of both accounts goes to `rent_destination`, a wallet
+    /// the user names, and the user can open a new vault at the same address
+    /// afterwards.
+    pub fn close_vault(ctx: Context<CloseVault>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.validate_invariant()?;
+        require!(vault.total_balance == 0, VaultError::VaultNotEmpty);
+        require!(vault.open_records == 0, VaultError::VaultHasOpenRecords);
+        
+        let rent_reclaimed = vault.to_account_info().lamports()
+            .checked_add(ctx.accounts.vault_token_account.to_account_info().lamports())
+            .ok_or(VaultError::Overflow)?;
+        
+        let sub_account_seed = Vault::sub_account_seed(vault.sub_account_id);
+        let signer_seeds = &[
+            VAULT_SEED,
+            vault.user.as_ref(),
+            sub_account_seed.as_slice(),
+            &[vault.bump],
+        ];
+        let signer = &[&signer_seeds[..]];
+        
+        harvest_withheld_fees(&ctx.accounts.token_program, &ctx.accounts.mint, &ctx.accounts.vault_token_account)?;
+        let cpi_accounts = CloseAccount {
+            account: ctx.accounts.vault_token_account.to_account_info(),
+            destination: ctx.accounts.rent_destination.to_account_info(),
+            authority: vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token_interface::close_account(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer))?;
+        
+        emit!(VaultClosed {
+            user: vault.user,
+            vault: vault.key(),
+            token_account: vault.token_account,
+            rent_destination: ctx.accounts.rent_destination.key(),
+            rent_reclaimed,
+            sequence: vault.next_sequence()?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+}
+
+/// Mark the vault as mid-operation and write the mark to its account before
+/// any token CPI, so a call that re-enters the program through an external
+/// protocol finds it: every accounts struct taking the vault mutably refuses
+/// it with `VaultBusy`. A failed instruction reverts the mark with everything
+/// else.
+fn begin_processing(vault: &mut Account<Vault>) -> Result<()> {
+    require!(!vault.processing, VaultError::VaultBusy);
+    vault.processing = true;
+    vault.exit(&crate::ID)
+}
+
+/// Clear the mark set by `begin_processing`; written when the instruction exits
+fn end_processing(vault: &mut Account<Vault>) {
+    vault.processing = false;
+}
+
+/// Move `amount` from `source_token_account`, signed by `depositor`, into the
+/// vault and credit what arrives; caller checks accounts
+#[allow(clippy::too_many_arguments)]
+fn deposit_into_vault<'info>(
+    vault: &mut Account<'info, Vault>,
+    vault_token_account: &mut InterfaceAccount<'info, TokenAccount>,
+    source_token_account: &InterfaceAccount<'info, TokenAccount>,
+    depositor: &Signer<'info>,
+    config: &ProgramConfig,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<()> {
+    require!(amount > 0, VaultError::InvalidAmount);
+    require!(config.deposit_allows(amount), VaultError::DepositBelowMinimum);
+    require!(vault.is_active, VaultError::VaultInactive);
+    begin_processing(vault)?;
+    
+    // Perform SPL token transfer from depositor to vault
+    let received = transfer_tokens(
+        token_program,
+        mint,
+        source_token_account,
+        vault_token_account,
+        depositor.to_account_info(),
+        &[],
+        amount,
+    )?;
+    require!(received > 0, VaultError::InvalidAmount);
+    
+    let clock = Clock::get()?;
+    
+    // Update vault balances with overflow protection
+    vault.total_balance = vault.total_balance.checked_add(received)
+        .ok_or(VaultError::Overflow)?;
+    vault.available_balance = vault.available_balance.checked_add(received)
+        .ok_or(VaultError::Overflow)?;
+    vault.record_deposit(received)?;
+    vault.last_updated = clock.unix_timestamp;
+    vault.validate_invariant()?;
+    
+    emit!(DepositEvent {
+        user: vault.user,
+        vault: vault.key(),
+        depositor: depositor.key(),
+        amount: received,
+        new_total_balance: vault.total_balance,
+        new_available_balance: vault.available_balance,
+        sequence: vault.next_sequence()?,
+        timestamp: clock.unix_timestamp,
+    });
+    
+    end_processing(vault);
+    Ok(())
+}
+
+/// Shared body of the withdraw instructions
+#[allow(clippy::too_many_arguments)]
+fn withdraw_from_vault<'info>(
+    vault: &mut Account<'info, Vault>,
+    vault_token_account: &InterfaceAccount<'info, TokenAccount>,
+    user_token_account: &mut InterfaceAccount<'info, TokenAccount>,
+    treasury_token_account: Option<&mut InterfaceAccount<'info, TokenAccount>>,
+    config: &ProgramConfig,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<()> {
+    require!(amount > 0, VaultError::InvalidAmount);
+    require!(vault.is_active, VaultError::VaultInactive);
+    require!(!vault.frozen, VaultError::VaultFrozen);
+    begin_processing(vault)?;
+    
+    let clock = Clock::get()?;
+    
+    // Ensure sufficient available balance
+    require!(vault.available_balance >= amount, VaultError::InsufficientAvailableBalance);
+    require!(config.withdrawal_allows(amount, vault.available_balance), VaultError::WithdrawalBelowMinimum);
+    
+    // Update balances with underflow protection
+    vault.total_balance = vault.total_balance.checked_sub(amount)
+        .ok_or(VaultError::Underflow)?;
+    vault.available_balance = vault.available_balance.checked_sub(amount)
+        .ok_or(VaultError::Underflow)?;
+    vault.record_withdrawal(amount)?;
+    vault.last_updated = clock.unix_timestamp;
+    vault.validate_invariant()?;
+    
+    // Transfer tokens from vault to user
+    let user = vault.user;
+    let sub_account_seed = Vault::sub_account_seed(vault.sub_account_id);
+    let signer_seeds = &[
+        VAULT_SEED,
+        user.as_ref(),
+        sub_account_seed.as_slice(),
+        &[vault.bump],
+    ];
+    let signer = &[&signer_seeds[..]];
+    
+    // The vault parts with the full amount: the protocol fee goes to the
+    // treasury, the rest to the user, and any transfer fee is theirs
+    let fee = config.withdrawal_fee(amount);
+    transfer_tokens(
+        token_program,
+        mint,
+        vault_token_account,
+        user_token_account,
+        vault.to_account_info(),
+        signer,
+        amount - fee,
+    )?;
+    
+    emit!(WithdrawEvent {
+        user: vault.user,
+        vault: vault.key(),
+        amount,
+        new_total_balance: vault.total_balance,
+        new_available_balance: vault.available_balance,
+        sequence: vault.next_sequence()?,
+        timestamp: clock.unix_timestamp,
+    });
+    
+    if fee > 0 {
+        let treasury_token_account = treasury_token_account.ok_or(VaultError::TreasuryMissing)?;
+        let received = transfer_tokens(
+            token_program,
+            mint,
+            vault_token_account,
+            treasury_token_account,
+            vault.to_account_info(),
+            signer,
+            fee,
+        )?;
+        
+        emit!(FeeCollected {
+            user: vault.user,
+            vault: vault.key(),
+            treasury: treasury_token_account.key(),
+            amount,
+            fee,
+            received,
+            sequence: vault.next_sequence()?,
+            timestamp: clock.unix_timestamp,
+        });
+    }
+    
+    end_processing(vault);
+    Ok(())
+}
+
+/// Move `amount` from available to locked, within the config's lock ratio cap
+/// and the mint's haircut and above the free balance floor; caller checks
+/// authority
+fn lock_available(vault: &mut Account<Vault>, config: &ProgramConfig, asset_config: &AssetConfig, amount: u64) -> Result<()> {
+    require!(!vault.frozen, VaultError::VaultFrozen);
+    let clock = Clock::get()?;
+    
+    // Ensure sufficient available balance
+    require!(vault.available_balance >= amount, VaultError::InsufficientAvailableBalance);
+    
+    // Atomically update balances
+    vault.available_balance = vault.available_balance.checked_sub(amount)
+        .ok_or(VaultError::Underflow)?;
+    vault.locked_balance = vault.locked_balance.checked_add(amount)
+        .ok_or(VaultError::Overflow)?;
+    vault.last_updated = clock.unix_timestamp;
+    
+    require!(config.lock_ratio_allows(vault.locked_balance, vault.total_balance),
+             VaultError::LockRatioExceeded);
+    require!(config.free_balance_allows(vault.available_balance), VaultError::FreeBalanceBelowMinimum);
+    require!(asset_config.haircut_allows(vault.locked_balance, vault.total_balance),
+             VaultError::HaircutExceeded);
+    vault.validate_invariant()?;
+    
+    emit!(CollateralLocked {
+        user: vault.user,
+        vault: vault.key(),
+        amount,
+        new_available_balance: vault.available_balance,
+        new_locked_balance: vault.locked_balance,
+        sequence: vault.next_sequence()?,
+        timestamp: clock.unix_timestamp,
+    });
+    
+    Ok(())
+}
+
+/// Move `amount` from locked back to available; caller checks authority
+fn unlock_locked(vault: &mut Account<Vault>, amount: u64) -> Result<()> {
+    let clock = Clock::get()?;
+    
+    // Ensure sufficient locked balance
+    require!(vault.locked_balance >= amount, VaultError::InsufficientLockedBalance);
+    
+    // Atomically update balances
+    vault.locked_balance = vault.locked_balance.checked_sub(amount)
+        .ok_or(VaultError::Underflow)?;
+    vault.available_balance = vault.available_balance.checked_add(amount)
+        .ok_or(VaultError::Overflow)?;
+    vault.last_updated = clock.unix_timestamp;
+    vault.validate_invariant()?;
+    
+    emit!(CollateralUnlocked {
+        user: vault.user,
+        vault: vault.key(),
+        amount,
+        new_available_balance: vault.available_balance,
+        new_locked_balance: vault.locked_balance,
+        sequence: vault.next_sequence()?,
+        timestamp: clock.unix_timestamp,
+    });
+    
+    Ok(())
+}
+
+/// Take `amount` off a lock record whose collateral has already left the
+/// locked balance, closing the record to `payer` once it is empty
+fn release_lock_record<'info>(
+    vault: &mut Account<'info, Vault>,
+    lock_record: &mut Account<'info, LockRecord>,
+    payer: &UncheckedAccount<'info>,
+    released_by: Pubkey,
+    amount: u64,
+    destination_vault: Option<Pubkey>,
+) -> Result<()> {
+    require!(lock_record.amount >= amount, VaultError::InsufficientLockRecordBalance);
+    
+    lock_record.amount = lock_record.amount.checked_sub(amount)
+        .ok_or(VaultError::Underflow)?;
+    
+    emit!(LockReleased {
+        user: vault.user,
+        vault: vault.key(),
+        lock: lock_record.key(),
+        lock_id: lock_record.lock_id,
+        released_by,
+        amount,
+        remaining: lock_record.amount,
+        destination_vault,
+        sequence: vault.next_sequence()?,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    if lock_record.amount == 0 {
+        lock_record.close(payer.to_account_info())?;
+        vault.count_record_closed();
+    }
+    
+    Ok(())
+}
+
+/// Shared body of the set_withdrawal_cooldown instructions; caller checks
+/// whether a decrease is allowed
+fn update_withdrawal_cooldown(vault: &mut Account<Vault>, cooldown_seconds: u32, approved_by: Option<Pubkey>) -> Result<()> {
+    require!(cooldown_seconds <= Vault::MAX_WITHDRAWAL_COOLDOWN, VaultError::InvalidWithdrawalCooldown);
+    
+    let clock = Clock::get()?;
+    let old_cooldown = vault.withdrawal_cooldown;
+    vault.withdrawal_cooldown = cooldown_seconds;
+    vault.last_updated = clock.unix_timestamp;
+    
+    emit!(WithdrawalCooldownUpdated {
+        user: vault.user,
+        vault: vault.key(),
+        old_cooldown,
+        new_cooldown: cooldown_seconds,
+        approved_by,
+        sequence: vault.next_sequence()?,
+        timestamp: clock.unix_timestamp,
+    });
+    
+    Ok(())
+}
+
+/// Shared body of the transfer_collateral instructions; caller checks authority
+/// 
+/// The source is debited `amount` and the destination credited what reaches
+/// its token account, net of any Token-2022 transfer fee.
+#[allow(clippy::too_many_arguments)]
+fn transfer_between_vaults<'info>(
+    source_vault: &mut Account<'info, Vault>,
+    destination_vault: &mut Account<'info, Vault>,
+    source_token_account: &InterfaceAccount<'info, TokenAccount>,
+    destination_token_account: &mut InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+    from_locked: bool,
+) -> Result<()> {
+    require!(amount > 0, VaultError::InvalidAmount);
+    require!(source_vault.is_active, VaultError::VaultInactive);
+    require!(destination_vault.is_active, VaultError::VaultInactive);
+    begin_processing(source_vault)?;
+    begin_processing(destination_vault)?;
+    
+    let clock = Clock::get()?;
+    
+    // Debit the source's locked or available balance, leaving the other alone
+    if from_locked {
+        require!(source_vault.locked_balance >= amount, VaultError::InsufficientLockedBalance);
+        source_vault.locked_balance = source_vault.locked_balance.checked_sub(amount)
+            .ok_or(VaultError::Underflow)?;
+    } else {
+        require!(!source_vault.frozen, VaultError::VaultFrozen);
+        require!(source_vault.available_balance >= amount, VaultError::InsufficientAvailableBalance);
+        source_vault.available_balance = source_vault.available_balance.checked_sub(amount)
+            .ok_or(VaultError::Underflow)?;
+    }
+    source_vault.total_balance = source_vault.total_balance.checked_sub(amount)
+        .ok_or(VaultError::Underflow)?;
+    source_vault.last_updated = clock.unix_timestamp;
+    source_vault.validate_invariant()?;
+    
+    // Perform actual token transfer
+    let source_user = source_vault.user;
+    let sub_account_seed = Vault::sub_account_seed(source_vault.sub_account_id);
+    let source_seeds = &[
+        VAULT_SEED,
+        source_user.as_ref(),
+        sub_account_seed.as_slice(),
+        &[source_vault.bump],
+    ];
+    let signer = &[&source_seeds[..]];
+    
+    let received = transfer_tokens(
+        token_program,
+        mint,
+        source_token_account,
+        destination_token_account,
+        source_vault.to_account_info(),
+        signer,
+        amount,
+    )?;
+    
+    // Update destination vault (increase available and total)
+    destination_vault.total_balance = destination_vault.total_balance.checked_add(received)
+        .ok_or(VaultError::Overflow)?;
+    destination_vault.available_balance = destination_vault.available_balance.checked_add(received)
+        .ok_or(VaultError::Overflow)?;
+    destination_vault.last_updated = clock.unix_timestamp;
+    destination_vault.validate_invariant()?;
+    
+    emit!(CollateralTransferred {
+        source_user: source_vault.user,
+        destination_user: destination_vault.user,
+        source_vault: source_vault.key(),
+        destination_vault: destination_vault.key(),
+        amount,
+        from_locked,
+        source_sequence: source_vault.next_sequence()?,
+        destination_sequence: destination_vault.next_sequence()?,
+        timestamp: clock.unix_timestamp,
+    });
+    
+    end_processing(source_vault);
+    end_processing(destination_vault);
+    Ok(())
+}
+
+/// Move `amount` of the vault's locked collateral no lock record holds out to
+/// `destination_token_account`, signed by the vault PDA; returns what the
+/// destination received
+fn pay_out_locked<'info>(
+    vault: &mut Account<'info, Vault>,
+    vault_token_account: &InterfaceAccount<'info, TokenAccount>,
+    destination_token_account: &mut InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<u64> {
+    vault.check_unrecorded_locked(amount)?;
+    begin_processing(vault)?;
+    
+    vault.locked_balance = vault.locked_balance.checked_sub(amount)
+        .ok_or(VaultError::Underflow)?;
+    vault.total_balance = vault.total_balance.checked_sub(amount)
+        .ok_or(VaultError::Underflow)?;
+    vault.last_updated = Clock::get()?.unix_timestamp;
+    vault.validate_invariant()?;
+    
+    let user = vault.user;
+    let sub_account_seed = Vault::sub_account_seed(vault.sub_account_id);
+    let signer_seeds = &[
+        VAULT_SEED,
+        user.as_ref(),
+        sub_account_seed.as_slice(),
+        &[vault.bump],
+    ];
+    let signer = &[&signer_seeds[..]];
+    
+    let received = transfer_tokens(
+        token_program,
+        mint,
+        vault_token_account,
+        destination_token_account,
+        vault.to_account_info(),
+        signer,
+        amount,
+    )?;
+    
+    end_processing(vault);
+    Ok(received)
+}
+
+/// Pay `amount` out of a vault's unrecorded locked collateral, or when it has
+/// less, all of it plus the shortfall from `insurance_fund`; returns what
+/// `destination_token_account` received in total
+#[allow(clippy::too_many_arguments)]
+fn pay_out_locked_or_insured<'info>(
+    vault: &mut Account<'info, Vault>,
+    vault_token_account: &InterfaceAccount<'info, TokenAccount>,
+    destination_token_account: &mut InterfaceAccount<'info, TokenAccount>,
+    insurance_fund: Option<&InterfaceAccount<'info, TokenAccount>>,
+    config: &Account<'info, ProgramConfig>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<u64> {
+    let from_vault = vault.unrecorded_locked_balance().min(amount);
+    let shortfall = amount - from_vault;
+    let insurance_fund = match insurance_fund {
+        Some(fund) if shortfall > 0 => fund,
+        _ => return pay_out_locked(vault, vault_token_account, destination_token_account, mint, token_program, amount),
+    };
+    require_keys_neq!(insurance_fund.key(), destination_token_account.key(), VaultError::TokenAccountMismatch);
+    
+    let mut received = 0;
+    if from_vault > 0 {
+        received = pay_out_locked(vault, vault_token_account, destination_token_account, mint, token_program, from_vault)?;
+    }
+    let covered = transfer_from_protocol_account(
+        config,
+        insurance_fund,
+        destination_token_account,
+        mint,
+        token_program,
+        shortfall,
+    )?;
+    
+    emit!(InsuranceShortfallCovered {
+        user: vault.user,
+        vault: vault.key(),
+        fund: insurance_fund.key(),
+        destination: destination_token_account.key(),
+        shortfall,
+        received: covered,
+        sequence: vault.next_sequence()?,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    let total = received.checked_add(covered)
+        .ok_or(VaultError::Overflow)?;
+    Ok(total)
+}
+
+/// Move `amount` out of a token account the config PDA owns: a treasury or an
+/// insurance fund
+fn transfer_from_protocol_account<'info>(
+    config: &Account<'info, ProgramConfig>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    to: &mut InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<u64> {
+    let signer_seeds = &[
+        CONFIG_SEED,
+        &[config.bump],
+    ];
+    let signer = &[&signer_seeds[..]];
+    
+    transfer_tokens(
+        token_program,
+        mint,
+        from,
+        to,
+        config.to_account_info(),
+        signer,
+        amount,
+    )
+}
+
+/// Move `amount` with `transfer_checked` and return what `to` received
+/// 
+/// Works for mints of either token program. A Token-2022 transfer fee is
+/// withheld in `to`, so it receives less than `amount`; the fee is emitted as
+/// `TransferFeeWithheld`.
+fn transfer_tokens<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    to: &mut InterfaceAccount<'info, TokenAccount>,
+    authority: AccountInfo<'info>,
+    signer: &[&[&[u8]]],
+    amount: u64,
+) -> Result<u64> {
+    let balance_before = to.amount;
+    
+    let cpi_accounts = TransferChecked {
+        from: from.to_account_info(),
+        mint: mint.to_account_info(),
+        to: to.to_account_info(),
+        authority,
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer);
+    token_interface::transfer_checked(cpi_ctx, amount, mint.decimals)?;
+    
+    to.reload()?;
+    let received = to.amount.checked_sub(balance_before)
+        .ok_or(VaultError::Underflow)?;
+    let fee = amount.checked_sub(received)
+        .ok_or(VaultError::Overflow)?;
+    
+    if fee > 0 {
+        emit!(TransferFeeWithheld {
+            mint: mint.key(),
+            source: from.key(),
+            destination: to.key(),
+            amount,
+            fee,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+    }
+    
+    Ok(received)
+}
+
+/// Close a temporary wSOL account to `user`, who receives its lamports as SOL
+fn close_wrapped_sol<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    wrapped_sol_account: &InterfaceAccount<'info, TokenAccount>,
+    user: &Signer<'info>,
+) -> Result<()> {
+    let cpi_accounts = CloseAccount {
+        account: wrapped_sol_account.to_account_info(),
+        destination: user.to_account_info(),
+        authority: user.to_account_info(),
+    };
+    token_interface::close_account(CpiContext::new(token_program.to_account_info(), cpi_accounts))
+}
+
+/// Empty an escrow's token account into `to` and close it to `payer`; returns
+/// what `to` received
+fn pay_out_escrow<'info>(
+    escrow: &Account<'info, EscrowedTransfer>,
+    escrow_token_account: &InterfaceAccount<'info, TokenAccount>,
+    to: &mut InterfaceAccount<'info, TokenAccount>,
+    payer: &AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<u64> {
+    let transfer_id = escrow.transfer_id.to_le_bytes();
+    let signer_seeds = &[
+        ESCROW_SEED,
+        escrow.source_vault.as_ref(),
+        &transfer_id,
+        &[escrow.bump],
+    ];
+    let signer = &[&signer_seeds[..]];
+    
+    let received = transfer_tokens(
+        token_program,
+        mint,
+        escrow_token_account,
+        to,
+        escrow.to_account_info(),
+        signer,
+        escrow.amount,
+    )?;
+    
+    harvest_withheld_fees(token_program, mint, escrow_token_account)?;
+    let cpi_accounts = CloseAccount {
+        account: escrow_token_account.to_account_info(),
+        destination: payer.clone(),
+        authority: escrow.to_account_info(),
+    };
+    token_interface::close_account(CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer))?;
+    
+    Ok(received)
+}
+
+/// Forward `memo` to the SPL Memo program, so it is logged with the token
+/// movement of the same transaction
+fn forward_memo<'info>(memo_program: Option<&Program<'info, Memo>>, memo: String) -> Result<()> {
+    require!(!memo.is_empty() && memo.len() <= MAX_MEMO_LEN, VaultError::InvalidMemo);
+    let memo_program = memo_program.ok_or(VaultError::MemoProgramMissing)?;
+    
+    memo::build_memo(CpiContext::new(memo_program.to_account_info(), BuildMemo {}), memo.as_bytes())
+}
+
+/// Refuse a collateral mint that could seize, block or tax vault funds
+/// 
+/// A permanent delegate could move or burn vault tokens, a transfer hook
+/// would run foreign code on every vault transfer and non-transferable
+/// tokens could never leave, so mints with any of them are refused outright.
+/// The freeze authority and transfer fee are weighed against the config's
+/// mint policy. Both fees a Token-2022 mint carries count, since the newer
+/// one takes effect at its epoch without further notice.
+fn check_mint_safety(mint: &InterfaceAccount<Mint>, config: &ProgramConfig) -> Result<()> {
+    let mint_info = mint.to_account_info();
+    let mut transfer_fee_bps = 0;
+    if mint_info.owner == &spl_token_2022::ID {
+        let data = mint_info.try_borrow_data()?;
+        let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+        let hostile = state.get_extension_types()?
+            .into_iter()
+            .any(|extension| matches!(extension,
+                ExtensionType::PermanentDelegate | ExtensionType::TransferHook | ExtensionType::NonTransferable));
+        require!(!hostile, VaultError::MintExtensionNotAllowed);
+        
+        if let Ok(fee_config) = state.get_extension::<TransferFeeConfig>() {
+            transfer_fee_bps = u16::from(fee_config.older_transfer_fee.transfer_fee_basis_points)
+                .max(u16::from(fee_config.newer_transfer_fee.transfer_fee_basis_points));
+        }
+    }
+    config.check_mint_policy(mint.freeze_authority.is_some(), transfer_fee_bps)
+}
+
+/// Harvest Token-2022 transfer fees withheld in `token_account` to the mint
+/// 
+/// An account holding withheld fees cannot be closed. Harvesting is
+/// permissionless and a no-op for legacy mints and accounts without fees.
+fn harvest_withheld_fees<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_account: &InterfaceAccount<'info, TokenAccount>,
+) -> Result<()> {
+    let token_account_info = token_account.to_account_info();
+    if token_account_info.owner != &spl_token_2022::ID {
+        return Ok(());
+    }
+    let withheld = {
+        let data = token_account_info.try_borrow_data()?;
+        let state = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?;
+        state.get_extension::<TransferFeeAmount>()
+            .map(|extension| u64::from(extension.withheld_amount))
+            .unwrap_or(0)
+    };
+    if withheld == 0 {
+        return Ok(());
+    }
+    
+    let ix = spl_token_2022::extension::transfer_fee::instruction::harvest_withheld_tokens_to_mint(
+        &token_program.key(),
+        &mint.key(),
+        &[&token_account.key()],
+    )?;
+    anchor_lang::solana_program::program::invoke(
+        &ix,
+        &[mint.to_account_info(), token_account_info, token_program.to_account_info()],
+    )?;
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = ProgramConfig::SIZE,
+        seeds = [CONFIG_SEED],
+        bump,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetCollateralMint<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    pub admin: Signer<'info>,
+    
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeAssetConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = AssetConfig::SIZE,
+        seeds = [ASSET_CONFIG_SEED, mint.key().as_ref()],
+        bump,
+    )]
+    pub asset_config: Account<'info, AssetConfig>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAssetConfig<'info> {
+    #[account(
+        mut,
+        seeds = [ASSET_CONFIG_SEED, asset_config.mint.as_ref()],
+        bump = asset_config.bump,
+    )]
+    pub asset_config: Account<'info, AssetConfig>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTreasury<'info> {
+    #[account(
+        init,
+        payer = admin,
+        token::mint = mint,
+        token::authority = config,
+        seeds = [TREASURY_SEED, mint.key().as_ref()],
+        bump,
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED, mint.key().as_ref()],
+        bump,
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = destination_token_account.mint == mint.key() @ VaultError::TokenAccountMismatch,
+        constraint = destination_token_account.key() != treasury_token_account.key() @ VaultError::TokenAccountMismatch,
+    )]
+    pub destination_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    pub admin: Signer<'info>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SyncBalance<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.user.as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        constraint = vault.is_active @ VaultError::VaultInactive,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    #[account(
+        constraint = vault_token_account.key() == vault.token_account @ VaultError::TokenAccountMismatch,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub caller: Signer<'info>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RepairVaultBump<'info> {
+    /// Checked against the canonical bump rather than the stored one
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.user.as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+        constraint = caller.key() == vault.authority @ VaultError::UnauthorizedCaller,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeInsuranceFund<'info> {
+    #[account(
+        init,
+        payer = admin,
+        token::mint = mint,
+        token::authority = config,
+        seeds = [INSURANCE_SEED, mint.key().as_ref()],
+        bump,
+    )]
+    pub insurance_fund: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(constraint = config.is_collateral_mint(&mint.key(), mint.decimals) @ VaultError::InvalidCollateralMint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct FundInsurance<'info> {
+    #[account(
+        mut,
+        seeds = [INSURANCE_SEED, mint.key().as_ref()],
+        bump,
+    )]
+    pub insurance_fund: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = funder_token_account.mint == mint.key() @ VaultError::TokenAccountMismatch,
+    )]
+    pub funder_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub funder: Signer<'info>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct DrawInsurance<'info> {
+    #[account(
+        mut,
+        seeds = [INSURANCE_SEED, mint.key().as_ref()],
+        bump,
+    )]
+    pub insurance_fund: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = destination_token_account.mint == mint.key() @ VaultError::TokenAccountMismatch,
+        constraint = destination_token_account.key() != insurance_fund.key() @ VaultError::TokenAccountMismatch,
+    )]
+    pub destination_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    pub admin: Signer<'info>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(sub_account_id: u16)]
 pub struct InitializeVault<'info> {
     #[account(
-        init,
-        payer = user,
-        space = Vault::SIZE,
-        seeds = [b"vault", user.key().as_ref()],
-        bump,
+        init,
+        payer = payer,
+        space = Vault::SIZE,
+        seeds = [VAULT_SEED, user.key().as_ref(), &Vault::sub_account_seed(sub_account_id)],
+        bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    #[account(
+        init,
+        payer = payer,
+        token::mint = usdt_mint,
+        token::authority = vault,
+        seeds = [TOKEN_SEED, vault.key().as_ref()],
+        bump,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub user: Signer<'info>,
+    
+    /// Funds the rent of both accounts; the user or a sponsor
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// CHECK: Authority for CPI calls (trading program)
+    pub authority: AccountInfo<'info>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    /// Only the configured collateral mint; vaults of any other mint cannot be opened
+    #[account(constraint = config.is_collateral_mint(&usdt_mint.key(), usdt_mint.decimals) @ VaultError::InvalidCollateralMint)]
+    pub usdt_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.user.as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        has_one = user,
+        constraint = vault.is_active @ VaultError::VaultInactive,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    /// No new collateral goes into an account someone else could move or close
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == vault.token_account,
+        constraint = vault_token_account.delegate.is_none() @ VaultError::TokenAccountDelegated,
+        constraint = vault_token_account.close_authority.is_none() @ VaultError::TokenAccountDelegated,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = user_token_account.owner == user.key(),
+        constraint = user_token_account.mint == vault_token_account.mint,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    /// New collateral is only taken in the configured mint; withdrawals stay
+    /// open so a vault of any other mint can still be emptied
+    #[account(
+        constraint = mint.key() == vault_token_account.mint,
+        constraint = config.is_collateral_mint(&mint.key(), mint.decimals) @ VaultError::InvalidCollateralMint,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct DepositFromAssociated<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.user.as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        has_one = user,
+        constraint = vault.is_active @ VaultError::VaultInactive,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == vault.token_account,
+        constraint = vault_token_account.delegate.is_none() @ VaultError::TokenAccountDelegated,
+        constraint = vault_token_account.close_authority.is_none() @ VaultError::TokenAccountDelegated,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(
+        constraint = mint.key() == vault_token_account.mint,
+        constraint = config.is_collateral_mint(&mint.key(), mint.decimals) @ VaultError::InvalidCollateralMint,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct DepositFor<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.user.as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        constraint = vault.is_active @ VaultError::VaultInactive,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    /// No new collateral goes into an account someone else could move or close
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == vault.token_account,
+        constraint = vault_token_account.delegate.is_none() @ VaultError::TokenAccountDelegated,
+        constraint = vault_token_account.close_authority.is_none() @ VaultError::TokenAccountDelegated,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = depositor_token_account.owner == depositor.key(),
+        constraint = depositor_token_account.mint == vault_token_account.mint,
+    )]
+    pub depositor_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub depositor: Signer<'info>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(
+        constraint = mint.key() == vault_token_account.mint,
+        constraint = config.is_collateral_mint(&mint.key(), mint.decimals) @ VaultError::InvalidCollateralMint,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct DepositSol<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, user.key().as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        has_one = user,
+        constraint = vault.is_active @ VaultError::VaultInactive,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    /// No new collateral goes into an account someone else could move or close
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == vault.token_account,
+        constraint = vault_token_account.delegate.is_none() @ VaultError::TokenAccountDelegated,
+        constraint = vault_token_account.close_authority.is_none() @ VaultError::TokenAccountDelegated,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Wraps the deposit and is closed back to the user before returning
+    #[account(
+        init,
+        payer = user,
+        token::mint = mint,
+        token::authority = user,
+        seeds = [WRAPPED_SOL_SEED, vault.key().as_ref()],
+        bump,
+    )]
+    pub wrapped_sol_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(
+        constraint = mint.key() == vault_token_account.mint,
+        constraint = mint.key() == spl_token::native_mint::ID @ VaultError::NotWrappedSol,
+        constraint = config.is_collateral_mint(&mint.key(), mint.decimals) @ VaultError::InvalidCollateralMint,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, user.key().as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        has_one = user,
+        constraint = vault.is_active @ VaultError::VaultInactive,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == vault.token_account @ VaultError::TokenAccountMismatch,
+        constraint = vault_token_account.owner == vault.key() @ VaultError::TokenAccountMismatch,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = user_token_account.owner == user.key() @ VaultError::UnauthorizedCaller,
+        constraint = user_token_account.mint == vault_token_account.mint @ VaultError::TokenAccountMismatch,
+        constraint = user_token_account.key() != vault_token_account.key() @ VaultError::TokenAccountMismatch,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Receives the withdrawal fee; needed only while the config charges one
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED, mint.key().as_ref()],
+        bump,
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(constraint = mint.key() == vault_token_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    /// Needed only by the `_with_memo` variant
+    pub memo_program: Option<Program<'info, Memo>>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawSol<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, user.key().as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        has_one = user,
+        constraint = vault.is_active @ VaultError::VaultInactive,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == vault.token_account @ VaultError::TokenAccountMismatch,
+        constraint = vault_token_account.owner == vault.key() @ VaultError::TokenAccountMismatch,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Receives the withdrawal and is closed to the user, unwrapping it
+    #[account(
+        init,
+        payer = user,
+        token::mint = mint,
+        token::authority = user,
+        seeds = [WRAPPED_SOL_SEED, vault.key().as_ref()],
+        bump,
+    )]
+    pub wrapped_sol_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Receives the withdrawal fee; needed only while the config charges one
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED, mint.key().as_ref()],
+        bump,
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(
+        constraint = mint.key() == vault_token_account.mint,
+        constraint = mint.key() == spl_token::native_mint::ID @ VaultError::NotWrappedSol,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawToAssociated<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, user.key().as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        has_one = user,
+        constraint = vault.is_active @ VaultError::VaultInactive,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == vault.token_account @ VaultError::TokenAccountMismatch,
+        constraint = vault_token_account.owner == vault.key() @ VaultError::TokenAccountMismatch,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Created here when missing, with the user paying its rent
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Receives the withdrawal fee; needed only while the config charges one
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED, mint.key().as_ref()],
+        bump,
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(constraint = mint.key() == vault_token_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetWithdrawalCooldown<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, user.key().as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        has_one = user,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    pub user: Signer<'info>,
+}
+
+/// Only the owner freezes, asks to unfreeze and unfreezes
+#[derive(Accounts)]
+pub struct SetVaultFreeze<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, user.key().as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        has_one = user,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetWithdrawalCooldownWithAdminApproval<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, user.key().as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        has_one = user,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    pub user: Signer<'info>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RequestWithdrawal<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, user.key().as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        has_one = user,
+        constraint = vault.is_active @ VaultError::VaultInactive,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    pub user: Signer<'info>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+pub struct CancelWithdrawal<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.user.as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    /// CHECK: The vault owner or the config admin
+    pub canceller: Signer<'info>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawWithAdminApproval<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, user.key().as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        has_one = user,
+        constraint = vault.is_active @ VaultError::VaultInactive,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == vault.token_account @ VaultError::TokenAccountMismatch,
+        constraint = vault_token_account.owner == vault.key() @ VaultError::TokenAccountMismatch,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = user_token_account.owner == user.key() @ VaultError::UnauthorizedCaller,
+        constraint = user_token_account.mint == vault_token_account.mint @ VaultError::TokenAccountMismatch,
+        constraint = user_token_account.key() != vault_token_account.key() @ VaultError::TokenAccountMismatch,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Receives the withdrawal fee; needed only while the config charges one
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED, mint.key().as_ref()],
+        bump,
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+        has_one = admin @ VaultError::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    pub admin: Signer<'info>,
+    
+    #[account(constraint = mint.key() == vault_token_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct LockCollateral<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.user.as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        constraint = vault.is_active @ VaultError::VaultInactive,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    /// The vault's collateral account; read only to learn its mint
+    #[account(constraint = vault_token_account.key() == vault.token_account)]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Haircut of the vault's mint; locks cannot be taken before it is set
+    #[account(
+        seeds = [ASSET_CONFIG_SEED, vault_token_account.mint.as_ref()],
+        bump = asset_config.bump,
+    )]
+    pub asset_config: Account<'info, AssetConfig>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    /// CHECK: Authority must be authorized by the vault for CPI calls
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UnlockCollateral<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.user.as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        constraint = vault.is_active @ VaultError::VaultInactive,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    /// CHECK: Authority must be authorized by the vault for CPI calls
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct TransferCollateral<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, source_vault.user.as_ref(), &Vault::sub_account_seed(source_vault.sub_account_id)],
+        bump = source_vault.bump,
+        constraint = source_vault.is_active @ VaultError::VaultInactive,
+        constraint = source_vault.key() != destination_vault.key() @ VaultError::SameVault,
+        constraint = !source_vault.processing @ VaultError::VaultBusy,
+    )]
+    pub source_vault: Account<'info, Vault>,
+    
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, destination_vault.user.as_ref(), &Vault::sub_account_seed(destination_vault.sub_account_id)],
+        bump = destination_vault.bump,
+        constraint = destination_vault.is_active @ VaultError::VaultInactive,
+        constraint = !destination_vault.processing @ VaultError::VaultBusy,
+    )]
+    pub destination_vault: Account<'info, Vault>,
+    
+    #[account(
+        mut,
+        constraint = source_token_account.key() == source_vault.token_account @ VaultError::TokenAccountMismatch,
+        constraint = source_token_account.owner == source_vault.key() @ VaultError::TokenAccountMismatch,
+    )]
+    pub source_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = destination_token_account.key() == destination_vault.token_account @ VaultError::TokenAccountMismatch,
+        constraint = destination_token_account.owner == destination_vault.key() @ VaultError::TokenAccountMismatch,
+        constraint = destination_token_account.mint == source_token_account.mint @ VaultError::TokenAccountMismatch,
+        constraint = destination_token_account.key() != source_token_account.key() @ VaultError::SameVault,
+    )]
+    pub destination_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: Authority must be authorized by source_vault for transfers
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(constraint = mint.key() == source_token_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    /// Needed only by the `_with_memo` variant
+    pub memo_program: Option<Program<'info, Memo>>,
+}
+
+#[derive(Accounts)]
+pub struct TransferAvailableCollateral<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, user.key().as_ref(), &Vault::sub_account_seed(source_vault.sub_account_id)],
+        bump = source_vault.bump,
+        has_one = user,
+        constraint = source_vault.is_active @ VaultError::VaultInactive,
+        constraint = source_vault.key() != destination_vault.key() @ VaultError::SameVault,
+        constraint = !source_vault.processing @ VaultError::VaultBusy,
+    )]
+    pub source_vault: Account<'info, Vault>,
+    
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, destination_vault.user.as_ref(), &Vault::sub_account_seed(destination_vault.sub_account_id)],
+        bump = destination_vault.bump,
+        constraint = destination_vault.is_active @ VaultError::VaultInactive,
+        constraint = !destination_vault.processing @ VaultError::VaultBusy,
+    )]
+    pub destination_vault: Account<'info, Vault>,
+    
+    #[account(
+        mut,
+        constraint = source_token_account.key() == source_vault.token_account @ VaultError::TokenAccountMismatch,
+        constraint = source_token_account.owner == source_vault.key() @ VaultError::TokenAccountMismatch,
+    )]
+    pub source_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = destination_token_account.key() == destination_vault.token_account @ VaultError::TokenAccountMismatch,
+        constraint = destination_token_account.owner == destination_vault.key() @ VaultError::TokenAccountMismatch,
+        constraint = destination_token_account.mint == source_token_account.mint @ VaultError::TokenAccountMismatch,
+        constraint = destination_token_account.key() != source_token_account.key() @ VaultError::SameVault,
+    )]
+    pub destination_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub user: Signer<'info>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(constraint = mint.key() == source_token_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(transfer_id: u64)]
+pub struct InitiateTransfer<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, source_vault.user.as_ref(), &Vault::sub_account_seed(source_vault.sub_account_id)],
+        bump = source_vault.bump,
+        constraint = source_vault.is_active @ VaultError::VaultInactive,
+        constraint = source_vault.key() != destination_vault.key() @ VaultError::SameVault,
+        constraint = !source_vault.processing @ VaultError::VaultBusy,
+    )]
+    pub source_vault: Account<'info, Vault>,
+    
+    #[account(
+        seeds = [VAULT_SEED, destination_vault.user.as_ref(), &Vault::sub_account_seed(destination_vault.sub_account_id)],
+        bump = destination_vault.bump,
+        constraint = destination_vault.is_active @ VaultError::VaultInactive,
+    )]
+    pub destination_vault: Account<'info, Vault>,
+    
+    #[account(
+        mut,
+        constraint = source_token_account.key() == source_vault.token_account @ VaultError::TokenAccountMismatch,
+        constraint = source_token_account.owner == source_vault.key() @ VaultError::TokenAccountMismatch,
+    )]
+    pub source_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = EscrowedTransfer::SIZE,
+        seeds = [ESCROW_SEED, source_vault.key().as_ref(), &transfer_id.to_le_bytes()],
+        bump,
+    )]
+    pub escrow: Account<'info, EscrowedTransfer>,
+    
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint,
+        token::authority = escrow,
+        seeds = [ESCROW_TOKEN_SEED, escrow.key().as_ref()],
+        bump,
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: Authority must be authorized by source_vault for transfers
+    pub authority: Signer<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(constraint = mint.key() == source_token_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptTransfer<'info> {
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, source_vault.key().as_ref(), &escrow.transfer_id.to_le_bytes()],
+        bump = escrow.bump,
+        has_one = source_vault,
+        has_one = destination_vault,
+        has_one = payer,
+        has_one = mint,
+        close = payer,
+    )]
+    pub escrow: Account<'info, EscrowedTransfer>,
+    
+    #[account(
+        mut,
+        seeds = [ESCROW_TOKEN_SEED, escrow.key().as_ref()],
+        bump,
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Counts the escrow among its open records until it closes
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, source_vault.user.as_ref(), &Vault::sub_account_seed(source_vault.sub_account_id)],
+        bump = source_vault.bump,
+        constraint = !source_vault.processing @ VaultError::VaultBusy,
+    )]
+    pub source_vault: Account<'info, Vault>,
+    
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, user.key().as_ref(), &Vault::sub_account_seed(destination_vault.sub_account_id)],
+        bump = destination_vault.bump,
+        has_one = user,
+        constraint = destination_vault.is_active @ VaultError::VaultInactive,
+        constraint = !destination_vault.processing @ VaultError::VaultBusy,
+    )]
+    pub destination_vault: Account<'info, Vault>,
+    
+    #[account(
+        mut,
+        constraint = destination_token_account.key() == destination_vault.token_account @ VaultError::TokenAccountMismatch,
+        constraint = destination_token_account.mint == mint.key() @ VaultError::TokenAccountMismatch,
+    )]
+    pub destination_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub user: Signer<'info>,
+    
+    /// CHECK: Receives the rent of the closed accounts; must be the recorded payer
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    /// Writable to take withheld Token-2022 transfer fees before the escrow
+    /// token account closes
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CancelTransfer<'info> {
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, source_vault.key().as_ref(), &escrow.transfer_id.to_le_bytes()],
+        bump = escrow.bump,
+        has_one = source_vault,
+        has_one = payer,
+        has_one = mint,
+        close = payer,
+    )]
+    pub escrow: Account<'info, EscrowedTransfer>,
+    
+    #[account(
+        mut,
+        seeds = [ESCROW_TOKEN_SEED, escrow.key().as_ref()],
+        bump,
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, source_vault.user.as_ref(), &Vault::sub_account_seed(source_vault.sub_account_id)],
+        bump = source_vault.bump,
+        constraint = source_vault.is_active @ VaultError::VaultInactive,
+        constraint = !source_vault.processing @ VaultError::VaultBusy,
+    )]
+    pub source_vault: Account<'info, Vault>,
+    
+    #[account(
+        mut,
+        constraint = source_token_account.key() == source_vault.token_account @ VaultError::TokenAccountMismatch,
+    )]
+    pub source_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Before expiry, the initiating authority or the source vault's authority
+    pub caller: Signer<'info>,
+    
+    /// CHECK: Receives the rent of the closed accounts; must be the recorded payer
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    /// Writable to take withheld Token-2022 transfer fees before the escrow
+    /// token account closes
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(lock_id: u64)]
+pub struct OpenLock<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.user.as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        constraint = vault.is_active @ VaultError::VaultInactive,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = LockRecord::SIZE,
+        seeds = [LOCK_SEED, vault.key().as_ref(), &lock_id.to_le_bytes()],
+        bump,
+    )]
+    pub lock_record: Account<'info, LockRecord>,
+    
+    /// The vault's collateral account; read only to learn its mint
+    #[account(constraint = vault_token_account.key() == vault.token_account)]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Haircut of the vault's mint; locks cannot be taken before it is set
+    #[account(
+        seeds = [ASSET_CONFIG_SEED, vault_token_account.mint.as_ref()],
+        bump = asset_config.bump,
+    )]
+    pub asset_config: Account<'info, AssetConfig>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    /// CHECK: Authority must be authorized by the vault for CPI calls
+    pub authority: Signer<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseLock<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.user.as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        constraint = vault.is_active @ VaultError::VaultInactive,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    #[account(
+        mut,
+        seeds = [LOCK_SEED, vault.key().as_ref(), &lock_record.lock_id.to_le_bytes()],
+        bump = lock_record.bump,
+        has_one = vault,
+        has_one = payer,
+    )]
+    pub lock_record: Account<'info, LockRecord>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    /// CHECK: Authority must be allowed to release the lock
+    pub authority: Signer<'info>,
+    
+    /// CHECK: Receives the record's rent once it is empty; must be the recorded payer
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AnnounceForceUnlock<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.user.as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        constraint = vault.is_active @ VaultError::VaultInactive,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    /// One announcement per vault at a time
+    #[account(
+        init,
+        payer = admin,
+        space = ForceUnlock::SIZE,
+        seeds = [FORCE_UNLOCK_SEED, vault.key().as_ref()],
+        bump,
+    )]
+    pub force_unlock: Account<'info, ForceUnlock>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = admin,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteForceUnlock<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.user.as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        constraint = vault.is_active @ VaultError::VaultInactive,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    /// Executed by the admin that announced it, while still the config admin
+    #[account(
+        mut,
+        seeds = [FORCE_UNLOCK_SEED, vault.key().as_ref()],
+        bump = force_unlock.bump,
+        has_one = vault,
+        has_one = admin,
+        close = admin,
+    )]
+    pub force_unlock: Account<'info, ForceUnlock>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = admin,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelForceUnlock<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.user.as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    #[account(
+        mut,
+        seeds = [FORCE_UNLOCK_SEED, vault.key().as_ref()],
+        bump = force_unlock.bump,
+        has_one = vault,
+        has_one = admin,
+        close = admin,
+    )]
+    pub force_unlock: Account<'info, ForceUnlock>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    /// The config admin or one of the vault's authorities
+    pub canceller: Signer<'info>,
+    
+    /// CHECK: Receives the announcement's rent; must be the admin that announced it
+    #[account(mut)]
+    pub admin: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireLock<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.user.as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        constraint = vault.is_active @ VaultError::VaultInactive,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    #[account(
+        mut,
+        seeds = [LOCK_SEED, vault.key().as_ref(), &lock_record.lock_id.to_le_bytes()],
+        bump = lock_record.bump,
+        has_one = vault,
+        has_one = payer,
+        close = payer,
+    )]
+    pub lock_record: Account<'info, LockRecord>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    /// Anyone; recorded on the event
+    pub caller: Signer<'info>,
+    
+    /// CHECK: Receives the record's rent; must be the recorded payer
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct TransferFromLock<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, source_vault.user.as_ref(), &Vault::sub_account_seed(source_vault.sub_account_id)],
+        bump = source_vault.bump,
+        constraint = source_vault.is_active @ VaultError::VaultInactive,
+        constraint = source_vault.key() != destination_vault.key() @ VaultError::SameVault,
+        constraint = !source_vault.processing @ VaultError::VaultBusy,
+    )]
+    pub source_vault: Account<'info, Vault>,
+    
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, destination_vault.user.as_ref(), &Vault::sub_account_seed(destination_vault.sub_account_id)],
+        bump = destination_vault.bump,
+        constraint = destination_vault.is_active @ VaultError::VaultInactive,
+        constraint = !destination_vault.processing @ VaultError::VaultBusy,
+    )]
+    pub destination_vault: Account<'info, Vault>,
+    
+    #[account(
+        mut,
+        seeds = [LOCK_SEED, source_vault.key().as_ref(), &lock_record.lock_id.to_le_bytes()],
+        bump = lock_record.bump,
+        constraint = lock_record.vault == source_vault.key() @ VaultError::UnauthorizedCaller,
+        has_one = payer,
+    )]
+    pub lock_record: Account<'info, LockRecord>,
+    
+    #[account(
+        mut,
+        constraint = source_token_account.key() == source_vault.token_account @ VaultError::TokenAccountMismatch,
+        constraint = source_token_account.owner == source_vault.key() @ VaultError::TokenAccountMismatch,
+    )]
+    pub source_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = destination_token_account.key() == destination_vault.token_account @ VaultError::TokenAccountMismatch,
+        constraint = destination_token_account.owner == destination_vault.key() @ VaultError::TokenAccountMismatch,
+        constraint = destination_token_account.mint == source_token_account.mint @ VaultError::TokenAccountMismatch,
+        constraint = destination_token_account.key() != source_token_account.key() @ VaultError::SameVault,
+    )]
+    pub destination_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    /// CHECK: Authority must be allowed to release the lock
+    pub authority: Signer<'info>,
+    
+    /// CHECK: Receives the record's rent once it is empty; must be the recorded payer
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    
+    #[account(constraint = mint.key() == source_token_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct TransferCollateralWithAdminApproval<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, source_vault.user.as_ref(), &Vault::sub_account_seed(source_vault.sub_account_id)],
+        bump = source_vault.bump,
+        constraint = source_vault.is_active @ VaultError::VaultInactive,
+        constraint = source_vault.key() != destination_vault.key() @ VaultError::SameVault,
+        constraint = !source_vault.processing @ VaultError::VaultBusy,
+    )]
+    pub source_vault: Account<'info, Vault>,
+    
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, destination_vault.user.as_ref(), &Vault::sub_account_seed(destination_vault.sub_account_id)],
+        bump = destination_vault.bump,
+        constraint = destination_vault.is_active @ VaultError::VaultInactive,
+        constraint = !destination_vault.processing @ VaultError::VaultBusy,
+    )]
+    pub destination_vault: Account<'info, Vault>,
+    
+    #[account(
+        mut,
+        constraint = source_token_account.key() == source_vault.token_account @ VaultError::TokenAccountMismatch,
+        constraint = source_token_account.owner == source_vault.key() @ VaultError::TokenAccountMismatch,
+    )]
+    pub source_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = destination_token_account.key() == destination_vault.token_account @ VaultError::TokenAccountMismatch,
+        constraint = destination_token_account.owner == destination_vault.key() @ VaultError::TokenAccountMismatch,
+        constraint = destination_token_account.mint == source_token_account.mint @ VaultError::TokenAccountMismatch,
+        constraint = destination_token_account.key() != source_token_account.key() @ VaultError::SameVault,
+    )]
+    pub destination_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: Authority must be authorized by source_vault for transfers
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+        has_one = admin @ VaultError::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    pub admin: Signer<'info>,
+    
+    #[account(constraint = mint.key() == source_token_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Liquidate<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.user.as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        constraint = vault.is_active @ VaultError::VaultInactive,
+        constraint = !vault.processing @ VaultError::VaultBusy,
     )]
     pub vault: Account<'info, Vault>,
     
     #[account(
-        init,
-        payer = user,
-        token::mint = usdt_mint,
-        token::authority = vault,
-        seeds = [b"token", vault.key().as_ref()],
+        mut,
+        constraint = vault_token_account.key() == vault.token_account @ VaultError::TokenAccountMismatch,
+        constraint = vault_token_account.owner == vault.key() @ VaultError::TokenAccountMismatch,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Receives the seized collateral
+    #[account(
+        mut,
+        constraint = destination_token_account.mint == vault_token_account.mint @ VaultError::TokenAccountMismatch,
+        constraint = destination_token_account.key() != vault_token_account.key() @ VaultError::TokenAccountMismatch,
+    )]
+    pub destination_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Pays any part of the amount beyond the locked balance; omit to refuse
+    /// such an amount instead
+    #[account(
+        mut,
+        seeds = [INSURANCE_SEED, mint.key().as_ref()],
         bump,
     )]
-    pub vault_token_account: Account<'info, TokenAccount>,
+    pub insurance_fund: Option<InterfaceAccount<'info, TokenAccount>>,
     
-    #[account(mut)]
-    pub user: Signer<'info>,
+    /// Receives the keeper reward out of the seized collateral; omit to pay
+    /// no reward
+    #[account(
+        mut,
+        constraint = keeper_token_account.mint == vault_token_account.mint @ VaultError::TokenAccountMismatch,
+        constraint = keeper_token_account.key() != vault_token_account.key() @ VaultError::TokenAccountMismatch,
+    )]
+    pub keeper_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
     
-    /// CHECK: Authority for CPI calls (trading program)
-    pub authority: AccountInfo<'info>,
+    /// CHECK: Liquidator must be authorized by the vault
+    pub liquidator: Signer<'info>,
     
-    pub usdt_mint: Account<'info, Mint>,
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(constraint = mint.key() == vault_token_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct Deposit<'info> {
+pub struct SettleLoss<'info> {
     #[account(
         mut,
-        has_one = user,
+        seeds = [VAULT_SEED, vault.user.as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
         constraint = vault.is_active @ VaultError::VaultInactive,
+        constraint = !vault.processing @ VaultError::VaultBusy,
     )]
     pub vault: Account<'info, Vault>,
     
     #[account(
         mut,
+        constraint = vault_token_account.key() == vault.token_account @ VaultError::TokenAccountMismatch,
+        constraint = vault_token_account.owner == vault.key() @ VaultError::TokenAccountMismatch,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Receives the loss
+    #[account(
+        mut,
+        constraint = counterparty_token_account.mint == vault_token_account.mint @ VaultError::TokenAccountMismatch,
+        constraint = counterparty_token_account.key() != vault_token_account.key() @ VaultError::TokenAccountMismatch,
+    )]
+    pub counterparty_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Pays any part of the amount beyond the locked balance; omit to refuse
+    /// such an amount instead
+    #[account(
+        mut,
+        seeds = [INSURANCE_SEED, mint.key().as_ref()],
+        bump,
+    )]
+    pub insurance_fund: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// CHECK: Authority must be authorized by the vault
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(constraint = mint.key() == vault_token_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct RecoverForeignTokens<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    pub admin: Signer<'info>,
+    
+    /// Writable to count the event
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.user.as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    /// The vault's collateral account; read only to learn the collateral mint
+    #[account(
         constraint = vault_token_account.key() == vault.token_account,
     )]
-    pub vault_token_account: Account<'info, TokenAccount>,
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
     
     #[account(
         mut,
-        constraint = user_token_account.owner == user.key(),
-        constraint = user_token_account.mint == vault_token_account.mint,
+        constraint = foreign_token_account.owner == vault.key() @ VaultError::UnauthorizedCaller,
+        constraint = foreign_token_account.key() != vault.token_account @ VaultError::CollateralNotRecoverable,
+        constraint = foreign_token_account.mint != vault_token_account.mint @ VaultError::CollateralNotRecoverable,
     )]
-    pub user_token_account: Account<'info, TokenAccount>,
+    pub foreign_token_account: InterfaceAccount<'info, TokenAccount>,
     
-    #[account(mut)]
-    pub user: Signer<'info>,
+    #[account(
+        mut,
+        constraint = recovery_token_account.mint == foreign_token_account.mint,
+    )]
+    pub recovery_token_account: InterfaceAccount<'info, TokenAccount>,
     
-    pub token_program: Program<'info, Token>,
+    #[account(constraint = foreign_mint.key() == foreign_token_account.mint)]
+    pub foreign_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct Withdraw<'info> {
+pub struct RevokeTokenDelegate<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    pub admin: Signer<'info>,
+    
+    /// Writable to count the event
     #[account(
         mut,
-        has_one = user,
-        constraint = vault.is_active @ VaultError::VaultInactive,
+        seeds = [VAULT_SEED, vault.user.as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        constraint = !vault.processing @ VaultError::VaultBusy,
     )]
     pub vault: Account<'info, Vault>,
     
@@ -381,152 +4423,263 @@ pub struct Withdraw<'info> {
         mut,
         constraint = vault_token_account.key() == vault.token_account,
     )]
-    pub vault_token_account: Account<'info, TokenAccount>,
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
     
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateConfigLayout<'info> {
+    /// CHECK: Legacy configs no longer deserialize as `ProgramConfig`; the PDA,
+    /// owner, size and discriminator are checked here and in the handler
     #[account(
         mut,
-        constraint = user_token_account.owner == user.key(),
-        constraint = user_token_account.mint == vault_token_account.mint,
+        seeds = [CONFIG_SEED],
+        bump,
+        owner = crate::ID,
     )]
-    pub user_token_account: Account<'info, TokenAccount>,
+    pub config: UncheckedAccount<'info>,
     
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub payer: Signer<'info>,
     
-    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct LockCollateral<'info> {
+#[instruction(sub_account_id: u16)]
+pub struct MigrateVaultLayout<'info> {
+    /// CHECK: Legacy vaults no longer deserialize as `Vault`; the PDA, owner,
+    /// size and discriminator are checked here and in the handler
     #[account(
         mut,
-        constraint = vault.is_active @ VaultError::VaultInactive,
+        seeds = [VAULT_SEED, user.key().as_ref(), &Vault::sub_account_seed(sub_account_id)],
+        bump,
+        owner = crate::ID,
+    )]
+    pub vault: UncheckedAccount<'info>,
+    
+    /// CHECK: Only used to derive the vault address
+    pub user: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RotateAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.user.as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        has_one = authority @ VaultError::UnauthorizedCaller,
+        constraint = !vault.processing @ VaultError::VaultBusy,
     )]
     pub vault: Account<'info, Vault>,
     
-    /// CHECK: Authority must match vault.authority for CPI calls
     pub authority: Signer<'info>,
+    
+    pub new_authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct UnlockCollateral<'info> {
+pub struct AddAuthorizedAuthority<'info> {
     #[account(
         mut,
-        constraint = vault.is_active @ VaultError::VaultInactive,
+        seeds = [VAULT_SEED, vault.user.as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        has_one = authority @ VaultError::UnauthorizedCaller,
+        realloc = Vault::size_with_authorities(vault.authorized_authorities.len() + 1),
+        realloc::payer = payer,
+        realloc::zero = true,
+        constraint = !vault.processing @ VaultError::VaultBusy,
     )]
     pub vault: Account<'info, Vault>,
     
-    /// CHECK: Authority must match vault.authority for CPI calls
     pub authority: Signer<'info>,
+    
+    pub new_authority: Signer<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct TransferCollateral<'info> {
+pub struct RemoveAuthorizedAuthority<'info> {
     #[account(
         mut,
-        constraint = source_vault.is_active @ VaultError::VaultInactive,
+        seeds = [VAULT_SEED, vault.user.as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        has_one = authority @ VaultError::UnauthorizedCaller,
+        realloc = Vault::size_with_authorities(vault.authorized_authorities.len().saturating_sub(1)),
+        realloc::payer = payer,
+        realloc::zero = true,
+        constraint = !vault.processing @ VaultError::VaultBusy,
     )]
-    pub source_vault: Account<'info, Vault>,
+    pub vault: Account<'info, Vault>,
+    
+    pub authority: Signer<'info>,
+    
+    /// Receives the rent freed by the shrink
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SweepDormantVault<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
     
     #[account(
         mut,
-        constraint = destination_vault.is_active @ VaultError::VaultInactive,
+        seeds = [VAULT_SEED, vault.user.as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        constraint = !vault.processing @ VaultError::VaultBusy,
     )]
-    pub destination_vault: Account<'info, Vault>,
+    pub vault: Account<'info, Vault>,
+    
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == vault.token_account,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = DormantFunds::SIZE,
+        seeds = [DORMANT_SEED, vault.key().as_ref()],
+        bump,
+    )]
+    pub dormant_funds: Account<'info, DormantFunds>,
+    
+    #[account(
+        init,
+        payer = admin,
+        token::mint = mint,
+        token::authority = dormant_funds,
+        seeds = [DORMANT_TOKEN_SEED, dormant_funds.key().as_ref()],
+        bump,
+    )]
+    pub dormant_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(constraint = mint.key() == vault_token_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimDormantFunds<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, user.key().as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        has_one = user,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    pub user: Signer<'info>,
     
     #[account(
         mut,
-        constraint = source_token_account.key() == source_vault.token_account,
+        constraint = vault_token_account.key() == vault.token_account,
+        constraint = vault_token_account.delegate.is_none() @ VaultError::TokenAccountDelegated,
+        constraint = vault_token_account.close_authority.is_none() @ VaultError::TokenAccountDelegated,
     )]
-    pub source_token_account: Account<'info, TokenAccount>,
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
     
     #[account(
         mut,
-        constraint = destination_token_account.key() == destination_vault.token_account,
-        constraint = destination_token_account.mint == source_token_account.mint,
+        seeds = [DORMANT_SEED, vault.key().as_ref()],
+        bump = dormant_funds.bump,
+        has_one = vault,
+        has_one = payer,
+        close = payer,
     )]
-    pub destination_token_account: Account<'info, TokenAccount>,
+    pub dormant_funds: Account<'info, DormantFunds>,
     
-    /// CHECK: Authority must match source_vault.authority for transfers
-    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [DORMANT_TOKEN_SEED, dormant_funds.key().as_ref()],
+        bump,
+    )]
+    pub dormant_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: Receives the rent of the closed accounts; must be the recorded payer
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = !config.paused @ VaultError::ProgramPaused,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    /// Writable to take withheld Token-2022 transfer fees before the dormant
+    /// token account closes
+    #[account(
+        mut,
+        constraint = mint.key() == vault_token_account.mint,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CloseVault<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, user.key().as_ref(), &Vault::sub_account_seed(vault.sub_account_id)],
+        bump = vault.bump,
+        has_one = user,
+        constraint = vault.is_active @ VaultError::VaultInactive,
+        close = rent_destination,
+        constraint = !vault.processing @ VaultError::VaultBusy,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    /// Receives the rent of the vault and its token account; the user's own
+    /// wallet or any other
+    #[account(mut)]
+    pub rent_destination: SystemAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == vault.token_account @ VaultError::TokenAccountMismatch,
+        constraint = vault_token_account.amount == 0 @ VaultError::VaultNotEmpty,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Writable to take withheld Token-2022 transfer fees before the token
+    /// account closes
+    #[account(
+        mut,
+        constraint = mint.key() == vault_token_account.mint,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
     
-    pub token_program: Program<'info, Token>,
-}
-
-#[error_code]
-pub enum VaultError {
-    #[msg("Vault is inactive")]
-    VaultInactive,
-    #[msg("Insufficient available balance")]
-    InsufficientAvailableBalance,
-    #[msg("Insufficient locked balance")]
-    InsufficientLockedBalance,
-    #[msg("Invalid amount")]
-    InvalidAmount,
-    #[msg("Unauthorized caller - only authorized programs can call this function")]
-    UnauthorizedCaller,
-    #[msg("Math overflow occurred")]
-    Overflow,
-    #[msg("Math underflow occurred")]
-    Underflow,
-    #[msg("Vault invariant violated - balances don't add up")]
-    InvariantViolated,
-}
-
-#[event]
-pub struct VaultInitialized {
-    pub user: Pubkey,
-    pub vault: Pubkey,
-    pub token_account: Pubkey,
-    pub timestamp: i64,
-}
-
-#[event]
-pub struct DepositEvent {
-    pub user: Pubkey,
-    pub vault: Pubkey,
-    pub amount: u64,
-    pub new_total_balance: u64,
-    pub new_available_balance: u64,
-    pub timestamp: i64,
-}
-
-#[event]
-pub struct WithdrawEvent {
-    pub user: Pubkey,
-    pub vault: Pubkey,
-    pub amount: u64,
-    pub new_total_balance: u64,
-    pub new_available_balance: u64,
-    pub timestamp: i64,
-}
-
-#[event]
-pub struct CollateralLocked {
-    pub user: Pubkey,
-    pub vault: Pubkey,
-    pub amount: u64,
-    pub new_available_balance: u64,
-    pub new_locked_balance: u64,
-    pub timestamp: i64,
-}
-
-#[event]
-pub struct CollateralUnlocked {
-    pub user: Pubkey,
-    pub vault: Pubkey,
-    pub amount: u64,
-    pub new_available_balance: u64,
-    pub new_locked_balance: u64,
-    pub timestamp: i64,
-}
-
-#[event]
-pub struct CollateralTransferred {
-    pub source_user: Pubkey,
-    pub destination_user: Pubkey,
-    pub source_vault: Pubkey,
-    pub destination_vault: Pubkey,
-    pub amount: u64,
-    pub timestamp: i64,
-}
\ No newline at end of file
+    pub token_program: Interface<'info, TokenInterface>,
+}
@@ -1,23 +1,44 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Token, TokenAccount, Mint};
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{transfer_fee, ExtensionType, StateWithExtensions},
+};
 use solana_program_test::*;
 use solana_sdk::{
+    instruction::InstructionError,
     pubkey::Pubkey,
     signature::{Keypair, Signer},
-    transaction::Transaction,
+    transaction::{Transaction, TransactionError},
     system_instruction,
 };
 use std::str::FromStr;
 
 use collateral_vault::{
     self,
-    accounts::{InitializeVault, Deposit, Withdraw, LockCollateral, UnlockCollateral, TransferCollateral},
+    accounts::{InitializeVault, Deposit, Withdraw, LockCollateral, UnlockCollateral, TransferCollateral,
+               InitializeConfig, UpdateConfig, WithdrawWithAdminApproval, RecoverForeignTokens, RotateAuthority,
+               RevokeTokenDelegate, SweepDormantVault, ReclaimDormantFunds, CloseVault,
+               AddAuthorizedAuthority, RemoveAuthorizedAuthority, Liquidate,
+               SettleLoss, SetWithdrawalCooldown, SetWithdrawalCooldownWithAdminApproval,
+               RequestWithdrawal, CancelWithdrawal, OpenLock, ReleaseLock, TransferFromLock,
+               ExpireLock, InitializeTreasury, WithdrawTreasury, InitializeInsuranceFund,
+               FundInsurance, DrawInsurance, TransferAvailableCollateral, InitiateTransfer,
+               AcceptTransfer, CancelTransfer, DepositFor, SyncBalance, SetCollateralMint,
+               InitializeAssetConfig, UpdateAssetConfig, DepositSol, WithdrawSol, SetVaultFreeze,
+               AnnounceForceUnlock, ExecuteForceUnlock, CancelForceUnlock, DepositFromAssociated,
+               WithdrawToAssociated, MigrateVaultLayout, RepairVaultBump},
     instruction,
-    Vault, VaultError,
+    Vault, VaultError, ProgramConfig, LockRecord, ForceUnlock,
 };
 
 const USDT_MINT: &str = "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB";
 
+const FOREIGN_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+// 1M USDT with 6 decimals
+const DEFAULT_MAX_TRANSACTION_AMOUNT: u64 = 1_000_000_000_000;
+
 #[tokio::test]
 async fn test_initialize_vault() {
     let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
@@ -26,6 +47,7 @@ async fn test_initialize_vault() {
     let user = Keypair::new();
     let authority = Keypair::new();
     let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
     
     // Create user account
     let create_user_ix = system_instruction::create_account(
@@ -49,12 +71,14 @@ async fn test_initialize_vault() {
     
     let init_ix = instruction::initialize_vault(
         collateral_vault::id(),
-        vault_bump,
+        0,
         InitializeVault {
             vault: vault_pda,
             vault_token_account: token_pda,
             user: user.pubkey(),
+            payer: payer.pubkey(),
             authority: authority.pubkey(),
+            config: config_pda(),
             usdt_mint,
             token_program: token::id(),
             system_program: system_program::id(),
@@ -77,10 +101,395 @@ async fn test_initialize_vault() {
     
     assert_eq!(vault.user, user.pubkey());
     assert_eq!(vault.token_account, token_pda);
+    assert_eq!(vault.bump, vault_bump, "the vault stores its canonical bump");
     assert_eq!(vault.total_balance, 0);
     assert_eq!(vault.locked_balance, 0);
     assert_eq!(vault.available_balance, 0);
     assert!(vault.is_active);
+    assert!(vault.created_at > 0);
+    assert_eq!(vault.counters_since, vault.created_at);
+    assert_eq!(vault.deposit_count, 0);
+    assert_eq!(vault.total_deposited, 0);
+}
+
+#[tokio::test]
+async fn test_sponsor_pays_vault_rent_for_unfunded_user() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, _recent_blockhash) = program.start().await;
+    
+    // The user holds no lamports at all; the sponsor pays fees and rent
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = Pubkey::find_program_address(
+        &[b"vault", user.pubkey().as_ref()],
+        &collateral_vault::id(),
+    );
+    
+    let init_ix = instruction::initialize_vault(
+        collateral_vault::id(),
+        0,
+        InitializeVault {
+            vault: vault_pda,
+            vault_token_account: get_vault_token_account(&mut banks_client, vault_pda).await,
+            user: user.pubkey(),
+            payer: payer.pubkey(),
+            authority: authority.pubkey(),
+            config: config_pda(),
+            usdt_mint,
+            token_program: token::id(),
+            system_program: system_program::id(),
+            rent: rent::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[init_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.user, user.pubkey());
+    assert!(banks_client.get_account(user.pubkey()).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_vault_only_opens_for_configured_collateral_mint() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, _recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    let foreign_mint = Pubkey::from_str(FOREIGN_MINT).unwrap();
+    fund_account(&mut banks_client, &payer, &user).await;
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    
+    let (vault_pda, _) = Pubkey::find_program_address(
+        &[b"vault", user.pubkey().as_ref()],
+        &collateral_vault::id(),
+    );
+    let vault_token_account = get_vault_token_account(&mut banks_client, vault_pda).await;
+    let init_ix = |mint: Pubkey| instruction::initialize_vault(
+        collateral_vault::id(),
+        0,
+        InitializeVault {
+            vault: vault_pda,
+            vault_token_account,
+            user: user.pubkey(),
+            payer: payer.pubkey(),
+            authority: authority.pubkey(),
+            config: config_pda(),
+            usdt_mint: mint,
+            token_program: token::id(),
+            system_program: system_program::id(),
+            rent: rent::id(),
+        },
+    );
+    
+    // A vault of any other mint is refused
+    let tx = Transaction::new_signed_with_payer(
+        &[init_ix(foreign_mint)],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    // Nor can the admin swap the mint once it is set
+    let set_mint_ix = instruction::set_collateral_mint(
+        collateral_vault::id(),
+        SetCollateralMint {
+            config: config_pda(),
+            admin: payer.pubkey(),
+            collateral_mint: foreign_mint,
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[set_mint_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    let config_account = banks_client.get_account(config_pda()).await.unwrap().unwrap();
+    let config = ProgramConfig::try_deserialize(&mut config_account.data.as_ref()).unwrap();
+    let mint_account = banks_client.get_account(usdt_mint).await.unwrap().unwrap();
+    let mint = Mint::try_deserialize(&mut mint_account.data.as_ref()).unwrap();
+    assert_eq!(config.collateral_mint, usdt_mint);
+    assert_eq!(config.collateral_decimals, mint.decimals);
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[init_ix(usdt_mint)],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let vault_token = banks_client.get_account(vault_token_account).await.unwrap().unwrap();
+    let vault_token = TokenAccount::try_deserialize(&mut vault_token.data.as_ref()).unwrap();
+    assert_eq!(vault_token.mint, usdt_mint);
+}
+
+#[tokio::test]
+async fn test_user_holds_isolated_sub_account_vaults() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, _recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (main_vault, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    
+    // Sub-account 1 lives at its own address next to the default vault
+    let (sub_vault, _) = Pubkey::find_program_address(
+        &[b"vault", user.pubkey().as_ref(), &1u16.to_le_bytes()],
+        &collateral_vault::id(),
+    );
+    assert_ne!(sub_vault, main_vault);
+    let init_ix = instruction::initialize_vault(
+        collateral_vault::id(),
+        1,
+        InitializeVault {
+            vault: sub_vault,
+            vault_token_account: vault_token_pda(sub_vault),
+            user: user.pubkey(),
+            payer: payer.pubkey(),
+            authority: authority.pubkey(),
+            config: config_pda(),
+            usdt_mint,
+            token_program: token::id(),
+            system_program: system_program::id(),
+            rent: rent::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[init_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    deposit_to_vault(&mut banks_client, &payer, &user, main_vault, usdt_mint, 1_000_000).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, sub_vault, usdt_mint, 250_000).await;
+    
+    // Each vault keeps its own token account and balances
+    let main_account = banks_client.get_account(main_vault).await.unwrap().unwrap();
+    let main = Vault::try_deserialize(&mut main_account.data.as_ref()).unwrap();
+    let sub_account = banks_client.get_account(sub_vault).await.unwrap().unwrap();
+    let sub = Vault::try_deserialize(&mut sub_account.data.as_ref()).unwrap();
+    assert_eq!(main.sub_account_id, 0);
+    assert_eq!(sub.sub_account_id, 1);
+    assert_eq!(sub.user, user.pubkey());
+    assert_ne!(sub.token_account, main.token_account);
+    assert_eq!(main.total_balance, 1_000_000);
+    assert_eq!(sub.total_balance, 250_000);
+}
+
+#[tokio::test]
+async fn test_sub_account_vault_migrates_to_the_current_layout() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let mut context = program.start_with_context().await;
+    let payer = context.payer.insecure_clone();
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    setup_config(&mut context.banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    
+    let (sub_vault, _) = Pubkey::find_program_address(
+        &[b"vault", user.pubkey().as_ref(), &2u16.to_le_bytes()],
+        &collateral_vault::id(),
+    );
+    let init_ix = instruction::initialize_vault(
+        collateral_vault::id(),
+        2,
+        InitializeVault {
+            vault: sub_vault,
+            vault_token_account: vault_token_pda(sub_vault),
+            user: user.pubkey(),
+            payer: payer.pubkey(),
+            authority: authority.pubkey(),
+            config: config_pda(),
+            usdt_mint,
+            token_program: token::id(),
+            system_program: system_program::id(),
+            rent: rent::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[init_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    deposit_to_vault(&mut context.banks_client, &payer, &user, sub_vault, usdt_mint, 250_000).await;
+    
+    // Cut the account back to its size before the freeze fields and `processing`
+    let mut vault_account = context.banks_client.get_account(sub_vault).await.unwrap().unwrap();
+    vault_account.data.truncate(Vault::SIZE - 10);
+    context.set_account(&sub_vault, &vault_account.into());
+    
+    let migrate_ix = |sub_account_id: u16| instruction::migrate_vault_layout(
+        collateral_vault::id(),
+        sub_account_id,
+        MigrateVaultLayout {
+            vault: sub_vault,
+            user: user.pubkey(),
+            payer: payer.pubkey(),
+            system_program: system_program::id(),
+        },
+    );
+    
+    // The vault is only found at the seeds of its own sub-account
+    for (sub_account_id, succeeds) in [(0, false), (2, true)] {
+        let tx = Transaction::new_signed_with_payer(
+            &[migrate_ix(sub_account_id)],
+            Some(&payer.pubkey()),
+            &[&payer],
+            context.banks_client.get_latest_blockhash().await.unwrap(),
+        );
+        assert_eq!(context.banks_client.process_transaction(tx).await.is_ok(), succeeds);
+    }
+    
+    let vault_account = context.banks_client.get_account(sub_vault).await.unwrap().unwrap();
+    assert_eq!(vault_account.data.len(), Vault::SIZE);
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.sub_account_id, 2);
+    assert_eq!(vault.total_balance, 250_000);
+    assert!(!vault.frozen);
+    assert!(!vault.processing);
+}
+
+#[tokio::test]
+async fn test_repair_vault_bump_restores_the_canonical_bump() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let mut context = program.start_with_context().await;
+    let payer = context.payer.insecure_clone();
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut context.banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, canonical_bump) = setup_vault(&mut context.banks_client, &payer, &user, &authority, usdt_mint).await;
+    
+    let settlement = Keypair::new();
+    let add_ix = instruction::add_authorized_authority(
+        collateral_vault::id(),
+        AddAuthorizedAuthority {
+            vault: vault_pda,
+            authority: authority.pubkey(),
+            new_authority: settlement.pubkey(),
+            payer: payer.pubkey(),
+            system_program: solana_sdk::system_program::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[add_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &authority, &settlement],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    
+    // As a vault created with a caller-chosen bump would hold
+    let mut vault_account = context.banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let mut vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    vault.bump = canonical_bump.wrapping_sub(1);
+    let mut data = Vec::new();
+    vault.try_serialize(&mut data).unwrap();
+    vault_account.data[..data.len()].copy_from_slice(&data);
+    context.set_account(&vault_pda, &vault_account.into());
+    
+    let repair_ix = |caller: Pubkey| instruction::repair_vault_bump(
+        collateral_vault::id(),
+        RepairVaultBump {
+            vault: vault_pda,
+            caller,
+        },
+    );
+    
+    // Neither a stranger, the owner nor a key authorized alongside the authority may repair it
+    for caller in [Keypair::new(), user.insecure_clone(), settlement] {
+        let tx = Transaction::new_signed_with_payer(
+            &[repair_ix(caller.pubkey())],
+            Some(&payer.pubkey()),
+            &[&payer, &caller],
+            context.banks_client.get_latest_blockhash().await.unwrap(),
+        );
+        assert_eq!(
+            context.banks_client.process_transaction(tx).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::Custom(VaultError::UnauthorizedCaller.into())),
+        );
+    }
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[repair_ix(authority.pubkey())],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    
+    let vault_account = context.banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let repaired = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(repaired.bump, canonical_bump);
+}
+
+#[tokio::test]
+async fn test_config_refuses_mint_outside_policy() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, _recent_blockhash) = program.start().await;
+    
+    // 1% transfer fee
+    let fee_mint = create_transfer_fee_mint(&mut banks_client, &payer, 100, 5000000).await;
+    let init_ix = |max_mint_transfer_fee_bps: u16| instruction::initialize_config(
+        collateral_vault::id(),
+        DEFAULT_MAX_TRANSACTION_AMOUNT,
+        false,
+        max_mint_transfer_fee_bps,
+        InitializeConfig {
+            config: config_pda(),
+            admin: payer.pubkey(),
+            collateral_mint: fee_mint,
+            system_program: system_program::id(),
+        },
+    );
+    
+    // A fee above the cap is refused, and so is a cap above 100%
+    for max_mint_transfer_fee_bps in [50, 10_001] {
+        let tx = Transaction::new_signed_with_payer(
+            &[init_ix(max_mint_transfer_fee_bps)],
+            Some(&payer.pubkey()),
+            &[&payer],
+            banks_client.get_latest_blockhash().await.unwrap(),
+        );
+        assert!(banks_client.process_transaction(tx).await.is_err());
+    }
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[init_ix(100)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let config_account = banks_client.get_account(config_pda()).await.unwrap().unwrap();
+    let config = ProgramConfig::try_deserialize(&mut config_account.data.as_ref()).unwrap();
+    assert_eq!(config.collateral_mint, fee_mint);
+    assert!(!config.allow_mint_freeze_authority);
+    assert_eq!(config.max_mint_transfer_fee_bps, 100);
 }
 
 #[tokio::test]
@@ -92,7 +501,8 @@ async fn test_deposit_withdraw_flow() {
     let authority = Keypair::new();
     let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
     
-    // Setup vault and token accounts
+    // Setup config, vault and token accounts
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
     let (vault_pda, vault_bump) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
     
     // Create user USDT account
@@ -101,221 +511,3954 @@ async fn test_deposit_withdraw_flow() {
     // Mint USDT to user
     mint_tokens(&mut banks_client, &payer, usdt_mint, user_usdt_account, 1000000000).await;
     
-    // Deposit 1000 USDT
-    let deposit_amount = 1000000000u64; // 1000 USDT with 6 decimals
+    // Deposit 1000 USDT
+    let deposit_amount = 1000000000u64; // 1000 USDT with 6 decimals
+    
+    let deposit_ix = instruction::deposit(
+        collateral_vault::id(),
+        deposit_amount,
+        Deposit {
+            vault: vault_pda,
+            vault_token_account: get_vault_token_account(&mut banks_client, vault_pda).await,
+            user_token_account: user_usdt_account,
+            user: user.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+        },
+    );
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[deposit_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        recent_blockhash,
+    );
+    
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    // Verify deposit
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    
+    assert_eq!(vault.total_balance, deposit_amount);
+    assert_eq!(vault.available_balance, deposit_amount);
+    assert_eq!(vault.locked_balance, 0);
+    assert_eq!(vault.deposit_count, 1);
+    assert_eq!(vault.total_deposited, deposit_amount);
+    
+    // Withdraw 500 USDT
+    let withdraw_amount = 500000000u64;
+    
+    let withdraw_ix = instruction::withdraw(
+        collateral_vault::id(),
+        withdraw_amount,
+        Withdraw {
+            vault: vault_pda,
+            vault_token_account: get_vault_token_account(&mut banks_client, vault_pda).await,
+            user_token_account: user_usdt_account,
+            treasury_token_account: Some(treasury_pda(usdt_mint)),
+            user: user.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+            memo_program: None,
+        },
+    );
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[withdraw_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        recent_blockhash,
+    );
+    
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    // Verify withdrawal
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    
+    assert_eq!(vault.total_balance, deposit_amount - withdraw_amount);
+    assert_eq!(vault.available_balance, deposit_amount - withdraw_amount);
+    assert_eq!(vault.locked_balance, 0);
+    assert_eq!(vault.withdraw_count, 1);
+    assert_eq!(vault.total_withdrawn, withdraw_amount);
+    assert_eq!(vault.deposit_count, 1);
+}
+
+#[tokio::test]
+async fn test_withdraw_forwards_its_memo() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, _recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    
+    let user_token_account = create_token_account(&mut banks_client, &payer, usdt_mint, user.pubkey()).await;
+    let withdraw = |memo: String, memo_program: Option<Pubkey>| instruction::withdraw_with_memo(
+        collateral_vault::id(),
+        100000000,
+        memo,
+        Withdraw {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            user_token_account,
+            treasury_token_account: Some(treasury_pda(usdt_mint)),
+            user: user.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+            memo_program,
+        },
+    );
+    
+    // Refused: a memo without the memo program, an empty one and an oversized one
+    let refused = [
+        withdraw("deposit-ref-42".to_string(), None),
+        withdraw(String::new(), Some(anchor_spl::memo::ID)),
+        withdraw("x".repeat(collateral_vault::MAX_MEMO_LEN + 1), Some(anchor_spl::memo::ID)),
+    ];
+    for ix in refused {
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&payer.pubkey()),
+            &[&payer, &user],
+            banks_client.get_latest_blockhash().await.unwrap(),
+        );
+        assert!(banks_client.process_transaction(tx).await.is_err());
+    }
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[withdraw("deposit-ref-42".to_string(), Some(anchor_spl::memo::ID))],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    let result = banks_client.process_transaction_with_metadata(tx).await.unwrap();
+    assert!(result.result.is_ok());
+    let logs = result.metadata.unwrap().log_messages;
+    assert!(logs.iter().any(|log| log.contains("deposit-ref-42")));
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.available_balance, 900000000);
+}
+
+#[tokio::test]
+async fn test_withdraw_all_takes_the_available_balance_at_execution() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, _recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    lock_collateral(&mut banks_client, &payer, &authority, vault_pda, 300000000).await;
+    
+    let user_token_account = create_token_account(&mut banks_client, &payer, usdt_mint, user.pubkey()).await;
+    let withdraw_all_ix = instruction::withdraw_all(
+        collateral_vault::id(),
+        Withdraw {
+            vault: vault_pda,
+            vault_token_account: get_vault_token_account(&mut banks_client, vault_pda).await,
+            user_token_account,
+            treasury_token_account: Some(treasury_pda(usdt_mint)),
+            user: user.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+            memo_program: None,
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[withdraw_all_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    // Locked collateral stays behind
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.available_balance, 0);
+    assert_eq!(vault.locked_balance, 300000000);
+    assert_eq!(vault.total_withdrawn, 700000000);
+    
+    let user_account = banks_client.get_account(user_token_account).await.unwrap().unwrap();
+    let user_account = TokenAccount::try_deserialize(&mut user_account.data.as_ref()).unwrap();
+    assert_eq!(user_account.amount, 700000000);
+}
+
+#[tokio::test]
+async fn test_withdraw_creates_and_deposit_reuses_the_associated_token_account() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, _recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    fund_account(&mut banks_client, &payer, &user).await;
+    
+    let user_ata = anchor_spl::associated_token::get_associated_token_address(&user.pubkey(), &usdt_mint);
+    assert!(banks_client.get_account(user_ata).await.unwrap().is_none());
+    
+    // The missing account is created on the way out, and an existing one reused
+    for _ in 0..2 {
+        let withdraw_ix = instruction::withdraw_to_associated(
+            collateral_vault::id(),
+            300000000,
+            WithdrawToAssociated {
+                vault: vault_pda,
+                vault_token_account: vault_token_pda(vault_pda),
+                user_token_account: user_ata,
+                treasury_token_account: Some(treasury_pda(usdt_mint)),
+                user: user.pubkey(),
+                config: config_pda(),
+                mint: usdt_mint,
+                token_program: token::id(),
+                associated_token_program: anchor_spl::associated_token::ID,
+                system_program: system_program::id(),
+            },
+        );
+        let tx = Transaction::new_signed_with_payer(
+            &[withdraw_ix],
+            Some(&payer.pubkey()),
+            &[&payer, &user],
+            banks_client.get_latest_blockhash().await.unwrap(),
+        );
+        banks_client.process_transaction(tx).await.unwrap();
+    }
+    
+    let deposit_ix = instruction::deposit_from_associated(
+        collateral_vault::id(),
+        100000000,
+        DepositFromAssociated {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            user_token_account: user_ata,
+            user: user.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[deposit_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let user_account = banks_client.get_account(user_ata).await.unwrap().unwrap();
+    let user_account = TokenAccount::try_deserialize(&mut user_account.data.as_ref()).unwrap();
+    assert_eq!(user_account.amount, 500000000);
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.available_balance, 500000000);
+}
+
+#[tokio::test]
+async fn test_native_sol_wraps_into_and_unwraps_out_of_the_vault() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, _recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let sol_mint = token::spl_token::native_mint::ID;
+    fund_account(&mut banks_client, &payer, &user).await;
+    setup_config_for_mint(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT, sol_mint, 0).await;
+    let treasury_ix = instruction::initialize_treasury(
+        collateral_vault::id(),
+        InitializeTreasury {
+            treasury_token_account: treasury_pda(sol_mint),
+            config: config_pda(),
+            admin: payer.pubkey(),
+            mint: sol_mint,
+            token_program: token::id(),
+            system_program: system_program::id(),
+            rent: rent::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[treasury_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, sol_mint).await;
+    let wrapped_sol_account = Pubkey::find_program_address(&[b"wrapped_sol", vault_pda.as_ref()], &collateral_vault::id()).0;
+    
+    // The user pays in lamports and holds no wSOL account afterwards
+    let lamports_before = banks_client.get_balance(user.pubkey()).await.unwrap();
+    let deposit_ix = instruction::deposit_sol(
+        collateral_vault::id(),
+        400_000_000,
+        DepositSol {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            wrapped_sol_account,
+            user: user.pubkey(),
+            config: config_pda(),
+            mint: sol_mint,
+            token_program: token::id(),
+            system_program: system_program::id(),
+            rent: rent::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[deposit_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    assert_eq!(banks_client.get_balance(user.pubkey()).await.unwrap(), lamports_before - 400_000_000);
+    assert!(banks_client.get_account(wrapped_sol_account).await.unwrap().is_none());
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.total_balance, 400_000_000);
+    
+    // And is paid back in lamports, the temporary account's rent included
+    let withdraw_ix = instruction::withdraw_sol(
+        collateral_vault::id(),
+        150_000_000,
+        WithdrawSol {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            wrapped_sol_account,
+            treasury_token_account: Some(treasury_pda(sol_mint)),
+            user: user.pubkey(),
+            config: config_pda(),
+            mint: sol_mint,
+            token_program: token::id(),
+            system_program: system_program::id(),
+            rent: rent::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[withdraw_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    assert_eq!(banks_client.get_balance(user.pubkey()).await.unwrap(), lamports_before - 250_000_000);
+    assert!(banks_client.get_account(wrapped_sol_account).await.unwrap().is_none());
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.total_balance, 250_000_000);
+    assert_eq!(vault.available_balance, 250_000_000);
+}
+
+#[tokio::test]
+async fn test_deposit_for_credits_another_users_vault() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, _recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let sponsor = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    
+    let sponsor_token_account = create_token_account(&mut banks_client, &payer, usdt_mint, sponsor.pubkey()).await;
+    mint_tokens(&mut banks_client, &payer, usdt_mint, sponsor_token_account, 250000000).await;
+    
+    // Only the sponsor signs; the vault owner is not involved
+    let deposit_for_ix = instruction::deposit_for(
+        collateral_vault::id(),
+        250000000,
+        DepositFor {
+            vault: vault_pda,
+            vault_token_account: get_vault_token_account(&mut banks_client, vault_pda).await,
+            depositor_token_account: sponsor_token_account,
+            depositor: sponsor.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[deposit_for_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &sponsor],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.user, user.pubkey());
+    assert_eq!(vault.available_balance, 250000000);
+    assert_eq!(vault.deposit_count, 1);
+    assert_eq!(vault.total_deposited, 250000000);
+}
+
+#[tokio::test]
+async fn test_sync_balance_credits_tokens_sent_directly() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, _recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    
+    // Tokens arriving outside a deposit
+    let vault_token_account = get_vault_token_account(&mut banks_client, vault_pda).await;
+    mint_tokens(&mut banks_client, &payer, usdt_mint, vault_token_account, 5000000).await;
+    
+    let stranger = Keypair::new();
+    let sync_ix = || instruction::sync_balance(
+        collateral_vault::id(),
+        SyncBalance {
+            vault: vault_pda,
+            vault_token_account,
+            caller: stranger.pubkey(),
+            config: config_pda(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[sync_ix()],
+        Some(&payer.pubkey()),
+        &[&payer, &stranger],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.total_balance, 1005000000);
+    assert_eq!(vault.available_balance, 1005000000);
+    assert_eq!(vault.total_deposited, 1000000000);
+    
+    // Nothing left to sync
+    let tx = Transaction::new_signed_with_payer(
+        &[sync_ix()],
+        Some(&payer.pubkey()),
+        &[&payer, &stranger],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+}
+
+#[tokio::test]
+async fn test_lock_unlock_collateral() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    // Setup vault with 1000 USDT
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    
+    // Lock 600 USDT for trading
+    let lock_amount = 600000000u64;
+    
+    let lock_ix = instruction::lock_collateral(
+        collateral_vault::id(),
+        lock_amount,
+        LockCollateral {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            asset_config: asset_config_pda(usdt_mint),
+            config: config_pda(),
+            authority: authority.pubkey(),
+        },
+    );
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[lock_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        recent_blockhash,
+    );
+    
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    // Verify lock
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    
+    assert_eq!(vault.total_balance, 1000000000);
+    assert_eq!(vault.available_balance, 400000000);
+    assert_eq!(vault.locked_balance, 600000000);
+    
+    // Unlock 200 USDT
+    let unlock_amount = 200000000u64;
+    
+    let unlock_ix = instruction::unlock_collateral(
+        collateral_vault::id(),
+        unlock_amount,
+        UnlockCollateral {
+            vault: vault_pda,
+            config: config_pda(),
+            authority: authority.pubkey(),
+        },
+    );
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[unlock_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        recent_blockhash,
+    );
+    
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    // Verify unlock
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    
+    assert_eq!(vault.total_balance, 1000000000);
+    assert_eq!(vault.available_balance, 600000000);
+    assert_eq!(vault.locked_balance, 400000000);
+}
+
+#[tokio::test]
+async fn test_security_withdraw_locked_funds() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    // Setup vault with 1000 USDT and lock 800
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    lock_collateral(&mut banks_client, &payer, &authority, vault_pda, 800000000).await;
+    
+    // Attempt to withdraw 500 USDT (should fail - only 200 available)
+    let withdraw_amount = 500000000u64;
+    
+    let withdraw_ix = instruction::withdraw(
+        collateral_vault::id(),
+        withdraw_amount,
+        Withdraw {
+            vault: vault_pda,
+            vault_token_account: get_vault_token_account(&mut banks_client, vault_pda).await,
+            user_token_account: create_token_account(&mut banks_client, &payer, usdt_mint, user.pubkey()).await,
+            treasury_token_account: Some(treasury_pda(usdt_mint)),
+            user: user.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+            memo_program: None,
+        },
+    );
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[withdraw_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        recent_blockhash,
+    );
+    
+    let result = banks_client.process_transaction(tx).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_security_unauthorized_cpi() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let unauthorized_caller = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    // Setup vault
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    
+    // Attempt to lock collateral with unauthorized caller
+    let lock_amount = 500000000u64;
+    
+    let lock_ix = instruction::lock_collateral(
+        collateral_vault::id(),
+        lock_amount,
+        LockCollateral {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            asset_config: asset_config_pda(usdt_mint),
+            config: config_pda(),
+            authority: unauthorized_caller.pubkey(),
+        },
+    );
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[lock_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &unauthorized_caller],
+        recent_blockhash,
+    );
+    
+    let result = banks_client.process_transaction(tx).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_withdraw_over_limit_requires_admin() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let admin = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    // Cap single movements at 100 USDT
+    fund_account(&mut banks_client, &payer, &admin).await;
+    setup_config(&mut banks_client, &payer, &admin, 100000000).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    let user_usdt_account = create_token_account(&mut banks_client, &payer, usdt_mint, user.pubkey()).await;
+    
+    // 500 USDT through the normal path is rejected
+    let withdraw_ix = instruction::withdraw(
+        collateral_vault::id(),
+        500000000,
+        Withdraw {
+            vault: vault_pda,
+            vault_token_account: get_vault_token_account(&mut banks_client, vault_pda).await,
+            user_token_account: user_usdt_account,
+            treasury_token_account: Some(treasury_pda(usdt_mint)),
+            user: user.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+            memo_program: None,
+        },
+    );
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[withdraw_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        recent_blockhash,
+    );
+    
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    // Co-signed by someone other than the admin is rejected
+    let impostor = Keypair::new();
+    let override_ix = |admin: Pubkey| instruction::withdraw_with_admin_approval(
+        collateral_vault::id(),
+        500000000,
+        WithdrawWithAdminApproval {
+            vault: vault_pda,
+            vault_token_account: Pubkey::find_program_address(&[b"token", vault_pda.as_ref()], &collateral_vault::id()).0,
+            user_token_account: user_usdt_account,
+            treasury_token_account: Some(treasury_pda(usdt_mint)),
+            user: user.pubkey(),
+            config: config_pda(),
+            admin,
+            mint: usdt_mint,
+            token_program: token::id(),
+        },
+    );
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[override_ix(impostor.pubkey())],
+        Some(&payer.pubkey()),
+        &[&payer, &user, &impostor],
+        recent_blockhash,
+    );
+    
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    // Admin co-signature lets it through
+    let tx = Transaction::new_signed_with_payer(
+        &[override_ix(admin.pubkey())],
+        Some(&payer.pubkey()),
+        &[&payer, &user, &admin],
+        recent_blockhash,
+    );
+    
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    
+    assert_eq!(vault.total_balance, 500000000);
+    assert_eq!(vault.available_balance, 500000000);
+}
+
+#[tokio::test]
+async fn test_only_admin_updates_max_transaction_amount() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, recent_blockhash) = program.start().await;
+    
+    let other = Keypair::new();
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    
+    let update_ix = |admin: Pubkey| instruction::update_max_transaction_amount(
+        collateral_vault::id(),
+        5000000,
+        UpdateConfig {
+            config: config_pda(),
+            admin,
+        },
+    );
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[update_ix(other.pubkey())],
+        Some(&payer.pubkey()),
+        &[&payer, &other],
+        recent_blockhash,
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[update_ix(payer.pubkey())],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let config_account = banks_client.get_account(config_pda()).await.unwrap().unwrap();
+    let config = ProgramConfig::try_deserialize(&mut config_account.data.as_ref()).unwrap();
+    
+    assert_eq!(config.admin, payer.pubkey());
+    assert_eq!(config.max_transaction_amount, 5000000);
+}
+
+#[tokio::test]
+async fn test_lock_ratio_caps_locked_share() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    
+    let update_ix = |max_lock_ratio_bps: u16| instruction::update_max_lock_ratio(
+        collateral_vault::id(),
+        max_lock_ratio_bps,
+        UpdateConfig {
+            config: config_pda(),
+            admin: payer.pubkey(),
+        },
+    );
+    
+    // Above 100% is meaningless
+    let tx = Transaction::new_signed_with_payer(
+        &[update_ix(10001)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    // At most half of the vault may be locked
+    let tx = Transaction::new_signed_with_payer(
+        &[update_ix(5000)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    lock_collateral(&mut banks_client, &payer, &authority, vault_pda, 400000000).await;
+    
+    let lock_ix = instruction::lock_collateral(
+        collateral_vault::id(),
+        200000000,
+        LockCollateral {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            asset_config: asset_config_pda(usdt_mint),
+            config: config_pda(),
+            authority: authority.pubkey(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[lock_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    // Exactly at the cap is allowed
+    lock_collateral(&mut banks_client, &payer, &authority, vault_pda, 100000000).await;
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.locked_balance, 500000000);
+    assert_eq!(vault.available_balance, 500000000);
+}
+
+#[tokio::test]
+async fn test_min_free_balance_keeps_collateral_unlocked() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, _recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    
+    // Every lock must leave 100 USDT free
+    let update_ix = instruction::update_min_free_balance(
+        collateral_vault::id(),
+        100000000,
+        UpdateConfig {
+            config: config_pda(),
+            admin: payer.pubkey(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[update_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let lock_ix = instruction::lock_collateral(
+        collateral_vault::id(),
+        1000000000,
+        LockCollateral {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            asset_config: asset_config_pda(usdt_mint),
+            config: config_pda(),
+            authority: authority.pubkey(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[lock_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    // Exactly at the floor is allowed
+    lock_collateral(&mut banks_client, &payer, &authority, vault_pda, 900000000).await;
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.locked_balance, 900000000);
+    assert_eq!(vault.available_balance, 100000000);
+}
+
+#[tokio::test]
+async fn test_minimum_amounts_refuse_dust() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, _recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    
+    // Nothing under 1 USDT goes in or out
+    let update_ix = instruction::update_minimum_amounts(
+        collateral_vault::id(),
+        1000000,
+        1000000,
+        UpdateConfig {
+            config: config_pda(),
+            admin: payer.pubkey(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[update_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 10000000).await;
+    
+    let user_token_account = create_token_account(&mut banks_client, &payer, usdt_mint, user.pubkey()).await;
+    mint_tokens(&mut banks_client, &payer, usdt_mint, user_token_account, 999999).await;
+    let deposit_ix = instruction::deposit(
+        collateral_vault::id(),
+        999999,
+        Deposit {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            user_token_account,
+            user: user.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[deposit_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    let withdraw = |amount: u64| instruction::withdraw(
+        collateral_vault::id(),
+        amount,
+        Withdraw {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            user_token_account,
+            treasury_token_account: Some(treasury_pda(usdt_mint)),
+            user: user.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+            memo_program: None,
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[withdraw(500000)],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[withdraw(9500000)],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    // The 0.5 USDT left behind can still be taken out in one go
+    let tx = Transaction::new_signed_with_payer(
+        &[withdraw(500000)],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.total_balance, 0);
+    assert_eq!(vault.total_withdrawn, 10000000);
+}
+
+#[tokio::test]
+async fn test_haircut_limits_locks_to_collateral_value() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, _recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    
+    let update_ix = |haircut_bps: u16| instruction::update_haircut(
+        collateral_vault::id(),
+        haircut_bps,
+        UpdateAssetConfig {
+            asset_config: asset_config_pda(usdt_mint),
+            config: config_pda(),
+            admin: payer.pubkey(),
+        },
+    );
+    
+    // Above 100% is meaningless
+    let tx = Transaction::new_signed_with_payer(
+        &[update_ix(10001)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    // Count the balance at 90%
+    let tx = Transaction::new_signed_with_payer(
+        &[update_ix(1000)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let lock_ix = instruction::lock_collateral(
+        collateral_vault::id(),
+        950000000,
+        LockCollateral {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            asset_config: asset_config_pda(usdt_mint),
+            config: config_pda(),
+            authority: authority.pubkey(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[lock_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    // Exactly the collateral value is allowed
+    lock_collateral(&mut banks_client, &payer, &authority, vault_pda, 900000000).await;
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.locked_balance, 900000000);
+}
+
+#[tokio::test]
+async fn test_liquidation_pays_keeper_reward_out_of_seized_collateral() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    lock_collateral(&mut banks_client, &payer, &authority, vault_pda, 300000000).await;
+    
+    // 1% of whatever is seized goes to the keeper
+    let reward_ix = instruction::update_keeper_reward(
+        collateral_vault::id(),
+        100,
+        UpdateConfig {
+            config: config_pda(),
+            admin: payer.pubkey(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[reward_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let destination = create_token_account(&mut banks_client, &payer, usdt_mint, payer.pubkey()).await;
+    let keeper = create_token_account(&mut banks_client, &payer, usdt_mint, Keypair::new().pubkey()).await;
+    let liquidate_ix = instruction::liquidate(
+        collateral_vault::id(),
+        200000000,
+        Liquidate {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            destination_token_account: destination,
+            insurance_fund: None,
+            keeper_token_account: Some(keeper),
+            liquidator: authority.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[liquidate_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    // The reward comes out of the 200 seized, not on top of it
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.locked_balance, 100000000);
+    assert_eq!(vault.total_balance, 800000000);
+    
+    let destination_account = banks_client.get_account(destination).await.unwrap().unwrap();
+    let destination_account = TokenAccount::try_deserialize(&mut destination_account.data.as_ref()).unwrap();
+    assert_eq!(destination_account.amount, 198000000);
+    let keeper_account = banks_client.get_account(keeper).await.unwrap().unwrap();
+    let keeper_account = TokenAccount::try_deserialize(&mut keeper_account.data.as_ref()).unwrap();
+    assert_eq!(keeper_account.amount, 2000000);
+}
+
+#[tokio::test]
+async fn test_insurance_fund_never_pays_the_keeper_reward() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, _recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    let insurance_fund = insurance_fund_pda(usdt_mint);
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    lock_collateral(&mut banks_client, &payer, &authority, vault_pda, 100000000).await;
+    
+    // 10% keeper reward and a fund holding 500
+    let reward_ix = instruction::update_keeper_reward(
+        collateral_vault::id(),
+        1000,
+        UpdateConfig {
+            config: config_pda(),
+            admin: payer.pubkey(),
+        },
+    );
+    let init_ix = instruction::initialize_insurance_fund(
+        collateral_vault::id(),
+        InitializeInsuranceFund {
+            insurance_fund,
+            config: config_pda(),
+            admin: payer.pubkey(),
+            mint: usdt_mint,
+            token_program: token::id(),
+            system_program: system_program::id(),
+            rent: rent::id(),
+        },
+    );
+    let funder = Keypair::new();
+    let funder_token_account = create_token_account(&mut banks_client, &payer, usdt_mint, funder.pubkey()).await;
+    mint_tokens(&mut banks_client, &payer, usdt_mint, funder_token_account, 500000000).await;
+    let fund_ix = instruction::fund_insurance(
+        collateral_vault::id(),
+        500000000,
+        FundInsurance {
+            insurance_fund,
+            funder_token_account,
+            funder: funder.pubkey(),
+            mint: usdt_mint,
+            token_program: token::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[reward_ix, init_ix, fund_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &funder],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    // 250 liquidated against 100 locked: the fund covers the other 150
+    let destination = create_token_account(&mut banks_client, &payer, usdt_mint, payer.pubkey()).await;
+    let keeper = create_token_account(&mut banks_client, &payer, usdt_mint, Keypair::new().pubkey()).await;
+    let liquidate_ix = instruction::liquidate(
+        collateral_vault::id(),
+        250000000,
+        Liquidate {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            destination_token_account: destination,
+            insurance_fund: Some(insurance_fund),
+            keeper_token_account: Some(keeper),
+            liquidator: authority.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[liquidate_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    // The reward is 10% of the 100 taken from the vault, all paid by the vault
+    let keeper_account = banks_client.get_account(keeper).await.unwrap().unwrap();
+    let keeper_account = TokenAccount::try_deserialize(&mut keeper_account.data.as_ref()).unwrap();
+    assert_eq!(keeper_account.amount, 10000000);
+    
+    let destination_account = banks_client.get_account(destination).await.unwrap().unwrap();
+    let destination_account = TokenAccount::try_deserialize(&mut destination_account.data.as_ref()).unwrap();
+    assert_eq!(destination_account.amount, 240000000);
+    
+    let fund_account = banks_client.get_account(insurance_fund).await.unwrap().unwrap();
+    let fund_account = TokenAccount::try_deserialize(&mut fund_account.data.as_ref()).unwrap();
+    assert_eq!(fund_account.amount, 350000000);
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.locked_balance, 0);
+    assert_eq!(vault.available_balance, 900000000);
+}
+
+#[tokio::test]
+async fn test_liquidate_seizes_locked_collateral_with_penalty() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    lock_collateral(&mut banks_client, &payer, &authority, vault_pda, 300000000).await;
+    
+    // 5% on top of each liquidated amount
+    let penalty_ix = instruction::update_liquidation_penalty(
+        collateral_vault::id(),
+        500,
+        UpdateConfig {
+            config: config_pda(),
+            admin: payer.pubkey(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[penalty_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let vault_token_account = get_vault_token_account(&mut banks_client, vault_pda).await;
+    let destination = create_token_account(&mut banks_client, &payer, usdt_mint, payer.pubkey()).await;
+    let liquidate_ix = |liquidator: &Keypair, amount: u64| instruction::liquidate(
+        collateral_vault::id(),
+        amount,
+        Liquidate {
+            vault: vault_pda,
+            vault_token_account,
+            destination_token_account: destination,
+            insurance_fund: None,
+            keeper_token_account: None,
+            liquidator: liquidator.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+        },
+    );
+    
+    // Only a key the vault authorizes may liquidate
+    let stranger = Keypair::new();
+    let tx = Transaction::new_signed_with_payer(
+        &[liquidate_ix(&stranger, 100000000)],
+        Some(&payer.pubkey()),
+        &[&payer, &stranger],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[liquidate_ix(&authority, 200000000)],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    // 200 seized plus a 10 penalty, all from the locked balance
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.locked_balance, 90000000);
+    assert_eq!(vault.available_balance, 700000000);
+    assert_eq!(vault.total_balance, 790000000);
+    vault.validate_invariant().unwrap();
+    
+    let destination_account = banks_client.get_account(destination).await.unwrap().unwrap();
+    let destination_account = TokenAccount::try_deserialize(&mut destination_account.data.as_ref()).unwrap();
+    assert_eq!(destination_account.amount, 210000000);
+    
+    // The available balance is never seized
+    let tx = Transaction::new_signed_with_payer(
+        &[liquidate_ix(&authority, 100000000)],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    // The penalty is capped at what remains locked
+    let tx = Transaction::new_signed_with_payer(
+        &[liquidate_ix(&authority, 88000000)],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.locked_balance, 0);
+    assert_eq!(vault.total_balance, 700000000);
+}
+
+#[tokio::test]
+async fn test_settle_loss_pays_counterparty_from_locked_balance() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, _recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, 250000000).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    lock_collateral(&mut banks_client, &payer, &authority, vault_pda, 400000000).await;
+    
+    let vault_token_account = get_vault_token_account(&mut banks_client, vault_pda).await;
+    let counterparty = create_token_account(&mut banks_client, &payer, usdt_mint, Keypair::new().pubkey()).await;
+    let settle_ix = |authority: &Keypair, amount: u64| instruction::settle_loss(
+        collateral_vault::id(),
+        amount,
+        SettleLoss {
+            vault: vault_pda,
+            vault_token_account,
+            counterparty_token_account: counterparty,
+            insurance_fund: None,
+            authority: authority.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+        },
+    );
+    
+    // An unauthorized key, an amount over the cap and the vault's own token
+    // account are all refused
+    let stranger = Keypair::new();
+    let tx = Transaction::new_signed_with_payer(
+        &[settle_ix(&stranger, 100000000)],
+        Some(&payer.pubkey()),
+        &[&payer, &stranger],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[settle_ix(&authority, 300000000)],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    let mut self_settle_ix = settle_ix(&authority, 100000000);
+    self_settle_ix.accounts[2].pubkey = vault_token_account;
+    let tx = Transaction::new_signed_with_payer(
+        &[self_settle_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[settle_ix(&authority, 150000000)],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.locked_balance, 250000000);
+    assert_eq!(vault.available_balance, 600000000);
+    assert_eq!(vault.total_balance, 850000000);
+    vault.validate_invariant().unwrap();
+    
+    let counterparty_account = banks_client.get_account(counterparty).await.unwrap().unwrap();
+    let counterparty_account = TokenAccount::try_deserialize(&mut counterparty_account.data.as_ref()).unwrap();
+    assert_eq!(counterparty_account.amount, 150000000);
+}
+
+#[tokio::test]
+async fn test_withdrawal_cooldown_requires_request_then_execute() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, _recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    
+    let vault_token_account = get_vault_token_account(&mut banks_client, vault_pda).await;
+    let user_token_account = create_token_account(&mut banks_client, &payer, usdt_mint, user.pubkey()).await;
+    let withdraw_accounts = || Withdraw {
+        vault: vault_pda,
+        vault_token_account,
+        user_token_account,
+        treasury_token_account: Some(treasury_pda(usdt_mint)),
+        user: user.pubkey(),
+        config: config_pda(),
+        mint: usdt_mint,
+        token_program: token::id(),
+        memo_program: None,
+    };
+    let set_cooldown_ix = |cooldown: u32| instruction::set_withdrawal_cooldown(
+        collateral_vault::id(),
+        cooldown,
+        SetWithdrawalCooldown {
+            vault: vault_pda,
+            user: user.pubkey(),
+        },
+    );
+    let request_ix = |amount: u64| instruction::request_withdrawal(
+        collateral_vault::id(),
+        amount,
+        RequestWithdrawal {
+            vault: vault_pda,
+            user: user.pubkey(),
+            config: config_pda(),
+        },
+    );
+    let cancel_ix = |canceller: &Keypair| instruction::cancel_withdrawal(
+        collateral_vault::id(),
+        CancelWithdrawal {
+            vault: vault_pda,
+            canceller: canceller.pubkey(),
+            config: config_pda(),
+        },
+    );
+    let execute_ix = || instruction::execute_withdrawal(collateral_vault::id(), withdraw_accounts());
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[set_cooldown_ix(3600)],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    // Direct withdrawals are refused once a cooldown is set
+    let withdraw_ix = instruction::withdraw(collateral_vault::id(), 100000000, withdraw_accounts());
+    let tx = Transaction::new_signed_with_payer(
+        &[withdraw_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    // The owner alone cannot lower the cooldown
+    let tx = Transaction::new_signed_with_payer(
+        &[set_cooldown_ix(0)],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[request_ix(100000000)],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.pending_withdrawal, 100000000);
+    assert!(vault.withdrawal_unlocks_at >= vault.last_updated + 3600);
+    assert_eq!(vault.available_balance, 1000000000);
+    
+    // Still cooling down
+    let tx = Transaction::new_signed_with_payer(
+        &[execute_ix()],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    // Only the owner or the admin may cancel
+    let stranger = Keypair::new();
+    let tx = Transaction::new_signed_with_payer(
+        &[cancel_ix(&stranger)],
+        Some(&payer.pubkey()),
+        &[&payer, &stranger],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[cancel_ix(&payer)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.pending_withdrawal, 0);
+    
+    // With admin approval the cooldown comes off, and a request unlocks at once
+    let lower_ix = instruction::set_withdrawal_cooldown_with_admin_approval(
+        collateral_vault::id(),
+        0,
+        SetWithdrawalCooldownWithAdminApproval {
+            vault: vault_pda,
+            user: user.pubkey(),
+            config: config_pda(),
+            admin: payer.pubkey(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[lower_ix, request_ix(100000000), execute_ix()],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.available_balance, 900000000);
+    assert_eq!(vault.pending_withdrawal, 0);
+    assert_eq!(vault.withdraw_count, 1);
+}
+
+#[tokio::test]
+async fn test_lock_records_keep_locks_apart() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, _recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let counterparty = Keypair::new();
+    let authority = Keypair::new();
+    let perps = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    let (counterparty_vault, _) = setup_vault(&mut banks_client, &payer, &counterparty, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    
+    let add_ix = instruction::add_authorized_authority(
+        collateral_vault::id(),
+        AddAuthorizedAuthority {
+            vault: vault_pda,
+            authority: authority.pubkey(),
+            new_authority: perps.pubkey(),
+            payer: payer.pubkey(),
+            system_program: solana_sdk::system_program::id(),
+        },
+    );
+    let open_ix = |locker: &Keypair, lock_id: u64, amount: u64| instruction::open_lock(
+        collateral_vault::id(),
+        lock_id,
+        amount,
+        [lock_id as u8; 32],
+        0,
+        OpenLock {
+            vault: vault_pda,
+            lock_record: lock_pda(vault_pda, lock_id),
+            vault_token_account: vault_token_pda(vault_pda),
+            asset_config: asset_config_pda(usdt_mint),
+            config: config_pda(),
+            authority: locker.pubkey(),
+            payer: payer.pubkey(),
+            system_program: solana_sdk::system_program::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[add_ix, open_ix(&authority, 1, 300000000), open_ix(&perps, 2, 200000000)],
+        Some(&payer.pubkey()),
+        &[&payer, &authority, &perps],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.locked_balance, 500000000);
+    assert_eq!(vault.recorded_locked_balance, 500000000);
+    let record_account = banks_client.get_account(lock_pda(vault_pda, 2)).await.unwrap().unwrap();
+    let record = LockRecord::try_deserialize(&mut record_account.data.as_ref()).unwrap();
+    assert_eq!((record.authority, record.amount, record.purpose), (perps.pubkey(), 200000000, [2; 32]));
+    
+    // A lock id is taken once per vault
+    let tx = Transaction::new_signed_with_payer(
+        &[open_ix(&perps, 2, 100000000)],
+        Some(&payer.pubkey()),
+        &[&payer, &perps],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    // Recorded collateral cannot be unlocked without naming its record
+    let unlock_ix = instruction::unlock_collateral(
+        collateral_vault::id(),
+        100000000,
+        UnlockCollateral {
+            vault: vault_pda,
+            config: config_pda(),
+            authority: authority.pubkey(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[unlock_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert_eq!(
+        banks_client.process_transaction(tx).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(VaultError::LockedUnderRecord.into())),
+    );
+    
+    let release_ix = |releaser: &Keypair, lock_id: u64, amount: u64| instruction::release_lock(
+        collateral_vault::id(),
+        amount,
+        ReleaseLock {
+            vault: vault_pda,
+            lock_record: lock_pda(vault_pda, lock_id),
+            config: config_pda(),
+            authority: releaser.pubkey(),
+            payer: payer.pubkey(),
+        },
+    );
+    
+    // One program cannot release another's lock, nor more than the lock holds
+    let tx = Transaction::new_signed_with_payer(
+        &[release_ix(&perps, 1, 100000000)],
+        Some(&payer.pubkey()),
+        &[&payer, &perps],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[release_ix(&authority, 1, 300000001)],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[release_ix(&authority, 1, 100000000)],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let record_account = banks_client.get_account(lock_pda(vault_pda, 1)).await.unwrap().unwrap();
+    let record = LockRecord::try_deserialize(&mut record_account.data.as_ref()).unwrap();
+    assert_eq!(record.amount, 200000000);
+    
+    // Settling the whole of lock 2 moves it to the counterparty and closes the record
+    let transfer_ix = instruction::transfer_from_lock(
+        collateral_vault::id(),
+        200000000,
+        TransferFromLock {
+            source_vault: vault_pda,
+            destination_vault: counterparty_vault,
+            lock_record: lock_pda(vault_pda, 2),
+            source_token_account: get_vault_token_account(&mut banks_client, vault_pda).await,
+            destination_token_account: get_vault_token_account(&mut banks_client, counterparty_vault).await,
+            config: config_pda(),
+            authority: perps.pubkey(),
+            payer: payer.pubkey(),
+            mint: usdt_mint,
+            token_program: token::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[transfer_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &perps],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    assert!(banks_client.get_account(lock_pda(vault_pda, 2)).await.unwrap().is_none());
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.locked_balance, 200000000);
+    assert_eq!(vault.recorded_locked_balance, 200000000);
+    assert_eq!(vault.available_balance, 600000000);
+    assert_eq!(vault.total_balance, 800000000);
+    
+    let counterparty_account = banks_client.get_account(counterparty_vault).await.unwrap().unwrap();
+    let counterparty_state = Vault::try_deserialize(&mut counterparty_account.data.as_ref()).unwrap();
+    assert_eq!(counterparty_state.available_balance, 200000000);
+}
+
+#[tokio::test]
+async fn test_expired_lock_returns_to_available_permissionlessly() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let mut context = program.start_with_context().await;
+    let payer = context.payer.insecure_clone();
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut context.banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut context.banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut context.banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    lock_collateral(&mut context.banks_client, &payer, &authority, vault_pda, 300000000).await;
+    
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    let expires_at = clock.unix_timestamp + 3600;
+    
+    // An expiry already passed is refused
+    let open_ix = |expires_at: i64| instruction::open_lock(
+        collateral_vault::id(),
+        9,
+        400000000,
+        [0; 32],
+        expires_at,
+        OpenLock {
+            vault: vault_pda,
+            lock_record: lock_pda(vault_pda, 9),
+            vault_token_account: vault_token_pda(vault_pda),
+            asset_config: asset_config_pda(usdt_mint),
+            config: config_pda(),
+            authority: authority.pubkey(),
+            payer: payer.pubkey(),
+            system_program: solana_sdk::system_program::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[open_ix(clock.unix_timestamp - 1)],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[open_ix(expires_at)],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    
+    // Liquidation seizes the lock taken without a record, and never the recorded one
+    let destination = create_token_account(&mut context.banks_client, &payer, usdt_mint, payer.pubkey()).await;
+    let liquidate_ix = |amount: u64| instruction::liquidate(
+        collateral_vault::id(),
+        amount,
+        Liquidate {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            destination_token_account: destination,
+            insurance_fund: None,
+            keeper_token_account: None,
+            liquidator: authority.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[liquidate_ix(300000000)],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[liquidate_ix(1)],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert_eq!(
+        context.banks_client.process_transaction(tx).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(VaultError::LockedUnderRecord.into())),
+    );
+    
+    // Anyone may expire the lock, but only once its expiry is reached
+    let stranger = Keypair::new();
+    let expire_ix = instruction::expire_lock(
+        collateral_vault::id(),
+        ExpireLock {
+            vault: vault_pda,
+            lock_record: lock_pda(vault_pda, 9),
+            config: config_pda(),
+            caller: stranger.pubkey(),
+            payer: payer.pubkey(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[expire_ix.clone()],
+        Some(&payer.pubkey()),
+        &[&payer, &stranger],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+    
+    clock.unix_timestamp = expires_at;
+    context.set_sysvar(&clock);
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[expire_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &stranger],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert!(context.banks_client.get_account(lock_pda(vault_pda, 9)).await.unwrap().is_none());
+    
+    // The record's full amount returns; the seized lock is gone
+    let vault_account = context.banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.locked_balance, 0);
+    assert_eq!(vault.recorded_locked_balance, 0);
+    assert_eq!(vault.available_balance, 700000000);
+    assert_eq!(vault.total_balance, 700000000);
+}
+
+#[tokio::test]
+async fn test_self_frozen_vault_unfreezes_only_after_the_delay() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let mut context = program.start_with_context().await;
+    let payer = context.payer.insecure_clone();
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut context.banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut context.banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut context.banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    lock_collateral(&mut context.banks_client, &payer, &authority, vault_pda, 200000000).await;
+    let user_token_account = create_token_account(&mut context.banks_client, &payer, usdt_mint, user.pubkey()).await;
+    
+    let freeze_accounts = || SetVaultFreeze {
+        vault: vault_pda,
+        user: user.pubkey(),
+    };
+    let withdraw_ix = instruction::withdraw(
+        collateral_vault::id(),
+        100000000,
+        Withdraw {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            user_token_account,
+            treasury_token_account: Some(treasury_pda(usdt_mint)),
+            user: user.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+            memo_program: None,
+        },
+    );
+    let lock_ix = instruction::lock_collateral(
+        collateral_vault::id(),
+        100000000,
+        LockCollateral {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            asset_config: asset_config_pda(usdt_mint),
+            config: config_pda(),
+            authority: authority.pubkey(),
+        },
+    );
+    let unlock_ix = instruction::unlock_collateral(
+        collateral_vault::id(),
+        200000000,
+        UnlockCollateral {
+            vault: vault_pda,
+            config: config_pda(),
+            authority: authority.pubkey(),
+        },
+    );
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction::freeze_vault(collateral_vault::id(), freeze_accounts())],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    
+    // Frozen: no withdrawals or new locks, but unlocks still go through
+    let tx = Transaction::new_signed_with_payer(
+        &[withdraw_ix.clone()],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+    let tx = Transaction::new_signed_with_payer(
+        &[lock_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+    let tx = Transaction::new_signed_with_payer(
+        &[unlock_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    
+    // An unfreeze waits out the delay after it is requested
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction::request_unfreeze(collateral_vault::id(), freeze_accounts())],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let unfreeze_ix = instruction::unfreeze_vault(collateral_vault::id(), freeze_accounts());
+    let tx = Transaction::new_signed_with_payer(
+        &[unfreeze_ix.clone()],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+    
+    clock.unix_timestamp += Vault::UNFREEZE_DELAY;
+    context.set_sysvar(&clock);
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[unfreeze_ix, withdraw_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    
+    let vault_account = context.banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert!(!vault.frozen);
+    assert_eq!(vault.unfreeze_requested_at, 0);
+    assert_eq!(vault.available_balance, 900000000);
+}
+
+#[tokio::test]
+async fn test_frozen_vault_refuses_transfers_of_available_collateral() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, _recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let peer = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    let (peer_vault_pda, _) = setup_vault(&mut banks_client, &payer, &peer, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction::freeze_vault(
+            collateral_vault::id(),
+            SetVaultFreeze {
+                vault: vault_pda,
+                user: user.pubkey(),
+            },
+        )],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    // Moving the balance into another vault would get around the freeze
+    let transfer_ix = instruction::transfer_available_collateral(
+        collateral_vault::id(),
+        400000000,
+        TransferAvailableCollateral {
+            source_vault: vault_pda,
+            destination_vault: peer_vault_pda,
+            source_token_account: vault_token_pda(vault_pda),
+            destination_token_account: vault_token_pda(peer_vault_pda),
+            user: user.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[transfer_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert_eq!(
+        banks_client.process_transaction(tx).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(VaultError::VaultFrozen.into())),
+    );
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.available_balance, 1000000000);
+    
+    let peer_vault_account = banks_client.get_account(peer_vault_pda).await.unwrap().unwrap();
+    let peer_vault = Vault::try_deserialize(&mut peer_vault_account.data.as_ref()).unwrap();
+    assert_eq!(peer_vault.total_balance, 0);
+}
+
+#[tokio::test]
+async fn test_force_unlock_frees_stranded_collateral_after_its_delay() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let mut context = program.start_with_context().await;
+    let payer = context.payer.insecure_clone();
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut context.banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut context.banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut context.banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    lock_collateral(&mut context.banks_client, &payer, &authority, vault_pda, 600000000).await;
+    
+    let force_unlock_pda = Pubkey::find_program_address(&[b"force_unlock", vault_pda.as_ref()], &collateral_vault::id()).0;
+    let announce_ix = instruction::announce_force_unlock(
+        collateral_vault::id(),
+        600000000,
+        AnnounceForceUnlock {
+            vault: vault_pda,
+            force_unlock: force_unlock_pda,
+            config: config_pda(),
+            admin: payer.pubkey(),
+            system_program: system_program::id(),
+        },
+    );
+    
+    // A trading program still in control cancels the announcement
+    let cancel_ix = instruction::cancel_force_unlock(
+        collateral_vault::id(),
+        CancelForceUnlock {
+            vault: vault_pda,
+            force_unlock: force_unlock_pda,
+            config: config_pda(),
+            canceller: authority.pubkey(),
+            admin: payer.pubkey(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[announce_ix.clone(), cancel_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert!(context.banks_client.get_account(force_unlock_pda).await.unwrap().is_none());
+    
+    // Uncancelled, it executes only once the delay has passed
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[announce_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    
+    let execute_ix = instruction::execute_force_unlock(
+        collateral_vault::id(),
+        ExecuteForceUnlock {
+            vault: vault_pda,
+            force_unlock: force_unlock_pda,
+            config: config_pda(),
+            admin: payer.pubkey(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[execute_ix.clone()],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+    
+    clock.unix_timestamp += ForceUnlock::DELAY;
+    context.set_sysvar(&clock);
+    
+    // Like every other movement of vault funds, it waits out a pause
+    let pause_ix = |paused: bool| instruction::set_pause(
+        collateral_vault::id(),
+        paused,
+        UpdateConfig {
+            config: config_pda(),
+            admin: payer.pubkey(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[pause_ix(true), execute_ix.clone()],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert_eq!(
+        context.banks_client.process_transaction(tx).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(1, InstructionError::Custom(VaultError::ProgramPaused.into())),
+    );
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[execute_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert!(context.banks_client.get_account(force_unlock_pda).await.unwrap().is_none());
+    
+    let vault_account = context.banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.locked_balance, 0);
+    assert_eq!(vault.available_balance, 1000000000);
+}
+
+#[tokio::test]
+async fn test_vault_with_broken_invariant_refuses_balance_changes() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let mut context = program.start_with_context().await;
+    let payer = context.payer.insecure_clone();
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut context.banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut context.banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut context.banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    lock_collateral(&mut context.banks_client, &payer, &authority, vault_pda, 200000000).await;
+    let user_token_account = create_token_account(&mut context.banks_client, &payer, usdt_mint, user.pubkey()).await;
+    mint_tokens(&mut context.banks_client, &payer, usdt_mint, user_token_account, 100000000).await;
+    
+    // Corrupt the books as a math bug would: total no longer adds up
+    let mut vault_account = context.banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let mut vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    vault.total_balance += 1;
+    let mut data = Vec::new();
+    vault.try_serialize(&mut data).unwrap();
+    vault_account.data[..data.len()].copy_from_slice(&data);
+    context.set_account(&vault_pda, &vault_account.into());
+    
+    let deposit_ix = instruction::deposit(
+        collateral_vault::id(),
+        100000000,
+        Deposit {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            user_token_account,
+            user: user.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+        },
+    );
+    let withdraw_ix = instruction::withdraw(
+        collateral_vault::id(),
+        100000000,
+        Withdraw {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            user_token_account,
+            treasury_token_account: Some(treasury_pda(usdt_mint)),
+            user: user.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+            memo_program: None,
+        },
+    );
+    let lock_ix = instruction::lock_collateral(
+        collateral_vault::id(),
+        100000000,
+        LockCollateral {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            asset_config: asset_config_pda(usdt_mint),
+            config: config_pda(),
+            authority: authority.pubkey(),
+        },
+    );
+    let unlock_ix = instruction::unlock_collateral(
+        collateral_vault::id(),
+        100000000,
+        UnlockCollateral {
+            vault: vault_pda,
+            config: config_pda(),
+            authority: authority.pubkey(),
+        },
+    );
+    
+    // Every balance change aborts rather than build on the corruption
+    for (ix, signer) in [(deposit_ix, &user), (withdraw_ix, &user), (lock_ix, &authority), (unlock_ix, &authority)] {
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&payer.pubkey()),
+            &[&payer, signer],
+            context.banks_client.get_latest_blockhash().await.unwrap(),
+        );
+        assert!(context.banks_client.process_transaction(tx).await.is_err());
+    }
+    
+    let vault_account = context.banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let unchanged = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(unchanged, vault);
+}
+
+#[tokio::test]
+async fn test_vault_mid_operation_refuses_reentrant_calls() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let mut context = program.start_with_context().await;
+    let payer = context.payer.insecure_clone();
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut context.banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut context.banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut context.banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    
+    // A completed operation leaves the mark cleared
+    let mut vault_account = context.banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let mut vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert!(!vault.processing);
+    
+    // As a call re-entering from inside a token CPI would find it
+    vault.processing = true;
+    let mut data = Vec::new();
+    vault.try_serialize(&mut data).unwrap();
+    vault_account.data[..data.len()].copy_from_slice(&data);
+    context.set_account(&vault_pda, &vault_account.into());
+    
+    let user_token_account = create_token_account(&mut context.banks_client, &payer, usdt_mint, user.pubkey()).await;
+    let withdraw_ix = instruction::withdraw(
+        collateral_vault::id(),
+        100000000,
+        Withdraw {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            user_token_account,
+            treasury_token_account: Some(treasury_pda(usdt_mint)),
+            user: user.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+            memo_program: None,
+        },
+    );
+    // A sync run mid-deposit would credit the transfer a second time
+    let sync_ix = instruction::sync_balance(
+        collateral_vault::id(),
+        SyncBalance {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            caller: payer.pubkey(),
+            config: config_pda(),
+        },
+    );
+    let lock_ix = instruction::lock_collateral(
+        collateral_vault::id(),
+        100000000,
+        LockCollateral {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            asset_config: asset_config_pda(usdt_mint),
+            config: config_pda(),
+            authority: authority.pubkey(),
+        },
+    );
+    
+    for (ix, signer) in [(withdraw_ix, &user), (sync_ix, &payer), (lock_ix, &authority)] {
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&payer.pubkey()),
+            &[&payer, signer],
+            context.banks_client.get_latest_blockhash().await.unwrap(),
+        );
+        assert_eq!(
+            context.banks_client.process_transaction(tx).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::Custom(VaultError::VaultBusy.into())),
+        );
+    }
+    
+    let vault_account = context.banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let unchanged = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(unchanged.available_balance, 1000000000);
+    assert_eq!(unchanged.locked_balance, 0);
+}
+
+#[tokio::test]
+async fn test_escrowed_transfer_needs_acceptance_and_returns_on_expiry() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let mut context = program.start_with_context().await;
+    let payer = context.payer.insecure_clone();
+    
+    let user = Keypair::new();
+    let peer = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut context.banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut context.banks_client, &payer, &user, &authority, usdt_mint).await;
+    let (peer_vault_pda, _) = setup_vault(&mut context.banks_client, &payer, &peer, &authority, usdt_mint).await;
+    deposit_to_vault(&mut context.banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    lock_collateral(&mut context.banks_client, &payer, &authority, vault_pda, 600000000).await;
+    
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    let expires_at = clock.unix_timestamp + 3600;
+    let source_token_account = get_vault_token_account(&mut context.banks_client, vault_pda).await;
+    let destination_token_account = get_vault_token_account(&mut context.banks_client, peer_vault_pda).await;
+    
+    let initiate_ix = |transfer_id: u64, amount: u64| instruction::initiate_transfer(
+        collateral_vault::id(),
+        transfer_id,
+        amount,
+        expires_at,
+        InitiateTransfer {
+            source_vault: vault_pda,
+            destination_vault: peer_vault_pda,
+            source_token_account,
+            escrow: escrow_pda(vault_pda, transfer_id),
+            escrow_token_account: escrow_token_pda(escrow_pda(vault_pda, transfer_id)),
+            authority: authority.pubkey(),
+            payer: payer.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+            system_program: system_program::id(),
+            rent: rent::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[initiate_ix(1, 200000000), initiate_ix(2, 300000000)],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    
+    // The source is debited at once, but nothing lands until accepted
+    let vault_account = context.banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.locked_balance, 100000000);
+    assert_eq!(vault.total_balance, 500000000);
+    assert_eq!(vault.open_records, 2);
+    
+    let accept_ix = |signer: &Keypair, transfer_id: u64| instruction::accept_transfer(
+        collateral_vault::id(),
+        AcceptTransfer {
+            escrow: escrow_pda(vault_pda, transfer_id),
+            escrow_token_account: escrow_token_pda(escrow_pda(vault_pda, transfer_id)),
+            source_vault: vault_pda,
+            destination_vault: peer_vault_pda,
+            destination_token_account,
+            user: signer.pubkey(),
+            payer: payer.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+        },
+    );
+    
+    // Only the destination's owner accepts
+    let tx = Transaction::new_signed_with_payer(
+        &[accept_ix(&authority, 1)],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[accept_ix(&peer, 1)],
+        Some(&payer.pubkey()),
+        &[&payer, &peer],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert!(context.banks_client.get_account(escrow_pda(vault_pda, 1)).await.unwrap().is_none());
+    
+    let peer_vault_account = context.banks_client.get_account(peer_vault_pda).await.unwrap().unwrap();
+    let peer_vault = Vault::try_deserialize(&mut peer_vault_account.data.as_ref()).unwrap();
+    assert_eq!(peer_vault.available_balance, 200000000);
+    
+    // Past expiry the second can no longer be accepted, and anyone may send it back
+    clock.unix_timestamp = expires_at;
+    context.set_sysvar(&clock);
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[accept_ix(&peer, 2)],
+        Some(&payer.pubkey()),
+        &[&payer, &peer],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+    
+    let stranger = Keypair::new();
+    let cancel_ix = instruction::cancel_transfer(
+        collateral_vault::id(),
+        CancelTransfer {
+            escrow: escrow_pda(vault_pda, 2),
+            escrow_token_account: escrow_token_pda(escrow_pda(vault_pda, 2)),
+            source_vault: vault_pda,
+            source_token_account,
+            caller: stranger.pubkey(),
+            payer: payer.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[cancel_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &stranger],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert!(context.banks_client.get_account(escrow_pda(vault_pda, 2)).await.unwrap().is_none());
+    
+    // Returned funds are available, not locked again
+    let vault_account = context.banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.locked_balance, 100000000);
+    assert_eq!(vault.available_balance, 700000000);
+    assert_eq!(vault.total_balance, 800000000);
+    assert_eq!(vault.open_records, 0);
+    vault.validate_invariant().unwrap();
+}
+
+#[tokio::test]
+async fn test_every_vault_event_takes_the_next_sequence() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, _recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.event_sequence, 1, "VaultInitialized carries 1");
+    
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    lock_collateral(&mut banks_client, &payer, &authority, vault_pda, 300000000).await;
+    
+    // A rejected instruction counts nothing
+    let stranger = Keypair::new();
+    let lock_ix = instruction::lock_collateral(
+        collateral_vault::id(),
+        100000000,
+        LockCollateral {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            asset_config: asset_config_pda(usdt_mint),
+            config: config_pda(),
+            authority: stranger.pubkey(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[lock_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &stranger],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.event_sequence, 3);
+}
+
+#[tokio::test]
+async fn test_pause_halts_balance_movements() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let outsider = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    
+    let pause_ix = |admin: Pubkey, paused: bool| instruction::set_pause(
+        collateral_vault::id(),
+        paused,
+        UpdateConfig {
+            config: config_pda(),
+            admin,
+        },
+    );
+    
+    // Only the admin may pause
+    let tx = Transaction::new_signed_with_payer(
+        &[pause_ix(outsider.pubkey(), true)],
+        Some(&payer.pubkey()),
+        &[&payer, &outsider],
+        recent_blockhash,
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[pause_ix(payer.pubkey(), true)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let config_account = banks_client.get_account(config_pda()).await.unwrap().unwrap();
+    let config = ProgramConfig::try_deserialize(&mut config_account.data.as_ref()).unwrap();
+    assert!(config.paused);
+    
+    let lock_ix = instruction::lock_collateral(
+        collateral_vault::id(),
+        400000000,
+        LockCollateral {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            asset_config: asset_config_pda(usdt_mint),
+            config: config_pda(),
+            authority: authority.pubkey(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[lock_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    let withdraw_ix = instruction::withdraw(
+        collateral_vault::id(),
+        100000000,
+        Withdraw {
+            vault: vault_pda,
+            vault_token_account: get_vault_token_account(&mut banks_client, vault_pda).await,
+            user_token_account: create_token_account(&mut banks_client, &payer, usdt_mint, user.pubkey()).await,
+            treasury_token_account: Some(treasury_pda(usdt_mint)),
+            user: user.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+            memo_program: None,
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[withdraw_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    // The vault itself was never deactivated, so resuming is enough
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert!(vault.is_active);
+    assert_eq!(vault.total_balance, 1000000000);
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[pause_ix(payer.pubkey(), false)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    lock_collateral(&mut banks_client, &payer, &authority, vault_pda, 400000000).await;
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.locked_balance, 400000000);
+}
+
+#[tokio::test]
+async fn test_recover_foreign_tokens() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    let foreign_mint = Pubkey::from_str(FOREIGN_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    
+    // Tokens of another mint land in an account owned by the vault PDA
+    let stranded_account = create_token_account(&mut banks_client, &payer, foreign_mint, vault_pda).await;
+    mint_tokens(&mut banks_client, &payer, foreign_mint, stranded_account, 250000000).await;
+    let recovery_account = create_token_account(&mut banks_client, &payer, foreign_mint, payer.pubkey()).await;
+    
+    let recover_ix = instruction::recover_foreign_tokens(
+        collateral_vault::id(),
+        RecoverForeignTokens {
+            config: config_pda(),
+            admin: payer.pubkey(),
+            vault: vault_pda,
+            vault_token_account: get_vault_token_account(&mut banks_client, vault_pda).await,
+            foreign_token_account: stranded_account,
+            recovery_token_account: recovery_account,
+            foreign_mint,
+            token_program: token::id(),
+        },
+    );
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[recover_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let recovered = banks_client.get_account(recovery_account).await.unwrap().unwrap();
+    let recovered = TokenAccount::try_deserialize(&mut recovered.data.as_ref()).unwrap();
+    assert_eq!(recovered.amount, 250000000);
+}
+
+#[tokio::test]
+async fn test_security_recover_never_touches_collateral() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    
+    let vault_token_account = get_vault_token_account(&mut banks_client, vault_pda).await;
+    let recovery_account = create_token_account(&mut banks_client, &payer, usdt_mint, payer.pubkey()).await;
+    
+    // Pointing the sweep at the collateral account itself must fail
+    let recover_ix = instruction::recover_foreign_tokens(
+        collateral_vault::id(),
+        RecoverForeignTokens {
+            config: config_pda(),
+            admin: payer.pubkey(),
+            vault: vault_pda,
+            vault_token_account,
+            foreign_token_account: vault_token_account,
+            recovery_token_account: recovery_account,
+            foreign_mint: usdt_mint,
+            token_program: token::id(),
+        },
+    );
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[recover_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.total_balance, 1000000000);
+}
+
+#[tokio::test]
+async fn test_revoke_token_delegate_requires_delegate() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    
+    // The program never sets a delegate, so a fresh vault has nothing to revoke
+    let revoke_ix = instruction::revoke_token_delegate(
+        collateral_vault::id(),
+        RevokeTokenDelegate {
+            config: config_pda(),
+            admin: payer.pubkey(),
+            vault: vault_pda,
+            vault_token_account: get_vault_token_account(&mut banks_client, vault_pda).await,
+            token_program: token::id(),
+        },
+    );
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[revoke_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    
+    assert!(banks_client.process_transaction(tx).await.is_err());
+}
+
+// Helper functions
+fn config_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &collateral_vault::id()).0
+}
+
+fn lock_pda(vault: Pubkey, lock_id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"lock", vault.as_ref(), &lock_id.to_le_bytes()], &collateral_vault::id()).0
+}
+
+fn vault_token_pda(vault: Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"token", vault.as_ref()], &collateral_vault::id()).0
+}
+
+fn asset_config_pda(mint: Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"asset_config", mint.as_ref()], &collateral_vault::id()).0
+}
+
+fn treasury_pda(mint: Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"treasury", mint.as_ref()], &collateral_vault::id()).0
+}
+
+fn insurance_fund_pda(mint: Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"insurance", mint.as_ref()], &collateral_vault::id()).0
+}
+
+fn escrow_pda(source_vault: Pubkey, transfer_id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"escrow", source_vault.as_ref(), &transfer_id.to_le_bytes()], &collateral_vault::id()).0
+}
+
+fn escrow_token_pda(escrow: Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"escrow_token", escrow.as_ref()], &collateral_vault::id()).0
+}
+
+#[tokio::test]
+async fn test_rotate_authority() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let old_authority = Keypair::new();
+    let new_authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &old_authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    
+    // The new key must co-sign; a rotation signed only by the current authority fails
+    let rotate = |authority: &Keypair, new: &Keypair| instruction::rotate_authority(
+        collateral_vault::id(),
+        RotateAuthority {
+            vault: vault_pda,
+            authority: authority.pubkey(),
+            new_authority: new.pubkey(),
+        },
+    );
+    
+    let stranger = Keypair::new();
+    let tx = Transaction::new_signed_with_payer(
+        &[rotate(&stranger, &new_authority)],
+        Some(&payer.pubkey()),
+        &[&payer, &stranger, &new_authority],
+        recent_blockhash,
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[rotate(&old_authority, &new_authority)],
+        Some(&payer.pubkey()),
+        &[&payer, &old_authority, &new_authority],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.authority, new_authority.pubkey());
+    
+    // Only the new key can lock collateral now
+    lock_collateral(&mut banks_client, &payer, &new_authority, vault_pda, 100000000).await;
+    
+    let lock_ix = instruction::lock_collateral(
+        collateral_vault::id(),
+        100000000,
+        LockCollateral {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            asset_config: asset_config_pda(usdt_mint),
+            config: config_pda(),
+            authority: old_authority.pubkey(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[lock_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &old_authority],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    // Rollback is the same instruction with the keys swapped
+    let tx = Transaction::new_signed_with_payer(
+        &[rotate(&new_authority, &old_authority)],
+        Some(&payer.pubkey()),
+        &[&payer, &new_authority, &old_authority],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.authority, old_authority.pubkey());
+    
+    // A key already authorized alongside the authority cannot be rotated in
+    let add_ix = instruction::add_authorized_authority(
+        collateral_vault::id(),
+        AddAuthorizedAuthority {
+            vault: vault_pda,
+            authority: old_authority.pubkey(),
+            new_authority: new_authority.pubkey(),
+            payer: payer.pubkey(),
+            system_program: solana_sdk::system_program::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[add_ix, rotate(&old_authority, &new_authority)],
+        Some(&payer.pubkey()),
+        &[&payer, &old_authority, &new_authority],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert_eq!(
+        banks_client.process_transaction(tx).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(1, InstructionError::Custom(VaultError::AuthorityAlreadyAuthorized.into())),
+    );
+}
+
+#[tokio::test]
+async fn test_authorized_authorities_share_cpi_rights() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let settlement = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    
+    let add = |authority: &Keypair, new: &Keypair| instruction::add_authorized_authority(
+        collateral_vault::id(),
+        AddAuthorizedAuthority {
+            vault: vault_pda,
+            authority: authority.pubkey(),
+            new_authority: new.pubkey(),
+            payer: payer.pubkey(),
+            system_program: solana_sdk::system_program::id(),
+        },
+    );
+    
+    // Only the vault's authority can authorize others
+    let tx = Transaction::new_signed_with_payer(
+        &[add(&settlement, &settlement)],
+        Some(&payer.pubkey()),
+        &[&payer, &settlement],
+        recent_blockhash,
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[add(&authority, &settlement)],
+        Some(&payer.pubkey()),
+        &[&payer, &authority, &settlement],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    assert_eq!(vault_account.data.len(), Vault::size_with_authorities(1));
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.authorized_authorities, vec![settlement.pubkey()]);
+    
+    // Both keys can lock and unlock
+    lock_collateral(&mut banks_client, &payer, &settlement, vault_pda, 100000000).await;
+    lock_collateral(&mut banks_client, &payer, &authority, vault_pda, 100000000).await;
+    let unlock_ix = instruction::unlock_collateral(
+        collateral_vault::id(),
+        200000000,
+        UnlockCollateral {
+            vault: vault_pda,
+            config: config_pda(),
+            authority: settlement.pubkey(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[unlock_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &settlement],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    // Authorizing the same key twice is rejected
+    let tx = Transaction::new_signed_with_payer(
+        &[add(&authority, &settlement)],
+        Some(&payer.pubkey()),
+        &[&payer, &authority, &settlement],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    let remove_ix = instruction::remove_authorized_authority(
+        collateral_vault::id(),
+        settlement.pubkey(),
+        RemoveAuthorizedAuthority {
+            vault: vault_pda,
+            authority: authority.pubkey(),
+            payer: payer.pubkey(),
+            system_program: solana_sdk::system_program::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[remove_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    assert_eq!(vault_account.data.len(), Vault::SIZE);
+    
+    // The removed key has lost its rights
+    let lock_ix = instruction::lock_collateral(
+        collateral_vault::id(),
+        100000000,
+        LockCollateral {
+            vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            asset_config: asset_config_pda(usdt_mint),
+            config: config_pda(),
+            authority: settlement.pubkey(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[lock_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &settlement],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+}
+
+#[tokio::test]
+async fn test_sweep_requires_dormant_vault() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    
+    let (dormant_funds, _) = Pubkey::find_program_address(&[b"dormant", vault_pda.as_ref()], &collateral_vault::id());
+    let (dormant_token_account, _) = Pubkey::find_program_address(
+        &[b"dormant_token", dormant_funds.as_ref()],
+        &collateral_vault::id(),
+    );
+    let vault_token_account = get_vault_token_account(&mut banks_client, vault_pda).await;
+    
+    // Just deposited into, so far from dormant
+    let sweep_ix = instruction::sweep_dormant_vault(
+        collateral_vault::id(),
+        SweepDormantVault {
+            config: config_pda(),
+            admin: payer.pubkey(),
+            vault: vault_pda,
+            vault_token_account,
+            dormant_funds,
+            dormant_token_account,
+            mint: usdt_mint,
+            token_program: token::id(),
+            system_program: system_program::id(),
+            rent: rent::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[sweep_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    // Nothing was swept, so there is nothing to reclaim
+    let reclaim_ix = instruction::reclaim_dormant_funds(
+        collateral_vault::id(),
+        ReclaimDormantFunds {
+            vault: vault_pda,
+            user: user.pubkey(),
+            vault_token_account,
+            dormant_funds,
+            dormant_token_account,
+            payer: payer.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[reclaim_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert!(vault.is_active);
+    assert_eq!(vault.total_balance, 1000000000);
+}
+
+#[tokio::test]
+async fn test_deposit_token_2022_credits_amount_after_transfer_fee() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    fund_account(&mut banks_client, &payer, &user).await;
+    
+    // 1% transfer fee, capped at 5 USDT
+    let fee_mint = create_transfer_fee_mint(&mut banks_client, &payer, 100, 5000000).await;
+    setup_config_for_mint(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT, fee_mint, 100).await;
+    
+    let (vault_pda, _) = Pubkey::find_program_address(
+        &[b"vault", user.pubkey().as_ref()],
+        &collateral_vault::id(),
+    );
+    let vault_token_account = get_vault_token_account(&mut banks_client, vault_pda).await;
+    
+    let init_ix = instruction::initialize_vault(
+        collateral_vault::id(),
+        0,
+        InitializeVault {
+            vault: vault_pda,
+            vault_token_account,
+            user: user.pubkey(),
+            payer: payer.pubkey(),
+            authority: authority.pubkey(),
+            config: config_pda(),
+            usdt_mint: fee_mint,
+            token_program: spl_token_2022::id(),
+            system_program: system_program::id(),
+            rent: rent::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[init_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let user_token_account = create_token_2022_account(&mut banks_client, &payer, fee_mint, user.pubkey()).await;
+    let mint_ix = spl_token_2022::instruction::mint_to(
+        &spl_token_2022::id(),
+        &fee_mint,
+        &user_token_account,
+        &payer.pubkey(),
+        &[],
+        100000000,
+    ).unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[mint_ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let deposit_ix = instruction::deposit(
+        collateral_vault::id(),
+        100000000,
+        Deposit {
+            vault: vault_pda,
+            vault_token_account,
+            user_token_account,
+            user: user.pubkey(),
+            config: config_pda(),
+            mint: fee_mint,
+            token_program: spl_token_2022::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[deposit_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    // 1 USDT was withheld, so the vault is credited exactly what its token account holds
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.total_balance, 99000000);
+    assert_eq!(vault.available_balance, 99000000);
+    assert_eq!(vault.total_deposited, 99000000);
+    
+    let token_account = banks_client.get_account(vault_token_account).await.unwrap().unwrap();
+    let token_account = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&token_account.data).unwrap();
+    assert_eq!(token_account.base.amount, vault.total_balance);
+}
+
+#[tokio::test]
+async fn test_security_transfer_rejects_same_vault() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    
+    // Debiting and crediting the same vault would leave its books inconsistent
+    let vault_token_account = get_vault_token_account(&mut banks_client, vault_pda).await;
+    let transfer_ix = instruction::transfer_collateral(
+        collateral_vault::id(),
+        500000000,
+        TransferCollateral {
+            source_vault: vault_pda,
+            destination_vault: vault_pda,
+            source_token_account: vault_token_account,
+            destination_token_account: vault_token_account,
+            authority: authority.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+            memo_program: None,
+        },
+    );
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[transfer_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        recent_blockhash,
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.total_balance, 1000000000);
+    assert_eq!(vault.available_balance, 1000000000);
+}
+
+#[tokio::test]
+async fn test_owner_transfers_available_collateral_to_another_vault() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, _recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let peer = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    let (peer_vault_pda, _) = setup_vault(&mut banks_client, &payer, &peer, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    lock_collateral(&mut banks_client, &payer, &authority, vault_pda, 600000000).await;
+    
+    let source_token_account = get_vault_token_account(&mut banks_client, vault_pda).await;
+    let destination_token_account = get_vault_token_account(&mut banks_client, peer_vault_pda).await;
+    let transfer_ix = |signer: &Keypair, amount: u64| instruction::transfer_available_collateral(
+        collateral_vault::id(),
+        amount,
+        TransferAvailableCollateral {
+            source_vault: vault_pda,
+            destination_vault: peer_vault_pda,
+            source_token_account,
+            destination_token_account,
+            user: signer.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+        },
+    );
+    
+    // Only the owner may move it, and never into the locked balance
+    let tx = Transaction::new_signed_with_payer(
+        &[transfer_ix(&authority, 100000000)],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[transfer_ix(&user, 500000000)],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[transfer_ix(&user, 400000000)],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.available_balance, 0);
+    assert_eq!(vault.locked_balance, 600000000);
+    assert_eq!(vault.total_balance, 600000000);
+    
+    let peer_vault_account = banks_client.get_account(peer_vault_pda).await.unwrap().unwrap();
+    let peer_vault = Vault::try_deserialize(&mut peer_vault_account.data.as_ref()).unwrap();
+    assert_eq!(peer_vault.available_balance, 400000000);
+    assert_eq!(peer_vault.total_balance, 400000000);
+}
+
+#[tokio::test]
+async fn test_security_transfer_rejects_substituted_token_accounts() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, _) = program.start().await;
+    
+    let source_user = Keypair::new();
+    let destination_user = Keypair::new();
+    let third_user = Keypair::new();
+    let authority = Keypair::new();
+    let attacker = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (source_vault, _) = setup_vault(&mut banks_client, &payer, &source_user, &authority, usdt_mint).await;
+    let (destination_vault, _) = setup_vault(&mut banks_client, &payer, &destination_user, &authority, usdt_mint).await;
+    let (third_vault, _) = setup_vault(&mut banks_client, &payer, &third_user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &source_user, source_vault, usdt_mint, 1000000000).await;
+    deposit_to_vault(&mut banks_client, &payer, &third_user, third_vault, usdt_mint, 1000000000).await;
+    
+    let source_token_account = get_vault_token_account(&mut banks_client, source_vault).await;
+    let destination_token_account = get_vault_token_account(&mut banks_client, destination_vault).await;
+    let third_token_account = get_vault_token_account(&mut banks_client, third_vault).await;
+    let attacker_token_account = create_token_account(&mut banks_client, &payer, usdt_mint, attacker.pubkey()).await;
+    
+    // (source token account, destination token account) pairs that don't belong to the vaults
+    let substitutions = [
+        (third_token_account, destination_token_account),
+        (source_token_account, attacker_token_account),
+        (source_token_account, third_token_account),
+        (source_token_account, source_token_account),
+    ];
+    
+    for (substituted_source, substituted_destination) in substitutions {
+        let transfer_ix = instruction::transfer_collateral(
+            collateral_vault::id(),
+            500000000,
+            TransferCollateral {
+                source_vault,
+                destination_vault,
+                source_token_account: substituted_source,
+                destination_token_account: substituted_destination,
+                authority: authority.pubkey(),
+                config: config_pda(),
+                mint: usdt_mint,
+                token_program: token::id(),
+                memo_program: None,
+            },
+        );
+        
+        let tx = Transaction::new_signed_with_payer(
+            &[transfer_ix],
+            Some(&payer.pubkey()),
+            &[&payer, &authority],
+            banks_client.get_latest_blockhash().await.unwrap(),
+        );
+        assert!(banks_client.process_transaction(tx).await.is_err());
+    }
+    
+    for (vault_pda, expected) in [(source_vault, 1000000000), (destination_vault, 0), (third_vault, 1000000000)] {
+        let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
+        let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+        assert_eq!(vault.total_balance, expected);
+    }
+    
+    let attacker_account = banks_client.get_account(attacker_token_account).await.unwrap().unwrap();
+    let attacker_account = TokenAccount::try_deserialize(&mut attacker_account.data.as_ref()).unwrap();
+    assert_eq!(attacker_account.amount, 0);
+}
+
+#[tokio::test]
+async fn test_security_withdraw_rejects_substituted_accounts() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, _) = program.start().await;
+    
+    let user = Keypair::new();
+    let victim = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    let (victim_vault, _) = setup_vault(&mut banks_client, &payer, &victim, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 100000000).await;
+    deposit_to_vault(&mut banks_client, &payer, &victim, victim_vault, usdt_mint, 1000000000).await;
+    
+    let vault_token_account = get_vault_token_account(&mut banks_client, vault_pda).await;
+    let victim_token_account = get_vault_token_account(&mut banks_client, victim_vault).await;
+    let user_token_account = create_token_account(&mut banks_client, &payer, usdt_mint, user.pubkey()).await;
+    let victim_user_token_account = create_token_account(&mut banks_client, &payer, usdt_mint, victim.pubkey()).await;
+    
+    // (vault, vault token account, user token account), each signed by `user`
+    let substitutions = [
+        (victim_vault, victim_token_account, user_token_account),
+        (vault_pda, victim_token_account, user_token_account),
+        (vault_pda, vault_token_account, vault_token_account),
+        (vault_pda, vault_token_account, victim_user_token_account),
+    ];
+    
+    for (substituted_vault, substituted_vault_token_account, substituted_user_token_account) in substitutions {
+        let withdraw_ix = instruction::withdraw(
+            collateral_vault::id(),
+            50000000,
+            Withdraw {
+                vault: substituted_vault,
+                vault_token_account: substituted_vault_token_account,
+                user_token_account: substituted_user_token_account,
+                treasury_token_account: Some(treasury_pda(usdt_mint)),
+                user: user.pubkey(),
+                config: config_pda(),
+                mint: usdt_mint,
+                token_program: token::id(),
+                memo_program: None,
+            },
+        );
+        
+        let tx = Transaction::new_signed_with_payer(
+            &[withdraw_ix],
+            Some(&payer.pubkey()),
+            &[&payer, &user],
+            banks_client.get_latest_blockhash().await.unwrap(),
+        );
+        assert!(banks_client.process_transaction(tx).await.is_err());
+    }
+    
+    for (vault, expected) in [(vault_pda, 100000000), (victim_vault, 1000000000)] {
+        let vault_account = banks_client.get_account(vault).await.unwrap().unwrap();
+        let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+        assert_eq!(vault.total_balance, expected);
+    }
+    
+    let victim_account = banks_client.get_account(victim_token_account).await.unwrap().unwrap();
+    let victim_account = TokenAccount::try_deserialize(&mut victim_account.data.as_ref()).unwrap();
+    assert_eq!(victim_account.amount, 1000000000);
+}
+
+#[tokio::test]
+async fn test_close_vault_requires_empty_vault_and_returns_rent() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    
+    let vault_token_account = get_vault_token_account(&mut banks_client, vault_pda).await;
+    let rent_destination = Keypair::new().pubkey();
+    let close_vault_ix = || instruction::close_vault(
+        collateral_vault::id(),
+        CloseVault {
+            vault: vault_pda,
+            user: user.pubkey(),
+            rent_destination,
+            vault_token_account,
+            mint: usdt_mint,
+            token_program: token::id(),
+        },
+    );
+    
+    // Still holding collateral
+    let tx = Transaction::new_signed_with_payer(
+        &[close_vault_ix()],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        recent_blockhash,
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
+    
+    let withdraw_ix = instruction::withdraw(
+        collateral_vault::id(),
+        1000000000,
+        Withdraw {
+            vault: vault_pda,
+            vault_token_account,
+            user_token_account: create_token_account(&mut banks_client, &payer, usdt_mint, user.pubkey()).await,
+            treasury_token_account: Some(treasury_pda(usdt_mint)),
+            user: user.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+            memo_program: None,
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[withdraw_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let vault_rent = banks_client.get_account(vault_pda).await.unwrap().unwrap().lamports;
+    let token_account_rent = banks_client.get_account(vault_token_account).await.unwrap().unwrap().lamports;
+    let user_lamports = banks_client.get_balance(user.pubkey()).await.unwrap();
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[close_vault_ix()],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    // Both accounts are gone and their rent reached the named wallet
+    assert!(banks_client.get_account(vault_pda).await.unwrap().is_none());
+    assert!(banks_client.get_account(vault_token_account).await.unwrap().is_none());
+    assert_eq!(
+        banks_client.get_balance(rent_destination).await.unwrap(),
+        vault_rent + token_account_rent,
+    );
+    assert_eq!(banks_client.get_balance(user.pubkey()).await.unwrap(), user_lamports);
+}
+
+#[tokio::test]
+async fn test_close_vault_waits_for_open_records() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, _recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let peer = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    let (peer_vault_pda, _) = setup_vault(&mut banks_client, &payer, &peer, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    lock_collateral(&mut banks_client, &payer, &authority, vault_pda, 1000000000).await;
+    
+    let vault_token_account = get_vault_token_account(&mut banks_client, vault_pda).await;
+    let destination_token_account = get_vault_token_account(&mut banks_client, peer_vault_pda).await;
+    let clock: Clock = banks_client.get_sysvar().await.unwrap();
+    
+    // Escrowing the whole balance leaves the vault empty but still drawn on
+    let initiate_ix = instruction::initiate_transfer(
+        collateral_vault::id(),
+        1,
+        1000000000,
+        clock.unix_timestamp + 3600,
+        InitiateTransfer {
+            source_vault: vault_pda,
+            destination_vault: peer_vault_pda,
+            source_token_account: vault_token_account,
+            escrow: escrow_pda(vault_pda, 1),
+            escrow_token_account: escrow_token_pda(escrow_pda(vault_pda, 1)),
+            authority: authority.pubkey(),
+            payer: payer.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+            system_program: system_program::id(),
+            rent: rent::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[initiate_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    let close_vault_ix = || instruction::close_vault(
+        collateral_vault::id(),
+        CloseVault {
+            vault: vault_pda,
+            user: user.pubkey(),
+            rent_destination: user.pubkey(),
+            vault_token_account,
+            mint: usdt_mint,
+            token_program: token::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[close_vault_ix()],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert_eq!(
+        banks_client.process_transaction(tx).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(VaultError::VaultHasOpenRecords.into())),
+    );
+    
+    // Once the escrow settles the vault may close
+    let accept_ix = instruction::accept_transfer(
+        collateral_vault::id(),
+        AcceptTransfer {
+            escrow: escrow_pda(vault_pda, 1),
+            escrow_token_account: escrow_token_pda(escrow_pda(vault_pda, 1)),
+            source_vault: vault_pda,
+            destination_vault: peer_vault_pda,
+            destination_token_account,
+            user: peer.pubkey(),
+            payer: payer.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[accept_ix, close_vault_ix()],
+        Some(&payer.pubkey()),
+        &[&payer, &peer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    assert!(banks_client.get_account(vault_pda).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_withdrawal_fee_is_paid_to_treasury() {
+    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
+    let (mut banks_client, payer, _recent_blockhash) = program.start().await;
+    
+    let user = Keypair::new();
+    let authority = Keypair::new();
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
+    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
+    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    
+    let fee_ix = |bps: u16| instruction::update_withdrawal_fee(
+        collateral_vault::id(),
+        bps,
+        UpdateConfig {
+            config: config_pda(),
+            admin: payer.pubkey(),
+        },
+    );
+    
+    // Above the maximum is refused
+    let tx = Transaction::new_signed_with_payer(
+        &[fee_ix(ProgramConfig::MAX_WITHDRAWAL_FEE_BPS + 1)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(banks_client.process_transaction(tx).await.is_err());
     
-    let deposit_ix = instruction::deposit(
+    let user_token_account = create_token_account(&mut banks_client, &payer, usdt_mint, user.pubkey()).await;
+    let vault_token_account = get_vault_token_account(&mut banks_client, vault_pda).await;
+    let withdraw_ix = |amount: u64, treasury_token_account: Option<Pubkey>| instruction::withdraw(
         collateral_vault::id(),
-        deposit_amount,
-        Deposit {
+        amount,
+        Withdraw {
             vault: vault_pda,
-            vault_token_account: get_vault_token_account(&mut banks_client, vault_pda).await,
-            user_token_account: user_usdt_account,
+            vault_token_account,
+            user_token_account,
+            treasury_token_account,
             user: user.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
             token_program: token::id(),
+            memo_program: None,
         },
     );
     
+    // Without a fee the treasury account may be left out
     let tx = Transaction::new_signed_with_payer(
-        &[deposit_ix],
+        &[withdraw_ix(100000000, None)],
         Some(&payer.pubkey()),
         &[&payer, &user],
-        recent_blockhash,
+        banks_client.get_latest_blockhash().await.unwrap(),
     );
+    banks_client.process_transaction(tx).await.unwrap();
     
+    // 0.3% of each withdrawal
+    let tx = Transaction::new_signed_with_payer(
+        &[fee_ix(30)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
     banks_client.process_transaction(tx).await.unwrap();
     
-    // Verify deposit
+    // Once one is charged, it is not
+    let tx = Transaction::new_signed_with_payer(
+        &[withdraw_ix(500000000, None)],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert_eq!(
+        banks_client.process_transaction(tx).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(VaultError::TreasuryMissing.into())),
+    );
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[withdraw_ix(500000000, Some(treasury_pda(usdt_mint)))],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
+    
+    // The vault is debited the full amount, split between user and treasury
     let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
     let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.total_balance, 400000000);
+    assert_eq!(vault.total_withdrawn, 600000000);
     
-    assert_eq!(vault.total_balance, deposit_amount);
-    assert_eq!(vault.available_balance, deposit_amount);
-    assert_eq!(vault.locked_balance, 0);
+    let user_account = banks_client.get_account(user_token_account).await.unwrap().unwrap();
+    let user_account = TokenAccount::try_deserialize(&mut user_account.data.as_ref()).unwrap();
+    assert_eq!(user_account.amount, 598500000);
     
-    // Withdraw 500 USDT
-    let withdraw_amount = 500000000u64;
+    let treasury_account = banks_client.get_account(treasury_pda(usdt_mint)).await.unwrap().unwrap();
+    let treasury_account = TokenAccount::try_deserialize(&mut treasury_account.data.as_ref()).unwrap();
+    assert_eq!(treasury_account.amount, 1500000);
     
-    let withdraw_ix = instruction::withdraw(
+    // Only the admin moves collected fees out
+    let destination = create_token_account(&mut banks_client, &payer, usdt_mint, payer.pubkey()).await;
+    let treasury_ix = |admin: &Keypair| instruction::withdraw_treasury(
         collateral_vault::id(),
-        withdraw_amount,
-        Withdraw {
-            vault: vault_pda,
-            vault_token_account: get_vault_token_account(&mut banks_client, vault_pda).await,
-            user_token_account: user_usdt_account,
-            user: user.pubkey(),
+        1500000,
+        WithdrawTreasury {
+            treasury_token_account: treasury_pda(usdt_mint),
+            destination_token_account: destination,
+            config: config_pda(),
+            admin: admin.pubkey(),
+            mint: usdt_mint,
             token_program: token::id(),
         },
     );
-    
+    let stranger = Keypair::new();
     let tx = Transaction::new_signed_with_payer(
-        &[withdraw_ix],
+        &[treasury_ix(&stranger)],
         Some(&payer.pubkey()),
-        &[&payer, &user],
-        recent_blockhash,
+        &[&payer, &stranger],
+        banks_client.get_latest_blockhash().await.unwrap(),
     );
+    assert!(banks_client.process_transaction(tx).await.is_err());
     
+    let tx = Transaction::new_signed_with_payer(
+        &[treasury_ix(&payer)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
     banks_client.process_transaction(tx).await.unwrap();
     
-    // Verify withdrawal
-    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
-    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
-    
-    assert_eq!(vault.total_balance, deposit_amount - withdraw_amount);
-    assert_eq!(vault.available_balance, deposit_amount - withdraw_amount);
-    assert_eq!(vault.locked_balance, 0);
+    let destination_account = banks_client.get_account(destination).await.unwrap().unwrap();
+    let destination_account = TokenAccount::try_deserialize(&mut destination_account.data.as_ref()).unwrap();
+    assert_eq!(destination_account.amount, 1500000);
 }
 
 #[tokio::test]
-async fn test_lock_unlock_collateral() {
+async fn test_insurance_fund_covers_settlement_shortfall() {
     let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
-    let (mut banks_client, payer, recent_blockhash) = program.start().await;
+    let (mut banks_client, payer, _recent_blockhash) = program.start().await;
     
     let user = Keypair::new();
     let authority = Keypair::new();
     let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    let insurance_fund = insurance_fund_pda(usdt_mint);
     
-    // Setup vault with 1000 USDT
+    setup_config(&mut banks_client, &payer, &payer, DEFAULT_MAX_TRANSACTION_AMOUNT).await;
     let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
     deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
+    lock_collateral(&mut banks_client, &payer, &authority, vault_pda, 100000000).await;
     
-    // Lock 600 USDT for trading
-    let lock_amount = 600000000u64;
+    // The admin opens the fund and anyone may pay into it
+    let init_ix = instruction::initialize_insurance_fund(
+        collateral_vault::id(),
+        InitializeInsuranceFund {
+            insurance_fund,
+            config: config_pda(),
+            admin: payer.pubkey(),
+            mint: usdt_mint,
+            token_program: token::id(),
+            system_program: system_program::id(),
+            rent: rent::id(),
+        },
+    );
+    let funder = Keypair::new();
+    let funder_token_account = create_token_account(&mut banks_client, &payer, usdt_mint, funder.pubkey()).await;
+    mint_tokens(&mut banks_client, &payer, usdt_mint, funder_token_account, 500000000).await;
+    let fund_ix = instruction::fund_insurance(
+        collateral_vault::id(),
+        500000000,
+        FundInsurance {
+            insurance_fund,
+            funder_token_account,
+            funder: funder.pubkey(),
+            mint: usdt_mint,
+            token_program: token::id(),
+        },
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[init_ix, fund_ix],
+        Some(&payer.pubkey()),
+        &[&payer, &funder],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    banks_client.process_transaction(tx).await.unwrap();
     
-    let lock_ix = instruction::lock_collateral(
+    let vault_token_account = get_vault_token_account(&mut banks_client, vault_pda).await;
+    let counterparty = create_token_account(&mut banks_client, &payer, usdt_mint, Keypair::new().pubkey()).await;
+    let settle_ix = |insurance_fund: Option<Pubkey>| instruction::settle_loss(
         collateral_vault::id(),
-        lock_amount,
-        LockCollateral {
+        250000000,
+        SettleLoss {
             vault: vault_pda,
+            vault_token_account,
+            counterparty_token_account: counterparty,
+            insurance_fund,
             authority: authority.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
+            token_program: token::id(),
         },
     );
     
+    // Without the fund a loss beyond the locked balance is refused
     let tx = Transaction::new_signed_with_payer(
-        &[lock_ix],
+        &[settle_ix(None)],
         Some(&payer.pubkey()),
         &[&payer, &authority],
-        recent_blockhash,
+        banks_client.get_latest_blockhash().await.unwrap(),
     );
+    assert!(banks_client.process_transaction(tx).await.is_err());
     
+    let tx = Transaction::new_signed_with_payer(
+        &[settle_ix(Some(insurance_fund))],
+        Some(&payer.pubkey()),
+        &[&payer, &authority],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
     banks_client.process_transaction(tx).await.unwrap();
     
-    // Verify lock
+    // All 100 locked went first; the fund paid the other 150, and the
+    // available balance was never touched
     let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
     let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
+    assert_eq!(vault.locked_balance, 0);
+    assert_eq!(vault.available_balance, 900000000);
+    vault.validate_invariant().unwrap();
     
-    assert_eq!(vault.total_balance, 1000000000);
-    assert_eq!(vault.available_balance, 400000000);
-    assert_eq!(vault.locked_balance, 600000000);
-    
-    // Unlock 200 USDT
-    let unlock_amount = 200000000u64;
+    let counterparty_account = banks_client.get_account(counterparty).await.unwrap().unwrap();
+    let counterparty_account = TokenAccount::try_deserialize(&mut counterparty_account.data.as_ref()).unwrap();
+    assert_eq!(counterparty_account.amount, 250000000);
     
-    let unlock_ix = instruction::unlock_collateral(
+    // Draws outside a settlement are the admin's and must give a reason
+    let destination = create_token_account(&mut banks_client, &payer, usdt_mint, payer.pubkey()).await;
+    let draw_ix = |reason: [u8; 32]| instruction::draw_insurance(
         collateral_vault::id(),
-        unlock_amount,
-        UnlockCollateral {
-            vault: vault_pda,
-            authority: authority.pubkey(),
+        350000000,
+        reason,
+        DrawInsurance {
+            insurance_fund,
+            destination_token_account: destination,
+            config: config_pda(),
+            admin: payer.pubkey(),
+            mint: usdt_mint,
+            token_program: token::id(),
         },
     );
-    
     let tx = Transaction::new_signed_with_payer(
-        &[unlock_ix],
+        &[draw_ix([0u8; 32])],
         Some(&payer.pubkey()),
-        &[&payer, &authority],
-        recent_blockhash,
+        &[&payer],
+        banks_client.get_latest_blockhash().await.unwrap(),
     );
+    assert!(banks_client.process_transaction(tx).await.is_err());
     
+    let mut reason = [0u8; 32];
+    reason[..11].copy_from_slice(b"incident-42");
+    let tx = Transaction::new_signed_with_payer(
+        &[draw_ix(reason)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
     banks_client.process_transaction(tx).await.unwrap();
     
-    // Verify unlock
-    let vault_account = banks_client.get_account(vault_pda).await.unwrap().unwrap();
-    let vault = Vault::try_deserialize(&mut vault_account.data.as_ref()).unwrap();
-    
-    assert_eq!(vault.total_balance, 1000000000);
-    assert_eq!(vault.available_balance, 600000000);
-    assert_eq!(vault.locked_balance, 400000000);
+    let fund_account = banks_client.get_account(insurance_fund).await.unwrap().unwrap();
+    let fund_account = TokenAccount::try_deserialize(&mut fund_account.data.as_ref()).unwrap();
+    assert_eq!(fund_account.amount, 0);
 }
 
-#[tokio::test]
-async fn test_security_withdraw_locked_funds() {
-    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
-    let (mut banks_client, payer, recent_blockhash) = program.start().await;
-    
-    let user = Keypair::new();
-    let authority = Keypair::new();
+async fn setup_config(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    admin: &Keypair,
+    max_transaction_amount: u64,
+) {
     let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
+    setup_config_for_mint(banks_client, payer, admin, max_transaction_amount, usdt_mint, 0).await;
     
-    // Setup vault with 1000 USDT and lock 800
-    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
-    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
-    lock_collateral(&mut banks_client, &payer, &authority, vault_pda, 800000000).await;
-    
-    // Attempt to withdraw 500 USDT (should fail - only 200 available)
-    let withdraw_amount = 500000000u64;
+    // USDT counts in full as collateral
+    let asset_config_ix = instruction::initialize_asset_config(
+        collateral_vault::id(),
+        0,
+        InitializeAssetConfig {
+            asset_config: asset_config_pda(usdt_mint),
+            config: config_pda(),
+            admin: admin.pubkey(),
+            mint: usdt_mint,
+            system_program: system_program::id(),
+        },
+    );
     
-    let withdraw_ix = instruction::withdraw(
+    // Withdrawals of USDT pay any protocol fee into its treasury
+    let treasury_ix = instruction::initialize_treasury(
         collateral_vault::id(),
-        withdraw_amount,
-        Withdraw {
-            vault: vault_pda,
-            vault_token_account: get_vault_token_account(&mut banks_client, vault_pda).await,
-            user_token_account: create_token_account(&mut banks_client, &payer, usdt_mint, user.pubkey()).await,
-            user: user.pubkey(),
+        InitializeTreasury {
+            treasury_token_account: treasury_pda(usdt_mint),
+            config: config_pda(),
+            admin: admin.pubkey(),
+            mint: usdt_mint,
             token_program: token::id(),
+            system_program: system_program::id(),
+            rent: rent::id(),
         },
     );
     
     let tx = Transaction::new_signed_with_payer(
-        &[withdraw_ix],
+        &[asset_config_ix, treasury_ix],
         Some(&payer.pubkey()),
-        &[&payer, &user],
-        recent_blockhash,
+        &[payer, admin],
+        banks_client.get_latest_blockhash().await.unwrap(),
     );
     
-    let result = banks_client.process_transaction(tx).await;
-    assert!(result.is_err());
+    banks_client.process_transaction(tx).await.unwrap();
 }
 
-#[tokio::test]
-async fn test_security_unauthorized_cpi() {
-    let program = ProgramTest::new("collateral_vault", collateral_vault::id(), processor!(collateral_vault::entry));
-    let (mut banks_client, payer, recent_blockhash) = program.start().await;
-    
-    let user = Keypair::new();
-    let authority = Keypair::new();
-    let unauthorized_caller = Keypair::new();
-    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
-    
-    // Setup vault
-    let (vault_pda, _) = setup_vault(&mut banks_client, &payer, &user, &authority, usdt_mint).await;
-    deposit_to_vault(&mut banks_client, &payer, &user, vault_pda, usdt_mint, 1000000000).await;
-    
-    // Attempt to lock collateral with unauthorized caller
-    let lock_amount = 500000000u64;
-    
-    let lock_ix = instruction::lock_collateral(
+/// Config alone, with `collateral_mint` as the only mint vaults may hold
+/// 
+/// Freeze authorities are allowed, as USDT has one; transfer fees up to
+/// `max_mint_transfer_fee_bps` are.
+async fn setup_config_for_mint(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    admin: &Keypair,
+    max_transaction_amount: u64,
+    collateral_mint: Pubkey,
+    max_mint_transfer_fee_bps: u16,
+) {
+    let init_ix = instruction::initialize_config(
         collateral_vault::id(),
-        lock_amount,
-        LockCollateral {
-            vault: vault_pda,
-            authority: unauthorized_caller.pubkey(),
+        max_transaction_amount,
+        true,
+        max_mint_transfer_fee_bps,
+        InitializeConfig {
+            config: config_pda(),
+            admin: admin.pubkey(),
+            collateral_mint,
+            system_program: system_program::id(),
         },
     );
     
     let tx = Transaction::new_signed_with_payer(
-        &[lock_ix],
+        &[init_ix],
         Some(&payer.pubkey()),
-        &[&payer, &unauthorized_caller],
-        recent_blockhash,
+        &[payer, admin],
+        banks_client.get_latest_blockhash().await.unwrap(),
     );
     
-    let result = banks_client.process_transaction(tx).await;
-    assert!(result.is_err());
+    banks_client.process_transaction(tx).await.unwrap();
+}
+
+async fn fund_account(banks_client: &mut BanksClient, payer: &Keypair, account: &Keypair) {
+    let tx = Transaction::new_signed_with_payer(
+        &[system_instruction::transfer(&payer.pubkey(), &account.pubkey(), 1000000000)],
+        Some(&payer.pubkey()),
+        &[payer],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    
+    banks_client.process_transaction(tx).await.unwrap();
 }
 
-// Helper functions
 async fn setup_vault(
     banks_client: &mut BanksClient,
     payer: &Keypair,
@@ -335,12 +4478,14 @@ async fn setup_vault(
     
     let init_ix = instruction::initialize_vault(
         collateral_vault::id(),
-        vault_bump,
+        0,
         InitializeVault {
             vault: vault_pda,
             vault_token_account: token_pda,
             user: user.pubkey(),
+            payer: payer.pubkey(),
             authority: authority.pubkey(),
+            config: config_pda(),
             usdt_mint,
             token_program: token::id(),
             system_program: system_program::id(),
@@ -378,6 +4523,8 @@ async fn deposit_to_vault(
             vault_token_account: get_vault_token_account(banks_client, vault_pda).await,
             user_token_account: user_usdt_account,
             user: user.pubkey(),
+            config: config_pda(),
+            mint: usdt_mint,
             token_program: token::id(),
         },
     );
@@ -399,11 +4546,15 @@ async fn lock_collateral(
     vault_pda: Pubkey,
     amount: u64,
 ) {
+    let usdt_mint = Pubkey::from_str(USDT_MINT).unwrap();
     let lock_ix = instruction::lock_collateral(
         collateral_vault::id(),
         amount,
         LockCollateral {
             vault: vault_pda,
+            vault_token_account: vault_token_pda(vault_pda),
+            asset_config: asset_config_pda(usdt_mint),
+            config: config_pda(),
             authority: authority.pubkey(),
         },
     );
@@ -455,6 +4606,92 @@ async fn create_token_account(
     account.pubkey()
 }
 
+async fn create_transfer_fee_mint(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    transfer_fee_basis_points: u16,
+    maximum_fee: u64,
+) -> Pubkey {
+    let mint = Keypair::new();
+    let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(
+        &[ExtensionType::TransferFeeConfig],
+    ).unwrap();
+    let lamports = banks_client.get_rent().await.unwrap().minimum_balance(space);
+    
+    let instructions = [
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &mint.pubkey(),
+            lamports,
+            space as u64,
+            &spl_token_2022::id(),
+        ),
+        transfer_fee::instruction::initialize_transfer_fee_config(
+            &spl_token_2022::id(),
+            &mint.pubkey(),
+            Some(&payer.pubkey()),
+            Some(&payer.pubkey()),
+            transfer_fee_basis_points,
+            maximum_fee,
+        ).unwrap(),
+        spl_token_2022::instruction::initialize_mint2(
+            &spl_token_2022::id(),
+            &mint.pubkey(),
+            &payer.pubkey(),
+            None,
+            6,
+        ).unwrap(),
+    ];
+    
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &mint],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    
+    banks_client.process_transaction(tx).await.unwrap();
+    mint.pubkey()
+}
+
+async fn create_token_2022_account(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    mint: Pubkey,
+    owner: Pubkey,
+) -> Pubkey {
+    let account = Keypair::new();
+    let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(
+        &[ExtensionType::TransferFeeAmount],
+    ).unwrap();
+    let lamports = banks_client.get_rent().await.unwrap().minimum_balance(space);
+    
+    let create_ix = system_instruction::create_account(
+        &payer.pubkey(),
+        &account.pubkey(),
+        lamports,
+        space as u64,
+        &spl_token_2022::id(),
+    );
+    
+    let init_ix = spl_token_2022::instruction::initialize_account3(
+        &spl_token_2022::id(),
+        &account.pubkey(),
+        &mint,
+        &owner,
+    ).unwrap();
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[create_ix, init_ix],
+        Some(&payer.pubkey()),
+        &[payer, &account],
+        banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    
+    banks_client.process_transaction(tx).await.unwrap();
+    account.pubkey()
+}
+
 async fn mint_tokens(
     banks_client: &mut BanksClient,
     payer: &Keypair,
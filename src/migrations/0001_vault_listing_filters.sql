@@ -0,0 +1,16 @@
+-- Columns and indexes backing filtered vault listing and search
+ALTER TABLE vaults ADD COLUMN IF NOT EXISTS mint_pubkey TEXT;
+ALTER TABLE vaults ADD COLUMN IF NOT EXISTS label TEXT;
+
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX IF NOT EXISTS idx_vaults_active_created_at ON vaults (is_active, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_vaults_total_balance ON vaults (total_balance);
+CREATE INDEX IF NOT EXISTS idx_vaults_locked_balance ON vaults (locked_balance) WHERE locked_balance > 0;
+CREATE INDEX IF NOT EXISTS idx_vaults_updated_at ON vaults (updated_at);
+CREATE INDEX IF NOT EXISTS idx_vaults_mint_pubkey ON vaults (mint_pubkey);
+
+-- Prefix search on pubkeys, substring search on labels
+CREATE INDEX IF NOT EXISTS idx_vaults_user_pubkey_prefix ON vaults (user_pubkey text_pattern_ops);
+CREATE INDEX IF NOT EXISTS idx_vaults_vault_pubkey_prefix ON vaults (vault_pubkey text_pattern_ops);
+CREATE INDEX IF NOT EXISTS idx_vaults_label_trgm ON vaults USING gin (label gin_trgm_ops);
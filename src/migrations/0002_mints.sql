@@ -0,0 +1,19 @@
+-- Per-mint configuration consulted by validation, formatting, risk and transaction building
+CREATE TABLE IF NOT EXISTS mints (
+    mint_pubkey TEXT PRIMARY KEY,
+    symbol TEXT NOT NULL,
+    decimals SMALLINT NOT NULL CHECK (decimals BETWEEN 0 AND 18),
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    min_deposit BIGINT NOT NULL DEFAULT 0 CHECK (min_deposit >= 0),
+    withdrawal_fee BIGINT NOT NULL DEFAULT 0 CHECK (withdrawal_fee >= 0),
+    oracle_feed_id TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_mints_enabled ON mints (enabled) WHERE enabled;
+
+-- Existing vaults were all created against USDT
+INSERT INTO mints (mint_pubkey, symbol, decimals, enabled, min_deposit, withdrawal_fee)
+VALUES ('Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB', 'USDT', 6, TRUE, 0, 0)
+ON CONFLICT (mint_pubkey) DO NOTHING;
@@ -0,0 +1,5 @@
+-- Per-stage latency (ms) for each operation, keyed by stage name
+ALTER TABLE transaction_records ADD COLUMN IF NOT EXISTS stage_timings JSONB;
+
+CREATE INDEX IF NOT EXISTS idx_transaction_records_created_at_timed
+    ON transaction_records (created_at) WHERE stage_timings IS NOT NULL;
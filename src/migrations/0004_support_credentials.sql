@@ -0,0 +1,13 @@
+-- Read-only support credentials; only the SHA-256 of each token is stored
+CREATE TABLE IF NOT EXISTS support_credentials (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_support_credentials_active ON support_credentials (token_hash) WHERE revoked_at IS NULL;
+
+-- Support access reviews filter the audit trail by event type
+CREATE INDEX IF NOT EXISTS idx_audit_logs_event_type_created_at ON audit_logs (event_type, created_at DESC);
@@ -0,0 +1,14 @@
+-- Outcome of every scheduled or on-demand reconciliation, per vault and mode
+CREATE TABLE IF NOT EXISTS reconciliation_results (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    vault_id UUID NOT NULL REFERENCES vaults (id),
+    mode TEXT NOT NULL CHECK (mode IN ('quick', 'standard', 'deep')),
+    is_consistent BOOLEAN NOT NULL,
+    findings JSONB NOT NULL DEFAULT '[]',
+    started_at TIMESTAMPTZ NOT NULL,
+    completed_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_reconciliation_results_vault ON reconciliation_results (vault_id, completed_at DESC);
+CREATE INDEX IF NOT EXISTS idx_reconciliation_results_inconsistent
+    ON reconciliation_results (completed_at DESC) WHERE NOT is_consistent;
@@ -0,0 +1,17 @@
+-- Withdrawals from multisig-owned vaults, tracked from proposal creation to execution
+CREATE TABLE IF NOT EXISTS multisig_proposals (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    transaction_id UUID NOT NULL UNIQUE REFERENCES transaction_records (id),
+    vault_id UUID NOT NULL REFERENCES vaults (id),
+    multisig_pubkey TEXT NOT NULL,
+    squads_vault_pubkey TEXT NOT NULL,
+    transaction_index BIGINT NOT NULL,
+    proposal_pubkey TEXT NOT NULL,
+    amount BIGINT NOT NULL CHECK (amount > 0),
+    status TEXT NOT NULL DEFAULT 'awaiting_signature',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_multisig_proposals_open ON multisig_proposals (updated_at)
+    WHERE status NOT IN ('executed', 'rejected', 'cancelled', 'expired');
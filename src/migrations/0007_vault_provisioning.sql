@@ -0,0 +1,19 @@
+-- Vault creation saga: reserve row -> submit init tx -> confirm on chain -> activate.
+-- Vaults created before this table existed have no row and are treated as active.
+CREATE UNIQUE INDEX IF NOT EXISTS idx_vaults_user_pubkey_unique ON vaults (user_pubkey);
+
+CREATE TABLE IF NOT EXISTS vault_provisioning (
+    vault_id UUID PRIMARY KEY REFERENCES vaults (id),
+    user_pubkey TEXT NOT NULL,
+    authority_pubkey TEXT NOT NULL,
+    mint_pubkey TEXT NOT NULL,
+    state TEXT NOT NULL DEFAULT 'reserved',
+    init_signature TEXT,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_vault_provisioning_incomplete ON vault_provisioning (updated_at)
+    WHERE state NOT IN ('active', 'failed');
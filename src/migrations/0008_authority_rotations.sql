@@ -0,0 +1,31 @@
+-- Coordinated rotation of the CPI authority key across every vault
+CREATE TABLE IF NOT EXISTS authority_rotations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    old_authority TEXT NOT NULL,
+    new_authority TEXT NOT NULL,
+    -- Where the backend loads the staged key from after a restart; never the key itself
+    new_key_path TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'staged',
+    batch_size INTEGER NOT NULL CHECK (batch_size > 0),
+    total_vaults INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
+
+-- At most one rotation may be open at a time
+CREATE UNIQUE INDEX IF NOT EXISTS idx_authority_rotations_open ON authority_rotations ((true))
+    WHERE status IN ('staged', 'in_progress', 'rolling_back');
+
+CREATE TABLE IF NOT EXISTS authority_rotation_vaults (
+    rotation_id UUID NOT NULL REFERENCES authority_rotations (id),
+    vault_id UUID NOT NULL REFERENCES vaults (id),
+    vault_pubkey TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    signature TEXT,
+    error_message TEXT,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (rotation_id, vault_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_authority_rotation_vaults_status ON authority_rotation_vaults (rotation_id, status);
@@ -0,0 +1,22 @@
+-- Hourly per-vault activity rolled up from confirmed transaction records.
+-- Maintained by the monitor; daily views aggregate the hourly rows.
+CREATE TABLE IF NOT EXISTS vault_activity_hourly (
+    bucket_start TIMESTAMPTZ NOT NULL,
+    vault_id UUID NOT NULL REFERENCES vaults (id),
+    operation_type TEXT NOT NULL,
+    tx_count BIGINT NOT NULL,
+    volume BIGINT NOT NULL,
+    PRIMARY KEY (bucket_start, vault_id, operation_type)
+);
+
+CREATE INDEX IF NOT EXISTS idx_vault_activity_hourly_vault ON vault_activity_hourly (vault_id, bucket_start);
+
+-- Single row: records updated before rolled_up_through are reflected in the rollup
+CREATE TABLE IF NOT EXISTS activity_rollup_state (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    rolled_up_through TIMESTAMPTZ NOT NULL,
+    refreshed_at TIMESTAMPTZ NOT NULL
+);
+
+-- Lets a refresh find the hours touched since the last one
+CREATE INDEX IF NOT EXISTS idx_transaction_records_updated_at ON transaction_records (updated_at);
@@ -0,0 +1,4 @@
+-- Database-only reconciliation of the vault row and snapshot history against the ledger
+ALTER TABLE reconciliation_results DROP CONSTRAINT IF EXISTS reconciliation_results_mode_check;
+ALTER TABLE reconciliation_results ADD CONSTRAINT reconciliation_results_mode_check
+    CHECK (mode IN ('quick', 'standard', 'deep', 'ledger'));
@@ -0,0 +1,43 @@
+-- Operations credentials may write notes, tags and cases; support credentials stay read-only
+ALTER TABLE support_credentials
+    ADD COLUMN IF NOT EXISTS role TEXT NOT NULL DEFAULT 'support'
+    CHECK (role IN ('support', 'operations'));
+
+-- Investigations and follow-ups on a vault
+CREATE TABLE IF NOT EXISTS vault_cases (
+    id UUID PRIMARY KEY,
+    vault_id UUID NOT NULL REFERENCES vaults (id),
+    title TEXT NOT NULL,
+    status TEXT NOT NULL CHECK (status IN ('open', 'closed')),
+    opened_by TEXT NOT NULL,
+    opened_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    closed_by TEXT,
+    closed_at TIMESTAMPTZ,
+    resolution TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_vault_cases_vault ON vault_cases (vault_id, opened_at DESC);
+CREATE INDEX IF NOT EXISTS idx_vault_cases_open ON vault_cases (vault_id) WHERE status = 'open';
+
+-- Free-form notes, optionally filed under a case
+CREATE TABLE IF NOT EXISTS vault_notes (
+    id UUID PRIMARY KEY,
+    vault_id UUID NOT NULL REFERENCES vaults (id),
+    case_id UUID REFERENCES vault_cases (id),
+    body TEXT NOT NULL,
+    author TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_vault_notes_vault ON vault_notes (vault_id, created_at DESC);
+
+-- Labels such as `vip` used to filter the admin vault list
+CREATE TABLE IF NOT EXISTS vault_tags (
+    vault_id UUID NOT NULL REFERENCES vaults (id),
+    tag TEXT NOT NULL,
+    added_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (vault_id, tag)
+);
+
+CREATE INDEX IF NOT EXISTS idx_vault_tags_tag ON vault_tags (tag);
@@ -0,0 +1,15 @@
+-- Balance reserved for an accepted operation until it settles on-chain.
+-- Spendable balance is the stored balance minus the vault's active holds.
+CREATE TABLE IF NOT EXISTS balance_holds (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    vault_id UUID NOT NULL REFERENCES vaults (id),
+    transaction_id UUID NOT NULL UNIQUE REFERENCES transaction_records (id),
+    balance TEXT NOT NULL CHECK (balance IN ('available', 'locked')),
+    amount BIGINT NOT NULL CHECK (amount > 0),
+    -- released: the operation failed; converted: its balance change was applied
+    status TEXT NOT NULL DEFAULT 'active' CHECK (status IN ('active', 'released', 'converted')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    settled_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_balance_holds_active ON balance_holds (vault_id) WHERE status = 'active';
@@ -0,0 +1,34 @@
+-- Admin bulk operations: previewed first, then executed a batch at a time
+CREATE TABLE IF NOT EXISTS bulk_jobs (
+    id UUID PRIMARY KEY,
+    operation TEXT NOT NULL,
+    parameters JSONB NOT NULL,
+    reason TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'previewed'
+        CHECK (status IN ('previewed', 'running', 'completed', 'aborted')),
+    batch_size INTEGER NOT NULL CHECK (batch_size > 0),
+    total_targets INTEGER NOT NULL,
+    requested_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_bulk_jobs_running ON bulk_jobs (created_at) WHERE status = 'running';
+
+-- Targets are resolved when the job is previewed, so execution acts on exactly what was shown
+CREATE TABLE IF NOT EXISTS bulk_job_targets (
+    job_id UUID NOT NULL REFERENCES bulk_jobs (id),
+    -- Vault, or transaction record for transaction operations
+    target_id UUID NOT NULL,
+    vault_id UUID NOT NULL REFERENCES vaults (id),
+    description TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'succeeded', 'failed', 'skipped')),
+    error_message TEXT,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (job_id, target_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_bulk_job_targets_status ON bulk_job_targets (job_id, status);
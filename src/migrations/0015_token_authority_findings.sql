@@ -0,0 +1,16 @@
+-- Vault token accounts found with an SPL delegate or close authority set; at most one open finding per vault
+CREATE TABLE IF NOT EXISTS token_authority_findings (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    vault_id UUID NOT NULL REFERENCES vaults (id),
+    token_account_pubkey TEXT NOT NULL,
+    delegate TEXT,
+    delegated_amount BIGINT NOT NULL DEFAULT 0,
+    close_authority TEXT,
+    detected_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    resolved_at TIMESTAMPTZ,
+    CHECK (delegate IS NOT NULL OR close_authority IS NOT NULL)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_token_authority_findings_open
+    ON token_authority_findings (vault_id) WHERE resolved_at IS NULL;
@@ -0,0 +1,24 @@
+-- Every sanctions/denylist screening of a deposit or withdrawal, with its outcome and any review
+CREATE TABLE IF NOT EXISTS screening_decisions (
+    id UUID PRIMARY KEY,
+    vault_id UUID NOT NULL REFERENCES vaults (id),
+    direction TEXT NOT NULL CHECK (direction IN ('deposit', 'withdrawal')),
+    address TEXT NOT NULL,
+    amount BIGINT NOT NULL,
+    risk TEXT NOT NULL CHECK (risk IN ('clear', 'elevated', 'severe')),
+    action TEXT NOT NULL CHECK (action IN ('allow', 'allow_and_flag', 'hold_for_review', 'block')),
+    findings JSONB NOT NULL DEFAULT '[]',
+    review_status TEXT CHECK (review_status IN ('pending', 'cleared', 'rejected')),
+    reviewed_by TEXT,
+    review_note TEXT,
+    reviewed_at TIMESTAMPTZ,
+    -- A cleared hold lets exactly one retry of the same operation through
+    clearance_used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_screening_decisions_vault ON screening_decisions (vault_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_screening_decisions_pending
+    ON screening_decisions (created_at) WHERE review_status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_screening_decisions_clearances
+    ON screening_decisions (vault_id, address, direction) WHERE review_status = 'cleared' AND clearance_used_at IS NULL;
@@ -0,0 +1,43 @@
+-- Outbound events, written in the same statement as the state change they describe.
+-- Sequence numbers come from the single counter row below; its row lock is held
+-- until the writing transaction commits, so events become visible in `seq`
+-- order without gaps and a consumer cursor never skips a late commit.
+CREATE TABLE IF NOT EXISTS event_outbox_head (
+    id SMALLINT PRIMARY KEY CHECK (id = 1),
+    seq BIGINT NOT NULL
+);
+
+INSERT INTO event_outbox_head (id, seq) VALUES (1, 0) ON CONFLICT (id) DO NOTHING;
+
+CREATE TABLE IF NOT EXISTS event_outbox (
+    seq BIGINT PRIMARY KEY,
+    event_type TEXT NOT NULL,
+    vault_id UUID,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_event_outbox_created ON event_outbox (created_at);
+
+-- Downstream systems receiving every outbox event, with how far each has acknowledged
+CREATE TABLE IF NOT EXISTS event_consumers (
+    name TEXT PRIMARY KEY,
+    delivery TEXT NOT NULL CHECK (delivery IN ('webhook', 'pull')),
+    endpoint_url TEXT,
+    signing_secret TEXT,
+    -- Every event up to this sequence number has been delivered and acknowledged
+    acked_seq BIGINT NOT NULL DEFAULT 0,
+    acked_at TIMESTAMPTZ,
+    failed_attempts INT NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Delivery worker holding the consumer; one at a time keeps delivery in order
+    leased_by TEXT,
+    leased_until TIMESTAMPTZ,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (delivery = 'pull' OR (endpoint_url IS NOT NULL AND signing_secret IS NOT NULL))
+);
+
+CREATE INDEX IF NOT EXISTS idx_event_consumers_due
+    ON event_consumers (next_attempt_at) WHERE delivery = 'webhook' AND enabled;
@@ -0,0 +1,15 @@
+-- Standing margin requirement a vault opted into: the backend locks or unlocks
+-- collateral after balance changes to keep locked_balance at the target
+CREATE TABLE IF NOT EXISTS vault_auto_lock (
+    vault_id UUID PRIMARY KEY REFERENCES vaults (id),
+    target_locked BIGINT NOT NULL CHECK (target_locked >= 0),
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    -- Signed amount of the latest adjustment: positive locked, negative unlocked
+    last_adjustment BIGINT,
+    last_adjusted_at TIMESTAMPTZ,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_vault_auto_lock_enabled ON vault_auto_lock (vault_id) WHERE enabled;
@@ -0,0 +1,47 @@
+-- Indexer progress per indexed program. `last_signature` is the newest
+-- signature processed; `polled_head_slot` the cluster slot of the last
+-- successful poll, from which indexer lag is measured.
+CREATE TABLE IF NOT EXISTS indexer_cursors (
+    program_id TEXT PRIMARY KEY,
+    last_slot BIGINT,
+    last_signature TEXT,
+    polled_head_slot BIGINT NOT NULL,
+    processed_count BIGINT NOT NULL DEFAULT 0,
+    polled_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Every signature the indexer has processed
+CREATE TABLE IF NOT EXISTS indexed_signatures (
+    signature TEXT PRIMARY KEY,
+    program_id TEXT NOT NULL,
+    slot BIGINT NOT NULL,
+    block_time TIMESTAMPTZ,
+    failed BOOLEAN NOT NULL,
+    -- 'poll' or 'rescan'
+    source TEXT NOT NULL CHECK (source IN ('poll', 'rescan')),
+    indexed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_indexed_signatures_program_slot ON indexed_signatures (program_id, slot);
+
+-- Slot ranges a poll could not cover. The re-scan walks back from
+-- `before_signature` until it reaches `until_signature` or `from_slot`,
+-- moving `before_signature` and `to_slot` down as it goes.
+CREATE TABLE IF NOT EXISTS indexer_gaps (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    program_id TEXT NOT NULL,
+    from_slot BIGINT NOT NULL,
+    to_slot BIGINT NOT NULL,
+    until_signature TEXT NOT NULL,
+    before_signature TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'resolved')),
+    recovered_count INT NOT NULL DEFAULT 0,
+    attempts INT NOT NULL DEFAULT 0,
+    last_error TEXT,
+    detected_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    resolved_at TIMESTAMPTZ,
+    CHECK (from_slot <= to_slot)
+);
+
+CREATE INDEX IF NOT EXISTS idx_indexer_gaps_open ON indexer_gaps (detected_at) WHERE status = 'open';
@@ -0,0 +1,49 @@
+-- Per-minute request counts of each rate-limited client, written by the same
+-- statement that consumes its tokens. Clients are `key:<hash prefix>` for
+-- bearer tokens and `ip:<address>` otherwise; raw tokens are never stored.
+CREATE TABLE IF NOT EXISTS rate_limit_usage (
+    client_id TEXT NOT NULL,
+    window_start TIMESTAMPTZ NOT NULL,
+    requests BIGINT NOT NULL DEFAULT 0,
+    -- Answered 429 because the token bucket was empty
+    rejected BIGINT NOT NULL DEFAULT 0,
+    -- Answered 429 because the client was banned
+    banned BIGINT NOT NULL DEFAULT 0,
+    last_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (client_id, window_start)
+);
+
+CREATE INDEX IF NOT EXISTS idx_rate_limit_usage_window ON rate_limit_usage (window_start);
+
+-- Temporary bans: a banned client is answered 429 until the ban expires or is lifted
+CREATE TABLE IF NOT EXISTS rate_limit_bans (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    client_id TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    -- Operator that placed the ban, or NULL when an escalation rule did
+    created_by TEXT,
+    rule_id UUID,
+    expires_at TIMESTAMPTZ NOT NULL,
+    lifted_at TIMESTAMPTZ,
+    lifted_by TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_rate_limit_bans_active ON rate_limit_bans (client_id, expires_at) WHERE lifted_at IS NULL;
+
+-- Automatic escalation: a client rejected at least `min_rejections` times within
+-- `window_seconds` is banned for `ban_seconds`, doubled for each earlier ban in
+-- the last week
+CREATE TABLE IF NOT EXISTS rate_limit_escalation_rules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL UNIQUE,
+    min_rejections BIGINT NOT NULL CHECK (min_rejections > 0),
+    window_seconds INT NOT NULL CHECK (window_seconds > 0),
+    ban_seconds INT NOT NULL CHECK (ban_seconds > 0),
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO rate_limit_escalation_rules (name, min_rejections, window_seconds, ban_seconds)
+VALUES ('sustained-429s', 600, 600, 900)
+ON CONFLICT (name) DO NOTHING;
@@ -0,0 +1,30 @@
+-- Identity verification linked to a user_pubkey, as last reported by a KYC
+-- provider. Only the provider's reference is kept; personal data stays with it.
+CREATE TABLE IF NOT EXISTS identities (
+    user_pubkey TEXT PRIMARY KEY,
+    provider TEXT NOT NULL,
+    external_id TEXT NOT NULL,
+    level TEXT NOT NULL CHECK (level IN ('none', 'basic', 'enhanced')),
+    status TEXT NOT NULL CHECK (status IN ('pending', 'verified', 'rejected', 'revoked')),
+    verified_at TIMESTAMPTZ,
+    expires_at TIMESTAMPTZ,
+    -- Provider time of the result applied last; results reported earlier are ignored
+    reported_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Every verification result received by webhook; redeliveries of an event are dropped
+CREATE TABLE IF NOT EXISTS identity_verification_events (
+    provider TEXT NOT NULL,
+    event_id TEXT NOT NULL,
+    user_pubkey TEXT NOT NULL,
+    level TEXT NOT NULL,
+    status TEXT NOT NULL,
+    reported_at TIMESTAMPTZ NOT NULL,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (provider, event_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_identity_verification_events_user
+    ON identity_verification_events (user_pubkey, received_at DESC);
@@ -0,0 +1,21 @@
+-- Alert rules vault owners set on their own vault. Balance and utilization
+-- rules fire when their condition starts to hold and re-arm once it stops;
+-- withdrawal rules fire on every withdrawal. Alerts are delivered as
+-- `vault_alert_triggered` outbox events.
+CREATE TABLE IF NOT EXISTS vault_alert_rules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    vault_id UUID NOT NULL REFERENCES vaults (id),
+    kind TEXT NOT NULL CHECK (kind IN ('balance_below', 'lock_utilization_above', 'any_withdrawal')),
+    -- Base units for balance_below, percent for lock_utilization_above, unused for any_withdrawal
+    threshold BIGINT,
+    label TEXT,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    -- The condition held at the last evaluation
+    triggered BOOLEAN NOT NULL DEFAULT FALSE,
+    trigger_count BIGINT NOT NULL DEFAULT 0,
+    last_triggered_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_vault_alert_rules_vault ON vault_alert_rules (vault_id) WHERE enabled;
@@ -0,0 +1,23 @@
+-- Bulk exports for data warehouse loads. Each export reads vaults, transactions
+-- and the ledger in one repeatable-read transaction, so the files agree with
+-- each other as of `snapshot_at`, and writes them to object storage.
+CREATE TABLE IF NOT EXISTS data_exports (
+    id UUID PRIMARY KEY,
+    requested_by TEXT NOT NULL,
+    range_start TIMESTAMPTZ NOT NULL,
+    range_end TIMESTAMPTZ NOT NULL CHECK (range_end > range_start),
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'running', 'completed', 'failed')),
+    -- Transaction time of the snapshot the files were read from
+    snapshot_at TIMESTAMPTZ,
+    -- Written objects: name, key, rows, bytes and sha256 of each
+    objects JSONB NOT NULL DEFAULT '[]',
+    error_message TEXT,
+    attempts INT NOT NULL DEFAULT 0,
+    leased_by TEXT,
+    leased_until TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_data_exports_queue ON data_exports (created_at) WHERE status IN ('pending', 'running');
@@ -0,0 +1,24 @@
+-- Responses of mutating requests made with an `Idempotency-Key` header, so a
+-- retry with the same key replays the first response instead of executing
+-- again. Keys are scoped to the client that sent them. A row is `in_progress`
+-- while its first request runs; `locked_until` lets a retry take over a key
+-- whose request died without finishing.
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    client_id TEXT NOT NULL,
+    idempotency_key TEXT NOT NULL,
+    method TEXT NOT NULL,
+    path TEXT NOT NULL,
+    -- SHA-256 of method, path and body; a reused key with another request is refused
+    request_hash TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'in_progress' CHECK (status IN ('in_progress', 'completed')),
+    response_status INTEGER,
+    response_content_type TEXT,
+    response_body BYTEA,
+    locked_until TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ,
+    expires_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (client_id, idempotency_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_idempotency_keys_expires ON idempotency_keys (expires_at);
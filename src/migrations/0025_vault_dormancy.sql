@@ -0,0 +1,22 @@
+-- Dormancy state of vaults with no transactions for the configured period.
+-- A vault is `notified` when first flagged and its owner is told through the
+-- `vault_dormancy_notice` outbox event; once `grace_ends_at` passes without
+-- activity its funds may be swept on-chain into a segregated dormant-funds
+-- account (`swept`). New activity during the grace period moves it to
+-- `cleared`; a reclaim by the owner moves a swept vault to `reclaimed`. A
+-- cleared or reclaimed vault that goes quiet again is flagged anew.
+CREATE TABLE IF NOT EXISTS vault_dormancy (
+    vault_id UUID PRIMARY KEY REFERENCES vaults (id),
+    status TEXT NOT NULL CHECK (status IN ('notified', 'swept', 'reclaimed', 'cleared')),
+    -- Time of the last transaction (or creation) before the vault was flagged
+    inactive_since TIMESTAMPTZ NOT NULL,
+    notified_at TIMESTAMPTZ NOT NULL,
+    grace_ends_at TIMESTAMPTZ NOT NULL,
+    swept_at TIMESTAMPTZ,
+    -- Base units moved into the dormant-funds account
+    swept_amount BIGINT,
+    reclaimed_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_vault_dormancy_status ON vault_dormancy (status, grace_ends_at);
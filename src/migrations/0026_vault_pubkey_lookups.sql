@@ -0,0 +1,5 @@
+-- Equality lookups of vaults by their on-chain addresses. Both addresses are
+-- PDAs derived from the vault owner, so neither can belong to two vaults; the
+-- prefix indexes from 0001 serve LIKE searches but not these lookups.
+CREATE UNIQUE INDEX IF NOT EXISTS idx_vaults_vault_pubkey ON vaults (vault_pubkey);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_vaults_token_account_pubkey ON vaults (token_account_pubkey);
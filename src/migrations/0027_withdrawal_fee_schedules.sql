@@ -0,0 +1,28 @@
+-- Withdrawal fee schedules per mint. A withdrawal pays the tier with the
+-- highest `min_amount` not above it: `flat_fee` plus `bps` basis points of the
+-- amount. A mint without tiers charges its flat `mints.withdrawal_fee`.
+CREATE TABLE IF NOT EXISTS withdrawal_fee_tiers (
+    mint_pubkey TEXT NOT NULL REFERENCES mints (mint_pubkey),
+    min_amount BIGINT NOT NULL CHECK (min_amount >= 0),
+    flat_fee BIGINT NOT NULL DEFAULT 0 CHECK (flat_fee >= 0),
+    bps INTEGER NOT NULL DEFAULT 0 CHECK (bps BETWEEN 0 AND 10000),
+    PRIMARY KEY (mint_pubkey, min_amount)
+);
+
+-- Time-boxed promotional fees, for every vault or for one user. While active a
+-- promotion replaces the tier fee when it is cheaper.
+CREATE TABLE IF NOT EXISTS withdrawal_fee_promotions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    mint_pubkey TEXT NOT NULL REFERENCES mints (mint_pubkey),
+    label TEXT NOT NULL,
+    -- Only this user's withdrawals qualify; everyone's when NULL
+    user_pubkey TEXT,
+    flat_fee BIGINT NOT NULL DEFAULT 0 CHECK (flat_fee >= 0),
+    bps INTEGER NOT NULL DEFAULT 0 CHECK (bps BETWEEN 0 AND 10000),
+    starts_at TIMESTAMPTZ NOT NULL,
+    ends_at TIMESTAMPTZ NOT NULL CHECK (ends_at > starts_at),
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_withdrawal_fee_promotions_mint ON withdrawal_fee_promotions (mint_pubkey, ends_at);
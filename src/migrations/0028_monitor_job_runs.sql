@@ -0,0 +1,15 @@
+-- One row per execution of a background monitor job, written when the run
+-- finishes. `skipped` runs were deferred (e.g. by cluster maintenance timing)
+-- but show the job's loop is still alive. The watchdog compares the latest
+-- run of each job with its interval to notice loops that stopped.
+CREATE TABLE IF NOT EXISTS monitor_job_runs (
+    id BIGSERIAL PRIMARY KEY,
+    job_type TEXT NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL,
+    duration_ms BIGINT NOT NULL CHECK (duration_ms >= 0),
+    outcome TEXT NOT NULL CHECK (outcome IN ('succeeded', 'failed', 'skipped')),
+    error TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_monitor_job_runs_job ON monitor_job_runs (job_type, started_at DESC);
+CREATE INDEX IF NOT EXISTS idx_monitor_job_runs_started ON monitor_job_runs (started_at);
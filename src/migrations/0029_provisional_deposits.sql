@@ -0,0 +1,10 @@
+-- Deposits are recorded as `pending` transaction records carrying the
+-- signature of the user's deposit transaction and credited to the vault only
+-- once the indexer has seen that signature finalized and found the program's
+-- DepositEvent for the vault and amount. Until then they are a provisional
+-- pending credit, never spendable. A signature backs at most one deposit.
+CREATE UNIQUE INDEX IF NOT EXISTS idx_transaction_records_deposit_signature
+    ON transaction_records (signature) WHERE operation_type = 'deposit';
+
+CREATE INDEX IF NOT EXISTS idx_transaction_records_pending_deposits
+    ON transaction_records (vault_id, created_at) WHERE operation_type = 'deposit' AND status = 'pending';
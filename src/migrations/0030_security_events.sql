@@ -0,0 +1,19 @@
+-- Authentication and authorization failures, rate limit bans and operator
+-- writes, kept apart from audit_logs so they can be queried and forwarded to
+-- a SIEM on their own. `source` is the client the event concerns, as the rate
+-- limiter identifies clients: the request's address (`ip:<address>`) for
+-- authentication failures and operator writes, the banned client for bans.
+-- A presented credential appears in `details` only as its hash prefix.
+CREATE TABLE IF NOT EXISTS security_events (
+    id BIGSERIAL PRIMARY KEY,
+    event_type TEXT NOT NULL,
+    severity TEXT NOT NULL CHECK (severity IN ('info', 'warning', 'critical')),
+    source TEXT NOT NULL,
+    -- Staff credential name, when the request authenticated as one
+    actor TEXT,
+    details JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_security_events_created ON security_events (created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_security_events_type_source ON security_events (event_type, source, created_at DESC);
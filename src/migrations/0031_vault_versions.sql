@@ -0,0 +1,51 @@
+-- A vault's version changes whenever anything its read endpoints serve
+-- changes: the vault row itself, its balance holds, or its pending deposits.
+-- It backs the ETags of GET /vaults/:user_pubkey and its /balance, so an
+-- unchanged vault can be answered with 304 from this one column.
+ALTER TABLE vaults ADD COLUMN IF NOT EXISTS version BIGINT NOT NULL DEFAULT 0;
+
+CREATE OR REPLACE FUNCTION bump_vault_version() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.version := OLD.version + 1;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS vaults_bump_version ON vaults;
+CREATE TRIGGER vaults_bump_version
+    BEFORE UPDATE ON vaults
+    FOR EACH ROW EXECUTE FUNCTION bump_vault_version();
+
+-- Touching the vault row is enough: the trigger above bumps its version
+CREATE OR REPLACE FUNCTION touch_vault_version() RETURNS TRIGGER AS $$
+DECLARE
+    row_vault_id UUID;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        row_vault_id := OLD.vault_id;
+    ELSE
+        row_vault_id := NEW.vault_id;
+    END IF;
+
+    IF TG_TABLE_NAME = 'transaction_records' THEN
+        -- Only deposits show on the read endpoints, as pending credit
+        IF (TG_OP = 'DELETE' AND OLD.operation_type <> 'deposit')
+           OR (TG_OP <> 'DELETE' AND NEW.operation_type <> 'deposit') THEN
+            RETURN NULL;
+        END IF;
+    END IF;
+
+    UPDATE vaults SET version = version WHERE id = row_vault_id;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS balance_holds_touch_vault_version ON balance_holds;
+CREATE TRIGGER balance_holds_touch_vault_version
+    AFTER INSERT OR UPDATE OF status, amount OR DELETE ON balance_holds
+    FOR EACH ROW EXECUTE FUNCTION touch_vault_version();
+
+DROP TRIGGER IF EXISTS transaction_records_touch_vault_version ON transaction_records;
+CREATE TRIGGER transaction_records_touch_vault_version
+    AFTER INSERT OR UPDATE OF status, amount OR DELETE ON transaction_records
+    FOR EACH ROW EXECUTE FUNCTION touch_vault_version();
@@ -0,0 +1,37 @@
+-- Incident runbooks: operator sequences such as pause, drain, reconcile and
+-- report, run a step at a time by a background worker. Each step's outcome is
+-- kept, so a run picks up where it stopped after a restart and a failed run
+-- resumes at the step that failed.
+CREATE TABLE IF NOT EXISTS incident_runs (
+    id UUID PRIMARY KEY,
+    runbook TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'running'
+        CHECK (status IN ('running', 'failed', 'completed', 'aborted')),
+    -- Vaults to reconcile and resync; every active vault when NULL
+    vault_ids UUID[],
+    -- Read-only window opened by the pause step, or taken over from the run this one follows
+    maintenance_window_id UUID REFERENCES maintenance_windows (id),
+    follows UUID REFERENCES incident_runs (id),
+    requested_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_incident_runs_running ON incident_runs (created_at) WHERE status = 'running';
+
+CREATE TABLE IF NOT EXISTS incident_steps (
+    run_id UUID NOT NULL REFERENCES incident_runs (id),
+    position INTEGER NOT NULL,
+    step TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'running', 'completed', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    -- What the step did, or how far it got while it is still running
+    output JSONB,
+    error_message TEXT,
+    started_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ,
+    PRIMARY KEY (run_id, position)
+);
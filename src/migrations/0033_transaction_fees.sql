@@ -0,0 +1,17 @@
+-- Send attempts and realized fees of transactions the backend submitted and
+-- paid for. `fee_recorded_at` is set once the fee is read from the finalized
+-- transaction; records of one transaction (both legs of a transfer) share it.
+ALTER TABLE transaction_records
+    ADD COLUMN IF NOT EXISTS submission_attempts INTEGER,
+    ADD COLUMN IF NOT EXISTS fee_lamports BIGINT,
+    ADD COLUMN IF NOT EXISTS priority_fee_lamports BIGINT,
+    ADD COLUMN IF NOT EXISTS compute_units_consumed BIGINT,
+    ADD COLUMN IF NOT EXISTS fee_recorded_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_transaction_records_fee_pending
+    ON transaction_records (created_at)
+    WHERE submission_attempts IS NOT NULL AND fee_recorded_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_transaction_records_fee_recorded
+    ON transaction_records (created_at)
+    WHERE fee_recorded_at IS NOT NULL;
@@ -0,0 +1,120 @@
+-- Append-only journal of the externally visible state: vaults and the mint
+-- configuration (mints, withdrawal fee tiers and promotions). Every committed
+-- insert, update and delete of those tables appends the row's image after the
+-- change (NULL for a delete), so replaying the journal in `lsn` order rebuilds
+-- them exactly.
+--
+-- Rows are journaled by deferred constraint triggers, which run at commit.
+-- LSNs come from the single counter row below, locked from a transaction's
+-- first journaled change until it commits: LSNs are gap-free and increase in
+-- commit order, and a journal read at any point holds every change up to its
+-- last LSN. Locking it only at commit, after any `event_outbox_head` lock the
+-- transaction took while running, keeps the two counters from deadlocking.
+CREATE TABLE IF NOT EXISTS state_journal_head (
+    id SMALLINT PRIMARY KEY CHECK (id = 1),
+    lsn BIGINT NOT NULL
+);
+
+INSERT INTO state_journal_head (id, lsn) VALUES (1, 0) ON CONFLICT (id) DO NOTHING;
+
+CREATE TABLE IF NOT EXISTS state_journal (
+    lsn BIGINT PRIMARY KEY,
+    -- Table the row belongs to
+    entity TEXT NOT NULL,
+    -- The row's primary key; key columns joined with ':'
+    entity_key TEXT NOT NULL,
+    operation TEXT NOT NULL CHECK (operation IN ('insert', 'update', 'delete')),
+    state JSONB,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK ((operation = 'delete') = (state IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS idx_state_journal_entity ON state_journal (entity, entity_key, lsn);
+
+CREATE OR REPLACE FUNCTION reject_state_journal_rewrite() RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'state_journal is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS state_journal_append_only ON state_journal;
+CREATE TRIGGER state_journal_append_only
+    BEFORE UPDATE OR DELETE ON state_journal
+    FOR EACH ROW EXECUTE FUNCTION reject_state_journal_rewrite();
+
+DROP TRIGGER IF EXISTS state_journal_no_truncate ON state_journal;
+CREATE TRIGGER state_journal_no_truncate
+    BEFORE TRUNCATE ON state_journal
+    FOR EACH STATEMENT EXECUTE FUNCTION reject_state_journal_rewrite();
+
+-- Trigger arguments name the table's key columns. Row images render
+-- timestamps in UTC whatever the writing session's time zone, so images of
+-- the same row compare equal.
+CREATE OR REPLACE FUNCTION journal_row_change() RETURNS TRIGGER
+SET TimeZone = 'UTC'
+AS $$
+DECLARE
+    row_state JSONB;
+    row_key TEXT;
+    next_lsn BIGINT;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        SELECT string_agg(to_jsonb(OLD) ->> key_column, ':' ORDER BY position) INTO row_key
+        FROM unnest(TG_ARGV) WITH ORDINALITY AS keys(key_column, position);
+    ELSE
+        row_state := to_jsonb(NEW);
+        IF TG_OP = 'UPDATE' AND row_state = to_jsonb(OLD) THEN
+            RETURN NULL;
+        END IF;
+        SELECT string_agg(row_state ->> key_column, ':' ORDER BY position) INTO row_key
+        FROM unnest(TG_ARGV) WITH ORDINALITY AS keys(key_column, position);
+    END IF;
+
+    UPDATE state_journal_head SET lsn = lsn + 1 WHERE id = 1 RETURNING lsn INTO next_lsn;
+    INSERT INTO state_journal (lsn, entity, entity_key, operation, state)
+    VALUES (next_lsn, TG_TABLE_NAME, row_key, lower(TG_OP), row_state);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS vaults_journal ON vaults;
+CREATE CONSTRAINT TRIGGER vaults_journal
+    AFTER INSERT OR UPDATE OR DELETE ON vaults
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW EXECUTE FUNCTION journal_row_change('id');
+
+DROP TRIGGER IF EXISTS mints_journal ON mints;
+CREATE CONSTRAINT TRIGGER mints_journal
+    AFTER INSERT OR UPDATE OR DELETE ON mints
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW EXECUTE FUNCTION journal_row_change('mint_pubkey');
+
+DROP TRIGGER IF EXISTS withdrawal_fee_tiers_journal ON withdrawal_fee_tiers;
+CREATE CONSTRAINT TRIGGER withdrawal_fee_tiers_journal
+    AFTER INSERT OR UPDATE OR DELETE ON withdrawal_fee_tiers
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW EXECUTE FUNCTION journal_row_change('mint_pubkey', 'min_amount');
+
+DROP TRIGGER IF EXISTS withdrawal_fee_promotions_journal ON withdrawal_fee_promotions;
+CREATE CONSTRAINT TRIGGER withdrawal_fee_promotions_journal
+    AFTER INSERT OR UPDATE OR DELETE ON withdrawal_fee_promotions
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW EXECUTE FUNCTION journal_row_change('id');
+
+-- The journal starts from the rows that exist now, as inserts
+SET LOCAL TimeZone = 'UTC';
+
+INSERT INTO state_journal (lsn, entity, entity_key, operation, state)
+SELECT row_number() OVER (ORDER BY position, entity_key), entity, entity_key, 'insert', state
+FROM (
+    SELECT 1 AS position, 'mints' AS entity, m.mint_pubkey AS entity_key, to_jsonb(m) AS state FROM mints m
+    UNION ALL
+    SELECT 2, 'withdrawal_fee_tiers', t.mint_pubkey || ':' || t.min_amount, to_jsonb(t) FROM withdrawal_fee_tiers t
+    UNION ALL
+    SELECT 3, 'withdrawal_fee_promotions', p.id::TEXT, to_jsonb(p) FROM withdrawal_fee_promotions p
+    UNION ALL
+    SELECT 4, 'vaults', v.id::TEXT, to_jsonb(v) FROM vaults v
+) baseline
+WHERE NOT EXISTS (SELECT 1 FROM state_journal);
+
+UPDATE state_journal_head SET lsn = (SELECT COALESCE(MAX(lsn), 0) FROM state_journal) WHERE id = 1;
@@ -0,0 +1,264 @@
+//! Rate limiter analytics, temporary bans and automatic escalation.
+//!
+//! Every rate-limited request is counted per client and minute by the same
+//! statement that consumes its tokens (see `RateLimitRepository::consume_tokens`).
+//! Clients are keyed `key:<hash prefix>` by bearer token and `ip:<address>`
+//! otherwise, so the usage tables and admin endpoints never hold raw tokens.
+//!
+//! Operators read the heaviest clients, recent 429s and clients showing
+//! abusive patterns, and can ban a client for a fixed time. Escalation rules
+//! ban clients rejected too often within a window; repeat offenders get
+//! longer bans. A banned client is answered 429 until the ban ends.
+
+use crate::database::RateLimitRepository;
+use crate::error::{Result, DomainError};
+use crate::models::{ClientUsage, EscalationRule, RateLimitBan, SupportCredential};
+use crate::support::hash_token;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+pub const RATE_LIMIT_BAN_CREATED_EVENT: &str = "rate_limit_ban_created";
+pub const RATE_LIMIT_BAN_LIFTED_EVENT: &str = "rate_limit_ban_lifted";
+pub const RATE_LIMIT_RULE_SAVED_EVENT: &str = "rate_limit_rule_saved";
+pub const RATE_LIMIT_BAN_ESCALATED_EVENT: &str = "rate_limit_ban_escalated";
+
+/// Hex characters of the token hash kept in a client id
+const KEY_HASH_PREFIX_LEN: usize = 16;
+
+/// Usage windows are kept this long; analytics can look back no further
+pub const USAGE_RETENTION_DAYS: i64 = 7;
+
+const MIN_BAN_SECONDS: i64 = 60;
+const MAX_BAN_SECONDS: i64 = 30 * 24 * 3600;
+
+/// Usage rows scanned for abusive patterns
+const ABUSE_SCAN_LIMIT: i64 = 1000;
+
+/// Minutes with 429s before traffic counts as sustained limiting
+const SUSTAINED_LIMITED_MINUTES: i64 = 5;
+
+/// 429s before the rejection share of a client's traffic is considered
+const MIN_REJECTIONS_FOR_SHARE: i64 = 100;
+
+/// Rate limiter key of a request: its bearer token, else the first address
+/// in `X-Forwarded-For` as set by the load balancer
+pub fn client_identifier(bearer: Option<&str>, forwarded_for: Option<&str>) -> String {
+    if let Some(token) = bearer.filter(|token| !token.is_empty()) {
+        return format!("key:{}", &hash_token(token)[..KEY_HASH_PREFIX_LEN]);
+    }
+    match forwarded_for.and_then(|header| header.split(',').next()).map(str::trim).filter(|ip| !ip.is_empty()) {
+        Some(ip) => format!("ip:{}", ip),
+        None => "ip:unknown".to_string(),
+    }
+}
+
+/// Client ids are what `client_identifier` produces
+pub fn validate_client_id(client_id: &str) -> Result<()> {
+    let valid = match client_id.split_once(':') {
+        Some(("key", hash)) => hash.len() == KEY_HASH_PREFIX_LEN && hash.chars().all(|c| c.is_ascii_hexdigit()),
+        Some(("ip", address)) => !address.is_empty() && address.len() <= 64,
+        _ => false,
+    };
+    if !valid {
+        return Err(DomainError::Validation(format!("Invalid client id {}", client_id)).into());
+    }
+    Ok(())
+}
+
+/// Traffic shapes worth an operator's attention
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AbusePattern {
+    /// 429s in many separate minutes: the client ignores the limit rather than bursting once
+    SustainedLimiting,
+    /// At least half of the client's requests were refused
+    MostlyRejected,
+    /// The client kept sending while banned
+    PersistsWhileBanned,
+    /// Banned more than once in the last week
+    RepeatOffender,
+}
+
+/// Patterns in one client's usage
+pub fn abuse_patterns(usage: &ClientUsage) -> Vec<AbusePattern> {
+    let refused = usage.rejected + usage.banned;
+    let mut patterns = Vec::new();
+    if usage.limited_minutes >= SUSTAINED_LIMITED_MINUTES {
+        patterns.push(AbusePattern::SustainedLimiting);
+    }
+    if refused >= MIN_REJECTIONS_FOR_SHARE && refused * 2 >= usage.requests {
+        patterns.push(AbusePattern::MostlyRejected);
+    }
+    if usage.banned >= MIN_REJECTIONS_FOR_SHARE {
+        patterns.push(AbusePattern::PersistsWhileBanned);
+    }
+    if usage.recent_bans >= 2 {
+        patterns.push(AbusePattern::RepeatOffender);
+    }
+    patterns
+}
+
+/// A client with the patterns it shows and any ban in force
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AbuseReport {
+    #[serde(flatten)]
+    pub usage: ClientUsage,
+    pub patterns: Vec<AbusePattern>,
+    pub active_ban: Option<RateLimitBan>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BanRequest {
+    pub client_id: String,
+    pub duration_seconds: i64,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EscalationRuleRequest {
+    pub name: String,
+    pub min_rejections: i64,
+    pub window_seconds: i32,
+    pub ban_seconds: i32,
+    /// Defaults to true
+    pub enabled: Option<bool>,
+}
+
+/// Analytics, bans and escalation on top of the rate limiter
+pub struct AbuseGuard {
+    repo: RateLimitRepository,
+}
+
+impl AbuseGuard {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { repo: RateLimitRepository::new(pool) }
+    }
+
+    /// Heaviest clients over the last `window`
+    pub async fn top_clients(&self, window: Duration, limit: i64) -> Result<Vec<ClientUsage>> {
+        self.repo.get_client_usage(usage_since(window), false, limit).await
+    }
+
+    /// Clients answered 429 over the last `window`, most refused first
+    pub async fn recent_rejections(&self, window: Duration, limit: i64) -> Result<Vec<ClientUsage>> {
+        self.repo.get_client_usage(usage_since(window), true, limit).await
+    }
+
+    /// Clients refused over the last `window` that show at least one abusive pattern
+    pub async fn abuse_reports(&self, window: Duration) -> Result<Vec<AbuseReport>> {
+        let active_bans = self.repo.get_bans(None, true, ABUSE_SCAN_LIMIT).await?;
+        Ok(self.repo.get_client_usage(usage_since(window), true, ABUSE_SCAN_LIMIT).await?
+            .into_iter()
+            .filter_map(|usage| {
+                let patterns = abuse_patterns(&usage);
+                (!patterns.is_empty()).then(|| AbuseReport {
+                    active_ban: active_bans.iter().find(|b| b.client_id == usage.client_id).cloned(),
+                    usage,
+                    patterns,
+                })
+            })
+            .collect())
+    }
+
+    pub async fn bans(&self, client_id: Option<&str>, active_only: bool, limit: i64) -> Result<Vec<RateLimitBan>> {
+        self.repo.get_bans(client_id, active_only, limit).await
+    }
+
+    /// Ban a client for a fixed time
+    pub async fn ban(&self, actor: &SupportCredential, request: BanRequest) -> Result<RateLimitBan> {
+        validate_client_id(&request.client_id)?;
+        if !(MIN_BAN_SECONDS..=MAX_BAN_SECONDS).contains(&request.duration_seconds) {
+            return Err(DomainError::Validation(format!(
+                "Ban duration must be between {} and {} seconds", MIN_BAN_SECONDS, MAX_BAN_SECONDS,
+            )).into());
+        }
+        if request.reason.trim().is_empty() {
+            return Err(DomainError::Validation("A ban needs a reason".to_string()).into());
+        }
+
+        let expires_at = Utc::now() + Duration::seconds(request.duration_seconds);
+        let ban = self.repo
+            .create_ban(&request.client_id, request.reason.trim(), &actor.name, expires_at, RATE_LIMIT_BAN_CREATED_EVENT)
+            .await?;
+        info!("{} banned rate limit client {} until {}: {}", actor.name, ban.client_id, ban.expires_at, ban.reason);
+        Ok(ban)
+    }
+
+    /// End a ban early
+    pub async fn lift(&self, actor: &SupportCredential, ban_id: Uuid) -> Result<RateLimitBan> {
+        match self.repo.lift_ban(ban_id, &actor.name, RATE_LIMIT_BAN_LIFTED_EVENT).await? {
+            Some(ban) => {
+                info!("{} lifted ban {} of rate limit client {}", actor.name, ban.id, ban.client_id);
+                Ok(ban)
+            }
+            None => {
+                let ban = self.repo.get_ban(ban_id).await?;
+                Err(DomainError::Validation(format!("Ban {} of {} is no longer in force", ban.id, ban.client_id)).into())
+            }
+        }
+    }
+
+    pub async fn rules(&self) -> Result<Vec<EscalationRule>> {
+        self.repo.get_rules().await
+    }
+
+    /// Create or replace the rule with the request's name
+    pub async fn save_rule(&self, actor: &SupportCredential, request: EscalationRuleRequest) -> Result<EscalationRule> {
+        let name = request.name.trim();
+        if name.is_empty() || name.len() > 64 {
+            return Err(DomainError::Validation("Rule name must be 1-64 characters".to_string()).into());
+        }
+        if request.min_rejections <= 0 || request.window_seconds <= 0 {
+            return Err(DomainError::Validation("min_rejections and window_seconds must be positive".to_string()).into());
+        }
+        if !(MIN_BAN_SECONDS..=MAX_BAN_SECONDS).contains(&(request.ban_seconds as i64)) {
+            return Err(DomainError::Validation(format!(
+                "ban_seconds must be between {} and {}", MIN_BAN_SECONDS, MAX_BAN_SECONDS,
+            )).into());
+        }
+        if request.window_seconds as i64 > USAGE_RETENTION_DAYS * 24 * 3600 {
+            return Err(DomainError::Validation("window_seconds exceeds usage retention".to_string()).into());
+        }
+
+        let rule = self.repo
+            .upsert_rule(
+                name,
+                request.min_rejections,
+                request.window_seconds,
+                request.ban_seconds,
+                request.enabled.unwrap_or(true),
+                &actor.name,
+                RATE_LIMIT_RULE_SAVED_EVENT,
+            )
+            .await?;
+        info!("{} saved escalation rule {} ({} rejections in {}s -> {}s ban, enabled: {})",
+              actor.name, rule.name, rule.min_rejections, rule.window_seconds, rule.ban_seconds, rule.enabled);
+        Ok(rule)
+    }
+
+    /// Apply every enabled rule; returns the bans placed
+    pub async fn escalate(&self) -> Result<Vec<RateLimitBan>> {
+        let mut placed = Vec::new();
+        for rule in self.repo.get_rules().await?.into_iter().filter(|r| r.enabled) {
+            let reason = format!("Escalation rule {}: {} or more 429s within {}s",
+                                 rule.name, rule.min_rejections, rule.window_seconds);
+            let bans = self.repo.apply_rule(&rule, &reason, RATE_LIMIT_BAN_ESCALATED_EVENT).await?;
+            for ban in &bans {
+                warn!("Rate limit client {} banned until {} by rule {}", ban.client_id, ban.expires_at, rule.name);
+            }
+            placed.extend(bans);
+        }
+        Ok(placed)
+    }
+
+    /// Delete usage past retention
+    pub async fn prune(&self, now: DateTime<Utc>) -> Result<u64> {
+        self.repo.prune_usage(now - Duration::days(USAGE_RETENTION_DAYS)).await
+    }
+}
+
+fn usage_since(window: Duration) -> DateTime<Utc> {
+    Utc::now() - window.min(Duration::days(USAGE_RETENTION_DAYS))
+}
@@ -0,0 +1,261 @@
+//! Watchlist alerts vault owners set on their own vault.
+//!
+//! Rules are evaluated by the monitor against the event stream: balance
+//! updates decide `balance_below` and `lock_utilization_above`, new withdrawal
+//! records fire `any_withdrawal`. Threshold rules fire once when their
+//! condition starts to hold and re-arm when it stops, so a vault sitting below
+//! its threshold is not alerted on every change. Whether a condition held is
+//! stored with the rule and survives restarts.
+//!
+//! A firing is written to the outbox as a `vault_alert_triggered` event in the
+//! same statement that records it, so notifications reach every consumer that
+//! delivers them (see `crate::outbox`).
+
+use crate::database::AlertRuleRepository;
+use crate::error::{Result, DomainError};
+use crate::models::{BalanceUpdate, TransactionRecord, TransactionStatus, TransactionType, VaultAlertRule};
+use crate::stream::{Channel, EventStream, StreamEvent};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{info, warn, error};
+use uuid::Uuid;
+
+pub const VAULT_ALERT_TRIGGERED_EVENT: &str = "vault_alert_triggered";
+pub const VAULT_ALERT_RULE_CHANGED_EVENT: &str = "vault_alert_rule_changed";
+
+pub const MAX_ALERT_RULES_PER_VAULT: i64 = 20;
+
+const MAX_LABEL_LENGTH: usize = 100;
+
+/// Stream events evaluated per read
+const EVALUATION_BATCH_SIZE: usize = 500;
+
+/// What a rule watches for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// Total balance under `amount` base units
+    BalanceBelow { amount: i64 },
+    /// Locked share of the total balance over `percent`
+    LockUtilizationAbove { percent: i64 },
+    AnyWithdrawal,
+}
+
+impl AlertCondition {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AlertCondition::BalanceBelow { .. } => "balance_below",
+            AlertCondition::LockUtilizationAbove { .. } => "lock_utilization_above",
+            AlertCondition::AnyWithdrawal => "any_withdrawal",
+        }
+    }
+
+    pub fn threshold(&self) -> Option<i64> {
+        match self {
+            AlertCondition::BalanceBelow { amount } => Some(*amount),
+            AlertCondition::LockUtilizationAbove { percent } => Some(*percent),
+            AlertCondition::AnyWithdrawal => None,
+        }
+    }
+
+    /// Condition of a stored rule
+    pub fn from_rule(rule: &VaultAlertRule) -> Option<Self> {
+        match (rule.kind.as_str(), rule.threshold) {
+            ("balance_below", Some(amount)) => Some(AlertCondition::BalanceBelow { amount }),
+            ("lock_utilization_above", Some(percent)) => Some(AlertCondition::LockUtilizationAbove { percent }),
+            ("any_withdrawal", _) => Some(AlertCondition::AnyWithdrawal),
+            _ => None,
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            AlertCondition::BalanceBelow { amount } if *amount <= 0 => {
+                Err(DomainError::Validation("amount must be positive".to_string()).into())
+            }
+            AlertCondition::LockUtilizationAbove { percent } if !(0..100).contains(percent) => {
+                Err(DomainError::Validation("percent must be between 0 and 99".to_string()).into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the condition holds for these balances; `None` when balances don't decide it
+    pub fn holds_for_balances(&self, total_balance: i64, locked_balance: i64) -> Option<bool> {
+        match self {
+            AlertCondition::BalanceBelow { amount } => Some(total_balance < *amount),
+            AlertCondition::LockUtilizationAbove { percent } => {
+                Some(total_balance > 0 && locked_balance as i128 * 100 > *percent as i128 * total_balance as i128)
+            }
+            AlertCondition::AnyWithdrawal => None,
+        }
+    }
+}
+
+/// Withdrawals are alerted on once, when their record is created
+pub fn is_new_withdrawal(record: &TransactionRecord) -> bool {
+    matches!(record.transaction_type, TransactionType::Withdraw) && matches!(record.status, TransactionStatus::Pending)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRuleRequest {
+    #[serde(flatten)]
+    pub condition: AlertCondition,
+    pub label: Option<String>,
+    /// Defaults to true
+    pub enabled: Option<bool>,
+}
+
+impl AlertRuleRequest {
+    fn validated_label(&self) -> Result<Option<&str>> {
+        let label = self.label.as_deref().map(str::trim).filter(|l| !l.is_empty());
+        if label.map_or(false, |l| l.chars().count() > MAX_LABEL_LENGTH) {
+            return Err(DomainError::Validation(format!("Label must be at most {} characters", MAX_LABEL_LENGTH)).into());
+        }
+        Ok(label)
+    }
+}
+
+/// Manages vault alert rules and evaluates them against the event stream
+pub struct VaultAlerts {
+    repo: AlertRuleRepository,
+}
+
+impl VaultAlerts {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { repo: AlertRuleRepository::new(pool) }
+    }
+
+    pub async fn rules(&self, vault_id: Uuid) -> Result<Vec<VaultAlertRule>> {
+        self.repo.get_rules(vault_id, false).await
+    }
+
+    pub async fn create(&self, vault_id: Uuid, request: AlertRuleRequest) -> Result<VaultAlertRule> {
+        request.condition.validate()?;
+        let label = request.validated_label()?;
+        let rule = self.repo
+            .create_rule(
+                vault_id,
+                request.condition.kind(),
+                request.condition.threshold(),
+                label,
+                request.enabled.unwrap_or(true),
+                MAX_ALERT_RULES_PER_VAULT,
+                VAULT_ALERT_RULE_CHANGED_EVENT,
+            )
+            .await?
+            .ok_or_else(|| DomainError::QuotaExceeded(format!(
+                "Vault {} already has {} alert rules", vault_id, MAX_ALERT_RULES_PER_VAULT,
+            )))?;
+        info!("Alert rule {} ({}) created for vault {}", rule.id, rule.kind, vault_id);
+        Ok(rule)
+    }
+
+    /// Replace a rule's threshold, label and enabled flag; its kind cannot change
+    pub async fn update(&self, vault_id: Uuid, rule_id: Uuid, request: AlertRuleRequest) -> Result<VaultAlertRule> {
+        request.condition.validate()?;
+        let label = request.validated_label()?;
+        let existing = self.repo.get_rules(vault_id, false).await?
+            .into_iter()
+            .find(|r| r.id == rule_id)
+            .ok_or_else(|| DomainError::NotFound(format!("Alert rule {}", rule_id)))?;
+        if existing.kind != request.condition.kind() {
+            return Err(DomainError::Validation(format!(
+                "Rule {} is {}; delete it and create a {} rule instead", rule_id, existing.kind, request.condition.kind(),
+            )).into());
+        }
+
+        self.repo.update_rule(
+            vault_id,
+            rule_id,
+            request.condition.threshold(),
+            label,
+            request.enabled.unwrap_or(true),
+            VAULT_ALERT_RULE_CHANGED_EVENT,
+        ).await
+    }
+
+    pub async fn delete(&self, vault_id: Uuid, rule_id: Uuid) -> Result<()> {
+        if !self.repo.delete_rule(vault_id, rule_id, VAULT_ALERT_RULE_CHANGED_EVENT).await? {
+            return Err(DomainError::NotFound(format!("Alert rule {}", rule_id)).into());
+        }
+        Ok(())
+    }
+
+    /// Evaluate the vault's rules against one stream event; returns the rules that fired
+    pub async fn evaluate(&self, event: &StreamEvent) -> Result<Vec<VaultAlertRule>> {
+        match event {
+            StreamEvent::BalanceUpdated(update) => self.evaluate_balances(update).await,
+            StreamEvent::TransactionUpdated(record) if is_new_withdrawal(record) => self.evaluate_withdrawal(record).await,
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    async fn evaluate_balances(&self, update: &BalanceUpdate) -> Result<Vec<VaultAlertRule>> {
+        let mut fired = Vec::new();
+        for rule in self.repo.get_rules(update.vault_id, true).await? {
+            let holds = AlertCondition::from_rule(&rule)
+                .and_then(|condition| condition.holds_for_balances(update.total_balance, update.locked_balance));
+            // Only transitions are written
+            let Some(holds) = holds.filter(|holds| *holds != rule.triggered) else {
+                continue;
+            };
+            let observed = serde_json::json!({
+                "total_balance": update.total_balance,
+                "locked_balance": update.locked_balance,
+                "available_balance": update.available_balance,
+                "as_of": update.as_of,
+            });
+            if let Some(rule) = self.repo.record_evaluation(rule.id, holds, false, observed, VAULT_ALERT_TRIGGERED_EVENT).await? {
+                info!("Alert rule {} ({}) fired for vault {}", rule.id, rule.kind, rule.vault_id);
+                fired.push(rule);
+            }
+        }
+        Ok(fired)
+    }
+
+    async fn evaluate_withdrawal(&self, record: &TransactionRecord) -> Result<Vec<VaultAlertRule>> {
+        let mut fired = Vec::new();
+        for rule in self.repo.get_rules(record.vault_id, true).await?.into_iter().filter(|r| r.kind == "any_withdrawal") {
+            let observed = serde_json::json!({
+                "transaction_id": record.id,
+                "amount": record.amount,
+                "created_at": record.created_at,
+            });
+            if let Some(rule) = self.repo.record_evaluation(rule.id, true, true, observed, VAULT_ALERT_TRIGGERED_EVENT).await? {
+                info!("Alert rule {} fired for withdrawal {} from vault {}", rule.id, record.id, rule.vault_id);
+                fired.push(rule);
+            }
+        }
+        Ok(fired)
+    }
+
+    /// Evaluate every balance and transaction event published from now on
+    pub async fn run_stream_evaluator(&self, stream: Arc<EventStream>) {
+        let mut head = stream.watch_head();
+        let mut cursor = stream.head();
+        loop {
+            match stream.channel_events_after(cursor, &[Channel::Balances, Channel::Transactions], EVALUATION_BATCH_SIZE).await {
+                Ok((events, next_cursor)) => {
+                    for event in &events {
+                        if let Err(e) = self.evaluate(&event.event).await {
+                            error!("Failed to evaluate alert rules for stream event {}: {}", event.seq, e);
+                        }
+                    }
+                    cursor = next_cursor;
+                }
+                Err(gap) => {
+                    warn!("Alert evaluation fell behind the event stream; skipping to {}", gap.oldest_buffered);
+                    cursor = gap.oldest_buffered - 1;
+                    continue;
+                }
+            }
+            if cursor < stream.head() {
+                continue;
+            }
+            if head.changed().await.is_err() {
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! Vault activity analytics.
+//!
+//! Confirmed transaction records are rolled up per vault, hour and operation
+//! into `vault_activity_hourly` by a monitor task; queries read only the
+//! rollup, so they stay cheap however large the ledger grows. Activity newer
+//! than `rolled_up_through` shows up after the next refresh.
+
+use crate::error::{Result, DomainError};
+use crate::models::{ActivityGranularity, ActivityBucketRow, VaultActivityVolume, ActivityRollupRefresh};
+use crate::database::ActivityRepository;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::info;
+
+/// Operation types (as stored on transaction records) reported by the activity endpoints
+pub const TRACKED_OPERATIONS: [&str; 3] = ["deposit", "withdraw", "lock"];
+
+/// Default and maximum number of top vaults returned
+pub const DEFAULT_TOP_VAULTS: i32 = 10;
+pub const MAX_TOP_VAULTS: i32 = 100;
+
+/// Longest period a single query may cover, per granularity
+const MAX_HOURLY_RANGE_DAYS: i64 = 31;
+const MAX_DAILY_RANGE_DAYS: i64 = 366;
+
+/// Count, volume (base units) and average ticket for one operation type
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OperationActivity {
+    pub count: i64,
+    pub volume: i64,
+    pub average_ticket: Option<f64>,
+}
+
+impl OperationActivity {
+    pub fn new(count: i64, volume: i64) -> Self {
+        Self {
+            count,
+            volume,
+            average_ticket: (count > 0).then(|| volume as f64 / count as f64),
+        }
+    }
+}
+
+/// Activity within one hour or day
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ActivitySummary {
+    pub deposits: OperationActivity,
+    pub withdrawals: OperationActivity,
+    pub locks: OperationActivity,
+    /// Distinct vaults with any tracked activity
+    pub active_vaults: i64,
+    /// Across all tracked operations
+    pub average_ticket_size: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActivityBucket {
+    pub bucket_start: DateTime<Utc>,
+    #[serde(flatten)]
+    pub activity: ActivitySummary,
+}
+
+/// Response of `GET /analytics/activity`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityReport {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub granularity: ActivityGranularity,
+    /// One entry per bucket in the period, including empty ones
+    pub buckets: Vec<ActivityBucket>,
+    pub totals: ActivitySummary,
+    pub top_vaults: Vec<VaultActivityVolume>,
+    /// Ledger changes after this point are not yet reflected
+    pub rolled_up_through: Option<DateTime<Utc>>,
+}
+
+/// Start of the bucket containing `at` (UTC)
+pub fn bucket_floor(at: DateTime<Utc>, granularity: ActivityGranularity) -> DateTime<Utc> {
+    let width = granularity.duration().num_seconds();
+    let seconds = at.timestamp().div_euclid(width) * width;
+    Utc.timestamp_opt(seconds, 0).single().unwrap_or(at)
+}
+
+/// Fold rollup rows of one bucket (per-operation rows plus the all-operations row) into a summary
+pub fn summarize(rows: &[&ActivityBucketRow]) -> ActivitySummary {
+    let mut summary = ActivitySummary::default();
+    let (mut count, mut volume) = (0, 0);
+
+    for row in rows {
+        let activity = OperationActivity::new(row.tx_count, row.volume);
+        match row.operation_type.as_deref() {
+            Some("deposit") => summary.deposits = activity,
+            Some("withdraw") => summary.withdrawals = activity,
+            Some("lock") => summary.locks = activity,
+            Some(_) => {}
+            None => {
+                summary.active_vaults = row.active_vaults;
+                count = row.tx_count;
+                volume = row.volume;
+            }
+        }
+    }
+
+    summary.average_ticket_size = OperationActivity::new(count, volume).average_ticket;
+    summary
+}
+
+/// One bucket per step of `[from, to)`, empty where the rollup has no rows
+pub fn fill_buckets(
+    rows: &[ActivityBucketRow],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    granularity: ActivityGranularity,
+) -> Vec<ActivityBucket> {
+    let mut by_bucket: HashMap<DateTime<Utc>, Vec<&ActivityBucketRow>> = HashMap::new();
+    for row in rows {
+        by_bucket.entry(row.bucket_start).or_default().push(row);
+    }
+
+    let mut buckets = Vec::new();
+    let mut bucket_start = bucket_floor(from, granularity);
+    while bucket_start < to {
+        let activity = by_bucket
+            .get(&bucket_start)
+            .map(|rows| summarize(rows))
+            .unwrap_or_default();
+        buckets.push(ActivityBucket { bucket_start, activity });
+        bucket_start = bucket_start + granularity.duration();
+    }
+
+    buckets
+}
+
+/// Serves activity analytics from the rollup and keeps the rollup current
+pub struct ActivityAnalytics {
+    activity_repo: ActivityRepository,
+}
+
+impl ActivityAnalytics {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self {
+            activity_repo: ActivityRepository::new(pool),
+        }
+    }
+
+    /// Fold ledger changes since the last refresh into the rollup
+    pub async fn refresh_rollups(&self) -> Result<ActivityRollupRefresh> {
+        let refresh = self.activity_repo.refresh_rollups().await?;
+        if refresh.buckets_refreshed > 0 {
+            info!("Activity rollup refreshed {} hourly buckets ({} rows)", refresh.buckets_refreshed, refresh.rows_written);
+        }
+        Ok(refresh)
+    }
+
+    /// Activity over `[from, to)`, aligned outward to whole buckets.
+    ///
+    /// Defaults to the last day for hourly buckets and the last 30 days for daily ones.
+    pub async fn activity(
+        &self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        granularity: ActivityGranularity,
+        top: Option<i32>,
+    ) -> Result<ActivityReport> {
+        let to = to.unwrap_or_else(Utc::now);
+        let from = from.unwrap_or_else(|| match granularity {
+            ActivityGranularity::Hour => to - Duration::days(1),
+            ActivityGranularity::Day => to - Duration::days(30),
+        });
+        if from >= to {
+            return Err(DomainError::Validation("`from` must be before `to`".to_string()).into());
+        }
+
+        let max_range = match granularity {
+            ActivityGranularity::Hour => Duration::days(MAX_HOURLY_RANGE_DAYS),
+            ActivityGranularity::Day => Duration::days(MAX_DAILY_RANGE_DAYS),
+        };
+        if to - from > max_range {
+            return Err(DomainError::Validation(format!(
+                "Period too long for {} buckets; at most {} days",
+                granularity.as_str(),
+                max_range.num_days()
+            )).into());
+        }
+
+        let top = top.unwrap_or(DEFAULT_TOP_VAULTS).clamp(1, MAX_TOP_VAULTS);
+        let from = bucket_floor(from, granularity);
+        let to = match bucket_floor(to, granularity) {
+            floor if floor == to => to,
+            floor => floor + granularity.duration(),
+        };
+        let operations: Vec<String> = TRACKED_OPERATIONS.iter().map(|op| op.to_string()).collect();
+
+        let rows = self.activity_repo.get_activity_buckets(granularity.as_str(), from, to, &operations).await?;
+        let totals = self.activity_repo.get_activity_totals(from, to, &operations).await?;
+        let top_vaults = self.activity_repo.get_top_vaults_by_volume(from, to, &operations, top).await?;
+        let rolled_up_through = self.activity_repo.get_rolled_up_through().await?;
+
+        Ok(ActivityReport {
+            from,
+            to,
+            granularity,
+            buckets: fill_buckets(&rows, from, to, granularity),
+            totals: summarize(&totals.iter().collect::<Vec<_>>()),
+            top_vaults,
+            rolled_up_through,
+        })
+    }
+}
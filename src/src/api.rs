@@ -3,7 +3,7 @@ use axum::{
     Router,
     extract::{Path, State, Json, Query},
     response::{Json as JsonResponse, Response},
-    http::StatusCode,
+    http::{header, StatusCode},
     middleware,
 };
 use axum::extract::ws::{WebSocket, WebSocketUpgrade};
@@ -11,12 +11,51 @@ use axum::response::IntoResponse;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use uuid::Uuid;
-use chrono::{DateTime, Utc};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use chrono::{DateTime, Datelike, Utc};
 use tracing::{info, warn, error};
 
 use crate::{
-    VaultManager, TransactionManager, BalanceTracker, CPIManager, VaultMonitor,
-    models::*, error::{Result, VaultError},
+    VaultManager, TransactionManager, BalanceTracker, CPIManager, VaultMonitor, MintRegistry,
+    mint_registry, derivation::{self, VaultDerivation}, latency::StageLatencySummary,
+    schema::{MigrationStatus, SchemaDrift, SchemaManager},
+    support::{self, SupportService, SupportVaultDetail},
+    cases::{self, CaseService},
+    multisig::{MultisigManager, MultisigWithdrawalRequest},
+    transaction_builder::{self, TransactionBuilder, SigningHints, NetworkFeeEstimate},
+    program_info::{ProgramInfo, ProgramIdl},
+    screening::{ScreeningService, ScreeningSubject, ScreeningDirection},
+    authority::{AuthorityRotationManager, AuthorityRotationProgress},
+    analytics::ActivityReport,
+    network_fees::FeeReport,
+    onchain_activity::{self, OnchainActivity},
+    rpc::RpcMethodClass,
+    tax::{self, TaxReport},
+    twab::{self, TwabReport},
+    cluster::ClusterConditions,
+    maintenance::{MaintenanceService, MaintenanceStatus},
+    bulk::{BulkOperation, BulkOperationManager, BulkJobPreview, BulkJobProgress},
+    incident::{IncidentManager, IncidentRunDetail, Runbook},
+    token_authority::PreparedTokenRevocation,
+    token_accounts::{self, TokenAccountRole},
+    outbox::{ConsumerStatus, EventBatch, RegisterConsumerRequest},
+    indexer::IndexerStatus,
+    jobs::JobStatus,
+    sla::SlaReport,
+    etag,
+    auto_lock::{AutoLockService, AutoLockRequest},
+    abuse::{self, AbuseReport, BanRequest, EscalationRuleRequest},
+    positions::UnlockGuard,
+    security_events::{self, NewSecurityEvent, SecurityEventKind, SecurityEventLog},
+    identities::{self, IdentityService, IdentityGate, VerificationLevel},
+    alerts::AlertRuleRequest,
+    exports::{ExportService, ExportRequest, ExportStatus},
+    idempotency::{self, IdempotencyService},
+    dormancy::{DormancyService, PreparedDormancySweep, PreparedDormancyReclaim},
+    fees::{self, FeeService, FeeQuote, FeeSchedule, FeeTierRequest, FeePromotionRequest},
+    stream::{EventStream, Subscription, Channel, ClientMessage, ServerMessage, PROTOCOL_VERSION, MAX_UNACKED_EVENTS},
+    reconciliation::{self, ReconciliationReport, LedgerReplay}, models::*, error::{DomainError, StorageError, ChainError, VaultError},
     database::RateLimitRepository,
 };
 
@@ -28,46 +67,183 @@ pub struct AppState {
     pub cpi_manager: Arc<CPIManager>,
     pub monitor: Arc<VaultMonitor>,
     pub rate_limit_repo: Arc<RateLimitRepository>,
+    pub mint_registry: Arc<MintRegistry>,
+    pub schema_manager: Arc<SchemaManager>,
+    pub support_service: Arc<SupportService>,
+    pub case_service: Arc<CaseService>,
+    pub multisig_manager: Arc<MultisigManager>,
+    pub authority_rotation: Arc<AuthorityRotationManager>,
+    pub event_stream: Arc<EventStream>,
+    pub maintenance: Arc<MaintenanceService>,
+    pub bulk_operations: Arc<BulkOperationManager>,
+    /// Runbook sequences run step by step through an incident
+    pub incidents: Arc<IncidentManager>,
+    /// Builds and refreshes unsigned transactions handed to clients
+    pub transaction_builder: Arc<TransactionBuilder>,
+    pub program_id: Pubkey,
+    /// IDL of the targeted program build; `None` when none was configured or it failed to load
+    pub program_idl: Option<Arc<ProgramIdl>>,
+    pub screening: Arc<ScreeningService>,
+    pub auto_lock: Arc<AutoLockService>,
+    /// Checks API unlocks against the owner's open positions
+    pub unlock_guard: Arc<UnlockGuard>,
+    pub identities: Arc<IdentityService>,
+    /// Bulk exports to object storage for warehouse loads
+    pub exports: Arc<ExportService>,
+    /// Replays responses of mutating requests retried with an `Idempotency-Key`
+    pub idempotency: Arc<IdempotencyService>,
+    /// Flags inactive vaults and prepares sweeps into dormant-funds accounts
+    pub dormancy: Arc<DormancyService>,
+    /// Withdrawal fee tiers and promotions
+    pub fees: Arc<FeeService>,
+    /// Authentication failures, bans and operator writes
+    pub security_events: Arc<SecurityEventLog>,
 }
 
 pub fn create_router(state: AppState) -> Router {
+    let maintenance = state.maintenance.clone();
+    let idempotency = state.idempotency.clone();
+    let security_log = state.security_events.clone();
+    
     Router::new()
         // Health and monitoring
         .route("/health", get(health_check))
         .route("/metrics", get(get_metrics))
         .route("/ws/metrics", get(metrics_websocket))
         
+        // Targeted program build
+        .route("/program/idl", get(get_program_idl))
+        .route("/program/info", get(get_program_info))
+        
         // Vault management
         .route("/vaults", get(list_vaults).post(create_vault))
+        .route("/vaults/search", get(search_vaults))
         .route("/vaults/:user_pubkey", get(get_vault))
         .route("/vaults/:user_pubkey/balance", get(get_balance))
         .route("/vaults/:user_pubkey/state", put(update_vault_state))
         
         // Transaction operations
         .route("/vaults/:user_pubkey/deposit", post(deposit))
+        .route("/vaults/:user_pubkey/deposit/prepare", post(prepare_deposit))
         .route("/vaults/:user_pubkey/withdraw", post(withdraw))
+        .route("/vaults/:user_pubkey/withdraw/preview", get(preview_withdrawal))
         .route("/vaults/:user_pubkey/lock", post(lock_collateral))
         .route("/vaults/:user_pubkey/unlock", post(unlock_collateral))
         .route("/vaults/:user_pubkey/transfer", post(transfer_collateral))
+        .route("/vaults/:user_pubkey/auto-lock", get(get_auto_lock).put(set_auto_lock))
+        .route("/vaults/:user_pubkey/alerts", get(list_vault_alerts).post(create_vault_alert))
+        .route("/vaults/:user_pubkey/alerts/:rule_id", put(update_vault_alert).delete(delete_vault_alert))
+        .route("/vaults/:user_pubkey/reclaim", post(prepare_dormancy_reclaim))
+        .route("/vaults/:user_pubkey/withdrawals/:transaction_id", get(get_withdrawal_status))
+        .route("/vaults/:user_pubkey/withdraw/multisig", post(withdraw_multisig))
+        .route("/vaults/:user_pubkey/withdraw/multisig/:transaction_id", get(get_multisig_withdrawal))
+        
+        // Address derivation
+        .route("/derive/vault/:user_pubkey", get(derive_vault))
+        
+        // Mint configuration
+        .route("/mints", get(list_mints))
+        .route("/mints/:mint_pubkey", get(get_mint).put(upsert_mint))
+        .route("/mints/:mint_pubkey/fees", get(get_fee_schedule).put(replace_fee_tiers))
+        .route("/mints/:mint_pubkey/fees/promotions", post(create_fee_promotion))
+        .route("/mints/:mint_pubkey/fees/promotions/:promotion_id/end", post(end_fee_promotion))
         
         // Transaction history
         .route("/vaults/:user_pubkey/transactions", get(get_vault_transactions))
         .route("/transactions/:transaction_id", get(get_transaction))
+        .route("/transactions/prepared/refresh", post(refresh_prepared_transaction))
         
         // Balance operations
         .route("/vaults/:user_pubkey/snapshots", get(get_balance_snapshots))
         .route("/vaults/:user_pubkey/reconcile", post(reconcile_balance))
+        .route("/vaults/:user_pubkey/reconciliations", get(get_reconciliation_history))
+        .route("/vaults/:user_pubkey/tax-report", get(get_tax_report))
+        .route("/vaults/:user_pubkey/twab", get(get_twab))
+        .route("/vaults/:user_pubkey/replay", get(replay_vault_ledger))
+        .route("/vaults/:user_pubkey/onchain-activity", get(get_onchain_activity))
         
         // System operations
         .route("/system/stats", get(get_system_stats))
         .route("/system/config", get(get_system_config).put(update_system_config))
         .route("/system/audit-log", get(get_audit_log))
+        .route("/system/latency", get(get_latency_breakdown))
+        .route("/system/migrations", get(get_migration_status))
+        .route("/system/cluster", get(get_cluster_timing))
+        .route("/system/jobs", get(get_monitor_jobs))
+        .route("/system/sla", get(get_sla_report))
+        .route("/system/maintenance", get(get_maintenance_status).post(schedule_maintenance))
+        .route("/system/maintenance/:window_id/cancel", post(cancel_maintenance))
+        .route("/system/authority-rotations", post(stage_authority_rotation))
+        .route("/system/authority-rotations/:rotation_id", get(get_authority_rotation))
+        .route("/system/authority-rotations/:rotation_id/batches", post(run_authority_rotation_batch))
+        .route("/system/authority-rotations/:rotation_id/rollback", post(rollback_authority_rotation))
+        
+        // Analytics
+        .route("/analytics/activity", get(get_activity_analytics))
+        .route("/analytics/fees", get(get_fee_analytics))
+        
+        // Support view (read-only, every access audited)
+        .route("/support/vaults/:user_pubkey", get(support_vault_detail))
+        
+        // Ops case management (operations credentials only, every write audited)
+        .route("/ops/vaults/:user_pubkey/notes", post(add_vault_note))
+        .route("/ops/vaults/:user_pubkey/tags/:tag", put(add_vault_tag).delete(remove_vault_tag))
+        .route("/ops/vaults/:user_pubkey/cases", post(open_vault_case))
+        .route("/ops/vaults/:user_pubkey/cases/:case_id/close", post(close_vault_case))
+        
+        // Admin bulk operations (operations credentials only; preview, then execute)
+        .route("/admin/bulk", post(preview_bulk_job))
+        .route("/admin/bulk/:job_id", get(get_bulk_job))
+        .route("/admin/bulk/:job_id/execute", post(execute_bulk_job))
+        .route("/admin/bulk/:job_id/abort", post(abort_bulk_job))
         
-        // WebSocket endpoints
+        // Incident runbooks (operations credentials only; writable during maintenance)
+        .route("/admin/incident", get(list_incident_runs).post(start_incident_run))
+        .route("/admin/incident/:run_id", get(get_incident_run))
+        .route("/admin/incident/:run_id/resume", post(resume_incident_run))
+        .route("/admin/incident/:run_id/abort", post(abort_incident_run))
+        .route("/admin/token-authorities", get(list_token_authority_findings))
+        .route("/admin/token-authorities/:finding_id/revoke", post(prepare_token_revocation))
+        .route("/admin/screening/reviews", get(list_screening_reviews))
+        .route("/admin/screening/reviews/:decision_id", post(review_screening_decision))
+        .route("/admin/screening/vaults/:user_pubkey", get(get_vault_screening_decisions))
+        .route("/admin/event-consumers", get(list_event_consumers).post(register_event_consumer))
+        .route("/admin/vaults/:user_pubkey/migrate-layout", post(migrate_vault_layout))
+        .route("/admin/indexer", get(get_indexer_status))
+        .route("/admin/indexer/gaps", get(list_indexer_gaps))
+        .route("/admin/rate-limits/top", get(list_top_rate_limit_clients))
+        .route("/admin/rate-limits/rejections", get(list_rate_limit_rejections))
+        .route("/admin/rate-limits/abuse", get(list_rate_limit_abuse))
+        .route("/admin/rate-limits/bans", get(list_rate_limit_bans).post(create_rate_limit_ban))
+        .route("/admin/rate-limits/bans/:ban_id/lift", post(lift_rate_limit_ban))
+        .route("/admin/rate-limits/rules", get(list_escalation_rules).post(save_escalation_rule))
+        .route("/admin/security-events", get(list_security_events))
+        .route("/admin/identities/:user_pubkey", get(get_identity))
+        .route("/admin/dormancy", get(list_dormant_vaults))
+        .route("/admin/dormancy/:user_pubkey/sweep", post(prepare_dormancy_sweep))
+        
+        // Snapshot-consistent bulk exports (format documented in `crate::exports`)
+        .route("/exports", get(list_exports).post(request_export))
+        .route("/exports/:export_id", get(get_export))
+        
+        // Verification results from KYC providers, authenticated by each provider's signature
+        .route("/webhooks/identity/:provider", post(ingest_identity_webhook))
+        
+        // Outbox delivery to pull consumers (protocol documented in `crate::outbox`)
+        .route("/events/:consumer", get(poll_events))
+        .route("/events/:consumer/ack", post(acknowledge_events))
+        
+        // WebSocket endpoints (protocol documented in `crate::stream`)
+        .route("/ws", get(event_stream_websocket))
         .route("/ws/vaults/:user_pubkey", get(vault_websocket))
+        .route("/ws/vaults/:user_pubkey/withdrawals/:transaction_id", get(withdrawal_websocket))
         
         .with_state(state)
+        .layer(middleware::from_fn_with_state(idempotency, idempotency_middleware))
+        .layer(middleware::from_fn_with_state(security_log.clone(), admin_action_middleware))
         .layer(middleware::from_fn(rate_limit_middleware))
+        .layer(middleware::from_fn_with_state(security_log, support_read_only_middleware))
+        .layer(middleware::from_fn_with_state(maintenance, maintenance_middleware))
 }
 
 // Request/Response DTOs
@@ -85,6 +261,9 @@ pub struct CreateVaultResponse {
     pub vault_pubkey: String,
     pub token_account_pubkey: String,
     pub bump: u8,
+    /// `active` once the vault exists on chain; otherwise retry the request to resume
+    pub provisioning_state: VaultProvisioningState,
+    pub provisioning_error: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -106,7 +285,26 @@ pub struct BalanceResponse {
     pub total_balance: i64,
     pub locked_balance: i64,
     pub available_balance: i64,
+    /// Reserved by operations accepted but not yet settled on-chain
+    pub held: HeldAmounts,
+    /// Available balance not held; what a new withdrawal or lock may use
+    pub spendable_balance: i64,
+    /// Deposits submitted but not yet finalized on-chain; part of no balance above until credited
+    pub pending_credit: i64,
+    pub symbol: String,
+    pub decimals: i16,
+    pub total_balance_display: String,
     pub last_updated_at: DateTime<Utc>,
+    /// When the balances were last known to be current
+    pub as_of: DateTime<Utc>,
+    pub source: BalanceReadSource,
+    pub stale: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BalanceQuery {
+    /// `strong` forces a database read; defaults to `eventual`
+    pub consistency: Option<ReadConsistency>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -116,6 +314,29 @@ pub struct TransactionRequest {
     pub metadata: Option<serde_json::Value>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DepositRequest {
+    pub amount: u64,
+    /// Signature of the deposit transaction the user submitted
+    pub signature: String,
+    pub idempotency_key: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PrepareDepositRequest {
+    pub amount: u64,
+    /// Token account to deposit from; the owner's associated token account when absent
+    pub source_token_account: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PreparedDepositResponse {
+    pub source_token_account: String,
+    /// Base64 bincode transaction for the owner to sign and submit, then report with its signature
+    pub unsigned_transaction: String,
+    pub hints: SigningHints,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TransferRequest {
     pub amount: u64,
@@ -124,6 +345,43 @@ pub struct TransferRequest {
     pub metadata: Option<serde_json::Value>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MultisigWithdrawRequest {
+    pub amount: u64,
+    /// Squads multisig account that owns the vault
+    pub multisig_pubkey: String,
+    /// Squads vault index; the vault's user must be this vault PDA
+    pub vault_index: Option<u8>,
+    /// Member who will sign, approve and pay for the proposal
+    pub creator_pubkey: String,
+    pub destination_token_account: String,
+    pub idempotency_key: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MultisigWithdrawResponse {
+    pub transaction_id: Uuid,
+    pub proposal_pubkey: String,
+    pub transaction_index: i64,
+    pub status: String,
+    /// Base64 bincode transaction for the creator to sign and submit; absent on idempotent replays
+    pub unsigned_transaction: Option<String>,
+    /// Fees and blockhash expiry for `unsigned_transaction`
+    pub hints: Option<SigningHints>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefreshTransactionRequest {
+    /// Base64 bincode of a prepared transaction, not yet signed
+    pub unsigned_transaction: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefreshTransactionResponse {
+    pub unsigned_transaction: String,
+    pub hints: SigningHints,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TransactionResponse {
     pub transaction_id: Uuid,
@@ -142,6 +400,243 @@ pub struct ListTransactionsQuery {
     pub end_date: Option<DateTime<Utc>>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListVaultsQuery {
+    pub page: Option<u32>,
+    pub limit: Option<u32>,
+    pub is_active: Option<bool>,
+    pub min_total_balance: Option<i64>,
+    pub max_total_balance: Option<i64>,
+    pub min_locked_balance: Option<i64>,
+    pub max_locked_balance: Option<i64>,
+    pub min_lock_utilization_bps: Option<i64>,
+    pub max_lock_utilization_bps: Option<i64>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub last_activity_after: Option<DateTime<Utc>>,
+    pub last_activity_before: Option<DateTime<Utc>>,
+    pub mint: Option<String>,
+    /// Ops tag, e.g. `vip`
+    pub tag: Option<String>,
+    pub has_open_case: Option<bool>,
+}
+
+impl ListVaultsQuery {
+    fn to_filter(&self) -> VaultListFilter {
+        VaultListFilter {
+            is_active: self.is_active,
+            min_total_balance: self.min_total_balance,
+            max_total_balance: self.max_total_balance,
+            min_locked_balance: self.min_locked_balance,
+            max_locked_balance: self.max_locked_balance,
+            min_lock_utilization_bps: self.min_lock_utilization_bps,
+            max_lock_utilization_bps: self.max_lock_utilization_bps,
+            created_after: self.created_after,
+            created_before: self.created_before,
+            last_activity_after: self.last_activity_after,
+            last_activity_before: self.last_activity_before,
+            mint: self.mint.clone(),
+            tag: self.tag.clone(),
+            has_open_case: self.has_open_case,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchVaultsQuery {
+    pub q: String,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeriveVaultQuery {
+    pub sub_account: Option<u16>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpsertMintRequest {
+    pub symbol: String,
+    pub decimals: i16,
+    pub enabled: bool,
+    pub min_deposit: i64,
+    pub withdrawal_fee: i64,
+    pub oracle_feed_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LatencyQuery {
+    pub window_minutes: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReconcileQuery {
+    /// `quick` (default), `standard`, `deep` or `ledger`
+    pub mode: Option<ReconciliationMode>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AddVaultNoteRequest {
+    pub body: String,
+    /// File the note under one of the vault's cases
+    pub case_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OpenVaultCaseRequest {
+    pub title: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CloseVaultCaseRequest {
+    pub resolution: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LimitQuery {
+    pub limit: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RateLimitUsageQuery {
+    /// Minutes to look back; defaults to 60
+    pub window_minutes: Option<i64>,
+    pub limit: Option<i32>,
+}
+
+impl RateLimitUsageQuery {
+    fn window(&self) -> chrono::Duration {
+        chrono::Duration::minutes(self.window_minutes.unwrap_or(60).clamp(1, abuse::USAGE_RETENTION_DAYS * 24 * 60))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RateLimitBanQuery {
+    pub client_id: Option<String>,
+    /// Include lifted and expired bans
+    pub all: Option<bool>,
+    pub limit: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SecurityEventsQuery {
+    pub event_type: Option<String>,
+    pub source: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub limit: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AcknowledgeEventsRequest {
+    /// Every event up to and including this sequence number was processed
+    pub seq: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ActivityQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// `hour` (default) or `day`
+    pub granularity: Option<ActivityGranularity>,
+    /// Number of top vaults by volume to return
+    pub top: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FeeAnalyticsQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaxReportQuery {
+    /// Calendar year; defaults to the current year
+    pub year: Option<i32>,
+    /// `json` (default) or `csv` for Form 8949 rows
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OnchainActivityQuery {
+    /// Return signatures older than this one, for paging back
+    pub before: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplayQuery {
+    /// First ledger sequence number to return a state for; defaults to the
+    /// start of the largest window ending at `until`
+    pub from: Option<u64>,
+    /// Last sequence number replayed; defaults to the end of the ledger
+    pub until: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TwabQuery {
+    /// Start of the first epoch's window; defaults to four epochs before `to`
+    pub from: Option<DateTime<Utc>>,
+    /// Defaults to now
+    pub to: Option<DateTime<Utc>>,
+    /// Epoch length; defaults to a week
+    pub epoch_seconds: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VaultLayoutMigrationResponse {
+    pub vault_id: Uuid,
+    pub solana_signature: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MigrationStatusResponse {
+    pub is_current: bool,
+    pub status: MigrationStatus,
+    pub drift: SchemaDrift,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MonitorJobsQuery {
+    /// Only runs of this job type
+    pub job: Option<String>,
+    pub limit: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MonitorJobsResponse {
+    /// True when no expected job has missed its runs
+    pub all_running: bool,
+    pub jobs: Vec<JobStatus>,
+    /// Recent runs, newest first
+    pub runs: Vec<MonitorJobRun>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClusterTimingResponse {
+    /// Latest fresh observation; absent until the monitor has polled the cluster
+    pub conditions: Option<ClusterConditions>,
+    pub maintenance_deferred: Option<String>,
+    pub submissions_deferred: bool,
+    pub seconds_to_next_epoch: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScheduleMaintenanceRequest {
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub reason: String,
+    /// Reject writes while the window is active (default true)
+    pub read_only: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LatencyBreakdownResponse {
+    /// Histograms since process start
+    pub live: Vec<StageLatencySummary>,
+    /// Percentiles from transaction records in the window, across all instances
+    pub persisted: Vec<StageLatencyRow>,
+    pub window_minutes: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SystemStatsResponse {
     pub vault_count: i64,
@@ -150,6 +645,9 @@ pub struct SystemStatsResponse {
     pub total_value_locked: i64,
     pub is_healthy: bool,
     pub last_reconciliation: Option<DateTime<Utc>>,
+    /// Slots the program indexer is behind the cluster
+    pub indexer_lag_slots: Option<u64>,
+    pub indexer_open_gaps: usize,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -157,6 +655,7 @@ pub struct HealthResponse {
     pub status: String,
     pub timestamp: DateTime<Utc>,
     pub details: Option<serde_json::Value>,
+    pub maintenance: MaintenanceStatus,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -172,10 +671,13 @@ pub struct ErrorResponse {
 async fn health_check(State(state): State<AppState>) -> JsonResponse<HealthResponse> {
     let is_healthy = state.monitor.get_health_status().await;
     
+    let now = Utc::now();
+    
     JsonResponse(HealthResponse {
         status: if is_healthy { "healthy".to_string() } else { "unhealthy".to_string() },
-        timestamp: Utc::now(),
+        timestamp: now,
         details: None,
+        maintenance: state.maintenance.status(now),
     })
 }
 
@@ -188,6 +690,8 @@ async fn get_metrics(State(state): State<AppState>) -> JsonResponse<SystemStatsR
             total_value_locked: stats.total_value_locked,
             is_healthy: stats.is_healthy,
             last_reconciliation: stats.last_reconciliation,
+            indexer_lag_slots: stats.indexer_lag_slots,
+            indexer_open_gaps: stats.indexer_open_gaps,
         }),
         Err(e) => {
             error!("Failed to get system stats: {}", e);
@@ -198,6 +702,8 @@ async fn get_metrics(State(state): State<AppState>) -> JsonResponse<SystemStatsR
                 total_value_locked: 0,
                 is_healthy: false,
                 last_reconciliation: None,
+                indexer_lag_slots: None,
+                indexer_open_gaps: 0,
             })
         }
     }
@@ -205,44 +711,59 @@ async fn get_metrics(State(state): State<AppState>) -> JsonResponse<SystemStatsR
 
 async fn list_vaults(
     State(state): State<AppState>,
-    Query(params): Query<ListTransactionsQuery>,
-) -> JsonResponse<Vec<VaultResponse>> {
+    Query(params): Query<ListVaultsQuery>,
+) -> ApiResult<JsonResponse<Vec<VaultResponse>>> {
     let limit = params.limit.unwrap_or(50).min(100) as i64;
-    let offset = ((params.page.unwrap_or(1) - 1) * params.limit.unwrap_or(50) as u32) as i64;
+    let offset = (params.page.unwrap_or(1).max(1) as i64 - 1) * limit;
     
-    match state.vault_manager.get_active_vaults(limit, offset).await {
-        Ok(vaults) => {
-            let responses: Vec<VaultResponse> = vaults.into_iter().map(|v| VaultResponse {
-                id: v.id,
-                user_pubkey: v.user_pubkey,
-                vault_pubkey: v.vault_pubkey,
-                token_account_pubkey: v.token_account_pubkey,
-                total_balance: v.total_balance,
-                locked_balance: v.locked_balance,
-                available_balance: v.available_balance,
-                is_active: v.is_active,
-                created_at: v.created_at,
-                last_activity_at: v.last_activity_at,
-            }).collect();
-            JsonResponse(responses)
-        }
-        Err(e) => {
-            error!("Failed to list vaults: {}", e);
-            JsonResponse(vec![])
-        }
+    let mut filter = params.to_filter();
+    filter.tag = filter.tag.as_deref().map(cases::normalize_tag).transpose()?;
+    
+    let vaults = state.vault_manager.list_vaults(&filter, limit, offset).await?;
+    Ok(JsonResponse(vaults.into_iter().map(to_vault_response).collect()))
+}
+
+async fn search_vaults(
+    State(state): State<AppState>,
+    Query(params): Query<SearchVaultsQuery>,
+) -> ApiResult<JsonResponse<Vec<VaultResponse>>> {
+    let limit = params.limit.unwrap_or(20).min(100) as i64;
+    
+    let vaults = state.vault_manager.search_vaults(&params.q, limit).await?;
+    Ok(JsonResponse(vaults.into_iter().map(to_vault_response).collect()))
+}
+
+fn to_vault_response(v: Vault) -> VaultResponse {
+    VaultResponse {
+        id: v.id,
+        user_pubkey: v.user_pubkey,
+        vault_pubkey: v.vault_pubkey,
+        token_account_pubkey: v.token_account_pubkey,
+        total_balance: v.total_balance,
+        locked_balance: v.locked_balance,
+        available_balance: v.available_balance,
+        is_active: v.is_active,
+        created_at: v.created_at,
+        last_activity_at: v.updated_at,
     }
 }
 
 async fn create_vault(
     State(state): State<AppState>,
     Json(request): Json<CreateVaultRequest>,
-) -> Result<JsonResponse<CreateVaultResponse>, VaultError> {
+) -> ApiResult<JsonResponse<CreateVaultResponse>> {
     info!("Creating vault for user: {}", request.user_pubkey);
     
-    let vault = state.vault_manager.create_vault(
+    state.identities.require(&request.user_pubkey, IdentityGate::VaultCreation).await?;
+    
+    let mint = state.mint_registry.resolve(None).await?;
+    let provisioned = state.monitor.provisioner().provision(
         &request.user_pubkey,
         &request.authority_pubkey,
+        &mint,
     ).await?;
+    let provisioning_state = provisioned.state();
+    let vault = provisioned.vault;
     
     Ok(JsonResponse(CreateVaultResponse {
         vault_id: vault.id,
@@ -250,16 +771,27 @@ async fn create_vault(
         vault_pubkey: vault.vault_pubkey,
         token_account_pubkey: vault.token_account_pubkey,
         bump: vault.bump as u8,
+        provisioning_state,
+        provisioning_error: provisioned.provisioning.and_then(|p| p.last_error),
     }))
 }
 
+/// Answered 304 while the vault's ETag still matches `If-None-Match`
 async fn get_vault(
     State(state): State<AppState>,
     Path(user_pubkey): Path<String>,
-) -> Result<JsonResponse<VaultResponse>, VaultError> {
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Response> {
+    // Read before the vault, so a change in between only costs the poller a refetch
+    let version = state.vault_manager.get_vault_version(&user_pubkey).await?;
+    let etag = etag::vault_etag(&version);
+    if let Some(response) = not_modified(&headers, &etag) {
+        return Ok(response);
+    }
+    
     let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
     
-    Ok(JsonResponse(VaultResponse {
+    Ok(with_etag(etag, JsonResponse(VaultResponse {
         id: vault.id,
         user_pubkey: vault.user_pubkey,
         vault_pubkey: vault.vault_pubkey,
@@ -270,28 +802,73 @@ async fn get_vault(
         is_active: vault.is_active,
         created_at: vault.created_at,
         last_activity_at: vault.last_activity_at,
-    }))
+    })))
 }
 
+/// Answered 304 while the vault's ETag still matches `If-None-Match`; a
+/// cached balance older than the vault is served without a tag
 async fn get_balance(
     State(state): State<AppState>,
     Path(user_pubkey): Path<String>,
-) -> Result<JsonResponse<BalanceResponse>, VaultError> {
-    let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
+    Query(query): Query<BalanceQuery>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Response> {
+    let version = state.vault_manager.get_vault_version(&user_pubkey).await?;
+    let etag = etag::vault_etag(&version);
+    if let Some(response) = not_modified(&headers, &etag) {
+        return Ok(response);
+    }
     
-    Ok(JsonResponse(BalanceResponse {
-        total_balance: vault.total_balance,
-        locked_balance: vault.locked_balance,
-        available_balance: vault.available_balance,
-        last_updated_at: vault.updated_at,
-    }))
+    let consistency = query.consistency.unwrap_or_default();
+    let balance = state.balance_tracker.read_balance(&user_pubkey, consistency).await?;
+    let current = etag::balance_read_current(&balance, &version);
+    let held = state.vault_manager.get_held_amounts(balance.vault_id).await?;
+    let spendable = SpendableBalance::new(balance.available_balance, balance.locked_balance, held);
+    let pending_credit = state.vault_manager.get_pending_credit(balance.vault_id).await?;
+    let mint = state.mint_registry.resolve(None).await?;
+    
+    let response = JsonResponse(BalanceResponse {
+        total_balance: balance.total_balance,
+        locked_balance: balance.locked_balance,
+        available_balance: balance.available_balance,
+        held,
+        spendable_balance: spendable.spendable_available(),
+        pending_credit,
+        total_balance_display: mint.format_amount(balance.total_balance),
+        symbol: mint.symbol,
+        decimals: mint.decimals,
+        last_updated_at: balance.as_of,
+        as_of: balance.as_of,
+        source: balance.source,
+        stale: balance.stale,
+    });
+    
+    if current {
+        Ok(with_etag(etag, response))
+    } else {
+        Ok(response.into_response())
+    }
+}
+
+/// 304 with the tag when `If-None-Match` already names `etag`
+fn not_modified(headers: &axum::http::HeaderMap, etag: &str) -> Option<Response> {
+    let matched = headers.get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .map_or(false, |value| etag::if_none_match(value, etag));
+    matched.then(|| (StatusCode::NOT_MODIFIED, [(header::ETAG, etag.to_string())]).into_response())
 }
 
+fn with_etag(etag: String, body: impl IntoResponse) -> Response {
+    ([(header::ETAG, etag)], body).into_response()
+}
+
+/// Record a submitted deposit; it stays `pending` and is shown as `pending_credit`
+/// until the indexer confirms it on-chain and credits the vault
 async fn deposit(
     State(state): State<AppState>,
     Path(user_pubkey): Path<String>,
-    Json(request): Json<TransactionRequest>,
-) -> Result<JsonResponse<TransactionResponse>, VaultError> {
+    Json(request): Json<DepositRequest>,
+) -> ApiResult<JsonResponse<TransactionResponse>> {
     info!("Processing deposit for user: {}, amount: {}", user_pubkey, request.amount);
     
     // Check idempotency
@@ -308,11 +885,22 @@ async fn deposit(
     }
     
     let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
+    let mint = state.mint_registry.resolve(None).await?;
+    mint_registry::validate_deposit(&mint, request.amount)?;
+    
+    // Attribution to the vault is refused when the depositing wallet fails screening
+    state.screening.screen(ScreeningSubject {
+        vault_id: vault.id,
+        direction: ScreeningDirection::Deposit,
+        address: vault.user_pubkey.clone(),
+        amount: request.amount as i64,
+    }).await?;
+    
     let tx_record = state.vault_manager.deposit(
         vault.id,
         request.amount,
+        request.signature,
         request.idempotency_key,
-        request.metadata,
     ).await?;
     
     Ok(JsonResponse(TransactionResponse {
@@ -323,11 +911,50 @@ async fn deposit(
     }))
 }
 
+/// Unsigned deposit for the owner to sign, once the source token account is
+/// known to exist, hold the vault's mint and cover the amount
+async fn prepare_deposit(
+    State(state): State<AppState>,
+    Path(user_pubkey): Path<String>,
+    Json(request): Json<PrepareDepositRequest>,
+) -> ApiResult<JsonResponse<PreparedDepositResponse>> {
+    let parse = |value: &str, what: &str| Pubkey::from_str(value)
+        .map_err(|_| DomainError::Validation(format!("Invalid {}", what)));
+    let user = parse(&user_pubkey, "user pubkey")?;
+    
+    state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
+    let mint = state.mint_registry.resolve(None).await?;
+    mint_registry::validate_deposit(&mint, request.amount)?;
+    let mint = parse(&mint.mint_pubkey, "mint pubkey")?;
+    let token_program = state.transaction_builder.fetch_token_program(mint).await?;
+    
+    let source = match &request.source_token_account {
+        Some(source) => parse(source, "source token account")?,
+        None => token_accounts::associated_token_address(&user, &mint, &token_program),
+    };
+    state.transaction_builder.check_user_token_account(
+        source,
+        mint,
+        token_program,
+        user,
+        TokenAccountRole::DepositSource { amount: request.amount },
+    ).await?;
+    
+    let built = state.transaction_builder.build_user_deposit_tx(user, request.amount, source).await?;
+    let hints = state.transaction_builder.signing_hints(&built.transaction, built.last_valid_block_height).await?;
+    
+    Ok(JsonResponse(PreparedDepositResponse {
+        source_token_account: source.to_string(),
+        unsigned_transaction: transaction_builder::encode_transaction(&built.transaction)?,
+        hints,
+    }))
+}
+
 async fn withdraw(
     State(state): State<AppState>,
     Path(user_pubkey): Path<String>,
     Json(request): Json<TransactionRequest>,
-) -> Result<JsonResponse<TransactionResponse>, VaultError> {
+) -> ApiResult<JsonResponse<TransactionResponse>> {
     info!("Processing withdrawal for user: {}, amount: {}", user_pubkey, request.amount);
     
     // Check idempotency
@@ -346,17 +973,28 @@ async fn withdraw(
     let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
     
     if vault.available_balance < request.amount as i64 {
-        return Err(VaultError::InsufficientBalance {
+        return Err(DomainError::InsufficientBalance {
             available: vault.available_balance as u64,
             required: request.amount,
-        });
+        }.into());
     }
     
+    let (_, fee_quote) = state.fees.quote_withdrawal(&vault.user_pubkey, request.amount).await?;
+    
+    state.identities.require(&vault.user_pubkey, IdentityGate::Withdrawal { amount: request.amount }).await?;
+    
+    state.screening.screen(ScreeningSubject {
+        vault_id: vault.id,
+        direction: ScreeningDirection::Withdrawal,
+        address: vault.user_pubkey.clone(),
+        amount: request.amount as i64,
+    }).await?;
+    
     let tx_record = state.vault_manager.withdraw(
         vault.id,
         request.amount,
         request.idempotency_key,
-        request.metadata,
+        fees::attach_to_metadata(request.metadata, &fee_quote),
     ).await?;
     
     Ok(JsonResponse(TransactionResponse {
@@ -367,55 +1005,175 @@ async fn withdraw(
     }))
 }
 
-async fn lock_collateral(
+#[derive(Debug, Deserialize)]
+pub struct WithdrawPreviewQuery {
+    pub amount: u64,
+}
+
+/// What a withdrawal would cost, before the user commits to it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WithdrawPreviewResponse {
+    pub quote: FeeQuote,
+    pub symbol: String,
+    pub decimals: i16,
+    pub fee_display: String,
+    pub net_amount_display: String,
+    pub available_balance: i64,
+    /// Whether a withdrawal of this amount would currently be accepted
+    pub accepted: bool,
+    pub rejection_reason: Option<String>,
+    /// Paid by the service's fee payer, not deducted from the withdrawal; absent when the RPC node is unreachable
+    pub network_fee: Option<NetworkFeeEstimate>,
+}
+
+async fn preview_withdrawal(
     State(state): State<AppState>,
     Path(user_pubkey): Path<String>,
-    Json(request): Json<TransactionRequest>,
-) -> Result<JsonResponse<TransactionResponse>, VaultError> {
-    info!("Processing collateral lock for user: {}, amount: {}", user_pubkey, request.amount);
-    
+    Query(query): Query<WithdrawPreviewQuery>,
+) -> ApiResult<JsonResponse<WithdrawPreviewResponse>> {
     let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
+    let schedule = state.fees.schedule(None).await?;
+    let quote = schedule.quote(&vault.user_pubkey, query.amount as i64, Utc::now());
     
-    if vault.available_balance < request.amount as i64 {
-        return Err(VaultError::InsufficientBalance {
-            available: vault.available_balance as u64,
-            required: request.amount,
-        });
+    let rejection_reason = match quote.validate(&schedule.mint) {
+        Err(e) => Some(e.to_string()),
+        Ok(()) if vault.available_balance < query.amount as i64 => Some(format!(
+            "Insufficient balance: available {}", schedule.mint.format_amount(vault.available_balance)
+        )),
+        Ok(()) => None,
+    };
+    
+    let network_fee = match Pubkey::from_str(&vault.vault_pubkey) {
+        Ok(vault_pubkey) => state.transaction_builder.estimate_withdraw_network_fee(vault_pubkey).await
+            .map_err(|e| warn!("Network fee estimate for vault {} failed: {}", vault.id, e))
+            .ok(),
+        Err(_) => None,
+    };
+    
+    Ok(JsonResponse(WithdrawPreviewResponse {
+        fee_display: schedule.mint.format_amount(quote.fee),
+        net_amount_display: schedule.mint.format_amount(quote.net_amount.max(0)),
+        symbol: schedule.mint.symbol.clone(),
+        decimals: schedule.mint.decimals,
+        available_balance: vault.available_balance,
+        accepted: rejection_reason.is_none(),
+        rejection_reason,
+        network_fee,
+        quote,
+    }))
+}
+
+async fn withdraw_multisig(
+    State(state): State<AppState>,
+    Path(user_pubkey): Path<String>,
+    Json(request): Json<MultisigWithdrawRequest>,
+) -> ApiResult<JsonResponse<MultisigWithdrawResponse>> {
+    info!("Preparing multisig withdrawal for user: {}, amount: {}", user_pubkey, request.amount);
+    
+    // A replay returns the existing proposal; its transaction was already handed out
+    if let Some(idempotency_key) = &request.idempotency_key {
+        if let Some(existing_tx) = state.transaction_manager
+            .get_transaction_by_idempotency_key(idempotency_key).await? {
+            let proposal = state.multisig_manager.get_proposal(existing_tx.id).await?;
+            return Ok(JsonResponse(MultisigWithdrawResponse {
+                transaction_id: existing_tx.id,
+                proposal_pubkey: proposal.proposal_pubkey,
+                transaction_index: proposal.transaction_index,
+                status: proposal.status,
+                unsigned_transaction: None,
+                hints: None,
+            }));
+        }
     }
     
-    let operation_id = Uuid::new_v4();
-    let signature = state.cpi_manager.lock_collateral(
-        vault.id,
-        request.amount,
-        operation_id,
-    ).await?;
+    let parse = |value: &str, what: &str| Pubkey::from_str(value)
+        .map_err(|_| DomainError::Validation(format!("Invalid {}", what)));
+    let multisig = parse(&request.multisig_pubkey, "multisig pubkey")?;
+    let creator = parse(&request.creator_pubkey, "creator pubkey")?;
+    let destination_token_account = parse(&request.destination_token_account, "destination token account")?;
     
-    Ok(JsonResponse(TransactionResponse {
-        transaction_id: operation_id,
-        solana_signature: Some(signature),
-        status: "confirmed".to_string(),
-        created_at: Utc::now(),
+    let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
+    let mint = state.mint_registry.resolve(None).await?;
+    let mint = parse(&mint.mint_pubkey, "mint pubkey")?;
+    
+    state.fees.quote_withdrawal(&vault.user_pubkey, request.amount).await?;
+    
+    state.identities.require(&vault.user_pubkey, IdentityGate::Withdrawal { amount: request.amount }).await?;
+    
+    state.screening.screen(ScreeningSubject {
+        vault_id: vault.id,
+        direction: ScreeningDirection::Withdrawal,
+        address: request.destination_token_account.clone(),
+        amount: request.amount as i64,
+    }).await?;
+    
+    let prepared = state.multisig_manager.prepare_withdrawal(MultisigWithdrawalRequest {
+        vault_id: vault.id,
+        multisig,
+        vault_index: request.vault_index.unwrap_or(0),
+        creator,
+        destination_token_account,
+        mint,
+        amount: request.amount,
+        idempotency_key: request.idempotency_key,
+    }).await?;
+    
+    Ok(JsonResponse(MultisigWithdrawResponse {
+        transaction_id: prepared.transaction.id,
+        proposal_pubkey: prepared.proposal.proposal_pubkey,
+        transaction_index: prepared.proposal.transaction_index,
+        status: prepared.proposal.status,
+        unsigned_transaction: Some(prepared.unsigned_transaction),
+        hints: Some(prepared.hints),
     }))
 }
 
-async fn unlock_collateral(
+/// Put a fresh blockhash into a prepared transaction whose blockhash expired before it was signed
+async fn refresh_prepared_transaction(
+    State(state): State<AppState>,
+    Json(request): Json<RefreshTransactionRequest>,
+) -> ApiResult<JsonResponse<RefreshTransactionResponse>> {
+    let transaction = transaction_builder::decode_transaction(&request.unsigned_transaction)?;
+    let (transaction, hints) = state.transaction_builder.refresh_unsigned(transaction).await?;
+    
+    Ok(JsonResponse(RefreshTransactionResponse {
+        unsigned_transaction: transaction_builder::encode_transaction(&transaction)?,
+        hints,
+    }))
+}
+
+async fn get_multisig_withdrawal(
+    State(state): State<AppState>,
+    Path((user_pubkey, transaction_id)): Path<(String, Uuid)>,
+) -> ApiResult<JsonResponse<MultisigProposal>> {
+    let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
+    let proposal = state.multisig_manager.get_proposal(transaction_id).await?;
+    
+    if proposal.vault_id != vault.id {
+        return Err(VaultError::from(StorageError::NotFound(format!("Multisig withdrawal {} not found", transaction_id))).into());
+    }
+    
+    Ok(JsonResponse(proposal))
+}
+
+async fn lock_collateral(
     State(state): State<AppState>,
     Path(user_pubkey): Path<String>,
     Json(request): Json<TransactionRequest>,
-) -> Result<JsonResponse<TransactionResponse>, VaultError> {
-    info!("Processing collateral unlock for user: {}, amount: {}", user_pubkey, request.amount);
+) -> ApiResult<JsonResponse<TransactionResponse>> {
+    info!("Processing collateral lock for user: {}, amount: {}", user_pubkey, request.amount);
     
     let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
     
-    if vault.locked_balance < request.amount as i64 {
-        return Err(VaultError::InsufficientLockedBalance {
-            locked: vault.locked_balance as u64,
+    if vault.available_balance < request.amount as i64 {
+        return Err(DomainError::InsufficientBalance {
+            available: vault.available_balance as u64,
             required: request.amount,
-        });
+        }.into());
     }
     
     let operation_id = Uuid::new_v4();
-    let signature = state.cpi_manager.unlock_collateral(
+    let signature = state.cpi_manager.lock_collateral(
         vault.id,
         request.amount,
         operation_id,
@@ -429,11 +1187,107 @@ async fn unlock_collateral(
     }))
 }
 
-async fn transfer_collateral(
+async fn get_auto_lock(
     State(state): State<AppState>,
     Path(user_pubkey): Path<String>,
-    Json(request): Json<TransferRequest>,
-) -> Result<JsonResponse<TransactionResponse>, VaultError> {
+) -> ApiResult<JsonResponse<VaultAutoLock>> {
+    let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
+    let setting = state.auto_lock.get(vault.id).await?
+        .ok_or_else(|| DomainError::NotFound(format!("Auto-lock for vault {}", user_pubkey)))?;
+    
+    Ok(JsonResponse(setting))
+}
+
+/// Opt into (or change) a standing locked amount the backend maintains
+async fn set_auto_lock(
+    State(state): State<AppState>,
+    Path(user_pubkey): Path<String>,
+    Json(request): Json<AutoLockRequest>,
+) -> ApiResult<JsonResponse<VaultAutoLock>> {
+    let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
+    
+    Ok(JsonResponse(state.auto_lock.configure(vault.id, request).await?))
+}
+
+async fn list_vault_alerts(
+    State(state): State<AppState>,
+    Path(user_pubkey): Path<String>,
+) -> ApiResult<JsonResponse<Vec<VaultAlertRule>>> {
+    let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
+    
+    Ok(JsonResponse(state.monitor.alerts().rules(vault.id).await?))
+}
+
+async fn create_vault_alert(
+    State(state): State<AppState>,
+    Path(user_pubkey): Path<String>,
+    Json(request): Json<AlertRuleRequest>,
+) -> ApiResult<JsonResponse<VaultAlertRule>> {
+    let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
+    
+    Ok(JsonResponse(state.monitor.alerts().create(vault.id, request).await?))
+}
+
+async fn update_vault_alert(
+    State(state): State<AppState>,
+    Path((user_pubkey, rule_id)): Path<(String, Uuid)>,
+    Json(request): Json<AlertRuleRequest>,
+) -> ApiResult<JsonResponse<VaultAlertRule>> {
+    let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
+    
+    Ok(JsonResponse(state.monitor.alerts().update(vault.id, rule_id, request).await?))
+}
+
+async fn delete_vault_alert(
+    State(state): State<AppState>,
+    Path((user_pubkey, rule_id)): Path<(String, Uuid)>,
+) -> ApiResult<JsonResponse<Vec<VaultAlertRule>>> {
+    let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
+    let alerts = state.monitor.alerts();
+    alerts.delete(vault.id, rule_id).await?;
+    
+    Ok(JsonResponse(alerts.rules(vault.id).await?))
+}
+
+async fn unlock_collateral(
+    State(state): State<AppState>,
+    Path(user_pubkey): Path<String>,
+    Json(request): Json<TransactionRequest>,
+) -> ApiResult<JsonResponse<TransactionResponse>> {
+    info!("Processing collateral unlock for user: {}, amount: {}", user_pubkey, request.amount);
+    
+    let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
+    
+    if vault.locked_balance < request.amount as i64 {
+        return Err(DomainError::InsufficientLockedBalance {
+            locked: vault.locked_balance as u64,
+            required: request.amount,
+        }.into());
+    }
+    
+    // The caller holds the CPI scope, but only collateral no open position needs is released
+    state.unlock_guard.check_unlock(&vault, request.amount).await?;
+    
+    let operation_id = Uuid::new_v4();
+    let signature = state.cpi_manager.unlock_collateral(
+        vault.id,
+        request.amount,
+        operation_id,
+    ).await?;
+    
+    Ok(JsonResponse(TransactionResponse {
+        transaction_id: operation_id,
+        solana_signature: Some(signature),
+        status: "confirmed".to_string(),
+        created_at: Utc::now(),
+    }))
+}
+
+async fn transfer_collateral(
+    State(state): State<AppState>,
+    Path(user_pubkey): Path<String>,
+    Json(request): Json<TransferRequest>,
+) -> ApiResult<JsonResponse<TransactionResponse>> {
     info!("Processing collateral transfer from {} to {}, amount: {}", 
           user_pubkey, request.destination_user_pubkey, request.amount);
     
@@ -441,10 +1295,10 @@ async fn transfer_collateral(
     let destination_vault = state.vault_manager.get_vault_by_user_pubkey(&request.destination_user_pubkey).await?;
     
     if source_vault.available_balance < request.amount as i64 {
-        return Err(VaultError::InsufficientBalance {
+        return Err(DomainError::InsufficientBalance {
             available: source_vault.available_balance as u64,
             required: request.amount,
-        });
+        }.into());
     }
     
     let operation_id = Uuid::new_v4();
@@ -463,11 +1317,109 @@ async fn transfer_collateral(
     }))
 }
 
+async fn derive_vault(
+    State(state): State<AppState>,
+    Path(user_pubkey): Path<String>,
+    Query(params): Query<DeriveVaultQuery>,
+) -> ApiResult<JsonResponse<VaultDerivation>> {
+    let user = Pubkey::from_str(&user_pubkey)
+        .map_err(|_| DomainError::Validation("Invalid user pubkey".to_string()))?;
+    
+    let derived = derivation::derive_vault(&state.program_id, &user, params.sub_account)?;
+    Ok(JsonResponse(derived))
+}
+
+async fn list_mints(State(state): State<AppState>) -> ApiResult<JsonResponse<Vec<MintConfig>>> {
+    let mints = state.mint_registry.list_mints().await?;
+    Ok(JsonResponse(mints))
+}
+
+async fn get_mint(
+    State(state): State<AppState>,
+    Path(mint_pubkey): Path<String>,
+) -> ApiResult<JsonResponse<MintConfig>> {
+    let mint = state.mint_registry.get_mint(&mint_pubkey).await?;
+    Ok(JsonResponse(mint))
+}
+
+async fn get_fee_schedule(
+    State(state): State<AppState>,
+    Path(mint_pubkey): Path<String>,
+) -> ApiResult<JsonResponse<FeeSchedule>> {
+    Ok(JsonResponse(state.fees.schedule(Some(&mint_pubkey)).await?))
+}
+
+/// Replace a mint's fee tiers (operations credentials only, audited)
+async fn replace_fee_tiers(
+    State(state): State<AppState>,
+    Path(mint_pubkey): Path<String>,
+    headers: axum::http::HeaderMap,
+    Json(tiers): Json<Vec<FeeTierRequest>>,
+) -> ApiResult<JsonResponse<FeeSchedule>> {
+    let actor = operations_credential(&state, &headers).await?;
+    state.fees.replace_tiers(&mint_pubkey, &tiers, &actor.name).await?;
+    
+    Ok(JsonResponse(state.fees.schedule(Some(&mint_pubkey)).await?))
+}
+
+async fn create_fee_promotion(
+    State(state): State<AppState>,
+    Path(mint_pubkey): Path<String>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<FeePromotionRequest>,
+) -> ApiResult<JsonResponse<WithdrawalFeePromotion>> {
+    let actor = operations_credential(&state, &headers).await?;
+    
+    Ok(JsonResponse(state.fees.create_promotion(&mint_pubkey, &request, &actor.name).await?))
+}
+
+async fn end_fee_promotion(
+    State(state): State<AppState>,
+    Path((mint_pubkey, promotion_id)): Path<(String, Uuid)>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<WithdrawalFeePromotion>> {
+    let actor = operations_credential(&state, &headers).await?;
+    
+    Ok(JsonResponse(state.fees.end_promotion(&mint_pubkey, promotion_id, &actor.name).await?))
+}
+
+async fn upsert_mint(
+    State(state): State<AppState>,
+    Path(mint_pubkey): Path<String>,
+    Json(request): Json<UpsertMintRequest>,
+) -> ApiResult<JsonResponse<MintConfig>> {
+    info!("Updating mint configuration: {} ({})", request.symbol, mint_pubkey);
+    
+    let now = Utc::now();
+    let mint = state.mint_registry.upsert_mint(&MintConfig {
+        mint_pubkey,
+        symbol: request.symbol,
+        decimals: request.decimals,
+        enabled: request.enabled,
+        min_deposit: request.min_deposit,
+        withdrawal_fee: request.withdrawal_fee,
+        oracle_feed_id: request.oracle_feed_id,
+        created_at: now,
+        updated_at: now,
+    }).await?;
+    
+    Ok(JsonResponse(mint))
+}
+
+async fn get_withdrawal_status(
+    State(state): State<AppState>,
+    Path((user_pubkey, transaction_id)): Path<(String, Uuid)>,
+) -> ApiResult<JsonResponse<WithdrawalQueueStatus>> {
+    let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
+    let status = state.transaction_manager.get_withdrawal_status(vault.id, transaction_id).await?;
+    Ok(JsonResponse(status))
+}
+
 async fn get_vault_transactions(
     State(state): State<AppState>,
     Path(user_pubkey): Path<String>,
     Query(params): Query<ListTransactionsQuery>,
-) -> Result<JsonResponse<Vec<TransactionRecord>>, VaultError> {
+) -> ApiResult<JsonResponse<Vec<TransactionRecord>>> {
     let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
     
     let limit = params.limit.unwrap_or(50).min(100) as i64;
@@ -487,10 +1439,110 @@ async fn get_vault_transactions(
     Ok(JsonResponse(transactions))
 }
 
+async fn get_tax_report(
+    State(state): State<AppState>,
+    Path(user_pubkey): Path<String>,
+    Query(query): Query<TaxReportQuery>,
+) -> ApiResult<Response> {
+    let current_year = Utc::now().year();
+    let year = query.year.unwrap_or(current_year);
+    if !(2020..=current_year).contains(&year) {
+        return Err(DomainError::Validation(format!("Tax year must be between 2020 and {}", current_year)).into());
+    }
+    
+    let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
+    let mint = state.mint_registry.resolve(None).await?;
+    let ledger = state.transaction_manager.get_confirmed_ledger(vault.id).await?;
+    let report: TaxReport = tax::build_tax_report(&vault, &ledger, &mint, year);
+    
+    match query.format.as_deref().unwrap_or("json") {
+        "json" => Ok(JsonResponse(report).into_response()),
+        "csv" => {
+            let filename = format!("form-8949-{}-{}.csv", user_pubkey, year);
+            Ok((
+                [
+                    (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                    (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+                ],
+                tax::form_8949_csv(&report, &mint),
+            ).into_response())
+        }
+        other => Err(DomainError::Validation(format!("Unsupported format `{}`; use json or csv", other)).into()),
+    }
+}
+
+async fn get_twab(
+    State(state): State<AppState>,
+    Path(user_pubkey): Path<String>,
+    Query(query): Query<TwabQuery>,
+) -> ApiResult<JsonResponse<TwabReport>> {
+    let epoch_seconds = query.epoch_seconds.unwrap_or(twab::DEFAULT_EPOCH_SECONDS);
+    if epoch_seconds < twab::MIN_EPOCH_SECONDS {
+        return Err(DomainError::Validation(format!("epoch_seconds must be at least {}", twab::MIN_EPOCH_SECONDS)).into());
+    }
+    let now = Utc::now();
+    let to = query.to.unwrap_or(now);
+    let from = query.from.unwrap_or_else(|| to - chrono::Duration::seconds(4 * epoch_seconds));
+    if from > to {
+        return Err(DomainError::Validation("from must not be after to".to_string()).into());
+    }
+    if twab::epoch_index(to, epoch_seconds) - twab::epoch_index(from, epoch_seconds) >= twab::MAX_EPOCHS {
+        return Err(DomainError::Validation(format!("At most {} epochs per request", twab::MAX_EPOCHS)).into());
+    }
+    
+    let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
+    let ledger = state.transaction_manager.get_confirmed_ledger(vault.id).await?;
+    Ok(JsonResponse(twab::build_twab_report(vault.id, &ledger, from, to, epoch_seconds, now)))
+}
+
+/// Balance state after each confirmed record, to find the one that introduced a discrepancy
+async fn replay_vault_ledger(
+    State(state): State<AppState>,
+    Path(user_pubkey): Path<String>,
+    Query(query): Query<ReplayQuery>,
+) -> ApiResult<JsonResponse<LedgerReplay>> {
+    let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
+    let ledger = state.transaction_manager.get_confirmed_ledger(vault.id).await?;
+    Ok(JsonResponse(reconciliation::build_ledger_replay(&vault, &ledger, query.from, query.until)?))
+}
+
+async fn get_onchain_activity(
+    State(state): State<AppState>,
+    Path(user_pubkey): Path<String>,
+    Query(query): Query<OnchainActivityQuery>,
+) -> ApiResult<JsonResponse<OnchainActivity>> {
+    let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
+    let vault_pubkey = Pubkey::from_str(&vault.vault_pubkey)
+        .map_err(|_| VaultError::from(ChainError::InvalidAccountData(format!("Invalid vault address {}", vault.vault_pubkey))))?;
+    let limit = query.limit
+        .unwrap_or(onchain_activity::DEFAULT_ACTIVITY_LIMIT)
+        .clamp(1, onchain_activity::MAX_ACTIVITY_LIMIT);
+    
+    let page = state.transaction_builder
+        .fetch_signature_page(vault_pubkey, query.before.as_deref(), None, limit, RpcMethodClass::Read)
+        .await?;
+    let mut transactions = Vec::with_capacity(page.len());
+    for entry in &page {
+        transactions.push(state.transaction_builder.fetch_onchain_transaction(entry, RpcMethodClass::Read).await?);
+    }
+    
+    let signatures: Vec<String> = page.iter().map(|entry| entry.signature.clone()).collect();
+    let records = state.transaction_manager.get_vault_transactions_by_signatures(vault.id, &signatures).await?;
+    let entries = onchain_activity::merge_activity(transactions, &records);
+    
+    Ok(JsonResponse(OnchainActivity {
+        user_pubkey: vault.user_pubkey,
+        vault_pubkey: vault.vault_pubkey,
+        external_count: entries.iter().filter(|entry| entry.external).count(),
+        next_before: page.last().filter(|_| page.len() == limit).map(|entry| entry.signature.clone()),
+        entries,
+    }))
+}
+
 async fn get_transaction(
     State(state): State<AppState>,
     Path(transaction_id): Path<Uuid>,
-) -> Result<JsonResponse<TransactionRecord>, VaultError> {
+) -> ApiResult<JsonResponse<TransactionRecord>> {
     let transaction = state.transaction_manager.get_transaction_by_id(transaction_id).await?;
     Ok(JsonResponse(transaction))
 }
@@ -499,7 +1551,7 @@ async fn get_balance_snapshots(
     State(state): State<AppState>,
     Path(user_pubkey): Path<String>,
     Query(params): Query<ListTransactionsQuery>,
-) -> Result<JsonResponse<Vec<BalanceSnapshot>>, VaultError> {
+) -> ApiResult<JsonResponse<Vec<BalanceSnapshot>>> {
     let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
     
     let limit = params.limit.unwrap_or(50).min(100) as i64;
@@ -512,26 +1564,38 @@ async fn get_balance_snapshots(
 async fn reconcile_balance(
     State(state): State<AppState>,
     Path(user_pubkey): Path<String>,
-) -> Result<JsonResponse<serde_json::Value>, VaultError> {
+    Query(query): Query<ReconcileQuery>,
+) -> ApiResult<JsonResponse<ReconciliationReport>> {
     let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
     
-    let result = state.balance_tracker.reconcile_balances(vault.id).await?;
+    let report = state.monitor.reconciler()
+        .reconcile(vault.id, query.mode.unwrap_or_default())
+        .await?;
     
-    Ok(JsonResponse(serde_json::json!({
-        "is_consistent": result.is_consistent,
-        "discrepancies": result.discrepancies,
-        "reconciled_at": Utc::now(),
-    })))
+    Ok(JsonResponse(report))
+}
+
+async fn get_reconciliation_history(
+    State(state): State<AppState>,
+    Path(user_pubkey): Path<String>,
+    Query(query): Query<LimitQuery>,
+) -> ApiResult<JsonResponse<Vec<ReconciliationRecord>>> {
+    let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
+    let limit = query.limit.unwrap_or(20).clamp(1, 200);
+    
+    let results = state.monitor.reconciler().recent_results(vault.id, limit).await?;
+    
+    Ok(JsonResponse(results))
 }
 
 async fn update_vault_state(
     State(state): State<AppState>,
     Path(user_pubkey): Path<String>,
     Json(payload): Json<serde_json::Value>,
-) -> Result<JsonResponse<VaultResponse>, VaultError> {
+) -> ApiResult<JsonResponse<VaultResponse>> {
     let is_active = payload.get("is_active")
         .and_then(|v| v.as_bool())
-        .ok_or_else(|| VaultError::ValidationError("Invalid is_active field".to_string()))?;
+        .ok_or_else(|| DomainError::Validation("Invalid is_active field".to_string()))?;
     
     let vault = state.vault_manager.update_vault_state(&user_pubkey, is_active).await?;
     
@@ -558,6 +1622,8 @@ async fn get_system_stats(State(state): State<AppState>) -> JsonResponse<SystemS
             total_value_locked: stats.total_value_locked,
             is_healthy: stats.is_healthy,
             last_reconciliation: stats.last_reconciliation,
+            indexer_lag_slots: stats.indexer_lag_slots,
+            indexer_open_gaps: stats.indexer_open_gaps,
         }),
         Err(e) => {
             error!("Failed to get system stats: {}", e);
@@ -568,6 +1634,8 @@ async fn get_system_stats(State(state): State<AppState>) -> JsonResponse<SystemS
                 total_value_locked: 0,
                 is_healthy: false,
                 last_reconciliation: None,
+                indexer_lag_slots: None,
+                indexer_open_gaps: 0,
             })
         }
     }
@@ -594,6 +1662,155 @@ async fn update_system_config(
     }))
 }
 
+async fn get_latency_breakdown(
+    State(state): State<AppState>,
+    Query(params): Query<LatencyQuery>,
+) -> ApiResult<JsonResponse<LatencyBreakdownResponse>> {
+    let window_minutes = params.window_minutes.unwrap_or(60).clamp(1, 7 * 24 * 60);
+    let since = Utc::now() - chrono::Duration::minutes(window_minutes);
+    
+    let persisted = state.transaction_manager.get_stage_latency(since).await?;
+    
+    Ok(JsonResponse(LatencyBreakdownResponse {
+        live: state.cpi_manager.latency().snapshot(),
+        persisted,
+        window_minutes,
+    }))
+}
+
+async fn get_migration_status(
+    State(state): State<AppState>,
+) -> ApiResult<JsonResponse<MigrationStatusResponse>> {
+    let status = state.schema_manager.status().await?;
+    let drift = state.schema_manager.detect_drift().await?;
+    
+    Ok(JsonResponse(MigrationStatusResponse {
+        is_current: status.is_current() && drift.is_empty(),
+        status,
+        drift,
+    }))
+}
+
+async fn get_cluster_timing(State(state): State<AppState>) -> JsonResponse<ClusterTimingResponse> {
+    let cluster_timing = state.monitor.cluster_timing();
+    let conditions = cluster_timing.current();
+    
+    JsonResponse(ClusterTimingResponse {
+        seconds_to_next_epoch: conditions.as_ref().map(|c| c.time_to_next_epoch(Utc::now()).as_secs()),
+        maintenance_deferred: cluster_timing.maintenance_deferral(),
+        submissions_deferred: cluster_timing.should_defer_submissions(),
+        conditions,
+    })
+}
+
+async fn get_monitor_jobs(
+    State(state): State<AppState>,
+    Query(query): Query<MonitorJobsQuery>,
+) -> ApiResult<JsonResponse<MonitorJobsResponse>> {
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    let jobs = state.monitor.jobs();
+    let statuses = jobs.status(Utc::now()).await?;
+    let runs = jobs.recent_runs(query.job.as_deref(), limit as i64).await?;
+    
+    Ok(JsonResponse(MonitorJobsResponse {
+        all_running: statuses.iter().all(|status| !status.missed),
+        jobs: statuses,
+        runs,
+    }))
+}
+
+/// SLA attainment per operation class; the window defaults to the breach check's
+/// and reaches back up to 31 days for monthly reports
+async fn get_sla_report(
+    State(state): State<AppState>,
+    Query(params): Query<LatencyQuery>,
+) -> ApiResult<JsonResponse<SlaReport>> {
+    let window_minutes = params.window_minutes.map(|minutes| minutes.clamp(1, 31 * 24 * 60));
+    let report = state.monitor.sla().report(Utc::now(), window_minutes).await?;
+    Ok(JsonResponse(report))
+}
+
+async fn get_maintenance_status(State(state): State<AppState>) -> JsonResponse<MaintenanceStatus> {
+    JsonResponse(state.maintenance.status(Utc::now()))
+}
+
+async fn schedule_maintenance(
+    State(state): State<AppState>,
+    Json(request): Json<ScheduleMaintenanceRequest>,
+) -> ApiResult<(StatusCode, JsonResponse<MaintenanceWindow>)> {
+    let window = state.maintenance
+        .schedule(request.starts_at, request.ends_at, &request.reason, request.read_only.unwrap_or(true))
+        .await?;
+    Ok((StatusCode::CREATED, JsonResponse(window)))
+}
+
+async fn cancel_maintenance(
+    State(state): State<AppState>,
+    Path(window_id): Path<Uuid>,
+) -> ApiResult<JsonResponse<MaintenanceWindow>> {
+    Ok(JsonResponse(state.maintenance.cancel(window_id).await?))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StageAuthorityRotationRequest {
+    /// Keypair file for the new authority, readable by the backend
+    pub new_key_path: String,
+    pub batch_size: Option<i32>,
+}
+
+async fn stage_authority_rotation(
+    State(state): State<AppState>,
+    Json(request): Json<StageAuthorityRotationRequest>,
+) -> ApiResult<JsonResponse<AuthorityRotationProgress>> {
+    let rotation = state.authority_rotation.stage(&request.new_key_path, request.batch_size).await?;
+    info!("Staged authority rotation {} to {}", rotation.id, rotation.new_authority);
+    
+    Ok(JsonResponse(state.authority_rotation.progress(rotation.id).await?))
+}
+
+async fn get_authority_rotation(
+    State(state): State<AppState>,
+    Path(rotation_id): Path<Uuid>,
+) -> ApiResult<JsonResponse<AuthorityRotationProgress>> {
+    Ok(JsonResponse(state.authority_rotation.progress(rotation_id).await?))
+}
+
+async fn run_authority_rotation_batch(
+    State(state): State<AppState>,
+    Path(rotation_id): Path<Uuid>,
+) -> ApiResult<JsonResponse<AuthorityRotationProgress>> {
+    Ok(JsonResponse(state.authority_rotation.run_batch(rotation_id).await?))
+}
+
+async fn rollback_authority_rotation(
+    State(state): State<AppState>,
+    Path(rotation_id): Path<Uuid>,
+) -> ApiResult<JsonResponse<AuthorityRotationProgress>> {
+    warn!("Rolling back authority rotation {}", rotation_id);
+    Ok(JsonResponse(state.authority_rotation.rollback(rotation_id).await?))
+}
+
+async fn get_activity_analytics(
+    State(state): State<AppState>,
+    Query(query): Query<ActivityQuery>,
+) -> ApiResult<JsonResponse<ActivityReport>> {
+    let report = state.monitor.analytics().activity(
+        query.from,
+        query.to,
+        query.granularity.unwrap_or_default(),
+        query.top,
+    ).await?;
+    
+    Ok(JsonResponse(report))
+}
+
+async fn get_fee_analytics(
+    State(state): State<AppState>,
+    Query(query): Query<FeeAnalyticsQuery>,
+) -> ApiResult<JsonResponse<FeeReport>> {
+    Ok(JsonResponse(state.monitor.network_fees().report(query.from, query.to, Utc::now()).await?))
+}
+
 async fn get_audit_log(
     State(state): State<AppState>,
     Query(params): Query<ListTransactionsQuery>,
@@ -605,105 +1822,898 @@ async fn get_audit_log(
     }))
 }
 
-// WebSocket handlers
+/// Header carrying the support reason code, e.g. `customer_ticket`
+const SUPPORT_REASON_HEADER: &str = "X-Support-Reason";
 
-async fn metrics_websocket(
-    ws: WebSocketUpgrade,
+/// Optional ticket or incident reference logged alongside the reason
+const SUPPORT_REFERENCE_HEADER: &str = "X-Support-Reference";
+
+async fn support_vault_detail(
     State(state): State<AppState>,
-) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_metrics_websocket(socket, state))
+    Path(user_pubkey): Path<String>,
+    Query(query): Query<LimitQuery>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<SupportVaultDetail>> {
+    let Some(token) = bearer_token(&headers) else {
+        let err = DomainError::Unauthorized("Support credential required".to_string()).into();
+        return Err(reject_credential(&state, &headers, err).await);
+    };
+    let reason = headers.get(SUPPORT_REASON_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| DomainError::Unauthorized(format!("{} header is required", SUPPORT_REASON_HEADER)))?;
+    let reference = headers.get(SUPPORT_REFERENCE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    
+    let access = match state.support_service.authenticate(token, reason, reference).await {
+        Ok(access) => access,
+        Err(e) => return Err(reject_credential(&state, &headers, e).await),
+    };
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    let detail = state.support_service.vault_detail(&access, &user_pubkey, limit).await?;
+    
+    Ok(JsonResponse(detail))
 }
 
-async fn handle_metrics_websocket(socket: WebSocket, state: AppState) {
-    use tokio::time::{interval, Duration};
-    use futures::{SinkExt, StreamExt};
-    
-    let (mut sender, mut _receiver) = socket.split();
-    let mut interval = interval(Duration::from_secs(30));
+/// Operations credential from the bearer token; support credentials are refused
+async fn operations_credential(state: &AppState, headers: &axum::http::HeaderMap) -> ApiResult<SupportCredential> {
+    let result = match bearer_token(headers) {
+        Some(token) => state.support_service.authorize_operations(token).await,
+        None => Err(DomainError::Unauthorized("Operations credential required".to_string()).into()),
+    };
     
-    loop {
-        interval.tick().await;
-        
-        match state.monitor.get_stats().await {
-            Ok(stats) => {
-                let message = serde_json::json!({
-                    "type": "metrics_update",
-                    "data": {
-                        "vault_count": stats.vault_count,
-                        "pending_transactions": stats.pending_transactions,
-                        "failed_transactions_24h": stats.failed_transactions_24h,
-                        "total_value_locked": stats.total_value_locked,
-                        "is_healthy": stats.is_healthy,
-                        "timestamp": Utc::now(),
-                    }
-                });
-                
-                if sender.send(axum::extract::ws::Message::Text(
-                    serde_json::to_string(&message).unwrap()
-                )).await.is_err() {
-                    break;
-                }
-            }
-            Err(e) => {
-                warn!("Failed to get metrics for WebSocket: {}", e);
-                break;
-            }
+    match result {
+        Ok(credential) => Ok(credential),
+        Err(e) => Err(reject_credential(state, headers, e).await),
+    }
+}
+
+/// Record a refused staff credential as a security event; other errors pass through
+async fn reject_credential(state: &AppState, headers: &axum::http::HeaderMap, err: VaultError) -> ApiError {
+    if matches!(err, VaultError::Domain(DomainError::Unauthorized(_))) {
+        let token = bearer_token(headers);
+        match state.support_service.rejection_kind(token).await {
+            Ok(kind) => state.security_events.record(
+                NewSecurityEvent::new(kind, security_events::request_source(forwarded_for(headers)))
+                    .with_details(serde_json::json!({
+                        "credential": token.map(security_events::credential_fingerprint),
+                        "reason": err.to_string(),
+                    })),
+            ).await,
+            Err(e) => warn!("Failed to classify refused credential: {}", e),
         }
     }
+    err.into()
 }
 
-async fn vault_websocket(
-    ws: WebSocketUpgrade,
-    Path(user_pubkey): Path<String>,
+async fn add_vault_note(
     State(state): State<AppState>,
-) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_vault_websocket(socket, user_pubkey, state))
+    Path(user_pubkey): Path<String>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<AddVaultNoteRequest>,
+) -> ApiResult<JsonResponse<VaultNote>> {
+    let actor = operations_credential(&state, &headers).await?;
+    let note = state.case_service.add_note(&actor, &user_pubkey, &request.body, request.case_id).await?;
+    
+    Ok(JsonResponse(note))
 }
 
-async fn handle_vault_websocket(socket: WebSocket, user_pubkey: String, state: AppState) {
-    use tokio::time::{interval, Duration};
-    use futures::{SinkExt, StreamExt};
-    
-    let (mut sender, mut _receiver) = socket.split();
-    let mut interval = interval(Duration::from_secs(10));
+async fn add_vault_tag(
+    State(state): State<AppState>,
+    Path((user_pubkey, tag)): Path<(String, String)>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<Vec<VaultTag>>> {
+    let actor = operations_credential(&state, &headers).await?;
     
-    loop {
-        interval.tick().await;
-        
-        match state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await {
-            Ok(vault) => {
-                let message = serde_json::json!({
-                    "type": "vault_update",
-                    "data": {
-                        "vault_id": vault.id,
-                        "user_pubkey": vault.user_pubkey,
-                        "total_balance": vault.total_balance,
-                        "locked_balance": vault.locked_balance,
-                        "available_balance": vault.available_balance,
-                        "is_active": vault.is_active,
-                        "timestamp": Utc::now(),
-                    }
-                });
-                
-                if sender.send(axum::extract::ws::Message::Text(
-                    serde_json::to_string(&message).unwrap()
-                )).await.is_err() {
-                    break;
-                }
-            }
-            Err(e) => {
-                warn!("Failed to get vault for WebSocket: {}", e);
-                break;
-            }
-        }
-    }
+    Ok(JsonResponse(state.case_service.add_tag(&actor, &user_pubkey, &tag).await?))
 }
 
-// Middleware
-
-async fn rate_limit_middleware(
+async fn remove_vault_tag(
     State(state): State<AppState>,
-    axum::extract::RequestParts { headers, .. }: axum::extract::RequestParts,
+    Path((user_pubkey, tag)): Path<(String, String)>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<Vec<VaultTag>>> {
+    let actor = operations_credential(&state, &headers).await?;
+    
+    Ok(JsonResponse(state.case_service.remove_tag(&actor, &user_pubkey, &tag).await?))
+}
+
+async fn open_vault_case(
+    State(state): State<AppState>,
+    Path(user_pubkey): Path<String>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<OpenVaultCaseRequest>,
+) -> ApiResult<JsonResponse<VaultCase>> {
+    let actor = operations_credential(&state, &headers).await?;
+    
+    Ok(JsonResponse(state.case_service.open_case(&actor, &user_pubkey, &request.title).await?))
+}
+
+async fn close_vault_case(
+    State(state): State<AppState>,
+    Path((user_pubkey, case_id)): Path<(String, Uuid)>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<CloseVaultCaseRequest>,
+) -> ApiResult<JsonResponse<VaultCase>> {
+    let actor = operations_credential(&state, &headers).await?;
+    
+    Ok(JsonResponse(state.case_service.close_case(&actor, &user_pubkey, case_id, &request.resolution).await?))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BulkJobRequest {
+    #[serde(flatten)]
+    pub operation: BulkOperation,
+    pub reason: String,
+    pub batch_size: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExecuteBulkJobRequest {
+    /// Target count from the preview
+    pub confirm_targets: i32,
+}
+
+async fn preview_bulk_job(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<BulkJobRequest>,
+) -> ApiResult<(StatusCode, JsonResponse<BulkJobPreview>)> {
+    let actor = operations_credential(&state, &headers).await?;
+    let preview = state.bulk_operations
+        .preview(&actor, request.operation, &request.reason, request.batch_size)
+        .await?;
+    
+    Ok((StatusCode::CREATED, JsonResponse(preview)))
+}
+
+async fn get_bulk_job(
+    State(state): State<AppState>,
+    Path(job_id): Path<Uuid>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<BulkJobProgress>> {
+    operations_credential(&state, &headers).await?;
+    
+    Ok(JsonResponse(state.bulk_operations.progress(job_id).await?))
+}
+
+async fn execute_bulk_job(
+    State(state): State<AppState>,
+    Path(job_id): Path<Uuid>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<ExecuteBulkJobRequest>,
+) -> ApiResult<JsonResponse<BulkJobProgress>> {
+    let actor = operations_credential(&state, &headers).await?;
+    
+    Ok(JsonResponse(state.bulk_operations.execute(&actor, job_id, request.confirm_targets).await?))
+}
+
+async fn abort_bulk_job(
+    State(state): State<AppState>,
+    Path(job_id): Path<Uuid>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<BulkJobProgress>> {
+    let actor = operations_credential(&state, &headers).await?;
+    
+    Ok(JsonResponse(state.bulk_operations.abort(&actor, job_id).await?))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IncidentRequest {
+    pub runbook: Runbook,
+    pub reason: String,
+    /// Vaults to reconcile and resync; every active vault when omitted
+    pub vault_ids: Option<Vec<Uuid>>,
+    /// Earlier run whose pause this run takes over
+    pub follows: Option<Uuid>,
+}
+
+async fn start_incident_run(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<IncidentRequest>,
+) -> ApiResult<(StatusCode, JsonResponse<IncidentRunDetail>)> {
+    let actor = operations_credential(&state, &headers).await?;
+    let run = state.incidents
+        .start(&actor, request.runbook, &request.reason, request.vault_ids, request.follows)
+        .await?;
+    
+    Ok((StatusCode::CREATED, JsonResponse(run)))
+}
+
+async fn list_incident_runs(
+    State(state): State<AppState>,
+    Query(query): Query<LimitQuery>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<Vec<IncidentRun>>> {
+    operations_credential(&state, &headers).await?;
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    
+    Ok(JsonResponse(state.incidents.list(limit as i64).await?))
+}
+
+async fn get_incident_run(
+    State(state): State<AppState>,
+    Path(run_id): Path<Uuid>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<IncidentRunDetail>> {
+    operations_credential(&state, &headers).await?;
+    
+    Ok(JsonResponse(state.incidents.detail(run_id).await?))
+}
+
+async fn resume_incident_run(
+    State(state): State<AppState>,
+    Path(run_id): Path<Uuid>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<IncidentRunDetail>> {
+    let actor = operations_credential(&state, &headers).await?;
+    
+    Ok(JsonResponse(state.incidents.resume(&actor, run_id).await?))
+}
+
+async fn abort_incident_run(
+    State(state): State<AppState>,
+    Path(run_id): Path<Uuid>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<IncidentRunDetail>> {
+    let actor = operations_credential(&state, &headers).await?;
+    
+    Ok(JsonResponse(state.incidents.abort(&actor, run_id).await?))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScreeningReviewRequest {
+    /// `true` lets one retry of the held operation through; `false` rejects it
+    pub clear: bool,
+    pub note: String,
+}
+
+async fn list_screening_reviews(
+    State(state): State<AppState>,
+    Query(query): Query<LimitQuery>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<Vec<ScreeningDecision>>> {
+    operations_credential(&state, &headers).await?;
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    
+    Ok(JsonResponse(state.screening.pending_reviews(limit as i64).await?))
+}
+
+async fn review_screening_decision(
+    State(state): State<AppState>,
+    Path(decision_id): Path<Uuid>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<ScreeningReviewRequest>,
+) -> ApiResult<JsonResponse<ScreeningDecision>> {
+    let actor = operations_credential(&state, &headers).await?;
+    let decision = state.screening.review(decision_id, request.clear, &actor.name, &request.note).await?;
+    
+    Ok(JsonResponse(decision))
+}
+
+async fn get_vault_screening_decisions(
+    State(state): State<AppState>,
+    Path(user_pubkey): Path<String>,
+    Query(query): Query<LimitQuery>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<Vec<ScreeningDecision>>> {
+    operations_credential(&state, &headers).await?;
+    let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    
+    Ok(JsonResponse(state.screening.vault_decisions(vault.id, limit as i64).await?))
+}
+
+async fn migrate_vault_layout(
+    State(state): State<AppState>,
+    Path(user_pubkey): Path<String>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<VaultLayoutMigrationResponse>> {
+    let actor = operations_credential(&state, &headers).await?;
+    let vault = state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await?;
+    info!("{} migrating account layout of vault {}", actor.name, vault.id);
+    
+    let solana_signature = state.cpi_manager.migrate_vault_layout(vault.id).await?;
+    Ok(JsonResponse(VaultLayoutMigrationResponse { vault_id: vault.id, solana_signature }))
+}
+
+async fn get_indexer_status(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<IndexerStatus>> {
+    operations_credential(&state, &headers).await?;
+    
+    Ok(JsonResponse(state.monitor.indexer_status().await?))
+}
+
+async fn list_indexer_gaps(
+    State(state): State<AppState>,
+    Query(query): Query<LimitQuery>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<Vec<IndexerGap>>> {
+    operations_credential(&state, &headers).await?;
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    
+    Ok(JsonResponse(state.monitor.indexer().gaps(limit as i64).await?))
+}
+
+async fn list_top_rate_limit_clients(
+    State(state): State<AppState>,
+    Query(query): Query<RateLimitUsageQuery>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<Vec<ClientUsage>>> {
+    operations_credential(&state, &headers).await?;
+    let limit = query.limit.unwrap_or(50).clamp(1, 1000);
+    
+    Ok(JsonResponse(state.monitor.abuse().top_clients(query.window(), limit as i64).await?))
+}
+
+async fn list_rate_limit_rejections(
+    State(state): State<AppState>,
+    Query(query): Query<RateLimitUsageQuery>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<Vec<ClientUsage>>> {
+    operations_credential(&state, &headers).await?;
+    let limit = query.limit.unwrap_or(50).clamp(1, 1000);
+    
+    Ok(JsonResponse(state.monitor.abuse().recent_rejections(query.window(), limit as i64).await?))
+}
+
+async fn list_rate_limit_abuse(
+    State(state): State<AppState>,
+    Query(query): Query<RateLimitUsageQuery>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<Vec<AbuseReport>>> {
+    operations_credential(&state, &headers).await?;
+    
+    Ok(JsonResponse(state.monitor.abuse().abuse_reports(query.window()).await?))
+}
+
+async fn list_rate_limit_bans(
+    State(state): State<AppState>,
+    Query(query): Query<RateLimitBanQuery>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<Vec<RateLimitBan>>> {
+    operations_credential(&state, &headers).await?;
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    let active_only = !query.all.unwrap_or(false);
+    
+    Ok(JsonResponse(state.monitor.abuse().bans(query.client_id.as_deref(), active_only, limit as i64).await?))
+}
+
+async fn create_rate_limit_ban(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<BanRequest>,
+) -> ApiResult<(StatusCode, JsonResponse<RateLimitBan>)> {
+    let actor = operations_credential(&state, &headers).await?;
+    let ban = state.monitor.abuse().ban(&actor, request).await?;
+    state.security_events.record(
+        NewSecurityEvent::new(SecurityEventKind::RateLimitBan, ban.client_id.clone())
+            .with_actor(actor.name.clone())
+            .with_details(serde_json::json!({ "ban_id": ban.id, "reason": ban.reason, "expires_at": ban.expires_at })),
+    ).await;
+    
+    Ok((StatusCode::CREATED, JsonResponse(ban)))
+}
+
+async fn lift_rate_limit_ban(
+    State(state): State<AppState>,
+    Path(ban_id): Path<Uuid>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<RateLimitBan>> {
+    let actor = operations_credential(&state, &headers).await?;
+    
+    Ok(JsonResponse(state.monitor.abuse().lift(&actor, ban_id).await?))
+}
+
+async fn list_security_events(
+    State(state): State<AppState>,
+    Query(query): Query<SecurityEventsQuery>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<Vec<SecurityEvent>>> {
+    operations_credential(&state, &headers).await?;
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    
+    Ok(JsonResponse(state.security_events.list(
+        query.event_type.as_deref(),
+        query.source.as_deref(),
+        query.since,
+        limit as i64,
+    ).await?))
+}
+
+async fn list_escalation_rules(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<Vec<EscalationRule>>> {
+    operations_credential(&state, &headers).await?;
+    
+    Ok(JsonResponse(state.monitor.abuse().rules().await?))
+}
+
+async fn save_escalation_rule(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<EscalationRuleRequest>,
+) -> ApiResult<JsonResponse<EscalationRule>> {
+    let actor = operations_credential(&state, &headers).await?;
+    
+    Ok(JsonResponse(state.monitor.abuse().save_rule(&actor, request).await?))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IdentityResponse {
+    pub identity: Option<Identity>,
+    /// Level the identity counts for now, taking status and expiry into account
+    pub effective_level: VerificationLevel,
+}
+
+async fn get_identity(
+    State(state): State<AppState>,
+    Path(user_pubkey): Path<String>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<IdentityResponse>> {
+    operations_credential(&state, &headers).await?;
+    let identity = state.identities.identity(&user_pubkey).await?;
+    
+    Ok(JsonResponse(IdentityResponse {
+        effective_level: identities::effective_level(identity.as_ref(), Utc::now()),
+        identity,
+    }))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IdentityWebhookResponse {
+    /// `false` for redeliveries and results older than the one applied
+    pub applied: bool,
+}
+
+async fn ingest_identity_webhook(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    headers: axum::http::HeaderMap,
+    body: axum::body::Bytes,
+) -> ApiResult<JsonResponse<IdentityWebhookResponse>> {
+    let signature_header = state.identities.provider(&provider)?.signature_header().to_string();
+    let signature = headers.get(signature_header.as_str()).and_then(|v| v.to_str().ok());
+    let identity = match state.identities.ingest_webhook(&provider, signature, &body).await {
+        Ok(identity) => identity,
+        Err(e @ VaultError::Domain(DomainError::Unauthorized(_))) => {
+            state.security_events.record(
+                NewSecurityEvent::new(SecurityEventKind::WebhookSignatureInvalid, security_events::request_source(forwarded_for(&headers)))
+                    .with_details(serde_json::json!({ "provider": provider, "reason": e.to_string() })),
+            ).await;
+            return Err(e.into());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    
+    Ok(JsonResponse(IdentityWebhookResponse { applied: identity.is_some() }))
+}
+
+async fn request_export(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<ExportRequest>,
+) -> ApiResult<JsonResponse<DataExport>> {
+    let actor = operations_credential(&state, &headers).await?;
+    
+    Ok(JsonResponse(state.exports.request(&actor, request).await?))
+}
+
+async fn list_exports(
+    State(state): State<AppState>,
+    Query(query): Query<LimitQuery>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<Vec<DataExport>>> {
+    operations_credential(&state, &headers).await?;
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    
+    Ok(JsonResponse(state.exports.list(limit as i64).await?))
+}
+
+async fn get_export(
+    State(state): State<AppState>,
+    Path(export_id): Path<Uuid>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<ExportStatus>> {
+    operations_credential(&state, &headers).await?;
+    
+    Ok(JsonResponse(state.exports.status(export_id).await?))
+}
+
+async fn list_event_consumers(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<Vec<ConsumerStatus>>> {
+    operations_credential(&state, &headers).await?;
+    
+    Ok(JsonResponse(state.monitor.outbox().consumers().await?))
+}
+
+async fn register_event_consumer(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<RegisterConsumerRequest>,
+) -> ApiResult<(StatusCode, JsonResponse<EventConsumer>)> {
+    let actor = operations_credential(&state, &headers).await?;
+    let consumer = state.monitor.outbox().register_consumer(request).await?;
+    info!("{} registered event consumer {}", actor.name, consumer.name);
+    
+    Ok((StatusCode::CREATED, JsonResponse(consumer)))
+}
+
+/// Events after the consumer's cursor; repeated until acknowledged
+async fn poll_events(
+    State(state): State<AppState>,
+    Path(consumer): Path<String>,
+    Query(query): Query<LimitQuery>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<EventBatch>> {
+    operations_credential(&state, &headers).await?;
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    
+    Ok(JsonResponse(state.monitor.outbox().poll(&consumer, limit as i64).await?))
+}
+
+async fn acknowledge_events(
+    State(state): State<AppState>,
+    Path(consumer): Path<String>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<AcknowledgeEventsRequest>,
+) -> ApiResult<JsonResponse<EventConsumer>> {
+    operations_credential(&state, &headers).await?;
+    
+    Ok(JsonResponse(state.monitor.outbox().acknowledge(&consumer, request.seq).await?))
+}
+
+/// Vault token accounts currently seen with a delegate or close authority
+async fn list_token_authority_findings(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<Vec<TokenAuthorityFinding>>> {
+    operations_credential(&state, &headers).await?;
+    
+    Ok(JsonResponse(state.monitor.token_authority_guard().open_findings().await?))
+}
+
+/// Unsigned revoke transaction for the config admin to approve and sign
+async fn prepare_token_revocation(
+    State(state): State<AppState>,
+    Path(finding_id): Path<Uuid>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<PreparedTokenRevocation>> {
+    let actor = operations_credential(&state, &headers).await?;
+    let prepared = state.monitor.token_authority_guard().prepare_revocation(finding_id).await?;
+    info!("{} prepared delegate revocation for finding {}", actor.name, finding_id);
+    
+    Ok(JsonResponse(prepared))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DormancyQuery {
+    /// `notified`, `swept`, `reclaimed` or `cleared`; flagged and swept vaults when omitted
+    pub status: Option<String>,
+    pub limit: Option<i32>,
+}
+
+/// Vaults flagged as dormant or already swept
+async fn list_dormant_vaults(
+    State(state): State<AppState>,
+    Query(query): Query<DormancyQuery>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<Vec<VaultDormancy>>> {
+    operations_credential(&state, &headers).await?;
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    
+    Ok(JsonResponse(state.dormancy.list(query.status.as_deref(), limit as i64).await?))
+}
+
+/// Unsigned sweep transaction for the config admin to approve and sign
+async fn prepare_dormancy_sweep(
+    State(state): State<AppState>,
+    Path(user_pubkey): Path<String>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<JsonResponse<PreparedDormancySweep>> {
+    let actor = operations_credential(&state, &headers).await?;
+    let prepared = state.dormancy.prepare_sweep(&user_pubkey).await?;
+    info!("{} prepared dormancy sweep of vault {}", actor.name, prepared.dormancy.vault_id);
+    
+    Ok(JsonResponse(prepared))
+}
+
+/// Unsigned transaction, for the owner to sign, returning swept funds to their vault
+async fn prepare_dormancy_reclaim(
+    State(state): State<AppState>,
+    Path(user_pubkey): Path<String>,
+) -> ApiResult<JsonResponse<PreparedDormancyReclaim>> {
+    Ok(JsonResponse(state.dormancy.prepare_reclaim(&user_pubkey).await?))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProgramInfoQuery {
+    /// Hash of the IDL the client bundles, to learn whether it is current
+    pub idl_sha256: Option<String>,
+}
+
+/// IDL JSON; the ETag is its SHA-256, so `If-None-Match` answers 304 while it is current
+async fn get_program_idl(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Response> {
+    let idl = state.program_idl.as_ref()
+        .ok_or_else(|| DomainError::NotFound("Program IDL".to_string()))?;
+    let etag = format!("\"{}\"", idl.sha256);
+    
+    let current = headers.get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .map_or(false, |value| value.split(',').any(|tag| tag.trim() == etag));
+    if current {
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response());
+    }
+    
+    Ok(([(header::ETAG, etag)], JsonResponse(idl.json.clone())).into_response())
+}
+
+async fn get_program_info(
+    State(state): State<AppState>,
+    Query(query): Query<ProgramInfoQuery>,
+) -> JsonResponse<ProgramInfo> {
+    JsonResponse(ProgramInfo::new(&state.program_id, state.program_idl.as_deref(), query.idl_sha256.as_deref()))
+}
+
+// WebSocket handlers
+
+async fn metrics_websocket(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_metrics_websocket(socket, state))
+}
+
+async fn handle_metrics_websocket(socket: WebSocket, state: AppState) {
+    let subscription = Subscription {
+        channels: [Channel::System].into_iter().collect(),
+        vault_id: None,
+    };
+    serve_event_stream(socket, state, subscription).await;
+}
+
+async fn vault_websocket(
+    ws: WebSocketUpgrade,
+    Path(user_pubkey): Path<String>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_vault_websocket(socket, user_pubkey, state))
+}
+
+async fn handle_vault_websocket(socket: WebSocket, user_pubkey: String, state: AppState) {
+    let vault = match state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await {
+        Ok(vault) => vault,
+        Err(e) => {
+            warn!("Failed to get vault for WebSocket: {}", e);
+            return;
+        }
+    };
+    
+    let subscription = Subscription {
+        channels: [Channel::Balances, Channel::Transactions].into_iter().collect(),
+        vault_id: Some(vault.id),
+    };
+    serve_event_stream(socket, state, subscription).await;
+}
+
+async fn event_stream_websocket(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| serve_event_stream(socket, state, Subscription::default()))
+}
+
+async fn send_stream_message<S>(sender: &mut S, message: &ServerMessage) -> std::result::Result<(), axum::Error>
+where
+    S: futures::Sink<axum::extract::ws::Message, Error = axum::Error> + Unpin,
+{
+    use futures::SinkExt;
+    
+    let text = serde_json::to_string(message).unwrap_or_default();
+    sender.send(axum::extract::ws::Message::Text(text)).await
+}
+
+/// Speak the event stream protocol until the client disconnects
+async fn serve_event_stream(socket: WebSocket, state: AppState, initial: Subscription) {
+    use futures::StreamExt;
+    use axum::extract::ws::Message;
+    
+    let (mut sender, mut receiver) = socket.split();
+    let stream = state.event_stream.clone();
+    let mut head = stream.watch_head();
+    let mut subscription = initial;
+    let mut cursor = stream.head();
+    let mut unacked: std::collections::VecDeque<u64> = std::collections::VecDeque::new();
+    
+    let hello = ServerMessage::Hello {
+        protocol_version: PROTOCOL_VERSION,
+        channels: subscription.sorted_channels(),
+        resume_token: stream.token_at(cursor),
+    };
+    if send_stream_message(&mut sender, &hello).await.is_err() {
+        return;
+    }
+    
+    loop {
+        // Deliver as much as the client's ack window allows
+        if unacked.len() < MAX_UNACKED_EVENTS {
+            let outgoing = match stream.events_after(cursor, &subscription, MAX_UNACKED_EVENTS - unacked.len()).await {
+                Ok((events, next_cursor)) => {
+                    cursor = next_cursor;
+                    events.into_iter()
+                        .map(|event| ServerMessage::Event {
+                            seq: event.seq,
+                            channel: event.event.channel(),
+                            resume_token: stream.token_at(event.seq),
+                            published_at: event.published_at,
+                            event: event.event,
+                        })
+                        .collect()
+                }
+                Err(gap) => {
+                    cursor = stream.head();
+                    vec![ServerMessage::Reset {
+                        reason: format!("Events before {} are no longer buffered", gap.oldest_buffered),
+                        resume_token: stream.token_at(cursor),
+                    }]
+                }
+            };
+            
+            for message in outgoing {
+                if let ServerMessage::Event { seq, .. } = &message {
+                    unacked.push_back(*seq);
+                }
+                if send_stream_message(&mut sender, &message).await.is_err() {
+                    return;
+                }
+            }
+        }
+        
+        tokio::select! {
+            changed = head.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+            }
+            message = receiver.next() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+                
+                let reply = match serde_json::from_str::<ClientMessage>(&text) {
+                    Ok(ClientMessage::Ack { seq }) => {
+                        while unacked.front().map_or(false, |sent| *sent <= seq) {
+                            unacked.pop_front();
+                        }
+                        continue;
+                    }
+                    Ok(ClientMessage::Subscribe { id, channels, vault, resume_token }) => {
+                        let vault_id = match vault {
+                            Some(user_pubkey) => match state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await {
+                                Ok(vault) => Some(vault.id),
+                                Err(_) => {
+                                    let error = ServerMessage::Error { id, message: format!("Unknown vault: {}", user_pubkey) };
+                                    if send_stream_message(&mut sender, &error).await.is_err() {
+                                        break;
+                                    }
+                                    continue;
+                                }
+                            },
+                            None => subscription.vault_id,
+                        };
+                        
+                        if subscription.vault_id.is_some() && vault_id != subscription.vault_id {
+                            ServerMessage::Error { id, message: "A connection follows a single vault".to_string() }
+                        } else if vault_id.is_none() && channels.iter().any(Channel::is_per_vault) {
+                            ServerMessage::Error { id, message: "`vault` is required for balances and transactions".to_string() }
+                        } else {
+                            subscription.vault_id = vault_id;
+                            subscription.channels.extend(channels);
+                            
+                            let mut resumed = false;
+                            if let Some(token) = resume_token {
+                                match stream.resume_cursor(&token) {
+                                    Ok(seq) => {
+                                        cursor = seq;
+                                        resumed = true;
+                                    }
+                                    Err(reason) => {
+                                        cursor = stream.head();
+                                        let reset = ServerMessage::Reset { reason, resume_token: stream.token_at(cursor) };
+                                        if send_stream_message(&mut sender, &reset).await.is_err() {
+                                            break;
+                                        }
+                                    }
+                                }
+                            }
+                            
+                            ServerMessage::Subscribed { id, channels: subscription.sorted_channels(), resumed }
+                        }
+                    }
+                    Ok(ClientMessage::Unsubscribe { id, channels }) => {
+                        for channel in &channels {
+                            subscription.channels.remove(channel);
+                        }
+                        ServerMessage::Subscribed { id, channels: subscription.sorted_channels(), resumed: false }
+                    }
+                    Err(e) => ServerMessage::Error { id: None, message: format!("Invalid message: {}", e) },
+                };
+                
+                if send_stream_message(&mut sender, &reply).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+async fn withdrawal_websocket(
+    ws: WebSocketUpgrade,
+    Path((user_pubkey, transaction_id)): Path<(String, Uuid)>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_withdrawal_websocket(socket, user_pubkey, transaction_id, state))
+}
+
+/// Push a message each time the withdrawal's status or queue position changes; close once it is final
+async fn handle_withdrawal_websocket(socket: WebSocket, user_pubkey: String, transaction_id: Uuid, state: AppState) {
+    use tokio::time::{interval, Duration};
+    use futures::{SinkExt, StreamExt};
+    
+    let (mut sender, mut _receiver) = socket.split();
+    let mut interval = interval(Duration::from_secs(2));
+    let mut last_sent: Option<(String, Option<i64>)> = None;
+    
+    let vault = match state.vault_manager.get_vault_by_user_pubkey(&user_pubkey).await {
+        Ok(vault) => vault,
+        Err(e) => {
+            warn!("Failed to get vault for withdrawal WebSocket: {}", e);
+            return;
+        }
+    };
+    
+    loop {
+        interval.tick().await;
+        
+        let status = match state.transaction_manager.get_withdrawal_status(vault.id, transaction_id).await {
+            Ok(status) => status,
+            Err(e) => {
+                warn!("Failed to get withdrawal status for WebSocket: {}", e);
+                break;
+            }
+        };
+        
+        let current = (format!("{:?}", status.status), status.queue_position);
+        if last_sent.as_ref() != Some(&current) {
+            if send_stream_message(&mut sender, &ServerMessage::WithdrawalStatus(status.clone())).await.is_err() {
+                break;
+            }
+            last_sent = Some(current);
+        }
+        
+        if status.is_terminal() {
+            let _ = sender.close().await;
+            break;
+        }
+    }
+}
+
+// Middleware
+
+async fn rate_limit_middleware(
+    State(state): State<AppState>,
+    axum::extract::RequestParts { headers, .. }: axum::extract::RequestParts,
     next: middleware::Next,
 ) -> Result<Response, StatusCode> {
     // Extract client identifier (API key or IP address)
@@ -717,6 +2727,10 @@ async fn rate_limit_middleware(
     // Check rate limit
     match state.rate_limit_repo.consume_tokens(&client_id, TOKENS_PER_REQUEST, MAX_TOKENS, REFILL_RATE).await {
         Ok(result) => {
+            if let Some(banned_until) = result.banned_until {
+                let retry_after = (banned_until - Utc::now()).num_seconds().max(1);
+                return Ok((StatusCode::TOO_MANY_REQUESTS, [(header::RETRY_AFTER, retry_after.to_string())]).into_response());
+            }
             if result.allowed {
                 // Request is allowed, proceed
                 let mut response = next.run(axum::extract::Request::from_parts(
@@ -768,44 +2782,285 @@ async fn rate_limit_middleware(
     }
 }
 
-fn extract_client_identifier(headers: &axum::http::request::Parts) -> String {
-    // Try to get API key from Authorization header first
-    if let Some(auth_header) = headers.headers.get("Authorization") {
-        if let Ok(auth_str) = auth_header.to_str() {
-            if auth_str.starts_with("Bearer ") {
-                return auth_str[7..].to_string(); // Return the API key
+/// Support credentials may only read: reject any other method before routing
+async fn support_read_only_middleware(
+    State(security_log): State<Arc<SecurityEventLog>>,
+    request: axum::extract::Request,
+    next: middleware::Next,
+) -> std::result::Result<Response, StatusCode> {
+    let is_read = matches!(*request.method(), axum::http::Method::GET | axum::http::Method::HEAD);
+    if let Some(token) = bearer_token(request.headers()).filter(|token| !is_read && support::is_support_token(token)) {
+        warn!("Rejected {} {} made with a support credential", request.method(), request.uri().path());
+        security_log.record(
+            NewSecurityEvent::new(SecurityEventKind::ScopeViolation, security_events::request_source(forwarded_for(request.headers())))
+                .with_details(serde_json::json!({
+                    "credential": security_events::credential_fingerprint(token),
+                    "method": request.method().as_str(),
+                    "path": request.uri().path(),
+                    "reason": "Support credentials are read-only",
+                })),
+        ).await;
+        return Err(StatusCode::FORBIDDEN);
+    }
+    
+    Ok(next.run(request).await)
+}
+
+/// Record successful writes to `/admin`, `/ops` and `/system` endpoints as security events
+async fn admin_action_middleware(
+    State(security_log): State<Arc<SecurityEventLog>>,
+    request: axum::extract::Request,
+    next: middleware::Next,
+) -> Response {
+    use axum::http::Method;
+    
+    let is_write = matches!(*request.method(), Method::POST | Method::PUT | Method::PATCH | Method::DELETE);
+    let path = request.uri().path().to_string();
+    if !is_write || !["/admin/", "/ops/", "/system/"].iter().any(|prefix| path.starts_with(prefix)) {
+        return next.run(request).await;
+    }
+    
+    let method = request.method().to_string();
+    let source = security_events::request_source(forwarded_for(request.headers()));
+    let credential = bearer_token(request.headers()).map(security_events::credential_fingerprint);
+    let response = next.run(request).await;
+    
+    if response.status().is_success() {
+        security_log.record(
+            NewSecurityEvent::new(SecurityEventKind::AdminAction, source)
+                .with_details(serde_json::json!({
+                    "credential": credential,
+                    "method": method,
+                    "path": path,
+                    "status": response.status().as_u16(),
+                })),
+        ).await;
+    }
+    response
+}
+
+/// Announce maintenance in response headers and reject writes during a read-only window.
+///
+/// `/system/maintenance` stays writable so a window can be cancelled while it is active,
+/// and `/admin/incident` so an incident run can lift the pause it opened.
+async fn maintenance_middleware(
+    State(maintenance): State<Arc<MaintenanceService>>,
+    request: axum::extract::Request,
+    next: middleware::Next,
+) -> Response {
+    let now = Utc::now();
+    let status = maintenance.status(now);
+    
+    let is_read = matches!(*request.method(), axum::http::Method::GET | axum::http::Method::HEAD | axum::http::Method::OPTIONS);
+    let path = request.uri().path();
+    let exempt = path.starts_with("/system/maintenance") || path.starts_with("/admin/incident");
+    let mut response = if status.read_only && !is_read && !exempt {
+        warn!("Rejected {} {} during maintenance", request.method(), request.uri().path());
+        let mut response = ApiError {
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            error: "Maintenance in progress",
+            message: status.active.as_ref().map_or_else(String::new, |w| w.reason.clone()),
+        }.into_response();
+        if let Some(window) = &status.active {
+            let retry_after = (window.ends_at - now).num_seconds().max(1);
+            response.headers_mut().insert(header::RETRY_AFTER, header::HeaderValue::from(retry_after));
+        }
+        response
+    } else {
+        next.run(request).await
+    };
+    
+    let headers = response.headers_mut();
+    if let Some(window) = &status.active {
+        if let Ok(value) = header::HeaderValue::from_str(&window.ends_at.to_rfc3339()) {
+            headers.insert("X-Maintenance-Until", value);
+        }
+    }
+    if let Some(window) = status.upcoming.first() {
+        if let Ok(value) = header::HeaderValue::from_str(&window.starts_at.to_rfc3339()) {
+            headers.insert("X-Maintenance-Start", value);
+        }
+    }
+    response
+}
+
+/// Replay the stored response of a mutating request retried with its `Idempotency-Key`.
+///
+/// Runs inside the rate limiter, read-only and maintenance checks, so requests
+/// they turn away never claim a key. Semantics are documented in `crate::idempotency`.
+async fn idempotency_middleware(
+    State(idempotency): State<Arc<IdempotencyService>>,
+    request: axum::extract::Request,
+    next: middleware::Next,
+) -> Response {
+    use axum::http::Method;
+    
+    let is_mutating = matches!(*request.method(), Method::POST | Method::PUT | Method::PATCH | Method::DELETE);
+    let key = request.headers().get(idempotency::IDEMPOTENCY_KEY_HEADER).and_then(|v| v.to_str().ok()).map(str::to_string);
+    let Some(key) = key.filter(|_| is_mutating) else {
+        return next.run(request).await;
+    };
+    
+    let (parts, body) = request.into_parts();
+    let body = match axum::body::to_bytes(body, idempotency::MAX_REQUEST_BYTES).await {
+        Ok(body) => body,
+        Err(_) => {
+            return ApiError {
+                status: StatusCode::PAYLOAD_TOO_LARGE,
+                error: "Request too large",
+                message: format!("Requests with an Idempotency-Key are limited to {} bytes", idempotency::MAX_REQUEST_BYTES),
+            }.into_response();
+        }
+    };
+    let client_id = extract_client_identifier(&parts);
+    let method = parts.method.to_string();
+    let path = parts.uri.path_and_query().map_or_else(|| parts.uri.path().to_string(), |p| p.to_string());
+    
+    match idempotency.claim(&client_id, &key, &method, &path, &body).await {
+        Ok(IdempotencyClaim::Claimed) => {}
+        Ok(IdempotencyClaim::Completed(stored)) => {
+            info!("Replayed {} {} for idempotency key {}", method, path, key);
+            let mut response = (StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK), stored.body).into_response();
+            if let Some(content_type) = stored.content_type.and_then(|v| header::HeaderValue::from_str(&v).ok()) {
+                response.headers_mut().insert(header::CONTENT_TYPE, content_type);
             }
+            response.headers_mut().insert(idempotency::IDEMPOTENT_REPLAYED_HEADER, header::HeaderValue::from_static("true"));
+            return response;
+        }
+        Ok(IdempotencyClaim::InProgress) => {
+            let mut response = ApiError {
+                status: StatusCode::CONFLICT,
+                error: idempotency::IN_PROGRESS_ERROR,
+                message: format!("The request with idempotency key {} has not finished", key),
+            }.into_response();
+            response.headers_mut().insert(header::RETRY_AFTER, header::HeaderValue::from(1));
+            return response;
         }
+        Ok(IdempotencyClaim::Mismatch) => {
+            return ApiError {
+                status: StatusCode::UNPROCESSABLE_ENTITY,
+                error: "Idempotency key reused",
+                message: format!("Idempotency key {} was used for a different request", key),
+            }.into_response();
+        }
+        Err(e) => return ApiError::from(e).into_response(),
     }
     
-    // Fallback to IP address (would need to be extracted from connection info in real implementation)
-    // For now, return a default identifier
-    "default_client".to_string()
+    let response = next.run(axum::extract::Request::from_parts(parts, axum::body::Body::from(body))).await;
+    let (parts, body) = response.into_parts();
+    let body = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            error!("Failed to read response of {} {} for idempotency key {}: {}", method, path, key, e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let stored = StoredResponse {
+        status: parts.status.as_u16(),
+        content_type: parts.headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()).map(str::to_string),
+        body: body.to_vec(),
+    };
+    if let Err(e) = idempotency.finish(&client_id, &key, &stored).await {
+        // The request ran; retries get 409 until the key's lock lapses, then run again
+        error!("Failed to store response of {} {} for idempotency key {}: {}", method, path, key, e);
+    }
+    
+    Response::from_parts(parts, axum::body::Body::from(body))
+}
+
+fn bearer_token(headers: &axum::http::HeaderMap) -> Option<&str> {
+    headers.get("Authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+}
+
+fn forwarded_for(headers: &axum::http::HeaderMap) -> Option<&str> {
+    headers.get("X-Forwarded-For").and_then(|value| value.to_str().ok())
+}
+
+/// Rate limiter key: hashed API key, else the client address (see `abuse::client_identifier`)
+fn extract_client_identifier(headers: &axum::http::request::Parts) -> String {
+    abuse::client_identifier(bearer_token(&headers.headers), forwarded_for(&headers.headers))
 }
 
 // Error handling
 
-impl IntoResponse for VaultError {
+pub type ApiResult<T> = std::result::Result<T, ApiError>;
+
+/// HTTP-facing error. Status codes are decided here and nowhere else.
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub error: &'static str,
+    pub message: String,
+}
+
+impl ApiError {
+    /// Map a service error onto its HTTP status and stable error label.
+    ///
+    /// Every match is exhaustive on purpose: adding a variant must force a decision here.
+    pub fn classify(err: &VaultError) -> (StatusCode, &'static str) {
+        match err {
+            VaultError::Domain(domain) => match domain {
+                DomainError::NotFound(_) => (StatusCode::NOT_FOUND, "Resource not found"),
+                DomainError::VaultAlreadyExists(_) => (StatusCode::CONFLICT, "Vault already exists"),
+                DomainError::InsufficientBalance { .. } => (StatusCode::BAD_REQUEST, "Insufficient balance"),
+                DomainError::InsufficientLockedBalance { .. } => (StatusCode::BAD_REQUEST, "Insufficient locked balance"),
+                DomainError::InvalidVaultState(_) => (StatusCode::CONFLICT, "Invalid vault state"),
+                DomainError::InvariantViolation(_) => (StatusCode::UNPROCESSABLE_ENTITY, "Balance invariant violated"),
+                DomainError::Validation(_) => (StatusCode::BAD_REQUEST, "Validation error"),
+                DomainError::Unauthorized(_) => (StatusCode::FORBIDDEN, "Unauthorized"),
+                DomainError::RateLimitExceeded(_) => (StatusCode::TOO_MANY_REQUESTS, "Rate limit exceeded"),
+                DomainError::QuotaExceeded(_) => (StatusCode::TOO_MANY_REQUESTS, "Pending quota exceeded"),
+                DomainError::ConcurrentConflict(_) => (StatusCode::CONFLICT, "Concurrent operation conflict"),
+                DomainError::ScreeningBlocked(_) => (StatusCode::FORBIDDEN, "Blocked by screening"),
+                DomainError::ScreeningHeld(_) => (StatusCode::CONFLICT, "Held for screening review"),
+                DomainError::UnderMargined(_) => (StatusCode::CONFLICT, "Open positions would be under-margined"),
+                DomainError::VerificationRequired(_) => (StatusCode::FORBIDDEN, "Identity verification required"),
+            },
+            VaultError::Storage(storage) => match storage {
+                StorageError::Database(_) | StorageError::Query(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database error"),
+                StorageError::NotFound(_) => (StatusCode::NOT_FOUND, "Resource not found"),
+            },
+            VaultError::Chain(chain) => match chain {
+                ChainError::Client(_) | ChainError::Network(_) => (StatusCode::SERVICE_UNAVAILABLE, "Network error"),
+                ChainError::Timeout(_) => (StatusCode::GATEWAY_TIMEOUT, "Timeout"),
+                ChainError::RateLimited(_) => (StatusCode::SERVICE_UNAVAILABLE, "RPC rate limited"),
+                ChainError::Signer(_) | ChainError::TransactionFailed(_) => (StatusCode::BAD_GATEWAY, "Transaction error"),
+                ChainError::InvalidAccountData(_) => (StatusCode::BAD_GATEWAY, "Invalid on-chain account"),
+            },
+            VaultError::Configuration(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Configuration error"),
+            VaultError::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Internal error"),
+        }
+    }
+}
+
+impl From<VaultError> for ApiError {
+    fn from(err: VaultError) -> Self {
+        let (status, error) = Self::classify(&err);
+        Self {
+            status,
+            error,
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<DomainError> for ApiError {
+    fn from(err: DomainError) -> Self {
+        VaultError::from(err).into()
+    }
+}
+
+impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
-        let (status, error_message) = match &self {
-            VaultError::NotFound(_) => (StatusCode::NOT_FOUND, "Resource not found"),
-            VaultError::InsufficientBalance { .. } => (StatusCode::BAD_REQUEST, "Insufficient balance"),
-            VaultError::InsufficientLockedBalance { .. } => (StatusCode::BAD_REQUEST, "Insufficient locked balance"),
-            VaultError::ConcurrentConflict(_) => (StatusCode::CONFLICT, "Concurrent operation conflict"),
-            VaultError::ValidationError(_) => (StatusCode::BAD_REQUEST, "Validation error"),
-            VaultError::TransactionError(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Transaction error"),
-            VaultError::NetworkError(_) => (StatusCode::SERVICE_UNAVAILABLE, "Network error"),
-            VaultError::DatabaseError(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database error"),
-            VaultError::ConfigurationError(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Configuration error"),
-        };
-        
         let error_response = ErrorResponse {
-            error: error_message.to_string(),
-            message: self.to_string(),
+            error: self.error.to_string(),
+            message: self.message,
             details: None,
             request_id: None,
         };
         
-        (status, JsonResponse(error_response)).into_response()
+        (self.status, JsonResponse(error_response)).into_response()
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,362 @@
+//! CPI authority keys and zero-downtime rotation between them.
+//!
+//! A rotation stages the new key alongside the old one, moves vaults to the
+//! new key on chain a batch at a time, and can move them back. While it is
+//! open, each vault's progress record says which key its on-chain account
+//! accepts, so CPI operations keep working on both sides of the cutover.
+
+use crate::error::{Result, DomainError, VaultError};
+use crate::models::{AuthorityRotation, AuthorityRotationStatus, RotationVaultStatus, AuthorityRotationVault};
+use crate::transaction_builder::{TransactionBuilder, TransactionSubmitter};
+use crate::rpc::RpcMethodClass;
+use crate::database::{AuthorityRotationRepository, AuditRepository};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use uuid::Uuid;
+use tracing::{info, warn, error};
+
+/// Vaults rotated per batch when the operator does not choose
+pub const DEFAULT_ROTATION_BATCH_SIZE: i32 = 50;
+
+/// Authority keypairs held by this process, one of which is the default signer
+pub struct AuthorityKeyRing {
+    keys: RwLock<HashMap<Pubkey, Arc<Keypair>>>,
+    active: RwLock<Pubkey>,
+}
+
+impl AuthorityKeyRing {
+    pub fn new(active: Arc<Keypair>) -> Self {
+        let pubkey = active.pubkey();
+        Self {
+            keys: RwLock::new(HashMap::from([(pubkey, active)])),
+            active: RwLock::new(pubkey),
+        }
+    }
+
+    /// Load a keypair file into the ring without making it the default
+    pub fn stage_from_file(&self, path: &str) -> Result<Pubkey> {
+        let keypair = read_keypair_file(path)
+            .map_err(|e| VaultError::Configuration(format!("Failed to read authority keypair {}: {}", path, e)))?;
+        let pubkey = keypair.pubkey();
+        self.keys.write().expect("key ring lock poisoned").insert(pubkey, Arc::new(keypair));
+        Ok(pubkey)
+    }
+
+    pub fn get(&self, pubkey: &Pubkey) -> Option<Arc<Keypair>> {
+        self.keys.read().expect("key ring lock poisoned").get(pubkey).cloned()
+    }
+
+    /// Key used for vaults with no rotation in flight
+    pub fn active(&self) -> Arc<Keypair> {
+        let pubkey = *self.active.read().expect("key ring lock poisoned");
+        self.get(&pubkey).expect("active key is always in the ring")
+    }
+
+    pub fn set_active(&self, pubkey: &Pubkey) -> Result<()> {
+        if self.get(pubkey).is_none() {
+            return Err(VaultError::Configuration(format!("Authority key {} is not loaded", pubkey)));
+        }
+        *self.active.write().expect("key ring lock poisoned") = *pubkey;
+        Ok(())
+    }
+}
+
+/// A rotation with per-status vault counts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorityRotationProgress {
+    pub rotation: AuthorityRotation,
+    pub pending: i64,
+    pub rotated: i64,
+    pub failed: i64,
+    pub rolled_back: i64,
+}
+
+/// Which key a vault's on-chain account accepts, given its rotation progress
+pub fn expected_authority(rotation: &AuthorityRotation, vault_status: Option<RotationVaultStatus>) -> &str {
+    match vault_status {
+        Some(RotationVaultStatus::Rotated) => &rotation.new_authority,
+        // Vaults created after the rotation was staged are never enrolled
+        None if rotation.status == AuthorityRotationStatus::Completed.as_str() => &rotation.new_authority,
+        _ => &rotation.old_authority,
+    }
+}
+
+/// Stages keys and drives authority rotations batch by batch
+pub struct AuthorityRotationManager {
+    rotation_repo: AuthorityRotationRepository,
+    audit_repo: AuditRepository,
+    key_ring: Arc<AuthorityKeyRing>,
+    transaction_builder: Arc<TransactionBuilder>,
+    transaction_submitter: Arc<TransactionSubmitter>,
+}
+
+impl AuthorityRotationManager {
+    pub fn new(
+        pool: sqlx::PgPool,
+        key_ring: Arc<AuthorityKeyRing>,
+        transaction_builder: Arc<TransactionBuilder>,
+        transaction_submitter: Arc<TransactionSubmitter>,
+    ) -> Self {
+        Self {
+            rotation_repo: AuthorityRotationRepository::new(pool.clone()),
+            audit_repo: AuditRepository::new(pool),
+            key_ring,
+            transaction_builder,
+            transaction_submitter,
+        }
+    }
+
+    /// Reload keys for the latest rotation after a restart. An open rotation's
+    /// new key is staged; a completed rotation's new key becomes the default.
+    pub async fn restore(&self) -> Result<()> {
+        let Some(rotation) = self.rotation_repo.get_latest_rotation().await? else {
+            return Ok(());
+        };
+        let status = parse_status(&rotation)?;
+        if status == AuthorityRotationStatus::RolledBack {
+            return Ok(());
+        }
+
+        let new_authority = parse_pubkey(&rotation.new_authority)?;
+        if self.key_ring.get(&new_authority).is_none() {
+            let loaded = self.key_ring.stage_from_file(&rotation.new_key_path)?;
+            if loaded != new_authority {
+                return Err(VaultError::Configuration(format!(
+                    "Key at {} is {}, rotation {} expects {}",
+                    rotation.new_key_path, loaded, rotation.id, new_authority
+                )));
+            }
+        }
+
+        if status == AuthorityRotationStatus::Completed {
+            if self.key_ring.active().pubkey() != new_authority {
+                warn!("Configured authority key predates rotation {}; using {} instead", rotation.id, new_authority);
+            }
+            self.key_ring.set_active(&new_authority)?;
+        }
+        Ok(())
+    }
+
+    /// Load the new key and enroll every vault in a staged rotation
+    pub async fn stage(&self, new_key_path: &str, batch_size: Option<i32>) -> Result<AuthorityRotation> {
+        let batch_size = batch_size.unwrap_or(DEFAULT_ROTATION_BATCH_SIZE);
+        if batch_size <= 0 {
+            return Err(DomainError::Validation("Batch size must be positive".to_string()).into());
+        }
+        if let Some(open) = self.open_rotation().await? {
+            return Err(DomainError::ConcurrentConflict(format!("Authority rotation {} is still {}", open.id, open.status)).into());
+        }
+
+        let old_authority = self.key_ring.active().pubkey();
+        let new_authority = self.key_ring.stage_from_file(new_key_path)?;
+        if new_authority == old_authority {
+            return Err(DomainError::Validation("New authority key is already the active key".to_string()).into());
+        }
+
+        let rotation = self.rotation_repo.create_rotation(
+            &old_authority.to_string(),
+            &new_authority.to_string(),
+            new_key_path,
+            batch_size,
+        ).await?;
+
+        self.log_rotation_event("authority_rotation_staged", &rotation).await?;
+        Ok(rotation)
+    }
+
+    /// Rotate (or, while rolling back, restore) the next batch of vaults
+    pub async fn run_batch(&self, rotation_id: Uuid) -> Result<AuthorityRotationProgress> {
+        let mut rotation = self.rotation_repo.get_rotation(rotation_id).await?;
+        let status = parse_status(&rotation)?;
+
+        let (from, to, eligible, done) = match status {
+            AuthorityRotationStatus::Staged | AuthorityRotationStatus::InProgress => (
+                &rotation.old_authority,
+                &rotation.new_authority,
+                vec![RotationVaultStatus::Pending.as_str().to_string(), RotationVaultStatus::Failed.as_str().to_string()],
+                RotationVaultStatus::Rotated,
+            ),
+            AuthorityRotationStatus::RollingBack => (
+                &rotation.new_authority,
+                &rotation.old_authority,
+                vec![RotationVaultStatus::Rotated.as_str().to_string()],
+                RotationVaultStatus::RolledBack,
+            ),
+            _ => return Err(DomainError::InvalidVaultState(format!("Authority rotation {} is {}", rotation.id, rotation.status)).into()),
+        };
+        let from_key = self.loaded_key(from)?;
+        let to_key = self.loaded_key(to)?;
+
+        if status == AuthorityRotationStatus::Staged {
+            rotation = self.rotation_repo
+                .update_status(rotation.id, AuthorityRotationStatus::InProgress.as_str(), false)
+                .await?;
+        }
+
+        let batch = self.rotation_repo.next_vaults(rotation.id, &eligible, rotation.batch_size).await?;
+        for vault in &batch {
+            self.rotate_vault(&rotation, vault, &from_key, &to_key, done).await?;
+        }
+
+        let progress = self.progress(rotation.id).await?;
+        let remaining = match status {
+            AuthorityRotationStatus::RollingBack => progress.rotated,
+            _ => progress.pending + progress.failed,
+        };
+        if remaining == 0 {
+            return self.finish(progress.rotation, status).await;
+        }
+
+        info!("Authority rotation {}: {} vaults processed this batch, {} remaining", rotation.id, batch.len(), remaining);
+        Ok(progress)
+    }
+
+    /// Start moving rotated vaults back to the old key
+    pub async fn rollback(&self, rotation_id: Uuid) -> Result<AuthorityRotationProgress> {
+        let rotation = self.rotation_repo.get_rotation(rotation_id).await?;
+        match parse_status(&rotation)? {
+            AuthorityRotationStatus::Staged | AuthorityRotationStatus::InProgress => {}
+            AuthorityRotationStatus::RollingBack => return self.progress(rotation_id).await,
+            _ => return Err(DomainError::InvalidVaultState(format!("Authority rotation {} is {}", rotation.id, rotation.status)).into()),
+        }
+
+        let rotation = self.rotation_repo
+            .update_status(rotation_id, AuthorityRotationStatus::RollingBack.as_str(), false)
+            .await?;
+        self.log_rotation_event("authority_rotation_rollback_started", &rotation).await?;
+
+        let progress = self.progress(rotation_id).await?;
+        if progress.rotated == 0 {
+            return self.finish(progress.rotation, AuthorityRotationStatus::RollingBack).await;
+        }
+        Ok(progress)
+    }
+
+    pub async fn progress(&self, rotation_id: Uuid) -> Result<AuthorityRotationProgress> {
+        let rotation = self.rotation_repo.get_rotation(rotation_id).await?;
+        let counts = self.rotation_repo.count_by_status(rotation_id).await?;
+        let count = |status: RotationVaultStatus| counts.get(status.as_str()).copied().unwrap_or(0);
+
+        Ok(AuthorityRotationProgress {
+            pending: count(RotationVaultStatus::Pending),
+            rotated: count(RotationVaultStatus::Rotated),
+            failed: count(RotationVaultStatus::Failed),
+            rolled_back: count(RotationVaultStatus::RolledBack),
+            rotation,
+        })
+    }
+
+    /// Key the vault's on-chain account accepts right now
+    pub async fn signing_key_for(&self, vault_id: Uuid) -> Result<Arc<Keypair>> {
+        let rotation = match self.rotation_repo.get_latest_rotation().await? {
+            Some(rotation) if parse_status(&rotation)?.is_open() => rotation,
+            _ => return Ok(self.key_ring.active()),
+        };
+
+        let vault_status = self.rotation_repo.get_vault_status(rotation.id, vault_id).await?
+            .and_then(|s| RotationVaultStatus::parse(&s));
+        self.loaded_key(expected_authority(&rotation, vault_status))
+    }
+
+    async fn rotate_vault(
+        &self,
+        rotation: &AuthorityRotation,
+        vault: &AuthorityRotationVault,
+        from_key: &Keypair,
+        to_key: &Keypair,
+        done: RotationVaultStatus,
+    ) -> Result<()> {
+        let vault_pubkey = parse_pubkey(&vault.vault_pubkey)?;
+
+        let result = async {
+            let built = self.transaction_builder.build_rotate_authority_tx(vault_pubkey, from_key, to_key).await?;
+            self.transaction_submitter.submit_transaction(built.transaction, vault.vault_id).await
+        }.await;
+
+        match result {
+            Ok(signature) => {
+                self.rotation_repo
+                    .set_vault_status(rotation.id, vault.vault_id, done.as_str(), Some(&signature), None)
+                    .await
+            }
+            Err(e) => {
+                // A timed-out confirmation may still have landed; the chain decides
+                let landed = match self.transaction_builder.fetch_vault_account(vault_pubkey, RpcMethodClass::Read).await {
+                    Ok(account) => account.authority == to_key.pubkey(),
+                    Err(_) => false,
+                };
+                if landed {
+                    return self.rotation_repo
+                        .set_vault_status(rotation.id, vault.vault_id, done.as_str(), None, None)
+                        .await;
+                }
+
+                error!("Authority rotation {} failed for vault {}: {}", rotation.id, vault.vault_id, e);
+                let status = match done {
+                    RotationVaultStatus::RolledBack => RotationVaultStatus::Rotated,
+                    _ => RotationVaultStatus::Failed,
+                };
+                self.rotation_repo
+                    .set_vault_status(rotation.id, vault.vault_id, status.as_str(), None, Some(&e.to_string()))
+                    .await
+            }
+        }
+    }
+
+    async fn finish(&self, rotation: AuthorityRotation, status: AuthorityRotationStatus) -> Result<AuthorityRotationProgress> {
+        let (final_status, event) = match status {
+            AuthorityRotationStatus::RollingBack => (AuthorityRotationStatus::RolledBack, "authority_rotation_rolled_back"),
+            _ => {
+                self.key_ring.set_active(&parse_pubkey(&rotation.new_authority)?)?;
+                (AuthorityRotationStatus::Completed, "authority_rotation_completed")
+            }
+        };
+
+        let rotation = self.rotation_repo.update_status(rotation.id, final_status.as_str(), true).await?;
+        self.log_rotation_event(event, &rotation).await?;
+        info!("Authority rotation {} {}", rotation.id, rotation.status);
+
+        self.progress(rotation.id).await
+    }
+
+    async fn open_rotation(&self) -> Result<Option<AuthorityRotation>> {
+        match self.rotation_repo.get_latest_rotation().await? {
+            Some(rotation) if parse_status(&rotation)?.is_open() => Ok(Some(rotation)),
+            _ => Ok(None),
+        }
+    }
+
+    fn loaded_key(&self, pubkey: &str) -> Result<Arc<Keypair>> {
+        self.key_ring.get(&parse_pubkey(pubkey)?)
+            .ok_or_else(|| VaultError::Configuration(format!("Authority key {} is not loaded", pubkey)))
+    }
+
+    async fn log_rotation_event(&self, event_type: &str, rotation: &AuthorityRotation) -> Result<()> {
+        self.audit_repo.log_event(
+            event_type,
+            None,
+            None,
+            Some(serde_json::json!({
+                "rotation_id": rotation.id,
+                "old_authority": rotation.old_authority,
+                "new_authority": rotation.new_authority,
+                "total_vaults": rotation.total_vaults,
+            })),
+            None,
+        ).await
+    }
+}
+
+fn parse_status(rotation: &AuthorityRotation) -> Result<AuthorityRotationStatus> {
+    AuthorityRotationStatus::parse(&rotation.status)
+        .ok_or_else(|| DomainError::InvalidVaultState(format!("Unknown rotation status: {}", rotation.status)).into())
+}
+
+fn parse_pubkey(value: &str) -> Result<Pubkey> {
+    Pubkey::from_str(value)
+        .map_err(|_| VaultError::Configuration(format!("Invalid authority pubkey: {}", value)))
+}
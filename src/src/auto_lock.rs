@@ -0,0 +1,171 @@
+//! Standing margin requirements kept by automatic lock and unlock.
+//!
+//! A vault that opts in names a target locked amount. After every balance
+//! change the backend compares `locked_balance` with the target and issues one
+//! lock or unlock CPI for the difference, so the trading engine no longer sends
+//! a lock per fill. Locks draw only on available balance not held by pending
+//! operations; a vault without enough stays short of the target until the
+//! next deposit. Collateral locked above the target is unlocked, so vaults
+//! using auto-lock should not also be locked per position.
+//!
+//! One adjustment runs per vault at a time. The balance write of a lock or
+//! unlock triggers another check, which finds the vault at its target.
+
+use crate::cpi_manager::CPIManager;
+use crate::database::AutoLockRepository;
+use crate::error::{Result, DomainError};
+use crate::models::{BalanceUpdate, SpendableBalance, VaultAutoLock};
+use crate::vault_manager::VaultManager;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::{broadcast, Mutex};
+use tracing::{info, warn, error};
+use uuid::Uuid;
+
+pub const AUTO_LOCK_CONFIGURED_EVENT: &str = "auto_lock_configured";
+
+/// Lock or unlock needed to bring a vault to its target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "direction", content = "amount", rename_all = "snake_case")]
+pub enum AutoLockAdjustment {
+    Lock(u64),
+    Unlock(u64),
+}
+
+impl AutoLockAdjustment {
+    /// Positive for locks, negative for unlocks
+    pub fn signed_amount(&self) -> i64 {
+        match self {
+            AutoLockAdjustment::Lock(amount) => *amount as i64,
+            AutoLockAdjustment::Unlock(amount) => -(*amount as i64),
+        }
+    }
+}
+
+/// Adjustment towards `target_locked` that the unheld balances allow; `None` at the target
+/// or when nothing can move
+pub fn auto_lock_adjustment(target_locked: i64, balance: &SpendableBalance) -> Option<AutoLockAdjustment> {
+    let shortfall = target_locked - balance.locked_balance;
+    let amount = if shortfall > 0 {
+        shortfall.min(balance.spendable_available())
+    } else {
+        (-shortfall).min(balance.spendable_locked())
+    };
+    match amount {
+        0 => None,
+        amount if shortfall > 0 => Some(AutoLockAdjustment::Lock(amount as u64)),
+        amount => Some(AutoLockAdjustment::Unlock(amount as u64)),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoLockRequest {
+    pub target_locked: u64,
+    /// Defaults to true; false keeps the target but stops adjusting
+    pub enabled: Option<bool>,
+}
+
+/// Keeps opted-in vaults at their target locked balance
+pub struct AutoLockService {
+    repo: AutoLockRepository,
+    vault_manager: Arc<VaultManager>,
+    cpi_manager: Arc<CPIManager>,
+    /// Vaults with an adjustment running in this process
+    in_flight: Mutex<HashSet<Uuid>>,
+}
+
+impl AutoLockService {
+    pub fn new(pool: sqlx::PgPool, vault_manager: Arc<VaultManager>, cpi_manager: Arc<CPIManager>) -> Self {
+        Self {
+            repo: AutoLockRepository::new(pool),
+            vault_manager,
+            cpi_manager,
+            in_flight: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub async fn get(&self, vault_id: Uuid) -> Result<Option<VaultAutoLock>> {
+        self.repo.get(vault_id).await
+    }
+
+    /// Set the vault's target; an enabled target is applied straight away
+    pub async fn configure(self: &Arc<Self>, vault_id: Uuid, request: AutoLockRequest) -> Result<VaultAutoLock> {
+        let target_locked = i64::try_from(request.target_locked)
+            .map_err(|_| DomainError::Validation("target_locked is too large".to_string()))?;
+        let setting = self.repo
+            .upsert(vault_id, target_locked, request.enabled.unwrap_or(true), AUTO_LOCK_CONFIGURED_EVENT)
+            .await?;
+        info!("Auto-lock for vault {} set to {} (enabled: {})", vault_id, setting.target_locked, setting.enabled);
+
+        if setting.enabled {
+            self.spawn_adjustment(vault_id);
+        }
+        Ok(setting)
+    }
+
+    /// Adjust vaults as their balances change; a lagged receiver rechecks every opted-in vault
+    pub async fn run_balance_listener(self: Arc<Self>, mut updates: broadcast::Receiver<BalanceUpdate>) {
+        loop {
+            match updates.recv().await {
+                Ok(update) => self.spawn_adjustment(update.vault_id),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Auto-lock missed {} balance updates; rechecking every target", skipped);
+                    match self.repo.get_enabled().await {
+                        Ok(settings) => settings.iter().for_each(|s| self.spawn_adjustment(s.vault_id)),
+                        Err(e) => error!("Failed to list auto-lock targets: {}", e),
+                    }
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+
+    fn spawn_adjustment(self: &Arc<Self>, vault_id: Uuid) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = service.adjust(vault_id).await {
+                error!("Auto-lock adjustment for vault {} failed: {}", vault_id, e);
+            }
+        });
+    }
+
+    /// Bring the vault to its target unless another adjustment for it is running
+    pub async fn adjust(&self, vault_id: Uuid) -> Result<Option<AutoLockAdjustment>> {
+        let Some(setting) = self.repo.get(vault_id).await?.filter(|s| s.enabled) else {
+            return Ok(None);
+        };
+        if !self.in_flight.lock().await.insert(vault_id) {
+            return Ok(None);
+        }
+        let result = self.apply(&setting).await;
+        self.in_flight.lock().await.remove(&vault_id);
+        result
+    }
+
+    async fn apply(&self, setting: &VaultAutoLock) -> Result<Option<AutoLockAdjustment>> {
+        let balance = self.vault_manager.get_spendable_balance(setting.vault_id).await?;
+        let Some(adjustment) = auto_lock_adjustment(setting.target_locked, &balance) else {
+            return Ok(None);
+        };
+
+        let operation_id = Uuid::new_v4();
+        let result = match adjustment {
+            AutoLockAdjustment::Lock(amount) => self.cpi_manager.lock_collateral(setting.vault_id, amount, operation_id).await,
+            AutoLockAdjustment::Unlock(amount) => self.cpi_manager.unlock_collateral(setting.vault_id, amount, operation_id).await,
+        };
+
+        match result {
+            Ok(signature) => {
+                info!("Auto-lock moved vault {} by {} towards {} ({})",
+                      setting.vault_id, adjustment.signed_amount(), setting.target_locked, signature);
+                self.repo.record_adjustment(setting.vault_id, adjustment.signed_amount(), None).await?;
+                Ok(Some(adjustment))
+            }
+            Err(e) => {
+                self.repo.record_adjustment(setting.vault_id, adjustment.signed_amount(), Some(&e.to_string())).await?;
+                Err(e)
+            }
+        }
+    }
+}
@@ -1,67 +1,100 @@
 use crate::error::{Result, VaultError};
-use crate::models::{Vault, BalanceSnapshot, SystemBalanceStats};
+use crate::models::{
+    Vault, BalanceSnapshot, SystemBalanceStats, BalanceUpdate, BalanceUpdateSource,
+    BalanceRead, BalanceReadSource, ReadConsistency,
+};
 use crate::database::{VaultRepository, SnapshotRepository};
 use chrono::{DateTime, Utc, Duration};
-use std::collections::HashMap;
-use tokio::sync::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tokio::sync::{broadcast, RwLock};
 use std::sync::Arc;
 use uuid::Uuid;
 use tracing::{info, warn, error};
 
+/// Maximum number of vaults written per snapshot INSERT
+const SNAPSHOT_BATCH_SIZE: usize = 500;
+
+/// Vaults read per page of a baseline snapshot pass
+const SNAPSHOT_PAGE_SIZE: i32 = 1000;
+
+/// Cached balances younger than this are served without revalidation
+const DEFAULT_FRESH_SECONDS: i64 = 2;
+
+/// Cached balances older than this are never served; the reader waits for the database
+const DEFAULT_MAX_STALE_SECONDS: i64 = 60;
+
 /// Balance tracker for real-time balance monitoring and reconciliation
 pub struct BalanceTracker {
     vault_repo: VaultRepository,
     snapshot_repo: SnapshotRepository,
     cache: Arc<RwLock<HashMap<Uuid, BalanceCache>>>,
+    /// user pubkey -> vault id; a user's vault never changes once created
+    user_index: Arc<RwLock<HashMap<String, Uuid>>>,
+    /// Vaults with a background revalidation in flight
+    revalidating: Arc<std::sync::Mutex<HashSet<Uuid>>>,
     reconciliation_window: Duration,
+    fresh_for: Duration,
+    max_stale: Duration,
 }
 
 #[derive(Debug, Clone)]
-struct BalanceCache {
+pub(crate) struct BalanceCache {
     total_balance: u64,
     locked_balance: u64,
     available_balance: u64,
     last_updated: DateTime<Utc>,
+    as_of: DateTime<Utc>, // updated_at / observation time of the values held
     last_snapshot: Option<DateTime<Utc>>,
 }
 
+impl BalanceCache {
+    /// (total, locked, available) as held
+    pub(crate) fn balances(&self) -> (u64, u64, u64) {
+        (self.total_balance, self.locked_balance, self.available_balance)
+    }
+}
+
 impl BalanceTracker {
     pub fn new(pool: sqlx::PgPool, reconciliation_window_seconds: i64) -> Self {
         Self {
             vault_repo: VaultRepository::new(pool.clone()),
             snapshot_repo: SnapshotRepository::new(pool),
             cache: Arc::new(RwLock::new(HashMap::new())),
+            user_index: Arc::new(RwLock::new(HashMap::new())),
+            revalidating: Arc::new(std::sync::Mutex::new(HashSet::new())),
             reconciliation_window: Duration::seconds(reconciliation_window_seconds),
+            fresh_for: Duration::seconds(DEFAULT_FRESH_SECONDS),
+            max_stale: Duration::seconds(DEFAULT_MAX_STALE_SECONDS),
         }
     }
     
-    /// Get current balance for a vault (from cache or database)
+    /// Override how long cached balances are served as fresh, and how long
+    /// past that they may still be served while being revalidated
+    pub fn with_read_freshness(mut self, fresh_for: Duration, max_stale: Duration) -> Self {
+        self.fresh_for = fresh_for;
+        self.max_stale = max_stale.max(fresh_for);
+        self
+    }
+    
+    /// Get current balance for a vault (from cache or database).
+    ///
+    /// Cache entries do not expire; they are replaced by pushed balance updates
+    /// (see `apply_update`) and dropped by `invalidate`.
     pub async fn get_balance(&self, vault_id: Uuid) -> Result<(u64, u64, u64)> {
         // Check cache first
         {
             let cache = self.cache.read().await;
             if let Some(cached) = cache.get(&vault_id) {
-                // Return cached data if it's recent (within 5 seconds)
-                if Utc::now() - cached.last_updated < Duration::seconds(5) {
-                    return Ok((cached.total_balance, cached.locked_balance, cached.available_balance));
-                }
+                return Ok((cached.total_balance, cached.locked_balance, cached.available_balance));
             }
         }
         
         // Fetch from database
         let vault = self.vault_repo.get_vault_by_id(vault_id).await?;
         
-        // Update cache
-        {
-            let mut cache = self.cache.write().await;
-            cache.insert(vault_id, BalanceCache {
-                total_balance: vault.total_balance as u64,
-                locked_balance: vault.locked_balance as u64,
-                available_balance: vault.available_balance as u64,
-                last_updated: Utc::now(),
-                last_snapshot: None,
-            });
-        }
+        // Update cache, unless an update newer than this read was pushed meanwhile
+        self.apply_update(&BalanceUpdate::from_vault(&vault, BalanceUpdateSource::Database)).await;
         
         Ok((vault.total_balance as u64, vault.locked_balance as u64, vault.available_balance as u64))
     }
@@ -69,15 +102,127 @@ impl BalanceTracker {
     /// Update cached balance for a vault
     pub async fn update_cached_balance(&self, vault_id: Uuid, total: u64, locked: u64, available: u64) {
         let mut cache = self.cache.write().await;
+        let last_snapshot = cache.get(&vault_id).and_then(|c| c.last_snapshot);
         cache.insert(vault_id, BalanceCache {
             total_balance: total,
             locked_balance: locked,
             available_balance: available,
             last_updated: Utc::now(),
-            last_snapshot: None,
+            as_of: Utc::now(),
+            last_snapshot,
         });
     }
     
+    /// Apply a pushed balance change; updates older than the cached values are ignored.
+    /// Returns whether the update was applied.
+    pub async fn apply_update(&self, update: &BalanceUpdate) -> bool {
+        apply_to_cache(&mut *self.cache.write().await, update)
+    }
+    
+    /// Read a user's balance for the API.
+    ///
+    /// `Eventual` reads are served from the cache while the entry is within
+    /// `max_stale`; entries past `fresh_for` are marked stale and refreshed in
+    /// the background. `Strong` reads, cache misses and entries past
+    /// `max_stale` go to the database.
+    pub async fn read_balance(&self, user_pubkey: &str, consistency: ReadConsistency) -> Result<BalanceRead> {
+        if consistency == ReadConsistency::Eventual {
+            let vault_id = self.user_index.read().await.get(user_pubkey).copied();
+            if let Some(vault_id) = vault_id {
+                if let Some(read) = self.read_cached(vault_id).await {
+                    return Ok(read);
+                }
+            }
+        }
+        
+        let vault = self.vault_repo.get_vault_by_user(user_pubkey).await?;
+        self.user_index.write().await.insert(user_pubkey.to_string(), vault.id);
+        self.apply_update(&BalanceUpdate::from_vault(&vault, BalanceUpdateSource::Database)).await;
+        
+        Ok(BalanceRead {
+            vault_id: vault.id,
+            total_balance: vault.total_balance,
+            locked_balance: vault.locked_balance,
+            available_balance: vault.available_balance,
+            as_of: vault.updated_at,
+            source: BalanceReadSource::Database,
+            stale: false,
+        })
+    }
+    
+    /// Serve a cached entry if it is within `max_stale`, revalidating it when past `fresh_for`
+    async fn read_cached(&self, vault_id: Uuid) -> Option<BalanceRead> {
+        let cached = self.cache.read().await.get(&vault_id).cloned()?;
+        let age = Utc::now() - cached.last_updated;
+        if age > self.max_stale {
+            return None;
+        }
+        
+        let stale = age > self.fresh_for;
+        if stale {
+            self.spawn_revalidation(vault_id);
+        }
+        
+        Some(BalanceRead {
+            vault_id,
+            total_balance: cached.total_balance as i64,
+            locked_balance: cached.locked_balance as i64,
+            available_balance: cached.available_balance as i64,
+            as_of: cached.as_of,
+            source: BalanceReadSource::Cache,
+            stale,
+        })
+    }
+    
+    /// Refresh one vault from the database without blocking the reader.
+    /// At most one revalidation per vault is in flight.
+    fn spawn_revalidation(&self, vault_id: Uuid) {
+        if !self.revalidating.lock().unwrap().insert(vault_id) {
+            return;
+        }
+        
+        let vault_repo = self.vault_repo.clone();
+        let cache = self.cache.clone();
+        let revalidating = self.revalidating.clone();
+        
+        tokio::spawn(async move {
+            match vault_repo.get_vault_by_id(vault_id).await {
+                Ok(vault) => {
+                    let update = BalanceUpdate::from_vault(&vault, BalanceUpdateSource::Database);
+                    apply_to_cache(&mut *cache.write().await, &update);
+                }
+                Err(e) => warn!("Background balance revalidation failed for vault {}: {}", vault_id, e),
+            }
+            revalidating.lock().unwrap().remove(&vault_id);
+        });
+    }
+    
+    /// Drop a vault's cached balance so the next read goes to the database
+    pub async fn invalidate(&self, vault_id: Uuid) {
+        self.cache.write().await.remove(&vault_id);
+    }
+    
+    /// Keep the cache current from a stream of balance updates (vault manager
+    /// writes, indexer events). Runs until the sender side is dropped.
+    pub async fn run_update_listener(&self, mut updates: broadcast::Receiver<BalanceUpdate>) {
+        loop {
+            match updates.recv().await {
+                Ok(update) => {
+                    self.apply_update(&update).await;
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    // Some updates were missed; any entry might be stale
+                    warn!("Balance update listener lagged by {} events, clearing cache", skipped);
+                    self.cache.write().await.clear();
+                }
+                Err(broadcast::error::RecvError::Closed) => {
+                    info!("Balance update stream closed");
+                    break;
+                }
+            }
+        }
+    }
+    
     /// Create balance snapshot for reconciliation
     pub async fn create_snapshot(&self, vault_id: Uuid, block_height: Option<i64>) -> Result<BalanceSnapshot> {
         let (total, locked, available) = self.get_balance(vault_id).await?;
@@ -102,67 +247,82 @@ impl BalanceTracker {
         Ok(snapshot)
     }
     
-    /// Reconcile balances between on-chain and database state
-    pub async fn reconcile_balances(&self, vault_id: Uuid) -> Result<ReconciliationResult> {
-        let vault = self.vault_repo.get_vault_by_id(vault_id).await?;
-        let (cached_total, cached_locked, cached_available) = self.get_balance(vault_id).await?;
+    /// Snapshot a set of vaults in batches, writing rows only for vaults whose
+    /// balances changed since their last snapshot or whose last snapshot is
+    /// not after `baseline_after`
+    pub async fn create_snapshots_batch(&self, vaults: &[Vault], block_height: Option<i64>, baseline_after: DateTime<Utc>) -> Result<u64> {
+        let mut written = 0;
         
-        let mut discrepancies = Vec::new();
-        
-        // Check total balance
-        if vault.total_balance != cached_total as i64 {
-            discrepancies.push(Discrepancy {
-                field: "total_balance".to_string(),
-                database_value: vault.total_balance,
-                cached_value: cached_total as i64,
-                severity: DiscrepancySeverity::High,
-                issue: format!("Total balance mismatch: DB={}, Cache={}", vault.total_balance, cached_total),
-            });
+        for chunk in vaults.chunks(SNAPSHOT_BATCH_SIZE) {
+            written += self.snapshot_repo.create_snapshots_batch(chunk, block_height, baseline_after).await?;
         }
+        Ok(written)
+    }
+    
+    /// Baseline pass over every active vault; see `create_snapshots_batch`
+    pub async fn snapshot_active_vaults(&self, block_height: Option<i64>, baseline_after: DateTime<Utc>) -> Result<u64> {
+        let (mut written, mut scanned, mut offset) = (0, 0, 0);
         
-        // Check locked balance
-        if vault.locked_balance != cached_locked as i64 {
-            discrepancies.push(Discrepancy {
-                field: "locked_balance".to_string(),
-                database_value: vault.locked_balance,
-                cached_value: cached_locked as i64,
-                severity: DiscrepancySeverity::High,
-                issue: format!("Locked balance mismatch: DB={}, Cache={}", vault.locked_balance, cached_locked),
-            });
+        loop {
+            let vaults = self.vault_repo.get_active_vaults(SNAPSHOT_PAGE_SIZE, offset).await?;
+            written += self.create_snapshots_batch(&vaults, block_height, baseline_after).await?;
+            scanned += vaults.len();
+            if vaults.len() < SNAPSHOT_PAGE_SIZE as usize {
+                break;
+            }
+            offset += SNAPSHOT_PAGE_SIZE;
         }
         
-        // Check available balance
-        if vault.available_balance != cached_available as i64 {
-            discrepancies.push(Discrepancy {
-                field: "available_balance".to_string(),
-                database_value: vault.available_balance,
-                cached_value: cached_available as i64,
-                severity: DiscrepancySeverity::High,
-                issue: format!("Available balance mismatch: DB={}, Cache={}", vault.available_balance, cached_available),
-            });
+        info!("Snapshotted {} of {} vaults at block {:?} (unchanged vaults skipped)", written, scanned, block_height);
+        Ok(written)
+    }
+    
+    /// Snapshot balances as they change. Vaults only get rows when they move;
+    /// idle vaults are covered by the periodic baseline. A lagged receiver
+    /// falls back to a change-only pass over every vault.
+    pub async fn run_snapshot_listener(&self, mut updates: broadcast::Receiver<BalanceUpdate>) {
+        loop {
+            match updates.recv().await {
+                Ok(update) => {
+                    if let Err(e) = self.snapshot_repo.record_change(&update).await {
+                        error!("Failed to snapshot balance change of vault {}: {}", update.vault_id, e);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Snapshot listener missed {} balance updates; snapshotting changed vaults", skipped);
+                    if let Err(e) = self.snapshot_active_vaults(None, DateTime::<Utc>::MIN_UTC).await {
+                        error!("Catch-up snapshot pass failed: {}", e);
+                    }
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
         }
+    }
+    
+    /// Reconcile balances between on-chain and database state
+    pub async fn reconcile_balances(&self, vault_id: Uuid) -> Result<ReconciliationResult> {
+        let vault = self.vault_repo.get_vault_by_id(vault_id).await?;
+        let (cached_total, cached_locked, cached_available) = self.get_balance(vault_id).await?;
         
-        // Check balance invariant
-        if vault.total_balance != vault.locked_balance + vault.available_balance {
-            discrepancies.push(Discrepancy {
-                field: "balance_invariant".to_string(),
-                database_value: vault.total_balance,
-                cached_value: (vault.locked_balance + vault.available_balance),
-                severity: DiscrepancySeverity::Critical,
-                issue: format!("Balance invariant violated: total={} != locked={} + available={}", 
-                             vault.total_balance, vault.locked_balance, vault.available_balance),
-            });
+        // The database is authoritative; never keep serving a cached value that disagrees
+        if (cached_total, cached_locked, cached_available)
+            != (vault.total_balance as u64, vault.locked_balance as u64, vault.available_balance as u64)
+        {
+            self.invalidate(vault_id).await;
         }
         
+        let discrepancies = compare_cached(&vault, (cached_total, cached_locked, cached_available));
         let is_consistent = discrepancies.is_empty();
         
         if !is_consistent {
             warn!("Balance reconciliation found {} discrepancies for vault {}", discrepancies.len(), vault_id);
             for discrepancy in &discrepancies {
+                let code = discrepancy.code.as_str();
                 match discrepancy.severity {
-                    DiscrepancySeverity::Critical => error!("CRITICAL: {}", discrepancy.issue),
-                    DiscrepancySeverity::High => warn!("HIGH: {}", discrepancy.issue),
-                    DiscrepancySeverity::Medium => info!("MEDIUM: {}", discrepancy.issue),
+                    DiscrepancySeverity::Critical => error!("CRITICAL [{}]: {}", code, discrepancy.issue),
+                    DiscrepancySeverity::High => warn!("HIGH [{}]: {}", code, discrepancy.issue),
+                    DiscrepancySeverity::Medium | DiscrepancySeverity::Low => info!("{} [{}]: {}",
+                        discrepancy.severity.as_str().to_uppercase(), code, discrepancy.issue),
                 }
             }
         }
@@ -234,7 +394,62 @@ impl BalanceTracker {
     }
 }
 
-#[derive(Debug, Clone)]
+/// Discrepancies between a vault row and its cached balances, plus the balance invariant
+pub fn compare_cached(vault: &Vault, cached: (u64, u64, u64)) -> Vec<Discrepancy> {
+    let mut discrepancies = Vec::new();
+    
+    for (field, database_value, cached_value) in [
+        ("total_balance", vault.total_balance, cached.0 as i64),
+        ("locked_balance", vault.locked_balance, cached.1 as i64),
+        ("available_balance", vault.available_balance, cached.2 as i64),
+    ] {
+        if database_value != cached_value {
+            discrepancies.push(Discrepancy::new(
+                DiscrepancyCode::CacheMismatch,
+                field,
+                database_value,
+                cached_value,
+                format!("{} mismatch: DB={}, Cache={}", field, database_value, cached_value),
+            ));
+        }
+    }
+    
+    // Check balance invariant; `cached_value` carries locked + available
+    if vault.total_balance != vault.locked_balance + vault.available_balance {
+        discrepancies.push(Discrepancy::new(
+            DiscrepancyCode::InvariantViolation,
+            "balance_invariant",
+            vault.total_balance,
+            vault.locked_balance + vault.available_balance,
+            format!("Balance invariant violated: total={} != locked={} + available={}",
+                    vault.total_balance, vault.locked_balance, vault.available_balance),
+        ));
+    }
+    
+    discrepancies
+}
+
+/// Insert `update` unless the cache already holds newer values. An update as
+/// new as the cached one re-confirms it and restarts its freshness window.
+pub(crate) fn apply_to_cache(cache: &mut HashMap<Uuid, BalanceCache>, update: &BalanceUpdate) -> bool {
+    let last_snapshot = match cache.get(&update.vault_id) {
+        Some(cached) if cached.as_of > update.as_of => return false,
+        Some(cached) => cached.last_snapshot,
+        None => None,
+    };
+    
+    cache.insert(update.vault_id, BalanceCache {
+        total_balance: update.total_balance as u64,
+        locked_balance: update.locked_balance as u64,
+        available_balance: update.available_balance as u64,
+        last_updated: Utc::now(),
+        as_of: update.as_of,
+        last_snapshot,
+    });
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReconciliationResult {
     pub vault_id: Uuid,
     pub is_consistent: bool,
@@ -244,16 +459,34 @@ pub struct ReconciliationResult {
     pub last_reconciliation: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Discrepancy {
+    pub code: DiscrepancyCode,
     pub field: String,
     pub database_value: i64,
     pub cached_value: i64,
     pub severity: DiscrepancySeverity,
+    pub remediation: Remediation,
+    /// Human-readable summary; automation should use `code` and the values instead
     pub issue: String,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+impl Discrepancy {
+    /// Severity and remediation follow from the code
+    pub fn new(code: DiscrepancyCode, field: &str, database_value: i64, cached_value: i64, issue: String) -> Self {
+        Self {
+            code,
+            field: field.to_string(),
+            database_value,
+            cached_value,
+            severity: code.severity(),
+            remediation: code.remediation(),
+            issue,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum DiscrepancySeverity {
     Critical,
     High,
@@ -261,7 +494,146 @@ pub enum DiscrepancySeverity {
     Low,
 }
 
-#[derive(Debug, Clone)]
+impl DiscrepancySeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiscrepancySeverity::Critical => "critical",
+            DiscrepancySeverity::High => "high",
+            DiscrepancySeverity::Medium => "medium",
+            DiscrepancySeverity::Low => "low",
+        }
+    }
+    
+    /// 4 for critical down to 1 for low, for alert thresholds
+    pub fn level(&self) -> u8 {
+        match self {
+            DiscrepancySeverity::Critical => 4,
+            DiscrepancySeverity::High => 3,
+            DiscrepancySeverity::Medium => 2,
+            DiscrepancySeverity::Low => 1,
+        }
+    }
+}
+
+/// Stable identifier of a kind of discrepancy. Codes are never renamed or
+/// reused; each carries a fixed severity and suggested remediation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscrepancyCode {
+    /// Balance cache disagrees with the database
+    CacheMismatch,
+    /// total != locked + available
+    InvariantViolation,
+    /// Database disagrees with the on-chain vault account
+    ChainBalanceMismatch,
+    /// Token account holds less than the vault account records
+    TokenAccountShortfall,
+    /// Database disagrees with a replay of confirmed records
+    LedgerMismatch,
+    /// A balance change between snapshots the confirmed records do not explain
+    UnexplainedBalanceChange,
+    /// On-chain transaction with no confirmed record
+    UnrecordedTransaction,
+    /// On-chain deposit/withdrawal counters disagree with confirmed records
+    CounterMismatch,
+    /// Vault account still has the legacy layout, so a check could not run
+    LegacyLayout,
+}
+
+impl DiscrepancyCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiscrepancyCode::CacheMismatch => "cache_mismatch",
+            DiscrepancyCode::InvariantViolation => "invariant_violation",
+            DiscrepancyCode::ChainBalanceMismatch => "chain_balance_mismatch",
+            DiscrepancyCode::TokenAccountShortfall => "token_account_shortfall",
+            DiscrepancyCode::LedgerMismatch => "ledger_mismatch",
+            DiscrepancyCode::UnexplainedBalanceChange => "unexplained_balance_change",
+            DiscrepancyCode::UnrecordedTransaction => "unrecorded_transaction",
+            DiscrepancyCode::CounterMismatch => "counter_mismatch",
+            DiscrepancyCode::LegacyLayout => "legacy_layout",
+        }
+    }
+    
+    pub fn severity(&self) -> DiscrepancySeverity {
+        match self {
+            DiscrepancyCode::InvariantViolation
+            | DiscrepancyCode::ChainBalanceMismatch
+            | DiscrepancyCode::TokenAccountShortfall => DiscrepancySeverity::Critical,
+            DiscrepancyCode::CacheMismatch
+            | DiscrepancyCode::LedgerMismatch
+            | DiscrepancyCode::UnexplainedBalanceChange
+            | DiscrepancyCode::CounterMismatch => DiscrepancySeverity::High,
+            DiscrepancyCode::UnrecordedTransaction | DiscrepancyCode::LegacyLayout => DiscrepancySeverity::Medium,
+        }
+    }
+    
+    pub fn remediation(&self) -> Remediation {
+        match self {
+            DiscrepancyCode::CacheMismatch => Remediation::RefreshCache,
+            DiscrepancyCode::InvariantViolation | DiscrepancyCode::ChainBalanceMismatch => Remediation::ResyncFromChain,
+            DiscrepancyCode::TokenAccountShortfall => Remediation::ManualReview,
+            DiscrepancyCode::LedgerMismatch | DiscrepancyCode::UnexplainedBalanceChange => Remediation::ReviewRecords,
+            DiscrepancyCode::UnrecordedTransaction | DiscrepancyCode::CounterMismatch => Remediation::BackfillRecords,
+            DiscrepancyCode::LegacyLayout => Remediation::MigrateLayout,
+        }
+    }
+}
+
+/// Suggested fix for a discrepancy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Remediation {
+    /// Drop the cached entry; the next read reloads it from the database
+    RefreshCache,
+    /// Overwrite database balances with the on-chain vault account
+    ResyncFromChain,
+    /// Backfill transaction records from the on-chain history
+    BackfillRecords,
+    /// Inspect the implicated transaction records
+    ReviewRecords,
+    /// Migrate the vault account to the current layout
+    MigrateLayout,
+    /// Needs an operator; funds may be missing
+    ManualReview,
+}
+
+impl Remediation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Remediation::RefreshCache => "refresh_cache",
+            Remediation::ResyncFromChain => "resync_from_chain",
+            Remediation::BackfillRecords => "backfill_records",
+            Remediation::ReviewRecords => "review_records",
+            Remediation::MigrateLayout => "migrate_layout",
+            Remediation::ManualReview => "manual_review",
+        }
+    }
+    
+    /// Whether the fix only restores state from a more authoritative source
+    /// and can be applied without review
+    pub fn is_automatable(&self) -> bool {
+        matches!(self, Remediation::RefreshCache | Remediation::ResyncFromChain | Remediation::MigrateLayout)
+    }
+}
+
+/// Where a compared value came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueSource {
+    Database,
+    Cache,
+    /// The on-chain vault account
+    Chain,
+    /// The SPL token account holding the vault's funds
+    TokenAccount,
+    /// Confirmed transaction records
+    Ledger,
+    /// Stored balance snapshots
+    Snapshots,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BulkReconciliationResult {
     pub total_vaults: usize,
     pub consistent_vaults: usize,
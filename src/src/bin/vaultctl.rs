@@ -0,0 +1,264 @@
+//! Operator CLI for the collateral vault backend.
+//!
+//! ```text
+//! vaultctl migrate status        show applied / pending migrations
+//! vaultctl migrate drift         compare the live schema with the compiled queries
+//! vaultctl migrate run [--yes]   apply pending migrations (dry run without --yes)
+//! vaultctl support issue <name> [--operations]
+//!                                issue a read-only support credential, or an
+//!                                operations one that may also manage cases
+//! vaultctl support list          list support credentials
+//! vaultctl support revoke <id>   revoke a support credential
+//! vaultctl journal export [--after <lsn>]
+//!                                print state journal entries as JSON lines
+//! vaultctl journal snapshot      print the journaled tables and their LSN as JSON
+//! vaultctl journal verify [<snapshot>]
+//!                                rebuild the journaled tables from the journal,
+//!                                starting from a snapshot file when given, and
+//!                                compare them with the live tables
+//! vaultctl journal restore <journal> [--snapshot <file>] [--yes]
+//!                                rebuild the journaled tables from an exported
+//!                                journal into a database holding no vaults
+//! ```
+//!
+//! Connects to `DATABASE_URL`. Production deploys set `AUTO_MIGRATE=false` on the
+//! service and run `vaultctl migrate run --yes` as a separate, supervised step.
+
+use collateral_vault_backend::{
+    SchemaManager, SupportService, VaultError, error::Result, support::StaffRole,
+    journal::{self, JournalSnapshot, JournalState, StateJournal}, models::JournalEntry,
+};
+use sqlx::postgres::PgPoolOptions;
+use std::process::ExitCode;
+use uuid::Uuid;
+
+const USAGE: &str = "usage: vaultctl migrate <status|drift|run [--yes]>\n       vaultctl support <issue NAME [--operations]|list|revoke ID>\n       vaultctl journal <export [--after LSN]|snapshot|verify [SNAPSHOT]|restore JOURNAL [--snapshot FILE] [--yes]>";
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match run(&args).await {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Returns whether the command succeeded
+async fn run(args: &[&str]) -> Result<bool> {
+    let (group, command, rest) = match args {
+        [group @ ("migrate" | "support" | "journal"), command, rest @ ..] => (*group, *command, rest),
+        _ => {
+            eprintln!("{}", USAGE);
+            return Ok(false);
+        }
+    };
+
+    dotenv::dotenv().ok();
+    let database_url = std::env::var("DATABASE_URL")
+        .map_err(|_| VaultError::Configuration("DATABASE_URL must be set".to_string()))?;
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&database_url)
+        .await?;
+
+    match group {
+        "migrate" => run_migrate(SchemaManager::new(pool), command, rest).await,
+        "journal" => run_journal(StateJournal::new(pool), command, rest).await,
+        _ => run_support(SupportService::new(pool), command, rest).await,
+    }
+}
+
+async fn run_support(support_service: SupportService, command: &str, args: &[&str]) -> Result<bool> {
+    match (command, args) {
+        ("issue", [name, flags @ ..]) if flags.iter().all(|f| *f == "--operations") => {
+            let role = if flags.is_empty() { StaffRole::Support } else { StaffRole::Operations };
+            let (credential, token) = support_service.issue_credential(name, role).await?;
+            println!("credential: {} ({}, {})", credential.id, credential.name, credential.role);
+            println!("token (shown once): {}", token);
+            Ok(true)
+        }
+        ("list", []) => {
+            for credential in support_service.list_credentials().await? {
+                let state = match credential.revoked_at {
+                    Some(at) => format!("revoked {}", at),
+                    None => "active".to_string(),
+                };
+                println!("{}  {}  {}  created {}  {}", credential.id, credential.name, credential.role, credential.created_at, state);
+            }
+            Ok(true)
+        }
+        ("revoke", [id]) => {
+            let id = Uuid::parse_str(id)
+                .map_err(|_| VaultError::Configuration(format!("Invalid credential id: {}", id)))?;
+            let credential = support_service.revoke_credential(id).await?;
+            println!("revoked: {} ({})", credential.id, credential.name);
+            Ok(true)
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            Ok(false)
+        }
+    }
+}
+
+/// Returns whether the schema ended up current
+async fn run_migrate(schema_manager: SchemaManager, command: &str, flags: &[&str]) -> Result<bool> {
+    match command {
+        "status" => {
+            let status = schema_manager.status().await?;
+            println!("expected version: {:?}", status.expected_version);
+            println!("applied version:  {:?}", status.applied_version);
+            for migration in &status.pending {
+                println!("pending: {} {}", migration.version, migration.description);
+            }
+            for problem in status.problems() {
+                println!("problem: {}", problem);
+            }
+            Ok(status.is_current())
+        }
+        "drift" => {
+            let drift = schema_manager.detect_drift().await?;
+            for table in &drift.missing_tables {
+                println!("missing table: {}", table);
+            }
+            for column in &drift.missing_columns {
+                println!("missing column: {}", column);
+            }
+            if drift.is_empty() {
+                println!("no drift");
+            }
+            Ok(drift.is_empty())
+        }
+        "run" => {
+            let status = schema_manager.status().await?;
+            if !status.failed.is_empty() || !status.checksum_mismatches.is_empty() || !status.unknown.is_empty() {
+                for problem in status.problems() {
+                    println!("problem: {}", problem);
+                }
+                return Err(VaultError::Configuration("Refusing to migrate until the problems above are resolved".to_string()));
+            }
+            if status.pending.is_empty() {
+                println!("already at version {:?}", status.applied_version);
+                return Ok(true);
+            }
+
+            for migration in &status.pending {
+                println!("will apply: {} {}", migration.version, migration.description);
+            }
+            if !flags.contains(&"--yes") {
+                println!("dry run; re-run with --yes to apply");
+                return Ok(false);
+            }
+
+            let applied = schema_manager.run_pending().await?;
+            println!("applied {} migration(s)", applied.len());
+            Ok(schema_manager.ensure_current().await?.is_current())
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            Ok(false)
+        }
+    }
+}
+
+async fn run_journal(state_journal: StateJournal, command: &str, args: &[&str]) -> Result<bool> {
+    match (command, args) {
+        ("export", []) | ("export", ["--after", _]) => {
+            let mut after_lsn = match args {
+                [_, lsn] => lsn.parse()
+                    .map_err(|_| VaultError::Configuration(format!("Invalid LSN: {}", lsn)))?,
+                _ => 0,
+            };
+            loop {
+                let entries = state_journal.entries_after(after_lsn, journal::EXPORT_BATCH_SIZE).await?;
+                let Some(last) = entries.last() else { break };
+                after_lsn = last.lsn;
+                for entry in &entries {
+                    println!("{}", serde_json::to_string(entry).map_err(|e| VaultError::Internal(e.to_string()))?);
+                }
+            }
+            eprintln!("exported through LSN {}", after_lsn);
+            Ok(true)
+        }
+        ("snapshot", []) => {
+            let snapshot = state_journal.snapshot(chrono::Utc::now()).await?;
+            println!("{}", serde_json::to_string_pretty(&snapshot).map_err(|e| VaultError::Internal(e.to_string()))?);
+            eprintln!("snapshot at LSN {}: {} row(s)", snapshot.state.lsn, snapshot.state.row_count());
+            Ok(true)
+        }
+        ("verify", []) | ("verify", [_]) => {
+            let base = match args {
+                [path] => Some(read_snapshot(path)?.state),
+                _ => None,
+            };
+            let verification = state_journal.verify(base).await?;
+            println!("replayed {} entries from LSN {} to {}; {} live row(s)",
+                     verification.entries_replayed, verification.base_lsn, verification.lsn, verification.rows);
+            for mismatch in &verification.mismatches {
+                println!("mismatch: {} {} {}", mismatch.entity, mismatch.entity_key, mismatch.kind.as_str());
+            }
+            if verification.is_consistent() {
+                println!("journal matches the live tables");
+            }
+            Ok(verification.is_consistent())
+        }
+        ("restore", [journal_path, flags @ ..]) => {
+            let (snapshot_path, confirmed) = match flags {
+                [] => (None, false),
+                ["--yes"] => (None, true),
+                ["--snapshot", path] => (Some(*path), false),
+                ["--snapshot", path, "--yes"] | ["--yes", "--snapshot", path] => (Some(*path), true),
+                _ => {
+                    eprintln!("{}", USAGE);
+                    return Ok(false);
+                }
+            };
+
+            let mut state = match snapshot_path {
+                Some(path) => read_snapshot(path)?.state,
+                None => JournalState::default(),
+            };
+            let base_lsn = state.lsn;
+            let replayed = state.replay(&read_journal(journal_path)?)?;
+            println!("rebuilt {} row(s) at LSN {}: {} entries replayed after LSN {}",
+                     state.row_count(), state.lsn, replayed, base_lsn);
+            if !confirmed {
+                println!("dry run; re-run with --yes to write them");
+                return Ok(false);
+            }
+
+            let restored = state_journal.restore(&state).await?;
+            println!("restored {} row(s)", restored);
+            Ok(true)
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            Ok(false)
+        }
+    }
+}
+
+fn read_snapshot(path: &str) -> Result<JournalSnapshot> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| VaultError::Configuration(format!("Failed to read {}: {}", path, e)))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| VaultError::Configuration(format!("Invalid snapshot {}: {}", path, e)))
+}
+
+/// Entries exported by `journal export`, one JSON object per line
+fn read_journal(path: &str) -> Result<Vec<JournalEntry>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| VaultError::Configuration(format!("Failed to read {}: {}", path, e)))?;
+    contents.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| serde_json::from_str(line)
+            .map_err(|e| VaultError::Configuration(format!("Invalid journal entry on line {} of {}: {}", number + 1, path, e))))
+        .collect()
+}
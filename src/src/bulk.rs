@@ -0,0 +1,327 @@
+//! Admin bulk operations: freeze many vaults, retry failed withdrawals, re-snapshot vaults.
+//!
+//! A job is always previewed first: its targets are resolved and stored, and
+//! nothing changes until an operator executes it, confirming the target count
+//! they were shown. Execution then runs in the background a batch at a time,
+//! so progress survives restarts and an abort takes effect at the next batch.
+//! Every state change is recorded in the audit trail.
+
+use crate::error::{Result, DomainError};
+use crate::models::{BulkJob, BulkJobStatus, BulkJobTarget, BulkTargetStatus, SupportCredential, TransactionType, VaultListFilter};
+use crate::vault_manager::VaultManager;
+use crate::database::{BulkJobRepository, TransactionRepository};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+use tracing::{info, warn, error};
+
+pub const BULK_JOB_PREVIEWED_EVENT: &str = "bulk_job_previewed";
+pub const BULK_JOB_STARTED_EVENT: &str = "bulk_job_started";
+pub const BULK_JOB_ABORTED_EVENT: &str = "bulk_job_aborted";
+pub const BULK_JOB_COMPLETED_EVENT: &str = "bulk_job_completed";
+
+/// Targets handled per batch when the operator does not choose
+pub const DEFAULT_BULK_BATCH_SIZE: i32 = 50;
+
+/// Most targets one job may act on
+pub const MAX_BULK_TARGETS: usize = 10_000;
+
+/// A preview older than this must be recreated before it can run
+pub const PREVIEW_TTL_MINUTES: i64 = 60;
+
+/// Targets shown in a preview
+const PREVIEW_SAMPLE_SIZE: i64 = 20;
+
+/// Vaults fetched per page while resolving a filter
+const TARGET_PAGE_SIZE: i64 = 1_000;
+
+/// Idempotency key of the withdrawal queued to retry `transaction_id`
+pub fn retry_idempotency_key(transaction_id: Uuid) -> String {
+    format!("bulk-retry:{}", transaction_id)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "operation", rename_all = "snake_case")]
+pub enum BulkOperation {
+    /// Deactivate every active vault matching the filter
+    FreezeVaults { filter: VaultListFilter },
+    /// Queue a new withdrawal for each withdrawal that failed since `since`
+    RetryFailedWithdrawals { since: DateTime<Utc> },
+    /// Take a fresh balance snapshot of each listed vault
+    ResnapshotVaults { vault_ids: Vec<Uuid> },
+}
+
+impl BulkOperation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BulkOperation::FreezeVaults { .. } => "freeze_vaults",
+            BulkOperation::RetryFailedWithdrawals { .. } => "retry_failed_withdrawals",
+            BulkOperation::ResnapshotVaults { .. } => "resnapshot_vaults",
+        }
+    }
+}
+
+/// A previewed job and the first targets it would act on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkJobPreview {
+    pub job: BulkJob,
+    pub sample: Vec<BulkJobTarget>,
+    /// Pass `job.total_targets` back as `confirm_targets` to execute
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A job with per-status target counts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkJobProgress {
+    pub job: BulkJob,
+    pub pending: i64,
+    pub succeeded: i64,
+    pub failed: i64,
+    pub skipped: i64,
+    /// Most recent failures
+    pub recent_failures: Vec<BulkJobTarget>,
+}
+
+/// Why a previewed job may not run, if it may not
+pub fn execution_refusal(job: &BulkJob, confirm_targets: i32, now: DateTime<Utc>) -> Option<String> {
+    if job.status != BulkJobStatus::Previewed.as_str() {
+        return Some(format!("Bulk job {} is {}", job.id, job.status));
+    }
+    if now - job.created_at > Duration::minutes(PREVIEW_TTL_MINUTES) {
+        return Some(format!("Preview of bulk job {} expired; preview it again", job.id));
+    }
+    if confirm_targets != job.total_targets {
+        return Some(format!("Preview has {} targets, confirmed {}", job.total_targets, confirm_targets));
+    }
+    None
+}
+
+/// Previews, runs and aborts bulk jobs
+pub struct BulkOperationManager {
+    job_repo: BulkJobRepository,
+    transaction_repo: TransactionRepository,
+    vault_manager: Arc<VaultManager>,
+}
+
+impl BulkOperationManager {
+    pub fn new(pool: sqlx::PgPool, vault_manager: Arc<VaultManager>) -> Self {
+        Self {
+            job_repo: BulkJobRepository::new(pool.clone()),
+            transaction_repo: TransactionRepository::new(pool),
+            vault_manager,
+        }
+    }
+
+    /// Resolve the operation's targets and store them as a previewed job
+    pub async fn preview(
+        &self,
+        actor: &SupportCredential,
+        operation: BulkOperation,
+        reason: &str,
+        batch_size: Option<i32>,
+    ) -> Result<BulkJobPreview> {
+        let batch_size = batch_size.unwrap_or(DEFAULT_BULK_BATCH_SIZE);
+        if batch_size <= 0 {
+            return Err(DomainError::Validation("Batch size must be positive".to_string()).into());
+        }
+        let reason = reason.trim();
+        if reason.is_empty() {
+            return Err(DomainError::Validation("Reason is required".to_string()).into());
+        }
+
+        let targets = self.resolve_targets(&operation).await?;
+        let parameters = serde_json::to_value(&operation)
+            .map_err(|e| DomainError::Validation(format!("Invalid bulk operation: {}", e)))?;
+
+        let job = self.job_repo.create_job(
+            Uuid::new_v4(),
+            operation.name(),
+            parameters,
+            reason,
+            batch_size,
+            &actor.name,
+            &targets,
+            BULK_JOB_PREVIEWED_EVENT,
+        ).await?;
+        let sample = self.job_repo.sample_targets(job.id, PREVIEW_SAMPLE_SIZE).await?;
+
+        Ok(BulkJobPreview {
+            expires_at: job.created_at + Duration::minutes(PREVIEW_TTL_MINUTES),
+            job,
+            sample,
+        })
+    }
+
+    /// Start a previewed job; `confirm_targets` must match the previewed count
+    pub async fn execute(&self, actor: &SupportCredential, job_id: Uuid, confirm_targets: i32) -> Result<BulkJobProgress> {
+        let job = self.job_repo.get_job(job_id).await?;
+        if let Some(refusal) = execution_refusal(&job, confirm_targets, Utc::now()) {
+            return Err(DomainError::InvalidVaultState(refusal).into());
+        }
+
+        let from = [BulkJobStatus::Previewed.as_str().to_string()];
+        self.job_repo
+            .transition(job_id, &from, BulkJobStatus::Running.as_str(), &actor.name, BULK_JOB_STARTED_EVENT)
+            .await?
+            .ok_or_else(|| DomainError::ConcurrentConflict(format!("Bulk job {} changed status", job_id)))?;
+        info!("{} started bulk job {} ({}, {} targets)", actor.name, job.id, job.operation, job.total_targets);
+
+        self.progress(job_id).await
+    }
+
+    /// Stop a job before or during execution; targets already handled stay handled
+    pub async fn abort(&self, actor: &SupportCredential, job_id: Uuid) -> Result<BulkJobProgress> {
+        let from = [BulkJobStatus::Previewed.as_str().to_string(), BulkJobStatus::Running.as_str().to_string()];
+        match self.job_repo
+            .transition(job_id, &from, BulkJobStatus::Aborted.as_str(), &actor.name, BULK_JOB_ABORTED_EVENT)
+            .await?
+        {
+            Some(job) => warn!("{} aborted bulk job {} ({})", actor.name, job.id, job.operation),
+            None => {
+                let job = self.job_repo.get_job(job_id).await?;
+                return Err(DomainError::InvalidVaultState(format!("Bulk job {} is {}", job.id, job.status)).into());
+            }
+        }
+
+        self.progress(job_id).await
+    }
+
+    pub async fn progress(&self, job_id: Uuid) -> Result<BulkJobProgress> {
+        let job = self.job_repo.get_job(job_id).await?;
+        let counts = self.job_repo.count_by_status(job_id).await?;
+        let count = |status: BulkTargetStatus| counts.get(status.as_str()).copied().unwrap_or(0);
+
+        Ok(BulkJobProgress {
+            pending: count(BulkTargetStatus::Pending),
+            succeeded: count(BulkTargetStatus::Succeeded),
+            failed: count(BulkTargetStatus::Failed),
+            skipped: count(BulkTargetStatus::Skipped),
+            recent_failures: self.job_repo.failed_targets(job_id, PREVIEW_SAMPLE_SIZE).await?,
+            job,
+        })
+    }
+
+    /// Run one batch of every running job until the process exits
+    pub async fn run_worker(&self, interval_seconds: u64) {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_seconds));
+        loop {
+            interval.tick().await;
+            let jobs = match self.job_repo.list_running_jobs().await {
+                Ok(jobs) => jobs,
+                Err(e) => {
+                    error!("Failed to list running bulk jobs: {}", e);
+                    continue;
+                }
+            };
+            for job in jobs {
+                if let Err(e) = self.run_batch(&job).await {
+                    error!("Bulk job {} batch failed: {}", job.id, e);
+                }
+            }
+        }
+    }
+
+    async fn run_batch(&self, job: &BulkJob) -> Result<()> {
+        let operation: BulkOperation = serde_json::from_value(job.parameters.clone())
+            .map_err(|e| DomainError::Validation(format!("Bulk job {} has invalid parameters: {}", job.id, e)))?;
+
+        let batch = self.job_repo.next_targets(job.id, job.batch_size).await?;
+        for target in &batch {
+            let (status, error_message) = match self.apply(&operation, job, target).await {
+                Ok(status) => (status, None),
+                Err(e) => {
+                    warn!("Bulk job {} failed on {}: {}", job.id, target.target_id, e);
+                    (BulkTargetStatus::Failed, Some(e.to_string()))
+                }
+            };
+            self.job_repo
+                .set_target_status(job.id, target.target_id, status.as_str(), error_message.as_deref())
+                .await?;
+        }
+
+        if (batch.len() as i32) < job.batch_size {
+            let running = [BulkJobStatus::Running.as_str().to_string()];
+            if let Some(job) = self.job_repo
+                .transition(job.id, &running, BulkJobStatus::Completed.as_str(), &job.requested_by, BULK_JOB_COMPLETED_EVENT)
+                .await?
+            {
+                info!("Bulk job {} completed", job.id);
+            }
+        } else {
+            info!("Bulk job {}: {} targets processed this batch", job.id, batch.len());
+        }
+        Ok(())
+    }
+
+    async fn apply(&self, operation: &BulkOperation, job: &BulkJob, target: &BulkJobTarget) -> Result<BulkTargetStatus> {
+        match operation {
+            BulkOperation::FreezeVaults { .. } => {
+                let vault = self.vault_manager.get_vault_by_id(target.vault_id).await?;
+                if !vault.is_active {
+                    return Ok(BulkTargetStatus::Skipped);
+                }
+                self.vault_manager
+                    .deactivate_vault(vault.id, &format!("Bulk job {}: {}", job.id, job.reason))
+                    .await?;
+            }
+            BulkOperation::RetryFailedWithdrawals { .. } => {
+                let key = retry_idempotency_key(target.target_id);
+                if self.transaction_repo.get_transaction_by_idempotency_key(&key).await?.is_some() {
+                    return Ok(BulkTargetStatus::Skipped);
+                }
+                let failed = self.transaction_repo.get_transaction_by_id(target.target_id).await?;
+                self.vault_manager
+                    .queue_transaction(failed.vault_id, TransactionType::Withdraw, failed.amount, None, Some(key))
+                    .await?;
+            }
+            BulkOperation::ResnapshotVaults { .. } => {
+                self.vault_manager.create_balance_snapshot(target.vault_id, None).await?;
+            }
+        }
+        Ok(BulkTargetStatus::Succeeded)
+    }
+
+    /// `(target_id, vault_id, description)` for each target of `operation`
+    async fn resolve_targets(&self, operation: &BulkOperation) -> Result<Vec<(Uuid, Uuid, String)>> {
+        let targets = match operation {
+            BulkOperation::FreezeVaults { filter } => {
+                let filter = VaultListFilter { is_active: Some(true), ..filter.clone() };
+                let mut targets = Vec::new();
+                loop {
+                    let page = self.vault_manager.list_vaults(&filter, TARGET_PAGE_SIZE, targets.len() as i64).await?;
+                    let done = (page.len() as i64) < TARGET_PAGE_SIZE;
+                    targets.extend(page.into_iter().map(|v| (v.id, v.id, v.user_pubkey)));
+                    if done || targets.len() > MAX_BULK_TARGETS {
+                        break;
+                    }
+                }
+                targets
+            }
+            BulkOperation::RetryFailedWithdrawals { since } => {
+                self.transaction_repo
+                    .get_failed_withdrawals_since(*since, MAX_BULK_TARGETS as i64 + 1)
+                    .await?
+                    .into_iter()
+                    .map(|tx| (tx.id, tx.vault_id, format!("withdraw {} failed at {}", tx.amount, tx.updated_at.to_rfc3339())))
+                    .collect()
+            }
+            BulkOperation::ResnapshotVaults { vault_ids } => {
+                let mut vault_ids = vault_ids.clone();
+                vault_ids.sort();
+                vault_ids.dedup();
+                vault_ids.into_iter().map(|id| (id, id, id.to_string())).collect()
+            }
+        };
+
+        if targets.is_empty() {
+            return Err(DomainError::Validation("Bulk operation matches no targets".to_string()).into());
+        }
+        if targets.len() > MAX_BULK_TARGETS {
+            return Err(DomainError::Validation(format!(
+                "Bulk operation matches more than {} targets; narrow it down", MAX_BULK_TARGETS
+            )).into());
+        }
+        Ok(targets)
+    }
+}
@@ -0,0 +1,164 @@
+//! Ops case management: notes, tags and open/closed cases on vaults.
+//!
+//! Only operations credentials may write. Every write is recorded in the audit
+//! trail under the vault, so it shows up in the support view's timeline next to
+//! the vault's other events. Tags also filter the admin vault list
+//! (`GET /vaults?tag=vip&has_open_case=true`).
+
+use crate::error::{Result, DomainError};
+use crate::models::{SupportCredential, VaultCase, VaultNote, VaultTag};
+use crate::database::{CaseRepository, VaultRepository};
+use uuid::Uuid;
+use tracing::info;
+
+pub const NOTE_ADDED_EVENT: &str = "vault_note_added";
+pub const TAG_ADDED_EVENT: &str = "vault_tag_added";
+pub const TAG_REMOVED_EVENT: &str = "vault_tag_removed";
+pub const CASE_OPENED_EVENT: &str = "vault_case_opened";
+pub const CASE_CLOSED_EVENT: &str = "vault_case_closed";
+
+const MAX_TAG_LENGTH: usize = 32;
+const MAX_TITLE_LENGTH: usize = 200;
+const MAX_NOTE_LENGTH: usize = 10_000;
+
+/// Canonical form of a tag: lowercase, words joined by `-`, e.g. `Under Investigation` -> `under-investigation`
+pub fn normalize_tag(tag: &str) -> Result<String> {
+    let tag = tag.split_whitespace().collect::<Vec<_>>().join("-").to_lowercase();
+
+    if tag.is_empty() || tag.len() > MAX_TAG_LENGTH {
+        return Err(DomainError::Validation(format!("Tag must be 1-{} characters", MAX_TAG_LENGTH)).into());
+    }
+    if !tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(DomainError::Validation("Tag may only contain letters, digits, `-` and `_`".to_string()).into());
+    }
+    Ok(tag)
+}
+
+/// Trimmed free text, rejected when empty or longer than `max`
+fn required_text(value: &str, what: &str, max: usize) -> Result<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(DomainError::Validation(format!("{} is required", what)).into());
+    }
+    if value.chars().count() > max {
+        return Err(DomainError::Validation(format!("{} is longer than {} characters", what, max)).into());
+    }
+    Ok(value.to_string())
+}
+
+/// Who made a change, as recorded in audit details
+fn actor_details(actor: &SupportCredential) -> serde_json::Value {
+    serde_json::json!({ "credential_id": actor.id, "credential_name": actor.name })
+}
+
+/// Writes notes, tags and cases on behalf of an operations credential
+pub struct CaseService {
+    case_repo: CaseRepository,
+    vault_repo: VaultRepository,
+}
+
+impl CaseService {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self {
+            case_repo: CaseRepository::new(pool.clone()),
+            vault_repo: VaultRepository::new(pool),
+        }
+    }
+
+    /// Add a note, optionally filed under one of the vault's cases
+    pub async fn add_note(
+        &self,
+        actor: &SupportCredential,
+        user_pubkey: &str,
+        body: &str,
+        case_id: Option<Uuid>,
+    ) -> Result<VaultNote> {
+        let body = required_text(body, "Note", MAX_NOTE_LENGTH)?;
+        let vault = self.vault_repo.get_vault_by_user(user_pubkey).await?;
+        if let Some(case_id) = case_id {
+            if self.case_repo.get_case(case_id).await?.vault_id != vault.id {
+                return Err(DomainError::NotFound(format!("Case {} on vault {}", case_id, user_pubkey)).into());
+            }
+        }
+
+        let note_id = Uuid::new_v4();
+        let mut details = actor_details(actor);
+        details["note_id"] = serde_json::json!(note_id);
+        details["case_id"] = serde_json::json!(case_id);
+
+        let note = self.case_repo.add_note(note_id, vault.id, case_id, &body, &actor.name, NOTE_ADDED_EVENT, details).await?;
+        info!("{} added note {} to vault {}", actor.name, note.id, vault.id);
+        Ok(note)
+    }
+
+    /// Tag a vault; tagging twice keeps the original tag
+    pub async fn add_tag(&self, actor: &SupportCredential, user_pubkey: &str, tag: &str) -> Result<Vec<VaultTag>> {
+        let tag = normalize_tag(tag)?;
+        let vault = self.vault_repo.get_vault_by_user(user_pubkey).await?;
+
+        let mut details = actor_details(actor);
+        details["tag"] = serde_json::json!(tag);
+        if self.case_repo.add_tag(vault.id, &tag, &actor.name, TAG_ADDED_EVENT, details).await?.is_some() {
+            info!("{} tagged vault {} as {}", actor.name, vault.id, tag);
+        }
+
+        self.case_repo.get_vault_tags(vault.id).await
+    }
+
+    pub async fn remove_tag(&self, actor: &SupportCredential, user_pubkey: &str, tag: &str) -> Result<Vec<VaultTag>> {
+        let tag = normalize_tag(tag)?;
+        let vault = self.vault_repo.get_vault_by_user(user_pubkey).await?;
+
+        let mut details = actor_details(actor);
+        details["tag"] = serde_json::json!(tag);
+        if !self.case_repo.remove_tag(vault.id, &tag, TAG_REMOVED_EVENT, details).await? {
+            return Err(DomainError::NotFound(format!("Tag {} on vault {}", tag, user_pubkey)).into());
+        }
+        info!("{} removed tag {} from vault {}", actor.name, tag, vault.id);
+
+        self.case_repo.get_vault_tags(vault.id).await
+    }
+
+    pub async fn open_case(&self, actor: &SupportCredential, user_pubkey: &str, title: &str) -> Result<VaultCase> {
+        let title = required_text(title, "Case title", MAX_TITLE_LENGTH)?;
+        let vault = self.vault_repo.get_vault_by_user(user_pubkey).await?;
+
+        let case_id = Uuid::new_v4();
+        let mut details = actor_details(actor);
+        details["case_id"] = serde_json::json!(case_id);
+        details["title"] = serde_json::json!(title);
+
+        let case = self.case_repo.open_case(case_id, vault.id, &title, &actor.name, CASE_OPENED_EVENT, details).await?;
+        info!("{} opened case {} on vault {}: {}", actor.name, case.id, vault.id, case.title);
+        Ok(case)
+    }
+
+    pub async fn close_case(
+        &self,
+        actor: &SupportCredential,
+        user_pubkey: &str,
+        case_id: Uuid,
+        resolution: &str,
+    ) -> Result<VaultCase> {
+        let resolution = required_text(resolution, "Resolution", MAX_NOTE_LENGTH)?;
+        let vault = self.vault_repo.get_vault_by_user(user_pubkey).await?;
+
+        let mut details = actor_details(actor);
+        details["case_id"] = serde_json::json!(case_id);
+        details["resolution"] = serde_json::json!(resolution);
+
+        match self.case_repo.close_case(case_id, vault.id, &actor.name, &resolution, CASE_CLOSED_EVENT, details).await? {
+            Some(case) => {
+                info!("{} closed case {} on vault {}", actor.name, case.id, vault.id);
+                Ok(case)
+            }
+            None => {
+                let case = self.case_repo.get_case(case_id).await?;
+                if case.vault_id != vault.id {
+                    return Err(DomainError::NotFound(format!("Case {} on vault {}", case_id, user_pubkey)).into());
+                }
+                Err(DomainError::InvalidVaultState(format!("Case {} is already closed", case_id)).into())
+            }
+        }
+    }
+}
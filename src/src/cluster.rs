@@ -0,0 +1,231 @@
+//! Cluster timing: where the cluster is in its epoch and whether it is keeping pace.
+//!
+//! The monitor polls `getEpochInfo` and `getRecentPerformanceSamples` and
+//! publishes the result here. Two consumers act on it:
+//!
+//! - the monitor's scheduler skips heavy maintenance (reconciliation passes,
+//!   snapshots, rollups) during the first slots of an epoch, when rewards and
+//!   the new leader schedule load every RPC node;
+//! - `TransactionSubmitter` holds submissions while slots are being produced
+//!   well below nominal speed, up to a bound short enough that the already
+//!   fetched blockhash stays valid.
+//!
+//! Observations older than `max_observation_age` are ignored, so a stalled
+//! poller never blocks work.
+
+use crate::error::Result;
+use crate::rpc::{BudgetedRpcClient, RpcMethodClass};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_response::RpcPerfSample;
+use solana_sdk::epoch_info::EpochInfo;
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+/// Target slot time of the cluster
+pub const NOMINAL_SLOT_TIME_MS: f64 = 400.0;
+
+/// Performance samples (one per minute) averaged per observation
+const PERFORMANCE_SAMPLE_COUNT: usize = 5;
+
+/// How often a deferred submission re-checks the cluster
+const DEFERRAL_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterTimingConfig {
+    /// Maintenance is deferred while the epoch is younger than this many slots
+    pub epoch_start_guard_slots: u64,
+    /// Average slot time above which the cluster counts as degraded
+    pub degraded_slot_time_ms: f64,
+    /// Longest a submission is held for a degraded cluster
+    pub max_submission_deferral: Duration,
+    pub max_observation_age: Duration,
+}
+
+impl Default for ClusterTimingConfig {
+    fn default() -> Self {
+        Self {
+            epoch_start_guard_slots: 1_500, // ~10 minutes at nominal slot time
+            degraded_slot_time_ms: 650.0,
+            max_submission_deferral: Duration::from_secs(20),
+            max_observation_age: Duration::from_secs(180),
+        }
+    }
+}
+
+/// One performance sample: slots produced over a sampling period
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SlotSample {
+    pub num_slots: u64,
+    pub num_transactions: u64,
+    pub sample_period_secs: u16,
+}
+
+impl From<&RpcPerfSample> for SlotSample {
+    fn from(sample: &RpcPerfSample) -> Self {
+        Self {
+            num_slots: sample.num_slots,
+            num_transactions: sample.num_transactions,
+            sample_period_secs: sample.sample_period_secs,
+        }
+    }
+}
+
+/// Average milliseconds per slot over `samples`; `None` without usable samples
+pub fn average_slot_time_ms(samples: &[SlotSample]) -> Option<f64> {
+    let slots: u64 = samples.iter().map(|s| s.num_slots).sum();
+    let seconds: u64 = samples.iter().map(|s| s.sample_period_secs as u64).sum();
+    (slots > 0).then(|| seconds as f64 * 1000.0 / slots as f64)
+}
+
+/// Average transactions per second over `samples`
+pub fn transactions_per_second(samples: &[SlotSample]) -> Option<f64> {
+    let transactions: u64 = samples.iter().map(|s| s.num_transactions).sum();
+    let seconds: u64 = samples.iter().map(|s| s.sample_period_secs as u64).sum();
+    (seconds > 0).then(|| transactions as f64 / seconds as f64)
+}
+
+/// Snapshot of cluster timing as seen by the monitor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClusterConditions {
+    pub epoch: u64,
+    pub slot_index: u64,
+    pub slots_in_epoch: u64,
+    pub absolute_slot: u64,
+    pub average_slot_time_ms: Option<f64>,
+    pub transactions_per_second: Option<f64>,
+    /// Slots are being produced slower than `degraded_slot_time_ms`
+    pub degraded: bool,
+    pub observed_at: DateTime<Utc>,
+}
+
+impl ClusterConditions {
+    pub fn new(epoch_info: &EpochInfo, samples: &[SlotSample], config: &ClusterTimingConfig, observed_at: DateTime<Utc>) -> Self {
+        let average_slot_time_ms = average_slot_time_ms(samples);
+        Self {
+            epoch: epoch_info.epoch,
+            slot_index: epoch_info.slot_index,
+            slots_in_epoch: epoch_info.slots_in_epoch,
+            absolute_slot: epoch_info.absolute_slot,
+            average_slot_time_ms,
+            transactions_per_second: transactions_per_second(samples),
+            degraded: average_slot_time_ms.map_or(false, |ms| ms > config.degraded_slot_time_ms),
+            observed_at,
+        }
+    }
+
+    /// Slots since the observation, assuming the observed slot time
+    fn slots_elapsed(&self, now: DateTime<Utc>) -> u64 {
+        let elapsed_ms = (now - self.observed_at).num_milliseconds().max(0) as f64;
+        (elapsed_ms / self.average_slot_time_ms.unwrap_or(NOMINAL_SLOT_TIME_MS)) as u64
+    }
+
+    /// Estimated index within the epoch at `now`; wraps into the next epoch
+    pub fn estimated_slot_index(&self, now: DateTime<Utc>) -> u64 {
+        (self.slot_index + self.slots_elapsed(now)) % self.slots_in_epoch.max(1)
+    }
+
+    /// Estimated cluster slot at `now`
+    pub fn estimated_absolute_slot(&self, now: DateTime<Utc>) -> u64 {
+        self.absolute_slot + self.slots_elapsed(now)
+    }
+
+    pub fn in_epoch_start(&self, now: DateTime<Utc>, guard_slots: u64) -> bool {
+        self.estimated_slot_index(now) < guard_slots
+    }
+
+    /// Estimated time until the next epoch begins
+    pub fn time_to_next_epoch(&self, now: DateTime<Utc>) -> Duration {
+        let remaining = self.slots_in_epoch.saturating_sub(self.estimated_slot_index(now));
+        Duration::from_millis((remaining as f64 * self.average_slot_time_ms.unwrap_or(NOMINAL_SLOT_TIME_MS)) as u64)
+    }
+
+    pub fn is_fresh(&self, now: DateTime<Utc>, max_age: Duration) -> bool {
+        (now - self.observed_at).to_std().map_or(true, |age| age <= max_age)
+    }
+}
+
+/// Latest cluster conditions, shared by the monitor and the submitter
+pub struct ClusterTiming {
+    config: ClusterTimingConfig,
+    conditions: watch::Sender<Option<ClusterConditions>>,
+}
+
+impl ClusterTiming {
+    pub fn new(config: ClusterTimingConfig) -> Self {
+        Self {
+            config,
+            conditions: watch::channel(None).0,
+        }
+    }
+
+    pub fn config(&self) -> &ClusterTimingConfig {
+        &self.config
+    }
+
+    /// Latest observation, if still fresh
+    pub fn current(&self) -> Option<ClusterConditions> {
+        self.conditions
+            .borrow()
+            .clone()
+            .filter(|c| c.is_fresh(Utc::now(), self.config.max_observation_age))
+    }
+
+    pub fn update(&self, conditions: ClusterConditions) {
+        let previous = self.conditions.send_replace(Some(conditions.clone()));
+        let was_degraded = previous.map_or(false, |p| p.degraded);
+        if conditions.degraded && !was_degraded {
+            warn!("Cluster degraded: {:.0} ms per slot; deferring submissions", conditions.average_slot_time_ms.unwrap_or_default());
+        } else if !conditions.degraded && was_degraded {
+            info!("Cluster recovered: {:.0} ms per slot", conditions.average_slot_time_ms.unwrap_or_default());
+        }
+    }
+
+    /// Fetch epoch info and performance samples and publish them
+    pub async fn observe(&self, rpc: &BudgetedRpcClient) -> Result<ClusterConditions> {
+        let epoch_info = rpc.call(RpcMethodClass::Read, |c| c.get_epoch_info()).await?;
+        let samples = rpc
+            .call(RpcMethodClass::Read, |c| c.get_recent_performance_samples(Some(PERFORMANCE_SAMPLE_COUNT)))
+            .await?;
+        let samples: Vec<SlotSample> = samples.iter().map(SlotSample::from).collect();
+
+        let conditions = ClusterConditions::new(&epoch_info, &samples, &self.config, Utc::now());
+        self.update(conditions.clone());
+        Ok(conditions)
+    }
+
+    /// Why heavy maintenance should wait, if it should
+    pub fn maintenance_deferral(&self) -> Option<String> {
+        let conditions = self.current()?;
+        let slot_index = conditions.estimated_slot_index(Utc::now());
+        (slot_index < self.config.epoch_start_guard_slots).then(|| {
+            format!("epoch is {} slots old (guard {})", slot_index, self.config.epoch_start_guard_slots)
+        })
+    }
+
+    /// Whether submissions should currently be held back
+    pub fn should_defer_submissions(&self) -> bool {
+        self.current().map_or(false, |c| c.degraded)
+    }
+
+    /// Hold a submission while the cluster is degraded, at most
+    /// `max_submission_deferral`; returns how long it waited
+    pub async fn wait_for_submission_window(&self) -> Duration {
+        if !self.should_defer_submissions() {
+            return Duration::ZERO;
+        }
+
+        let started = std::time::Instant::now();
+        while self.should_defer_submissions() && started.elapsed() < self.config.max_submission_deferral {
+            tokio::time::sleep(DEFERRAL_POLL_INTERVAL).await;
+        }
+        started.elapsed()
+    }
+}
+
+impl Default for ClusterTiming {
+    fn default() -> Self {
+        Self::new(ClusterTimingConfig::default())
+    }
+}
@@ -1,7 +1,10 @@
-use crate::error::{Result, VaultError};
+use crate::error::{Result, DomainError};
 use crate::models::{Vault, TransactionRecord, TransactionType, TransactionStatus};
 use crate::vault_manager::VaultManager;
 use crate::transaction_builder::{TransactionBuilder, TransactionSubmitter, BuiltTransaction};
+use crate::latency::{LatencyHistograms, PipelineStage, StageTimings};
+use crate::authority::AuthorityRotationManager;
+use crate::rpc::RpcMethodClass;
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Keypair;
@@ -9,7 +12,9 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 use std::collections::HashMap;
+use std::str::FromStr;
 use chrono::{DateTime, Utc, Duration};
+use std::time::Instant;
 use tracing::{info, warn, error};
 
 /// CPI Manager handles cross-program invocations for trading operations
@@ -18,7 +23,10 @@ pub struct CPIManager {
     transaction_builder: Arc<TransactionBuilder>,
     transaction_submitter: Arc<TransactionSubmitter>,
     authority_keypair: Arc<Keypair>,
+    /// Set while authority rotations are possible; picks the key each vault currently trusts
+    authority_rotation: Option<Arc<AuthorityRotationManager>>,
     pending_operations: Arc<RwLock<HashMap<Uuid, PendingOperation>>>,
+    latency: Arc<LatencyHistograms>,
 }
 
 #[derive(Debug, Clone)]
@@ -30,6 +38,34 @@ struct PendingOperation {
     expires_at: DateTime<Utc>,
 }
 
+/// Furthest point an operation reached before failing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationStage {
+    /// Transaction records exist but nothing was sent to the chain
+    RecordsCreated,
+    /// Submission or confirmation failed; the transaction may or may not have landed
+    Submitted,
+    /// Confirmed on-chain, but the database balance write did not complete
+    Confirmed,
+}
+
+/// Steps needed to make the database agree with the chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompensationPlan {
+    pub fail_records: bool,
+    pub resync_from_chain: bool,
+}
+
+/// Decide how to converge after a failure at `stage`
+pub fn compensation_for(stage: OperationStage) -> CompensationPlan {
+    match stage {
+        OperationStage::RecordsCreated => CompensationPlan { fail_records: true, resync_from_chain: false },
+        // A timed-out confirmation can still land, so balances are taken from the chain
+        OperationStage::Submitted => CompensationPlan { fail_records: true, resync_from_chain: true },
+        OperationStage::Confirmed => CompensationPlan { fail_records: false, resync_from_chain: true },
+    }
+}
+
 impl CPIManager {
     pub fn new(
         vault_manager: Arc<VaultManager>,
@@ -42,144 +78,191 @@ impl CPIManager {
             transaction_builder,
             transaction_submitter,
             authority_keypair,
+            authority_rotation: None,
             pending_operations: Arc::new(RwLock::new(HashMap::new())),
+            latency: Arc::new(LatencyHistograms::new()),
         }
     }
     
+    /// Sign with whichever authority key each vault holds during a rotation
+    pub fn with_authority_rotation(mut self, authority_rotation: Arc<AuthorityRotationManager>) -> Self {
+        self.authority_rotation = Some(authority_rotation);
+        self
+    }
+    
+    /// Authority key the vault's on-chain account currently accepts
+    async fn signing_authority(&self, vault_id: Uuid) -> Result<Arc<Keypair>> {
+        match &self.authority_rotation {
+            Some(rotation) => rotation.signing_key_for(vault_id).await,
+            None => Ok(self.authority_keypair.clone()),
+        }
+    }
+    
+    /// Per-stage latency histograms for operations run through this manager
+    pub fn latency(&self) -> &Arc<LatencyHistograms> {
+        &self.latency
+    }
+    
     /// Lock collateral for trading position
     pub async fn lock_collateral(&self, vault_id: Uuid, amount: u64, operation_id: Uuid) -> Result<String> {
         info!("Locking collateral: vault={}, amount={}, operation={}", vault_id, amount, operation_id);
+        let started = Instant::now();
         
         // Validate vault exists and has sufficient balance
         let vault = self.vault_manager.get_vault_by_id(vault_id).await?;
         if vault.available_balance < amount as i64 {
-            return Err(VaultError::InsufficientBalance {
+            return Err(DomainError::InsufficientBalance {
                 available: vault.available_balance as u64,
                 required: amount,
-            });
+            }.into());
         }
         
         // Check for duplicate operations
         if self.is_operation_pending(operation_id).await {
-            return Err(VaultError::ConcurrentConflict(format!(
+            return Err(DomainError::ConcurrentConflict(format!(
                 "Operation {} already in progress", operation_id
-            )));
+            )).into());
         }
         
+        let mut timings = StageTimings::new();
+        timings.record(PipelineStage::Validation, started.elapsed());
+        
         // Add to pending operations
         self.add_pending_operation(operation_id, "lock", vault_id, amount).await;
+        let result = self.execute_lock(&vault, amount, &mut timings).await;
+        self.latency.observe(&timings);
+        self.remove_pending_operation(operation_id).await;
         
+        result
+    }
+    
+    async fn execute_lock(&self, vault: &Vault, amount: u64, timings: &mut StageTimings) -> Result<String> {
         // Build and submit transaction
         let vault_pubkey = Pubkey::from_str(&vault.vault_pubkey)
-            .map_err(|_| VaultError::ValidationError("Invalid vault pubkey".to_string()))?;
+            .map_err(|_| DomainError::Validation("Invalid vault pubkey".to_string()))?;
         
-        let built_tx = self.transaction_builder
-            .build_lock_collateral_tx(vault_pubkey, amount, &self.authority_keypair)
-            .await?;
+        let authority = self.signing_authority(vault.id).await?;
+        let built_tx = timings.time(PipelineStage::Build, self.transaction_builder
+            .build_lock_collateral_tx(vault_pubkey, amount, &authority)
+        ).await?;
         
         // Create transaction record
-        let tx_record = self.vault_manager.transaction_manager()
-            .create_transaction(vault_id, TransactionType::Lock, amount as i64, None)
+        let tx_record = timings.time(PipelineStage::DbWrite, self.vault_manager
+            .queue_transaction(vault.id, TransactionType::Lock, amount as i64, None, None))
             .await?;
         
         // Submit transaction
-        let result = self.submit_and_confirm(built_tx, tx_record.id).await;
-        
-        // Remove from pending operations
-        self.remove_pending_operation(operation_id).await;
-        
-        match result {
-            Ok(signature) => {
-                info!("Collateral locked successfully: {}", signature);
-                
-                // Update vault balances
-                let new_locked = vault.locked_balance + amount as i64;
-                let new_available = vault.available_balance - amount as i64;
-                
-                self.vault_manager.update_balances(
-                    vault_id,
-                    vault.total_balance,
-                    new_locked,
-                    new_available,
-                    Some(tx_record.id),
-                    "cp_manager",
-                ).await?;
-                
-                Ok(signature)
-            }
+        let signature = match self.submit_and_confirm(built_tx, &[tx_record.id], timings).await {
+            Ok(signature) => signature,
             Err(e) => {
                 error!("Failed to lock collateral: {}", e);
-                Err(e)
+                self.compensate(OperationStage::Submitted, &[tx_record.id], &[vault.id], &e.to_string()).await;
+                self.persist_timings(&[tx_record.id], timings).await;
+                return Err(e);
             }
+        };
+        
+        info!("Collateral locked successfully: {}", signature);
+        
+        // Update vault balances
+        let new_locked = vault.locked_balance + amount as i64;
+        let new_available = vault.available_balance - amount as i64;
+        
+        if let Err(e) = timings.time(PipelineStage::PostUpdate, self.vault_manager.update_balances(
+            vault.id,
+            vault.total_balance,
+            new_locked,
+            new_available,
+            Some(tx_record.id),
+            "cp_manager",
+        )).await {
+            error!("Lock {} confirmed on-chain but balance update failed: {}", signature, e);
+            self.compensate(OperationStage::Confirmed, &[tx_record.id], &[vault.id], &e.to_string()).await;
         }
+        
+        self.persist_timings(&[tx_record.id], timings).await;
+        Ok(signature)
     }
     
     /// Unlock collateral when position is closed
     pub async fn unlock_collateral(&self, vault_id: Uuid, amount: u64, operation_id: Uuid) -> Result<String> {
         info!("Unlocking collateral: vault={}, amount={}, operation={}", vault_id, amount, operation_id);
+        let started = Instant::now();
         
         // Validate vault exists and has sufficient locked balance
         let vault = self.vault_manager.get_vault_by_id(vault_id).await?;
         if vault.locked_balance < amount as i64 {
-            return Err(VaultError::InsufficientBalance {
-                available: vault.locked_balance as u64,
+            return Err(DomainError::InsufficientLockedBalance {
+                locked: vault.locked_balance as u64,
                 required: amount,
-            });
+            }.into());
         }
         
         // Check for duplicate operations
         if self.is_operation_pending(operation_id).await {
-            return Err(VaultError::ConcurrentConflict(format!(
+            return Err(DomainError::ConcurrentConflict(format!(
                 "Operation {} already in progress", operation_id
-            )));
+            )).into());
         }
         
+        let mut timings = StageTimings::new();
+        timings.record(PipelineStage::Validation, started.elapsed());
+        
         // Add to pending operations
         self.add_pending_operation(operation_id, "unlock", vault_id, amount).await;
+        let result = self.execute_unlock(&vault, amount, &mut timings).await;
+        self.latency.observe(&timings);
+        self.remove_pending_operation(operation_id).await;
         
+        result
+    }
+    
+    async fn execute_unlock(&self, vault: &Vault, amount: u64, timings: &mut StageTimings) -> Result<String> {
         // Build and submit transaction
         let vault_pubkey = Pubkey::from_str(&vault.vault_pubkey)
-            .map_err(|_| VaultError::ValidationError("Invalid vault pubkey".to_string()))?;
+            .map_err(|_| DomainError::Validation("Invalid vault pubkey".to_string()))?;
         
-        let built_tx = self.transaction_builder
-            .build_unlock_collateral_tx(vault_pubkey, amount, &self.authority_keypair)
-            .await?;
+        let authority = self.signing_authority(vault.id).await?;
+        let built_tx = timings.time(PipelineStage::Build, self.transaction_builder
+            .build_unlock_collateral_tx(vault_pubkey, amount, &authority)
+        ).await?;
         
         // Create transaction record
-        let tx_record = self.vault_manager.transaction_manager()
-            .create_transaction(vault_id, TransactionType::Unlock, amount as i64, None)
+        let tx_record = timings.time(PipelineStage::DbWrite, self.vault_manager
+            .queue_transaction(vault.id, TransactionType::Unlock, amount as i64, None, None))
             .await?;
         
         // Submit transaction
-        let result = self.submit_and_confirm(built_tx, tx_record.id).await;
-        
-        // Remove from pending operations
-        self.remove_pending_operation(operation_id).await;
-        
-        match result {
-            Ok(signature) => {
-                info!("Collateral unlocked successfully: {}", signature);
-                
-                // Update vault balances
-                let new_locked = vault.locked_balance - amount as i64;
-                let new_available = vault.available_balance + amount as i64;
-                
-                self.vault_manager.update_balances(
-                    vault_id,
-                    vault.total_balance,
-                    new_locked,
-                    new_available,
-                    Some(tx_record.id),
-                    "cp_manager",
-                ).await?;
-                
-                Ok(signature)
-            }
+        let signature = match self.submit_and_confirm(built_tx, &[tx_record.id], timings).await {
+            Ok(signature) => signature,
             Err(e) => {
                 error!("Failed to unlock collateral: {}", e);
-                Err(e)
+                self.compensate(OperationStage::Submitted, &[tx_record.id], &[vault.id], &e.to_string()).await;
+                self.persist_timings(&[tx_record.id], timings).await;
+                return Err(e);
             }
+        };
+        
+        info!("Collateral unlocked successfully: {}", signature);
+        
+        // Update vault balances
+        let new_locked = vault.locked_balance - amount as i64;
+        let new_available = vault.available_balance + amount as i64;
+        
+        if let Err(e) = timings.time(PipelineStage::PostUpdate, self.vault_manager.update_balances(
+            vault.id,
+            vault.total_balance,
+            new_locked,
+            new_available,
+            Some(tx_record.id),
+            "cp_manager",
+        )).await {
+            error!("Unlock {} confirmed on-chain but balance update failed: {}", signature, e);
+            self.compensate(OperationStage::Confirmed, &[tx_record.id], &[vault.id], &e.to_string()).await;
         }
+        
+        self.persist_timings(&[tx_record.id], timings).await;
+        Ok(signature)
     }
     
     /// Transfer collateral between vaults (for settlement)
@@ -192,113 +275,235 @@ impl CPIManager {
     ) -> Result<String> {
         info!("Transferring collateral: source={}, dest={}, amount={}, operation={}", 
               source_vault_id, destination_vault_id, amount, operation_id);
+        let started = Instant::now();
         
         // Validate both vaults exist and source has sufficient locked balance
         let source_vault = self.vault_manager.get_vault_by_id(source_vault_id).await?;
         let destination_vault = self.vault_manager.get_vault_by_id(destination_vault_id).await?;
         
         if source_vault.locked_balance < amount as i64 {
-            return Err(VaultError::InsufficientBalance {
-                available: source_vault.locked_balance as u64,
+            return Err(DomainError::InsufficientLockedBalance {
+                locked: source_vault.locked_balance as u64,
                 required: amount,
-            });
+            }.into());
         }
         
         // Check for duplicate operations
         if self.is_operation_pending(operation_id).await {
-            return Err(VaultError::ConcurrentConflict(format!(
+            return Err(DomainError::ConcurrentConflict(format!(
                 "Operation {} already in progress", operation_id
-            )));
+            )).into());
         }
         
+        let mut timings = StageTimings::new();
+        timings.record(PipelineStage::Validation, started.elapsed());
+        
         // Add to pending operations
         self.add_pending_operation(operation_id, "transfer", source_vault_id, amount).await;
+        let result = self.execute_transfer(&source_vault, &destination_vault, amount, &mut timings).await;
+        self.latency.observe(&timings);
+        self.remove_pending_operation(operation_id).await;
+        
+        result
+    }
+    
+    async fn execute_transfer(&self, source_vault: &Vault, destination_vault: &Vault, amount: u64, timings: &mut StageTimings) -> Result<String> {
+        let vault_ids = [source_vault.id, destination_vault.id];
         
         // Build and submit transaction
         let source_vault_pubkey = Pubkey::from_str(&source_vault.vault_pubkey)
-            .map_err(|_| VaultError::ValidationError("Invalid source vault pubkey".to_string()))?;
+            .map_err(|_| DomainError::Validation("Invalid source vault pubkey".to_string()))?;
         let destination_vault_pubkey = Pubkey::from_str(&destination_vault.vault_pubkey)
-            .map_err(|_| VaultError::ValidationError("Invalid destination vault pubkey".to_string()))?;
+            .map_err(|_| DomainError::Validation("Invalid destination vault pubkey".to_string()))?;
         
-        let built_tx = self.transaction_builder
+        // Transfers are authorized by the source vault's authority
+        let authority = self.signing_authority(source_vault.id).await?;
+        let built_tx = timings.time(PipelineStage::Build, self.transaction_builder
             .build_transfer_collateral_tx(
                 source_vault_pubkey,
                 destination_vault_pubkey,
                 amount,
-                &self.authority_keypair,
+                &authority,
+                None,
             )
-            .await?;
+        ).await?;
         
-        // Create transaction records for both vaults
-        let source_tx_record = self.vault_manager.transaction_manager()
-            .create_transaction(source_vault_id, TransactionType::Transfer, -(amount as i64), None)
+        // Create transaction records for both vaults; only the paying vault's quota applies
+        let source_tx_record = timings.time(PipelineStage::DbWrite, self.vault_manager
+            .queue_transaction(source_vault.id, TransactionType::Transfer, -(amount as i64), None, None))
             .await?;
         
-        let destination_tx_record = self.vault_manager.transaction_manager()
-            .create_transaction(destination_vault_id, TransactionType::Transfer, amount as i64, None)
-            .await?;
-        
-        // Submit transaction
-        let result = self.submit_and_confirm(built_tx, source_tx_record.id).await;
+        let destination_tx_record = match timings.time(PipelineStage::DbWrite, self.vault_manager.transaction_manager()
+            .create_transaction(destination_vault.id, TransactionType::Transfer, amount as i64, None, None))
+            .await
+        {
+            Ok(record) => record,
+            Err(e) => {
+                // Nothing was submitted; don't leave the source record dangling
+                self.compensate(OperationStage::RecordsCreated, &[source_tx_record.id], &[], &e.to_string()).await;
+                return Err(e);
+            }
+        };
         
-        // Remove from pending operations
-        self.remove_pending_operation(operation_id).await;
+        let tx_record_ids = [source_tx_record.id, destination_tx_record.id];
         
-        match result {
-            Ok(signature) => {
-                info!("Collateral transferred successfully: {}", signature);
-                
-                // Update source vault balances (reduce locked and total)
-                let source_new_locked = source_vault.locked_balance - amount as i64;
-                let source_new_total = source_vault.total_balance - amount as i64;
-                
-                self.vault_manager.update_balances(
-                    source_vault_id,
-                    source_new_total,
-                    source_new_locked,
-                    source_vault.available_balance,
-                    Some(source_tx_record.id),
-                    "cp_manager",
-                ).await?;
-                
-                // Update destination vault balances (increase available and total)
-                let destination_new_total = destination_vault.total_balance + amount as i64;
-                let destination_new_available = destination_vault.available_balance + amount as i64;
-                
-                self.vault_manager.update_balances(
-                    destination_vault_id,
-                    destination_new_total,
-                    destination_vault.locked_balance,
-                    destination_new_available,
-                    Some(destination_tx_record.id),
-                    "cp_manager",
-                ).await?;
-                
-                Ok(signature)
-            }
+        // Submit transaction
+        let signature = match self.submit_and_confirm(built_tx, &tx_record_ids, timings).await {
+            Ok(signature) => signature,
             Err(e) => {
                 error!("Failed to transfer collateral: {}", e);
-                
-                // Mark destination transaction as failed
-                self.vault_manager.transaction_manager()
-                    .update_transaction_status(destination_tx_record.id, TransactionStatus::Failed, Some(e.to_string()))
-                    .await?;
-                
-                Err(e)
+                self.compensate(OperationStage::Submitted, &tx_record_ids, &vault_ids, &e.to_string()).await;
+                self.persist_timings(&tx_record_ids, timings).await;
+                return Err(e);
             }
+        };
+        
+        info!("Collateral transferred successfully: {}", signature);
+        
+        // Update source vault balances (reduce locked and total)
+        let source_new_locked = source_vault.locked_balance - amount as i64;
+        let source_new_total = source_vault.total_balance - amount as i64;
+        
+        // Update destination vault balances (increase available and total)
+        let destination_new_total = destination_vault.total_balance + amount as i64;
+        let destination_new_available = destination_vault.available_balance + amount as i64;
+        
+        let applied = timings.time(PipelineStage::PostUpdate, async {
+            self.vault_manager.update_balances(
+                source_vault.id,
+                source_new_total,
+                source_new_locked,
+                source_vault.available_balance,
+                Some(source_tx_record.id),
+                "cp_manager",
+            ).await?;
+            
+            self.vault_manager.update_balances(
+                destination_vault.id,
+                destination_new_total,
+                destination_vault.locked_balance,
+                destination_new_available,
+                Some(destination_tx_record.id),
+                "cp_manager",
+            ).await
+        }).await;
+        
+        if let Err(e) = applied {
+            // One side may already be written; re-sync both from the chain
+            error!("Transfer {} confirmed on-chain but balance update failed: {}", signature, e);
+            self.compensate(OperationStage::Confirmed, &tx_record_ids, &vault_ids, &e.to_string()).await;
         }
+        
+        self.persist_timings(&tx_record_ids, timings).await;
+        Ok(signature)
     }
     
     /// Submit transaction and wait for confirmation
-    async fn submit_and_confirm(&self, built_tx: BuiltTransaction, tx_record_id: Uuid) -> Result<String> {
+    async fn submit_and_confirm(&self, built_tx: BuiltTransaction, tx_record_ids: &[Uuid], timings: &mut StageTimings) -> Result<String> {
         // Submit transaction
-        let signature = self.transaction_submitter.submit_transaction(built_tx.transaction, tx_record_id).await?;
-        
-        // Update transaction record with signature
-        self.vault_manager.transaction_manager()
-            .update_transaction_status(tx_record_id, TransactionStatus::Confirmed, None)
+        let submitted = self.transaction_submitter
+            .submit_transaction_with_timings(built_tx.transaction, tx_record_ids[0], timings)
             .await?;
         
+        // Mark every record belonging to this transaction as confirmed
+        for tx_record_id in tx_record_ids {
+            // Best effort: the signature only feeds fee analytics
+            if let Err(e) = self.vault_manager.transaction_manager().record_submission(*tx_record_id, &submitted).await {
+                warn!("Failed to store submission of transaction record {}: {}", tx_record_id, e);
+            }
+            if let Err(e) = timings.time(PipelineStage::PostUpdate, self.vault_manager.transaction_manager()
+                .update_transaction_status(*tx_record_id, TransactionStatus::Confirmed, None))
+                .await
+            {
+                // The chain outcome stands; a stale record status is repaired by reconciliation
+                error!("Failed to mark transaction record {} confirmed: {}", tx_record_id, e);
+            }
+        }
+        
+        Ok(submitted.signature)
+    }
+    
+    /// Store stage timings on the operation's transaction records (best effort)
+    async fn persist_timings(&self, tx_record_ids: &[Uuid], timings: &StageTimings) {
+        for tx_record_id in tx_record_ids {
+            if let Err(e) = self.vault_manager.transaction_manager()
+                .record_stage_timings(*tx_record_id, timings)
+                .await
+            {
+                warn!("Failed to store stage timings for transaction record {}: {}", tx_record_id, e);
+            }
+        }
+    }
+    
+    /// Converge database state with the on-chain outcome after a failure at `stage`.
+    ///
+    /// Never returns an error: compensation runs on an already-failing path, so problems
+    /// are logged and left for the reconciliation loop rather than masking the original error.
+    async fn compensate(&self, stage: OperationStage, tx_record_ids: &[Uuid], vault_ids: &[Uuid], reason: &str) {
+        let plan = compensation_for(stage);
+        
+        if plan.fail_records {
+            for tx_record_id in tx_record_ids {
+                if let Err(e) = self.vault_manager.transaction_manager()
+                    .update_transaction_status(*tx_record_id, TransactionStatus::Failed, Some(reason.to_string()))
+                    .await
+                {
+                    error!("Compensation: failed to mark transaction record {} failed: {}", tx_record_id, e);
+                }
+            }
+        }
+        
+        if plan.resync_from_chain {
+            for vault_id in vault_ids {
+                if let Err(e) = self.resync_vault_from_chain(*vault_id).await {
+                    error!("Compensation: failed to re-sync vault {} from chain, left for reconciliation: {}", vault_id, e);
+                }
+            }
+        }
+    }
+    
+    /// Overwrite a vault's DB balances with the on-chain Vault account
+    pub async fn resync_vault_from_chain(&self, vault_id: Uuid) -> Result<Vault> {
+        let vault = self.vault_manager.get_vault_by_id(vault_id).await?;
+        let vault_pubkey = Pubkey::from_str(&vault.vault_pubkey)
+            .map_err(|_| DomainError::Validation("Invalid vault pubkey".to_string()))?;
+        
+        let (total, locked, available) = self.transaction_builder
+            .fetch_vault_account(vault_pubkey, RpcMethodClass::Read).await?
+            .balances()
+            .as_signed();
+        
+        if (total, locked, available) == (vault.total_balance, vault.locked_balance, vault.available_balance) {
+            return Ok(vault);
+        }
+        
+        warn!("Re-syncing vault {} from chain: db=({}, {}, {}) chain=({}, {}, {})",
+              vault_id, vault.total_balance, vault.locked_balance, vault.available_balance,
+              total, locked, available);
+        
+        self.vault_manager.update_balances(
+            vault_id,
+            total,
+            locked,
+            available,
+            None,
+            "compensation",
+        ).await
+    }
+    
+    /// Grow a vault allocated for an older account layout to the current one.
+    ///
+    /// The instruction only touches the new bytes, so no balances or records change.
+    pub async fn migrate_vault_layout(&self, vault_id: Uuid) -> Result<String> {
+        let vault = self.vault_manager.get_vault_by_id(vault_id).await?;
+        let user_pubkey = Pubkey::from_str(&vault.user_pubkey)
+            .map_err(|_| DomainError::Validation("Invalid user pubkey".to_string()))?;
+        
+        // Vaults are provisioned as sub-account 0
+        let built_tx = self.transaction_builder.build_migrate_vault_layout_tx(user_pubkey, 0).await?;
+        let signature = self.transaction_submitter.submit_transaction(built_tx.transaction, vault_id).await?;
+        
+        info!("Migrated vault {} to the current account layout: {}", vault_id, signature);
         Ok(signature)
     }
     
@@ -1,12 +1,37 @@
-use crate::error::{Result, VaultError};
-use crate::models::{Vault, TransactionRecord, BalanceSnapshot, SystemBalanceStats};
+use crate::error::{Result, DomainError, StorageError};
+use crate::models::{Vault, VaultVersion, VaultListFilter, MintConfig, TransactionRecord, BalanceSnapshot, SystemBalanceStats, StageLatencyRow, SlaLatencyCounts,
+    NetworkFeeDayRow, AppliedMigration, SchemaColumn, SupportCredential,
+    ReconciliationRecord, MultisigProposal, VaultProvisioning,
+    AuthorityRotation, AuthorityRotationVault, ActivityBucketRow, VaultActivityVolume, ActivityRollupRefresh,
+    VaultCase, VaultNote, VaultTag, PendingQuota, PendingUsage, MaintenanceWindow,
+    BalanceHold, HoldBalance, HeldAmounts, SpendableBalance, QueueOutcome, BulkJob, BulkJobTarget,
+    TokenAuthorityFinding, ScreeningDecision, OutboxEvent, EventConsumer, VaultAutoLock, IndexerCursor, IndexerGap,
+    SignatureEntry, IndexerGapRange, BalanceUpdate,
+    ClientUsage, RateLimitBan, EscalationRule, Identity, VaultAlertRule, DataExport, ExportedVault, ExportSnapshot,
+    StoredResponse, IdempotencyClaim, VaultDormancy, DormancyCandidate,
+    WithdrawalFeeTier, WithdrawalFeePromotion, MonitorJobRun, PendingDeposit, SecurityEvent, SecurityEventCount,
+    IncidentRun, IncidentStepRecord, JournalEntry, JournalRead};
 use sqlx::{PgPool, Row};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 use std::collections::HashMap;
 use tracing::{info, warn, error};
 
+/// Distinguish a missing row from an actual database failure
+fn not_found_or_database(err: sqlx::Error, what: String) -> StorageError {
+    match err {
+        sqlx::Error::RowNotFound => StorageError::NotFound(what),
+        other => StorageError::Database(other),
+    }
+}
+
+/// Escape LIKE wildcards so user input is matched literally
+fn escape_like(input: &str) -> String {
+    input.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
 /// Database operations for vault management
+#[derive(Clone)]
 pub struct VaultRepository {
     pool: PgPool,
 }
@@ -31,7 +56,7 @@ impl VaultRepository {
         )
         .fetch_one(&self.pool)
         .await
-        .map_err(|e| VaultError::DatabaseError(format!("Failed to create vault: {}", e)))?;
+        .map_err(|e| StorageError::Query(format!("Failed to create vault: {}", e)))?;
 
         info!("Created vault {} for user {}", vault.id, user_pubkey);
         Ok(vault)
@@ -50,7 +75,7 @@ impl VaultRepository {
         )
         .fetch_one(&self.pool)
         .await
-        .map_err(|e| VaultError::NotFound(format!("Vault {} not found: {}", vault_id, e)))?;
+        .map_err(|e| not_found_or_database(e, format!("Vault {}", vault_id)))?;
 
         Ok(vault)
     }
@@ -68,7 +93,59 @@ impl VaultRepository {
         )
         .fetch_one(&self.pool)
         .await
-        .map_err(|e| VaultError::NotFound(format!("Vault for user {} not found: {}", user_pubkey, e)))?;
+        .map_err(|e| not_found_or_database(e, format!("Vault for user {}", user_pubkey)))?;
+
+        Ok(vault)
+    }
+
+    /// Version of a user's active vault, without reading the vault itself
+    pub async fn get_vault_version(&self, user_pubkey: &str) -> Result<VaultVersion> {
+        sqlx::query_as!(
+            VaultVersion,
+            r#"
+            SELECT id as "vault_id!", version as "version!", updated_at as "updated_at!"
+            FROM vaults
+            WHERE user_pubkey = $1 AND is_active = true
+            "#,
+            user_pubkey
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| not_found_or_database(e, format!("Vault for user {}", user_pubkey)))
+    }
+
+    /// Get vault by its PDA, active or not
+    pub async fn get_vault_by_vault_pubkey(&self, vault_pubkey: &str) -> Result<Vault> {
+        let vault = sqlx::query_as!(
+            Vault,
+            r#"
+            SELECT id, user_pubkey, vault_pubkey, token_account_pubkey, total_balance, locked_balance, available_balance, is_active, created_at, updated_at
+            FROM vaults
+            WHERE vault_pubkey = $1
+            "#,
+            vault_pubkey
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| not_found_or_database(e, format!("Vault {}", vault_pubkey)))?;
+
+        Ok(vault)
+    }
+
+    /// Get vault by the address of its token account, active or not
+    pub async fn get_vault_by_token_account(&self, token_account_pubkey: &str) -> Result<Vault> {
+        let vault = sqlx::query_as!(
+            Vault,
+            r#"
+            SELECT id, user_pubkey, vault_pubkey, token_account_pubkey, total_balance, locked_balance, available_balance, is_active, created_at, updated_at
+            FROM vaults
+            WHERE token_account_pubkey = $1
+            "#,
+            token_account_pubkey
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| not_found_or_database(e, format!("Vault with token account {}", token_account_pubkey)))?;
 
         Ok(vault)
     }
@@ -77,10 +154,10 @@ impl VaultRepository {
     pub async fn update_vault_balances(&self, vault_id: Uuid, total: i64, locked: i64, available: i64) -> Result<Vault> {
         // Validate balance invariant
         if total != locked + available {
-            return Err(VaultError::InvalidInput(format!(
-                "Balance invariant violation: total={} != locked={} + available={}",
+            return Err(DomainError::InvariantViolation(format!(
+                "total={} != locked={} + available={}",
                 total, locked, available
-            )));
+            )).into());
         }
 
         let vault = sqlx::query_as!(
@@ -98,13 +175,150 @@ impl VaultRepository {
         )
         .fetch_one(&self.pool)
         .await
-        .map_err(|e| VaultError::DatabaseError(format!("Failed to update vault balances: {}", e)))?;
+        .map_err(|e| StorageError::Query(format!("Failed to update vault balances: {}", e)))?;
+
+        info!("Updated balances for vault {}: total={}, locked={}, available={}", 
+              vault_id, total, locked, available);
+        Ok(vault)
+    }
+
+    /// Write balances and settle the holds they account for.
+    ///
+    /// With `transaction_id`, that operation's balance change is being applied and
+    /// its hold is converted. Without one the balances come from a sync with the
+    /// chain, which already reflects every confirmed operation, so the holds of
+    /// confirmed transactions are converted instead.
+    ///
+    /// The `balance_updated` outbox event commits with the balances.
+    pub async fn apply_balances(
+        &self,
+        vault_id: Uuid,
+        total: i64,
+        locked: i64,
+        available: i64,
+        transaction_id: Option<Uuid>,
+    ) -> Result<Vault> {
+        if total != locked + available {
+            return Err(DomainError::InvariantViolation(format!(
+                "total={} != locked={} + available={}",
+                total, locked, available
+            )).into());
+        }
+
+        let vault = sqlx::query_as!(
+            Vault,
+            r#"
+            WITH updated AS (
+                UPDATE vaults 
+                SET total_balance = $2, locked_balance = $3, available_balance = $4, updated_at = NOW()
+                WHERE id = $1
+                RETURNING id, user_pubkey, vault_pubkey, token_account_pubkey, total_balance, locked_balance, available_balance, is_active, created_at, updated_at
+            ), converted AS (
+                UPDATE balance_holds
+                SET status = 'converted', settled_at = NOW()
+                WHERE vault_id = $1 AND status = 'active'
+                  AND (transaction_id = $5
+                       OR ($5::UUID IS NULL AND transaction_id IN (
+                           SELECT id FROM transaction_records WHERE vault_id = $1 AND status = 'confirmed')))
+            ), head AS (
+                UPDATE event_outbox_head SET seq = seq + 1
+                WHERE id = 1 AND EXISTS (SELECT 1 FROM updated)
+                RETURNING seq
+            ), outboxed AS (
+                INSERT INTO event_outbox (seq, event_type, vault_id, payload)
+                SELECT head.seq, 'balance_updated', u.id, jsonb_build_object(
+                    'vault_id', u.id, 'total_balance', u.total_balance, 'locked_balance', u.locked_balance,
+                    'available_balance', u.available_balance, 'as_of', u.updated_at, 'source', 'Database')
+                FROM updated u, head
+            )
+            SELECT id, user_pubkey, vault_pubkey, token_account_pubkey, total_balance, locked_balance, available_balance, is_active, created_at, updated_at
+            FROM updated
+            "#,
+            vault_id,
+            total,
+            locked,
+            available,
+            transaction_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to update vault balances: {}", e)))?;
 
         info!("Updated balances for vault {}: total={}, locked={}, available={}", 
               vault_id, total, locked, available);
         Ok(vault)
     }
 
+    /// Credit a pending deposit: confirm its record at the `amount` that reached
+    /// the vault and add that to the vault's total and available balances, with
+    /// `transaction_updated` and `balance_updated` outbox events, in one
+    /// statement. `None` if the deposit was no longer pending.
+    pub async fn credit_deposit(&self, transaction_id: Uuid, slot: i64, amount: i64, audit_event: &str) -> Result<Option<Vault>> {
+        let vault = sqlx::query_as!(
+            Vault,
+            r#"
+            WITH credited AS (
+                UPDATE transaction_records t
+                SET status = 'confirmed', amount = $4, updated_at = NOW()
+                FROM transaction_records requested
+                WHERE t.id = $1 AND t.operation_type = 'deposit' AND t.status = 'pending' AND requested.id = t.id
+                RETURNING t.id, t.vault_id, t.operation_type, t.amount, t.signature, t.status, t.error_message,
+                          t.created_at, t.updated_at, requested.amount AS requested_amount
+            ), updated AS (
+                UPDATE vaults v
+                SET total_balance = v.total_balance + c.amount,
+                    available_balance = v.available_balance + c.amount,
+                    updated_at = NOW()
+                FROM credited c
+                WHERE v.id = c.vault_id
+                RETURNING v.id, v.user_pubkey, v.vault_pubkey, v.token_account_pubkey, v.total_balance, v.locked_balance,
+                          v.available_balance, v.is_active, v.created_at, v.updated_at
+            ), head AS (
+                UPDATE event_outbox_head SET seq = seq + 2
+                WHERE id = 1 AND EXISTS (SELECT 1 FROM updated)
+                RETURNING seq
+            ), outboxed AS (
+                INSERT INTO event_outbox (seq, event_type, vault_id, payload)
+                SELECT head.seq - 1, 'transaction_updated', c.vault_id, jsonb_build_object(
+                    'id', c.id, 'vault_id', c.vault_id, 'transaction_type', initcap(c.operation_type),
+                    'amount', c.amount, 'tx_signature', c.signature, 'status', initcap(c.status),
+                    'error_message', c.error_message, 'created_at', c.created_at, 'updated_at', c.updated_at)
+                FROM credited c, head
+                UNION ALL
+                SELECT head.seq, 'balance_updated', u.id, jsonb_build_object(
+                    'vault_id', u.id, 'total_balance', u.total_balance, 'locked_balance', u.locked_balance,
+                    'available_balance', u.available_balance, 'as_of', u.updated_at, 'source', 'Database')
+                FROM updated u, head
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, vault_id, details, created_at)
+                SELECT $3, c.vault_id, jsonb_build_object('transaction_id', c.id, 'signature', c.signature,
+                                                          'amount', c.amount, 'requested_amount', c.requested_amount,
+                                                          'slot', $2::BIGINT),
+                       NOW()
+                FROM credited c
+            )
+            SELECT id as "id!", user_pubkey as "user_pubkey!", vault_pubkey as "vault_pubkey!",
+                   token_account_pubkey as "token_account_pubkey!", total_balance as "total_balance!",
+                   locked_balance as "locked_balance!", available_balance as "available_balance!",
+                   is_active as "is_active!", created_at as "created_at!", updated_at as "updated_at!"
+            FROM updated
+            "#,
+            transaction_id,
+            slot,
+            audit_event,
+            amount
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to credit deposit: {}", e)))?;
+
+        if let Some(vault) = &vault {
+            info!("Credited deposit {} to vault {}: total={}, available={}",
+                  transaction_id, vault.id, vault.total_balance, vault.available_balance);
+        }
+        Ok(vault)
+    }
+
     /// List active vaults with pagination
     pub async fn get_active_vaults(&self, limit: i32, offset: i32) -> Result<Vec<Vault>> {
         let vaults = sqlx::query_as!(
@@ -121,7 +335,83 @@ impl VaultRepository {
         )
         .fetch_all(&self.pool)
         .await
-        .map_err(|e| VaultError::DatabaseError(format!("Failed to list vaults: {}", e)))?;
+        .map_err(|e| StorageError::Query(format!("Failed to list vaults: {}", e)))?;
+
+        Ok(vaults)
+    }
+
+    /// List vaults matching the given filter, newest first
+    pub async fn list_vaults(&self, filter: &VaultListFilter, limit: i32, offset: i32) -> Result<Vec<Vault>> {
+        let vaults = sqlx::query_as!(
+            Vault,
+            r#"
+            SELECT id, user_pubkey, vault_pubkey, token_account_pubkey, total_balance, locked_balance, available_balance, is_active, created_at, updated_at
+            FROM vaults
+            WHERE ($1::bool IS NULL OR is_active = $1)
+              AND ($2::bigint IS NULL OR total_balance >= $2)
+              AND ($3::bigint IS NULL OR total_balance <= $3)
+              AND ($4::bigint IS NULL OR locked_balance >= $4)
+              AND ($5::bigint IS NULL OR locked_balance <= $5)
+              AND ($6::bigint IS NULL OR (total_balance > 0 AND locked_balance * 10000 / total_balance >= $6))
+              AND ($7::bigint IS NULL OR total_balance = 0 OR locked_balance * 10000 / total_balance <= $7)
+              AND ($8::timestamptz IS NULL OR created_at >= $8)
+              AND ($9::timestamptz IS NULL OR created_at <= $9)
+              AND ($10::timestamptz IS NULL OR updated_at >= $10)
+              AND ($11::timestamptz IS NULL OR updated_at <= $11)
+              AND ($12::text IS NULL OR mint_pubkey = $12)
+              AND ($13::text IS NULL OR EXISTS (SELECT 1 FROM vault_tags t WHERE t.vault_id = vaults.id AND t.tag = $13))
+              AND ($14::bool IS NULL OR EXISTS (SELECT 1 FROM vault_cases c WHERE c.vault_id = vaults.id AND c.status = 'open') = $14)
+            ORDER BY created_at DESC
+            LIMIT $15 OFFSET $16
+            "#,
+            filter.is_active,
+            filter.min_total_balance,
+            filter.max_total_balance,
+            filter.min_locked_balance,
+            filter.max_locked_balance,
+            filter.min_lock_utilization_bps,
+            filter.max_lock_utilization_bps,
+            filter.created_after,
+            filter.created_before,
+            filter.last_activity_after,
+            filter.last_activity_before,
+            filter.mint,
+            filter.tag,
+            filter.has_open_case,
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to list vaults: {}", e)))?;
+
+        Ok(vaults)
+    }
+
+    /// Search vaults by user/vault pubkey prefix or label substring
+    pub async fn search_vaults(&self, query: &str, limit: i32) -> Result<Vec<Vault>> {
+        let prefix = format!("{}%", escape_like(query));
+        let substring = format!("%{}%", escape_like(query));
+
+        let vaults = sqlx::query_as!(
+            Vault,
+            r#"
+            SELECT id, user_pubkey, vault_pubkey, token_account_pubkey, total_balance, locked_balance, available_balance, is_active, created_at, updated_at
+            FROM vaults
+            WHERE user_pubkey LIKE $1
+               OR vault_pubkey LIKE $1
+               OR token_account_pubkey LIKE $1
+               OR label ILIKE $2
+            ORDER BY created_at DESC
+            LIMIT $3
+            "#,
+            prefix,
+            substring,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to search vaults: {}", e)))?;
 
         Ok(vaults)
     }
@@ -138,10 +428,10 @@ impl VaultRepository {
         )
         .execute(&self.pool)
         .await
-        .map_err(|e| VaultError::DatabaseError(format!("Failed to deactivate vault: {}", e)))?;
+        .map_err(|e| StorageError::Query(format!("Failed to deactivate vault: {}", e)))?;
 
         if result.rows_affected() == 0 {
-            return Err(VaultError::NotFound(format!("Vault {} not found", vault_id)));
+            return Err(StorageError::NotFound(format!("Vault {} not found", vault_id)).into());
         }
 
         info!("Deactivated vault {}", vault_id);
@@ -159,7 +449,7 @@ impl TransactionRepository {
         Self { pool }
     }
 
-    /// Create transaction record
+    /// Create transaction record, with its `transaction_updated` outbox event
     pub async fn create_transaction(
         &self,
         vault_id: Uuid,
@@ -171,9 +461,24 @@ impl TransactionRepository {
         let tx = sqlx::query_as!(
             TransactionRecord,
             r#"
-            INSERT INTO transaction_records (vault_id, operation_type, amount, signature, status, idempotency_key, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, 'pending', $5, NOW(), NOW())
-            RETURNING id, vault_id, operation_type, amount, signature, status, error_message, idempotency_key, created_at, updated_at
+            WITH created AS (
+                INSERT INTO transaction_records (vault_id, operation_type, amount, signature, status, idempotency_key, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, 'pending', $5, NOW(), NOW())
+                RETURNING id, vault_id, operation_type, amount, signature, status, error_message, idempotency_key, created_at, updated_at
+            ), head AS (
+                UPDATE event_outbox_head SET seq = seq + 1
+                WHERE id = 1
+                RETURNING seq
+            ), outboxed AS (
+                INSERT INTO event_outbox (seq, event_type, vault_id, payload)
+                SELECT head.seq, 'transaction_updated', c.vault_id, jsonb_build_object(
+                    'id', c.id, 'vault_id', c.vault_id, 'transaction_type', initcap(c.operation_type),
+                    'amount', c.amount, 'tx_signature', c.signature, 'status', initcap(c.status),
+                    'error_message', c.error_message, 'created_at', c.created_at, 'updated_at', c.updated_at)
+                FROM created c, head
+            )
+            SELECT id, vault_id, operation_type, amount, signature, status, error_message, idempotency_key, created_at, updated_at
+            FROM created
             "#,
             vault_id,
             operation_type,
@@ -183,13 +488,223 @@ impl TransactionRepository {
         )
         .fetch_one(&self.pool)
         .await
-        .map_err(|e| VaultError::DatabaseError(format!("Failed to create transaction record: {}", e)))?;
+        .map_err(|e| StorageError::Query(format!("Failed to create transaction record: {}", e)))?;
 
         info!("Created transaction {} for vault {}: {} {}", tx.id, vault_id, operation_type, amount);
         Ok(tx)
     }
 
-    /// Update transaction status
+    /// Deposit backed by `signature`, of any status
+    pub async fn get_deposit_by_signature(&self, signature: &str) -> Result<Option<TransactionRecord>> {
+        let tx = sqlx::query_as!(
+            TransactionRecord,
+            r#"
+            SELECT id, vault_id, operation_type, amount, signature, status, error_message, idempotency_key, created_at, updated_at
+            FROM transaction_records
+            WHERE signature = $1 AND operation_type = 'deposit'
+            "#,
+            signature
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get deposit by signature: {}", e)))?;
+
+        Ok(tx)
+    }
+
+    /// Pending deposits with their indexed signature, oldest first
+    pub async fn get_pending_deposits(&self, limit: i64) -> Result<Vec<PendingDeposit>> {
+        let deposits = sqlx::query_as!(
+            PendingDeposit,
+            r#"
+            SELECT t.id as "transaction_id!", t.vault_id as "vault_id!", v.vault_pubkey as "vault_pubkey!",
+                   v.token_account_pubkey as "token_account_pubkey!", t.amount as "amount!", t.signature as "signature!", s.slot as "slot?", s.failed as "failed?",
+                   t.created_at as "created_at!"
+            FROM transaction_records t
+            JOIN vaults v ON v.id = t.vault_id
+            LEFT JOIN indexed_signatures s ON s.signature = t.signature
+            WHERE t.operation_type = 'deposit' AND t.status = 'pending' AND t.signature IS NOT NULL
+            ORDER BY t.created_at
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get pending deposits: {}", e)))?;
+
+        Ok(deposits)
+    }
+
+    /// Sum of a vault's deposits not yet credited
+    pub async fn get_pending_credit(&self, vault_id: Uuid) -> Result<i64> {
+        let row = sqlx::query!(
+            r#"
+            SELECT COALESCE(SUM(amount), 0)::BIGINT as "pending_credit!"
+            FROM transaction_records
+            WHERE vault_id = $1 AND operation_type = 'deposit' AND status = 'pending'
+            "#,
+            vault_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get pending credit: {}", e)))?;
+
+        Ok(row.pending_credit)
+    }
+
+    /// Pending and processing work queued for a vault
+    pub async fn get_pending_usage(&self, vault_id: Uuid) -> Result<PendingUsage> {
+        let row = sqlx::query!(
+            r#"
+            SELECT COUNT(*) AS "pending_operations!",
+                   COALESCE(SUM(amount) FILTER (WHERE operation_type = 'withdraw'), 0)::BIGINT AS "pending_withdrawal_amount!"
+            FROM transaction_records
+            WHERE vault_id = $1 AND status IN ('pending', 'processing')
+            "#,
+            vault_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get pending usage: {}", e)))?;
+
+        Ok(PendingUsage {
+            pending_operations: row.pending_operations,
+            pending_withdrawal_amount: row.pending_withdrawal_amount,
+        })
+    }
+
+    /// Create a pending record unless it would take the vault past `quota` or
+    /// past its spendable balance, placing `hold` alongside it.
+    ///
+    /// The vault row stays locked from the checks through the inserts, so
+    /// concurrent requests for one vault cannot all slip in under the cap or
+    /// spend the same balance twice before either settles on-chain.
+    pub async fn create_transaction_within_quota(
+        &self,
+        vault_id: Uuid,
+        operation_type: &str,
+        amount: i64,
+        signature: Option<&str>,
+        idempotency_key: Option<&str>,
+        quota: &PendingQuota,
+        hold: Option<(HoldBalance, i64)>,
+    ) -> Result<QueueOutcome> {
+        let mut db_tx = self.pool.begin().await
+            .map_err(|e| StorageError::Query(format!("Failed to begin quota check: {}", e)))?;
+
+        let vault = sqlx::query!(
+            "SELECT available_balance, locked_balance FROM vaults WHERE id = $1 FOR UPDATE",
+            vault_id
+        )
+            .fetch_optional(&mut db_tx)
+            .await
+            .map_err(|e| StorageError::Query(format!("Failed to lock vault: {}", e)))?
+            .ok_or_else(|| StorageError::NotFound(format!("Vault {}", vault_id)))?;
+
+        let row = sqlx::query!(
+            r#"
+            SELECT COUNT(*) AS "pending_operations!",
+                   COALESCE(SUM(amount) FILTER (WHERE operation_type = 'withdraw'), 0)::BIGINT AS "pending_withdrawal_amount!"
+            FROM transaction_records
+            WHERE vault_id = $1 AND status IN ('pending', 'processing')
+            "#,
+            vault_id
+        )
+        .fetch_one(&mut db_tx)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get pending usage: {}", e)))?;
+        let usage = PendingUsage {
+            pending_operations: row.pending_operations,
+            pending_withdrawal_amount: row.pending_withdrawal_amount,
+        };
+
+        // Dropping the transaction on refusal rolls it back and releases the lock
+        let withdrawal_amount = if operation_type == "withdraw" { amount } else { 0 };
+        if quota.violation(&usage, withdrawal_amount).is_some() {
+            return Ok(QueueOutcome::QuotaExceeded(usage));
+        }
+
+        if let Some((balance, hold_amount)) = hold {
+            let held = sqlx::query!(
+                r#"
+                SELECT COALESCE(SUM(amount) FILTER (WHERE balance = 'available'), 0)::BIGINT AS "available!",
+                       COALESCE(SUM(amount) FILTER (WHERE balance = 'locked'), 0)::BIGINT AS "locked!"
+                FROM balance_holds
+                WHERE vault_id = $1 AND status = 'active'
+                "#,
+                vault_id
+            )
+            .fetch_one(&mut db_tx)
+            .await
+            .map_err(|e| StorageError::Query(format!("Failed to get held amounts: {}", e)))?;
+
+            let spendable = SpendableBalance::new(
+                vault.available_balance,
+                vault.locked_balance,
+                HeldAmounts { available: held.available, locked: held.locked },
+            ).spendable(balance);
+            if spendable < hold_amount {
+                return Ok(QueueOutcome::InsufficientSpendable { balance, spendable });
+            }
+        }
+
+        let tx = sqlx::query_as!(
+            TransactionRecord,
+            r#"
+            WITH created AS (
+                INSERT INTO transaction_records (vault_id, operation_type, amount, signature, status, idempotency_key, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, 'pending', $5, NOW(), NOW())
+                RETURNING id, vault_id, operation_type, amount, signature, status, error_message, idempotency_key, created_at, updated_at
+            ), head AS (
+                UPDATE event_outbox_head SET seq = seq + 1
+                WHERE id = 1
+                RETURNING seq
+            ), outboxed AS (
+                INSERT INTO event_outbox (seq, event_type, vault_id, payload)
+                SELECT head.seq, 'transaction_updated', c.vault_id, jsonb_build_object(
+                    'id', c.id, 'vault_id', c.vault_id, 'transaction_type', initcap(c.operation_type),
+                    'amount', c.amount, 'tx_signature', c.signature, 'status', initcap(c.status),
+                    'error_message', c.error_message, 'created_at', c.created_at, 'updated_at', c.updated_at)
+                FROM created c, head
+            )
+            SELECT id, vault_id, operation_type, amount, signature, status, error_message, idempotency_key, created_at, updated_at
+            FROM created
+            "#,
+            vault_id,
+            operation_type,
+            amount,
+            signature,
+            idempotency_key
+        )
+        .fetch_one(&mut db_tx)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to create transaction record: {}", e)))?;
+
+        if let Some((balance, hold_amount)) = hold {
+            sqlx::query!(
+                r#"
+                INSERT INTO balance_holds (vault_id, transaction_id, balance, amount)
+                VALUES ($1, $2, $3, $4)
+                "#,
+                vault_id,
+                tx.id,
+                balance.as_str(),
+                hold_amount
+            )
+            .execute(&mut db_tx)
+            .await
+            .map_err(|e| StorageError::Query(format!("Failed to place balance hold: {}", e)))?;
+        }
+
+        db_tx.commit().await
+            .map_err(|e| StorageError::Query(format!("Failed to commit transaction record: {}", e)))?;
+
+        info!("Created transaction {} for vault {}: {} {}", tx.id, vault_id, operation_type, amount);
+        Ok(QueueOutcome::Queued(tx))
+    }
+
+    /// Update transaction status, with its `transaction_updated` outbox event
     pub async fn update_transaction_status(
         &self,
         transaction_id: Uuid,
@@ -197,13 +712,33 @@ impl TransactionRepository {
         signature: Option<&str>,
         error_message: Option<&str>,
     ) -> Result<TransactionRecord> {
+        // A failed operation never changes balances, so its hold is released with it
         let tx = sqlx::query_as!(
             TransactionRecord,
             r#"
-            UPDATE transaction_records 
-            SET status = $2, signature = COALESCE($3, signature), error_message = $4, updated_at = NOW()
-            WHERE id = $1
-            RETURNING id, vault_id, operation_type, amount, signature, status, error_message, idempotency_key, created_at, updated_at
+            WITH updated AS (
+                UPDATE transaction_records 
+                SET status = $2, signature = COALESCE($3, signature), error_message = $4, updated_at = NOW()
+                WHERE id = $1
+                RETURNING id, vault_id, operation_type, amount, signature, status, error_message, idempotency_key, created_at, updated_at
+            ), released AS (
+                UPDATE balance_holds
+                SET status = 'released', settled_at = NOW()
+                WHERE transaction_id = $1 AND status = 'active' AND $2 IN ('failed', 'reverted')
+            ), head AS (
+                UPDATE event_outbox_head SET seq = seq + 1
+                WHERE id = 1 AND EXISTS (SELECT 1 FROM updated)
+                RETURNING seq
+            ), outboxed AS (
+                INSERT INTO event_outbox (seq, event_type, vault_id, payload)
+                SELECT head.seq, 'transaction_updated', u.vault_id, jsonb_build_object(
+                    'id', u.id, 'vault_id', u.vault_id, 'transaction_type', initcap(u.operation_type),
+                    'amount', u.amount, 'tx_signature', u.signature, 'status', initcap(u.status),
+                    'error_message', u.error_message, 'created_at', u.created_at, 'updated_at', u.updated_at)
+                FROM updated u, head
+            )
+            SELECT id, vault_id, operation_type, amount, signature, status, error_message, idempotency_key, created_at, updated_at
+            FROM updated
             "#,
             transaction_id,
             status,
@@ -212,92 +747,292 @@ impl TransactionRepository {
         )
         .fetch_one(&self.pool)
         .await
-        .map_err(|e| VaultError::DatabaseError(format!("Failed to update transaction status: {}", e)))?;
+        .map_err(|e| StorageError::Query(format!("Failed to update transaction status: {}", e)))?;
 
         info!("Updated transaction {} status to {}", transaction_id, status);
         Ok(tx)
     }
 
-    /// Get transaction by idempotency key
-    pub async fn get_transaction_by_idempotency_key(&self, idempotency_key: &str) -> Result<Option<TransactionRecord>> {
+    /// Get transaction by ID
+    pub async fn get_transaction_by_id(&self, transaction_id: Uuid) -> Result<TransactionRecord> {
         let tx = sqlx::query_as!(
             TransactionRecord,
             r#"
             SELECT id, vault_id, operation_type, amount, signature, status, error_message, idempotency_key, created_at, updated_at
             FROM transaction_records
-            WHERE idempotency_key = $1
-            ORDER BY created_at DESC
-            LIMIT 1
+            WHERE id = $1
             "#,
-            idempotency_key
+            transaction_id
         )
-        .fetch_optional(&self.pool)
+        .fetch_one(&self.pool)
         .await
-        .map_err(|e| VaultError::DatabaseError(format!("Failed to get transaction by idempotency key: {}", e)))?;
+        .map_err(|e| not_found_or_database(e, format!("transaction {}", transaction_id)))?;
 
         Ok(tx)
     }
 
-    /// Get recent transactions for vault
-    pub async fn get_vault_transactions(&self, vault_id: Uuid, limit: i32) -> Result<Vec<TransactionRecord>> {
-        let transactions = sqlx::query_as!(
-            TransactionRecord,
+    /// Number of unfinished operations of the same type queued ahead of a transaction
+    pub async fn get_queue_position(&self, transaction_id: Uuid) -> Result<i64> {
+        let row = sqlx::query!(
             r#"
-            SELECT id, vault_id, operation_type, amount, signature, status, error_message, idempotency_key, created_at, updated_at
-            FROM transaction_records
-            WHERE vault_id = $1
-            ORDER BY created_at DESC
-            LIMIT $2
+            SELECT COUNT(*) as ahead
+            FROM transaction_records queued, transaction_records target
+            WHERE target.id = $1
+              AND queued.operation_type = target.operation_type
+              AND queued.status IN ('pending', 'processing')
+              AND (queued.created_at, queued.id) < (target.created_at, target.id)
             "#,
-            vault_id,
-            limit
+            transaction_id
         )
-        .fetch_all(&self.pool)
+        .fetch_one(&self.pool)
         .await
-        .map_err(|e| VaultError::DatabaseError(format!("Failed to get vault transactions: {}", e)))?;
+        .map_err(|e| StorageError::Query(format!("Failed to get queue position: {}", e)))?;
 
-        Ok(transactions)
+        Ok(row.ahead.unwrap_or(0))
     }
 
-    /// Get pending transactions count
-    pub async fn get_pending_transactions_count(&self) -> Result<i64> {
-        let count = sqlx::query!(
+    /// Average seconds from creation to confirmation for recent operations of a type
+    pub async fn get_average_confirmation_seconds(&self, operation_type: &str, since: DateTime<Utc>) -> Result<Option<f64>> {
+        let row = sqlx::query!(
             r#"
-            SELECT COUNT(*) as count FROM transaction_records WHERE status = 'pending'
-            "#
+            SELECT AVG(EXTRACT(EPOCH FROM (updated_at - created_at)))::float8 as avg_seconds
+            FROM transaction_records
+            WHERE operation_type = $1 AND status = 'confirmed' AND updated_at >= $2
+            "#,
+            operation_type,
+            since
         )
         .fetch_one(&self.pool)
-        .await?;
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get confirmation latency: {}", e)))?;
 
-        Ok(count.count.unwrap_or(0))
+        Ok(row.avg_seconds)
     }
 
-    /// Cleanup stale pending transactions
-    pub async fn cleanup_stale_transactions(&self, cutoff_time: DateTime<Utc>) -> Result<i64> {
-        let result = sqlx::query!(
+    /// Store per-stage latency (milliseconds) on a transaction record
+    pub async fn record_stage_timings(&self, transaction_id: Uuid, stage_timings: serde_json::Value) -> Result<()> {
+        sqlx::query!(
             r#"
-            UPDATE transaction_records 
-            SET status = 'failed', error_message = 'Transaction expired', updated_at = NOW()
-            WHERE status = 'pending' AND created_at < $1
+            UPDATE transaction_records
+            SET stage_timings = $2
+            WHERE id = $1
             "#,
-            cutoff_time
+            transaction_id,
+            stage_timings
         )
         .execute(&self.pool)
         .await
-        .map_err(|e| VaultError::DatabaseError(format!("Failed to cleanup stale transactions: {}", e)))?;
+        .map_err(|e| StorageError::Query(format!("Failed to record stage timings: {}", e)))?;
 
-        Ok(result.rows_affected() as i64)
+        Ok(())
     }
-}
 
-/// Database operations for balance snapshots
-pub struct SnapshotRepository {
-    pool: PgPool,
-}
+    /// Store the signature a backend-submitted transaction landed with, and its send attempts
+    pub async fn record_submission(&self, transaction_id: Uuid, signature: &str, attempts: i32) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE transaction_records
+            SET signature = $2, submission_attempts = $3
+            WHERE id = $1
+            "#,
+            transaction_id,
+            signature,
+            attempts
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to record submission: {}", e)))?;
 
-impl SnapshotRepository {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Ok(())
+    }
+
+    /// Per-stage latency percentiles (ms) across transaction records created since `since`
+    pub async fn get_stage_latency_percentiles(&self, since: DateTime<Utc>) -> Result<Vec<StageLatencyRow>> {
+        let rows = sqlx::query_as!(
+            StageLatencyRow,
+            r#"
+            SELECT stage.key as "stage!",
+                   COUNT(*) as "count!",
+                   percentile_cont(0.5) WITHIN GROUP (ORDER BY stage.value::float8) as "p50_ms!",
+                   percentile_cont(0.95) WITHIN GROUP (ORDER BY stage.value::float8) as "p95_ms!",
+                   percentile_cont(0.99) WITHIN GROUP (ORDER BY stage.value::float8) as "p99_ms!"
+            FROM transaction_records, jsonb_each_text(stage_timings) AS stage
+            WHERE stage_timings IS NOT NULL AND created_at >= $1
+            GROUP BY stage.key
+            "#,
+            since
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get stage latency percentiles: {}", e)))?;
+
+        Ok(rows)
+    }
+
+    /// Get transaction by idempotency key
+    pub async fn get_transaction_by_idempotency_key(&self, idempotency_key: &str) -> Result<Option<TransactionRecord>> {
+        let tx = sqlx::query_as!(
+            TransactionRecord,
+            r#"
+            SELECT id, vault_id, operation_type, amount, signature, status, error_message, idempotency_key, created_at, updated_at
+            FROM transaction_records
+            WHERE idempotency_key = $1
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+            idempotency_key
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get transaction by idempotency key: {}", e)))?;
+
+        Ok(tx)
+    }
+
+    /// Get recent transactions for vault
+    pub async fn get_vault_transactions(&self, vault_id: Uuid, limit: i32) -> Result<Vec<TransactionRecord>> {
+        let transactions = sqlx::query_as!(
+            TransactionRecord,
+            r#"
+            SELECT id, vault_id, operation_type, amount, signature, status, error_message, idempotency_key, created_at, updated_at
+            FROM transaction_records
+            WHERE vault_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+            vault_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get vault transactions: {}", e)))?;
+
+        Ok(transactions)
+    }
+
+    /// Records of a vault carrying any of `signatures`
+    pub async fn get_vault_transactions_by_signatures(&self, vault_id: Uuid, signatures: &[String]) -> Result<Vec<TransactionRecord>> {
+        let records = sqlx::query_as!(
+            TransactionRecord,
+            r#"
+            SELECT id, vault_id, operation_type, amount, signature, status, error_message, idempotency_key, created_at, updated_at
+            FROM transaction_records
+            WHERE vault_id = $1 AND signature = ANY($2)
+            "#,
+            vault_id,
+            signatures
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get vault transactions by signature: {}", e)))?;
+
+        Ok(records)
+    }
+
+    /// Every confirmed record for a vault, oldest first, for ledger replay
+    pub async fn get_confirmed_ledger(&self, vault_id: Uuid) -> Result<Vec<TransactionRecord>> {
+        let records = sqlx::query_as!(
+            TransactionRecord,
+            r#"
+            SELECT id, vault_id, operation_type, amount, signature, status, error_message, idempotency_key, created_at, updated_at
+            FROM transaction_records
+            WHERE vault_id = $1 AND status = 'confirmed'
+            ORDER BY created_at
+            "#,
+            vault_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get confirmed ledger: {}", e)))?;
+
+        Ok(records)
+    }
+
+    /// Get pending transactions count
+    pub async fn get_pending_transactions_count(&self) -> Result<i64> {
+        let count = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as count FROM transaction_records WHERE status = 'pending'
+            "#
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count.count.unwrap_or(0))
+    }
+
+    /// Operations submitted or queued and not yet settled; deposits awaiting
+    /// the indexer are not counted, as nothing of ours is in flight for them
+    pub async fn count_in_flight_operations(&self) -> Result<i64> {
+        let row = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM transaction_records
+            WHERE status IN ('pending', 'processing') AND operation_type <> 'deposit'
+            "#
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to count in-flight operations: {}", e)))?;
+
+        Ok(row.count)
+    }
+
+    /// Failed withdrawals created at or after `since`, oldest first
+    pub async fn get_failed_withdrawals_since(&self, since: DateTime<Utc>, limit: i64) -> Result<Vec<TransactionRecord>> {
+        let transactions = sqlx::query_as!(
+            TransactionRecord,
+            r#"
+            SELECT id, vault_id, operation_type, amount, signature, status, error_message, idempotency_key, created_at, updated_at
+            FROM transaction_records
+            WHERE operation_type = 'withdraw' AND status = 'failed' AND created_at >= $1
+            ORDER BY created_at ASC
+            LIMIT $2
+            "#,
+            since,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to list failed withdrawals: {}", e)))?;
+
+        Ok(transactions)
+    }
+
+    /// Cleanup stale pending transactions; pending deposits are settled or expired by the indexer
+    pub async fn cleanup_stale_transactions(&self, cutoff_time: DateTime<Utc>) -> Result<i64> {
+        let row = sqlx::query!(
+            r#"
+            WITH expired AS (
+                UPDATE transaction_records 
+                SET status = 'failed', error_message = 'Transaction expired', updated_at = NOW()
+                WHERE status = 'pending' AND created_at < $1 AND operation_type <> 'deposit'
+                RETURNING id
+            ), released AS (
+                UPDATE balance_holds
+                SET status = 'released', settled_at = NOW()
+                WHERE status = 'active' AND transaction_id IN (SELECT id FROM expired)
+            )
+            SELECT COUNT(*) AS "expired!" FROM expired
+            "#,
+            cutoff_time
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to cleanup stale transactions: {}", e)))?;
+
+        Ok(row.expired)
+    }
+}
+
+/// Database operations for balance snapshots
+pub struct SnapshotRepository {
+    pool: PgPool,
+}
+
+impl SnapshotRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
     }
 
     /// Create balance snapshot
@@ -324,11 +1059,89 @@ impl SnapshotRepository {
         )
         .fetch_one(&self.pool)
         .await
-        .map_err(|e| VaultError::DatabaseError(format!("Failed to create balance snapshot: {}", e)))?;
+        .map_err(|e| StorageError::Query(format!("Failed to create balance snapshot: {}", e)))?;
 
         Ok(snapshot)
     }
 
+    /// Snapshot a pushed balance change, unless the vault's latest snapshot already
+    /// holds these balances or is newer than the change. Returns whether a row was written.
+    pub async fn record_change(&self, update: &BalanceUpdate) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO balance_snapshots (vault_id, total_balance, locked_balance, available_balance, block_height, created_at)
+            SELECT $1, $2, $3, $4, NULL, $5
+            WHERE NOT EXISTS (
+                SELECT 1 FROM (
+                    SELECT b.total_balance, b.locked_balance, b.available_balance, b.created_at
+                    FROM balance_snapshots b
+                    WHERE b.vault_id = $1
+                    ORDER BY b.created_at DESC
+                    LIMIT 1
+                ) last
+                WHERE last.created_at >= $5
+                   OR (last.total_balance = $2 AND last.locked_balance = $3 AND last.available_balance = $4)
+            )
+            "#,
+            update.vault_id,
+            update.total_balance,
+            update.locked_balance,
+            update.available_balance,
+            update.as_of
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to record balance snapshot: {}", e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Snapshot many vaults in a single statement, skipping vaults whose balances
+    /// are identical to a snapshot taken after `baseline_after`. Returns the number of rows written.
+    pub async fn create_snapshots_batch(&self, vaults: &[Vault], block_height: Option<i64>, baseline_after: DateTime<Utc>) -> Result<u64> {
+        if vaults.is_empty() {
+            return Ok(0);
+        }
+
+        let vault_ids: Vec<Uuid> = vaults.iter().map(|v| v.id).collect();
+        let totals: Vec<i64> = vaults.iter().map(|v| v.total_balance).collect();
+        let locked: Vec<i64> = vaults.iter().map(|v| v.locked_balance).collect();
+        let available: Vec<i64> = vaults.iter().map(|v| v.available_balance).collect();
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO balance_snapshots (vault_id, total_balance, locked_balance, available_balance, block_height, created_at)
+            SELECT s.vault_id, s.total_balance, s.locked_balance, s.available_balance, $5, NOW()
+            FROM UNNEST($1::uuid[], $2::bigint[], $3::bigint[], $4::bigint[])
+                AS s(vault_id, total_balance, locked_balance, available_balance)
+            WHERE NOT EXISTS (
+                SELECT 1 FROM (
+                    SELECT b.total_balance, b.locked_balance, b.available_balance, b.created_at
+                    FROM balance_snapshots b
+                    WHERE b.vault_id = s.vault_id
+                    ORDER BY b.created_at DESC
+                    LIMIT 1
+                ) last
+                WHERE last.total_balance = s.total_balance
+                  AND last.locked_balance = s.locked_balance
+                  AND last.available_balance = s.available_balance
+                  AND last.created_at > $6
+            )
+            "#,
+            &vault_ids,
+            &totals,
+            &locked,
+            &available,
+            block_height,
+            baseline_after
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to create balance snapshot batch: {}", e)))?;
+
+        Ok(result.rows_affected())
+    }
+
     /// Get recent snapshots for vault
     pub async fn get_vault_snapshots(&self, vault_id: Uuid, limit: i32) -> Result<Vec<BalanceSnapshot>> {
         let snapshots = sqlx::query_as!(
@@ -345,7 +1158,27 @@ impl SnapshotRepository {
         )
         .fetch_all(&self.pool)
         .await
-        .map_err(|e| VaultError::DatabaseError(format!("Failed to get vault snapshots: {}", e)))?;
+        .map_err(|e| StorageError::Query(format!("Failed to get vault snapshots: {}", e)))?;
+
+        Ok(snapshots)
+    }
+
+    /// Snapshots of a vault taken since `since`, oldest first
+    pub async fn get_snapshot_history(&self, vault_id: Uuid, since: DateTime<Utc>) -> Result<Vec<BalanceSnapshot>> {
+        let snapshots = sqlx::query_as!(
+            BalanceSnapshot,
+            r#"
+            SELECT id, vault_id, total_balance, locked_balance, available_balance, block_height, created_at as snapshot_time
+            FROM balance_snapshots
+            WHERE vault_id = $1 AND created_at >= $2
+            ORDER BY created_at
+            "#,
+            vault_id,
+            since
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get snapshot history: {}", e)))?;
 
         Ok(snapshots)
     }
@@ -365,7 +1198,7 @@ impl SnapshotRepository {
         )
         .fetch_one(&self.pool)
         .await
-        .map_err(|e| VaultError::DatabaseError(format!("Failed to get system stats: {}", e)))?;
+        .map_err(|e| StorageError::Query(format!("Failed to get system stats: {}", e)))?;
 
         Ok(SystemBalanceStats {
             total_value_locked: stats.total_value_locked.unwrap_or(0),
@@ -376,6 +1209,93 @@ impl SnapshotRepository {
     }
 }
 
+/// Database operations for per-mint configuration
+pub struct MintRepository {
+    pool: PgPool,
+}
+
+impl MintRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Get configuration for a mint
+    pub async fn get_mint(&self, mint_pubkey: &str) -> Result<MintConfig> {
+        let mint = sqlx::query_as!(
+            MintConfig,
+            r#"
+            SELECT mint_pubkey, symbol, decimals, enabled, min_deposit, withdrawal_fee, oracle_feed_id, created_at, updated_at
+            FROM mints
+            WHERE mint_pubkey = $1
+            "#,
+            mint_pubkey
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| not_found_or_database(e, format!("mint {}", mint_pubkey)))?;
+
+        Ok(mint)
+    }
+
+    /// List all configured mints
+    pub async fn list_mints(&self) -> Result<Vec<MintConfig>> {
+        let mints = sqlx::query_as!(
+            MintConfig,
+            r#"
+            SELECT mint_pubkey, symbol, decimals, enabled, min_deposit, withdrawal_fee, oracle_feed_id, created_at, updated_at
+            FROM mints
+            ORDER BY symbol ASC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to list mints: {}", e)))?;
+
+        Ok(mints)
+    }
+
+    /// Insert or update a mint's configuration
+    pub async fn upsert_mint(
+        &self,
+        mint_pubkey: &str,
+        symbol: &str,
+        decimals: i16,
+        enabled: bool,
+        min_deposit: i64,
+        withdrawal_fee: i64,
+        oracle_feed_id: Option<&str>,
+    ) -> Result<MintConfig> {
+        let mint = sqlx::query_as!(
+            MintConfig,
+            r#"
+            INSERT INTO mints (mint_pubkey, symbol, decimals, enabled, min_deposit, withdrawal_fee, oracle_feed_id, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, NOW(), NOW())
+            ON CONFLICT (mint_pubkey) DO UPDATE
+            SET symbol = EXCLUDED.symbol,
+                decimals = EXCLUDED.decimals,
+                enabled = EXCLUDED.enabled,
+                min_deposit = EXCLUDED.min_deposit,
+                withdrawal_fee = EXCLUDED.withdrawal_fee,
+                oracle_feed_id = EXCLUDED.oracle_feed_id,
+                updated_at = NOW()
+            RETURNING mint_pubkey, symbol, decimals, enabled, min_deposit, withdrawal_fee, oracle_feed_id, created_at, updated_at
+            "#,
+            mint_pubkey,
+            symbol,
+            decimals,
+            enabled,
+            min_deposit,
+            withdrawal_fee,
+            oracle_feed_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to upsert mint: {}", e)))?;
+
+        Ok(mint)
+    }
+}
+
 /// Database operations for audit logs
 pub struct AuditRepository {
     pool: PgPool,
@@ -408,7 +1328,7 @@ impl AuditRepository {
         )
         .execute(&self.pool)
         .await
-        .map_err(|e| VaultError::DatabaseError(format!("Failed to log audit event: {}", e)))?;
+        .map_err(|e| StorageError::Query(format!("Failed to log audit event: {}", e)))?;
 
         Ok(())
     }
@@ -427,7 +1347,7 @@ impl AuditRepository {
         )
         .fetch_all(&self.pool)
         .await
-        .map_err(|e| VaultError::DatabaseError(format!("Failed to get audit events: {}", e)))?;
+        .map_err(|e| StorageError::Query(format!("Failed to get audit events: {}", e)))?;
 
         Ok(events)
     }
@@ -448,47 +1368,4890 @@ impl AuditRepository {
         )
         .fetch_all(&self.pool)
         .await
-        .map_err(|e| VaultError::DatabaseError(format!("Failed to get vault audit events: {}", e)))?;
+        .map_err(|e| StorageError::Query(format!("Failed to get vault audit events: {}", e)))?;
 
         Ok(events)
     }
 }
 
-/// Rate limiting operations
-pub struct RateLimitRepository {
+/// Database operations for support credentials
+pub struct SupportRepository {
     pool: PgPool,
 }
 
-impl RateLimitRepository {
+impl SupportRepository {
     pub fn new(pool: PgPool) -> Self {
         Self { pool }
     }
 
-    /// Consume rate limit tokens
-    pub async fn consume_tokens(
-        &self,
-        bucket_key: &str,
-        tokens_to_consume: i32,
-        max_tokens: i32,
-        refill_rate: i32,
-    ) -> Result<crate::models::RateLimitResult> {
-        let result = sqlx::query!(
+    /// Store a new credential by token hash
+    pub async fn create_credential(&self, name: &str, role: &str, token_hash: &str) -> Result<SupportCredential> {
+        let credential = sqlx::query_as!(
+            SupportCredential,
             r#"
-            SELECT * FROM consume_rate_limit_token($1, $2, $3, $4)
+            INSERT INTO support_credentials (name, role, token_hash, created_at)
+            VALUES ($1, $2, $3, NOW())
+            RETURNING id, name, token_hash, role, created_at, revoked_at
             "#,
-            bucket_key,
-            tokens_to_consume,
-            max_tokens,
-            refill_rate
+            name,
+            role,
+            token_hash
         )
         .fetch_one(&self.pool)
         .await
-        .map_err(|e| VaultError::DatabaseError(format!("Failed to consume rate limit tokens: {}", e)))?;
+        .map_err(|e| StorageError::Query(format!("Failed to create support credential: {}", e)))?;
 
-        Ok(crate::models::RateLimitResult {
-            allowed: result.allowed.unwrap_or(false),
-            remaining_tokens: result.remaining_tokens.unwrap_or(0),
-            reset_at: result.reset_at,
-        })
+        Ok(credential)
+    }
+
+    /// Find an unrevoked credential by token hash
+    pub async fn get_active_credential(&self, token_hash: &str) -> Result<Option<SupportCredential>> {
+        let credential = sqlx::query_as!(
+            SupportCredential,
+            r#"
+            SELECT id, name, token_hash, role, created_at, revoked_at
+            FROM support_credentials
+            WHERE token_hash = $1 AND revoked_at IS NULL
+            "#,
+            token_hash
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get support credential: {}", e)))?;
+
+        Ok(credential)
+    }
+
+    /// Find a credential by token hash, revoked or not
+    pub async fn get_credential_by_hash(&self, token_hash: &str) -> Result<Option<SupportCredential>> {
+        let credential = sqlx::query_as!(
+            SupportCredential,
+            r#"
+            SELECT id, name, token_hash, role, created_at, revoked_at
+            FROM support_credentials
+            WHERE token_hash = $1
+            "#,
+            token_hash
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get support credential: {}", e)))?;
+
+        Ok(credential)
+    }
+
+    /// List all credentials, newest first
+    pub async fn list_credentials(&self) -> Result<Vec<SupportCredential>> {
+        let credentials = sqlx::query_as!(
+            SupportCredential,
+            r#"
+            SELECT id, name, token_hash, role, created_at, revoked_at
+            FROM support_credentials
+            ORDER BY created_at DESC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to list support credentials: {}", e)))?;
+
+        Ok(credentials)
+    }
+
+    /// Revoke a credential; revoking twice keeps the original timestamp
+    pub async fn revoke_credential(&self, credential_id: Uuid) -> Result<SupportCredential> {
+        let credential = sqlx::query_as!(
+            SupportCredential,
+            r#"
+            UPDATE support_credentials
+            SET revoked_at = COALESCE(revoked_at, NOW())
+            WHERE id = $1
+            RETURNING id, name, token_hash, role, created_at, revoked_at
+            "#,
+            credential_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| not_found_or_database(e, format!("Support credential {}", credential_id)))?;
+
+        Ok(credential)
     }
-}
\ No newline at end of file
+}
+
+/// Database operations for reconciliation results
+pub struct ReconciliationRepository {
+    pool: PgPool,
+}
+
+impl ReconciliationRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Store one reconciliation outcome. An inconsistent outcome is also
+    /// written to the event outbox as `alert_event`, findings included.
+    pub async fn record_result(
+        &self,
+        vault_id: Uuid,
+        mode: &str,
+        is_consistent: bool,
+        findings: serde_json::Value,
+        started_at: DateTime<Utc>,
+        completed_at: DateTime<Utc>,
+        alert_event: &str,
+    ) -> Result<ReconciliationRecord> {
+        let record = sqlx::query_as!(
+            ReconciliationRecord,
+            r#"
+            WITH recorded AS (
+                INSERT INTO reconciliation_results (vault_id, mode, is_consistent, findings, started_at, completed_at)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                RETURNING id, vault_id, mode, is_consistent, findings, started_at, completed_at
+            ), head AS (
+                UPDATE event_outbox_head SET seq = seq + 1
+                WHERE id = 1 AND NOT $3
+                RETURNING seq
+            ), outboxed AS (
+                INSERT INTO event_outbox (seq, event_type, vault_id, payload)
+                SELECT head.seq, $7, r.vault_id, jsonb_build_object(
+                    'reconciliation_id', r.id, 'vault_id', r.vault_id, 'mode', r.mode,
+                    'findings', r.findings, 'completed_at', r.completed_at)
+                FROM recorded r, head
+            )
+            SELECT id as "id!", vault_id as "vault_id!", mode as "mode!", is_consistent as "is_consistent!",
+                   findings as "findings!", started_at as "started_at!", completed_at as "completed_at!"
+            FROM recorded
+            "#,
+            vault_id,
+            mode,
+            is_consistent,
+            findings,
+            started_at,
+            completed_at,
+            alert_event
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to record reconciliation result: {}", e)))?;
+
+        Ok(record)
+    }
+
+    /// Recent results for a vault, newest first
+    pub async fn get_vault_results(&self, vault_id: Uuid, limit: i32) -> Result<Vec<ReconciliationRecord>> {
+        let records = sqlx::query_as!(
+            ReconciliationRecord,
+            r#"
+            SELECT id, vault_id, mode, is_consistent, findings, started_at, completed_at
+            FROM reconciliation_results
+            WHERE vault_id = $1
+            ORDER BY completed_at DESC
+            LIMIT $2
+            "#,
+            vault_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get reconciliation results: {}", e)))?;
+
+        Ok(records)
+    }
+}
+
+/// Database operations for multisig withdrawal proposals
+pub struct MultisigProposalRepository {
+    pool: PgPool,
+}
+
+impl MultisigProposalRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Start tracking a proposal
+    pub async fn create_proposal(
+        &self,
+        transaction_id: Uuid,
+        vault_id: Uuid,
+        multisig_pubkey: &str,
+        squads_vault_pubkey: &str,
+        transaction_index: i64,
+        proposal_pubkey: &str,
+        amount: i64,
+    ) -> Result<MultisigProposal> {
+        let proposal = sqlx::query_as!(
+            MultisigProposal,
+            r#"
+            INSERT INTO multisig_proposals (transaction_id, vault_id, multisig_pubkey, squads_vault_pubkey, transaction_index, proposal_pubkey, amount, status, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, 'awaiting_signature', NOW(), NOW())
+            RETURNING id, transaction_id, vault_id, multisig_pubkey, squads_vault_pubkey, transaction_index, proposal_pubkey, amount, status, created_at, updated_at
+            "#,
+            transaction_id,
+            vault_id,
+            multisig_pubkey,
+            squads_vault_pubkey,
+            transaction_index,
+            proposal_pubkey,
+            amount
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to create multisig proposal: {}", e)))?;
+
+        Ok(proposal)
+    }
+
+    /// Get the proposal backing a withdrawal transaction record
+    pub async fn get_by_transaction_id(&self, transaction_id: Uuid) -> Result<MultisigProposal> {
+        let proposal = sqlx::query_as!(
+            MultisigProposal,
+            r#"
+            SELECT id, transaction_id, vault_id, multisig_pubkey, squads_vault_pubkey, transaction_index, proposal_pubkey, amount, status, created_at, updated_at
+            FROM multisig_proposals
+            WHERE transaction_id = $1
+            "#,
+            transaction_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| not_found_or_database(e, format!("Multisig proposal for transaction {}", transaction_id)))?;
+
+        Ok(proposal)
+    }
+
+    /// Proposals not yet in a terminal status, least recently checked first
+    pub async fn list_open_proposals(&self, limit: i32) -> Result<Vec<MultisigProposal>> {
+        let proposals = sqlx::query_as!(
+            MultisigProposal,
+            r#"
+            SELECT id, transaction_id, vault_id, multisig_pubkey, squads_vault_pubkey, transaction_index, proposal_pubkey, amount, status, created_at, updated_at
+            FROM multisig_proposals
+            WHERE status NOT IN ('executed', 'rejected', 'cancelled', 'expired')
+            ORDER BY updated_at
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to list open multisig proposals: {}", e)))?;
+
+        Ok(proposals)
+    }
+
+    /// Record a status change
+    pub async fn update_status(&self, proposal_id: Uuid, status: &str) -> Result<MultisigProposal> {
+        let proposal = sqlx::query_as!(
+            MultisigProposal,
+            r#"
+            UPDATE multisig_proposals
+            SET status = $2, updated_at = NOW()
+            WHERE id = $1
+            RETURNING id, transaction_id, vault_id, multisig_pubkey, squads_vault_pubkey, transaction_index, proposal_pubkey, amount, status, created_at, updated_at
+            "#,
+            proposal_id,
+            status
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| not_found_or_database(e, format!("Multisig proposal {}", proposal_id)))?;
+
+        Ok(proposal)
+    }
+}
+
+/// Rate limiting operations
+pub struct RateLimitRepository {
+    pool: PgPool,
+}
+
+impl RateLimitRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Consume rate limit tokens unless the client is banned, counting the
+    /// request in the client's usage for the current minute
+    pub async fn consume_tokens(
+        &self,
+        bucket_key: &str,
+        tokens_to_consume: i32,
+        max_tokens: i32,
+        refill_rate: i32,
+    ) -> Result<crate::models::RateLimitResult> {
+        let result = sqlx::query!(
+            r#"
+            WITH ban AS (
+                SELECT MAX(expires_at) AS banned_until
+                FROM rate_limit_bans
+                WHERE client_id = $1 AND lifted_at IS NULL AND expires_at > NOW()
+            ), consumed AS (
+                SELECT * FROM consume_rate_limit_token($1, $2, $3, $4)
+            ), usage AS (
+                INSERT INTO rate_limit_usage (client_id, window_start, requests, rejected, banned, last_seen_at)
+                SELECT $1, date_trunc('minute', NOW()), 1,
+                       CASE WHEN ban.banned_until IS NULL AND NOT COALESCE(consumed.allowed, FALSE) THEN 1 ELSE 0 END,
+                       CASE WHEN ban.banned_until IS NOT NULL THEN 1 ELSE 0 END,
+                       NOW()
+                FROM consumed, ban
+                ON CONFLICT (client_id, window_start) DO UPDATE
+                SET requests = rate_limit_usage.requests + 1,
+                    rejected = rate_limit_usage.rejected + EXCLUDED.rejected,
+                    banned = rate_limit_usage.banned + EXCLUDED.banned,
+                    last_seen_at = EXCLUDED.last_seen_at
+            )
+            SELECT consumed.allowed, consumed.remaining_tokens, consumed.reset_at, ban.banned_until
+            FROM consumed, ban
+            "#,
+            bucket_key,
+            tokens_to_consume,
+            max_tokens,
+            refill_rate
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to consume rate limit tokens: {}", e)))?;
+
+        Ok(crate::models::RateLimitResult {
+            allowed: result.allowed.unwrap_or(false) && result.banned_until.is_none(),
+            remaining_tokens: result.remaining_tokens.unwrap_or(0),
+            reset_at: result.reset_at,
+            banned_until: result.banned_until,
+        })
+    }
+
+    /// Traffic per client since `since`, by requests or, with `rejected_only`,
+    /// by 429s among clients that received any
+    pub async fn get_client_usage(&self, since: DateTime<Utc>, rejected_only: bool, limit: i64) -> Result<Vec<ClientUsage>> {
+        let usage = sqlx::query_as!(
+            ClientUsage,
+            r#"
+            SELECT u.client_id as "client_id!",
+                   SUM(u.requests)::BIGINT as "requests!",
+                   SUM(u.rejected)::BIGINT as "rejected!",
+                   SUM(u.banned)::BIGINT as "banned!",
+                   COUNT(*) as "active_minutes!",
+                   COUNT(*) FILTER (WHERE u.rejected + u.banned > 0) as "limited_minutes!",
+                   MIN(u.window_start) as "first_seen_at!",
+                   MAX(u.last_seen_at) as "last_seen_at!",
+                   (SELECT COUNT(*) FROM rate_limit_bans b
+                    WHERE b.client_id = u.client_id AND b.created_at > NOW() - INTERVAL '7 days') as "recent_bans!"
+            FROM rate_limit_usage u
+            WHERE u.window_start >= date_trunc('minute', $1::timestamptz)
+            GROUP BY u.client_id
+            HAVING NOT $2 OR SUM(u.rejected + u.banned) > 0
+            ORDER BY CASE WHEN $2 THEN SUM(u.rejected + u.banned) ELSE SUM(u.requests) END DESC, u.client_id
+            LIMIT $3
+            "#,
+            since,
+            rejected_only,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get rate limit usage: {}", e)))?;
+
+        Ok(usage)
+    }
+
+    /// Bans newest first; `active_only` keeps those neither lifted nor expired
+    pub async fn get_bans(&self, client_id: Option<&str>, active_only: bool, limit: i64) -> Result<Vec<RateLimitBan>> {
+        let bans = sqlx::query_as!(
+            RateLimitBan,
+            r#"
+            SELECT id, client_id, reason, created_by, rule_id, expires_at, lifted_at, lifted_by, created_at
+            FROM rate_limit_bans
+            WHERE ($1::text IS NULL OR client_id = $1)
+              AND (NOT $2 OR (lifted_at IS NULL AND expires_at > NOW()))
+            ORDER BY created_at DESC
+            LIMIT $3
+            "#,
+            client_id,
+            active_only,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get rate limit bans: {}", e)))?;
+
+        Ok(bans)
+    }
+
+    pub async fn create_ban(
+        &self,
+        client_id: &str,
+        reason: &str,
+        created_by: &str,
+        expires_at: DateTime<Utc>,
+        audit_event: &str,
+    ) -> Result<RateLimitBan> {
+        let ban = sqlx::query_as!(
+            RateLimitBan,
+            r#"
+            WITH banned AS (
+                INSERT INTO rate_limit_bans (client_id, reason, created_by, expires_at)
+                VALUES ($1, $2, $3, $4)
+                RETURNING id, client_id, reason, created_by, rule_id, expires_at, lifted_at, lifted_by, created_at
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, details, created_at)
+                SELECT $5, jsonb_build_object('ban_id', id, 'client_id', client_id, 'reason', reason,
+                                              'actor', created_by, 'expires_at', expires_at),
+                       NOW()
+                FROM banned
+            )
+            SELECT id as "id!", client_id as "client_id!", reason as "reason!", created_by, rule_id,
+                   expires_at as "expires_at!", lifted_at, lifted_by, created_at as "created_at!"
+            FROM banned
+            "#,
+            client_id,
+            reason,
+            created_by,
+            expires_at,
+            audit_event
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to create rate limit ban: {}", e)))?;
+
+        Ok(ban)
+    }
+
+    /// Lift a ban still in force; `None` when it was already lifted or has expired
+    pub async fn lift_ban(&self, ban_id: Uuid, lifted_by: &str, audit_event: &str) -> Result<Option<RateLimitBan>> {
+        let ban = sqlx::query_as!(
+            RateLimitBan,
+            r#"
+            WITH lifted AS (
+                UPDATE rate_limit_bans
+                SET lifted_at = NOW(), lifted_by = $2
+                WHERE id = $1 AND lifted_at IS NULL AND expires_at > NOW()
+                RETURNING id, client_id, reason, created_by, rule_id, expires_at, lifted_at, lifted_by, created_at
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, details, created_at)
+                SELECT $3, jsonb_build_object('ban_id', id, 'client_id', client_id, 'actor', lifted_by), NOW()
+                FROM lifted
+            )
+            SELECT id as "id!", client_id as "client_id!", reason as "reason!", created_by, rule_id,
+                   expires_at as "expires_at!", lifted_at, lifted_by, created_at as "created_at!"
+            FROM lifted
+            "#,
+            ban_id,
+            lifted_by,
+            audit_event
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to lift rate limit ban: {}", e)))?;
+
+        Ok(ban)
+    }
+
+    pub async fn get_ban(&self, ban_id: Uuid) -> Result<RateLimitBan> {
+        let ban = sqlx::query_as!(
+            RateLimitBan,
+            r#"
+            SELECT id, client_id, reason, created_by, rule_id, expires_at, lifted_at, lifted_by, created_at
+            FROM rate_limit_bans
+            WHERE id = $1
+            "#,
+            ban_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| not_found_or_database(e, format!("Rate limit ban {}", ban_id)))?;
+
+        Ok(ban)
+    }
+
+    pub async fn get_rules(&self) -> Result<Vec<EscalationRule>> {
+        let rules = sqlx::query_as!(
+            EscalationRule,
+            r#"
+            SELECT id, name, min_rejections, window_seconds, ban_seconds, enabled, created_at
+            FROM rate_limit_escalation_rules
+            ORDER BY name
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get escalation rules: {}", e)))?;
+
+        Ok(rules)
+    }
+
+    /// Create the rule named `name` or replace its thresholds
+    pub async fn upsert_rule(
+        &self,
+        name: &str,
+        min_rejections: i64,
+        window_seconds: i32,
+        ban_seconds: i32,
+        enabled: bool,
+        actor: &str,
+        audit_event: &str,
+    ) -> Result<EscalationRule> {
+        let rule = sqlx::query_as!(
+            EscalationRule,
+            r#"
+            WITH saved AS (
+                INSERT INTO rate_limit_escalation_rules (name, min_rejections, window_seconds, ban_seconds, enabled)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (name) DO UPDATE
+                SET min_rejections = EXCLUDED.min_rejections,
+                    window_seconds = EXCLUDED.window_seconds,
+                    ban_seconds = EXCLUDED.ban_seconds,
+                    enabled = EXCLUDED.enabled
+                RETURNING id, name, min_rejections, window_seconds, ban_seconds, enabled, created_at
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, details, created_at)
+                SELECT $7, jsonb_build_object('rule_id', id, 'name', name, 'min_rejections', min_rejections,
+                                              'window_seconds', window_seconds, 'ban_seconds', ban_seconds,
+                                              'enabled', enabled, 'actor', $6::text),
+                       NOW()
+                FROM saved
+            )
+            SELECT id as "id!", name as "name!", min_rejections as "min_rejections!",
+                   window_seconds as "window_seconds!", ban_seconds as "ban_seconds!",
+                   enabled as "enabled!", created_at as "created_at!"
+            FROM saved
+            "#,
+            name,
+            min_rejections,
+            window_seconds,
+            ban_seconds,
+            enabled,
+            actor,
+            audit_event
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to save escalation rule: {}", e)))?;
+
+        Ok(rule)
+    }
+
+    /// Ban every client the rule matches that is not banned already. Only
+    /// rejections after the client's last ban ended count, so lifting a ban
+    /// does not re-trigger it from the traffic that caused it. The ban length
+    /// doubles for each ban of the client in the last week, up to 64 times.
+    pub async fn apply_rule(&self, rule: &EscalationRule, reason: &str, audit_event: &str) -> Result<Vec<RateLimitBan>> {
+        let bans = sqlx::query_as!(
+            RateLimitBan,
+            r#"
+            WITH offenders AS (
+                SELECT u.client_id
+                FROM rate_limit_usage u
+                LEFT JOIN LATERAL (
+                    SELECT MAX(LEAST(COALESCE(b.lifted_at, b.expires_at), b.expires_at)) AS ended_at
+                    FROM rate_limit_bans b
+                    WHERE b.client_id = u.client_id
+                ) last_ban ON TRUE
+                WHERE u.window_start >= date_trunc('minute', NOW() - $2 * INTERVAL '1 second')
+                  AND (last_ban.ended_at IS NULL OR u.window_start >= date_trunc('minute', last_ban.ended_at))
+                GROUP BY u.client_id
+                HAVING SUM(u.rejected) >= $3
+            ), eligible AS (
+                SELECT o.client_id,
+                       (SELECT COUNT(*) FROM rate_limit_bans b
+                        WHERE b.client_id = o.client_id AND b.created_at > NOW() - INTERVAL '7 days') AS prior_bans
+                FROM offenders o
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM rate_limit_bans b
+                    WHERE b.client_id = o.client_id AND b.lifted_at IS NULL AND b.expires_at > NOW()
+                )
+            ), banned AS (
+                INSERT INTO rate_limit_bans (client_id, reason, rule_id, expires_at)
+                SELECT client_id, $5, $1,
+                       NOW() + $4 * power(2, LEAST(prior_bans, 6)) * INTERVAL '1 second'
+                FROM eligible
+                RETURNING id, client_id, reason, created_by, rule_id, expires_at, lifted_at, lifted_by, created_at
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, details, created_at)
+                SELECT $6, jsonb_build_object('ban_id', id, 'client_id', client_id, 'rule_id', rule_id,
+                                              'reason', reason, 'expires_at', expires_at),
+                       NOW()
+                FROM banned
+            )
+            SELECT id as "id!", client_id as "client_id!", reason as "reason!", created_by, rule_id,
+                   expires_at as "expires_at!", lifted_at, lifted_by, created_at as "created_at!"
+            FROM banned
+            "#,
+            rule.id,
+            rule.window_seconds as f64,
+            rule.min_rejections,
+            rule.ban_seconds as f64,
+            reason,
+            audit_event
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to apply escalation rule {}: {}", rule.name, e)))?;
+
+        Ok(bans)
+    }
+
+    /// Delete usage windows that started before `before`
+    pub async fn prune_usage(&self, before: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query!("DELETE FROM rate_limit_usage WHERE window_start < $1", before)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| StorageError::Query(format!("Failed to prune rate limit usage: {}", e)))?;
+
+        Ok(result.rows_affected())
+    }
+}
+/// Read-only access to migration bookkeeping and the live schema
+pub struct SchemaRepository {
+    pool: PgPool,
+}
+
+impl SchemaRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Migrations recorded by sqlx, oldest first; empty if none ever ran
+    pub async fn list_applied_migrations(&self) -> Result<Vec<AppliedMigration>> {
+        let exists = sqlx::query_scalar!(
+            r#"SELECT to_regclass('_sqlx_migrations') IS NOT NULL AS "exists!""#
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to check migrations table: {}", e)))?;
+
+        if !exists {
+            return Ok(Vec::new());
+        }
+
+        let migrations = sqlx::query_as!(
+            AppliedMigration,
+            r#"
+            SELECT version, description, installed_on, success, checksum
+            FROM _sqlx_migrations
+            ORDER BY version
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to list applied migrations: {}", e)))?;
+
+        Ok(migrations)
+    }
+
+    /// Columns of the given tables in the current schema
+    pub async fn list_columns(&self, tables: &[String]) -> Result<Vec<SchemaColumn>> {
+        let columns = sqlx::query_as!(
+            SchemaColumn,
+            r#"
+            SELECT table_name::text AS "table_name!", column_name::text AS "column_name!"
+            FROM information_schema.columns
+            WHERE table_schema = current_schema()
+              AND table_name = ANY($1)
+            ORDER BY table_name, ordinal_position
+            "#,
+            tables
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to list schema columns: {}", e)))?;
+
+        Ok(columns)
+    }
+}
+
+/// Vault provisioning saga state
+pub struct ProvisioningRepository {
+    pool: PgPool,
+}
+
+impl ProvisioningRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Insert an inactive vault row and its provisioning record in one statement.
+    /// Returns `None` when a vault already exists for the user.
+    pub async fn reserve(
+        &self,
+        user_pubkey: &str,
+        vault_pubkey: &str,
+        token_account: &str,
+        authority_pubkey: &str,
+        mint_pubkey: &str,
+    ) -> Result<Option<VaultProvisioning>> {
+        let provisioning = sqlx::query_as!(
+            VaultProvisioning,
+            r#"
+            WITH reserved AS (
+                INSERT INTO vaults (user_pubkey, vault_pubkey, token_account_pubkey, total_balance, locked_balance, available_balance, is_active, created_at, updated_at)
+                VALUES ($1, $2, $3, 0, 0, 0, false, NOW(), NOW())
+                ON CONFLICT (user_pubkey) DO NOTHING
+                RETURNING id
+            )
+            INSERT INTO vault_provisioning (vault_id, user_pubkey, authority_pubkey, mint_pubkey, state, attempts, created_at, updated_at)
+            SELECT id, $1, $4, $5, 'reserved', 0, NOW(), NOW() FROM reserved
+            RETURNING vault_id, user_pubkey, authority_pubkey, mint_pubkey, state, init_signature, attempts, last_error, created_at, updated_at
+            "#,
+            user_pubkey,
+            vault_pubkey,
+            token_account,
+            authority_pubkey,
+            mint_pubkey
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to reserve vault: {}", e)))?;
+
+        if let Some(p) = &provisioning {
+            info!("Reserved vault {} for user {}", p.vault_id, user_pubkey);
+        }
+        Ok(provisioning)
+    }
+
+    /// Provisioning record for a vault; `None` for vaults created before provisioning was tracked
+    pub async fn get_by_vault_id(&self, vault_id: Uuid) -> Result<Option<VaultProvisioning>> {
+        let provisioning = sqlx::query_as!(
+            VaultProvisioning,
+            r#"
+            SELECT vault_id, user_pubkey, authority_pubkey, mint_pubkey, state, init_signature, attempts, last_error, created_at, updated_at
+            FROM vault_provisioning
+            WHERE vault_id = $1
+            "#,
+            vault_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get vault provisioning: {}", e)))?;
+
+        Ok(provisioning)
+    }
+
+    /// Provisioning records that have not reached `active` or `failed`, least recently touched first
+    pub async fn list_incomplete(&self, limit: i32) -> Result<Vec<VaultProvisioning>> {
+        let provisioning = sqlx::query_as!(
+            VaultProvisioning,
+            r#"
+            SELECT vault_id, user_pubkey, authority_pubkey, mint_pubkey, state, init_signature, attempts, last_error, created_at, updated_at
+            FROM vault_provisioning
+            WHERE state NOT IN ('active', 'failed')
+            ORDER BY updated_at
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to list incomplete vault provisioning: {}", e)))?;
+
+        Ok(provisioning)
+    }
+
+    /// Record the init transaction's signature before it is sent
+    pub async fn record_submission(&self, vault_id: Uuid, signature: &str) -> Result<VaultProvisioning> {
+        let provisioning = sqlx::query_as!(
+            VaultProvisioning,
+            r#"
+            UPDATE vault_provisioning
+            SET state = 'submitted', init_signature = $2, attempts = attempts + 1, updated_at = NOW()
+            WHERE vault_id = $1
+            RETURNING vault_id, user_pubkey, authority_pubkey, mint_pubkey, state, init_signature, attempts, last_error, created_at, updated_at
+            "#,
+            vault_id,
+            signature
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| not_found_or_database(e, format!("Vault provisioning {}", vault_id)))?;
+
+        Ok(provisioning)
+    }
+
+    /// Move to another step, keeping the last error (if any) for operators
+    pub async fn update_state(&self, vault_id: Uuid, state: &str, last_error: Option<&str>) -> Result<VaultProvisioning> {
+        let provisioning = sqlx::query_as!(
+            VaultProvisioning,
+            r#"
+            UPDATE vault_provisioning
+            SET state = $2, last_error = COALESCE($3, last_error), updated_at = NOW()
+            WHERE vault_id = $1
+            RETURNING vault_id, user_pubkey, authority_pubkey, mint_pubkey, state, init_signature, attempts, last_error, created_at, updated_at
+            "#,
+            vault_id,
+            state,
+            last_error
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| not_found_or_database(e, format!("Vault provisioning {}", vault_id)))?;
+
+        Ok(provisioning)
+    }
+
+    /// Mark provisioning complete and activate the vault row in one statement
+    pub async fn activate(&self, vault_id: Uuid) -> Result<VaultProvisioning> {
+        let provisioning = sqlx::query_as!(
+            VaultProvisioning,
+            r#"
+            WITH activated AS (
+                UPDATE vaults SET is_active = true, updated_at = NOW()
+                WHERE id = $1
+                RETURNING id
+            )
+            UPDATE vault_provisioning
+            SET state = 'active', last_error = NULL, updated_at = NOW()
+            WHERE vault_id IN (SELECT id FROM activated)
+            RETURNING vault_id, user_pubkey, authority_pubkey, mint_pubkey, state, init_signature, attempts, last_error, created_at, updated_at
+            "#,
+            vault_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| not_found_or_database(e, format!("Vault provisioning {}", vault_id)))?;
+
+        info!("Activated vault {}", vault_id);
+        Ok(provisioning)
+    }
+}
+
+/// Authority key rotations and per-vault progress
+pub struct AuthorityRotationRepository {
+    pool: PgPool,
+}
+
+impl AuthorityRotationRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create a staged rotation and enroll every vault in it
+    pub async fn create_rotation(
+        &self,
+        old_authority: &str,
+        new_authority: &str,
+        new_key_path: &str,
+        batch_size: i32,
+    ) -> Result<AuthorityRotation> {
+        let rotation = sqlx::query_as!(
+            AuthorityRotation,
+            r#"
+            WITH rotation AS (
+                INSERT INTO authority_rotations (old_authority, new_authority, new_key_path, status, batch_size, total_vaults, created_at, updated_at)
+                SELECT $1, $2, $3, 'staged', $4, COUNT(*)::INTEGER, NOW(), NOW() FROM vaults
+                RETURNING id, old_authority, new_authority, new_key_path, status, batch_size, total_vaults, created_at, updated_at, completed_at
+            ), enrolled AS (
+                INSERT INTO authority_rotation_vaults (rotation_id, vault_id, vault_pubkey, status, updated_at)
+                SELECT rotation.id, vaults.id, vaults.vault_pubkey, 'pending', NOW() FROM rotation CROSS JOIN vaults
+            )
+            SELECT id as "id!", old_authority as "old_authority!", new_authority as "new_authority!",
+                   new_key_path as "new_key_path!", status as "status!", batch_size as "batch_size!",
+                   total_vaults as "total_vaults!", created_at as "created_at!", updated_at as "updated_at!", completed_at
+            FROM rotation
+            "#,
+            old_authority,
+            new_authority,
+            new_key_path,
+            batch_size
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to create authority rotation: {}", e)))?;
+
+        info!("Staged authority rotation {} ({} vaults)", rotation.id, rotation.total_vaults);
+        Ok(rotation)
+    }
+
+    pub async fn get_rotation(&self, rotation_id: Uuid) -> Result<AuthorityRotation> {
+        let rotation = sqlx::query_as!(
+            AuthorityRotation,
+            r#"
+            SELECT id, old_authority, new_authority, new_key_path, status, batch_size, total_vaults, created_at, updated_at, completed_at
+            FROM authority_rotations
+            WHERE id = $1
+            "#,
+            rotation_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| not_found_or_database(e, format!("Authority rotation {}", rotation_id)))?;
+
+        Ok(rotation)
+    }
+
+    /// Most recently created rotation, open or not
+    pub async fn get_latest_rotation(&self) -> Result<Option<AuthorityRotation>> {
+        let rotation = sqlx::query_as!(
+            AuthorityRotation,
+            r#"
+            SELECT id, old_authority, new_authority, new_key_path, status, batch_size, total_vaults, created_at, updated_at, completed_at
+            FROM authority_rotations
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get latest authority rotation: {}", e)))?;
+
+        Ok(rotation)
+    }
+
+    /// Move a rotation to a new status; terminal statuses stamp `completed_at`
+    pub async fn update_status(&self, rotation_id: Uuid, status: &str, terminal: bool) -> Result<AuthorityRotation> {
+        let rotation = sqlx::query_as!(
+            AuthorityRotation,
+            r#"
+            UPDATE authority_rotations
+            SET status = $2, updated_at = NOW(), completed_at = CASE WHEN $3 THEN NOW() ELSE completed_at END
+            WHERE id = $1
+            RETURNING id, old_authority, new_authority, new_key_path, status, batch_size, total_vaults, created_at, updated_at, completed_at
+            "#,
+            rotation_id,
+            status,
+            terminal
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| not_found_or_database(e, format!("Authority rotation {}", rotation_id)))?;
+
+        Ok(rotation)
+    }
+
+    /// Next vaults in any of `statuses`, untried ones first
+    pub async fn next_vaults(&self, rotation_id: Uuid, statuses: &[String], limit: i32) -> Result<Vec<AuthorityRotationVault>> {
+        let vaults = sqlx::query_as!(
+            AuthorityRotationVault,
+            r#"
+            SELECT rotation_id, vault_id, vault_pubkey, status, signature, error_message, updated_at
+            FROM authority_rotation_vaults
+            WHERE rotation_id = $1 AND status = ANY($2)
+            ORDER BY (status = 'failed'), updated_at
+            LIMIT $3
+            "#,
+            rotation_id,
+            statuses,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to list rotation vaults: {}", e)))?;
+
+        Ok(vaults)
+    }
+
+    /// A vault's status within a rotation, if it is enrolled
+    pub async fn get_vault_status(&self, rotation_id: Uuid, vault_id: Uuid) -> Result<Option<String>> {
+        let status = sqlx::query_scalar!(
+            r#"
+            SELECT status FROM authority_rotation_vaults
+            WHERE rotation_id = $1 AND vault_id = $2
+            "#,
+            rotation_id,
+            vault_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get rotation vault status: {}", e)))?;
+
+        Ok(status)
+    }
+
+    pub async fn set_vault_status(
+        &self,
+        rotation_id: Uuid,
+        vault_id: Uuid,
+        status: &str,
+        signature: Option<&str>,
+        error_message: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE authority_rotation_vaults
+            SET status = $3, signature = COALESCE($4, signature), error_message = $5, updated_at = NOW()
+            WHERE rotation_id = $1 AND vault_id = $2
+            "#,
+            rotation_id,
+            vault_id,
+            status,
+            signature,
+            error_message
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to update rotation vault status: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Vault count per status within a rotation
+    pub async fn count_by_status(&self, rotation_id: Uuid) -> Result<HashMap<String, i64>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT status, COUNT(*) as "count!"
+            FROM authority_rotation_vaults
+            WHERE rotation_id = $1
+            GROUP BY status
+            "#,
+            rotation_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to count rotation vaults: {}", e)))?;
+
+        Ok(rows.into_iter().map(|r| (r.status, r.count)).collect())
+    }
+}
+
+/// Database operations for the hourly activity rollup
+pub struct ActivityRepository {
+    pool: PgPool,
+}
+
+impl ActivityRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Recompute every hour containing a record updated since the last refresh.
+    ///
+    /// The five minute overlap catches records whose update committed after
+    /// the previous refresh took its snapshot. The first refresh backfills
+    /// the whole ledger.
+    pub async fn refresh_rollups(&self) -> Result<ActivityRollupRefresh> {
+        let refresh = sqlx::query_as!(
+            ActivityRollupRefresh,
+            r#"
+            WITH since AS (
+                SELECT COALESCE((SELECT rolled_up_through FROM activity_rollup_state), '-infinity'::TIMESTAMPTZ)
+                       - INTERVAL '5 minutes' AS at
+            ), touched AS (
+                SELECT DISTINCT date_trunc('hour', r.created_at) AS bucket_start
+                FROM transaction_records r, since
+                WHERE r.updated_at >= since.at
+            ), fresh AS (
+                SELECT date_trunc('hour', r.created_at) AS bucket_start, r.vault_id, r.operation_type::TEXT AS operation_type,
+                       COUNT(*) AS tx_count, SUM(ABS(r.amount))::BIGINT AS volume
+                FROM transaction_records r
+                WHERE r.status = 'confirmed' AND date_trunc('hour', r.created_at) IN (SELECT bucket_start FROM touched)
+                GROUP BY 1, 2, 3
+            ), cleared AS (
+                DELETE FROM vault_activity_hourly h
+                WHERE h.bucket_start IN (SELECT bucket_start FROM touched)
+                  AND NOT EXISTS (
+                      SELECT 1 FROM fresh f
+                      WHERE f.bucket_start = h.bucket_start AND f.vault_id = h.vault_id AND f.operation_type = h.operation_type
+                  )
+            ), written AS (
+                INSERT INTO vault_activity_hourly (bucket_start, vault_id, operation_type, tx_count, volume)
+                SELECT bucket_start, vault_id, operation_type, tx_count, volume FROM fresh
+                ON CONFLICT (bucket_start, vault_id, operation_type)
+                DO UPDATE SET tx_count = EXCLUDED.tx_count, volume = EXCLUDED.volume
+                RETURNING 1
+            ), state AS (
+                INSERT INTO activity_rollup_state (id, rolled_up_through, refreshed_at)
+                VALUES (TRUE, NOW(), NOW())
+                ON CONFLICT (id) DO UPDATE
+                SET rolled_up_through = EXCLUDED.rolled_up_through, refreshed_at = EXCLUDED.refreshed_at
+                RETURNING rolled_up_through
+            )
+            SELECT (SELECT COUNT(*) FROM touched) as "buckets_refreshed!",
+                   (SELECT COUNT(*) FROM written) as "rows_written!",
+                   rolled_up_through as "rolled_up_through!"
+            FROM state
+            "#
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to refresh activity rollups: {}", e)))?;
+
+        Ok(refresh)
+    }
+
+    /// Point up to which the rollup reflects the ledger; `None` before the first refresh
+    pub async fn get_rolled_up_through(&self) -> Result<Option<DateTime<Utc>>> {
+        let rolled_up_through = sqlx::query_scalar!(
+            r#"
+            SELECT rolled_up_through FROM activity_rollup_state
+            "#
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get activity rollup state: {}", e)))?;
+
+        Ok(rolled_up_through)
+    }
+
+    /// Activity per bucket and operation in `[from, to)`, plus one all-operations
+    /// row per bucket (`operation_type` NULL) carrying the distinct vault count
+    pub async fn get_activity_buckets(
+        &self,
+        granularity: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        operation_types: &[String],
+    ) -> Result<Vec<ActivityBucketRow>> {
+        let rows = sqlx::query_as!(
+            ActivityBucketRow,
+            r#"
+            SELECT date_trunc($1, bucket_start) as "bucket_start!",
+                   operation_type as "operation_type?",
+                   SUM(tx_count)::BIGINT as "tx_count!",
+                   SUM(volume)::BIGINT as "volume!",
+                   COUNT(DISTINCT vault_id) as "active_vaults!"
+            FROM vault_activity_hourly
+            WHERE bucket_start >= $2 AND bucket_start < $3 AND operation_type = ANY($4)
+            GROUP BY GROUPING SETS ((date_trunc($1, bucket_start), operation_type), (date_trunc($1, bucket_start)))
+            ORDER BY 1
+            "#,
+            granularity,
+            from,
+            to,
+            operation_types
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get activity buckets: {}", e)))?;
+
+        Ok(rows)
+    }
+
+    /// Activity over the whole of `[from, to)` as a single bucket starting at `from`,
+    /// shaped like `get_activity_buckets`
+    pub async fn get_activity_totals(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        operation_types: &[String],
+    ) -> Result<Vec<ActivityBucketRow>> {
+        let rows = sqlx::query_as!(
+            ActivityBucketRow,
+            r#"
+            SELECT $1::TIMESTAMPTZ as "bucket_start!",
+                   operation_type as "operation_type?",
+                   SUM(tx_count)::BIGINT as "tx_count!",
+                   SUM(volume)::BIGINT as "volume!",
+                   COUNT(DISTINCT vault_id) as "active_vaults!"
+            FROM vault_activity_hourly
+            WHERE bucket_start >= $1 AND bucket_start < $2 AND operation_type = ANY($3)
+            GROUP BY GROUPING SETS ((operation_type), ())
+            "#,
+            from,
+            to,
+            operation_types
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get activity totals: {}", e)))?;
+
+        Ok(rows)
+    }
+
+    /// Vaults with the highest volume in `[from, to)`
+    pub async fn get_top_vaults_by_volume(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        operation_types: &[String],
+        limit: i32,
+    ) -> Result<Vec<VaultActivityVolume>> {
+        let vaults = sqlx::query_as!(
+            VaultActivityVolume,
+            r#"
+            SELECT h.vault_id, v.user_pubkey, v.vault_pubkey,
+                   SUM(h.tx_count)::BIGINT as "tx_count!",
+                   SUM(h.volume)::BIGINT as "volume!"
+            FROM vault_activity_hourly h
+            JOIN vaults v ON v.id = h.vault_id
+            WHERE h.bucket_start >= $1 AND h.bucket_start < $2 AND h.operation_type = ANY($3)
+            GROUP BY h.vault_id, v.user_pubkey, v.vault_pubkey
+            ORDER BY 5 DESC, h.vault_id
+            LIMIT $4
+            "#,
+            from,
+            to,
+            operation_types,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get top vaults by volume: {}", e)))?;
+
+        Ok(vaults)
+    }
+}
+
+/// Database operations for ops notes, tags and cases. Every write records its
+/// audit event in the same statement, so neither lands without the other.
+pub struct CaseRepository {
+    pool: PgPool,
+}
+
+impl CaseRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn get_vault_cases(&self, vault_id: Uuid, limit: i32) -> Result<Vec<VaultCase>> {
+        let cases = sqlx::query_as!(
+            VaultCase,
+            r#"
+            SELECT id, vault_id, title, status, opened_by, opened_at, closed_by, closed_at, resolution
+            FROM vault_cases
+            WHERE vault_id = $1
+            ORDER BY (status = 'open') DESC, opened_at DESC
+            LIMIT $2
+            "#,
+            vault_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get vault cases: {}", e)))?;
+
+        Ok(cases)
+    }
+
+    pub async fn get_case(&self, case_id: Uuid) -> Result<VaultCase> {
+        let case = sqlx::query_as!(
+            VaultCase,
+            r#"
+            SELECT id, vault_id, title, status, opened_by, opened_at, closed_by, closed_at, resolution
+            FROM vault_cases
+            WHERE id = $1
+            "#,
+            case_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| not_found_or_database(e, format!("Case {}", case_id)))?;
+
+        Ok(case)
+    }
+
+    /// Notes on a vault, newest first
+    pub async fn get_vault_notes(&self, vault_id: Uuid, limit: i32) -> Result<Vec<VaultNote>> {
+        let notes = sqlx::query_as!(
+            VaultNote,
+            r#"
+            SELECT id, vault_id, case_id, body, author, created_at
+            FROM vault_notes
+            WHERE vault_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+            vault_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get vault notes: {}", e)))?;
+
+        Ok(notes)
+    }
+
+    pub async fn get_vault_tags(&self, vault_id: Uuid) -> Result<Vec<VaultTag>> {
+        let tags = sqlx::query_as!(
+            VaultTag,
+            r#"
+            SELECT vault_id, tag, added_by, created_at
+            FROM vault_tags
+            WHERE vault_id = $1
+            ORDER BY tag
+            "#,
+            vault_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get vault tags: {}", e)))?;
+
+        Ok(tags)
+    }
+
+    pub async fn add_note(
+        &self,
+        note_id: Uuid,
+        vault_id: Uuid,
+        case_id: Option<Uuid>,
+        body: &str,
+        author: &str,
+        audit_event: &str,
+        audit_details: serde_json::Value,
+    ) -> Result<VaultNote> {
+        let note = sqlx::query_as!(
+            VaultNote,
+            r#"
+            WITH note AS (
+                INSERT INTO vault_notes (id, vault_id, case_id, body, author, created_at)
+                VALUES ($1, $2, $3, $4, $5, NOW())
+                RETURNING id, vault_id, case_id, body, author, created_at
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, vault_id, details, created_at)
+                SELECT $6, vault_id, $7, NOW() FROM note
+            )
+            SELECT id as "id!", vault_id as "vault_id!", case_id, body as "body!", author as "author!", created_at as "created_at!"
+            FROM note
+            "#,
+            note_id,
+            vault_id,
+            case_id,
+            body,
+            author,
+            audit_event,
+            audit_details
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to add vault note: {}", e)))?;
+
+        Ok(note)
+    }
+
+    /// Tag a vault; returns `None` when it already had the tag (nothing is audited)
+    pub async fn add_tag(
+        &self,
+        vault_id: Uuid,
+        tag: &str,
+        added_by: &str,
+        audit_event: &str,
+        audit_details: serde_json::Value,
+    ) -> Result<Option<VaultTag>> {
+        let tag = sqlx::query_as!(
+            VaultTag,
+            r#"
+            WITH tag AS (
+                INSERT INTO vault_tags (vault_id, tag, added_by, created_at)
+                VALUES ($1, $2, $3, NOW())
+                ON CONFLICT (vault_id, tag) DO NOTHING
+                RETURNING vault_id, tag, added_by, created_at
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, vault_id, details, created_at)
+                SELECT $4, vault_id, $5, NOW() FROM tag
+            )
+            SELECT vault_id as "vault_id!", tag as "tag!", added_by as "added_by!", created_at as "created_at!"
+            FROM tag
+            "#,
+            vault_id,
+            tag,
+            added_by,
+            audit_event,
+            audit_details
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to add vault tag: {}", e)))?;
+
+        Ok(tag)
+    }
+
+    /// Untag a vault; returns whether the tag was present
+    pub async fn remove_tag(
+        &self,
+        vault_id: Uuid,
+        tag: &str,
+        audit_event: &str,
+        audit_details: serde_json::Value,
+    ) -> Result<bool> {
+        let removed = sqlx::query!(
+            r#"
+            WITH removed AS (
+                DELETE FROM vault_tags
+                WHERE vault_id = $1 AND tag = $2
+                RETURNING vault_id
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, vault_id, details, created_at)
+                SELECT $3, vault_id, $4, NOW() FROM removed
+            )
+            SELECT COUNT(*) as "count!" FROM removed
+            "#,
+            vault_id,
+            tag,
+            audit_event,
+            audit_details
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to remove vault tag: {}", e)))?;
+
+        Ok(removed.count > 0)
+    }
+
+    pub async fn open_case(
+        &self,
+        case_id: Uuid,
+        vault_id: Uuid,
+        title: &str,
+        opened_by: &str,
+        audit_event: &str,
+        audit_details: serde_json::Value,
+    ) -> Result<VaultCase> {
+        let case = sqlx::query_as!(
+            VaultCase,
+            r#"
+            WITH opened AS (
+                INSERT INTO vault_cases (id, vault_id, title, status, opened_by, opened_at)
+                VALUES ($1, $2, $3, 'open', $4, NOW())
+                RETURNING id, vault_id, title, status, opened_by, opened_at, closed_by, closed_at, resolution
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, vault_id, details, created_at)
+                SELECT $5, vault_id, $6, NOW() FROM opened
+            )
+            SELECT id as "id!", vault_id as "vault_id!", title as "title!", status as "status!",
+                   opened_by as "opened_by!", opened_at as "opened_at!", closed_by, closed_at, resolution
+            FROM opened
+            "#,
+            case_id,
+            vault_id,
+            title,
+            opened_by,
+            audit_event,
+            audit_details
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to open case: {}", e)))?;
+
+        Ok(case)
+    }
+
+    /// Close an open case; `None` if it is not open (or not on this vault)
+    pub async fn close_case(
+        &self,
+        case_id: Uuid,
+        vault_id: Uuid,
+        closed_by: &str,
+        resolution: &str,
+        audit_event: &str,
+        audit_details: serde_json::Value,
+    ) -> Result<Option<VaultCase>> {
+        let case = sqlx::query_as!(
+            VaultCase,
+            r#"
+            WITH closed AS (
+                UPDATE vault_cases
+                SET status = 'closed', closed_by = $3, closed_at = NOW(), resolution = $4
+                WHERE id = $1 AND vault_id = $2 AND status = 'open'
+                RETURNING id, vault_id, title, status, opened_by, opened_at, closed_by, closed_at, resolution
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, vault_id, details, created_at)
+                SELECT $5, vault_id, $6, NOW() FROM closed
+            )
+            SELECT id as "id!", vault_id as "vault_id!", title as "title!", status as "status!",
+                   opened_by as "opened_by!", opened_at as "opened_at!", closed_by, closed_at, resolution
+            FROM closed
+            "#,
+            case_id,
+            vault_id,
+            closed_by,
+            resolution,
+            audit_event,
+            audit_details
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to close case: {}", e)))?;
+
+        Ok(case)
+    }
+}
+
+/// Repository for announced maintenance windows
+pub struct MaintenanceRepository {
+    pool: PgPool,
+}
+
+impl MaintenanceRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Windows not cancelled that end after `now`, earliest first
+    pub async fn get_pending_windows(&self, now: DateTime<Utc>) -> Result<Vec<MaintenanceWindow>> {
+        let windows = sqlx::query_as!(
+            MaintenanceWindow,
+            r#"
+            SELECT id, starts_at, ends_at, reason, read_only, created_at, cancelled_at
+            FROM maintenance_windows
+            WHERE cancelled_at IS NULL AND ends_at > $1
+            ORDER BY starts_at
+            "#,
+            now
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get maintenance windows: {}", e)))?;
+
+        Ok(windows)
+    }
+
+    pub async fn create_window(
+        &self,
+        window_id: Uuid,
+        starts_at: DateTime<Utc>,
+        ends_at: DateTime<Utc>,
+        reason: &str,
+        read_only: bool,
+        audit_event: &str,
+        audit_details: serde_json::Value,
+    ) -> Result<MaintenanceWindow> {
+        let window = sqlx::query_as!(
+            MaintenanceWindow,
+            r#"
+            WITH created AS (
+                INSERT INTO maintenance_windows (id, starts_at, ends_at, reason, read_only, created_at)
+                VALUES ($1, $2, $3, $4, $5, NOW())
+                RETURNING id, starts_at, ends_at, reason, read_only, created_at, cancelled_at
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, details, created_at)
+                SELECT $6, $7, NOW() FROM created
+            )
+            SELECT id as "id!", starts_at as "starts_at!", ends_at as "ends_at!", reason as "reason!",
+                   read_only as "read_only!", created_at as "created_at!", cancelled_at
+            FROM created
+            "#,
+            window_id,
+            starts_at,
+            ends_at,
+            reason,
+            read_only,
+            audit_event,
+            audit_details
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to create maintenance window: {}", e)))?;
+
+        Ok(window)
+    }
+
+    /// Cancel a window that has not ended; `None` if it is unknown, cancelled or over
+    pub async fn cancel_window(
+        &self,
+        window_id: Uuid,
+        audit_event: &str,
+        audit_details: serde_json::Value,
+    ) -> Result<Option<MaintenanceWindow>> {
+        let window = sqlx::query_as!(
+            MaintenanceWindow,
+            r#"
+            WITH cancelled AS (
+                UPDATE maintenance_windows
+                SET cancelled_at = NOW()
+                WHERE id = $1 AND cancelled_at IS NULL AND ends_at > NOW()
+                RETURNING id, starts_at, ends_at, reason, read_only, created_at, cancelled_at
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, details, created_at)
+                SELECT $2, $3, NOW() FROM cancelled
+            )
+            SELECT id as "id!", starts_at as "starts_at!", ends_at as "ends_at!", reason as "reason!",
+                   read_only as "read_only!", created_at as "created_at!", cancelled_at
+            FROM cancelled
+            "#,
+            window_id,
+            audit_event,
+            audit_details
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to cancel maintenance window: {}", e)))?;
+
+        Ok(window)
+    }
+}
+
+pub struct HoldRepository {
+    pool: PgPool,
+}
+
+impl HoldRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Sum of a vault's active holds per balance
+    pub async fn get_held_amounts(&self, vault_id: Uuid) -> Result<HeldAmounts> {
+        let row = sqlx::query!(
+            r#"
+            SELECT COALESCE(SUM(amount) FILTER (WHERE balance = 'available'), 0)::BIGINT AS "available!",
+                   COALESCE(SUM(amount) FILTER (WHERE balance = 'locked'), 0)::BIGINT AS "locked!"
+            FROM balance_holds
+            WHERE vault_id = $1 AND status = 'active'
+            "#,
+            vault_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get held amounts: {}", e)))?;
+
+        Ok(HeldAmounts { available: row.available, locked: row.locked })
+    }
+
+    /// Active holds on a vault, oldest first
+    pub async fn get_active_holds(&self, vault_id: Uuid) -> Result<Vec<BalanceHold>> {
+        let holds = sqlx::query_as!(
+            BalanceHold,
+            r#"
+            SELECT id, vault_id, transaction_id, balance, amount, status, created_at, settled_at
+            FROM balance_holds
+            WHERE vault_id = $1 AND status = 'active'
+            ORDER BY created_at ASC
+            "#,
+            vault_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get active holds: {}", e)))?;
+
+        Ok(holds)
+    }
+}
+
+pub struct BulkJobRepository {
+    pool: PgPool,
+}
+
+impl BulkJobRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create a previewed job with its resolved targets. `targets` are
+    /// `(target_id, vault_id, description)`; ids of unknown vaults are dropped.
+    pub async fn create_job(
+        &self,
+        job_id: Uuid,
+        operation: &str,
+        parameters: serde_json::Value,
+        reason: &str,
+        batch_size: i32,
+        requested_by: &str,
+        targets: &[(Uuid, Uuid, String)],
+        audit_event: &str,
+    ) -> Result<BulkJob> {
+        let target_ids: Vec<Uuid> = targets.iter().map(|(target_id, _, _)| *target_id).collect();
+        let vault_ids: Vec<Uuid> = targets.iter().map(|(_, vault_id, _)| *vault_id).collect();
+        let descriptions: Vec<String> = targets.iter().map(|(_, _, description)| description.clone()).collect();
+
+        let job = sqlx::query_as!(
+            BulkJob,
+            r#"
+            WITH requested AS (
+                SELECT t.target_id, t.vault_id, t.description
+                FROM UNNEST($7::uuid[], $8::uuid[], $9::text[]) AS t (target_id, vault_id, description)
+                JOIN vaults v ON v.id = t.vault_id
+            ), job AS (
+                INSERT INTO bulk_jobs (id, operation, parameters, reason, status, batch_size, total_targets, requested_by, created_at, updated_at)
+                SELECT $1, $2, $3, $4, 'previewed', $5, COUNT(*)::INTEGER, $6, NOW(), NOW() FROM requested
+                RETURNING id, operation, parameters, reason, status, batch_size, total_targets, requested_by, created_at, updated_at, started_at, completed_at
+            ), enrolled AS (
+                INSERT INTO bulk_job_targets (job_id, target_id, vault_id, description, status, updated_at)
+                SELECT job.id, requested.target_id, requested.vault_id, requested.description, 'pending', NOW()
+                FROM job CROSS JOIN requested
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, details, metadata, created_at)
+                SELECT $10, jsonb_build_object('job_id', job.id, 'operation', job.operation, 'parameters', job.parameters,
+                                               'reason', job.reason, 'total_targets', job.total_targets),
+                       jsonb_build_object('performed_by', job.requested_by), NOW()
+                FROM job
+            )
+            SELECT id as "id!", operation as "operation!", parameters as "parameters!", reason as "reason!",
+                   status as "status!", batch_size as "batch_size!", total_targets as "total_targets!",
+                   requested_by as "requested_by!", created_at as "created_at!", updated_at as "updated_at!",
+                   started_at, completed_at
+            FROM job
+            "#,
+            job_id,
+            operation,
+            parameters,
+            reason,
+            batch_size,
+            requested_by,
+            &target_ids,
+            &vault_ids,
+            &descriptions,
+            audit_event
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to create bulk job: {}", e)))?;
+
+        info!("Previewed bulk job {} ({}, {} targets)", job.id, job.operation, job.total_targets);
+        Ok(job)
+    }
+
+    pub async fn get_job(&self, job_id: Uuid) -> Result<BulkJob> {
+        let job = sqlx::query_as!(
+            BulkJob,
+            r#"
+            SELECT id, operation, parameters, reason, status, batch_size, total_targets, requested_by, created_at, updated_at, started_at, completed_at
+            FROM bulk_jobs
+            WHERE id = $1
+            "#,
+            job_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| not_found_or_database(e, format!("Bulk job {}", job_id)))?;
+
+        Ok(job)
+    }
+
+    /// Running jobs, oldest first
+    pub async fn list_running_jobs(&self) -> Result<Vec<BulkJob>> {
+        let jobs = sqlx::query_as!(
+            BulkJob,
+            r#"
+            SELECT id, operation, parameters, reason, status, batch_size, total_targets, requested_by, created_at, updated_at, started_at, completed_at
+            FROM bulk_jobs
+            WHERE status = 'running'
+            ORDER BY created_at ASC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to list running bulk jobs: {}", e)))?;
+
+        Ok(jobs)
+    }
+
+    /// Move a job from one of `from` to `to`, auditing the change; `None` if it was in another status
+    pub async fn transition(
+        &self,
+        job_id: Uuid,
+        from: &[String],
+        to: &str,
+        performed_by: &str,
+        audit_event: &str,
+    ) -> Result<Option<BulkJob>> {
+        let job = sqlx::query_as!(
+            BulkJob,
+            r#"
+            WITH updated AS (
+                UPDATE bulk_jobs
+                SET status = $3, updated_at = NOW(),
+                    started_at = CASE WHEN $3 = 'running' THEN COALESCE(started_at, NOW()) ELSE started_at END,
+                    completed_at = CASE WHEN $3 IN ('completed', 'aborted') THEN NOW() ELSE completed_at END
+                WHERE id = $1 AND status = ANY($2)
+                RETURNING id, operation, parameters, reason, status, batch_size, total_targets, requested_by, created_at, updated_at, started_at, completed_at
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, details, metadata, created_at)
+                SELECT $5, jsonb_build_object('job_id', updated.id, 'operation', updated.operation, 'status', updated.status),
+                       jsonb_build_object('performed_by', $4::text), NOW()
+                FROM updated
+            )
+            SELECT id as "id!", operation as "operation!", parameters as "parameters!", reason as "reason!",
+                   status as "status!", batch_size as "batch_size!", total_targets as "total_targets!",
+                   requested_by as "requested_by!", created_at as "created_at!", updated_at as "updated_at!",
+                   started_at, completed_at
+            FROM updated
+            "#,
+            job_id,
+            from,
+            to,
+            performed_by,
+            audit_event
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to update bulk job status: {}", e)))?;
+
+        Ok(job)
+    }
+
+    /// First targets of a job in execution order, for previews
+    pub async fn sample_targets(&self, job_id: Uuid, limit: i64) -> Result<Vec<BulkJobTarget>> {
+        let targets = sqlx::query_as!(
+            BulkJobTarget,
+            r#"
+            SELECT job_id, target_id, vault_id, description, status, error_message, updated_at
+            FROM bulk_job_targets
+            WHERE job_id = $1
+            ORDER BY description, target_id
+            LIMIT $2
+            "#,
+            job_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to list bulk job targets: {}", e)))?;
+
+        Ok(targets)
+    }
+
+    /// Most recent failures within a job
+    pub async fn failed_targets(&self, job_id: Uuid, limit: i64) -> Result<Vec<BulkJobTarget>> {
+        let targets = sqlx::query_as!(
+            BulkJobTarget,
+            r#"
+            SELECT job_id, target_id, vault_id, description, status, error_message, updated_at
+            FROM bulk_job_targets
+            WHERE job_id = $1 AND status = 'failed'
+            ORDER BY updated_at DESC
+            LIMIT $2
+            "#,
+            job_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to list failed bulk job targets: {}", e)))?;
+
+        Ok(targets)
+    }
+
+    pub async fn next_targets(&self, job_id: Uuid, limit: i32) -> Result<Vec<BulkJobTarget>> {
+        let targets = sqlx::query_as!(
+            BulkJobTarget,
+            r#"
+            SELECT job_id, target_id, vault_id, description, status, error_message, updated_at
+            FROM bulk_job_targets
+            WHERE job_id = $1 AND status = 'pending'
+            ORDER BY description, target_id
+            LIMIT $2
+            "#,
+            job_id,
+            limit as i64
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to list pending bulk job targets: {}", e)))?;
+
+        Ok(targets)
+    }
+
+    pub async fn set_target_status(&self, job_id: Uuid, target_id: Uuid, status: &str, error_message: Option<&str>) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE bulk_job_targets
+            SET status = $3, error_message = $4, updated_at = NOW()
+            WHERE job_id = $1 AND target_id = $2
+            "#,
+            job_id,
+            target_id,
+            status,
+            error_message
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to update bulk job target: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Target count per status within a job
+    pub async fn count_by_status(&self, job_id: Uuid) -> Result<HashMap<String, i64>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT status, COUNT(*) as "count!"
+            FROM bulk_job_targets
+            WHERE job_id = $1
+            GROUP BY status
+            "#,
+            job_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to count bulk job targets: {}", e)))?;
+
+        Ok(rows.into_iter().map(|r| (r.status, r.count)).collect())
+    }
+}
+
+pub struct TokenAuthorityRepository {
+    pool: PgPool,
+}
+
+impl TokenAuthorityRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Open or refresh the vault's finding; the audit event is written only when it opens
+    pub async fn record_exposure(
+        &self,
+        vault_id: Uuid,
+        token_account_pubkey: &str,
+        delegate: Option<&str>,
+        delegated_amount: i64,
+        close_authority: Option<&str>,
+        audit_event: &str,
+        audit_details: serde_json::Value,
+    ) -> Result<TokenAuthorityFinding> {
+        let finding = sqlx::query_as!(
+            TokenAuthorityFinding,
+            r#"
+            WITH recorded AS (
+                INSERT INTO token_authority_findings
+                    (vault_id, token_account_pubkey, delegate, delegated_amount, close_authority, detected_at, last_seen_at)
+                VALUES ($1, $2, $3, $4, $5, NOW(), NOW())
+                ON CONFLICT (vault_id) WHERE resolved_at IS NULL DO UPDATE
+                SET token_account_pubkey = EXCLUDED.token_account_pubkey,
+                    delegate = EXCLUDED.delegate,
+                    delegated_amount = EXCLUDED.delegated_amount,
+                    close_authority = EXCLUDED.close_authority,
+                    last_seen_at = NOW()
+                RETURNING id, vault_id, token_account_pubkey, delegate, delegated_amount, close_authority,
+                          detected_at, last_seen_at, resolved_at
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, vault_id, details, created_at)
+                SELECT $6, vault_id, $7, NOW() FROM recorded WHERE detected_at = last_seen_at
+            )
+            SELECT id as "id!", vault_id as "vault_id!", token_account_pubkey as "token_account_pubkey!",
+                   delegate, delegated_amount as "delegated_amount!", close_authority,
+                   detected_at as "detected_at!", last_seen_at as "last_seen_at!", resolved_at
+            FROM recorded
+            "#,
+            vault_id,
+            token_account_pubkey,
+            delegate,
+            delegated_amount,
+            close_authority,
+            audit_event,
+            audit_details
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to record token authority finding: {}", e)))?;
+
+        Ok(finding)
+    }
+
+    /// Close the vault's open finding; `None` if it had none
+    pub async fn resolve(
+        &self,
+        vault_id: Uuid,
+        audit_event: &str,
+        audit_details: serde_json::Value,
+    ) -> Result<Option<TokenAuthorityFinding>> {
+        let finding = sqlx::query_as!(
+            TokenAuthorityFinding,
+            r#"
+            WITH resolved AS (
+                UPDATE token_authority_findings
+                SET resolved_at = NOW()
+                WHERE vault_id = $1 AND resolved_at IS NULL
+                RETURNING id, vault_id, token_account_pubkey, delegate, delegated_amount, close_authority,
+                          detected_at, last_seen_at, resolved_at
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, vault_id, details, created_at)
+                SELECT $2, vault_id, $3, NOW() FROM resolved
+            )
+            SELECT id as "id!", vault_id as "vault_id!", token_account_pubkey as "token_account_pubkey!",
+                   delegate, delegated_amount as "delegated_amount!", close_authority,
+                   detected_at as "detected_at!", last_seen_at as "last_seen_at!", resolved_at
+            FROM resolved
+            "#,
+            vault_id,
+            audit_event,
+            audit_details
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to resolve token authority finding: {}", e)))?;
+
+        Ok(finding)
+    }
+
+    pub async fn get_finding(&self, finding_id: Uuid) -> Result<TokenAuthorityFinding> {
+        let finding = sqlx::query_as!(
+            TokenAuthorityFinding,
+            r#"
+            SELECT id, vault_id, token_account_pubkey, delegate, delegated_amount, close_authority,
+                   detected_at, last_seen_at, resolved_at
+            FROM token_authority_findings
+            WHERE id = $1
+            "#,
+            finding_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| not_found_or_database(e, format!("Token authority finding {}", finding_id)))?;
+
+        Ok(finding)
+    }
+
+    /// Findings not yet resolved, oldest first
+    pub async fn get_open_findings(&self) -> Result<Vec<TokenAuthorityFinding>> {
+        let findings = sqlx::query_as!(
+            TokenAuthorityFinding,
+            r#"
+            SELECT id, vault_id, token_account_pubkey, delegate, delegated_amount, close_authority,
+                   detected_at, last_seen_at, resolved_at
+            FROM token_authority_findings
+            WHERE resolved_at IS NULL
+            ORDER BY detected_at
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get token authority findings: {}", e)))?;
+
+        Ok(findings)
+    }
+}
+
+pub struct ScreeningRepository {
+    pool: PgPool,
+}
+
+impl ScreeningRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Store a decision together with its audit record
+    pub async fn record_decision(
+        &self,
+        decision_id: Uuid,
+        vault_id: Uuid,
+        direction: &str,
+        address: &str,
+        amount: i64,
+        risk: &str,
+        action: &str,
+        findings: serde_json::Value,
+        review_status: Option<&str>,
+        audit_event: &str,
+    ) -> Result<ScreeningDecision> {
+        let decision = sqlx::query_as!(
+            ScreeningDecision,
+            r#"
+            WITH recorded AS (
+                INSERT INTO screening_decisions
+                    (id, vault_id, direction, address, amount, risk, action, findings, review_status, created_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, NOW())
+                RETURNING id, vault_id, direction, address, amount, risk, action, findings, review_status,
+                          reviewed_by, review_note, reviewed_at, clearance_used_at, created_at
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, vault_id, details, created_at)
+                SELECT $10, vault_id,
+                       jsonb_build_object('decision_id', id, 'direction', direction, 'address', address,
+                                          'amount', amount, 'risk', risk, 'action', action, 'findings', findings),
+                       NOW()
+                FROM recorded
+            )
+            SELECT id as "id!", vault_id as "vault_id!", direction as "direction!", address as "address!",
+                   amount as "amount!", risk as "risk!", action as "action!", findings as "findings!",
+                   review_status, reviewed_by, review_note, reviewed_at, clearance_used_at,
+                   created_at as "created_at!"
+            FROM recorded
+            "#,
+            decision_id,
+            vault_id,
+            direction,
+            address,
+            amount,
+            risk,
+            action,
+            findings,
+            review_status,
+            audit_event
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to record screening decision: {}", e)))?;
+
+        Ok(decision)
+    }
+
+    /// Use up a cleared hold for the same vault, address and direction covering `amount`
+    pub async fn take_clearance(
+        &self,
+        vault_id: Uuid,
+        direction: &str,
+        address: &str,
+        amount: i64,
+        cleared_since: DateTime<Utc>,
+    ) -> Result<Option<ScreeningDecision>> {
+        let decision = sqlx::query_as!(
+            ScreeningDecision,
+            r#"
+            WITH taken AS (
+                UPDATE screening_decisions
+                SET clearance_used_at = NOW()
+                WHERE id = (
+                    SELECT id FROM screening_decisions
+                    WHERE vault_id = $1 AND direction = $2 AND address = $3 AND amount >= $4
+                      AND review_status = 'cleared' AND clearance_used_at IS NULL AND reviewed_at >= $5
+                    ORDER BY reviewed_at DESC
+                    LIMIT 1
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING id, vault_id, direction, address, amount, risk, action, findings, review_status,
+                          reviewed_by, review_note, reviewed_at, clearance_used_at, created_at
+            )
+            SELECT id as "id!", vault_id as "vault_id!", direction as "direction!", address as "address!",
+                   amount as "amount!", risk as "risk!", action as "action!", findings as "findings!",
+                   review_status, reviewed_by, review_note, reviewed_at, clearance_used_at,
+                   created_at as "created_at!"
+            FROM taken
+            "#,
+            vault_id,
+            direction,
+            address,
+            amount,
+            cleared_since
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to take screening clearance: {}", e)))?;
+
+        Ok(decision)
+    }
+
+    /// Holds awaiting review, oldest first
+    pub async fn get_pending_reviews(&self, limit: i64) -> Result<Vec<ScreeningDecision>> {
+        let decisions = sqlx::query_as!(
+            ScreeningDecision,
+            r#"
+            SELECT id, vault_id, direction, address, amount, risk, action, findings, review_status,
+                   reviewed_by, review_note, reviewed_at, clearance_used_at, created_at
+            FROM screening_decisions
+            WHERE review_status = 'pending'
+            ORDER BY created_at
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get pending screening reviews: {}", e)))?;
+
+        Ok(decisions)
+    }
+
+    pub async fn get_vault_decisions(&self, vault_id: Uuid, limit: i64) -> Result<Vec<ScreeningDecision>> {
+        let decisions = sqlx::query_as!(
+            ScreeningDecision,
+            r#"
+            SELECT id, vault_id, direction, address, amount, risk, action, findings, review_status,
+                   reviewed_by, review_note, reviewed_at, clearance_used_at, created_at
+            FROM screening_decisions
+            WHERE vault_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+            vault_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get screening decisions: {}", e)))?;
+
+        Ok(decisions)
+    }
+
+    /// Clear or reject a pending hold; `None` if it is unknown or not pending
+    pub async fn review(
+        &self,
+        decision_id: Uuid,
+        review_status: &str,
+        reviewed_by: &str,
+        review_note: &str,
+        audit_event: &str,
+    ) -> Result<Option<ScreeningDecision>> {
+        let decision = sqlx::query_as!(
+            ScreeningDecision,
+            r#"
+            WITH reviewed AS (
+                UPDATE screening_decisions
+                SET review_status = $2, reviewed_by = $3, review_note = $4, reviewed_at = NOW()
+                WHERE id = $1 AND review_status = 'pending'
+                RETURNING id, vault_id, direction, address, amount, risk, action, findings, review_status,
+                          reviewed_by, review_note, reviewed_at, clearance_used_at, created_at
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, vault_id, details, created_at)
+                SELECT $5, vault_id,
+                       jsonb_build_object('decision_id', id, 'review_status', review_status,
+                                          'reviewed_by', reviewed_by, 'review_note', review_note),
+                       NOW()
+                FROM reviewed
+            )
+            SELECT id as "id!", vault_id as "vault_id!", direction as "direction!", address as "address!",
+                   amount as "amount!", risk as "risk!", action as "action!", findings as "findings!",
+                   review_status, reviewed_by, review_note, reviewed_at, clearance_used_at,
+                   created_at as "created_at!"
+            FROM reviewed
+            "#,
+            decision_id,
+            review_status,
+            reviewed_by,
+            review_note,
+            audit_event
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to review screening decision: {}", e)))?;
+
+        Ok(decision)
+    }
+}
+
+/// Outbox events and the cursors of the consumers they are delivered to
+pub struct OutboxRepository {
+    pool: PgPool,
+}
+
+impl OutboxRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Sequence number of the latest committed event
+    pub async fn head_seq(&self) -> Result<i64> {
+        let head = sqlx::query_scalar!("SELECT seq FROM event_outbox_head WHERE id = 1")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| StorageError::Query(format!("Failed to get outbox head: {}", e)))?;
+
+        Ok(head)
+    }
+
+    /// Events after `after_seq`, in order
+    pub async fn events_after(&self, after_seq: i64, limit: i64) -> Result<Vec<OutboxEvent>> {
+        let events = sqlx::query_as!(
+            OutboxEvent,
+            r#"
+            SELECT seq, event_type, vault_id, payload, created_at
+            FROM event_outbox
+            WHERE seq > $1
+            ORDER BY seq
+            LIMIT $2
+            "#,
+            after_seq,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get outbox events: {}", e)))?;
+
+        Ok(events)
+    }
+
+    /// Register a consumer acknowledged up to `start_after` (the current head when `None`);
+    /// `None` if the name is taken
+    pub async fn register_consumer(
+        &self,
+        name: &str,
+        delivery: &str,
+        endpoint_url: Option<&str>,
+        signing_secret: Option<&str>,
+        start_after: Option<i64>,
+        audit_event: &str,
+    ) -> Result<Option<EventConsumer>> {
+        let consumer = sqlx::query_as!(
+            EventConsumer,
+            r#"
+            WITH registered AS (
+                INSERT INTO event_consumers (name, delivery, endpoint_url, signing_secret, acked_seq)
+                SELECT $1, $2, $3, $4, COALESCE($5, seq) FROM event_outbox_head WHERE id = 1
+                ON CONFLICT (name) DO NOTHING
+                RETURNING name, delivery, endpoint_url, signing_secret, acked_seq, acked_at, failed_attempts,
+                          last_error, next_attempt_at, leased_by, leased_until, enabled, created_at
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, details, created_at)
+                SELECT $6, jsonb_build_object('consumer', name, 'delivery', delivery,
+                                              'endpoint_url', endpoint_url, 'acked_seq', acked_seq),
+                       NOW()
+                FROM registered
+            )
+            SELECT name as "name!", delivery as "delivery!", endpoint_url, signing_secret,
+                   acked_seq as "acked_seq!", acked_at, failed_attempts as "failed_attempts!", last_error,
+                   next_attempt_at as "next_attempt_at!", leased_by, leased_until, enabled as "enabled!",
+                   created_at as "created_at!"
+            FROM registered
+            "#,
+            name,
+            delivery,
+            endpoint_url,
+            signing_secret,
+            start_after,
+            audit_event
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to register event consumer: {}", e)))?;
+
+        Ok(consumer)
+    }
+
+    pub async fn get_consumer(&self, name: &str) -> Result<EventConsumer> {
+        let consumer = sqlx::query_as!(
+            EventConsumer,
+            r#"
+            SELECT name, delivery, endpoint_url, signing_secret, acked_seq, acked_at, failed_attempts,
+                   last_error, next_attempt_at, leased_by, leased_until, enabled, created_at
+            FROM event_consumers
+            WHERE name = $1
+            "#,
+            name
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| not_found_or_database(e, format!("event consumer {}", name)))?;
+
+        Ok(consumer)
+    }
+
+    pub async fn get_consumers(&self) -> Result<Vec<EventConsumer>> {
+        let consumers = sqlx::query_as!(
+            EventConsumer,
+            r#"
+            SELECT name, delivery, endpoint_url, signing_secret, acked_seq, acked_at, failed_attempts,
+                   last_error, next_attempt_at, leased_by, leased_until, enabled, created_at
+            FROM event_consumers
+            ORDER BY name
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to list event consumers: {}", e)))?;
+
+        Ok(consumers)
+    }
+
+    /// Lease the enabled webhook consumer longest due for delivery; `None` when none is due
+    pub async fn lease_due_webhook(&self, worker: &str, lease_seconds: i64) -> Result<Option<EventConsumer>> {
+        let consumer = sqlx::query_as!(
+            EventConsumer,
+            r#"
+            UPDATE event_consumers
+            SET leased_by = $1, leased_until = NOW() + make_interval(secs => $2)
+            WHERE name = (
+                SELECT name FROM event_consumers
+                WHERE delivery = 'webhook' AND enabled AND next_attempt_at <= NOW()
+                  AND (leased_until IS NULL OR leased_until < NOW())
+                  AND acked_seq < (SELECT seq FROM event_outbox_head WHERE id = 1)
+                ORDER BY next_attempt_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING name, delivery, endpoint_url, signing_secret, acked_seq, acked_at, failed_attempts,
+                      last_error, next_attempt_at, leased_by, leased_until, enabled, created_at
+            "#,
+            worker,
+            lease_seconds as f64
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to lease event consumer: {}", e)))?;
+
+        Ok(consumer)
+    }
+
+    /// Move the consumer's cursor forward to `seq`; `None` if it is already there
+    /// or past it, or `seq` has not been committed
+    pub async fn acknowledge(&self, name: &str, seq: i64) -> Result<Option<EventConsumer>> {
+        let consumer = sqlx::query_as!(
+            EventConsumer,
+            r#"
+            UPDATE event_consumers
+            SET acked_seq = $2, acked_at = NOW(), failed_attempts = 0, last_error = NULL
+            WHERE name = $1 AND acked_seq < $2
+              AND $2 <= (SELECT seq FROM event_outbox_head WHERE id = 1)
+            RETURNING name, delivery, endpoint_url, signing_secret, acked_seq, acked_at, failed_attempts,
+                      last_error, next_attempt_at, leased_by, leased_until, enabled, created_at
+            "#,
+            name,
+            seq
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to acknowledge events: {}", e)))?;
+
+        Ok(consumer)
+    }
+
+    /// Give up the lease after a failed delivery and schedule the retry
+    pub async fn record_failure(
+        &self,
+        name: &str,
+        worker: &str,
+        error: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE event_consumers
+            SET failed_attempts = failed_attempts + 1, last_error = $3, next_attempt_at = $4,
+                leased_by = NULL, leased_until = NULL
+            WHERE name = $1 AND leased_by = $2
+            "#,
+            name,
+            worker,
+            error,
+            next_attempt_at
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to record delivery failure: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub async fn release_lease(&self, name: &str, worker: &str) -> Result<()> {
+        sqlx::query!(
+            "UPDATE event_consumers SET leased_by = NULL, leased_until = NULL WHERE name = $1 AND leased_by = $2",
+            name,
+            worker
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to release event consumer: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Delete events older than `before` that every enabled consumer has acknowledged
+    pub async fn prune(&self, before: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM event_outbox
+            WHERE created_at < $1
+              AND seq <= COALESCE((SELECT MIN(acked_seq) FROM event_consumers WHERE enabled), seq)
+            "#,
+            before
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to prune outbox: {}", e)))?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+/// Per-vault auto-lock targets
+pub struct AutoLockRepository {
+    pool: PgPool,
+}
+
+impl AutoLockRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Set the vault's target, keeping the record of its last adjustment
+    pub async fn upsert(
+        &self,
+        vault_id: Uuid,
+        target_locked: i64,
+        enabled: bool,
+        audit_event: &str,
+    ) -> Result<VaultAutoLock> {
+        let setting = sqlx::query_as!(
+            VaultAutoLock,
+            r#"
+            WITH upserted AS (
+                INSERT INTO vault_auto_lock (vault_id, target_locked, enabled, created_at, updated_at)
+                VALUES ($1, $2, $3, NOW(), NOW())
+                ON CONFLICT (vault_id) DO UPDATE
+                SET target_locked = EXCLUDED.target_locked, enabled = EXCLUDED.enabled, updated_at = NOW()
+                RETURNING vault_id, target_locked, enabled, last_adjustment, last_adjusted_at, last_error,
+                          created_at, updated_at
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, vault_id, details, created_at)
+                SELECT $4, vault_id, jsonb_build_object('target_locked', target_locked, 'enabled', enabled), NOW()
+                FROM upserted
+            )
+            SELECT vault_id as "vault_id!", target_locked as "target_locked!", enabled as "enabled!",
+                   last_adjustment, last_adjusted_at, last_error,
+                   created_at as "created_at!", updated_at as "updated_at!"
+            FROM upserted
+            "#,
+            vault_id,
+            target_locked,
+            enabled,
+            audit_event
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to set auto-lock: {}", e)))?;
+
+        Ok(setting)
+    }
+
+    pub async fn get(&self, vault_id: Uuid) -> Result<Option<VaultAutoLock>> {
+        let setting = sqlx::query_as!(
+            VaultAutoLock,
+            r#"
+            SELECT vault_id, target_locked, enabled, last_adjustment, last_adjusted_at, last_error,
+                   created_at, updated_at
+            FROM vault_auto_lock
+            WHERE vault_id = $1
+            "#,
+            vault_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get auto-lock: {}", e)))?;
+
+        Ok(setting)
+    }
+
+    pub async fn get_enabled(&self) -> Result<Vec<VaultAutoLock>> {
+        let settings = sqlx::query_as!(
+            VaultAutoLock,
+            r#"
+            SELECT vault_id, target_locked, enabled, last_adjustment, last_adjusted_at, last_error,
+                   created_at, updated_at
+            FROM vault_auto_lock
+            WHERE enabled
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to list auto-lock targets: {}", e)))?;
+
+        Ok(settings)
+    }
+
+    /// Record an adjustment attempt; `error` is `None` when it succeeded
+    pub async fn record_adjustment(&self, vault_id: Uuid, adjustment: i64, error: Option<&str>) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE vault_auto_lock
+            SET last_adjustment = CASE WHEN $3::TEXT IS NULL THEN $2 ELSE last_adjustment END,
+                last_adjusted_at = CASE WHEN $3::TEXT IS NULL THEN NOW() ELSE last_adjusted_at END,
+                last_error = $3
+            WHERE vault_id = $1
+            "#,
+            vault_id,
+            adjustment,
+            error
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to record auto-lock adjustment: {}", e)))?;
+
+        Ok(())
+    }
+}
+
+/// Column arrays for inserting signatures with UNNEST
+fn signature_columns(entries: &[SignatureEntry]) -> (Vec<String>, Vec<i64>, Vec<Option<i64>>, Vec<bool>) {
+    (
+        entries.iter().map(|e| e.signature.clone()).collect(),
+        entries.iter().map(|e| e.slot as i64).collect(),
+        entries.iter().map(|e| e.block_time).collect(),
+        entries.iter().map(|e| e.failed).collect(),
+    )
+}
+
+pub struct IndexerRepository {
+    pool: PgPool,
+}
+
+impl IndexerRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn get_cursor(&self, program_id: &str) -> Result<Option<IndexerCursor>> {
+        let cursor = sqlx::query_as!(
+            IndexerCursor,
+            r#"
+            SELECT program_id, last_slot, last_signature, polled_head_slot, processed_count, polled_at, created_at
+            FROM indexer_cursors
+            WHERE program_id = $1
+            "#,
+            program_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get indexer cursor: {}", e)))?;
+
+        Ok(cursor)
+    }
+
+    pub async fn get_cursors(&self) -> Result<Vec<IndexerCursor>> {
+        let cursors = sqlx::query_as!(
+            IndexerCursor,
+            r#"
+            SELECT program_id, last_slot, last_signature, polled_head_slot, processed_count, polled_at, created_at
+            FROM indexer_cursors
+            ORDER BY program_id
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to list indexer cursors: {}", e)))?;
+
+        Ok(cursors)
+    }
+
+    /// Store one poll: its signatures, the advanced cursor and the gap it left, if any.
+    ///
+    /// The cursor only moves if it still points at `expected_signature`; `None` means
+    /// another worker polled first, and only the (idempotent) signature inserts took effect.
+    pub async fn record_poll(
+        &self,
+        program_id: &str,
+        expected_signature: Option<&str>,
+        entries: &[SignatureEntry],
+        head_slot: u64,
+        gap: Option<&IndexerGapRange>,
+    ) -> Result<Option<IndexerCursor>> {
+        let (signatures, slots, block_times, failed) = signature_columns(entries);
+        let newest = entries.iter().max_by_key(|e| e.slot);
+
+        let cursor = sqlx::query_as!(
+            IndexerCursor,
+            r#"
+            WITH inserted AS (
+                INSERT INTO indexed_signatures (signature, program_id, slot, block_time, failed, source, indexed_at)
+                SELECT signature, $1, slot, to_timestamp(block_time), failed, 'poll', NOW()
+                FROM UNNEST($2::text[], $3::bigint[], $4::bigint[], $5::bool[]) AS t (signature, slot, block_time, failed)
+                ON CONFLICT (signature) DO NOTHING
+                RETURNING signature
+            ), advanced AS (
+                INSERT INTO indexer_cursors (program_id, last_slot, last_signature, polled_head_slot, processed_count, polled_at, created_at)
+                VALUES ($1, $6, $7, $8, (SELECT COUNT(*) FROM inserted), NOW(), NOW())
+                ON CONFLICT (program_id) DO UPDATE
+                SET last_slot = COALESCE(EXCLUDED.last_slot, indexer_cursors.last_slot),
+                    last_signature = COALESCE(EXCLUDED.last_signature, indexer_cursors.last_signature),
+                    polled_head_slot = GREATEST(EXCLUDED.polled_head_slot, indexer_cursors.polled_head_slot),
+                    processed_count = indexer_cursors.processed_count + EXCLUDED.processed_count,
+                    polled_at = NOW()
+                WHERE indexer_cursors.last_signature IS NOT DISTINCT FROM $9
+                RETURNING program_id, last_slot, last_signature, polled_head_slot, processed_count, polled_at, created_at
+            ), gap AS (
+                INSERT INTO indexer_gaps (program_id, from_slot, to_slot, until_signature, before_signature, status, detected_at)
+                SELECT $1, $10, $11, $12, $13, 'open', NOW()
+                FROM advanced
+                WHERE $12::TEXT IS NOT NULL
+            )
+            SELECT program_id as "program_id!", last_slot, last_signature, polled_head_slot as "polled_head_slot!",
+                   processed_count as "processed_count!", polled_at as "polled_at!", created_at as "created_at!"
+            FROM advanced
+            "#,
+            program_id,
+            &signatures,
+            &slots,
+            &block_times as &[Option<i64>],
+            &failed,
+            newest.map(|e| e.slot as i64),
+            newest.map(|e| e.signature.as_str()),
+            head_slot as i64,
+            expected_signature,
+            gap.map(|g| g.from_slot as i64),
+            gap.map(|g| g.to_slot as i64),
+            gap.map(|g| g.until_signature.as_str()),
+            gap.map(|g| g.before_signature.as_str())
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to record indexer poll: {}", e)))?;
+
+        Ok(cursor)
+    }
+
+    /// Open gaps, oldest first
+    pub async fn get_open_gaps(&self, limit: i64) -> Result<Vec<IndexerGap>> {
+        let gaps = sqlx::query_as!(
+            IndexerGap,
+            r#"
+            SELECT id, program_id, from_slot, to_slot, until_signature, before_signature, status,
+                   recovered_count, attempts, last_error, detected_at, resolved_at
+            FROM indexer_gaps
+            WHERE status = 'open'
+            ORDER BY detected_at
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to list open indexer gaps: {}", e)))?;
+
+        Ok(gaps)
+    }
+
+    /// Gaps of every status, newest first
+    pub async fn get_gaps(&self, limit: i64) -> Result<Vec<IndexerGap>> {
+        let gaps = sqlx::query_as!(
+            IndexerGap,
+            r#"
+            SELECT id, program_id, from_slot, to_slot, until_signature, before_signature, status,
+                   recovered_count, attempts, last_error, detected_at, resolved_at
+            FROM indexer_gaps
+            ORDER BY detected_at DESC
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to list indexer gaps: {}", e)))?;
+
+        Ok(gaps)
+    }
+
+    /// Store one re-scanned page of a gap and either resolve the gap or move its
+    /// upper bound down to `next`, the oldest signature of the page
+    pub async fn record_rescan(
+        &self,
+        gap_id: Uuid,
+        entries: &[SignatureEntry],
+        next: Option<&SignatureEntry>,
+    ) -> Result<IndexerGap> {
+        let (signatures, slots, block_times, failed) = signature_columns(entries);
+
+        let gap = sqlx::query_as!(
+            IndexerGap,
+            r#"
+            WITH gap AS (
+                SELECT program_id FROM indexer_gaps WHERE id = $1
+            ), inserted AS (
+                INSERT INTO indexed_signatures (signature, program_id, slot, block_time, failed, source, indexed_at)
+                SELECT t.signature, gap.program_id, t.slot, to_timestamp(t.block_time), t.failed, 'rescan', NOW()
+                FROM UNNEST($2::text[], $3::bigint[], $4::bigint[], $5::bool[]) AS t (signature, slot, block_time, failed), gap
+                ON CONFLICT (signature) DO NOTHING
+                RETURNING signature
+            )
+            UPDATE indexer_gaps
+            SET recovered_count = recovered_count + (SELECT COUNT(*) FROM inserted)::INT,
+                attempts = attempts + 1,
+                last_error = NULL,
+                before_signature = COALESCE($6, before_signature),
+                to_slot = GREATEST(from_slot, COALESCE($7, to_slot)),
+                status = CASE WHEN $6::TEXT IS NULL THEN 'resolved' ELSE status END,
+                resolved_at = CASE WHEN $6::TEXT IS NULL THEN NOW() ELSE resolved_at END
+            WHERE id = $1
+            RETURNING id, program_id, from_slot, to_slot, until_signature, before_signature, status,
+                      recovered_count, attempts, last_error, detected_at, resolved_at
+            "#,
+            gap_id,
+            &signatures,
+            &slots,
+            &block_times as &[Option<i64>],
+            &failed,
+            next.map(|e| e.signature.as_str()),
+            next.map(|e| e.slot as i64)
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| not_found_or_database(e, format!("Indexer gap {}", gap_id)))?;
+
+        Ok(gap)
+    }
+
+    pub async fn record_rescan_failure(&self, gap_id: Uuid, error: &str) -> Result<()> {
+        sqlx::query!(
+            "UPDATE indexer_gaps SET attempts = attempts + 1, last_error = $2 WHERE id = $1",
+            gap_id,
+            error
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to record indexer re-scan failure: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Confirmed transaction records in `[since, until)` whose signature the indexer never processed
+    pub async fn count_unindexed_confirmed(&self, since: DateTime<Utc>, until: DateTime<Utc>) -> Result<i64> {
+        let row = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM transaction_records t
+            WHERE t.status = 'confirmed'
+              AND t.signature IS NOT NULL
+              AND t.updated_at >= $1 AND t.updated_at < $2
+              AND NOT EXISTS (SELECT 1 FROM indexed_signatures s WHERE s.signature = t.signature)
+            "#,
+            since,
+            until
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to count unindexed transactions: {}", e)))?;
+
+        Ok(row.count)
+    }
+}
+
+pub struct IdentityRepository {
+    pool: PgPool,
+}
+
+impl IdentityRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn get_identity(&self, user_pubkey: &str) -> Result<Option<Identity>> {
+        let identity = sqlx::query_as!(
+            Identity,
+            r#"
+            SELECT user_pubkey, provider, external_id, level, status, verified_at, expires_at, reported_at,
+                   created_at, updated_at
+            FROM identities
+            WHERE user_pubkey = $1
+            "#,
+            user_pubkey
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get identity: {}", e)))?;
+
+        Ok(identity)
+    }
+
+    /// Store a verification event and apply it to the identity, with an audit record.
+    ///
+    /// Returns `None` for a redelivered event and for one reported before the
+    /// result already applied.
+    pub async fn record_verification(
+        &self,
+        provider: &str,
+        event_id: &str,
+        user_pubkey: &str,
+        external_id: &str,
+        level: &str,
+        status: &str,
+        expires_at: Option<DateTime<Utc>>,
+        reported_at: DateTime<Utc>,
+        audit_event: &str,
+    ) -> Result<Option<Identity>> {
+        let identity = sqlx::query_as!(
+            Identity,
+            r#"
+            WITH event AS (
+                INSERT INTO identity_verification_events (provider, event_id, user_pubkey, level, status, reported_at, received_at)
+                VALUES ($1, $2, $3, $5, $6, $8, NOW())
+                ON CONFLICT (provider, event_id) DO NOTHING
+                RETURNING user_pubkey
+            ), applied AS (
+                INSERT INTO identities
+                    (user_pubkey, provider, external_id, level, status, verified_at, expires_at, reported_at, created_at, updated_at)
+                SELECT user_pubkey, $1, $4, $5, $6, CASE WHEN $6 = 'verified' THEN $8 END, $7, $8, NOW(), NOW()
+                FROM event
+                ON CONFLICT (user_pubkey) DO UPDATE
+                SET provider = EXCLUDED.provider,
+                    external_id = EXCLUDED.external_id,
+                    level = EXCLUDED.level,
+                    status = EXCLUDED.status,
+                    verified_at = EXCLUDED.verified_at,
+                    expires_at = EXCLUDED.expires_at,
+                    reported_at = EXCLUDED.reported_at,
+                    updated_at = NOW()
+                WHERE identities.reported_at <= EXCLUDED.reported_at
+                RETURNING user_pubkey, provider, external_id, level, status, verified_at, expires_at, reported_at,
+                          created_at, updated_at
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, vault_id, details, created_at)
+                SELECT $9, (SELECT id FROM vaults WHERE vaults.user_pubkey = applied.user_pubkey),
+                       jsonb_build_object('user_pubkey', user_pubkey, 'provider', provider, 'event_id', $2::TEXT,
+                                          'external_id', external_id, 'level', level, 'status', status,
+                                          'expires_at', expires_at),
+                       NOW()
+                FROM applied
+            )
+            SELECT user_pubkey as "user_pubkey!", provider as "provider!", external_id as "external_id!",
+                   level as "level!", status as "status!", verified_at, expires_at,
+                   reported_at as "reported_at!", created_at as "created_at!", updated_at as "updated_at!"
+            FROM applied
+            "#,
+            provider,
+            event_id,
+            user_pubkey,
+            external_id,
+            level,
+            status,
+            expires_at,
+            reported_at,
+            audit_event
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to record identity verification: {}", e)))?;
+
+        Ok(identity)
+    }
+}
+
+pub struct AlertRuleRepository {
+    pool: PgPool,
+}
+
+impl AlertRuleRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Rules of a vault, oldest first; `enabled_only` for evaluation
+    pub async fn get_rules(&self, vault_id: Uuid, enabled_only: bool) -> Result<Vec<VaultAlertRule>> {
+        let rules = sqlx::query_as!(
+            VaultAlertRule,
+            r#"
+            SELECT id, vault_id, kind, threshold, label, enabled, triggered, trigger_count, last_triggered_at,
+                   created_at, updated_at
+            FROM vault_alert_rules
+            WHERE vault_id = $1 AND (enabled OR NOT $2)
+            ORDER BY created_at
+            "#,
+            vault_id,
+            enabled_only
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get alert rules: {}", e)))?;
+
+        Ok(rules)
+    }
+
+    /// Create a rule unless the vault already has `max_rules`; `None` when it has
+    pub async fn create_rule(
+        &self,
+        vault_id: Uuid,
+        kind: &str,
+        threshold: Option<i64>,
+        label: Option<&str>,
+        enabled: bool,
+        max_rules: i64,
+        audit_event: &str,
+    ) -> Result<Option<VaultAlertRule>> {
+        let rule = sqlx::query_as!(
+            VaultAlertRule,
+            r#"
+            WITH created AS (
+                INSERT INTO vault_alert_rules (vault_id, kind, threshold, label, enabled, created_at, updated_at)
+                SELECT $1, $2, $3, $4, $5, NOW(), NOW()
+                WHERE (SELECT COUNT(*) FROM vault_alert_rules WHERE vault_id = $1) < $6
+                RETURNING id, vault_id, kind, threshold, label, enabled, triggered, trigger_count, last_triggered_at,
+                          created_at, updated_at
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, vault_id, details, created_at)
+                SELECT $7, vault_id,
+                       jsonb_build_object('rule_id', id, 'kind', kind, 'threshold', threshold, 'label', label,
+                                          'enabled', enabled),
+                       NOW()
+                FROM created
+            )
+            SELECT id as "id!", vault_id as "vault_id!", kind as "kind!", threshold, label, enabled as "enabled!",
+                   triggered as "triggered!", trigger_count as "trigger_count!", last_triggered_at,
+                   created_at as "created_at!", updated_at as "updated_at!"
+            FROM created
+            "#,
+            vault_id,
+            kind,
+            threshold,
+            label,
+            enabled,
+            max_rules,
+            audit_event
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to create alert rule: {}", e)))?;
+
+        Ok(rule)
+    }
+
+    /// Replace a rule's threshold, label and enabled flag; a changed threshold re-arms it
+    pub async fn update_rule(
+        &self,
+        vault_id: Uuid,
+        rule_id: Uuid,
+        threshold: Option<i64>,
+        label: Option<&str>,
+        enabled: bool,
+        audit_event: &str,
+    ) -> Result<VaultAlertRule> {
+        let rule = sqlx::query_as!(
+            VaultAlertRule,
+            r#"
+            WITH updated AS (
+                UPDATE vault_alert_rules
+                SET threshold = $3,
+                    label = $4,
+                    enabled = $5,
+                    triggered = triggered AND threshold IS NOT DISTINCT FROM $3,
+                    updated_at = NOW()
+                WHERE id = $2 AND vault_id = $1
+                RETURNING id, vault_id, kind, threshold, label, enabled, triggered, trigger_count, last_triggered_at,
+                          created_at, updated_at
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, vault_id, details, created_at)
+                SELECT $6, vault_id,
+                       jsonb_build_object('rule_id', id, 'kind', kind, 'threshold', threshold, 'label', label,
+                                          'enabled', enabled),
+                       NOW()
+                FROM updated
+            )
+            SELECT id as "id!", vault_id as "vault_id!", kind as "kind!", threshold, label, enabled as "enabled!",
+                   triggered as "triggered!", trigger_count as "trigger_count!", last_triggered_at,
+                   created_at as "created_at!", updated_at as "updated_at!"
+            FROM updated
+            "#,
+            vault_id,
+            rule_id,
+            threshold,
+            label,
+            enabled,
+            audit_event
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| not_found_or_database(e, format!("Alert rule {}", rule_id)))?;
+
+        Ok(rule)
+    }
+
+    /// Delete a rule; `false` when the vault has no such rule
+    pub async fn delete_rule(&self, vault_id: Uuid, rule_id: Uuid, audit_event: &str) -> Result<bool> {
+        let deleted = sqlx::query!(
+            r#"
+            WITH deleted AS (
+                DELETE FROM vault_alert_rules
+                WHERE id = $2 AND vault_id = $1
+                RETURNING id, vault_id, kind
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, vault_id, details, created_at)
+                SELECT $3, vault_id, jsonb_build_object('rule_id', id, 'kind', kind, 'deleted', true), NOW()
+                FROM deleted
+            )
+            SELECT COUNT(*) as "count!" FROM deleted
+            "#,
+            vault_id,
+            rule_id,
+            audit_event
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to delete alert rule: {}", e)))?;
+
+        Ok(deleted.count > 0)
+    }
+
+    /// Store whether a rule's condition holds.
+    ///
+    /// When it starts to hold, or on every call with `always_fire`, the rule
+    /// fires: its count is bumped and an alert event carrying `observed` is
+    /// written to the outbox. Returns the rule when it fired.
+    pub async fn record_evaluation(
+        &self,
+        rule_id: Uuid,
+        holds: bool,
+        always_fire: bool,
+        observed: serde_json::Value,
+        alert_event: &str,
+    ) -> Result<Option<VaultAlertRule>> {
+        let rule = sqlx::query_as!(
+            VaultAlertRule,
+            r#"
+            WITH previous AS (
+                SELECT id, triggered FROM vault_alert_rules WHERE id = $1 AND enabled FOR UPDATE
+            ), updated AS (
+                UPDATE vault_alert_rules r
+                SET triggered = $2,
+                    trigger_count = r.trigger_count + CASE WHEN $2 AND ($3 OR NOT p.triggered) THEN 1 ELSE 0 END,
+                    last_triggered_at = CASE WHEN $2 AND ($3 OR NOT p.triggered) THEN NOW() ELSE r.last_triggered_at END,
+                    updated_at = NOW()
+                FROM previous p
+                WHERE r.id = p.id AND (p.triggered <> $2 OR ($2 AND $3))
+                RETURNING r.id, r.vault_id, r.kind, r.threshold, r.label, r.enabled, r.triggered, r.trigger_count,
+                          r.last_triggered_at, r.created_at, r.updated_at, $2 AND ($3 OR NOT p.triggered) AS fired
+            ), head AS (
+                UPDATE event_outbox_head SET seq = seq + 1
+                WHERE id = 1 AND EXISTS (SELECT 1 FROM updated WHERE fired)
+                RETURNING seq
+            ), outboxed AS (
+                INSERT INTO event_outbox (seq, event_type, vault_id, payload)
+                SELECT head.seq, $5, u.vault_id,
+                       jsonb_build_object('rule_id', u.id, 'vault_id', u.vault_id, 'kind', u.kind,
+                                          'threshold', u.threshold, 'label', u.label,
+                                          'triggered_at', u.last_triggered_at, 'observed', $4::JSONB)
+                FROM updated u, head
+                WHERE u.fired
+            )
+            SELECT id as "id!", vault_id as "vault_id!", kind as "kind!", threshold, label, enabled as "enabled!",
+                   triggered as "triggered!", trigger_count as "trigger_count!", last_triggered_at,
+                   created_at as "created_at!", updated_at as "updated_at!"
+            FROM updated
+            WHERE fired
+            "#,
+            rule_id,
+            holds,
+            always_fire,
+            observed,
+            alert_event
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to record alert evaluation: {}", e)))?;
+
+        Ok(rule)
+    }
+}
+
+pub struct ExportRepository {
+    pool: PgPool,
+}
+
+impl ExportRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create_export(
+        &self,
+        export_id: Uuid,
+        requested_by: &str,
+        range_start: DateTime<Utc>,
+        range_end: DateTime<Utc>,
+        audit_event: &str,
+    ) -> Result<DataExport> {
+        let export = sqlx::query_as!(
+            DataExport,
+            r#"
+            WITH created AS (
+                INSERT INTO data_exports (id, requested_by, range_start, range_end, created_at)
+                VALUES ($1, $2, $3, $4, NOW())
+                RETURNING id, requested_by, range_start, range_end, status, snapshot_at, objects, error_message, attempts,
+                      leased_by, leased_until, created_at, started_at, completed_at
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, details, created_at)
+                SELECT $5, jsonb_build_object('export_id', id, 'requested_by', requested_by,
+                                              'range_start', range_start, 'range_end', range_end),
+                       NOW()
+                FROM created
+            )
+            SELECT id as "id!", requested_by as "requested_by!", range_start as "range_start!", range_end as "range_end!",
+                   status as "status!", snapshot_at, objects as "objects!", error_message, attempts as "attempts!",
+                   leased_by, leased_until, created_at as "created_at!", started_at, completed_at
+            FROM created
+            "#,
+            export_id,
+            requested_by,
+            range_start,
+            range_end,
+            audit_event
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to create export: {}", e)))?;
+
+        Ok(export)
+    }
+
+    pub async fn get_export(&self, export_id: Uuid) -> Result<DataExport> {
+        let export = sqlx::query_as!(
+            DataExport,
+            r#"
+            SELECT id, requested_by, range_start, range_end, status, snapshot_at, objects, error_message, attempts,
+                      leased_by, leased_until, created_at, started_at, completed_at
+            FROM data_exports
+            WHERE id = $1
+            "#,
+            export_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| not_found_or_database(e, format!("Export {}", export_id)))?;
+
+        Ok(export)
+    }
+
+    /// Newest first
+    pub async fn get_exports(&self, limit: i64) -> Result<Vec<DataExport>> {
+        let exports = sqlx::query_as!(
+            DataExport,
+            r#"
+            SELECT id, requested_by, range_start, range_end, status, snapshot_at, objects, error_message, attempts,
+                      leased_by, leased_until, created_at, started_at, completed_at
+            FROM data_exports
+            ORDER BY created_at DESC
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get exports: {}", e)))?;
+
+        Ok(exports)
+    }
+
+    /// Take the oldest pending export, or a running one whose worker's lease ran out
+    pub async fn lease_next(&self, worker: &str, lease_seconds: i64) -> Result<Option<DataExport>> {
+        let export = sqlx::query_as!(
+            DataExport,
+            r#"
+            UPDATE data_exports
+            SET status = 'running', attempts = attempts + 1, leased_by = $1,
+                leased_until = NOW() + make_interval(secs => $2), started_at = COALESCE(started_at, NOW())
+            WHERE id = (
+                SELECT id FROM data_exports
+                WHERE status = 'pending' OR (status = 'running' AND leased_until < NOW())
+                ORDER BY created_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, requested_by, range_start, range_end, status, snapshot_at, objects, error_message, attempts,
+                      leased_by, leased_until, created_at, started_at, completed_at
+            "#,
+            worker,
+            lease_seconds as f64
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to lease export: {}", e)))?;
+
+        Ok(export)
+    }
+
+    /// Read vaults, the range's transactions and the confirmed ledger up to its end
+    /// in one read-only repeatable-read transaction
+    pub async fn read_snapshot(&self, range_start: DateTime<Utc>, range_end: DateTime<Utc>) -> Result<ExportSnapshot> {
+        let mut db_tx = self.pool.begin().await
+            .map_err(|e| StorageError::Query(format!("Failed to begin export snapshot: {}", e)))?;
+        sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+            .execute(&mut db_tx)
+            .await
+            .map_err(|e| StorageError::Query(format!("Failed to set export isolation: {}", e)))?;
+
+        // Transaction time is fixed for the transaction, so this is the snapshot's instant
+        let snapshot_at = sqlx::query!(r#"SELECT NOW() as "now!""#)
+            .fetch_one(&mut db_tx)
+            .await
+            .map_err(|e| StorageError::Query(format!("Failed to read export snapshot time: {}", e)))?
+            .now;
+
+        let vaults = sqlx::query_as!(
+            ExportedVault,
+            r#"
+            SELECT id, user_pubkey, vault_pubkey, token_account_pubkey, mint_pubkey, total_balance, locked_balance,
+                   available_balance, is_active, created_at, updated_at
+            FROM vaults
+            ORDER BY created_at, id
+            "#
+        )
+        .fetch_all(&mut db_tx)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to export vaults: {}", e)))?;
+
+        let transactions = sqlx::query_as!(
+            TransactionRecord,
+            r#"
+            SELECT id, vault_id, operation_type, amount, signature, status, error_message, idempotency_key, created_at, updated_at
+            FROM transaction_records
+            WHERE created_at >= $1 AND created_at < $2
+            ORDER BY created_at, id
+            "#,
+            range_start,
+            range_end
+        )
+        .fetch_all(&mut db_tx)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to export transactions: {}", e)))?;
+
+        let confirmed = sqlx::query_as!(
+            TransactionRecord,
+            r#"
+            SELECT id, vault_id, operation_type, amount, signature, status, error_message, idempotency_key, created_at, updated_at
+            FROM transaction_records
+            WHERE status = 'confirmed' AND updated_at < $1
+            ORDER BY vault_id, updated_at, id
+            "#,
+            range_end
+        )
+        .fetch_all(&mut db_tx)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to export ledger: {}", e)))?;
+
+        db_tx.commit().await
+            .map_err(|e| StorageError::Query(format!("Failed to end export snapshot: {}", e)))?;
+
+        Ok(ExportSnapshot { snapshot_at, vaults, transactions, confirmed })
+    }
+
+    /// Mark a leased export completed and emit its completion event
+    pub async fn complete_export(
+        &self,
+        export_id: Uuid,
+        worker: &str,
+        snapshot_at: DateTime<Utc>,
+        objects: serde_json::Value,
+        event_payload: serde_json::Value,
+        completed_event: &str,
+    ) -> Result<Option<DataExport>> {
+        let export = sqlx::query_as!(
+            DataExport,
+            r#"
+            WITH completed AS (
+                UPDATE data_exports
+                SET status = 'completed', snapshot_at = $3, objects = $4, error_message = NULL,
+                    leased_by = NULL, leased_until = NULL, completed_at = NOW()
+                WHERE id = $1 AND status = 'running' AND leased_by = $2
+                RETURNING id, requested_by, range_start, range_end, status, snapshot_at, objects, error_message, attempts,
+                      leased_by, leased_until, created_at, started_at, completed_at
+            ), head AS (
+                UPDATE event_outbox_head SET seq = seq + 1
+                WHERE id = 1 AND EXISTS (SELECT 1 FROM completed)
+                RETURNING seq
+            ), outboxed AS (
+                INSERT INTO event_outbox (seq, event_type, vault_id, payload)
+                SELECT head.seq, $6, NULL, $5::JSONB
+                FROM head
+            )
+            SELECT id as "id!", requested_by as "requested_by!", range_start as "range_start!", range_end as "range_end!",
+                   status as "status!", snapshot_at, objects as "objects!", error_message, attempts as "attempts!",
+                   leased_by, leased_until, created_at as "created_at!", started_at, completed_at
+            FROM completed
+            "#,
+            export_id,
+            worker,
+            snapshot_at,
+            objects,
+            event_payload,
+            completed_event
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to complete export: {}", e)))?;
+
+        Ok(export)
+    }
+
+    /// Record a failed attempt: the export is retried until `max_attempts`,
+    /// then marked failed with a failure event
+    pub async fn fail_export(
+        &self,
+        export_id: Uuid,
+        worker: &str,
+        error_message: &str,
+        max_attempts: i32,
+        failed_event: &str,
+    ) -> Result<Option<DataExport>> {
+        let export = sqlx::query_as!(
+            DataExport,
+            r#"
+            WITH failed AS (
+                UPDATE data_exports
+                SET status = CASE WHEN attempts >= $4 THEN 'failed' ELSE 'pending' END,
+                    error_message = $3, leased_by = NULL, leased_until = NULL,
+                    completed_at = CASE WHEN attempts >= $4 THEN NOW() END
+                WHERE id = $1 AND status = 'running' AND leased_by = $2
+                RETURNING id, requested_by, range_start, range_end, status, snapshot_at, objects, error_message, attempts,
+                      leased_by, leased_until, created_at, started_at, completed_at
+            ), head AS (
+                UPDATE event_outbox_head SET seq = seq + 1
+                WHERE id = 1 AND EXISTS (SELECT 1 FROM failed WHERE status = 'failed')
+                RETURNING seq
+            ), outboxed AS (
+                INSERT INTO event_outbox (seq, event_type, vault_id, payload)
+                SELECT head.seq, $5, NULL,
+                       jsonb_build_object('export_id', f.id, 'status', f.status, 'range_start', f.range_start,
+                                          'range_end', f.range_end, 'attempts', f.attempts, 'error', f.error_message)
+                FROM failed f, head
+            )
+            SELECT id as "id!", requested_by as "requested_by!", range_start as "range_start!", range_end as "range_end!",
+                   status as "status!", snapshot_at, objects as "objects!", error_message, attempts as "attempts!",
+                   leased_by, leased_until, created_at as "created_at!", started_at, completed_at
+            FROM failed
+            "#,
+            export_id,
+            worker,
+            error_message,
+            max_attempts,
+            failed_event
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to record export failure: {}", e)))?;
+
+        Ok(export)
+    }
+}
+
+pub struct IdempotencyRepository {
+    pool: PgPool,
+}
+
+impl IdempotencyRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Claim `key` of `client_id` for a request, unless the key is held.
+    ///
+    /// An expired key is claimed afresh whatever it was used for; an abandoned
+    /// in-progress key (past `locked_until`) only by the same request.
+    pub async fn claim(
+        &self,
+        client_id: &str,
+        key: &str,
+        method: &str,
+        path: &str,
+        request_hash: &str,
+        lock_seconds: i64,
+        retention_seconds: i64,
+    ) -> Result<IdempotencyClaim> {
+        let row = sqlx::query!(
+            r#"
+            WITH claimed AS (
+                INSERT INTO idempotency_keys (client_id, idempotency_key, method, path, request_hash, status,
+                                              locked_until, created_at, expires_at)
+                VALUES ($1, $2, $3, $4, $5, 'in_progress', NOW() + make_interval(secs => $6),
+                        NOW(), NOW() + make_interval(secs => $7))
+                ON CONFLICT (client_id, idempotency_key) DO UPDATE
+                SET method = EXCLUDED.method,
+                    path = EXCLUDED.path,
+                    request_hash = EXCLUDED.request_hash,
+                    status = 'in_progress',
+                    response_status = NULL,
+                    response_content_type = NULL,
+                    response_body = NULL,
+                    locked_until = EXCLUDED.locked_until,
+                    created_at = NOW(),
+                    completed_at = NULL,
+                    expires_at = EXCLUDED.expires_at
+                WHERE idempotency_keys.expires_at < NOW()
+                   OR (idempotency_keys.status = 'in_progress' AND idempotency_keys.locked_until < NOW()
+                       AND idempotency_keys.request_hash = EXCLUDED.request_hash)
+                RETURNING client_id
+            )
+            SELECT TRUE AS "claimed!", NULL::TEXT AS request_hash, NULL::TEXT AS status,
+                   NULL::INTEGER AS response_status, NULL::TEXT AS response_content_type, NULL::BYTEA AS response_body
+            FROM claimed
+            UNION ALL
+            SELECT FALSE, request_hash, status, response_status, response_content_type, response_body
+            FROM idempotency_keys
+            WHERE client_id = $1 AND idempotency_key = $2 AND NOT EXISTS (SELECT 1 FROM claimed)
+            "#,
+            client_id,
+            key,
+            method,
+            path,
+            request_hash,
+            lock_seconds as f64,
+            retention_seconds as f64
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to claim idempotency key: {}", e)))?;
+
+        // No row: another request inserted the key after this statement's snapshot
+        let Some(row) = row else {
+            return Ok(IdempotencyClaim::InProgress);
+        };
+        if row.claimed {
+            return Ok(IdempotencyClaim::Claimed);
+        }
+        if row.request_hash.as_deref() != Some(request_hash) {
+            return Ok(IdempotencyClaim::Mismatch);
+        }
+        match (row.status.as_deref(), row.response_status) {
+            (Some("completed"), Some(status)) => Ok(IdempotencyClaim::Completed(StoredResponse {
+                status: status as u16,
+                content_type: row.response_content_type,
+                body: row.response_body.unwrap_or_default(),
+            })),
+            _ => Ok(IdempotencyClaim::InProgress),
+        }
+    }
+
+    /// Store the response of a claimed key's request for replay
+    pub async fn complete(&self, client_id: &str, key: &str, response: &StoredResponse) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE idempotency_keys
+            SET status = 'completed', response_status = $3, response_content_type = $4, response_body = $5,
+                completed_at = NOW()
+            WHERE client_id = $1 AND idempotency_key = $2 AND status = 'in_progress'
+            "#,
+            client_id,
+            key,
+            response.status as i32,
+            response.content_type.as_deref(),
+            &response.body
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to store idempotent response: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Free a claimed key whose request did not run to a replayable response
+    pub async fn release(&self, client_id: &str, key: &str) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM idempotency_keys WHERE client_id = $1 AND idempotency_key = $2 AND status = 'in_progress'",
+            client_id,
+            key
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to release idempotency key: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Delete expired keys; returns how many were deleted
+    pub async fn purge_expired(&self) -> Result<u64> {
+        let result = sqlx::query!("DELETE FROM idempotency_keys WHERE expires_at < NOW()")
+            .execute(&self.pool)
+            .await
+            .map_err(|e| StorageError::Query(format!("Failed to purge idempotency keys: {}", e)))?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+pub struct DormancyRepository {
+    pool: PgPool,
+}
+
+impl DormancyRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Active vaults holding unlocked funds whose last transaction (or creation,
+    /// or last dormancy resolution) is before `inactive_before`, and that are not
+    /// already flagged; longest inactive first
+    pub async fn find_candidates(&self, inactive_before: DateTime<Utc>, limit: i64) -> Result<Vec<DormancyCandidate>> {
+        let candidates = sqlx::query_as!(
+            DormancyCandidate,
+            r#"
+            SELECT id as "vault_id!", user_pubkey as "user_pubkey!", total_balance as "total_balance!",
+                   inactive_since as "inactive_since!"
+            FROM (
+                SELECT v.id, v.user_pubkey, v.total_balance,
+                       GREATEST(v.created_at,
+                                (SELECT MAX(t.created_at) FROM transaction_records t WHERE t.vault_id = v.id),
+                                d.updated_at) AS inactive_since
+                FROM vaults v
+                LEFT JOIN vault_dormancy d ON d.vault_id = v.id
+                WHERE v.is_active = true
+                  AND v.total_balance > 0
+                  AND v.locked_balance = 0
+                  AND (d.status IS NULL OR d.status IN ('cleared', 'reclaimed'))
+            ) activity
+            WHERE inactive_since < $1
+            ORDER BY inactive_since
+            LIMIT $2
+            "#,
+            inactive_before,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to find dormancy candidates: {}", e)))?;
+
+        Ok(candidates)
+    }
+
+    /// Flag a candidate and write the owner's notice to the outbox; `None` if it
+    /// was flagged concurrently
+    pub async fn notify(
+        &self,
+        candidate: &DormancyCandidate,
+        grace_ends_at: DateTime<Utc>,
+        notice_event: &str,
+        audit_event: &str,
+    ) -> Result<Option<VaultDormancy>> {
+        let dormancy = sqlx::query_as!(
+            VaultDormancy,
+            r#"
+            WITH flagged AS (
+                INSERT INTO vault_dormancy (vault_id, status, inactive_since, notified_at, grace_ends_at, updated_at)
+                VALUES ($1, 'notified', $2, NOW(), $3, NOW())
+                ON CONFLICT (vault_id) DO UPDATE
+                SET status = 'notified',
+                    inactive_since = EXCLUDED.inactive_since,
+                    notified_at = NOW(),
+                    grace_ends_at = EXCLUDED.grace_ends_at,
+                    swept_at = NULL,
+                    swept_amount = NULL,
+                    reclaimed_at = NULL,
+                    updated_at = NOW()
+                WHERE vault_dormancy.status IN ('cleared', 'reclaimed')
+                RETURNING vault_id, status, inactive_since, notified_at, grace_ends_at, swept_at, swept_amount,
+                          reclaimed_at, updated_at
+            ), head AS (
+                UPDATE event_outbox_head SET seq = seq + 1
+                WHERE id = 1 AND EXISTS (SELECT 1 FROM flagged)
+                RETURNING seq
+            ), outboxed AS (
+                INSERT INTO event_outbox (seq, event_type, vault_id, payload)
+                SELECT head.seq, $4, f.vault_id,
+                       jsonb_build_object('vault_id', f.vault_id, 'user_pubkey', $5::TEXT,
+                                          'total_balance', $6::BIGINT, 'inactive_since', f.inactive_since,
+                                          'grace_ends_at', f.grace_ends_at)
+                FROM flagged f, head
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, vault_id, details, created_at)
+                SELECT $7, vault_id,
+                       jsonb_build_object('status', status, 'inactive_since', inactive_since,
+                                          'grace_ends_at', grace_ends_at, 'total_balance', $6::BIGINT),
+                       NOW()
+                FROM flagged
+            )
+            SELECT vault_id as "vault_id!", $5::TEXT as "user_pubkey!", status as "status!",
+                   inactive_since as "inactive_since!", notified_at as "notified_at!", grace_ends_at as "grace_ends_at!",
+                   swept_at, swept_amount, reclaimed_at, updated_at as "updated_at!"
+            FROM flagged
+            "#,
+            candidate.vault_id,
+            candidate.inactive_since,
+            grace_ends_at,
+            notice_event,
+            candidate.user_pubkey,
+            candidate.total_balance,
+            audit_event
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to flag dormant vault: {}", e)))?;
+
+        Ok(dormancy)
+    }
+
+    /// Clear flagged vaults that have had a transaction since being flagged;
+    /// returns how many were cleared
+    pub async fn clear_resumed(&self, audit_event: &str) -> Result<u64> {
+        let cleared = sqlx::query!(
+            r#"
+            WITH cleared AS (
+                UPDATE vault_dormancy d
+                SET status = 'cleared', updated_at = NOW()
+                WHERE d.status = 'notified'
+                  AND EXISTS (
+                      SELECT 1 FROM transaction_records t
+                      WHERE t.vault_id = d.vault_id AND t.created_at > d.notified_at
+                  )
+                RETURNING d.vault_id, d.inactive_since, d.notified_at
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, vault_id, details, created_at)
+                SELECT $1, vault_id,
+                       jsonb_build_object('status', 'cleared', 'inactive_since', inactive_since,
+                                          'notified_at', notified_at),
+                       NOW()
+                FROM cleared
+            )
+            SELECT COUNT(*) as "count!" FROM cleared
+            "#,
+            audit_event
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to clear resumed vaults: {}", e)))?;
+
+        Ok(cleared.count as u64)
+    }
+
+    /// Record an observed on-chain sweep: the vault's balances go to zero and it
+    /// is deactivated, in the same statement as the outbox event and audit entry.
+    /// `None` if the vault was not awaiting a sweep.
+    pub async fn record_swept(
+        &self,
+        vault_id: Uuid,
+        amount: i64,
+        swept_at: DateTime<Utc>,
+        swept_event: &str,
+        audit_event: &str,
+    ) -> Result<Option<VaultDormancy>> {
+        let dormancy = sqlx::query_as!(
+            VaultDormancy,
+            r#"
+            WITH swept AS (
+                UPDATE vault_dormancy
+                SET status = 'swept', swept_at = $3, swept_amount = $2, updated_at = NOW()
+                WHERE vault_id = $1 AND status = 'notified'
+                RETURNING vault_id, status, inactive_since, notified_at, grace_ends_at, swept_at, swept_amount,
+                          reclaimed_at, updated_at
+            ), vault AS (
+                UPDATE vaults v
+                SET total_balance = 0, locked_balance = 0, available_balance = 0, is_active = false, updated_at = NOW()
+                FROM swept s
+                WHERE v.id = s.vault_id
+                RETURNING v.id, v.user_pubkey
+            ), head AS (
+                UPDATE event_outbox_head SET seq = seq + 1
+                WHERE id = 1 AND EXISTS (SELECT 1 FROM swept)
+                RETURNING seq
+            ), outboxed AS (
+                INSERT INTO event_outbox (seq, event_type, vault_id, payload)
+                SELECT head.seq, $4, s.vault_id,
+                       jsonb_build_object('vault_id', s.vault_id, 'user_pubkey', v.user_pubkey,
+                                          'amount', s.swept_amount, 'swept_at', s.swept_at)
+                FROM swept s JOIN vault v ON v.id = s.vault_id, head
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, vault_id, details, created_at)
+                SELECT $5, vault_id,
+                       jsonb_build_object('status', status, 'amount', swept_amount, 'swept_at', swept_at),
+                       NOW()
+                FROM swept
+            )
+            SELECT s.vault_id as "vault_id!", v.user_pubkey as "user_pubkey!", s.status as "status!",
+                   s.inactive_since as "inactive_since!", s.notified_at as "notified_at!",
+                   s.grace_ends_at as "grace_ends_at!", s.swept_at, s.swept_amount, s.reclaimed_at,
+                   s.updated_at as "updated_at!"
+            FROM swept s JOIN vault v ON v.id = s.vault_id
+            "#,
+            vault_id,
+            amount,
+            swept_at,
+            swept_event,
+            audit_event
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to record dormancy sweep: {}", e)))?;
+
+        Ok(dormancy)
+    }
+
+    /// Record an observed on-chain reclaim: the swept amount is credited back to
+    /// the vault's available balance and it is reactivated. `None` if the vault
+    /// was not swept.
+    pub async fn record_reclaimed(
+        &self,
+        vault_id: Uuid,
+        reclaimed_event: &str,
+        audit_event: &str,
+    ) -> Result<Option<VaultDormancy>> {
+        let dormancy = sqlx::query_as!(
+            VaultDormancy,
+            r#"
+            WITH reclaimed AS (
+                UPDATE vault_dormancy
+                SET status = 'reclaimed', reclaimed_at = NOW(), updated_at = NOW()
+                WHERE vault_id = $1 AND status = 'swept'
+                RETURNING vault_id, status, inactive_since, notified_at, grace_ends_at, swept_at, swept_amount,
+                          reclaimed_at, updated_at
+            ), vault AS (
+                UPDATE vaults v
+                SET total_balance = v.total_balance + COALESCE(r.swept_amount, 0),
+                    available_balance = v.available_balance + COALESCE(r.swept_amount, 0),
+                    is_active = true,
+                    updated_at = NOW()
+                FROM reclaimed r
+                WHERE v.id = r.vault_id
+                RETURNING v.id, v.user_pubkey
+            ), head AS (
+                UPDATE event_outbox_head SET seq = seq + 1
+                WHERE id = 1 AND EXISTS (SELECT 1 FROM reclaimed)
+                RETURNING seq
+            ), outboxed AS (
+                INSERT INTO event_outbox (seq, event_type, vault_id, payload)
+                SELECT head.seq, $2, r.vault_id,
+                       jsonb_build_object('vault_id', r.vault_id, 'user_pubkey', v.user_pubkey,
+                                          'amount', r.swept_amount, 'reclaimed_at', r.reclaimed_at)
+                FROM reclaimed r JOIN vault v ON v.id = r.vault_id, head
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, vault_id, details, created_at)
+                SELECT $3, vault_id,
+                       jsonb_build_object('status', status, 'amount', swept_amount, 'reclaimed_at', reclaimed_at),
+                       NOW()
+                FROM reclaimed
+            )
+            SELECT r.vault_id as "vault_id!", v.user_pubkey as "user_pubkey!", r.status as "status!",
+                   r.inactive_since as "inactive_since!", r.notified_at as "notified_at!",
+                   r.grace_ends_at as "grace_ends_at!", r.swept_at, r.swept_amount, r.reclaimed_at,
+                   r.updated_at as "updated_at!"
+            FROM reclaimed r JOIN vault v ON v.id = r.vault_id
+            "#,
+            vault_id,
+            reclaimed_event,
+            audit_event
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to record dormancy reclaim: {}", e)))?;
+
+        Ok(dormancy)
+    }
+
+    /// The dormancy state of a user's vault, whether or not the vault is active
+    pub async fn get_by_user(&self, user_pubkey: &str) -> Result<VaultDormancy> {
+        let dormancy = sqlx::query_as!(
+            VaultDormancy,
+            r#"
+            SELECT d.vault_id, v.user_pubkey, d.status, d.inactive_since, d.notified_at, d.grace_ends_at,
+                   d.swept_at, d.swept_amount, d.reclaimed_at, d.updated_at
+            FROM vault_dormancy d
+            JOIN vaults v ON v.id = d.vault_id
+            WHERE v.user_pubkey = $1
+            "#,
+            user_pubkey
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| not_found_or_database(e, format!("Dormancy record of {}", user_pubkey)))?;
+
+        Ok(dormancy)
+    }
+
+    /// Vaults in `status`, or every flagged or swept vault; longest inactive first
+    pub async fn list(&self, status: Option<&str>, limit: i64) -> Result<Vec<VaultDormancy>> {
+        let records = sqlx::query_as!(
+            VaultDormancy,
+            r#"
+            SELECT d.vault_id, v.user_pubkey, d.status, d.inactive_since, d.notified_at, d.grace_ends_at,
+                   d.swept_at, d.swept_amount, d.reclaimed_at, d.updated_at
+            FROM vault_dormancy d
+            JOIN vaults v ON v.id = d.vault_id
+            WHERE ($1::TEXT IS NULL AND d.status IN ('notified', 'swept')) OR d.status = $1
+            ORDER BY d.inactive_since
+            LIMIT $2
+            "#,
+            status,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to list dormant vaults: {}", e)))?;
+
+        Ok(records)
+    }
+}
+
+pub struct FeeRepository {
+    pool: PgPool,
+}
+
+impl FeeRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// A mint's fee tiers, smallest first
+    pub async fn get_tiers(&self, mint_pubkey: &str) -> Result<Vec<WithdrawalFeeTier>> {
+        let tiers = sqlx::query_as!(
+            WithdrawalFeeTier,
+            r#"
+            SELECT mint_pubkey, min_amount, flat_fee, bps
+            FROM withdrawal_fee_tiers
+            WHERE mint_pubkey = $1
+            ORDER BY min_amount
+            "#,
+            mint_pubkey
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get fee tiers: {}", e)))?;
+
+        Ok(tiers)
+    }
+
+    /// Replace a mint's fee tiers, auditing the new schedule
+    pub async fn replace_tiers(
+        &self,
+        mint_pubkey: &str,
+        tiers: &[WithdrawalFeeTier],
+        actor: &str,
+        audit_event: &str,
+    ) -> Result<Vec<WithdrawalFeeTier>> {
+        let mut db_tx = self.pool.begin().await
+            .map_err(|e| StorageError::Query(format!("Failed to begin fee tier update: {}", e)))?;
+
+        sqlx::query!("DELETE FROM withdrawal_fee_tiers WHERE mint_pubkey = $1", mint_pubkey)
+            .execute(&mut db_tx)
+            .await
+            .map_err(|e| StorageError::Query(format!("Failed to clear fee tiers: {}", e)))?;
+
+        let min_amounts: Vec<i64> = tiers.iter().map(|t| t.min_amount).collect();
+        let flat_fees: Vec<i64> = tiers.iter().map(|t| t.flat_fee).collect();
+        let bps: Vec<i32> = tiers.iter().map(|t| t.bps).collect();
+        let saved = sqlx::query_as!(
+            WithdrawalFeeTier,
+            r#"
+            WITH inserted AS (
+                INSERT INTO withdrawal_fee_tiers (mint_pubkey, min_amount, flat_fee, bps)
+                SELECT $1, t.min_amount, t.flat_fee, t.bps
+                FROM UNNEST($2::bigint[], $3::bigint[], $4::int[]) AS t (min_amount, flat_fee, bps)
+                RETURNING mint_pubkey, min_amount, flat_fee, bps
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, details, created_at)
+                SELECT $5,
+                       jsonb_build_object('mint_pubkey', $1::TEXT, 'actor', $6::TEXT,
+                                          'tiers', COALESCE((SELECT jsonb_agg(to_jsonb(i) ORDER BY i.min_amount) FROM inserted i), '[]'::jsonb)),
+                       NOW()
+            )
+            SELECT mint_pubkey as "mint_pubkey!", min_amount as "min_amount!", flat_fee as "flat_fee!", bps as "bps!"
+            FROM inserted
+            ORDER BY min_amount
+            "#,
+            mint_pubkey,
+            &min_amounts,
+            &flat_fees,
+            &bps,
+            audit_event,
+            actor
+        )
+        .fetch_all(&mut db_tx)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to save fee tiers: {}", e)))?;
+
+        db_tx.commit().await
+            .map_err(|e| StorageError::Query(format!("Failed to commit fee tiers: {}", e)))?;
+
+        Ok(saved)
+    }
+
+    /// Promotions of a mint that have not ended by `now`, soonest ending first
+    pub async fn get_current_promotions(&self, mint_pubkey: &str, now: DateTime<Utc>) -> Result<Vec<WithdrawalFeePromotion>> {
+        let promotions = sqlx::query_as!(
+            WithdrawalFeePromotion,
+            r#"
+            SELECT id, mint_pubkey, label, user_pubkey, flat_fee, bps, starts_at, ends_at, created_by, created_at
+            FROM withdrawal_fee_promotions
+            WHERE mint_pubkey = $1 AND ends_at > $2
+            ORDER BY ends_at
+            "#,
+            mint_pubkey,
+            now
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get fee promotions: {}", e)))?;
+
+        Ok(promotions)
+    }
+
+    pub async fn create_promotion(
+        &self,
+        mint_pubkey: &str,
+        label: &str,
+        user_pubkey: Option<&str>,
+        flat_fee: i64,
+        bps: i32,
+        starts_at: DateTime<Utc>,
+        ends_at: DateTime<Utc>,
+        actor: &str,
+        audit_event: &str,
+    ) -> Result<WithdrawalFeePromotion> {
+        let promotion = sqlx::query_as!(
+            WithdrawalFeePromotion,
+            r#"
+            WITH created AS (
+                INSERT INTO withdrawal_fee_promotions
+                    (mint_pubkey, label, user_pubkey, flat_fee, bps, starts_at, ends_at, created_by)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                RETURNING id, mint_pubkey, label, user_pubkey, flat_fee, bps, starts_at, ends_at, created_by, created_at
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, details, created_at)
+                SELECT $9, to_jsonb(created), NOW() FROM created
+            )
+            SELECT id as "id!", mint_pubkey as "mint_pubkey!", label as "label!", user_pubkey, flat_fee as "flat_fee!",
+                   bps as "bps!", starts_at as "starts_at!", ends_at as "ends_at!", created_by as "created_by!",
+                   created_at as "created_at!"
+            FROM created
+            "#,
+            mint_pubkey,
+            label,
+            user_pubkey,
+            flat_fee,
+            bps,
+            starts_at,
+            ends_at,
+            actor,
+            audit_event
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to create fee promotion: {}", e)))?;
+
+        Ok(promotion)
+    }
+
+    /// End a promotion now; ending one that is over already is a no-op
+    pub async fn end_promotion(
+        &self,
+        mint_pubkey: &str,
+        promotion_id: Uuid,
+        actor: &str,
+        audit_event: &str,
+    ) -> Result<WithdrawalFeePromotion> {
+        let promotion = sqlx::query_as!(
+            WithdrawalFeePromotion,
+            r#"
+            WITH ended AS (
+                UPDATE withdrawal_fee_promotions
+                SET ends_at = GREATEST(LEAST(ends_at, NOW()), starts_at + INTERVAL '1 microsecond')
+                WHERE id = $2 AND mint_pubkey = $1
+                RETURNING id, mint_pubkey, label, user_pubkey, flat_fee, bps, starts_at, ends_at, created_by, created_at
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, details, created_at)
+                SELECT $4, jsonb_build_object('promotion_id', id, 'mint_pubkey', mint_pubkey, 'ends_at', ends_at,
+                                              'actor', $3::TEXT),
+                       NOW()
+                FROM ended
+            )
+            SELECT id as "id!", mint_pubkey as "mint_pubkey!", label as "label!", user_pubkey, flat_fee as "flat_fee!",
+                   bps as "bps!", starts_at as "starts_at!", ends_at as "ends_at!", created_by as "created_by!",
+                   created_at as "created_at!"
+            FROM ended
+            "#,
+            mint_pubkey,
+            promotion_id,
+            actor,
+            audit_event
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| not_found_or_database(e, format!("Fee promotion {}", promotion_id)))?;
+
+        Ok(promotion)
+    }
+}
+
+pub struct MonitorJobRepository {
+    pool: PgPool,
+}
+
+impl MonitorJobRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn record(
+        &self,
+        job_type: &str,
+        started_at: DateTime<Utc>,
+        duration_ms: i64,
+        outcome: &str,
+        error: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO monitor_job_runs (job_type, started_at, duration_ms, outcome, error)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            job_type,
+            started_at,
+            duration_ms,
+            outcome,
+            error
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to record monitor job run: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Most recent run of each job type that has ever run
+    pub async fn latest_runs(&self) -> Result<Vec<MonitorJobRun>> {
+        let runs = sqlx::query_as!(
+            MonitorJobRun,
+            r#"
+            SELECT DISTINCT ON (job_type)
+                   id as "id!", job_type as "job_type!", started_at as "started_at!", duration_ms as "duration_ms!",
+                   outcome as "outcome!", error
+            FROM monitor_job_runs
+            ORDER BY job_type, started_at DESC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get latest monitor job runs: {}", e)))?;
+
+        Ok(runs)
+    }
+
+    /// Recent runs, newest first, optionally of one job type
+    pub async fn recent(&self, job_type: Option<&str>, limit: i64) -> Result<Vec<MonitorJobRun>> {
+        let runs = sqlx::query_as!(
+            MonitorJobRun,
+            r#"
+            SELECT id as "id!", job_type as "job_type!", started_at as "started_at!", duration_ms as "duration_ms!",
+                   outcome as "outcome!", error
+            FROM monitor_job_runs
+            WHERE $1::TEXT IS NULL OR job_type = $1
+            ORDER BY started_at DESC
+            LIMIT $2
+            "#,
+            job_type,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to list monitor job runs: {}", e)))?;
+
+        Ok(runs)
+    }
+
+    /// Audit a job that has not run within its allowance
+    pub async fn record_missed(
+        &self,
+        job_type: &str,
+        last_started_at: Option<DateTime<Utc>>,
+        interval_seconds: i64,
+        audit_event: &str,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO audit_logs (event_type, details, created_at)
+            VALUES ($1, jsonb_build_object('job_type', $2::TEXT, 'last_started_at', $3::TIMESTAMPTZ,
+                                           'interval_seconds', $4::BIGINT),
+                    NOW())
+            "#,
+            audit_event,
+            job_type,
+            last_started_at,
+            interval_seconds
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to record missed monitor job: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Delete runs started before `before`
+    pub async fn prune(&self, before: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query!("DELETE FROM monitor_job_runs WHERE started_at < $1", before)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| StorageError::Query(format!("Failed to prune monitor job runs: {}", e)))?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+pub struct SecurityEventRepository {
+    pool: PgPool,
+}
+
+impl SecurityEventRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn insert(
+        &self,
+        event_type: &str,
+        severity: &str,
+        source: &str,
+        actor: Option<&str>,
+        details: &serde_json::Value,
+    ) -> Result<SecurityEvent> {
+        let event = sqlx::query_as!(
+            SecurityEvent,
+            r#"
+            INSERT INTO security_events (event_type, severity, source, actor, details, created_at)
+            VALUES ($1, $2, $3, $4, $5, NOW())
+            RETURNING id, event_type, severity, source, actor, details, created_at
+            "#,
+            event_type,
+            severity,
+            source,
+            actor,
+            details
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to record security event: {}", e)))?;
+
+        Ok(event)
+    }
+
+    /// Events newest first, optionally of one type and/or source, since a time
+    pub async fn list(
+        &self,
+        event_type: Option<&str>,
+        source: Option<&str>,
+        since: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> Result<Vec<SecurityEvent>> {
+        let events = sqlx::query_as!(
+            SecurityEvent,
+            r#"
+            SELECT id, event_type, severity, source, actor, details, created_at
+            FROM security_events
+            WHERE ($1::TEXT IS NULL OR event_type = $1)
+              AND ($2::TEXT IS NULL OR source = $2)
+              AND ($3::TIMESTAMPTZ IS NULL OR created_at >= $3)
+            ORDER BY created_at DESC, id DESC
+            LIMIT $4
+            "#,
+            event_type,
+            source,
+            since,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to list security events: {}", e)))?;
+
+        Ok(events)
+    }
+
+    /// Events of the given types per source since `since`, most first
+    pub async fn count_by_source(&self, event_types: &[String], since: DateTime<Utc>) -> Result<Vec<SecurityEventCount>> {
+        let counts = sqlx::query_as!(
+            SecurityEventCount,
+            r#"
+            SELECT source as "source!", COUNT(*) as "events!"
+            FROM security_events
+            WHERE event_type = ANY($1) AND created_at >= $2
+            GROUP BY source
+            ORDER BY COUNT(*) DESC
+            "#,
+            event_types,
+            since
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to count security events: {}", e)))?;
+
+        Ok(counts)
+    }
+
+    /// Audit a burst of security events matching an alerting rule
+    pub async fn record_burst(
+        &self,
+        rule: &str,
+        source: Option<&str>,
+        events: i64,
+        window_seconds: i64,
+        audit_event: &str,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO audit_logs (event_type, details, created_at)
+            VALUES ($1, jsonb_build_object('rule', $2::TEXT, 'source', $3::TEXT, 'events', $4::BIGINT,
+                                           'window_seconds', $5::BIGINT),
+                    NOW())
+            "#,
+            audit_event,
+            rule,
+            source,
+            events,
+            window_seconds
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to record security event burst: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Delete events recorded before `before`
+    pub async fn prune(&self, before: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query!("DELETE FROM security_events WHERE created_at < $1", before)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| StorageError::Query(format!("Failed to prune security events: {}", e)))?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+pub struct SlaRepository {
+    pool: PgPool,
+}
+
+impl SlaRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Latency of the `operation_type` operations confirmed since `since`:
+    /// summed stage timers where recorded, creation to confirmation otherwise
+    pub async fn latency_counts(&self, operation_type: &str, target_ms: u64, since: DateTime<Utc>) -> Result<SlaLatencyCounts> {
+        let counts = sqlx::query_as!(
+            SlaLatencyCounts,
+            r#"
+            WITH latencies AS (
+                SELECT COALESCE(
+                           (SELECT SUM(stage.value::float8) FROM jsonb_each_text(stage_timings) AS stage),
+                           EXTRACT(EPOCH FROM (updated_at - created_at)) * 1000
+                       )::float8 AS latency_ms
+                FROM transaction_records
+                WHERE operation_type = $1 AND status = 'confirmed' AND updated_at >= $2
+            )
+            SELECT COUNT(*) as "operations!",
+                   COUNT(*) FILTER (WHERE latency_ms <= $3) as "within_target!",
+                   percentile_cont(0.95) WITHIN GROUP (ORDER BY latency_ms) as p95_ms,
+                   MAX(latency_ms) as max_ms
+            FROM latencies
+            "#,
+            operation_type,
+            since,
+            target_ms as f64
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get SLA latency counts: {}", e)))?;
+
+        Ok(counts)
+    }
+
+    /// Audit a class falling below its SLA objective
+    pub async fn record_breach(
+        &self,
+        operation_type: &str,
+        target_ms: i64,
+        objective_percent: f64,
+        attainment_percent: Option<f64>,
+        operations: i64,
+        window_minutes: i64,
+        audit_event: &str,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO audit_logs (event_type, details, created_at)
+            VALUES ($1, jsonb_build_object('operation_type', $2::TEXT, 'target_ms', $3::BIGINT,
+                                           'objective_percent', $4::FLOAT8, 'attainment_percent', $5::FLOAT8,
+                                           'operations', $6::BIGINT, 'window_minutes', $7::BIGINT),
+                    NOW())
+            "#,
+            audit_event,
+            operation_type,
+            target_ms,
+            objective_percent,
+            attainment_percent,
+            operations,
+            window_minutes
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to record SLA breach: {}", e)))?;
+
+        Ok(())
+    }
+}
+
+/// Repository for realized fees of backend-submitted transactions
+pub struct NetworkFeeRepository {
+    pool: PgPool,
+}
+
+impl NetworkFeeRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Signatures of confirmed transactions created since `since` whose fee is not yet recorded
+    pub async fn get_unrecorded_signatures(&self, since: DateTime<Utc>, limit: i64) -> Result<Vec<String>> {
+        let signatures = sqlx::query_scalar!(
+            r#"
+            SELECT DISTINCT signature as "signature!"
+            FROM transaction_records
+            WHERE submission_attempts IS NOT NULL AND fee_recorded_at IS NULL
+              AND signature IS NOT NULL AND status = 'confirmed' AND created_at >= $1
+            LIMIT $2
+            "#,
+            since,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get unrecorded fee signatures: {}", e)))?;
+
+        Ok(signatures)
+    }
+
+    /// Store a transaction's realized fee on every record it carries
+    pub async fn record_realized_fee(
+        &self,
+        signature: &str,
+        fee_lamports: i64,
+        priority_fee_lamports: i64,
+        compute_units_consumed: Option<i64>,
+    ) -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE transaction_records
+            SET fee_lamports = $2, priority_fee_lamports = $3, compute_units_consumed = $4, fee_recorded_at = NOW()
+            WHERE signature = $1 AND fee_recorded_at IS NULL
+            "#,
+            signature,
+            fee_lamports,
+            priority_fee_lamports,
+            compute_units_consumed
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to record realized fee: {}", e)))?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Realized fees per day and operation for transactions created in `[from, to)`;
+    /// a transaction with several records (a transfer) counts once
+    pub async fn get_daily_fees(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<NetworkFeeDayRow>> {
+        let rows = sqlx::query_as!(
+            NetworkFeeDayRow,
+            r#"
+            WITH transactions AS (
+                SELECT DISTINCT ON (signature)
+                       created_at, operation_type::TEXT AS operation_type, fee_lamports, priority_fee_lamports,
+                       submission_attempts, compute_units_consumed
+                FROM transaction_records
+                WHERE fee_recorded_at IS NOT NULL AND created_at >= $1 AND created_at < $2
+                ORDER BY signature, created_at
+            )
+            SELECT date_trunc('day', created_at) as "day!",
+                   operation_type as "operation_type!",
+                   COUNT(*) as "transactions!",
+                   SUM(fee_lamports)::BIGINT as "fee_lamports!",
+                   SUM(priority_fee_lamports)::BIGINT as "priority_fee_lamports!",
+                   SUM(GREATEST(submission_attempts - 1, 0))::BIGINT as "retries!",
+                   COALESCE(SUM(compute_units_consumed), 0)::BIGINT as "compute_units_consumed!"
+            FROM transactions
+            GROUP BY 1, 2
+            ORDER BY 1, 2
+            "#,
+            from,
+            to
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get daily fees: {}", e)))?;
+
+        Ok(rows)
+    }
+}
+
+/// Repository for incident runbook runs and their steps
+pub struct IncidentRepository {
+    pool: PgPool,
+}
+
+impl IncidentRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create a running run with its steps pending, in order
+    pub async fn create_run(
+        &self,
+        run_id: Uuid,
+        runbook: &str,
+        reason: &str,
+        vault_ids: Option<&[Uuid]>,
+        maintenance_window_id: Option<Uuid>,
+        follows: Option<Uuid>,
+        requested_by: &str,
+        steps: &[String],
+        audit_event: &str,
+    ) -> Result<IncidentRun> {
+        let run = sqlx::query_as!(
+            IncidentRun,
+            r#"
+            WITH run AS (
+                INSERT INTO incident_runs (id, runbook, reason, status, vault_ids, maintenance_window_id, follows,
+                                           requested_by, created_at, updated_at)
+                VALUES ($1, $2, $3, 'running', $4, $5, $6, $7, NOW(), NOW())
+                RETURNING id, runbook, reason, status, vault_ids, maintenance_window_id, follows, requested_by,
+                          created_at, updated_at, completed_at
+            ), steps AS (
+                INSERT INTO incident_steps (run_id, position, step, status)
+                SELECT run.id, s.position::INTEGER - 1, s.step, 'pending'
+                FROM run CROSS JOIN UNNEST($8::text[]) WITH ORDINALITY AS s (step, position)
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, details, metadata, created_at)
+                SELECT $9, jsonb_build_object('run_id', run.id, 'runbook', run.runbook, 'reason', run.reason,
+                                               'steps', $8::text[], 'follows', run.follows),
+                       jsonb_build_object('performed_by', run.requested_by), NOW()
+                FROM run
+            )
+            SELECT id as "id!", runbook as "runbook!", reason as "reason!", status as "status!", vault_ids,
+                   maintenance_window_id, follows, requested_by as "requested_by!", created_at as "created_at!",
+                   updated_at as "updated_at!", completed_at
+            FROM run
+            "#,
+            run_id,
+            runbook,
+            reason,
+            vault_ids,
+            maintenance_window_id,
+            follows,
+            requested_by,
+            steps,
+            audit_event
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to create incident run: {}", e)))?;
+
+        Ok(run)
+    }
+
+    pub async fn get_run(&self, run_id: Uuid) -> Result<IncidentRun> {
+        let run = sqlx::query_as!(
+            IncidentRun,
+            r#"
+            SELECT id, runbook, reason, status, vault_ids, maintenance_window_id, follows, requested_by,
+                   created_at, updated_at, completed_at
+            FROM incident_runs
+            WHERE id = $1
+            "#,
+            run_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| not_found_or_database(e, format!("Incident run {}", run_id)))?;
+
+        Ok(run)
+    }
+
+    /// Most recent runs first
+    pub async fn list_runs(&self, limit: i64) -> Result<Vec<IncidentRun>> {
+        let runs = sqlx::query_as!(
+            IncidentRun,
+            r#"
+            SELECT id, runbook, reason, status, vault_ids, maintenance_window_id, follows, requested_by,
+                   created_at, updated_at, completed_at
+            FROM incident_runs
+            ORDER BY created_at DESC
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to list incident runs: {}", e)))?;
+
+        Ok(runs)
+    }
+
+    /// Running runs, oldest first
+    pub async fn list_running_runs(&self) -> Result<Vec<IncidentRun>> {
+        let runs = sqlx::query_as!(
+            IncidentRun,
+            r#"
+            SELECT id, runbook, reason, status, vault_ids, maintenance_window_id, follows, requested_by,
+                   created_at, updated_at, completed_at
+            FROM incident_runs
+            WHERE status = 'running'
+            ORDER BY created_at ASC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to list running incident runs: {}", e)))?;
+
+        Ok(runs)
+    }
+
+    /// Steps of a run in order
+    pub async fn get_steps(&self, run_id: Uuid) -> Result<Vec<IncidentStepRecord>> {
+        let steps = sqlx::query_as!(
+            IncidentStepRecord,
+            r#"
+            SELECT run_id, position, step, status, attempts, output, error_message, started_at, completed_at
+            FROM incident_steps
+            WHERE run_id = $1
+            ORDER BY position
+            "#,
+            run_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to get incident steps: {}", e)))?;
+
+        Ok(steps)
+    }
+
+    /// Mark a pending step running and count the attempt; a step already
+    /// running keeps its start time, so waits are measured from the first attempt
+    pub async fn start_step(&self, run_id: Uuid, position: i32) -> Result<IncidentStepRecord> {
+        let step = sqlx::query_as!(
+            IncidentStepRecord,
+            r#"
+            UPDATE incident_steps
+            SET status = 'running', attempts = attempts + 1, started_at = COALESCE(started_at, NOW())
+            WHERE run_id = $1 AND position = $2
+            RETURNING run_id, position, step, status, attempts, output, error_message, started_at, completed_at
+            "#,
+            run_id,
+            position
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| not_found_or_database(e, format!("Step {} of incident run {}", position, run_id)))?;
+
+        Ok(step)
+    }
+
+    /// Store how far a step that is still running has got
+    pub async fn record_step_progress(&self, run_id: Uuid, position: i32, output: &serde_json::Value) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE incident_steps SET output = $3 WHERE run_id = $1 AND position = $2
+            "#,
+            run_id,
+            position,
+            output
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to record incident step progress: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub async fn complete_step(
+        &self,
+        run_id: Uuid,
+        position: i32,
+        output: &serde_json::Value,
+        audit_event: &str,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            WITH completed AS (
+                UPDATE incident_steps
+                SET status = 'completed', output = $3, error_message = NULL, completed_at = NOW()
+                WHERE run_id = $1 AND position = $2
+                RETURNING run_id, position, step, output
+            ), touched AS (
+                UPDATE incident_runs SET updated_at = NOW() WHERE id = $1
+            )
+            INSERT INTO audit_logs (event_type, details, created_at)
+            SELECT $4, jsonb_build_object('run_id', completed.run_id, 'position', completed.position,
+                                          'step', completed.step, 'output', completed.output), NOW()
+            FROM completed
+            "#,
+            run_id,
+            position,
+            output,
+            audit_event
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to complete incident step: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Fail a step and its run, which waits for an operator to resume or abort it
+    pub async fn fail_step(
+        &self,
+        run_id: Uuid,
+        position: i32,
+        error_message: &str,
+        audit_event: &str,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            WITH failed AS (
+                UPDATE incident_steps
+                SET status = 'failed', error_message = $3
+                WHERE run_id = $1 AND position = $2
+                RETURNING run_id, position, step
+            ), run AS (
+                UPDATE incident_runs SET status = 'failed', updated_at = NOW()
+                WHERE id = $1 AND status = 'running'
+            )
+            INSERT INTO audit_logs (event_type, details, created_at)
+            SELECT $4, jsonb_build_object('run_id', failed.run_id, 'position', failed.position,
+                                          'step', failed.step, 'error', $3::text), NOW()
+            FROM failed
+            "#,
+            run_id,
+            position,
+            error_message,
+            audit_event
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to fail incident step: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub async fn set_maintenance_window(&self, run_id: Uuid, window_id: Uuid) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE incident_runs SET maintenance_window_id = $2, updated_at = NOW() WHERE id = $1
+            "#,
+            run_id,
+            window_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to record incident maintenance window: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Move a run from one of `from` to `to`, auditing the change; failed steps
+    /// go back to pending when the run resumes. `None` if it was in another status.
+    pub async fn transition(
+        &self,
+        run_id: Uuid,
+        from: &[String],
+        to: &str,
+        performed_by: &str,
+        audit_event: &str,
+    ) -> Result<Option<IncidentRun>> {
+        let run = sqlx::query_as!(
+            IncidentRun,
+            r#"
+            WITH updated AS (
+                UPDATE incident_runs
+                SET status = $3, updated_at = NOW(),
+                    completed_at = CASE WHEN $3 IN ('completed', 'aborted') THEN NOW() ELSE completed_at END
+                WHERE id = $1 AND status = ANY($2)
+                RETURNING id, runbook, reason, status, vault_ids, maintenance_window_id, follows, requested_by,
+                          created_at, updated_at, completed_at
+            ), retried AS (
+                UPDATE incident_steps
+                SET status = 'pending', started_at = NULL
+                WHERE run_id IN (SELECT id FROM updated WHERE status = 'running') AND status = 'failed'
+            ), audit AS (
+                INSERT INTO audit_logs (event_type, details, metadata, created_at)
+                SELECT $5, jsonb_build_object('run_id', updated.id, 'runbook', updated.runbook, 'status', updated.status),
+                       jsonb_build_object('performed_by', $4::text), NOW()
+                FROM updated
+            )
+            SELECT id as "id!", runbook as "runbook!", reason as "reason!", status as "status!", vault_ids,
+                   maintenance_window_id, follows, requested_by as "requested_by!", created_at as "created_at!",
+                   updated_at as "updated_at!", completed_at
+            FROM updated
+            "#,
+            run_id,
+            from,
+            to,
+            performed_by,
+            audit_event
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to update incident run status: {}", e)))?;
+
+        Ok(run)
+    }
+}
+
+/// The state journal and the tables it covers
+pub struct JournalRepository {
+    pool: PgPool,
+}
+
+impl JournalRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn head_lsn(&self) -> Result<i64> {
+        let lsn = sqlx::query_scalar!("SELECT lsn FROM state_journal_head WHERE id = 1")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| StorageError::Query(format!("Failed to read journal head: {}", e)))?;
+
+        Ok(lsn)
+    }
+
+    /// Entries after `after_lsn`, oldest first
+    pub async fn entries_after(&self, after_lsn: i64, limit: i64) -> Result<Vec<JournalEntry>> {
+        let entries = sqlx::query_as!(
+            JournalEntry,
+            r#"
+            SELECT lsn, entity, entity_key, operation, state, recorded_at
+            FROM state_journal
+            WHERE lsn > $1
+            ORDER BY lsn
+            LIMIT $2
+            "#,
+            after_lsn,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::Query(format!("Failed to read journal entries: {}", e)))?;
+
+        Ok(entries)
+    }
+
+    /// Rows of `tables` as JSON, the journal head and, when `entries_after` is
+    /// given, the entries after it, all in one read-only repeatable-read transaction
+    pub async fn read_consistent(&self, tables: &[&str], entries_after: Option<i64>) -> Result<JournalRead> {
+        let mut db_tx = self.pool.begin().await
+            .map_err(|e| StorageError::Query(format!("Failed to begin journal read: {}", e)))?;
+        sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+            .execute(&mut db_tx)
+            .await
+            .map_err(|e| StorageError::Query(format!("Failed to set journal read isolation: {}", e)))?;
+        // Rows render timestamps as the journal trigger does
+        sqlx::query("SET LOCAL TimeZone = 'UTC'")
+            .execute(&mut db_tx)
+            .await
+            .map_err(|e| StorageError::Query(format!("Failed to set journal read time zone: {}", e)))?;
+
+        let lsn = sqlx::query_scalar!("SELECT lsn FROM state_journal_head WHERE id = 1")
+            .fetch_one(&mut db_tx)
+            .await
+            .map_err(|e| StorageError::Query(format!("Failed to read journal head: {}", e)))?;
+
+        // Table names come from the fixed list of journaled tables, never from input
+        let mut rows = Vec::new();
+        for table in tables {
+            let states = sqlx::query_scalar::<_, serde_json::Value>(&format!("SELECT to_jsonb(t) FROM {} t", table))
+                .fetch_all(&mut db_tx)
+                .await
+                .map_err(|e| StorageError::Query(format!("Failed to read {}: {}", table, e)))?;
+            rows.extend(states.into_iter().map(|state| (table.to_string(), state)));
+        }
+
+        let entries = match entries_after {
+            Some(after_lsn) => sqlx::query_as!(
+                JournalEntry,
+                r#"
+                SELECT lsn, entity, entity_key, operation, state, recorded_at
+                FROM state_journal
+                WHERE lsn > $1
+                ORDER BY lsn
+                "#,
+                after_lsn
+            )
+            .fetch_all(&mut db_tx)
+            .await
+            .map_err(|e| StorageError::Query(format!("Failed to read journal entries: {}", e)))?,
+            None => Vec::new(),
+        };
+
+        db_tx.commit().await
+            .map_err(|e| StorageError::Query(format!("Failed to end journal read: {}", e)))?;
+
+        Ok(JournalRead { lsn, rows, entries })
+    }
+
+    /// Replace the contents of `tables` with `rows`, inserting in the order of
+    /// `tables`, in one transaction. Refuses when `guard_table` has any rows.
+    pub async fn restore(&self, tables: &[&str], guard_table: &str, rows: &[(String, serde_json::Value)]) -> Result<u64> {
+        let mut db_tx = self.pool.begin().await
+            .map_err(|e| StorageError::Query(format!("Failed to begin restore: {}", e)))?;
+
+        let occupied = sqlx::query_scalar::<_, bool>(&format!("SELECT EXISTS (SELECT 1 FROM {})", guard_table))
+            .fetch_one(&mut db_tx)
+            .await
+            .map_err(|e| StorageError::Query(format!("Failed to inspect {}: {}", guard_table, e)))?;
+        if occupied {
+            return Err(DomainError::Validation(format!("Restore target already holds {}", guard_table)).into());
+        }
+
+        // Referencing tables come later in `tables`, so clear them first
+        for table in tables.iter().rev() {
+            sqlx::query(&format!("DELETE FROM {}", table))
+                .execute(&mut db_tx)
+                .await
+                .map_err(|e| StorageError::Query(format!("Failed to clear {}: {}", table, e)))?;
+        }
+
+        let mut restored = 0;
+        for table in tables {
+            let statement = format!("INSERT INTO {0} SELECT * FROM jsonb_populate_record(NULL::{0}, $1)", table);
+            for (_, state) in rows.iter().filter(|(entity, _)| entity == table) {
+                restored += sqlx::query(&statement)
+                    .bind(state)
+                    .execute(&mut db_tx)
+                    .await
+                    .map_err(|e| StorageError::Query(format!("Failed to restore {} row: {}", table, e)))?
+                    .rows_affected();
+            }
+        }
+
+        db_tx.commit().await
+            .map_err(|e| StorageError::Query(format!("Failed to commit restore: {}", e)))?;
+
+        Ok(restored)
+    }
+}
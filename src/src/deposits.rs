@@ -0,0 +1,139 @@
+//! Provisional deposits, credited only once confirmed on-chain.
+//!
+//! A deposit is signed and submitted by the user; the API records it as a
+//! `pending` transaction carrying the transaction's signature and touches no
+//! balance. Its amount is reported as `pending_credit` and cannot be withdrawn,
+//! locked or transferred.
+//!
+//! Each indexer pass settles pending deposits. Once the indexer has seen the
+//! signature and its slot is finalized, the transaction's logs are fetched at
+//! finalized commitment and the `DepositEvent`s emitted by the program itself
+//! (not by programs it invokes, nor by other programs in the transaction) are
+//! checked against the record: they must add up to its amount for its vault.
+//! With a Token-2022 mint charging a transfer fee the program credits only what
+//! reaches the vault token account, so the fees it reports withheld on the way
+//! in (`TransferFeeWithheld`) make up the difference. A match confirms the
+//! record at the credited amount and credits the vault in one statement; a
+//! failed transaction, a mismatch, or a signature never seen on-chain within
+//! `DEPOSIT_EXPIRY_HOURS` fails the record.
+
+use crate::models::PendingDeposit;
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use chrono::{DateTime, Duration, Utc};
+use collateral_vault_types::{DepositEvent, TransferFeeWithheld};
+use solana_sdk::pubkey::Pubkey;
+
+pub const DEPOSIT_CREDITED_EVENT: &str = "deposit_credited";
+
+/// Pending deposits whose signature has not been indexed after this long are failed
+pub const DEPOSIT_EXPIRY_HOURS: i64 = 24;
+
+/// Pending deposits settled per indexer pass
+pub const DEPOSIT_BATCH_SIZE: i64 = 200;
+
+/// What to do next with a pending deposit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DepositCheck {
+    /// Not indexed or not finalized yet
+    Wait,
+    /// Finalized in `slot`; its events decide whether it is credited
+    Verify { slot: i64 },
+    Reject(String),
+}
+
+/// Next step for `deposit` given the highest finalized slot
+pub fn check_pending(deposit: &PendingDeposit, finalized_slot: u64, now: DateTime<Utc>) -> DepositCheck {
+    match deposit.slot {
+        Some(slot) if slot < 0 || slot as u64 > finalized_slot => DepositCheck::Wait,
+        Some(_) if deposit.failed.unwrap_or(false) => {
+            DepositCheck::Reject("Deposit transaction failed on-chain".to_string())
+        }
+        Some(slot) => DepositCheck::Verify { slot },
+        None if now - deposit.created_at >= Duration::hours(DEPOSIT_EXPIRY_HOURS) => DepositCheck::Reject(format!(
+            "Deposit signature not seen on-chain within {} hours", DEPOSIT_EXPIRY_HOURS
+        )),
+        None => DepositCheck::Wait,
+    }
+}
+
+/// Payloads logged with `sol_log_data` by `program_id` itself, in order.
+///
+/// Invocation frames are tracked from the `invoke` / `success` / `failed`
+/// lines, so data logged by a program `program_id` invokes, or by another
+/// program in the same transaction, is not attributed to it.
+pub fn program_data(logs: &[String], program_id: &Pubkey) -> Vec<Vec<u8>> {
+    let program = program_id.to_string();
+    let mut frames: Vec<&str> = Vec::new();
+    let mut data = Vec::new();
+
+    for line in logs {
+        let Some(rest) = line.strip_prefix("Program ") else { continue };
+        if let Some(encoded) = rest.strip_prefix("data: ") {
+            if frames.last() == Some(&program.as_str()) {
+                // Events are logged as a single base64 field
+                if let Ok(bytes) = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, encoded.trim()) {
+                    data.push(bytes);
+                }
+            }
+            continue;
+        }
+        let mut words = rest.split_whitespace();
+        match (words.next(), words.next()) {
+            (Some(id), Some("invoke")) => frames.push(id),
+            (Some(_), Some("success")) | (Some(_), Some("failed:")) => {
+                frames.pop();
+            }
+            _ => {}
+        }
+    }
+    data
+}
+
+/// Events of type `E` emitted by `program_id` in a transaction's logs
+fn program_events<E: AnchorDeserialize + Discriminator>(logs: &[String], program_id: &Pubkey) -> Vec<E> {
+    program_data(logs, program_id)
+        .iter()
+        .filter(|data| data.starts_with(&E::DISCRIMINATOR))
+        .filter_map(|data| E::try_from_slice(&data[E::DISCRIMINATOR.len()..]).ok())
+        .collect()
+}
+
+/// `DepositEvent`s emitted by `program_id` in a transaction's logs
+pub fn deposit_events(logs: &[String], program_id: &Pubkey) -> Vec<DepositEvent> {
+    program_events(logs, program_id)
+}
+
+/// `TransferFeeWithheld` events emitted by `program_id` in a transaction's logs
+pub fn transfer_fee_events(logs: &[String], program_id: &Pubkey) -> Vec<TransferFeeWithheld> {
+    program_events(logs, program_id)
+}
+
+/// The amount credited to the deposit's vault, if `events` and the transfer
+/// fees withheld into its token account account for exactly the deposit's amount
+pub fn verify_events(
+    deposit: &PendingDeposit,
+    events: &[DepositEvent],
+    fees: &[TransferFeeWithheld],
+) -> Result<i64, String> {
+    let credited: u128 = events
+        .iter()
+        .filter(|event| event.vault.to_string() == deposit.vault_pubkey)
+        .map(|event| event.amount as u128)
+        .sum();
+    let withheld: u128 = fees
+        .iter()
+        .filter(|fee| fee.destination.to_string() == deposit.token_account_pubkey)
+        .map(|fee| fee.fee as u128)
+        .sum();
+
+    if credited == 0 {
+        return Err(format!("No deposit into vault {} in the transaction", deposit.vault_pubkey));
+    }
+    if credited + withheld != deposit.amount as u128 {
+        return Err(format!(
+            "Transaction deposited {} base units with {} withheld as transfer fees, not {}",
+            credited, withheld, deposit.amount
+        ));
+    }
+    i64::try_from(credited).map_err(|_| format!("Credited amount {} out of range", credited))
+}
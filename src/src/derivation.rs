@@ -0,0 +1,87 @@
+use crate::error::Result;
+use collateral_vault_types::Vault;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+pub use collateral_vault_types::seeds::{VAULT_SEED, TOKEN_SEED, CONFIG_SEED, DORMANT_SEED, DORMANT_TOKEN_SEED, TREASURY_SEED, INSURANCE_SEED, ASSET_CONFIG_SEED};
+
+/// Every address the program derives for one user's vault
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VaultDerivation {
+    pub program_id: String,
+    pub user_pubkey: String,
+    pub sub_account: u16,
+    pub vault_pda: String,
+    pub vault_bump: u8,
+    pub token_pda: String,
+    pub token_bump: u8,
+    pub config_pda: String,
+}
+
+/// Vault PDA for `user`, seeds `[b"vault", user]`; the user's sub-account 0
+pub fn derive_vault_pda(program_id: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    derive_sub_account_vault_pda(program_id, user, 0)
+}
+
+/// Vault PDA of one of `user`'s isolated vaults, seeds `[b"vault", user,
+/// sub_account_id as little-endian u16]`; sub-account 0 omits the id
+pub fn derive_sub_account_vault_pda(program_id: &Pubkey, user: &Pubkey, sub_account_id: u16) -> (Pubkey, u8) {
+    let sub_account_seed = Vault::sub_account_seed(sub_account_id);
+    Pubkey::find_program_address(&[VAULT_SEED, user.as_ref(), &sub_account_seed], program_id)
+}
+
+/// Token account PDA owned by `vault`, seeds `[b"token", vault]`
+pub fn derive_token_pda(program_id: &Pubkey, vault: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TOKEN_SEED, vault.as_ref()], program_id)
+}
+
+/// Global config PDA, seeds `[b"config"]`
+pub fn derive_config_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CONFIG_SEED], program_id)
+}
+
+/// Dormant-funds record of a swept `vault`, seeds `[b"dormant", vault]`
+pub fn derive_dormant_funds_pda(program_id: &Pubkey, vault: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[DORMANT_SEED, vault.as_ref()], program_id)
+}
+
+/// Token account holding a swept vault's funds, seeds `[b"dormant_token", dormant_funds]`
+pub fn derive_dormant_token_pda(program_id: &Pubkey, dormant_funds: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[DORMANT_TOKEN_SEED, dormant_funds.as_ref()], program_id)
+}
+
+/// Treasury token account collecting withdrawal fees of `mint`, seeds `[b"treasury", mint]`
+pub fn derive_treasury_token_pda(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TREASURY_SEED, mint.as_ref()], program_id)
+}
+
+/// Insurance fund token account of `mint`, seeds `[b"insurance", mint]`
+pub fn derive_insurance_fund_pda(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[INSURANCE_SEED, mint.as_ref()], program_id)
+}
+
+/// Collateral settings of `mint`, such as its haircut, seeds `[b"asset_config", mint]`
+pub fn derive_asset_config_pda(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ASSET_CONFIG_SEED, mint.as_ref()], program_id)
+}
+
+/// Derive vault, token and config addresses exactly as the program does.
+///
+/// `sub_account` picks one of the user's isolated vaults; 0 when omitted.
+pub fn derive_vault(program_id: &Pubkey, user: &Pubkey, sub_account: Option<u16>) -> Result<VaultDerivation> {
+    let sub_account = sub_account.unwrap_or(0);
+    let (vault_pda, vault_bump) = derive_sub_account_vault_pda(program_id, user, sub_account);
+    let (token_pda, token_bump) = derive_token_pda(program_id, &vault_pda);
+    let (config_pda, _) = derive_config_pda(program_id);
+
+    Ok(VaultDerivation {
+        program_id: program_id.to_string(),
+        user_pubkey: user.to_string(),
+        sub_account,
+        vault_pda: vault_pda.to_string(),
+        vault_bump,
+        token_pda: token_pda.to_string(),
+        token_bump,
+        config_pda: config_pda.to_string(),
+    })
+}
@@ -0,0 +1,314 @@
+//! Dormancy handling for vaults left untouched for a long time.
+//!
+//! A vault holding unlocked funds with no transactions for
+//! `DormancyPolicy::inactive_months` is flagged and its owner is sent a
+//! `vault_dormancy_notice` outbox event, which reaches them through whatever
+//! consumers (webhooks, pull feeds) are registered. Any transaction during the
+//! following `grace_days` clears the flag.
+//!
+//! Once the grace period is over, operations can prepare an unsigned
+//! `sweep_dormant_vault` transaction for the config admin to sign. It moves the
+//! vault's funds into a dormant-funds token account segregated per vault and
+//! deactivates the vault. The program refuses the sweep unless the vault has
+//! been untouched on-chain for `DormantFunds::MIN_DORMANCY_SECONDS`, whatever
+//! this policy says. The owner gets the funds back at any time by signing a
+//! `reclaim_dormant_funds` transaction, which reactivates the vault.
+//!
+//! Sweeps and reclaims are signed outside the backend, so each pass checks the
+//! dormant-funds account of flagged vaults and records what it finds, writing
+//! `vault_dormancy_swept` and `vault_dormancy_reclaimed` events.
+
+use crate::error::{Result, DomainError, ChainError, VaultError};
+use crate::models::{VaultDormancy, DormancyCandidate};
+use crate::transaction_builder::{self, TransactionBuilder, SigningHints};
+use crate::rpc::RpcMethodClass;
+use crate::database::{VaultRepository, DormancyRepository};
+use chrono::{DateTime, Duration, Months, TimeZone, Utc};
+use collateral_vault_types::DormantFunds;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{info, warn, error};
+
+pub const DORMANCY_NOTICE_EVENT: &str = "vault_dormancy_notice";
+pub const DORMANCY_SWEPT_EVENT: &str = "vault_dormancy_swept";
+pub const DORMANCY_RECLAIMED_EVENT: &str = "vault_dormancy_reclaimed";
+pub const DORMANCY_CLEARED_EVENT: &str = "vault_dormancy_cleared";
+
+/// Vaults flagged or checked per pass
+const PASS_LIMIT: i64 = 500;
+
+/// Shortest inactivity period accepted; anything shorter could flag vaults the
+/// program will not let us sweep
+pub const MIN_INACTIVE_MONTHS: u32 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DormancyPolicy {
+    pub inactive_months: u32,
+    pub grace_days: u32,
+}
+
+impl DormancyPolicy {
+    pub fn new(inactive_months: u32, grace_days: u32) -> Result<Self> {
+        if inactive_months < MIN_INACTIVE_MONTHS {
+            return Err(VaultError::Configuration(format!(
+                "Dormancy period must be at least {} months", MIN_INACTIVE_MONTHS
+            )));
+        }
+        Ok(Self { inactive_months, grace_days })
+    }
+
+    /// Vaults with no activity since before this are dormant at `now`
+    pub fn inactive_before(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now.checked_sub_months(Months::new(self.inactive_months)).unwrap_or(DateTime::<Utc>::MIN_UTC)
+    }
+
+    /// End of the grace period of a vault flagged at `notified_at`
+    pub fn grace_ends_at(&self, notified_at: DateTime<Utc>) -> DateTime<Utc> {
+        notified_at + Duration::days(self.grace_days as i64)
+    }
+}
+
+/// Whether a flagged vault's funds may be swept at `now`
+pub fn sweep_due(dormancy: &VaultDormancy, now: DateTime<Utc>) -> bool {
+    dormancy.status == "notified" && dormancy.grace_ends_at <= now
+}
+
+/// Counts from one dormancy pass
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DormancyPassSummary {
+    pub flagged: usize,
+    pub cleared: u64,
+    pub swept: usize,
+    pub reclaimed: usize,
+    pub errors: usize,
+}
+
+/// A sweep transaction awaiting the config admin's signature
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreparedDormancySweep {
+    pub dormancy: VaultDormancy,
+    /// Fee payer and required signer
+    pub admin: String,
+    /// Base64 bincode of the unsigned transaction
+    pub unsigned_transaction: String,
+    pub hints: SigningHints,
+}
+
+/// A reclaim transaction awaiting the vault owner's signature
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreparedDormancyReclaim {
+    pub dormancy: VaultDormancy,
+    /// Base units held in the dormant-funds account
+    pub amount: u64,
+    /// Base64 bincode of the unsigned transaction, paid and signed by the owner
+    pub unsigned_transaction: String,
+    pub hints: SigningHints,
+}
+
+/// Flags dormant vaults, tracks their sweeps and reclaims, and prepares both transactions
+pub struct DormancyService {
+    repo: DormancyRepository,
+    vault_repo: VaultRepository,
+    transaction_builder: Arc<TransactionBuilder>,
+    policy: DormancyPolicy,
+}
+
+impl DormancyService {
+    pub fn new(pool: sqlx::PgPool, transaction_builder: Arc<TransactionBuilder>, policy: DormancyPolicy) -> Self {
+        Self {
+            repo: DormancyRepository::new(pool.clone()),
+            vault_repo: VaultRepository::new(pool),
+            transaction_builder,
+            policy,
+        }
+    }
+
+    pub fn policy(&self) -> DormancyPolicy {
+        self.policy
+    }
+
+    /// Run a pass every `interval_seconds`
+    pub async fn run_worker(&self, interval_seconds: u64) {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_seconds));
+        loop {
+            interval.tick().await;
+            match self.run_pass(Utc::now()).await {
+                Ok(summary) if summary != DormancyPassSummary::default() => info!("Dormancy pass: {:?}", summary),
+                Ok(_) => {}
+                Err(e) => error!("Dormancy pass failed: {}", e),
+            }
+        }
+    }
+
+    /// Clear vaults that saw activity, flag newly dormant ones and record
+    /// sweeps and reclaims seen on-chain; failures are logged per vault
+    pub async fn run_pass(&self, now: DateTime<Utc>) -> Result<DormancyPassSummary> {
+        let mut summary = DormancyPassSummary {
+            cleared: self.repo.clear_resumed(DORMANCY_CLEARED_EVENT).await?,
+            ..Default::default()
+        };
+
+        let candidates = self.repo.find_candidates(self.policy.inactive_before(now), PASS_LIMIT).await?;
+        for candidate in &candidates {
+            match self.flag(candidate, now).await {
+                Ok(true) => summary.flagged += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    summary.errors += 1;
+                    error!("Failed to flag dormant vault {}: {}", candidate.vault_id, e);
+                }
+            }
+        }
+
+        // Only vaults past their grace period can have been swept
+        let flagged = self.repo.list(Some("notified"), PASS_LIMIT).await?;
+        for dormancy in flagged.iter().filter(|d| sweep_due(d, now)) {
+            match self.reconcile_sweep(dormancy).await {
+                Ok(true) => summary.swept += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    summary.errors += 1;
+                    error!("Failed to check sweep of vault {}: {}", dormancy.vault_id, e);
+                }
+            }
+        }
+
+        for dormancy in &self.repo.list(Some("swept"), PASS_LIMIT).await? {
+            match self.reconcile_reclaim(dormancy).await {
+                Ok(true) => summary.reclaimed += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    summary.errors += 1;
+                    error!("Failed to check reclaim of vault {}: {}", dormancy.vault_id, e);
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+
+    async fn flag(&self, candidate: &DormancyCandidate, now: DateTime<Utc>) -> Result<bool> {
+        let grace_ends_at = self.policy.grace_ends_at(now);
+        let flagged = self.repo.notify(candidate, grace_ends_at, DORMANCY_NOTICE_EVENT, DORMANCY_NOTICE_EVENT).await?;
+        if flagged.is_some() {
+            info!("Vault {} inactive since {}; owner notified, sweep possible after {}",
+                  candidate.vault_id, candidate.inactive_since, grace_ends_at);
+        }
+        Ok(flagged.is_some())
+    }
+
+    async fn reconcile_sweep(&self, dormancy: &VaultDormancy) -> Result<bool> {
+        let vault_pubkey = self.vault_pubkey(dormancy).await?;
+        let Some(funds) = self.transaction_builder.fetch_dormant_funds(vault_pubkey).await? else {
+            return Ok(false);
+        };
+
+        let swept_at = Utc.timestamp_opt(funds.swept_at, 0).single().unwrap_or_else(Utc::now);
+        let recorded = self.repo.record_swept(
+            dormancy.vault_id,
+            funds.amount as i64,
+            swept_at,
+            DORMANCY_SWEPT_EVENT,
+            DORMANCY_SWEPT_EVENT,
+        ).await?;
+        if recorded.is_some() {
+            info!("Vault {} swept: {} moved to its dormant-funds account", dormancy.vault_id, funds.amount);
+        }
+        Ok(recorded.is_some())
+    }
+
+    async fn reconcile_reclaim(&self, dormancy: &VaultDormancy) -> Result<bool> {
+        let vault_pubkey = self.vault_pubkey(dormancy).await?;
+        if self.transaction_builder.fetch_dormant_funds(vault_pubkey).await?.is_some() {
+            return Ok(false);
+        }
+
+        let recorded = self.repo.record_reclaimed(
+            dormancy.vault_id,
+            DORMANCY_RECLAIMED_EVENT,
+            DORMANCY_RECLAIMED_EVENT,
+        ).await?;
+        if recorded.is_some() {
+            info!("Vault {} reclaimed its dormant funds and is active again", dormancy.vault_id);
+        }
+        Ok(recorded.is_some())
+    }
+
+    /// Flagged and swept vaults, or those in `status`
+    pub async fn list(&self, status: Option<&str>, limit: i64) -> Result<Vec<VaultDormancy>> {
+        self.repo.list(status, limit).await
+    }
+
+    /// Build a fresh unsigned sweep transaction for a vault past its grace period
+    pub async fn prepare_sweep(&self, user_pubkey: &str) -> Result<PreparedDormancySweep> {
+        let dormancy = self.repo.get_by_user(user_pubkey).await?;
+        let now = Utc::now();
+        if !sweep_due(&dormancy, now) {
+            return Err(DomainError::Validation(format!(
+                "Vault {} is not due for a sweep (status {}, grace ends {})",
+                dormancy.vault_id, dormancy.status, dormancy.grace_ends_at
+            )).into());
+        }
+
+        let vault_pubkey = self.vault_pubkey(&dormancy).await?;
+        let account = self.transaction_builder.fetch_vault_account(vault_pubkey, RpcMethodClass::Read).await?;
+        if !DormantFunds::is_dormant(&account, now.timestamp()) {
+            return Err(DomainError::Validation(format!(
+                "Vault {} was updated on-chain within the last {} days",
+                dormancy.vault_id, DormantFunds::MIN_DORMANCY_SECONDS / 86_400
+            )).into());
+        }
+        if account.locked_balance > 0 {
+            warn!("Vault {} was flagged dormant but has {} locked on-chain", dormancy.vault_id, account.locked_balance);
+            return Err(DomainError::Validation(format!("Vault {} has locked collateral", dormancy.vault_id)).into());
+        }
+
+        let admin = self.transaction_builder.fetch_program_config(RpcMethodClass::Read).await?.admin;
+        let built = self.transaction_builder.build_sweep_dormant_vault_tx(vault_pubkey, admin).await?;
+        let hints = self.transaction_builder.signing_hints(&built.transaction, built.last_valid_block_height).await?;
+
+        info!("Prepared dormancy sweep of vault {}", dormancy.vault_id);
+
+        Ok(PreparedDormancySweep {
+            dormancy,
+            admin: admin.to_string(),
+            unsigned_transaction: transaction_builder::encode_transaction(&built.transaction)?,
+            hints,
+        })
+    }
+
+    /// Build a fresh unsigned reclaim transaction for the owner of a swept vault
+    pub async fn prepare_reclaim(&self, user_pubkey: &str) -> Result<PreparedDormancyReclaim> {
+        let dormancy = self.repo.get_by_user(user_pubkey).await?;
+        // A sweep may have landed since the last pass, so a flagged vault is checked on-chain too
+        if dormancy.status != "notified" && dormancy.status != "swept" {
+            return Err(DomainError::Validation(format!("Vault {} has no dormant funds", dormancy.vault_id)).into());
+        }
+
+        let user = Pubkey::from_str(user_pubkey)
+            .map_err(|_| DomainError::Validation(format!("Invalid user pubkey: {}", user_pubkey)))?;
+        let vault_pubkey = self.vault_pubkey(&dormancy).await?;
+        let funds = self.transaction_builder.fetch_dormant_funds(vault_pubkey).await?
+            .ok_or_else(|| DomainError::Validation(format!("Vault {} has no dormant funds", dormancy.vault_id)))?;
+
+        let built = self.transaction_builder.build_reclaim_dormant_funds_tx(user).await?;
+        let hints = self.transaction_builder.signing_hints(&built.transaction, built.last_valid_block_height).await?;
+
+        info!("Prepared reclaim of {} dormant funds for vault {}", funds.amount, dormancy.vault_id);
+
+        Ok(PreparedDormancyReclaim {
+            dormancy,
+            amount: funds.amount,
+            unsigned_transaction: transaction_builder::encode_transaction(&built.transaction)?,
+            hints,
+        })
+    }
+
+    async fn vault_pubkey(&self, dormancy: &VaultDormancy) -> Result<Pubkey> {
+        let vault = self.vault_repo.get_vault_by_id(dormancy.vault_id).await?;
+        Pubkey::from_str(&vault.vault_pubkey)
+            .map_err(|_| ChainError::InvalidAccountData(format!("Invalid vault pubkey: {}", vault.vault_pubkey)).into())
+    }
+}
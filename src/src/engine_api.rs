@@ -0,0 +1,415 @@
+//! Collateral operations for the co-located matching engine without HTTP/JSON.
+//!
+//! `EngineApi` is the in-process facade: an engine embedded in this process
+//! calls it directly. An engine in its own process connects to the unix socket
+//! served by `serve_unix`, which speaks a fixed binary protocol. Every integer
+//! is little-endian and every frame is preceded by its `u32` length:
+//!
+//! ```text
+//! request  (49 bytes): op u8 | request_id u64 | vault_id [16] | amount u64 | operation_id [16]
+//! response (35 + n):   request_id u64 | status u8 | total u64 | locked u64 | available u64
+//!                      | message_len u16 | message [n]
+//! ```
+//!
+//! Requests on one connection may be pipelined and are answered out of order;
+//! the engine matches responses by `request_id`. Balance reads and checks are
+//! served from the balance cache and answered before the connection reads the
+//! next request. Locks and unlocks submit a transaction, so each runs in its
+//! own task; their message is the transaction signature, or the error text for
+//! any other status. `operation_id` makes a retried lock or unlock a duplicate
+//! rather than a second operation while the first is in flight.
+//!
+//! The socket has no authentication beyond its file mode (0600): only
+//! processes running as the backend's user can connect.
+
+use crate::balance_tracker::BalanceTracker;
+use crate::cpi_manager::CPIManager;
+use crate::error::{Result, ChainError, DomainError, StorageError, VaultError};
+use crate::maintenance::MaintenanceService;
+use chrono::Utc;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt, BufWriter};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::mpsc;
+use tracing::{info, warn, error};
+use uuid::Uuid;
+
+pub const REQUEST_LEN: usize = 49;
+
+/// Responses carry at most this much message text
+pub const MAX_MESSAGE_LEN: usize = 1024;
+
+/// Responses queued per connection before reading waits for the writer
+const RESPONSE_QUEUE_DEPTH: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum EngineOp {
+    Balance = 1,
+    /// Whether `amount` is available to lock
+    Check = 2,
+    Lock = 3,
+    Unlock = 4,
+}
+
+impl EngineOp {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(EngineOp::Balance),
+            2 => Some(EngineOp::Check),
+            3 => Some(EngineOp::Lock),
+            4 => Some(EngineOp::Unlock),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum EngineStatus {
+    Ok = 0,
+    /// Not enough available (check, lock) or locked (unlock) balance
+    Insufficient = 1,
+    NotFound = 2,
+    /// Duplicate operation or conflicting vault state
+    Conflict = 3,
+    /// Malformed request or failed validation
+    Invalid = 4,
+    /// Maintenance, RPC or rate limits; retry later
+    Unavailable = 5,
+    Error = 6,
+}
+
+impl EngineStatus {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(EngineStatus::Ok),
+            1 => Some(EngineStatus::Insufficient),
+            2 => Some(EngineStatus::NotFound),
+            3 => Some(EngineStatus::Conflict),
+            4 => Some(EngineStatus::Invalid),
+            5 => Some(EngineStatus::Unavailable),
+            6 => Some(EngineStatus::Error),
+            _ => None,
+        }
+    }
+
+    pub fn from_error(err: &VaultError) -> Self {
+        match err {
+            VaultError::Domain(domain) => match domain {
+                DomainError::NotFound(_) => EngineStatus::NotFound,
+                DomainError::InsufficientBalance { .. } | DomainError::InsufficientLockedBalance { .. } => EngineStatus::Insufficient,
+                DomainError::VaultAlreadyExists(_)
+                | DomainError::InvalidVaultState(_)
+                | DomainError::ConcurrentConflict(_)
+                | DomainError::ScreeningHeld(_)
+                | DomainError::UnderMargined(_) => EngineStatus::Conflict,
+                DomainError::Validation(_)
+                | DomainError::Unauthorized(_)
+                | DomainError::ScreeningBlocked(_)
+                | DomainError::VerificationRequired(_) => EngineStatus::Invalid,
+                DomainError::RateLimitExceeded(_) | DomainError::QuotaExceeded(_) => EngineStatus::Unavailable,
+                DomainError::InvariantViolation(_) => EngineStatus::Error,
+            },
+            VaultError::Storage(storage) => match storage {
+                StorageError::NotFound(_) => EngineStatus::NotFound,
+                StorageError::Database(_) | StorageError::Query(_) => EngineStatus::Error,
+            },
+            VaultError::Chain(chain) => match chain {
+                ChainError::Client(_) | ChainError::Network(_) | ChainError::Timeout(_) | ChainError::RateLimited(_) => EngineStatus::Unavailable,
+                ChainError::Signer(_) | ChainError::TransactionFailed(_) | ChainError::InvalidAccountData(_) => EngineStatus::Error,
+            },
+            VaultError::Configuration(_) | VaultError::Internal(_) => EngineStatus::Error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EngineRequest {
+    pub request_id: u64,
+    pub op: EngineOp,
+    pub vault_id: Uuid,
+    /// Ignored by balance reads
+    pub amount: u64,
+    /// Used by locks and unlocks
+    pub operation_id: Uuid,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EngineResponse {
+    pub request_id: u64,
+    pub status: EngineStatus,
+    pub total_balance: u64,
+    pub locked_balance: u64,
+    pub available_balance: u64,
+    pub message: String,
+}
+
+impl EngineResponse {
+    fn new(request_id: u64, status: EngineStatus, balances: (u64, u64, u64), message: String) -> Self {
+        Self {
+            request_id,
+            status,
+            total_balance: balances.0,
+            locked_balance: balances.1,
+            available_balance: balances.2,
+            message,
+        }
+    }
+
+    fn failed(request_id: u64, err: &VaultError) -> Self {
+        Self::new(request_id, EngineStatus::from_error(err), (0, 0, 0), err.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameError {
+    Length(usize),
+    UnknownOp(u8),
+    UnknownStatus(u8),
+}
+
+impl std::fmt::Display for FrameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FrameError::Length(len) => write!(f, "unexpected frame length {}", len),
+            FrameError::UnknownOp(op) => write!(f, "unknown op {}", op),
+            FrameError::UnknownStatus(status) => write!(f, "unknown status {}", status),
+        }
+    }
+}
+
+fn read_u64(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().expect("8-byte slice"))
+}
+
+fn read_uuid(bytes: &[u8], at: usize) -> Uuid {
+    Uuid::from_bytes(bytes[at..at + 16].try_into().expect("16-byte slice"))
+}
+
+/// Request body, without the length prefix
+pub fn encode_request(request: &EngineRequest) -> Vec<u8> {
+    let mut body = Vec::with_capacity(REQUEST_LEN);
+    body.push(request.op as u8);
+    body.extend_from_slice(&request.request_id.to_le_bytes());
+    body.extend_from_slice(request.vault_id.as_bytes());
+    body.extend_from_slice(&request.amount.to_le_bytes());
+    body.extend_from_slice(request.operation_id.as_bytes());
+    body
+}
+
+pub fn decode_request(body: &[u8]) -> std::result::Result<EngineRequest, FrameError> {
+    if body.len() != REQUEST_LEN {
+        return Err(FrameError::Length(body.len()));
+    }
+    Ok(EngineRequest {
+        op: EngineOp::from_u8(body[0]).ok_or(FrameError::UnknownOp(body[0]))?,
+        request_id: read_u64(body, 1),
+        vault_id: read_uuid(body, 9),
+        amount: read_u64(body, 25),
+        operation_id: read_uuid(body, 33),
+    })
+}
+
+/// Response body, without the length prefix; the message is cut at `MAX_MESSAGE_LEN` bytes
+pub fn encode_response(response: &EngineResponse) -> Vec<u8> {
+    let mut end = response.message.len().min(MAX_MESSAGE_LEN);
+    while !response.message.is_char_boundary(end) {
+        end -= 1;
+    }
+    let message = &response.message.as_bytes()[..end];
+
+    let mut body = Vec::with_capacity(35 + message.len());
+    body.extend_from_slice(&response.request_id.to_le_bytes());
+    body.push(response.status as u8);
+    body.extend_from_slice(&response.total_balance.to_le_bytes());
+    body.extend_from_slice(&response.locked_balance.to_le_bytes());
+    body.extend_from_slice(&response.available_balance.to_le_bytes());
+    body.extend_from_slice(&(message.len() as u16).to_le_bytes());
+    body.extend_from_slice(message);
+    body
+}
+
+pub fn decode_response(body: &[u8]) -> std::result::Result<EngineResponse, FrameError> {
+    if body.len() < 35 {
+        return Err(FrameError::Length(body.len()));
+    }
+    let message_len = u16::from_le_bytes([body[33], body[34]]) as usize;
+    if body.len() != 35 + message_len {
+        return Err(FrameError::Length(body.len()));
+    }
+    Ok(EngineResponse {
+        request_id: read_u64(body, 0),
+        status: EngineStatus::from_u8(body[8]).ok_or(FrameError::UnknownStatus(body[8]))?,
+        total_balance: read_u64(body, 9),
+        locked_balance: read_u64(body, 17),
+        available_balance: read_u64(body, 25),
+        message: String::from_utf8_lossy(&body[35..]).into_owned(),
+    })
+}
+
+/// Lock, unlock and balance checks for the matching engine
+pub struct EngineApi {
+    cpi_manager: Arc<CPIManager>,
+    balance_tracker: Arc<BalanceTracker>,
+    maintenance: Arc<MaintenanceService>,
+}
+
+impl EngineApi {
+    pub fn new(cpi_manager: Arc<CPIManager>, balance_tracker: Arc<BalanceTracker>, maintenance: Arc<MaintenanceService>) -> Self {
+        Self { cpi_manager, balance_tracker, maintenance }
+    }
+
+    /// (total, locked, available) from the balance cache, loading it on a miss
+    pub async fn balance(&self, vault_id: Uuid) -> Result<(u64, u64, u64)> {
+        self.balance_tracker.get_balance(vault_id).await
+    }
+
+    /// Whether `amount` could be locked now, judged like the lock itself: against available balance
+    pub async fn check_available(&self, vault_id: Uuid, amount: u64) -> Result<bool> {
+        let (_, _, available) = self.balance(vault_id).await?;
+        Ok(available >= amount)
+    }
+
+    pub async fn lock(&self, vault_id: Uuid, amount: u64, operation_id: Uuid) -> Result<String> {
+        self.ensure_writable()?;
+        self.cpi_manager.lock_collateral(vault_id, amount, operation_id).await
+    }
+
+    pub async fn unlock(&self, vault_id: Uuid, amount: u64, operation_id: Uuid) -> Result<String> {
+        self.ensure_writable()?;
+        self.cpi_manager.unlock_collateral(vault_id, amount, operation_id).await
+    }
+
+    /// Writes are refused during read-only maintenance, as on the HTTP API
+    fn ensure_writable(&self) -> Result<()> {
+        let status = self.maintenance.status(Utc::now());
+        if status.read_only {
+            let reason = status.active.map_or_else(String::new, |w| w.reason);
+            return Err(DomainError::RateLimitExceeded(format!("Maintenance in progress: {}", reason)).into());
+        }
+        Ok(())
+    }
+
+    /// Serve one decoded request
+    pub async fn handle(&self, request: &EngineRequest) -> EngineResponse {
+        let id = request.request_id;
+        let result = match request.op {
+            EngineOp::Balance => self.balance(request.vault_id).await
+                .map(|_| (EngineStatus::Ok, String::new())),
+            EngineOp::Check => self.check_available(request.vault_id, request.amount).await
+                .map(|ok| (if ok { EngineStatus::Ok } else { EngineStatus::Insufficient }, String::new())),
+            EngineOp::Lock => self.lock(request.vault_id, request.amount, request.operation_id).await
+                .map(|signature| (EngineStatus::Ok, signature)),
+            EngineOp::Unlock => self.unlock(request.vault_id, request.amount, request.operation_id).await
+                .map(|signature| (EngineStatus::Ok, signature)),
+        };
+
+        match result {
+            Ok((status, message)) => {
+                // Balances after the operation; a lock or unlock has already updated the cache
+                let balances = self.balance(request.vault_id).await.unwrap_or_default();
+                EngineResponse::new(id, status, balances, message)
+            }
+            Err(e) => EngineResponse::failed(id, &e),
+        }
+    }
+}
+
+fn io_error(context: &str, err: std::io::Error) -> VaultError {
+    VaultError::Internal(format!("{}: {}", context, err))
+}
+
+/// Accept matching engine connections on a unix socket at `path`, replacing any stale socket file
+pub async fn serve_unix(api: Arc<EngineApi>, path: &Path) -> Result<()> {
+    if path.exists() {
+        std::fs::remove_file(path).map_err(|e| io_error("Failed to remove stale engine socket", e))?;
+    }
+    let listener = UnixListener::bind(path).map_err(|e| io_error("Failed to bind engine socket", e))?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+        .map_err(|e| io_error("Failed to restrict engine socket", e))?;
+    info!("Matching engine API listening on {}", path.display());
+
+    loop {
+        let (stream, _) = listener.accept().await.map_err(|e| io_error("Engine socket accept failed", e))?;
+        let api = api.clone();
+        tokio::spawn(async move {
+            if let Err(e) = serve_connection(api, stream).await {
+                warn!("Engine connection closed: {}", e);
+            }
+        });
+    }
+}
+
+async fn serve_connection(api: Arc<EngineApi>, stream: UnixStream) -> Result<()> {
+    let (mut reader, writer) = stream.into_split();
+    let (responses, mut queued) = mpsc::channel::<EngineResponse>(RESPONSE_QUEUE_DEPTH);
+
+    let writer_task = tokio::spawn(async move {
+        let mut writer = BufWriter::new(writer);
+        while let Some(response) = queued.recv().await {
+            let body = encode_response(&response);
+            writer.write_all(&(body.len() as u32).to_le_bytes()).await?;
+            writer.write_all(&body).await?;
+            // Flush once the queue is drained, so pipelined responses share writes
+            if queued.is_empty() {
+                writer.flush().await?;
+            }
+        }
+        writer.flush().await
+    });
+
+    let mut body = [0u8; REQUEST_LEN];
+    loop {
+        let len = match reader.read_u32_le().await {
+            Ok(len) => len as usize,
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(io_error("Engine socket read failed", e)),
+        };
+        if len != REQUEST_LEN {
+            // The stream cannot be resynchronised after a bad length
+            let _ = responses.send(EngineResponse::new(0, EngineStatus::Invalid, (0, 0, 0),
+                                                       FrameError::Length(len).to_string())).await;
+            break;
+        }
+        reader.read_exact(&mut body).await.map_err(|e| io_error("Engine socket read failed", e))?;
+
+        let request = match decode_request(&body) {
+            Ok(request) => request,
+            Err(e) => {
+                let request_id = read_u64(&body, 1);
+                if responses.send(EngineResponse::new(request_id, EngineStatus::Invalid, (0, 0, 0), e.to_string())).await.is_err() {
+                    break;
+                }
+                continue;
+            }
+        };
+
+        match request.op {
+            EngineOp::Balance | EngineOp::Check => {
+                if responses.send(api.handle(&request).await).await.is_err() {
+                    break;
+                }
+            }
+            EngineOp::Lock | EngineOp::Unlock => {
+                let api = api.clone();
+                let responses = responses.clone();
+                tokio::spawn(async move {
+                    let _ = responses.send(api.handle(&request).await).await;
+                });
+            }
+        }
+    }
+
+    drop(responses);
+    match writer_task.await {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(e)) => Err(io_error("Engine socket write failed", e)),
+        Err(e) => {
+            error!("Engine socket writer panicked: {}", e);
+            Err(VaultError::Internal("Engine socket writer panicked".to_string()))
+        }
+    }
+}
@@ -3,55 +3,147 @@ use solana_client::client_error::ClientError;
 use solana_sdk::signature::SignerError;
 use sqlx::Error as SqlxError;
 
+/// Business-rule failures, independent of how a request arrived or where state lives
 #[derive(Error, Debug)]
-pub enum VaultError {
-    #[error("Database error: {0}")]
-    DatabaseError(#[from] SqlxError),
-    
-    #[error("Solana client error: {0}")]
-    SolanaClientError(#[from] ClientError),
-    
-    #[error("Signer error: {0}")]
-    SignerError(#[from] SignerError),
-    
-    #[error("Transaction failed: {0}")]
-    TransactionFailed(String),
-    
-    #[error("Vault not found: {0}")]
-    VaultNotFound(String),
-    
-    #[error("Insufficient balance: available={available}, required={required}")]
-    InsufficientBalance { available: u64, required: u64 },
-    
+pub enum DomainError {
+    #[error("Not found: {0}")]
+    NotFound(String),
+
     #[error("Vault already exists: {0}")]
     VaultAlreadyExists(String),
-    
+
+    #[error("Insufficient balance: available={available}, required={required}")]
+    InsufficientBalance { available: u64, required: u64 },
+
+    #[error("Insufficient locked balance: locked={locked}, required={required}")]
+    InsufficientLockedBalance { locked: u64, required: u64 },
+
     #[error("Invalid vault state: {0}")]
     InvalidVaultState(String),
-    
+
+    #[error("Balance invariant violated: {0}")]
+    InvariantViolation(String),
+
+    #[error("Validation error: {0}")]
+    Validation(String),
+
     #[error("Unauthorized operation: {0}")]
     Unauthorized(String),
-    
+
     #[error("Rate limit exceeded: {0}")]
     RateLimitExceeded(String),
-    
+
+    #[error("Pending quota exceeded: {0}")]
+    QuotaExceeded(String),
+
     #[error("Concurrent operation conflict: {0}")]
     ConcurrentConflict(String),
-    
+
+    #[error("Blocked by screening: {0}")]
+    ScreeningBlocked(String),
+
+    #[error("Held for screening review: {0}")]
+    ScreeningHeld(String),
+
+    #[error("Open positions would be under-margined: {0}")]
+    UnderMargined(String),
+
+    #[error("Identity verification required: {0}")]
+    VerificationRequired(String),
+}
+
+/// Persistence failures raised by the repositories
+#[derive(Error, Debug)]
+pub enum StorageError {
+    #[error("Database error: {0}")]
+    Database(#[from] SqlxError),
+
+    #[error("Query failed: {0}")]
+    Query(String),
+
+    #[error("Record not found: {0}")]
+    NotFound(String),
+}
+
+/// Failures talking to the Solana cluster
+#[derive(Error, Debug)]
+pub enum ChainError {
+    #[error("Solana client error: {0}")]
+    Client(#[from] ClientError),
+
+    #[error("Signer error: {0}")]
+    Signer(#[from] SignerError),
+
+    #[error("Transaction failed: {0}")]
+    TransactionFailed(String),
+
+    #[error("Invalid account data: {0}")]
+    InvalidAccountData(String),
+
     #[error("Network error: {0}")]
-    NetworkError(String),
-    
-    #[error("Configuration error: {0}")]
-    ConfigurationError(String),
-    
-    #[error("Validation error: {0}")]
-    ValidationError(String),
-    
+    Network(String),
+
     #[error("Timeout error: {0}")]
-    TimeoutError(String),
-    
+    Timeout(String),
+
+    #[error("RPC rate limited: {0}")]
+    RateLimited(String),
+}
+
+/// Service-level error returned by managers and background jobs.
+///
+/// HTTP status codes are deliberately not decided here; see `api::ApiError`.
+#[derive(Error, Debug)]
+pub enum VaultError {
+    #[error(transparent)]
+    Domain(#[from] DomainError),
+
+    #[error(transparent)]
+    Storage(StorageError),
+
+    #[error(transparent)]
+    Chain(#[from] ChainError),
+
+    #[error("Configuration error: {0}")]
+    Configuration(String),
+
     #[error("Internal error: {0}")]
-    InternalError(String),
+    Internal(String),
+}
+
+impl From<StorageError> for VaultError {
+    fn from(err: StorageError) -> Self {
+        match err {
+            // A missing row is a domain outcome, not an infrastructure failure
+            StorageError::NotFound(what) => VaultError::Domain(DomainError::NotFound(what)),
+            other => VaultError::Storage(other),
+        }
+    }
+}
+
+impl From<SqlxError> for VaultError {
+    fn from(err: SqlxError) -> Self {
+        StorageError::from(err).into()
+    }
+}
+
+impl From<ClientError> for VaultError {
+    fn from(err: ClientError) -> Self {
+        VaultError::Chain(ChainError::Client(err))
+    }
+}
+
+impl From<SignerError> for VaultError {
+    fn from(err: SignerError) -> Self {
+        VaultError::Chain(ChainError::Signer(err))
+    }
+}
+
+impl VaultError {
+    /// True when the error means the requested record does not exist
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, VaultError::Domain(DomainError::NotFound(_)))
+    }
 }
 
-pub type Result<T> = std::result::Result<T, VaultError>;
\ No newline at end of file
+pub type Result<T> = std::result::Result<T, VaultError>;
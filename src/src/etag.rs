@@ -0,0 +1,26 @@
+//! Entity tags for conditional reads of vault state.
+//!
+//! A vault's `version` changes whenever anything its read endpoints serve
+//! changes, so it makes a cheap tag: a poller that sends the tag back in
+//! `If-None-Match` is answered 304 from that one column, without the reads
+//! and serialization behind the full response. Tags are weak because equal
+//! tags promise equivalent bodies, not identical bytes.
+
+use crate::models::{BalanceRead, BalanceReadSource, VaultVersion};
+
+/// Weak entity tag of a vault at `version`
+pub fn vault_etag(version: &VaultVersion) -> String {
+    format!("W/\"{}-{}\"", version.vault_id.simple(), version.version)
+}
+
+/// Whether an `If-None-Match` header value names `etag`, by weak comparison
+pub fn if_none_match(header: &str, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    header.split(',').any(|candidate| candidate.trim() == "*" || opaque(candidate) == opaque(etag))
+}
+
+/// Whether a balance read can carry the tag of `version`, read before it;
+/// a cached entry older than the vault row cannot
+pub fn balance_read_current(read: &BalanceRead, version: &VaultVersion) -> bool {
+    read.source == BalanceReadSource::Database || read.as_of >= version.updated_at
+}
@@ -0,0 +1,466 @@
+//! Snapshot-consistent bulk exports for data warehouse loads.
+//!
+//! `POST /exports` queues an export of a time range. A worker reads every vault,
+//! the transaction records created in the range and the confirmed ledger in one
+//! read-only repeatable-read transaction (see `ExportRepository::read_snapshot`),
+//! so the files agree with each other as of the export's `snapshot_at`. Each
+//! dataset is written as JSON lines to object storage:
+//!
+//! ```text
+//! <prefix>/<export_id>/vaults.jsonl
+//! <prefix>/<export_id>/transactions.jsonl
+//! <prefix>/<export_id>/ledger.jsonl
+//! <prefix>/<export_id>/manifest.json   written last; lists the files with row counts and sha256
+//! ```
+//!
+//! Ledger entries are the range's confirmed records with each vault's running
+//! balances after them, replayed from the vault's first record.
+//!
+//! Completion and final failure are outbox events (`export_completed`,
+//! `export_failed`), delivered to webhook consumers like any other event. The
+//! completion event carries presigned download URLs; `GET /exports/:id` signs
+//! fresh ones. Failed attempts are retried up to `MAX_EXPORT_ATTEMPTS`.
+
+use crate::database::ExportRepository;
+use crate::error::{Result, DomainError, VaultError};
+use crate::models::{DataExport, SupportCredential, TransactionRecord, TransactionType};
+use crate::reconciliation::record_effect;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{info, warn, error};
+use uuid::Uuid;
+
+pub const EXPORT_REQUESTED_EVENT: &str = "export_requested";
+pub const EXPORT_COMPLETED_EVENT: &str = "export_completed";
+pub const EXPORT_FAILED_EVENT: &str = "export_failed";
+
+pub const MAX_EXPORT_ATTEMPTS: i32 = 3;
+
+/// Longest range one export may cover
+pub const MAX_EXPORT_RANGE_DAYS: i64 = 366;
+
+/// A worker holds an export this long before another may take it over
+const EXPORT_LEASE_SECONDS: i64 = 1800;
+
+const JSONL_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Somewhere exported files are written and downloaded from
+#[async_trait]
+pub trait ObjectStore: Send + Sync {
+    async fn put(&self, key: &str, body: Vec<u8>, content_type: &str) -> Result<()>;
+
+    /// URL that downloads `key` without credentials until it expires
+    fn presigned_get_url(&self, key: &str, expires_in: Duration) -> String;
+}
+
+/// Percent-encode as SigV4 requires; `/` is kept in paths
+pub fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn hmac_sha256(key: &[u8], message: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(message.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// S3 or any store speaking its API (MinIO, R2, GCS interoperability), signed with SigV4
+pub struct S3ObjectStore {
+    client: reqwest::Client,
+    scheme: String,
+    endpoint_host: String,
+    bucket: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+    /// `<endpoint>/<bucket>/<key>` rather than `<bucket>.<endpoint>/<key>`; most S3-compatible stores need it
+    path_style: bool,
+}
+
+impl S3ObjectStore {
+    pub fn new(
+        endpoint: &str,
+        bucket: String,
+        region: String,
+        access_key_id: String,
+        secret_access_key: String,
+        path_style: bool,
+    ) -> Result<Self> {
+        let url = reqwest::Url::parse(endpoint)
+            .map_err(|e| VaultError::Configuration(format!("Invalid object storage endpoint {}: {}", endpoint, e)))?;
+        let host = url.host_str()
+            .ok_or_else(|| VaultError::Configuration(format!("Object storage endpoint {} has no host", endpoint)))?;
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(300))
+                .build()
+                .unwrap_or_default(),
+            scheme: url.scheme().to_string(),
+            endpoint_host: match url.port() {
+                Some(port) => format!("{}:{}", host, port),
+                None => host.to_string(),
+            },
+            bucket,
+            region,
+            access_key_id,
+            secret_access_key,
+            path_style,
+        })
+    }
+
+    /// Host and canonical path of `key`
+    fn locate(&self, key: &str) -> (String, String) {
+        if self.path_style {
+            (self.endpoint_host.clone(), format!("/{}/{}", uri_encode(&self.bucket, true), uri_encode(key, false)))
+        } else {
+            (format!("{}.{}", self.bucket, self.endpoint_host), format!("/{}", uri_encode(key, false)))
+        }
+    }
+
+    fn credential_scope(&self, at: DateTime<Utc>) -> String {
+        format!("{}/{}/s3/aws4_request", at.format("%Y%m%d"), self.region)
+    }
+
+    fn signature(&self, at: DateTime<Utc>, canonical_request: &str) -> String {
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            at.format("%Y%m%dT%H%M%SZ"),
+            self.credential_scope(at),
+            hex::encode(Sha256::digest(canonical_request.as_bytes())),
+        );
+        let date_key = hmac_sha256(format!("AWS4{}", self.secret_access_key).as_bytes(), &at.format("%Y%m%d").to_string());
+        let region_key = hmac_sha256(&date_key, &self.region);
+        let service_key = hmac_sha256(&region_key, "s3");
+        let signing_key = hmac_sha256(&service_key, "aws4_request");
+        hex::encode(hmac_sha256(&signing_key, &string_to_sign))
+    }
+
+    /// Presigned GET of `key` valid for `expires_in` from `at`
+    pub fn presigned_get_url_at(&self, key: &str, expires_in: Duration, at: DateTime<Utc>) -> String {
+        let (host, path) = self.locate(key);
+        let credential = format!("{}/{}", self.access_key_id, self.credential_scope(at));
+        let query = format!(
+            "X-Amz-Algorithm=AWS4-HMAC-SHA256&X-Amz-Credential={}&X-Amz-Date={}&X-Amz-Expires={}&X-Amz-SignedHeaders=host",
+            uri_encode(&credential, true),
+            at.format("%Y%m%dT%H%M%SZ"),
+            expires_in.num_seconds(),
+        );
+        let canonical_request = format!("GET\n{}\n{}\nhost:{}\n\nhost\nUNSIGNED-PAYLOAD", path, query, host);
+        let signature = self.signature(at, &canonical_request);
+        format!("{}://{}{}?{}&X-Amz-Signature={}", self.scheme, host, path, query, signature)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for S3ObjectStore {
+    async fn put(&self, key: &str, body: Vec<u8>, content_type: &str) -> Result<()> {
+        let now = Utc::now();
+        let (host, path) = self.locate(key);
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_hash = hex::encode(Sha256::digest(&body));
+        let canonical_request = format!(
+            "PUT\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+            path, host, payload_hash, amz_date, payload_hash,
+        );
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+            self.access_key_id,
+            self.credential_scope(now),
+            self.signature(now, &canonical_request),
+        );
+
+        self.client
+            .put(format!("{}://{}{}", self.scheme, host, path))
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", amz_date)
+            .header("Authorization", authorization)
+            .header("Content-Type", content_type)
+            .body(body)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| VaultError::Internal(format!("Upload of {} failed: {}", key, e)))?;
+        Ok(())
+    }
+
+    fn presigned_get_url(&self, key: &str, expires_in: Duration) -> String {
+        self.presigned_get_url_at(key, expires_in, Utc::now())
+    }
+}
+
+/// A confirmed record with its vault's balances after it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub transaction_id: Uuid,
+    pub vault_id: Uuid,
+    pub transaction_type: TransactionType,
+    pub amount: i64,
+    pub confirmed_at: DateTime<Utc>,
+    pub total_change: i64,
+    pub locked_change: i64,
+    pub total_balance_after: i64,
+    pub locked_balance_after: i64,
+}
+
+/// Ledger entries confirmed in `[range_start, range_end)`.
+///
+/// `confirmed` holds each vault's confirmed records from its first, in
+/// confirmation order, so running balances start from zero.
+pub fn ledger_entries(confirmed: &[TransactionRecord], range_start: DateTime<Utc>, range_end: DateTime<Utc>) -> Vec<LedgerEntry> {
+    let mut balances: HashMap<Uuid, (i64, i64)> = HashMap::new();
+    let mut entries = Vec::new();
+    for record in confirmed {
+        let (total_change, locked_change) = record_effect(record);
+        let (total, locked) = balances.entry(record.vault_id).or_insert((0, 0));
+        *total += total_change;
+        *locked += locked_change;
+        if record.updated_at >= range_start && record.updated_at < range_end {
+            entries.push(LedgerEntry {
+                transaction_id: record.id,
+                vault_id: record.vault_id,
+                transaction_type: record.transaction_type.clone(),
+                amount: record.amount,
+                confirmed_at: record.updated_at,
+                total_change,
+                locked_change,
+                total_balance_after: *total,
+                locked_balance_after: *locked,
+            });
+        }
+    }
+    entries
+}
+
+/// One JSON document per line
+pub fn to_jsonl<T: Serialize>(rows: &[T]) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    for row in rows {
+        serde_json::to_writer(&mut body, row)
+            .map_err(|e| VaultError::Internal(format!("Failed to serialize export row: {}", e)))?;
+        body.push(b'\n');
+    }
+    Ok(body)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportRequest {
+    pub range_start: DateTime<Utc>,
+    pub range_end: DateTime<Utc>,
+}
+
+impl ExportRequest {
+    pub fn validate(&self, now: DateTime<Utc>) -> Result<()> {
+        if self.range_end <= self.range_start {
+            return Err(DomainError::Validation("range_end must be after range_start".to_string()).into());
+        }
+        if self.range_end > now {
+            return Err(DomainError::Validation("range_end must not be in the future".to_string()).into());
+        }
+        if self.range_end - self.range_start > Duration::days(MAX_EXPORT_RANGE_DAYS) {
+            return Err(DomainError::Validation(format!("An export covers at most {} days", MAX_EXPORT_RANGE_DAYS)).into());
+        }
+        Ok(())
+    }
+}
+
+/// One written file, as recorded on the export and in its manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportObject {
+    pub name: String,
+    pub key: String,
+    pub rows: usize,
+    pub bytes: usize,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportDownload {
+    #[serde(flatten)]
+    pub object: ExportObject,
+    pub url: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportStatus {
+    #[serde(flatten)]
+    pub export: DataExport,
+    /// Empty until the export completes
+    pub downloads: Vec<ExportDownload>,
+}
+
+/// Queues exports, runs them and signs their downloads
+pub struct ExportService {
+    repo: ExportRepository,
+    store: Option<Arc<dyn ObjectStore>>,
+    prefix: String,
+    url_ttl: Duration,
+    /// Identifies this process's leases
+    worker_id: String,
+}
+
+impl ExportService {
+    pub fn new(pool: sqlx::PgPool, prefix: String, url_ttl: Duration) -> Self {
+        Self {
+            repo: ExportRepository::new(pool),
+            store: None,
+            prefix: prefix.trim_matches('/').to_string(),
+            url_ttl,
+            worker_id: format!("exports-{}", Uuid::new_v4().simple()),
+        }
+    }
+
+    pub fn with_store(mut self, store: Arc<dyn ObjectStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    fn store(&self) -> Result<&Arc<dyn ObjectStore>> {
+        self.store.as_ref()
+            .ok_or_else(|| VaultError::Configuration("No object storage is configured for exports".to_string()))
+    }
+
+    pub async fn request(&self, actor: &SupportCredential, request: ExportRequest) -> Result<DataExport> {
+        self.store()?;
+        request.validate(Utc::now())?;
+        let export = self.repo
+            .create_export(Uuid::new_v4(), &actor.name, request.range_start, request.range_end, EXPORT_REQUESTED_EVENT)
+            .await?;
+        info!("{} requested export {} of {}..{}", actor.name, export.id, export.range_start, export.range_end);
+        Ok(export)
+    }
+
+    pub async fn list(&self, limit: i64) -> Result<Vec<DataExport>> {
+        self.repo.get_exports(limit).await
+    }
+
+    /// The export with freshly signed download URLs once it has completed
+    pub async fn status(&self, export_id: Uuid) -> Result<ExportStatus> {
+        let export = self.repo.get_export(export_id).await?;
+        let downloads = match (&self.store, export.status.as_str()) {
+            (Some(store), "completed") => self.downloads(store.as_ref(), &export.objects)?,
+            _ => Vec::new(),
+        };
+        Ok(ExportStatus { export, downloads })
+    }
+
+    fn downloads(&self, store: &dyn ObjectStore, objects: &serde_json::Value) -> Result<Vec<ExportDownload>> {
+        let objects: Vec<ExportObject> = serde_json::from_value(objects.clone())
+            .map_err(|e| VaultError::Internal(format!("Unreadable export objects: {}", e)))?;
+        let expires_at = Utc::now() + self.url_ttl;
+        Ok(objects
+            .into_iter()
+            .map(|object| ExportDownload {
+                url: store.presigned_get_url(&object.key, self.url_ttl),
+                object,
+                expires_at,
+            })
+            .collect())
+    }
+
+    /// Run queued exports one at a time
+    pub async fn run_worker(&self, interval_seconds: u64) {
+        let Some(store) = self.store.clone() else {
+            info!("Export worker not started: no object storage configured");
+            return;
+        };
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_seconds));
+        loop {
+            interval.tick().await;
+            loop {
+                let export = match self.repo.lease_next(&self.worker_id, EXPORT_LEASE_SECONDS).await {
+                    Ok(Some(export)) => export,
+                    Ok(None) => break,
+                    Err(e) => {
+                        error!("Failed to lease export: {}", e);
+                        break;
+                    }
+                };
+                if let Err(e) = self.run(store.as_ref(), &export).await {
+                    error!("Export {} failed: {}", export.id, e);
+                }
+            }
+        }
+    }
+
+    async fn run(&self, store: &dyn ObjectStore, export: &DataExport) -> Result<()> {
+        match self.write_files(store, export).await {
+            Ok((snapshot_at, objects)) => {
+                let objects = serde_json::to_value(&objects)
+                    .map_err(|e| VaultError::Internal(format!("Failed to serialize export objects: {}", e)))?;
+                let payload = serde_json::json!({
+                    "export_id": export.id,
+                    "status": "completed",
+                    "range_start": export.range_start,
+                    "range_end": export.range_end,
+                    "snapshot_at": snapshot_at,
+                    "downloads": self.downloads(store, &objects)?,
+                });
+                match self.repo
+                    .complete_export(export.id, &self.worker_id, snapshot_at, objects, payload, EXPORT_COMPLETED_EVENT)
+                    .await? {
+                    Some(_) => info!("Export {} completed as of {}", export.id, snapshot_at),
+                    None => warn!("Export {} was taken over before it completed here", export.id),
+                }
+                Ok(())
+            }
+            Err(e) => {
+                if let Some(failed) = self.repo
+                    .fail_export(export.id, &self.worker_id, &e.to_string(), MAX_EXPORT_ATTEMPTS, EXPORT_FAILED_EVENT)
+                    .await? {
+                    warn!("Export {} attempt {} failed ({}): {}", failed.id, failed.attempts, failed.status, e);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Read the snapshot and write every file, the manifest last
+    async fn write_files(&self, store: &dyn ObjectStore, export: &DataExport) -> Result<(DateTime<Utc>, Vec<ExportObject>)> {
+        let snapshot = self.repo.read_snapshot(export.range_start, export.range_end).await?;
+        let ledger = ledger_entries(&snapshot.confirmed, export.range_start, export.range_end);
+
+        let datasets = [
+            ("vaults", snapshot.vaults.len(), to_jsonl(&snapshot.vaults)?),
+            ("transactions", snapshot.transactions.len(), to_jsonl(&snapshot.transactions)?),
+            ("ledger", ledger.len(), to_jsonl(&ledger)?),
+        ];
+        let mut objects = Vec::with_capacity(datasets.len());
+        for (name, rows, body) in datasets {
+            let object = ExportObject {
+                name: name.to_string(),
+                key: format!("{}/{}/{}.jsonl", self.prefix, export.id, name),
+                rows,
+                bytes: body.len(),
+                sha256: hex::encode(Sha256::digest(&body)),
+            };
+            store.put(&object.key, body, JSONL_CONTENT_TYPE).await?;
+            objects.push(object);
+        }
+
+        let manifest = serde_json::to_vec_pretty(&serde_json::json!({
+            "export_id": export.id,
+            "range_start": export.range_start,
+            "range_end": export.range_end,
+            "snapshot_at": snapshot.snapshot_at,
+            "objects": objects,
+        }))
+        .map_err(|e| VaultError::Internal(format!("Failed to serialize export manifest: {}", e)))?;
+        store.put(&format!("{}/{}/manifest.json", self.prefix, export.id), manifest, "application/json").await?;
+
+        Ok((snapshot.snapshot_at, objects))
+    }
+}
@@ -0,0 +1,312 @@
+//! Withdrawal fee schedules.
+//!
+//! Each mint may have fee tiers keyed by the smallest amount they apply to; a
+//! withdrawal pays the flat fee plus basis points of its amount from the
+//! highest tier it reaches. A mint without tiers charges its flat
+//! `withdrawal_fee`. Promotions, for everyone or for one user, replace the
+//! tier fee while they run, but only when they are cheaper.
+//!
+//! The fee is charged out of the withdrawn amount: the user receives
+//! `amount - fee`. Network fees are paid by the service's fee payer and are
+//! only reported by the preview.
+
+use crate::error::{Result, DomainError};
+use crate::models::{MintConfig, WithdrawalFeeTier, WithdrawalFeePromotion};
+use crate::database::FeeRepository;
+use crate::mint_registry::MintRegistry;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+pub const FEE_TIERS_UPDATED_EVENT: &str = "withdrawal_fee_tiers_updated";
+pub const FEE_PROMOTION_CREATED_EVENT: &str = "withdrawal_fee_promotion_created";
+pub const FEE_PROMOTION_ENDED_EVENT: &str = "withdrawal_fee_promotion_ended";
+
+pub const MAX_BPS: i32 = 10_000;
+
+/// Most tiers one mint may have
+pub const MAX_FEE_TIERS: usize = 32;
+
+/// Where a quoted fee came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeSource {
+    /// The mint's flat `withdrawal_fee`; it has no tiers
+    MintDefault,
+    Tier,
+    Promotion,
+}
+
+/// How a fee was computed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeBreakdown {
+    pub source: FeeSource,
+    pub flat_fee: i64,
+    pub bps: i32,
+    /// `bps` of the amount, rounded down
+    pub bps_fee: i64,
+    /// Smallest amount of the tier applied, also set under a promotion
+    pub tier_min_amount: Option<i64>,
+    pub promotion_id: Option<Uuid>,
+    pub promotion_label: Option<String>,
+    /// Fee the tier would have charged, when a promotion replaced it
+    pub undiscounted_fee: Option<i64>,
+}
+
+/// The fee a withdrawal of `amount` pays and what the user receives
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeQuote {
+    pub mint_pubkey: String,
+    pub amount: i64,
+    pub fee: i64,
+    pub net_amount: i64,
+    pub breakdown: FeeBreakdown,
+}
+
+impl FeeQuote {
+    /// Reject withdrawals that would not exceed their fee
+    pub fn validate(&self, mint: &MintConfig) -> Result<()> {
+        if self.amount <= 0 || self.net_amount <= 0 {
+            return Err(DomainError::Validation(format!(
+                "Withdrawal must exceed the fee of {}",
+                mint.format_amount(self.fee)
+            )).into());
+        }
+        Ok(())
+    }
+}
+
+/// `flat_fee` plus `bps` of `amount`, rounded down
+pub fn fee_for(amount: i64, flat_fee: i64, bps: i32) -> (i64, i64) {
+    let bps_fee = (amount.max(0) as i128 * bps as i128 / MAX_BPS as i128) as i64;
+    (flat_fee.saturating_add(bps_fee), bps_fee)
+}
+
+/// Tier applying to `amount`: the one with the highest `min_amount` not above it
+pub fn tier_for(tiers: &[WithdrawalFeeTier], amount: i64) -> Option<&WithdrawalFeeTier> {
+    tiers.iter().filter(|t| t.min_amount <= amount).max_by_key(|t| t.min_amount)
+}
+
+/// Whether a promotion applies to `user_pubkey` at `now`
+pub fn promotion_applies(promotion: &WithdrawalFeePromotion, user_pubkey: &str, now: DateTime<Utc>) -> bool {
+    promotion.starts_at <= now
+        && now < promotion.ends_at
+        && promotion.user_pubkey.as_deref().map_or(true, |user| user == user_pubkey)
+}
+
+/// Quote the fee for a withdrawal of `amount` by `user_pubkey` at `now`
+pub fn quote_fee(
+    mint: &MintConfig,
+    tiers: &[WithdrawalFeeTier],
+    promotions: &[WithdrawalFeePromotion],
+    user_pubkey: &str,
+    amount: i64,
+    now: DateTime<Utc>,
+) -> FeeQuote {
+    let tier = tier_for(tiers, amount);
+    let (flat_fee, bps, source) = match tier {
+        Some(tier) => (tier.flat_fee, tier.bps, FeeSource::Tier),
+        // Without a matching tier the mint's flat fee applies, as before schedules existed
+        None => (mint.withdrawal_fee, 0, FeeSource::MintDefault),
+    };
+    let (fee, bps_fee) = fee_for(amount, flat_fee, bps);
+    let mut breakdown = FeeBreakdown {
+        source,
+        flat_fee,
+        bps,
+        bps_fee,
+        tier_min_amount: tier.map(|t| t.min_amount),
+        promotion_id: None,
+        promotion_label: None,
+        undiscounted_fee: None,
+    };
+    let mut quoted = fee;
+
+    let cheapest = promotions.iter()
+        .filter(|p| p.mint_pubkey == mint.mint_pubkey && promotion_applies(p, user_pubkey, now))
+        .map(|p| (p, fee_for(amount, p.flat_fee, p.bps)))
+        .min_by_key(|(_, (fee, _))| *fee);
+    if let Some((promotion, (promoted, promoted_bps_fee))) = cheapest {
+        if promoted < fee {
+            quoted = promoted;
+            breakdown = FeeBreakdown {
+                source: FeeSource::Promotion,
+                flat_fee: promotion.flat_fee,
+                bps: promotion.bps,
+                bps_fee: promoted_bps_fee,
+                tier_min_amount: breakdown.tier_min_amount,
+                promotion_id: Some(promotion.id),
+                promotion_label: Some(promotion.label.clone()),
+                undiscounted_fee: Some(fee),
+            };
+        }
+    }
+
+    FeeQuote {
+        mint_pubkey: mint.mint_pubkey.clone(),
+        amount,
+        fee: quoted,
+        net_amount: amount - quoted,
+        breakdown,
+    }
+}
+
+/// Check a schedule before it replaces a mint's tiers
+pub fn validate_tiers(tiers: &[WithdrawalFeeTier]) -> Result<()> {
+    if tiers.len() > MAX_FEE_TIERS {
+        return Err(DomainError::Validation(format!("At most {} fee tiers per mint", MAX_FEE_TIERS)).into());
+    }
+    let mut min_amounts: Vec<i64> = tiers.iter().map(|t| t.min_amount).collect();
+    min_amounts.sort_unstable();
+    if min_amounts.windows(2).any(|w| w[0] == w[1]) {
+        return Err(DomainError::Validation("Fee tiers must have distinct min_amount values".to_string()).into());
+    }
+    for tier in tiers {
+        validate_fee(tier.flat_fee, tier.bps)?;
+        if tier.min_amount < 0 {
+            return Err(DomainError::Validation("Fee tier min_amount must not be negative".to_string()).into());
+        }
+    }
+    Ok(())
+}
+
+fn validate_fee(flat_fee: i64, bps: i32) -> Result<()> {
+    if flat_fee < 0 {
+        return Err(DomainError::Validation("Flat fee must not be negative".to_string()).into());
+    }
+    if !(0..=MAX_BPS).contains(&bps) {
+        return Err(DomainError::Validation(format!("bps must be between 0 and {}", MAX_BPS)).into());
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeTierRequest {
+    pub min_amount: i64,
+    #[serde(default)]
+    pub flat_fee: i64,
+    #[serde(default)]
+    pub bps: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeePromotionRequest {
+    pub label: String,
+    /// Limit the promotion to one user
+    pub user_pubkey: Option<String>,
+    #[serde(default)]
+    pub flat_fee: i64,
+    #[serde(default)]
+    pub bps: i32,
+    /// Defaults to now
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: DateTime<Utc>,
+}
+
+/// A mint's tiers and the promotions that have not ended
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeSchedule {
+    pub mint: MintConfig,
+    pub tiers: Vec<WithdrawalFeeTier>,
+    pub promotions: Vec<WithdrawalFeePromotion>,
+}
+
+impl FeeSchedule {
+    pub fn quote(&self, user_pubkey: &str, amount: i64, now: DateTime<Utc>) -> FeeQuote {
+        quote_fee(&self.mint, &self.tiers, &self.promotions, user_pubkey, amount, now)
+    }
+}
+
+/// Loads fee schedules, quotes withdrawals and manages tiers and promotions
+pub struct FeeService {
+    repo: FeeRepository,
+    mint_registry: Arc<MintRegistry>,
+}
+
+impl FeeService {
+    pub fn new(pool: sqlx::PgPool, mint_registry: Arc<MintRegistry>) -> Self {
+        Self {
+            repo: FeeRepository::new(pool),
+            mint_registry,
+        }
+    }
+
+    /// Schedule of `mint_pubkey`, or of the default mint
+    pub async fn schedule(&self, mint_pubkey: Option<&str>) -> Result<FeeSchedule> {
+        let mint = self.mint_registry.resolve(mint_pubkey).await?;
+        let tiers = self.repo.get_tiers(&mint.mint_pubkey).await?;
+        let promotions = self.repo.get_current_promotions(&mint.mint_pubkey, Utc::now()).await?;
+        Ok(FeeSchedule { mint, tiers, promotions })
+    }
+
+    /// Quote a withdrawal and check it exceeds its fee
+    pub async fn quote_withdrawal(&self, user_pubkey: &str, amount: u64) -> Result<(MintConfig, FeeQuote)> {
+        let schedule = self.schedule(None).await?;
+        let quote = schedule.quote(user_pubkey, amount as i64, Utc::now());
+        quote.validate(&schedule.mint)?;
+        Ok((schedule.mint, quote))
+    }
+
+    pub async fn replace_tiers(&self, mint_pubkey: &str, tiers: &[FeeTierRequest], actor: &str) -> Result<Vec<WithdrawalFeeTier>> {
+        let mint = self.mint_registry.get_mint(mint_pubkey).await?;
+        let tiers: Vec<WithdrawalFeeTier> = tiers.iter().map(|t| WithdrawalFeeTier {
+            mint_pubkey: mint.mint_pubkey.clone(),
+            min_amount: t.min_amount,
+            flat_fee: t.flat_fee,
+            bps: t.bps,
+        }).collect();
+        validate_tiers(&tiers)?;
+
+        let saved = self.repo.replace_tiers(&mint.mint_pubkey, &tiers, actor, FEE_TIERS_UPDATED_EVENT).await?;
+        info!("{} replaced the withdrawal fee tiers of {} ({} tiers)", actor, mint.symbol, saved.len());
+        Ok(saved)
+    }
+
+    pub async fn create_promotion(&self, mint_pubkey: &str, request: &FeePromotionRequest, actor: &str) -> Result<WithdrawalFeePromotion> {
+        let mint = self.mint_registry.get_mint(mint_pubkey).await?;
+        validate_fee(request.flat_fee, request.bps)?;
+        if request.label.trim().is_empty() {
+            return Err(DomainError::Validation("Promotion label is required".to_string()).into());
+        }
+        let starts_at = request.starts_at.unwrap_or_else(Utc::now);
+        if request.ends_at <= starts_at || request.ends_at <= Utc::now() {
+            return Err(DomainError::Validation("ends_at must be after starts_at and in the future".to_string()).into());
+        }
+
+        let promotion = self.repo.create_promotion(
+            &mint.mint_pubkey,
+            request.label.trim(),
+            request.user_pubkey.as_deref(),
+            request.flat_fee,
+            request.bps,
+            starts_at,
+            request.ends_at,
+            actor,
+            FEE_PROMOTION_CREATED_EVENT,
+        ).await?;
+        info!("{} created withdrawal fee promotion {} ({}) for {}", actor, promotion.id, promotion.label, mint.symbol);
+        Ok(promotion)
+    }
+
+    pub async fn end_promotion(&self, mint_pubkey: &str, promotion_id: Uuid, actor: &str) -> Result<WithdrawalFeePromotion> {
+        let promotion = self.repo.end_promotion(mint_pubkey, promotion_id, actor, FEE_PROMOTION_ENDED_EVENT).await?;
+        info!("{} ended withdrawal fee promotion {}", actor, promotion_id);
+        Ok(promotion)
+    }
+}
+
+/// Record the quote with the withdrawal: under `withdrawal_fee` in an object,
+/// or next to the client's non-object metadata under `client`
+pub fn attach_to_metadata(metadata: Option<serde_json::Value>, quote: &FeeQuote) -> Option<serde_json::Value> {
+    let fee = serde_json::to_value(quote).unwrap_or(serde_json::Value::Null);
+    Some(match metadata {
+        Some(serde_json::Value::Object(mut object)) => {
+            object.insert("withdrawal_fee".to_string(), fee);
+            serde_json::Value::Object(object)
+        }
+        Some(other) => serde_json::json!({ "client": other, "withdrawal_fee": fee }),
+        None => serde_json::json!({ "withdrawal_fee": fee }),
+    })
+}
@@ -0,0 +1,113 @@
+//! Safe retries of mutating requests.
+//!
+//! Any `POST`, `PUT`, `PATCH` or `DELETE` may carry an `Idempotency-Key`
+//! header. The first request with a key runs and its response is stored; a
+//! retry with the same key, from the same client, within `KEY_RETENTION_HOURS`
+//! gets that response back with `Idempotent-Replayed: true` instead of running
+//! again. So a client that lost a response to a network blip can resend a lock
+//! or transfer without executing it twice.
+//!
+//! - A retry arriving while the first request still runs is answered `409`
+//!   with error `Idempotent request in progress` and `Retry-After`.
+//! - Reusing a key for a different method, path or body is answered `422`.
+//! - `429` and `503` responses are not stored: the request was turned away
+//!   before it could take effect, so a retry with the key runs it again.
+//!
+//! Keys are scoped to the client as identified for rate limiting. The
+//! `idempotency_key` body field of deposits and withdrawals still dedupes at
+//! the transaction record independently of the header.
+
+use crate::database::IdempotencyRepository;
+use crate::error::{Result, DomainError};
+use crate::models::{IdempotencyClaim, StoredResponse};
+use sha2::{Digest, Sha256};
+use tracing::{info, error};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// Error label of the `409` answered while a key's first request runs
+pub const IN_PROGRESS_ERROR: &str = "Idempotent request in progress";
+
+pub const MAX_KEY_LENGTH: usize = 255;
+
+/// How long a key's response is replayed
+pub const KEY_RETENTION_HOURS: i64 = 24;
+
+/// Largest request body accepted with a key
+pub const MAX_REQUEST_BYTES: usize = 1024 * 1024;
+
+/// A key whose request has not finished after this long may be claimed again
+const REQUEST_LOCK_SECONDS: i64 = 60;
+
+pub fn validate_key(key: &str) -> Result<()> {
+    if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+        return Err(DomainError::Validation(format!("Idempotency-Key must be 1 to {} characters", MAX_KEY_LENGTH)).into());
+    }
+    if !key.bytes().all(|b| b.is_ascii_graphic()) {
+        return Err(DomainError::Validation("Idempotency-Key must be printable ASCII".to_string()).into());
+    }
+    Ok(())
+}
+
+/// Hex SHA-256 identifying a request by method, path and body
+pub fn request_hash(method: &str, path: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_bytes());
+    hasher.update([0]);
+    hasher.update(path.as_bytes());
+    hasher.update([0]);
+    hasher.update(body);
+    hex::encode(hasher.finalize())
+}
+
+/// Whether a response with this status is stored and replayed
+pub fn is_replayable(status: u16) -> bool {
+    !matches!(status, 429 | 503)
+}
+
+/// Claims keys for requests and stores their responses
+pub struct IdempotencyService {
+    repo: IdempotencyRepository,
+}
+
+impl IdempotencyService {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { repo: IdempotencyRepository::new(pool) }
+    }
+
+    pub async fn claim(&self, client_id: &str, key: &str, method: &str, path: &str, body: &[u8]) -> Result<IdempotencyClaim> {
+        validate_key(key)?;
+        self.repo.claim(
+            client_id,
+            key,
+            method,
+            path,
+            &request_hash(method, path, body),
+            REQUEST_LOCK_SECONDS,
+            KEY_RETENTION_HOURS * 3600,
+        ).await
+    }
+
+    /// Record the response of a claimed key's request; unreplayable responses free the key
+    pub async fn finish(&self, client_id: &str, key: &str, response: &StoredResponse) -> Result<()> {
+        if is_replayable(response.status) {
+            self.repo.complete(client_id, key, response).await
+        } else {
+            self.repo.release(client_id, key).await
+        }
+    }
+
+    /// Delete expired keys every `interval_seconds`
+    pub async fn run_purger(&self, interval_seconds: u64) {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_seconds));
+        loop {
+            interval.tick().await;
+            match self.repo.purge_expired().await {
+                Ok(0) => {}
+                Ok(purged) => info!("Purged {} expired idempotency keys", purged),
+                Err(e) => error!("Failed to purge idempotency keys: {}", e),
+            }
+        }
+    }
+}
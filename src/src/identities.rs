@@ -0,0 +1,280 @@
+//! Identity verification (KYC) linked to user_pubkeys.
+//!
+//! Providers report verification results by webhook. Each configured provider
+//! authenticates and normalizes its own deliveries into a `VerificationResult`;
+//! the result is stored once per provider event id and applied to the
+//! identity of its user_pubkey unless a later result was applied already.
+//!
+//! `IdentityPolicy` decides what needs a verified identity: vault creation can
+//! require basic verification, and withdrawals from a configured amount up
+//! require enhanced verification. A verification counts only while its status
+//! is `verified` and it has not expired. Without a policy requirement nothing
+//! is checked.
+
+use crate::database::IdentityRepository;
+use crate::error::{Result, DomainError};
+use crate::models::Identity;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+pub const IDENTITY_VERIFICATION_EVENT: &str = "identity_verification";
+
+/// Verification depth, least to most thorough
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationLevel {
+    None,
+    Basic,
+    Enhanced,
+}
+
+impl VerificationLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VerificationLevel::None => "none",
+            VerificationLevel::Basic => "basic",
+            VerificationLevel::Enhanced => "enhanced",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "none" => Some(VerificationLevel::None),
+            "basic" => Some(VerificationLevel::Basic),
+            "enhanced" => Some(VerificationLevel::Enhanced),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationStatus {
+    Pending,
+    Verified,
+    Rejected,
+    /// Withdrawn by the provider after being verified
+    Revoked,
+}
+
+impl VerificationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VerificationStatus::Pending => "pending",
+            VerificationStatus::Verified => "verified",
+            VerificationStatus::Rejected => "rejected",
+            VerificationStatus::Revoked => "revoked",
+        }
+    }
+}
+
+/// Level an identity counts for at `now`
+pub fn effective_level(identity: Option<&Identity>, now: DateTime<Utc>) -> VerificationLevel {
+    match identity {
+        Some(identity) if identity.status == VerificationStatus::Verified.as_str()
+            && identity.expires_at.map_or(true, |expires_at| expires_at > now) => {
+            VerificationLevel::parse(&identity.level).unwrap_or(VerificationLevel::None)
+        }
+        _ => VerificationLevel::None,
+    }
+}
+
+/// A provider's verification result in provider-independent form
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationResult {
+    /// Provider's id of the delivery; redeliveries carry the same one
+    pub event_id: String,
+    pub user_pubkey: String,
+    /// Provider's reference for the applicant
+    pub external_id: String,
+    pub level: VerificationLevel,
+    pub status: VerificationStatus,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// When the provider reached the result
+    pub reported_at: DateTime<Utc>,
+}
+
+/// A KYC provider delivering verification results by webhook
+#[async_trait]
+pub trait IdentityProvider: Send + Sync {
+    /// Appears in the webhook path and on stored identities
+    fn name(&self) -> &str;
+
+    /// Header carrying the provider's signature of the delivery
+    fn signature_header(&self) -> &str;
+
+    /// Authenticate a webhook delivery and normalize its result
+    async fn parse_webhook(&self, signature: Option<&str>, body: &[u8]) -> Result<VerificationResult>;
+}
+
+/// Hex HMAC-SHA256 of the body under a shared secret
+pub fn webhook_signature(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Provider posting `VerificationResult` JSON signed with a shared secret in
+/// `X-Identity-Signature`, e.g. an in-house bridge to a vendor
+pub struct SignedWebhookProvider {
+    name: String,
+    secret: String,
+}
+
+impl SignedWebhookProvider {
+    pub const SIGNATURE_HEADER: &'static str = "x-identity-signature";
+
+    pub fn new(name: String, secret: String) -> Self {
+        Self { name, secret }
+    }
+}
+
+#[async_trait]
+impl IdentityProvider for SignedWebhookProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn signature_header(&self) -> &str {
+        Self::SIGNATURE_HEADER
+    }
+
+    async fn parse_webhook(&self, signature: Option<&str>, body: &[u8]) -> Result<VerificationResult> {
+        let signature = signature
+            .and_then(|s| hex::decode(s.trim()).ok())
+            .ok_or_else(|| DomainError::Unauthorized("Missing or malformed webhook signature".to_string()))?;
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret.as_bytes()).expect("HMAC accepts any key length");
+        mac.update(body);
+        mac.verify_slice(&signature)
+            .map_err(|_| DomainError::Unauthorized("Webhook signature does not match".to_string()))?;
+
+        serde_json::from_slice(body)
+            .map_err(|e| DomainError::Validation(format!("Invalid verification result: {}", e)).into())
+    }
+}
+
+/// Operations gated on identity verification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdentityGate {
+    VaultCreation,
+    Withdrawal { amount: u64 },
+}
+
+/// What needs a verified identity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdentityPolicy {
+    /// Vault creation requires basic verification
+    pub require_verified_for_vault_creation: bool,
+    /// Withdrawals of at least this amount require enhanced verification
+    pub enhanced_withdrawal_threshold: Option<u64>,
+}
+
+impl IdentityPolicy {
+    /// Level the operation requires, `None` when it is not gated
+    pub fn required_level(&self, gate: IdentityGate) -> Option<VerificationLevel> {
+        match gate {
+            IdentityGate::VaultCreation => self.require_verified_for_vault_creation.then_some(VerificationLevel::Basic),
+            IdentityGate::Withdrawal { amount } => self.enhanced_withdrawal_threshold
+                .filter(|threshold| amount >= *threshold)
+                .map(|_| VerificationLevel::Enhanced),
+        }
+    }
+}
+
+/// Ingests provider webhooks and enforces the identity policy
+pub struct IdentityService {
+    repo: IdentityRepository,
+    providers: HashMap<String, Arc<dyn IdentityProvider>>,
+    policy: IdentityPolicy,
+}
+
+impl IdentityService {
+    pub fn new(pool: sqlx::PgPool, policy: IdentityPolicy) -> Self {
+        Self {
+            repo: IdentityRepository::new(pool),
+            providers: HashMap::new(),
+            policy,
+        }
+    }
+
+    pub fn with_provider(mut self, provider: Arc<dyn IdentityProvider>) -> Self {
+        self.providers.insert(provider.name().to_string(), provider);
+        self
+    }
+
+    pub fn policy(&self) -> &IdentityPolicy {
+        &self.policy
+    }
+
+    pub fn provider(&self, name: &str) -> Result<&Arc<dyn IdentityProvider>> {
+        self.providers.get(name)
+            .ok_or_else(|| DomainError::NotFound(format!("Identity provider {}", name)).into())
+    }
+
+    /// Authenticate and apply one webhook delivery of `provider`.
+    ///
+    /// Returns the identity as updated, or `None` when the delivery was a
+    /// redelivery or older than the result already applied.
+    pub async fn ingest_webhook(&self, provider: &str, signature: Option<&str>, body: &[u8]) -> Result<Option<Identity>> {
+        let provider = self.provider(provider)?;
+        let result = match provider.parse_webhook(signature, body).await {
+            Ok(result) => result,
+            Err(e) => {
+                warn!("Rejected identity webhook from {}: {}", provider.name(), e);
+                return Err(e);
+            }
+        };
+        Pubkey::from_str(&result.user_pubkey)
+            .map_err(|_| DomainError::Validation(format!("Invalid user pubkey {}", result.user_pubkey)))?;
+        if result.event_id.is_empty() || result.external_id.is_empty() {
+            return Err(DomainError::Validation("event_id and external_id are required".to_string()).into());
+        }
+
+        let identity = self.repo.record_verification(
+            provider.name(),
+            &result.event_id,
+            &result.user_pubkey,
+            &result.external_id,
+            result.level.as_str(),
+            result.status.as_str(),
+            result.expires_at,
+            result.reported_at,
+            IDENTITY_VERIFICATION_EVENT,
+        ).await?;
+
+        match &identity {
+            Some(identity) => info!("Identity of {} is {} at level {} per {} event {}",
+                                    identity.user_pubkey, identity.status, identity.level, provider.name(), result.event_id),
+            None => info!("Ignored {} event {} for {}: redelivered or superseded",
+                          provider.name(), result.event_id, result.user_pubkey),
+        }
+        Ok(identity)
+    }
+
+    pub async fn identity(&self, user_pubkey: &str) -> Result<Option<Identity>> {
+        self.repo.get_identity(user_pubkey).await
+    }
+
+    /// Refuse the operation unless the policy's level for it is met
+    pub async fn require(&self, user_pubkey: &str, gate: IdentityGate) -> Result<()> {
+        let Some(required) = self.policy.required_level(gate) else {
+            return Ok(());
+        };
+        let identity = self.repo.get_identity(user_pubkey).await?;
+        let level = effective_level(identity.as_ref(), Utc::now());
+        if level < required {
+            return Err(DomainError::VerificationRequired(format!(
+                "{} verification required, {} has {}", required.as_str(), user_pubkey, level.as_str(),
+            )).into());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,553 @@
+//! Incident runbooks: common operator sequences run as one operation.
+//!
+//! `POST /admin/incident` starts a run of a runbook, e.g. `pause_and_assess`:
+//! pause the API, drain in-flight operations, reconcile deeply and report. A
+//! background worker runs the steps in order and records and audits each
+//! outcome, so a run carries on after a restart. A failing step stops its run
+//! until an operator resumes it, at that step, or aborts it. Every step is
+//! safe to run again: a pause already in effect is kept, a drain waits afresh
+//! and a resync only rewrites balances that differ from the chain.
+//!
+//! Pausing opens a read-only maintenance window; `/admin/incident` stays
+//! writable through it. A run that `follows` a paused run takes over its
+//! window, so `resync_and_resume` after `pause_and_assess` lifts the pause.
+
+use crate::balance_tracker::Remediation;
+use crate::cpi_manager::CPIManager;
+use crate::database::{IncidentRepository, TransactionRepository};
+use crate::error::{Result, DomainError, VaultError};
+use crate::maintenance::{self, MaintenanceService};
+use crate::models::{IncidentRun, IncidentRunStatus, IncidentStepRecord, IncidentStepStatus, ReconciliationMode,
+                    SupportCredential, VaultListFilter};
+use crate::reconciliation::{ReconciliationReport, ReconciliationRunSummary, Reconciler};
+use crate::vault_manager::VaultManager;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{info, warn, error};
+use uuid::Uuid;
+
+pub const INCIDENT_STARTED_EVENT: &str = "incident_started";
+pub const INCIDENT_STEP_COMPLETED_EVENT: &str = "incident_step_completed";
+pub const INCIDENT_STEP_FAILED_EVENT: &str = "incident_step_failed";
+pub const INCIDENT_RESUMED_EVENT: &str = "incident_resumed";
+pub const INCIDENT_ABORTED_EVENT: &str = "incident_aborted";
+pub const INCIDENT_COMPLETED_EVENT: &str = "incident_completed";
+
+/// Leaves room for the run id in the maintenance window's reason
+const MAX_REASON_LENGTH: usize = 400;
+
+/// Vaults fetched per page when a run covers every active vault
+const VAULT_PAGE_SIZE: i64 = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IncidentStep {
+    /// Open a read-only maintenance window
+    Pause,
+    /// Wait for submitted and queued operations to settle
+    Drain,
+    /// Deep reconciliation of the run's vaults
+    Reconcile,
+    /// Overwrite balances from chain where the reconciliation says to
+    Resync,
+    /// Lift the run's read-only window
+    Resume,
+    /// Summarise the run
+    Report,
+}
+
+impl IncidentStep {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IncidentStep::Pause => "pause",
+            IncidentStep::Drain => "drain",
+            IncidentStep::Reconcile => "reconcile",
+            IncidentStep::Resync => "resync",
+            IncidentStep::Resume => "resume",
+            IncidentStep::Report => "report",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Some(match value {
+            "pause" => IncidentStep::Pause,
+            "drain" => IncidentStep::Drain,
+            "reconcile" => IncidentStep::Reconcile,
+            "resync" => IncidentStep::Resync,
+            "resume" => IncidentStep::Resume,
+            "report" => IncidentStep::Report,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Runbook {
+    /// Stop writes and find out what is wrong; the pause stays in effect
+    PauseAndAssess,
+    /// Repair what reconciliation finds and lift the pause
+    ResyncAndResume,
+    /// Both of the above in one run
+    FullRecovery,
+    /// Lift the pause only
+    Resume,
+}
+
+impl Runbook {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Runbook::PauseAndAssess => "pause_and_assess",
+            Runbook::ResyncAndResume => "resync_and_resume",
+            Runbook::FullRecovery => "full_recovery",
+            Runbook::Resume => "resume",
+        }
+    }
+
+    pub fn steps(&self) -> &'static [IncidentStep] {
+        use IncidentStep::*;
+        match self {
+            Runbook::PauseAndAssess => &[Pause, Drain, Reconcile, Report],
+            Runbook::ResyncAndResume => &[Reconcile, Resync, Resume, Report],
+            Runbook::FullRecovery => &[Pause, Drain, Reconcile, Resync, Resume, Report],
+            Runbook::Resume => &[Resume, Report],
+        }
+    }
+}
+
+/// Limits of the pause and drain steps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IncidentPolicy {
+    /// Length of the read-only window a pause opens, unless lifted sooner
+    pub pause_hours: i64,
+    /// A drain still waiting on operations this long fails
+    pub drain_timeout_seconds: i64,
+}
+
+impl Default for IncidentPolicy {
+    fn default() -> Self {
+        Self { pause_hours: 4, drain_timeout_seconds: 600 }
+    }
+}
+
+impl IncidentPolicy {
+    pub fn new(pause_hours: i64, drain_timeout_seconds: i64) -> Result<Self> {
+        if !(1..=maintenance::MAX_WINDOW_HOURS).contains(&pause_hours) {
+            return Err(VaultError::Configuration(format!(
+                "Incident pause must be 1-{} hours", maintenance::MAX_WINDOW_HOURS
+            )));
+        }
+        if drain_timeout_seconds <= 0 {
+            return Err(VaultError::Configuration("Incident drain timeout must be positive".to_string()));
+        }
+        Ok(Self { pause_hours, drain_timeout_seconds })
+    }
+}
+
+/// What running a step came to
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepOutcome {
+    Completed(serde_json::Value),
+    /// Not done yet; tried again on the worker's next tick
+    Waiting(serde_json::Value),
+    Failed(String),
+}
+
+/// Drain is done when nothing is in flight, and fails once it has waited `timeout`
+pub fn drain_outcome(in_flight: i64, waited: Duration, timeout: Duration) -> StepOutcome {
+    let output = serde_json::json!({ "in_flight": in_flight, "waited_seconds": waited.num_seconds() });
+    if in_flight == 0 {
+        StepOutcome::Completed(output)
+    } else if waited >= timeout {
+        StepOutcome::Failed(format!("{} operations still in flight after {}s", in_flight, waited.num_seconds()))
+    } else {
+        StepOutcome::Waiting(output)
+    }
+}
+
+/// The first step not yet completed
+pub fn next_step(steps: &[IncidentStepRecord]) -> Option<&IncidentStepRecord> {
+    steps.iter().find(|step| step.status != IncidentStepStatus::Completed.as_str())
+}
+
+/// Vaults a resync can repair, and vaults that need an operator
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResyncCandidates {
+    pub resync_vault_ids: Vec<Uuid>,
+    pub manual_review_vault_ids: Vec<Uuid>,
+}
+
+pub fn resync_candidates(reports: &[ReconciliationReport]) -> ResyncCandidates {
+    let mut candidates = ResyncCandidates::default();
+    for report in reports.iter().filter(|r| !r.is_consistent) {
+        let needs = |remediation: Remediation| report.findings.iter().any(|f| f.remediation == remediation);
+        if needs(Remediation::ManualReview) {
+            candidates.manual_review_vault_ids.push(report.vault_id);
+        } else if needs(Remediation::ResyncFromChain) {
+            candidates.resync_vault_ids.push(report.vault_id);
+        }
+    }
+    candidates
+}
+
+/// Output of a reconcile step
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReconcileOutput {
+    pub mode: ReconciliationMode,
+    pub summary: ReconciliationRunSummary,
+    #[serde(flatten)]
+    pub candidates: ResyncCandidates,
+}
+
+/// Summary written by a run's report step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentReport {
+    pub run_id: Uuid,
+    pub runbook: String,
+    pub reason: String,
+    pub started_at: DateTime<Utc>,
+    pub reported_at: DateTime<Utc>,
+    /// Steps before the report, with what each did
+    pub steps: Vec<IncidentStepRecord>,
+    pub in_flight_operations: i64,
+    /// Whether the API was still rejecting writes when the report was written
+    pub read_only: bool,
+}
+
+/// A run with its steps
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentRunDetail {
+    pub run: IncidentRun,
+    pub steps: Vec<IncidentStepRecord>,
+}
+
+/// Starts, advances, resumes and aborts incident runs
+pub struct IncidentManager {
+    repo: IncidentRepository,
+    transaction_repo: TransactionRepository,
+    vault_manager: Arc<VaultManager>,
+    maintenance: Arc<MaintenanceService>,
+    reconciler: Arc<Reconciler>,
+    cpi_manager: Arc<CPIManager>,
+    policy: IncidentPolicy,
+}
+
+impl IncidentManager {
+    pub fn new(
+        pool: sqlx::PgPool,
+        vault_manager: Arc<VaultManager>,
+        maintenance: Arc<MaintenanceService>,
+        reconciler: Arc<Reconciler>,
+        cpi_manager: Arc<CPIManager>,
+        policy: IncidentPolicy,
+    ) -> Self {
+        Self {
+            repo: IncidentRepository::new(pool.clone()),
+            transaction_repo: TransactionRepository::new(pool),
+            vault_manager,
+            maintenance,
+            reconciler,
+            cpi_manager,
+            policy,
+        }
+    }
+
+    /// Start a run; the worker picks it up on its next tick
+    pub async fn start(
+        &self,
+        actor: &SupportCredential,
+        runbook: Runbook,
+        reason: &str,
+        vault_ids: Option<Vec<Uuid>>,
+        follows: Option<Uuid>,
+    ) -> Result<IncidentRunDetail> {
+        let reason = reason.trim();
+        if reason.is_empty() || reason.chars().count() > MAX_REASON_LENGTH {
+            return Err(DomainError::Validation(format!("Reason must be 1-{} characters", MAX_REASON_LENGTH)).into());
+        }
+        let vault_ids = match vault_ids {
+            Some(mut ids) => {
+                ids.sort();
+                ids.dedup();
+                if ids.is_empty() {
+                    return Err(DomainError::Validation("Omit vault_ids to cover every active vault".to_string()).into());
+                }
+                Some(ids)
+            }
+            None => None,
+        };
+        let maintenance_window_id = match follows {
+            Some(previous) => self.repo.get_run(previous).await?.maintenance_window_id,
+            None => None,
+        };
+
+        let steps: Vec<String> = runbook.steps().iter().map(|step| step.as_str().to_string()).collect();
+        let run = self.repo.create_run(
+            Uuid::new_v4(),
+            runbook.as_str(),
+            reason,
+            vault_ids.as_deref(),
+            maintenance_window_id,
+            follows,
+            &actor.name,
+            &steps,
+            INCIDENT_STARTED_EVENT,
+        ).await?;
+        warn!("{} started incident run {} ({}): {}", actor.name, run.id, run.runbook, run.reason);
+
+        self.detail(run.id).await
+    }
+
+    /// Continue a failed run at the step that failed
+    pub async fn resume(&self, actor: &SupportCredential, run_id: Uuid) -> Result<IncidentRunDetail> {
+        let from = [IncidentRunStatus::Failed.as_str().to_string()];
+        self.repo
+            .transition(run_id, &from, IncidentRunStatus::Running.as_str(), &actor.name, INCIDENT_RESUMED_EVENT)
+            .await?
+            .ok_or_else(|| DomainError::InvalidVaultState(format!("Incident run {} has not failed", run_id)))?;
+        info!("{} resumed incident run {}", actor.name, run_id);
+
+        self.detail(run_id).await
+    }
+
+    /// Stop a run; steps already run are not undone, so a pause stays in effect
+    pub async fn abort(&self, actor: &SupportCredential, run_id: Uuid) -> Result<IncidentRunDetail> {
+        let from = [IncidentRunStatus::Running.as_str().to_string(), IncidentRunStatus::Failed.as_str().to_string()];
+        match self.repo
+            .transition(run_id, &from, IncidentRunStatus::Aborted.as_str(), &actor.name, INCIDENT_ABORTED_EVENT)
+            .await?
+        {
+            Some(run) => warn!("{} aborted incident run {} ({})", actor.name, run.id, run.runbook),
+            None => {
+                let run = self.repo.get_run(run_id).await?;
+                return Err(DomainError::InvalidVaultState(format!("Incident run {} is {}", run.id, run.status)).into());
+            }
+        }
+
+        self.detail(run_id).await
+    }
+
+    pub async fn detail(&self, run_id: Uuid) -> Result<IncidentRunDetail> {
+        Ok(IncidentRunDetail {
+            run: self.repo.get_run(run_id).await?,
+            steps: self.repo.get_steps(run_id).await?,
+        })
+    }
+
+    /// Most recent runs first
+    pub async fn list(&self, limit: i64) -> Result<Vec<IncidentRun>> {
+        self.repo.list_runs(limit).await
+    }
+
+    /// Advance every running run until the process exits
+    pub async fn run_worker(&self, interval_seconds: u64) {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_seconds));
+        loop {
+            interval.tick().await;
+            let runs = match self.repo.list_running_runs().await {
+                Ok(runs) => runs,
+                Err(e) => {
+                    error!("Failed to list running incident runs: {}", e);
+                    continue;
+                }
+            };
+            for run in runs {
+                if let Err(e) = self.advance(run.id).await {
+                    error!("Incident run {} could not advance: {}", run.id, e);
+                }
+            }
+        }
+    }
+
+    /// Run steps until one waits or fails, or the run is done
+    async fn advance(&self, run_id: Uuid) -> Result<()> {
+        loop {
+            // Re-read each time: an abort, or the window a pause opened, takes effect between steps
+            let run = self.repo.get_run(run_id).await?;
+            if run.status != IncidentRunStatus::Running.as_str() {
+                return Ok(());
+            }
+
+            let steps = self.repo.get_steps(run.id).await?;
+            let Some(next) = next_step(&steps) else {
+                let running = [IncidentRunStatus::Running.as_str().to_string()];
+                if self.repo
+                    .transition(run.id, &running, IncidentRunStatus::Completed.as_str(), &run.requested_by, INCIDENT_COMPLETED_EVENT)
+                    .await?
+                    .is_some()
+                {
+                    info!("Incident run {} ({}) completed", run.id, run.runbook);
+                }
+                return Ok(());
+            };
+
+            let record = self.repo.start_step(run.id, next.position).await?;
+            let outcome = match IncidentStep::parse(&record.step) {
+                Some(step) => self.run_step(&run, step, &record, &steps).await
+                    .unwrap_or_else(|e| StepOutcome::Failed(e.to_string())),
+                None => StepOutcome::Failed(format!("Unknown step {}", record.step)),
+            };
+
+            match outcome {
+                StepOutcome::Completed(output) => {
+                    info!("Incident run {}: {} completed", run.id, record.step);
+                    self.repo.complete_step(run.id, record.position, &output, INCIDENT_STEP_COMPLETED_EVENT).await?;
+                }
+                StepOutcome::Waiting(output) => {
+                    self.repo.record_step_progress(run.id, record.position, &output).await?;
+                    return Ok(());
+                }
+                StepOutcome::Failed(error_message) => {
+                    error!("Incident run {}: {} failed (attempt {}): {}", run.id, record.step, record.attempts, error_message);
+                    self.repo.fail_step(run.id, record.position, &error_message, INCIDENT_STEP_FAILED_EVENT).await?;
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    async fn run_step(
+        &self,
+        run: &IncidentRun,
+        step: IncidentStep,
+        record: &IncidentStepRecord,
+        steps: &[IncidentStepRecord],
+    ) -> Result<StepOutcome> {
+        let now = Utc::now();
+        match step {
+            IncidentStep::Pause => self.pause(run, now).await,
+            IncidentStep::Drain => {
+                let in_flight = self.transaction_repo.count_in_flight_operations().await?;
+                let waited = now - record.started_at.unwrap_or(now);
+                Ok(drain_outcome(in_flight, waited, Duration::seconds(self.policy.drain_timeout_seconds)))
+            }
+            IncidentStep::Reconcile => self.reconcile(run, record).await,
+            IncidentStep::Resync => self.resync(run, record, steps).await,
+            IncidentStep::Resume => self.lift_pause(run).await,
+            IncidentStep::Report => {
+                let report = IncidentReport {
+                    run_id: run.id,
+                    runbook: run.runbook.clone(),
+                    reason: run.reason.clone(),
+                    started_at: run.created_at,
+                    reported_at: now,
+                    steps: steps.iter().filter(|s| s.position < record.position).cloned().collect(),
+                    in_flight_operations: self.transaction_repo.count_in_flight_operations().await?,
+                    read_only: self.maintenance.status(now).read_only,
+                };
+                Ok(StepOutcome::Completed(serde_json::to_value(&report).unwrap_or_default()))
+            }
+        }
+    }
+
+    async fn pause(&self, run: &IncidentRun, now: DateTime<Utc>) -> Result<StepOutcome> {
+        if let Some(window_id) = run.maintenance_window_id {
+            if self.maintenance.is_window_active(window_id, now) {
+                return Ok(StepOutcome::Completed(serde_json::json!({ "window_id": window_id, "opened": false })));
+            }
+        }
+
+        let window = self.maintenance
+            .schedule(now, now + Duration::hours(self.policy.pause_hours), &format!("Incident {}: {}", run.id, run.reason), true)
+            .await?;
+        self.repo.set_maintenance_window(run.id, window.id).await?;
+        warn!("Incident run {} paused writes until {}", run.id, window.ends_at);
+
+        Ok(StepOutcome::Completed(serde_json::json!({ "window_id": window.id, "opened": true, "ends_at": window.ends_at })))
+    }
+
+    async fn lift_pause(&self, run: &IncidentRun) -> Result<StepOutcome> {
+        let Some(window_id) = run.maintenance_window_id else {
+            return Ok(StepOutcome::Completed(serde_json::json!({ "window_id": null, "lifted": false })));
+        };
+
+        let lifted = match self.maintenance.cancel(window_id).await {
+            Ok(_) => true,
+            // Already cancelled or over
+            Err(e) if e.is_not_found() => false,
+            Err(e) => return Err(e),
+        };
+        if lifted {
+            info!("Incident run {} lifted pause {}", run.id, window_id);
+        }
+        Ok(StepOutcome::Completed(serde_json::json!({ "window_id": window_id, "lifted": lifted })))
+    }
+
+    async fn reconcile(&self, run: &IncidentRun, record: &IncidentStepRecord) -> Result<StepOutcome> {
+        let mode = ReconciliationMode::Deep;
+        let vault_ids = self.run_vault_ids(run).await?;
+
+        let mut summary = ReconciliationRunSummary::default();
+        let mut reports = Vec::new();
+        for vault_id in vault_ids {
+            let outcome = self.reconciler.reconcile(vault_id, mode).await;
+            summary.record(vault_id, mode, outcome.as_ref());
+            if let Ok(report) = outcome {
+                reports.push(report);
+            }
+        }
+
+        let output = ReconcileOutput { mode, candidates: resync_candidates(&reports), summary };
+        let errors = output.summary.errors;
+        let output = serde_json::to_value(&output).unwrap_or_default();
+        if errors > 0 {
+            // Keep what was found; the retry reconciles every vault again
+            self.repo.record_step_progress(run.id, record.position, &output).await?;
+            return Ok(StepOutcome::Failed(format!("{} vaults could not be reconciled", errors)));
+        }
+        Ok(StepOutcome::Completed(output))
+    }
+
+    async fn resync(&self, run: &IncidentRun, record: &IncidentStepRecord, steps: &[IncidentStepRecord]) -> Result<StepOutcome> {
+        // Vaults named by the latest reconciliation before this step
+        let reconciled = steps.iter()
+            .rev()
+            .filter(|s| s.position < record.position && s.step == IncidentStep::Reconcile.as_str())
+            .find(|s| s.status == IncidentStepStatus::Completed.as_str())
+            .and_then(|s| s.output.clone())
+            .and_then(|output| serde_json::from_value::<ReconcileOutput>(output).ok())
+            .unwrap_or_default();
+
+        let mut resynced = Vec::new();
+        let mut failed = Vec::new();
+        for vault_id in &reconciled.candidates.resync_vault_ids {
+            match self.cpi_manager.resync_vault_from_chain(*vault_id).await {
+                Ok(_) => resynced.push(*vault_id),
+                Err(e) => {
+                    warn!("Incident run {} could not resync vault {}: {}", run.id, vault_id, e);
+                    failed.push(serde_json::json!({ "vault_id": vault_id, "error": e.to_string() }));
+                }
+            }
+        }
+
+        let output = serde_json::json!({
+            "resynced": resynced,
+            "failed": failed,
+            "manual_review_vault_ids": reconciled.candidates.manual_review_vault_ids,
+        });
+        if !failed.is_empty() {
+            self.repo.record_step_progress(run.id, record.position, &output).await?;
+            return Ok(StepOutcome::Failed(format!("{} vaults could not be resynced", failed.len())));
+        }
+        Ok(StepOutcome::Completed(output))
+    }
+
+    /// The run's vaults, or every active vault
+    async fn run_vault_ids(&self, run: &IncidentRun) -> Result<Vec<Uuid>> {
+        if let Some(vault_ids) = &run.vault_ids {
+            return Ok(vault_ids.clone());
+        }
+
+        let filter = VaultListFilter { is_active: Some(true), ..Default::default() };
+        let mut vault_ids = Vec::new();
+        loop {
+            let page = self.vault_manager.list_vaults(&filter, VAULT_PAGE_SIZE, vault_ids.len() as i64).await?;
+            let done = (page.len() as i64) < VAULT_PAGE_SIZE;
+            vault_ids.extend(page.into_iter().map(|v| v.id));
+            if done {
+                return Ok(vault_ids);
+            }
+        }
+    }
+}
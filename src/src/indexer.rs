@@ -0,0 +1,318 @@
+//! Program indexer with slot-gap detection and re-scan.
+//!
+//! Each poll asks `getSignaturesForAddress` for everything newer than the
+//! program's cursor, records every signature in `indexed_signatures` and moves
+//! the cursor to the newest one, in one statement. A poll answered with a full
+//! page did not reach the cursor: the signatures between the cursor and the
+//! oldest one returned were never seen, and that slot range is stored as a gap.
+//! A node that no longer knows the cursor signature answers the same way, so
+//! signature discontinuities are caught by the same rule.
+//!
+//! Open gaps are re-scanned page by page from their newest end until the scan
+//! reaches the cursor signature or slot where the gap began; only then is the
+//! gap resolved. A gap therefore stays open, and alerted on, until every slot
+//! in it has been covered.
+//!
+//! After polling, pending deposits whose signatures are indexed and finalized
+//! are verified against their logs and credited or failed (see
+//! `crate::deposits`).
+//!
+//! Proof of completeness comes from two sides: no open gaps, and no confirmed
+//! transaction record whose signature the indexer never processed. Lag is the
+//! number of slots the cluster has advanced since the last successful poll.
+
+use crate::database::IndexerRepository;
+use crate::deposits::{self, DepositCheck, DEPOSIT_BATCH_SIZE};
+use crate::error::{Result, DomainError};
+use crate::models::{IndexerCursor, IndexerGap, IndexerGapRange, SignatureEntry, TransactionStatus};
+use crate::rpc::RpcMethodClass;
+use crate::transaction_builder::TransactionBuilder;
+use crate::vault_manager::VaultManager;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{info, warn, error};
+
+/// Signatures requested per poll and per re-scan page
+pub const SIGNATURE_PAGE_SIZE: usize = 1000;
+
+/// Re-scan pages fetched per gap in one pass, so a wide gap cannot starve polling
+const MAX_RESCAN_PAGES: usize = 5;
+
+/// Open gaps re-scanned in one pass
+const RESCAN_BATCH_SIZE: i64 = 10;
+
+/// Confirmed records younger than this may not be indexed yet
+pub const UNINDEXED_GRACE_SECONDS: i64 = 300;
+
+/// Gap left by a poll for everything newer than the cursor at `(cursor_slot, cursor_signature)`.
+///
+/// A short page reached the cursor. A full page whose oldest signature is in a
+/// slot before the cursor's went past it, which also leaves nothing uncovered.
+pub fn poll_gap(cursor_slot: u64, cursor_signature: &str, page: &[SignatureEntry], page_limit: usize) -> Option<IndexerGapRange> {
+    if page.len() < page_limit {
+        return None;
+    }
+    let oldest = page.iter().min_by_key(|e| e.slot)?;
+    (oldest.slot >= cursor_slot).then(|| IndexerGapRange {
+        from_slot: cursor_slot,
+        to_slot: oldest.slot,
+        until_signature: cursor_signature.to_string(),
+        before_signature: oldest.signature.clone(),
+    })
+}
+
+/// Where a re-scan continues after `page`: `None` when the gap is covered,
+/// otherwise the oldest signature of the page
+pub fn rescan_next<'a>(gap: &IndexerGap, page: &'a [SignatureEntry], page_limit: usize) -> Option<&'a SignatureEntry> {
+    if page.len() < page_limit {
+        return None;
+    }
+    page.iter().min_by_key(|e| e.slot).filter(|oldest| oldest.slot as i64 >= gap.from_slot)
+}
+
+/// Slots the cluster has advanced since the poll that saw `polled_head_slot`
+pub fn indexer_lag_slots(head_slot: u64, polled_head_slot: i64) -> u64 {
+    head_slot.saturating_sub(polled_head_slot.max(0) as u64)
+}
+
+/// One indexed program with its lag
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgramIndexStatus {
+    #[serde(flatten)]
+    pub cursor: IndexerCursor,
+    /// `None` until the cluster slot has been observed
+    pub lag_slots: Option<u64>,
+    pub open_gaps: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexerStatus {
+    pub programs: Vec<ProgramIndexStatus>,
+    pub open_gaps: Vec<IndexerGap>,
+    /// Confirmed records past the grace period whose signature was never indexed
+    pub unindexed_confirmed_records: i64,
+    pub max_lag_slots: Option<u64>,
+}
+
+impl IndexerStatus {
+    /// Conditions worth alerting on: lag past `lag_alert_slots`, open gaps, missed signatures
+    pub fn alerts(&self, lag_alert_slots: u64) -> Vec<String> {
+        let mut alerts = Vec::new();
+        for program in &self.programs {
+            if let Some(lag) = program.lag_slots.filter(|lag| *lag > lag_alert_slots) {
+                alerts.push(format!("Indexer for {} is {} slots behind (threshold {})",
+                                    program.cursor.program_id, lag, lag_alert_slots));
+            }
+        }
+        if !self.open_gaps.is_empty() {
+            let slots: i64 = self.open_gaps.iter().map(|g| g.to_slot - g.from_slot).sum();
+            alerts.push(format!("Indexer has {} open gaps spanning {} slots", self.open_gaps.len(), slots));
+        }
+        if self.unindexed_confirmed_records > 0 {
+            alerts.push(format!("{} confirmed transactions were never indexed", self.unindexed_confirmed_records));
+        }
+        alerts
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexerPassSummary {
+    pub indexed: usize,
+    pub gaps_detected: usize,
+    pub gaps_resolved: usize,
+    pub recovered: usize,
+    pub deposits_credited: usize,
+    pub deposits_rejected: usize,
+}
+
+/// Polls the program's signatures and re-scans the gaps polling leaves
+pub struct ProgramIndexer {
+    repo: IndexerRepository,
+    transaction_builder: Arc<TransactionBuilder>,
+    vault_manager: Arc<VaultManager>,
+    programs: Vec<Pubkey>,
+}
+
+impl ProgramIndexer {
+    pub fn new(pool: sqlx::PgPool, transaction_builder: Arc<TransactionBuilder>, vault_manager: Arc<VaultManager>) -> Self {
+        let programs = vec![transaction_builder.program_id()];
+        Self {
+            repo: IndexerRepository::new(pool),
+            transaction_builder,
+            vault_manager,
+            programs,
+        }
+    }
+
+    /// Poll every program, re-scan open gaps, then settle pending deposits
+    pub async fn run_pass(&self) -> Result<IndexerPassSummary> {
+        let mut summary = IndexerPassSummary::default();
+        for program in &self.programs {
+            let (indexed, gap) = self.poll(*program).await?;
+            summary.indexed += indexed;
+            summary.gaps_detected += gap as usize;
+        }
+
+        for gap in self.repo.get_open_gaps(RESCAN_BATCH_SIZE).await? {
+            match self.rescan(&gap).await {
+                Ok(rescanned) => {
+                    summary.recovered += (rescanned.recovered_count - gap.recovered_count) as usize;
+                    summary.gaps_resolved += (rescanned.status == "resolved") as usize;
+                }
+                Err(e) => {
+                    warn!("Re-scan of indexer gap {} ({}..{}) failed: {}", gap.id, gap.from_slot, gap.to_slot, e);
+                    self.repo.record_rescan_failure(gap.id, &e.to_string()).await?;
+                }
+            }
+        }
+
+        self.settle_deposits(&mut summary).await?;
+        Ok(summary)
+    }
+
+    /// Credit pending deposits finalized with matching events; fail those that
+    /// failed on-chain, do not match, or were never seen
+    async fn settle_deposits(&self, summary: &mut IndexerPassSummary) -> Result<()> {
+        let pending = self.vault_manager.get_pending_deposits(DEPOSIT_BATCH_SIZE).await?;
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let finalized_slot = self.transaction_builder.fetch_finalized_slot(RpcMethodClass::Snapshot).await?;
+        let program_id = self.transaction_builder.program_id();
+        let now = Utc::now();
+
+        for deposit in &pending {
+            let verdict = match deposits::check_pending(deposit, finalized_slot, now) {
+                DepositCheck::Wait => continue,
+                DepositCheck::Reject(reason) => Err(reason),
+                DepositCheck::Verify { slot } => {
+                    match self.transaction_builder.fetch_finalized_logs(&deposit.signature, RpcMethodClass::Snapshot).await {
+                        Ok(logs) => deposits::verify_events(
+                            deposit,
+                            &deposits::deposit_events(&logs, &program_id),
+                            &deposits::transfer_fee_events(&logs, &program_id),
+                        ).map(|credited| (slot, credited)),
+                        Err(e) => {
+                            warn!("Failed to fetch deposit {} transaction {}: {}", deposit.transaction_id, deposit.signature, e);
+                            continue;
+                        }
+                    }
+                }
+            };
+
+            match verdict {
+                Ok((slot, credited)) => match self.vault_manager.credit_deposit(deposit.transaction_id, slot, credited).await {
+                    Ok(Some(_)) => summary.deposits_credited += 1,
+                    Ok(None) => {}
+                    Err(e) => error!("Failed to credit deposit {}: {}", deposit.transaction_id, e),
+                },
+                Err(reason) => {
+                    warn!("Rejecting deposit {} ({}): {}", deposit.transaction_id, deposit.signature, reason);
+                    match self.vault_manager.transaction_manager()
+                        .update_transaction_status(deposit.transaction_id, TransactionStatus::Failed, Some(reason))
+                        .await
+                    {
+                        Ok(_) => summary.deposits_rejected += 1,
+                        Err(e) => error!("Failed to reject deposit {}: {}", deposit.transaction_id, e),
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Index everything newer than the cursor; returns the signatures fetched and whether a gap was left
+    async fn poll(&self, program: Pubkey) -> Result<(usize, bool)> {
+        let program_id = program.to_string();
+        let cursor = self.repo.get_cursor(&program_id).await?;
+        let cursor_position = cursor.as_ref()
+            .and_then(|c| c.last_slot.zip(c.last_signature.clone()));
+
+        let head_slot = self.transaction_builder.rpc().call(RpcMethodClass::Snapshot, |c| c.get_slot()).await?;
+        let page = self.transaction_builder.fetch_signature_page(
+            program,
+            None,
+            cursor_position.as_ref().map(|(_, signature)| signature.as_str()),
+            SIGNATURE_PAGE_SIZE,
+            RpcMethodClass::Snapshot,
+        ).await?;
+
+        // Without a cursor indexing starts at the newest signature; nothing before it is owed
+        let gap = cursor_position.as_ref()
+            .and_then(|(slot, signature)| poll_gap(*slot as u64, signature, &page, SIGNATURE_PAGE_SIZE));
+        if let Some(gap) = &gap {
+            error!("Indexer gap for {}: slots {}..{} not covered by the poll; re-scanning",
+                   program_id, gap.from_slot, gap.to_slot);
+        }
+
+        let expected = cursor_position.as_ref().map(|(_, signature)| signature.as_str());
+        if self.repo.record_poll(&program_id, expected, &page, head_slot, gap.as_ref()).await?.is_none() {
+            info!("Indexer cursor for {} moved during the poll; another worker indexed it", program_id);
+            return Ok((page.len(), false));
+        }
+        Ok((page.len(), gap.is_some()))
+    }
+
+    /// Walk one gap back from its newest end for up to `MAX_RESCAN_PAGES` pages
+    async fn rescan(&self, gap: &IndexerGap) -> Result<IndexerGap> {
+        let program = Pubkey::from_str(&gap.program_id)
+            .map_err(|_| DomainError::Validation(format!("Invalid program id {}", gap.program_id)))?;
+        let mut gap = gap.clone();
+
+        for _ in 0..MAX_RESCAN_PAGES {
+            let page = self.transaction_builder.fetch_signature_page(
+                program,
+                Some(&gap.before_signature),
+                Some(&gap.until_signature),
+                SIGNATURE_PAGE_SIZE,
+                RpcMethodClass::Snapshot,
+            ).await?;
+            let next = rescan_next(&gap, &page, SIGNATURE_PAGE_SIZE);
+            gap = self.repo.record_rescan(gap.id, &page, next).await?;
+
+            if gap.status == "resolved" {
+                info!("Indexer gap {} for {} resolved; {} signatures recovered", gap.id, gap.program_id, gap.recovered_count);
+                break;
+            }
+        }
+        Ok(gap)
+    }
+
+    /// Cursors, lag against `head_slot`, open gaps and missed confirmed records
+    pub async fn status(&self, head_slot: Option<u64>, now: DateTime<Utc>) -> Result<IndexerStatus> {
+        let cursors = self.repo.get_cursors().await?;
+        let open_gaps = self.repo.get_open_gaps(1000).await?;
+
+        let unindexed_confirmed_records = match cursors.iter().map(|c| c.created_at).min() {
+            Some(started) => self.repo
+                .count_unindexed_confirmed(started, now - Duration::seconds(UNINDEXED_GRACE_SECONDS))
+                .await?,
+            None => 0,
+        };
+
+        let programs: Vec<ProgramIndexStatus> = cursors
+            .into_iter()
+            .map(|cursor| ProgramIndexStatus {
+                lag_slots: head_slot.map(|head| indexer_lag_slots(head, cursor.polled_head_slot)),
+                open_gaps: open_gaps.iter().filter(|g| g.program_id == cursor.program_id).count(),
+                cursor,
+            })
+            .collect();
+
+        Ok(IndexerStatus {
+            max_lag_slots: programs.iter().filter_map(|p| p.lag_slots).max(),
+            programs,
+            open_gaps,
+            unindexed_confirmed_records,
+        })
+    }
+
+    /// Gaps of every status, newest first
+    pub async fn gaps(&self, limit: i64) -> Result<Vec<IndexerGap>> {
+        self.repo.get_gaps(limit).await
+    }
+}
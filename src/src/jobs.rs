@@ -0,0 +1,237 @@
+//! Persistent record of background monitor job runs, with missed-run detection.
+//!
+//! Every execution of a `VaultMonitor` job is written to `monitor_job_runs`
+//! with its start time, duration and outcome. A job's loop that dies (a panic,
+//! a task that ended) leaves no trace in the logs beyond its last run, so a
+//! watchdog running outside the monitor compares each job's latest run with
+//! its interval: a job that has not run within `MISSED_RUN_FACTOR` intervals
+//! is logged at error level and audited as `monitor_job_missed`, once until it
+//! runs again.
+
+use crate::error::Result;
+use crate::models::{MonitorJobRun, ReconciliationMode};
+use crate::database::MonitorJobRepository;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::Mutex;
+use tracing::{info, warn, error};
+
+pub const MONITOR_JOB_MISSED_EVENT: &str = "monitor_job_missed";
+
+/// Intervals a job may go without running before it counts as missed
+pub const MISSED_RUN_FACTOR: i64 = 2;
+
+/// Runs kept before pruning
+const RUN_RETENTION_DAYS: i64 = 7;
+
+pub const HEALTH_CHECK_JOB: &str = "health_check";
+pub const STALE_TRANSACTION_CLEANUP_JOB: &str = "stale_transaction_cleanup";
+pub const BALANCE_SNAPSHOT_JOB: &str = "balance_snapshot";
+pub const PROVISIONING_REPAIR_JOB: &str = "provisioning_repair";
+pub const ACTIVITY_ROLLUP_JOB: &str = "activity_rollup";
+pub const CLUSTER_TIMING_JOB: &str = "cluster_timing";
+pub const TOKEN_AUTHORITY_SWEEP_JOB: &str = "token_authority_sweep";
+pub const OUTBOX_DELIVERY_JOB: &str = "outbox_delivery";
+pub const INDEXER_JOB: &str = "indexer";
+pub const ABUSE_ESCALATION_JOB: &str = "abuse_escalation";
+pub const SECURITY_BURST_JOB: &str = "security_burst_check";
+pub const SLA_CHECK_JOB: &str = "sla_check";
+pub const FEE_COLLECTION_JOB: &str = "fee_collection";
+
+/// Job type of the reconciliation pass for `mode`
+pub fn reconciliation_job(mode: ReconciliationMode) -> String {
+    format!("reconciliation_{}", mode.as_str())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobOutcome {
+    Succeeded,
+    Failed,
+    /// Deferred without doing any work; the loop is still alive
+    Skipped,
+}
+
+impl JobOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobOutcome::Succeeded => "succeeded",
+            JobOutcome::Failed => "failed",
+            JobOutcome::Skipped => "skipped",
+        }
+    }
+}
+
+/// A job the monitor runs on a fixed interval
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpectedJob {
+    pub job_type: String,
+    pub interval_seconds: u64,
+}
+
+impl ExpectedJob {
+    pub fn new(job_type: impl Into<String>, interval_seconds: u64) -> Self {
+        Self { job_type: job_type.into(), interval_seconds }
+    }
+
+    /// When the job counts as missed: `MISSED_RUN_FACTOR` intervals after its
+    /// last run, or after watching began if it has not run since
+    pub fn overdue_at(&self, last_started_at: Option<DateTime<Utc>>, watching_since: DateTime<Utc>) -> DateTime<Utc> {
+        let from = last_started_at.map_or(watching_since, |at| at.max(watching_since));
+        from + Duration::seconds(self.interval_seconds as i64 * MISSED_RUN_FACTOR)
+    }
+}
+
+/// Latest run of an expected job and whether it is overdue
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobStatus {
+    pub job_type: String,
+    pub interval_seconds: u64,
+    /// `None` if the job has never run
+    pub last_run: Option<MonitorJobRun>,
+    pub overdue_at: DateTime<Utc>,
+    pub missed: bool,
+}
+
+/// Status of every expected job given the latest run of each job type
+pub fn job_statuses(
+    expected: &[ExpectedJob],
+    latest: &[MonitorJobRun],
+    watching_since: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Vec<JobStatus> {
+    expected
+        .iter()
+        .map(|job| {
+            let last_run = latest.iter().find(|run| run.job_type == job.job_type).cloned();
+            let overdue_at = job.overdue_at(last_run.as_ref().map(|run| run.started_at), watching_since);
+            JobStatus {
+                job_type: job.job_type.clone(),
+                interval_seconds: job.interval_seconds,
+                last_run,
+                overdue_at,
+                missed: overdue_at <= now,
+            }
+        })
+        .collect()
+}
+
+/// Records monitor job runs and watches for jobs that stopped running
+pub struct MonitorJobs {
+    repo: MonitorJobRepository,
+    expected: Vec<ExpectedJob>,
+    watching_since: DateTime<Utc>,
+    /// Jobs already alerted as missed, cleared when they run again
+    alerted: Mutex<HashSet<String>>,
+}
+
+impl MonitorJobs {
+    pub fn new(pool: sqlx::PgPool, expected: Vec<ExpectedJob>) -> Self {
+        Self {
+            repo: MonitorJobRepository::new(pool),
+            expected,
+            watching_since: Utc::now(),
+            alerted: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Run one execution of a job and record its outcome; the job's result is
+    /// returned unchanged whether or not recording succeeds
+    pub async fn track<T, F>(&self, job_type: &str, run: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let started_at = Utc::now();
+        let timer = std::time::Instant::now();
+        let result = run.await;
+        let (outcome, error) = match &result {
+            Ok(_) => (JobOutcome::Succeeded, None),
+            Err(e) => (JobOutcome::Failed, Some(e.to_string())),
+        };
+        self.record(job_type, started_at, timer.elapsed(), outcome, error.as_deref()).await;
+        result
+    }
+
+    /// Record a run; failures to record are logged, never propagated to the job
+    pub async fn record(
+        &self,
+        job_type: &str,
+        started_at: DateTime<Utc>,
+        elapsed: std::time::Duration,
+        outcome: JobOutcome,
+        error: Option<&str>,
+    ) {
+        let duration_ms = elapsed.as_millis().min(i64::MAX as u128) as i64;
+        if let Err(e) = self.repo.record(job_type, started_at, duration_ms, outcome.as_str(), error).await {
+            warn!("Failed to record {} job run: {}", job_type, e);
+        }
+    }
+
+    /// Status of every expected job at `now`
+    pub async fn status(&self, now: DateTime<Utc>) -> Result<Vec<JobStatus>> {
+        let latest = self.repo.latest_runs().await?;
+        Ok(job_statuses(&self.expected, &latest, self.watching_since, now))
+    }
+
+    /// Recent runs, newest first, optionally of one job type
+    pub async fn recent_runs(&self, job_type: Option<&str>, limit: i64) -> Result<Vec<MonitorJobRun>> {
+        self.repo.recent(job_type, limit).await
+    }
+
+    /// Check for missed jobs every `interval_seconds`; runs past retention are pruned hourly
+    pub async fn run_watchdog(&self, interval_seconds: u64) {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_seconds));
+        let mut last_pruned: Option<DateTime<Utc>> = None;
+        loop {
+            interval.tick().await;
+
+            let now = Utc::now();
+            if let Err(e) = self.check(now).await {
+                error!("Monitor job watchdog failed: {}", e);
+            }
+
+            if last_pruned.map_or(true, |at| now - at >= Duration::hours(1)) {
+                match self.repo.prune(now - Duration::days(RUN_RETENTION_DAYS)).await {
+                    Ok(pruned) if pruned > 0 => info!("Pruned {} monitor job runs", pruned),
+                    Ok(_) => {}
+                    Err(e) => error!("Monitor job run pruning failed: {}", e),
+                }
+                last_pruned = Some(now);
+            }
+        }
+    }
+
+    /// Alert on jobs newly missed at `now`; returns how many were alerted
+    pub async fn check(&self, now: DateTime<Utc>) -> Result<usize> {
+        let statuses = self.status(now).await?;
+        let mut newly_missed = Vec::new();
+        {
+            let mut alerted = self.alerted.lock().unwrap_or_else(|e| e.into_inner());
+            for status in &statuses {
+                if status.missed {
+                    if alerted.insert(status.job_type.clone()) {
+                        newly_missed.push(status);
+                    }
+                } else if alerted.remove(&status.job_type) {
+                    info!("Monitor job {} is running again", status.job_type);
+                }
+            }
+        }
+
+        for status in &newly_missed {
+            let last_started_at = status.last_run.as_ref().map(|run| run.started_at);
+            error!("Monitor job {} has not run since {} (interval {}s)",
+                   status.job_type,
+                   last_started_at.map_or_else(|| "startup".to_string(), |at| at.to_rfc3339()),
+                   status.interval_seconds);
+            if let Err(e) = self.repo.record_missed(&status.job_type, last_started_at, status.interval_seconds as i64,
+                                                   MONITOR_JOB_MISSED_EVENT).await {
+                warn!("Failed to audit missed {} job: {}", status.job_type, e);
+            }
+        }
+
+        Ok(newly_missed.len())
+    }
+}
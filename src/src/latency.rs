@@ -0,0 +1,177 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+/// Steps a vault operation passes through between the API call and the final DB update
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineStage {
+    Validation,
+    DbWrite,
+    /// Includes signing when the builder signs the transaction itself
+    Build,
+    /// Only recorded when signing is a separate step from building
+    Sign,
+    Submit,
+    Confirm,
+    PostUpdate,
+}
+
+impl PipelineStage {
+    pub const ALL: [PipelineStage; 7] = [
+        PipelineStage::Validation,
+        PipelineStage::DbWrite,
+        PipelineStage::Build,
+        PipelineStage::Sign,
+        PipelineStage::Submit,
+        PipelineStage::Confirm,
+        PipelineStage::PostUpdate,
+    ];
+}
+
+/// Milliseconds spent per stage for a single operation; repeated stages accumulate
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StageTimings {
+    stages: BTreeMap<PipelineStage, u64>,
+}
+
+impl StageTimings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `elapsed` to a stage
+    pub fn record(&mut self, stage: PipelineStage, elapsed: Duration) {
+        *self.stages.entry(stage).or_insert(0) += elapsed.as_millis() as u64;
+    }
+
+    /// Run `fut` and charge its wall-clock time to `stage`
+    pub async fn time<F: Future>(&mut self, stage: PipelineStage, fut: F) -> F::Output {
+        let started = Instant::now();
+        let output = fut.await;
+        self.record(stage, started.elapsed());
+        output
+    }
+
+    pub fn get(&self, stage: PipelineStage) -> Option<u64> {
+        self.stages.get(&stage).copied()
+    }
+
+    pub fn total_ms(&self) -> u64 {
+        self.stages.values().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// JSON object keyed by stage name, as stored on transaction records
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(&self.stages).unwrap_or(serde_json::Value::Null)
+    }
+}
+
+/// Upper bounds (ms) of the histogram buckets; the last bucket is unbounded
+const BUCKET_BOUNDS_MS: [u64; 15] = [1, 2, 5, 10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 30_000, 60_000];
+
+#[derive(Debug, Clone)]
+struct Histogram {
+    counts: [u64; BUCKET_BOUNDS_MS.len() + 1],
+    count: u64,
+    sum_ms: u64,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Self { counts: [0; BUCKET_BOUNDS_MS.len() + 1], count: 0, sum_ms: 0 }
+    }
+
+    fn observe(&mut self, value_ms: u64) {
+        let bucket = BUCKET_BOUNDS_MS.iter().position(|&bound| value_ms <= bound).unwrap_or(BUCKET_BOUNDS_MS.len());
+        self.counts[bucket] += 1;
+        self.count += 1;
+        self.sum_ms += value_ms;
+    }
+
+    /// Upper bound of the bucket holding the `q` quantile (None past the last bound)
+    fn quantile(&self, q: f64) -> Option<u64> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((self.count as f64) * q).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (i, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return BUCKET_BOUNDS_MS.get(i).copied();
+            }
+        }
+        None
+    }
+}
+
+/// Latency distribution of one stage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageLatencySummary {
+    pub stage: PipelineStage,
+    pub count: u64,
+    pub mean_ms: f64,
+    pub p50_ms: Option<u64>,
+    pub p95_ms: Option<u64>,
+    pub p99_ms: Option<u64>,
+    /// (upper bound in ms, cumulative count); `None` bound is +Inf
+    pub buckets: Vec<(Option<u64>, u64)>,
+}
+
+/// Process-wide per-stage latency histograms
+pub struct LatencyHistograms {
+    stages: RwLock<HashMap<PipelineStage, Histogram>>,
+}
+
+impl LatencyHistograms {
+    pub fn new() -> Self {
+        Self { stages: RwLock::new(HashMap::new()) }
+    }
+
+    /// Add every stage of a finished operation
+    pub fn observe(&self, timings: &StageTimings) {
+        let mut stages = self.stages.write().unwrap();
+        for (stage, ms) in &timings.stages {
+            stages.entry(*stage).or_insert_with(Histogram::new).observe(*ms);
+        }
+    }
+
+    /// Summary for every stage, in pipeline order
+    pub fn snapshot(&self) -> Vec<StageLatencySummary> {
+        let stages = self.stages.read().unwrap();
+        PipelineStage::ALL
+            .iter()
+            .map(|stage| {
+                let histogram = stages.get(stage).cloned().unwrap_or_else(Histogram::new);
+                let mut cumulative = 0;
+                let buckets = histogram.counts.iter().enumerate().map(|(i, count)| {
+                    cumulative += count;
+                    (BUCKET_BOUNDS_MS.get(i).copied(), cumulative)
+                }).collect();
+
+                StageLatencySummary {
+                    stage: *stage,
+                    count: histogram.count,
+                    mean_ms: if histogram.count == 0 { 0.0 } else { histogram.sum_ms as f64 / histogram.count as f64 },
+                    p50_ms: histogram.quantile(0.50),
+                    p95_ms: histogram.quantile(0.95),
+                    p99_ms: histogram.quantile(0.99),
+                    buckets,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for LatencyHistograms {
+    fn default() -> Self {
+        Self::new()
+    }
+}
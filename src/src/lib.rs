@@ -6,12 +6,76 @@ pub mod transaction_builder;
 pub mod cpi_manager;
 pub mod vault_monitor;
 pub mod database;
+pub mod mint_registry;
+pub mod derivation;
+pub mod latency;
+pub mod reconciliation;
+pub mod schema;
+pub mod support;
+pub mod cases;
+pub mod multisig;
+pub mod provisioning;
+pub mod authority;
+pub mod analytics;
+pub mod stream;
+pub mod rpc;
+pub mod cluster;
+pub mod maintenance;
+pub mod bulk;
+pub mod token_authority;
+pub mod program_info;
+pub mod screening;
+pub mod outbox;
+pub mod auto_lock;
+pub mod deposits;
+pub mod indexer;
+pub mod jobs;
+pub mod engine_api;
+pub mod abuse;
+pub mod positions;
+pub mod identities;
+pub mod alerts;
+pub mod exports;
+pub mod idempotency;
+pub mod dormancy;
+pub mod token_accounts;
+pub mod security_events;
+pub mod fees;
+pub mod sdk;
+pub mod tax;
+pub mod twab;
+pub mod simulation;
+pub mod sla;
+pub mod etag;
+pub mod incident;
+pub mod network_fees;
+pub mod onchain_activity;
+pub mod profiles;
+pub mod journal;
+pub mod api;
 
-pub use error::{VaultError, Result};
+pub use error::{VaultError, DomainError, StorageError, ChainError, Result};
 pub use models::*;
 pub use vault_manager::{VaultManager, TransactionManager};
 pub use balance_tracker::BalanceTracker;
 pub use transaction_builder::{TransactionBuilder, TransactionSubmitter, BuiltTransaction};
 pub use cpi_manager::CPIManager;
+pub use mint_registry::MintRegistry;
+pub use schema::SchemaManager;
+pub use support::SupportService;
+pub use cases::CaseService;
+pub use reconciliation::Reconciler;
+pub use multisig::MultisigManager;
+pub use provisioning::VaultProvisioner;
+pub use authority::{AuthorityKeyRing, AuthorityRotationManager};
+pub use analytics::ActivityAnalytics;
+pub use stream::EventStream;
+pub use cluster::{ClusterTiming, ClusterTimingConfig};
+pub use maintenance::MaintenanceService;
+pub use bulk::BulkOperationManager;
+pub use token_authority::TokenAuthorityGuard;
+pub use screening::ScreeningService;
 pub use vault_monitor::{VaultMonitor, MonitorConfig, MonitoringStats};
-pub use database::{VaultRepository, TransactionRepository, SnapshotRepository, AuditRepository, RateLimitRepository};
\ No newline at end of file
+pub use database::{VaultRepository, TransactionRepository, SnapshotRepository, AuditRepository, RateLimitRepository, MintRepository, SchemaRepository, SupportRepository,
+    ReconciliationRepository, MultisigProposalRepository, ProvisioningRepository, AuthorityRotationRepository, ActivityRepository, CaseRepository, MaintenanceRepository, HoldRepository,
+    BulkJobRepository, TokenAuthorityRepository, ScreeningRepository};
\ No newline at end of file
@@ -0,0 +1,234 @@
+//! Scheduled maintenance windows.
+//!
+//! Admins announce windows ahead of time through `/system/maintenance`.
+//! Upcoming and active windows are reported by `/health`, in the
+//! `X-Maintenance-Start` / `X-Maintenance-Until` response headers and on the
+//! WebSocket `system` channel (`scheduled`, `started`, `ended`, `cancelled`).
+//!
+//! While a read-only window is active the API rejects writes with 503. The
+//! switch needs no job to flip it: read-only state is derived from the clock
+//! and the cached windows, so it starts and ends exactly on schedule. The
+//! scheduler only announces transitions and refreshes the cache, which picks
+//! up windows created through other instances.
+
+use crate::error::{Result, DomainError};
+use crate::models::MaintenanceWindow;
+use crate::database::MaintenanceRepository;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+use tokio::sync::{broadcast, Notify};
+use tracing::{info, warn, error};
+use uuid::Uuid;
+
+pub const MAINTENANCE_SCHEDULED_EVENT: &str = "maintenance_scheduled";
+pub const MAINTENANCE_CANCELLED_EVENT: &str = "maintenance_cancelled";
+
+/// Longest window that may be announced
+pub const MAX_WINDOW_HOURS: i64 = 24;
+
+const MAX_REASON_LENGTH: usize = 500;
+
+/// How often windows are reloaded when no transition is due sooner
+const REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Buffered notices per subscriber before it is considered lagged
+const NOTICE_CHANNEL_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenancePhase {
+    Scheduled,
+    Started,
+    Ended,
+    Cancelled,
+}
+
+/// A change to a window, published on the `system` channel
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaintenanceNotice {
+    pub phase: MaintenancePhase,
+    pub window: MaintenanceWindow,
+}
+
+/// Maintenance as seen at one instant
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MaintenanceStatus {
+    /// Active window ending last, when any is active
+    pub active: Option<MaintenanceWindow>,
+    pub upcoming: Vec<MaintenanceWindow>,
+    /// An active window rejects writes
+    pub read_only: bool,
+}
+
+impl MaintenanceStatus {
+    pub fn at(windows: &[MaintenanceWindow], now: DateTime<Utc>) -> Self {
+        let active: Vec<&MaintenanceWindow> = windows.iter().filter(|w| w.is_active(now)).collect();
+        let mut upcoming: Vec<MaintenanceWindow> = windows.iter().filter(|w| w.is_upcoming(now)).cloned().collect();
+        upcoming.sort_by_key(|w| w.starts_at);
+
+        Self {
+            read_only: active.iter().any(|w| w.read_only),
+            active: active.into_iter().max_by_key(|w| w.ends_at).cloned(),
+            upcoming,
+        }
+    }
+}
+
+/// Windows that started or ended in `(since, now]`, in time order
+pub fn transitions(windows: &[MaintenanceWindow], since: DateTime<Utc>, now: DateTime<Utc>) -> Vec<MaintenanceNotice> {
+    let mut notices: Vec<(DateTime<Utc>, MaintenanceNotice)> = Vec::new();
+    for window in windows.iter().filter(|w| w.cancelled_at.is_none()) {
+        if since < window.starts_at && window.starts_at <= now {
+            notices.push((window.starts_at, MaintenanceNotice { phase: MaintenancePhase::Started, window: window.clone() }));
+        }
+        if since < window.ends_at && window.ends_at <= now {
+            notices.push((window.ends_at, MaintenanceNotice { phase: MaintenancePhase::Ended, window: window.clone() }));
+        }
+    }
+    notices.sort_by_key(|(at, _)| *at);
+    notices.into_iter().map(|(_, notice)| notice).collect()
+}
+
+/// Next instant after `now` at which a window starts or ends
+pub fn next_transition(windows: &[MaintenanceWindow], now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    windows
+        .iter()
+        .filter(|w| w.cancelled_at.is_none())
+        .flat_map(|w| [w.starts_at, w.ends_at])
+        .filter(|at| *at > now)
+        .min()
+}
+
+/// Check an announcement before it is stored
+pub fn validate_window(starts_at: DateTime<Utc>, ends_at: DateTime<Utc>, reason: &str, now: DateTime<Utc>) -> Result<()> {
+    if ends_at <= starts_at {
+        return Err(DomainError::Validation("Maintenance must end after it starts".to_string()).into());
+    }
+    if ends_at <= now {
+        return Err(DomainError::Validation("Maintenance window is already over".to_string()).into());
+    }
+    if ends_at - starts_at > Duration::hours(MAX_WINDOW_HOURS) {
+        return Err(DomainError::Validation(format!("Maintenance windows are limited to {} hours", MAX_WINDOW_HOURS)).into());
+    }
+    let reason = reason.trim();
+    if reason.is_empty() || reason.chars().count() > MAX_REASON_LENGTH {
+        return Err(DomainError::Validation(format!("Reason must be 1-{} characters", MAX_REASON_LENGTH)).into());
+    }
+    Ok(())
+}
+
+/// Announced maintenance windows and the read-only state they imply
+pub struct MaintenanceService {
+    repo: MaintenanceRepository,
+    /// Windows not cancelled that had not ended at the last refresh
+    windows: RwLock<Vec<MaintenanceWindow>>,
+    changed: Notify,
+    notices: broadcast::Sender<MaintenanceNotice>,
+}
+
+impl MaintenanceService {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self {
+            repo: MaintenanceRepository::new(pool),
+            windows: RwLock::new(Vec::new()),
+            changed: Notify::new(),
+            notices: broadcast::channel(NOTICE_CHANNEL_CAPACITY).0,
+        }
+    }
+
+    /// Receive every announcement, start, end and cancellation
+    pub fn subscribe_notices(&self) -> broadcast::Receiver<MaintenanceNotice> {
+        self.notices.subscribe()
+    }
+
+    pub fn status(&self, now: DateTime<Utc>) -> MaintenanceStatus {
+        MaintenanceStatus::at(&self.windows.read().unwrap(), now)
+    }
+
+    /// Whether a window is in effect at `now`, as of the last refresh
+    pub fn is_window_active(&self, window_id: Uuid, now: DateTime<Utc>) -> bool {
+        self.windows.read().unwrap().iter().any(|w| w.id == window_id && w.is_active(now))
+    }
+
+    /// Reload windows from the database
+    pub async fn refresh(&self) -> Result<()> {
+        let windows = self.repo.get_pending_windows(Utc::now()).await?;
+        *self.windows.write().unwrap() = windows;
+        Ok(())
+    }
+
+    pub async fn schedule(
+        &self,
+        starts_at: DateTime<Utc>,
+        ends_at: DateTime<Utc>,
+        reason: &str,
+        read_only: bool,
+    ) -> Result<MaintenanceWindow> {
+        validate_window(starts_at, ends_at, reason, Utc::now())?;
+
+        let window_id = Uuid::new_v4();
+        let details = serde_json::json!({
+            "window_id": window_id,
+            "starts_at": starts_at,
+            "ends_at": ends_at,
+            "read_only": read_only,
+        });
+        let window = self.repo
+            .create_window(window_id, starts_at, ends_at, reason.trim(), read_only, MAINTENANCE_SCHEDULED_EVENT, details)
+            .await?;
+        info!("Scheduled maintenance {} from {} to {} (read-only: {}): {}",
+              window.id, window.starts_at, window.ends_at, window.read_only, window.reason);
+
+        self.windows.write().unwrap().push(window.clone());
+        self.changed.notify_one();
+        let _ = self.notices.send(MaintenanceNotice { phase: MaintenancePhase::Scheduled, window: window.clone() });
+        Ok(window)
+    }
+
+    /// Cancel a window that has not ended; an active window ends immediately
+    pub async fn cancel(&self, window_id: Uuid) -> Result<MaintenanceWindow> {
+        let details = serde_json::json!({ "window_id": window_id });
+        let window = self.repo
+            .cancel_window(window_id, MAINTENANCE_CANCELLED_EVENT, details)
+            .await?
+            .ok_or_else(|| DomainError::NotFound(format!("Maintenance window {} (or it has ended)", window_id)))?;
+        info!("Cancelled maintenance {}", window.id);
+
+        self.windows.write().unwrap().retain(|w| w.id != window_id);
+        self.changed.notify_one();
+        let _ = self.notices.send(MaintenanceNotice { phase: MaintenancePhase::Cancelled, window: window.clone() });
+        Ok(window)
+    }
+
+    /// Announce windows as they start and end, reloading the cache after each wake-up
+    pub async fn run_scheduler(&self) {
+        let mut last_check = Utc::now();
+        loop {
+            let wait = next_transition(&self.windows.read().unwrap(), last_check)
+                .and_then(|at| (at - last_check).to_std().ok())
+                .map_or(REFRESH_INTERVAL, |wait| wait.min(REFRESH_INTERVAL));
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = self.changed.notified() => {}
+            }
+
+            let now = Utc::now();
+            let notices = transitions(&self.windows.read().unwrap(), last_check, now);
+            for notice in notices {
+                match (notice.phase, notice.window.read_only) {
+                    (MaintenancePhase::Started, true) => warn!("Maintenance {} started; API is read-only until {}",
+                                                               notice.window.id, notice.window.ends_at),
+                    (MaintenancePhase::Started, false) => info!("Maintenance {} started", notice.window.id),
+                    _ => info!("Maintenance {} ended", notice.window.id),
+                }
+                let _ = self.notices.send(notice);
+            }
+            last_check = now;
+
+            if let Err(e) = self.refresh().await {
+                error!("Failed to refresh maintenance windows: {}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use crate::error::{Result, DomainError};
+use crate::models::MintConfig;
+use crate::database::MintRepository;
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use std::sync::Arc;
+use tracing::info;
+
+/// Per-mint configuration lookup shared by validation, formatting and transaction building
+pub struct MintRegistry {
+    mint_repo: MintRepository,
+    default_mint: String,
+    cache: Arc<RwLock<HashMap<String, MintConfig>>>,
+}
+
+impl MintRegistry {
+    pub fn new(pool: sqlx::PgPool, default_mint: String) -> Self {
+        Self {
+            mint_repo: MintRepository::new(pool),
+            default_mint,
+            cache: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Mint assumed for vaults that predate per-mint tracking
+    pub fn default_mint(&self) -> &str {
+        &self.default_mint
+    }
+
+    /// Get configuration for a mint (from cache or database)
+    pub async fn get_mint(&self, mint_pubkey: &str) -> Result<MintConfig> {
+        {
+            let cache = self.cache.read().await;
+            if let Some(mint) = cache.get(mint_pubkey) {
+                return Ok(mint.clone());
+            }
+        }
+
+        let mint = self.mint_repo.get_mint(mint_pubkey).await?;
+        self.cache.write().await.insert(mint_pubkey.to_string(), mint.clone());
+
+        Ok(mint)
+    }
+
+    /// Get configuration for a mint, falling back to the default mint
+    pub async fn resolve(&self, mint_pubkey: Option<&str>) -> Result<MintConfig> {
+        self.get_mint(mint_pubkey.unwrap_or(&self.default_mint)).await
+    }
+
+    /// List all configured mints
+    pub async fn list_mints(&self) -> Result<Vec<MintConfig>> {
+        self.mint_repo.list_mints().await
+    }
+
+    /// Insert or update a mint and drop its cached entry
+    pub async fn upsert_mint(&self, mint: &MintConfig) -> Result<MintConfig> {
+        mint.validate_pubkey().map_err(DomainError::Validation)?;
+        if !(0..=18).contains(&mint.decimals) {
+            return Err(DomainError::Validation(format!("Invalid decimals: {}", mint.decimals)).into());
+        }
+        if mint.min_deposit < 0 || mint.withdrawal_fee < 0 {
+            return Err(DomainError::Validation("Mint amounts must not be negative".to_string()).into());
+        }
+
+        let updated = self.mint_repo.upsert_mint(
+            &mint.mint_pubkey,
+            &mint.symbol,
+            mint.decimals,
+            mint.enabled,
+            mint.min_deposit,
+            mint.withdrawal_fee,
+            mint.oracle_feed_id.as_deref(),
+        ).await?;
+
+        self.cache.write().await.remove(&mint.mint_pubkey);
+        info!("Mint configuration updated: {} ({})", updated.symbol, updated.mint_pubkey);
+
+        Ok(updated)
+    }
+}
+
+/// Reject deposits into a disabled mint or below its minimum
+pub fn validate_deposit(mint: &MintConfig, amount: u64) -> Result<()> {
+    if !mint.enabled {
+        return Err(DomainError::Validation(format!("Mint {} is disabled", mint.symbol)).into());
+    }
+    if amount == 0 || amount < mint.min_deposit as u64 {
+        return Err(DomainError::Validation(format!(
+            "Deposit must be at least {}",
+            mint.format_amount(mint.min_deposit.max(1))
+        )).into());
+    }
+
+    Ok(())
+}
+
+/// Check a withdrawal covers the mint's fee; returns the amount the user receives
+pub fn validate_withdrawal(mint: &MintConfig, amount: u64) -> Result<u64> {
+    let fee = mint.withdrawal_fee as u64;
+    if amount == 0 || amount <= fee {
+        return Err(DomainError::Validation(format!(
+            "Withdrawal must exceed the fee of {}",
+            mint.format_amount(mint.withdrawal_fee)
+        )).into());
+    }
+
+    Ok(amount - fee)
+}
@@ -11,7 +11,7 @@ pub struct Vault {
     pub vault_pubkey: String,
     pub token_account_pubkey: String,
     pub bump: i32,
-    pub total_balance: i64,      // Stored as micro-USDT to avoid floating point
+    pub total_balance: i64,      // Stored in mint base units to avoid floating point
     pub locked_balance: i64,
     pub available_balance: i64,
     pub last_updated: DateTime<Utc>,
@@ -30,14 +30,14 @@ pub struct VaultCreateRequest {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VaultDepositRequest {
     pub user_pubkey: String,
-    pub amount: u64, // In USDT with 6 decimals
+    pub amount: u64, // In base units of the vault's mint
     pub user_token_account: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VaultWithdrawRequest {
     pub user_pubkey: String,
-    pub amount: u64, // In USDT with 6 decimals
+    pub amount: u64, // In base units of the vault's mint
     pub user_token_account: String,
 }
 
@@ -77,6 +77,39 @@ pub struct VaultResponse {
     pub authority: String,
 }
 
+/// Optional criteria for filtered vault listing; unset fields match everything
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VaultListFilter {
+    pub is_active: Option<bool>,
+    pub min_total_balance: Option<i64>,
+    pub max_total_balance: Option<i64>,
+    pub min_locked_balance: Option<i64>,
+    pub max_locked_balance: Option<i64>,
+    pub min_lock_utilization_bps: Option<i64>, // locked / total, in basis points
+    pub max_lock_utilization_bps: Option<i64>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub last_activity_after: Option<DateTime<Utc>>,
+    pub last_activity_before: Option<DateTime<Utc>>,
+    pub mint: Option<String>,
+    pub tag: Option<String>,
+    pub has_open_case: Option<bool>,
+}
+
+/// Per-mint settings; amounts are in the mint's base units
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct MintConfig {
+    pub mint_pubkey: String,
+    pub symbol: String,
+    pub decimals: i16,
+    pub enabled: bool,
+    pub min_deposit: i64,
+    pub withdrawal_fee: i64,
+    pub oracle_feed_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransactionRecord {
     pub id: Uuid,
@@ -90,6 +123,157 @@ pub struct TransactionRecord {
     pub updated_at: DateTime<Utc>,
 }
 
+/// Where a withdrawal sits in the processing queue and when it should confirm
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WithdrawalQueueStatus {
+    pub transaction_id: Uuid,
+    pub vault_id: Uuid,
+    pub amount: i64,
+    pub status: TransactionStatus,
+    pub queue_position: Option<i64>, // None once the withdrawal has left the queue
+    pub estimated_confirmation_at: Option<DateTime<Utc>>,
+    pub signature: Option<String>,
+    pub error_message: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl WithdrawalQueueStatus {
+    /// Confirmed and failed withdrawals never change status again
+    pub fn is_terminal(&self) -> bool {
+        matches!(self.status, TransactionStatus::Confirmed | TransactionStatus::Failed | TransactionStatus::Reverted)
+    }
+}
+
+/// Per-vault caps on work queued but not yet confirmed or failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingQuota {
+    /// Pending or processing records of any type
+    pub max_pending_operations: i64,
+    /// Sum of pending or processing withdrawals, in base units
+    pub max_pending_withdrawal_amount: i64,
+}
+
+impl Default for PendingQuota {
+    fn default() -> Self {
+        Self {
+            max_pending_operations: 20,
+            max_pending_withdrawal_amount: 1_000_000_000_000, // 1M at 6 decimals
+        }
+    }
+}
+
+impl PendingQuota {
+    /// Why queueing one more operation (withdrawing `withdrawal_amount`) would break the quota
+    pub fn violation(&self, usage: &PendingUsage, withdrawal_amount: i64) -> Option<String> {
+        if usage.pending_operations >= self.max_pending_operations {
+            return Some(format!("{} operations already pending (limit {})",
+                                usage.pending_operations, self.max_pending_operations));
+        }
+        let pending_withdrawals = usage.pending_withdrawal_amount.saturating_add(withdrawal_amount.max(0));
+        if withdrawal_amount > 0 && pending_withdrawals > self.max_pending_withdrawal_amount {
+            return Some(format!("pending withdrawals would total {} (limit {})",
+                                pending_withdrawals, self.max_pending_withdrawal_amount));
+        }
+        None
+    }
+}
+
+/// Work currently queued for a vault
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingUsage {
+    pub pending_operations: i64,
+    pub pending_withdrawal_amount: i64,
+}
+
+/// Which balance a hold reserves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HoldBalance {
+    Available,
+    Locked,
+}
+
+impl HoldBalance {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HoldBalance::Available => "available",
+            HoldBalance::Locked => "locked",
+        }
+    }
+
+    /// Hold an operation places while it is unsettled: withdrawals and locks reserve
+    /// available balance, unlocks and outgoing transfers reserve locked balance
+    pub fn for_operation(tx_type: &TransactionType, amount: i64) -> Option<(HoldBalance, i64)> {
+        match tx_type {
+            TransactionType::Withdraw | TransactionType::Lock if amount > 0 => Some((HoldBalance::Available, amount)),
+            TransactionType::Unlock if amount > 0 => Some((HoldBalance::Locked, amount)),
+            TransactionType::Transfer if amount < 0 => Some((HoldBalance::Locked, -amount)),
+            _ => None,
+        }
+    }
+}
+
+/// Balance reserved for an accepted operation until it settles on-chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalanceHold {
+    pub id: Uuid,
+    pub vault_id: Uuid,
+    pub transaction_id: Uuid,
+    pub balance: String,
+    pub amount: i64,
+    /// `active`, `released` (operation failed) or `converted` (balance change applied)
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub settled_at: Option<DateTime<Utc>>,
+}
+
+/// Sum of a vault's active holds per balance
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeldAmounts {
+    pub available: i64,
+    pub locked: i64,
+}
+
+/// Stored balances net of active holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpendableBalance {
+    pub available_balance: i64,
+    pub locked_balance: i64,
+    pub held: HeldAmounts,
+}
+
+impl SpendableBalance {
+    pub fn new(available_balance: i64, locked_balance: i64, held: HeldAmounts) -> Self {
+        Self { available_balance, locked_balance, held }
+    }
+
+    pub fn spendable_available(&self) -> i64 {
+        (self.available_balance - self.held.available).max(0)
+    }
+
+    pub fn spendable_locked(&self) -> i64 {
+        (self.locked_balance - self.held.locked).max(0)
+    }
+
+    pub fn spendable(&self, balance: HoldBalance) -> i64 {
+        match balance {
+            HoldBalance::Available => self.spendable_available(),
+            HoldBalance::Locked => self.spendable_locked(),
+        }
+    }
+}
+
+/// Result of trying to queue an operation for a vault
+#[derive(Debug, Clone)]
+pub enum QueueOutcome {
+    Queued(TransactionRecord),
+    /// The vault already has as much pending work as its quota allows
+    QuotaExceeded(PendingUsage),
+    /// Active holds leave too little of the balance the operation draws on
+    InsufficientSpendable { balance: HoldBalance, spendable: i64 },
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "transaction_type", rename_all = "snake_case")]
 pub enum TransactionType {
@@ -111,6 +295,129 @@ pub enum TransactionStatus {
     Reverted,
 }
 
+/// Where a pushed balance change was observed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BalanceUpdateSource {
+    Database,
+    Chain,
+}
+
+/// Balance change pushed to caches as soon as it is known
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalanceUpdate {
+    pub vault_id: Uuid,
+    pub total_balance: i64,
+    pub locked_balance: i64,
+    pub available_balance: i64,
+    pub as_of: DateTime<Utc>,
+    pub source: BalanceUpdateSource,
+}
+
+impl BalanceUpdate {
+    pub fn from_vault(vault: &Vault, source: BalanceUpdateSource) -> Self {
+        Self {
+            vault_id: vault.id,
+            total_balance: vault.total_balance,
+            locked_balance: vault.locked_balance,
+            available_balance: vault.available_balance,
+            as_of: vault.updated_at,
+            source,
+        }
+    }
+}
+
+/// How fresh a balance read must be
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadConsistency {
+    /// Cached values are acceptable; stale entries are revalidated in the background
+    #[default]
+    Eventual,
+    /// Always read the database
+    Strong,
+}
+
+/// Where a served balance came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BalanceReadSource {
+    Cache,
+    Database,
+}
+
+/// Balance served to a reader, with enough metadata to judge its freshness
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalanceRead {
+    pub vault_id: Uuid,
+    pub total_balance: i64,
+    pub locked_balance: i64,
+    pub available_balance: i64,
+    /// When the values were last known to be current
+    pub as_of: DateTime<Utc>,
+    pub source: BalanceReadSource,
+    /// True when a cached entry past its freshness window was served
+    pub stale: bool,
+}
+
+/// What a conditional read needs to know about a vault
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VaultVersion {
+    pub vault_id: Uuid,
+    /// Bumped whenever the vault, its holds or its pending deposits change
+    pub version: i64,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Persisted latency percentiles for one pipeline stage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageLatencyRow {
+    pub stage: String,
+    pub count: i64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+}
+
+/// Confirmed operations of one class within a window, and how many met a target latency
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SlaLatencyCounts {
+    pub operations: i64,
+    pub within_target: i64,
+    pub p95_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+}
+
+/// Realized fees of the transactions of one operation class submitted on one day
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NetworkFeeDayRow {
+    pub day: DateTime<Utc>,
+    pub operation_type: String,
+    pub transactions: i64,
+    pub fee_lamports: i64,
+    pub priority_fee_lamports: i64,
+    /// Sends beyond the first
+    pub retries: i64,
+    pub compute_units_consumed: i64,
+}
+
+/// Row of sqlx's `_sqlx_migrations` bookkeeping table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub description: String,
+    pub installed_on: DateTime<Utc>,
+    pub success: bool,
+    #[serde(skip)]
+    pub checksum: Vec<u8>,
+}
+
+/// A column present in the live database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaColumn {
+    pub table_name: String,
+    pub column_name: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BalanceSnapshot {
     pub id: Uuid,
@@ -122,6 +429,648 @@ pub struct BalanceSnapshot {
     pub block_height: Option<i64>,
 }
 
+/// How far a reconciliation looks beyond the database
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReconciliationMode {
+    /// Database vs balance cache, plus the balance invariant
+    #[default]
+    Quick,
+    /// Quick, plus the on-chain vault account
+    Standard,
+    /// Standard, plus the token account, ledger replay, balance trajectory and event gap check
+    Deep,
+    /// Database only: the vault row and its snapshot history against a replay of the ledger
+    Ledger,
+}
+
+impl ReconciliationMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReconciliationMode::Quick => "quick",
+            ReconciliationMode::Standard => "standard",
+            ReconciliationMode::Deep => "deep",
+            ReconciliationMode::Ledger => "ledger",
+        }
+    }
+}
+
+/// Persisted reconciliation outcome
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconciliationRecord {
+    pub id: Uuid,
+    pub vault_id: Uuid,
+    pub mode: String,
+    pub is_consistent: bool,
+    pub findings: serde_json::Value,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+}
+
+/// Lifecycle of a multisig withdrawal proposal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MultisigProposalStatus {
+    /// Built and handed to a member; not yet on chain
+    AwaitingSignature,
+    Draft,
+    Active,
+    Approved,
+    Executing,
+    Executed,
+    Rejected,
+    Cancelled,
+    /// Never submitted within the tracking window
+    Expired,
+}
+
+impl MultisigProposalStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MultisigProposalStatus::AwaitingSignature => "awaiting_signature",
+            MultisigProposalStatus::Draft => "draft",
+            MultisigProposalStatus::Active => "active",
+            MultisigProposalStatus::Approved => "approved",
+            MultisigProposalStatus::Executing => "executing",
+            MultisigProposalStatus::Executed => "executed",
+            MultisigProposalStatus::Rejected => "rejected",
+            MultisigProposalStatus::Cancelled => "cancelled",
+            MultisigProposalStatus::Expired => "expired",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Some(match value {
+            "awaiting_signature" => MultisigProposalStatus::AwaitingSignature,
+            "draft" => MultisigProposalStatus::Draft,
+            "active" => MultisigProposalStatus::Active,
+            "approved" => MultisigProposalStatus::Approved,
+            "executing" => MultisigProposalStatus::Executing,
+            "executed" => MultisigProposalStatus::Executed,
+            "rejected" => MultisigProposalStatus::Rejected,
+            "cancelled" => MultisigProposalStatus::Cancelled,
+            "expired" => MultisigProposalStatus::Expired,
+            _ => return None,
+        })
+    }
+
+    /// No further status changes will be tracked
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            MultisigProposalStatus::Executed
+                | MultisigProposalStatus::Rejected
+                | MultisigProposalStatus::Cancelled
+                | MultisigProposalStatus::Expired
+        )
+    }
+}
+
+/// Tracked multisig withdrawal proposal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultisigProposal {
+    pub id: Uuid,
+    pub transaction_id: Uuid,
+    pub vault_id: Uuid,
+    pub multisig_pubkey: String,
+    pub squads_vault_pubkey: String,
+    pub transaction_index: i64,
+    pub proposal_pubkey: String,
+    pub amount: i64,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Steps of the vault creation saga; each is persisted so provisioning resumes after a crash
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VaultProvisioningState {
+    /// Row written, init transaction not yet sent
+    Reserved,
+    /// Init transaction sent; signature recorded
+    Submitted,
+    /// Vault account exists on chain
+    Confirmed,
+    /// Vault row activated; provisioning complete
+    Active,
+    /// Gave up after repeated init failures
+    Failed,
+}
+
+impl VaultProvisioningState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VaultProvisioningState::Reserved => "reserved",
+            VaultProvisioningState::Submitted => "submitted",
+            VaultProvisioningState::Confirmed => "confirmed",
+            VaultProvisioningState::Active => "active",
+            VaultProvisioningState::Failed => "failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Some(match value {
+            "reserved" => VaultProvisioningState::Reserved,
+            "submitted" => VaultProvisioningState::Submitted,
+            "confirmed" => VaultProvisioningState::Confirmed,
+            "active" => VaultProvisioningState::Active,
+            "failed" => VaultProvisioningState::Failed,
+            _ => return None,
+        })
+    }
+
+    /// Provisioning will not advance further on its own
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, VaultProvisioningState::Active | VaultProvisioningState::Failed)
+    }
+}
+
+/// Provisioning progress for one vault
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultProvisioning {
+    pub vault_id: Uuid,
+    pub user_pubkey: String,
+    pub authority_pubkey: String,
+    pub mint_pubkey: String,
+    pub state: String,
+    pub init_signature: Option<String>,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Lifecycle of an authority key rotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthorityRotationStatus {
+    /// New key loaded and vaults enrolled; nothing rotated yet
+    Staged,
+    InProgress,
+    /// Every vault moved to the new key, which is now the default
+    Completed,
+    RollingBack,
+    /// Every rotated vault moved back to the old key
+    RolledBack,
+}
+
+impl AuthorityRotationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthorityRotationStatus::Staged => "staged",
+            AuthorityRotationStatus::InProgress => "in_progress",
+            AuthorityRotationStatus::Completed => "completed",
+            AuthorityRotationStatus::RollingBack => "rolling_back",
+            AuthorityRotationStatus::RolledBack => "rolled_back",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Some(match value {
+            "staged" => AuthorityRotationStatus::Staged,
+            "in_progress" => AuthorityRotationStatus::InProgress,
+            "completed" => AuthorityRotationStatus::Completed,
+            "rolling_back" => AuthorityRotationStatus::RollingBack,
+            "rolled_back" => AuthorityRotationStatus::RolledBack,
+            _ => return None,
+        })
+    }
+
+    /// Vaults may be on either key while a rotation is open
+    pub fn is_open(&self) -> bool {
+        !matches!(self, AuthorityRotationStatus::Completed | AuthorityRotationStatus::RolledBack)
+    }
+}
+
+/// Where one vault stands within a rotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RotationVaultStatus {
+    Pending,
+    /// On-chain authority is the new key
+    Rotated,
+    /// Last attempt failed; on-chain authority is still the old key
+    Failed,
+    /// Rotated, then moved back to the old key
+    RolledBack,
+}
+
+impl RotationVaultStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RotationVaultStatus::Pending => "pending",
+            RotationVaultStatus::Rotated => "rotated",
+            RotationVaultStatus::Failed => "failed",
+            RotationVaultStatus::RolledBack => "rolled_back",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Some(match value {
+            "pending" => RotationVaultStatus::Pending,
+            "rotated" => RotationVaultStatus::Rotated,
+            "failed" => RotationVaultStatus::Failed,
+            "rolled_back" => RotationVaultStatus::RolledBack,
+            _ => return None,
+        })
+    }
+}
+
+/// A rotation of the CPI authority key across all vaults
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorityRotation {
+    pub id: Uuid,
+    pub old_authority: String,
+    pub new_authority: String,
+    pub new_key_path: String,
+    pub status: String,
+    pub batch_size: i32,
+    pub total_vaults: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// One vault's progress within a rotation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorityRotationVault {
+    pub rotation_id: Uuid,
+    pub vault_id: Uuid,
+    pub vault_pubkey: String,
+    pub status: String,
+    pub signature: Option<String>,
+    pub error_message: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Bucket width for activity analytics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivityGranularity {
+    #[default]
+    Hour,
+    Day,
+}
+
+impl ActivityGranularity {
+    /// Postgres `date_trunc` field
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActivityGranularity::Hour => "hour",
+            ActivityGranularity::Day => "day",
+        }
+    }
+
+    pub fn duration(&self) -> chrono::Duration {
+        match self {
+            ActivityGranularity::Hour => chrono::Duration::hours(1),
+            ActivityGranularity::Day => chrono::Duration::days(1),
+        }
+    }
+}
+
+/// Rolled-up activity for one bucket; `operation_type` is `None` on the
+/// all-operations row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityBucketRow {
+    pub bucket_start: DateTime<Utc>,
+    pub operation_type: Option<String>,
+    pub tx_count: i64,
+    pub volume: i64,
+    pub active_vaults: i64,
+}
+
+/// Activity of one vault over a period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultActivityVolume {
+    pub vault_id: Uuid,
+    pub user_pubkey: String,
+    pub vault_pubkey: String,
+    pub tx_count: i64,
+    pub volume: i64,
+}
+
+/// Outcome of one rollup refresh
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityRollupRefresh {
+    pub buckets_refreshed: i64,
+    pub rows_written: i64,
+    pub rolled_up_through: DateTime<Utc>,
+}
+
+/// Credential issued to a support engineer (read-only) or an operator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupportCredential {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(skip)]
+    pub token_hash: String,
+    /// `support` or `operations`
+    pub role: String,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// Whether an ops case is still being worked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VaultCaseStatus {
+    Open,
+    Closed,
+}
+
+impl VaultCaseStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VaultCaseStatus::Open => "open",
+            VaultCaseStatus::Closed => "closed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Some(match value {
+            "open" => VaultCaseStatus::Open,
+            "closed" => VaultCaseStatus::Closed,
+            _ => return None,
+        })
+    }
+}
+
+/// An investigation or follow-up opened by operations on a vault
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultCase {
+    pub id: Uuid,
+    pub vault_id: Uuid,
+    pub title: String,
+    pub status: String,
+    pub opened_by: String,
+    pub opened_at: DateTime<Utc>,
+    pub closed_by: Option<String>,
+    pub closed_at: Option<DateTime<Utc>>,
+    pub resolution: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultNote {
+    pub id: Uuid,
+    pub vault_id: Uuid,
+    pub case_id: Option<Uuid>,
+    pub body: String,
+    pub author: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultTag {
+    pub vault_id: Uuid,
+    pub tag: String,
+    pub added_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkJobStatus {
+    /// Targets resolved and shown to the operator; nothing changed yet
+    Previewed,
+    Running,
+    Completed,
+    /// Stopped by an operator; remaining targets were not touched
+    Aborted,
+}
+
+impl BulkJobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BulkJobStatus::Previewed => "previewed",
+            BulkJobStatus::Running => "running",
+            BulkJobStatus::Completed => "completed",
+            BulkJobStatus::Aborted => "aborted",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Some(match value {
+            "previewed" => BulkJobStatus::Previewed,
+            "running" => BulkJobStatus::Running,
+            "completed" => BulkJobStatus::Completed,
+            "aborted" => BulkJobStatus::Aborted,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkTargetStatus {
+    Pending,
+    Succeeded,
+    Failed,
+    /// Nothing to do, e.g. the withdrawal was already retried
+    Skipped,
+}
+
+impl BulkTargetStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BulkTargetStatus::Pending => "pending",
+            BulkTargetStatus::Succeeded => "succeeded",
+            BulkTargetStatus::Failed => "failed",
+            BulkTargetStatus::Skipped => "skipped",
+        }
+    }
+}
+
+/// An admin bulk operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkJob {
+    pub id: Uuid,
+    pub operation: String,
+    pub parameters: serde_json::Value,
+    pub reason: String,
+    pub status: String,
+    pub batch_size: i32,
+    pub total_targets: i32,
+    pub requested_by: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// One vault or transaction a bulk job acts on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkJobTarget {
+    pub job_id: Uuid,
+    pub target_id: Uuid,
+    pub vault_id: Uuid,
+    pub description: String,
+    pub status: String,
+    pub error_message: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A vault token account seen with an SPL delegate or close authority set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenAuthorityFinding {
+    pub id: Uuid,
+    pub vault_id: Uuid,
+    pub token_account_pubkey: String,
+    pub delegate: Option<String>,
+    pub delegated_amount: i64,
+    pub close_authority: Option<String>,
+    pub detected_at: DateTime<Utc>,
+    /// Last sweep that still saw the authorities set
+    pub last_seen_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+/// Outcome of screening one deposit or withdrawal address
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScreeningDecision {
+    pub id: Uuid,
+    pub vault_id: Uuid,
+    pub direction: String,
+    pub address: String,
+    pub amount: i64,
+    pub risk: String,
+    pub action: String,
+    /// What each provider returned, or why it failed
+    pub findings: serde_json::Value,
+    /// Set for holds: pending until an operator clears or rejects it
+    pub review_status: Option<String>,
+    pub reviewed_by: Option<String>,
+    pub review_note: Option<String>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub clearance_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Event committed with the state change it describes, awaiting delivery
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboxEvent {
+    /// Gap-free and increasing in commit order; consumers dedupe on it
+    pub seq: i64,
+    pub event_type: String,
+    pub vault_id: Option<Uuid>,
+    /// Same JSON as the stream event of this type
+    pub payload: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Downstream system receiving outbox events and how far it has acknowledged
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventConsumer {
+    pub name: String,
+    /// `webhook` (pushed) or `pull`
+    pub delivery: String,
+    pub endpoint_url: Option<String>,
+    #[serde(skip_serializing, default)]
+    pub signing_secret: Option<String>,
+    pub acked_seq: i64,
+    pub acked_at: Option<DateTime<Utc>>,
+    pub failed_attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub leased_by: Option<String>,
+    pub leased_until: Option<DateTime<Utc>>,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Target locked balance a vault keeps through automatic lock and unlock
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VaultAutoLock {
+    pub vault_id: Uuid,
+    pub target_locked: i64,
+    pub enabled: bool,
+    /// Positive when collateral was locked, negative when unlocked
+    pub last_adjustment: Option<i64>,
+    pub last_adjusted_at: Option<DateTime<Utc>>,
+    /// Why the latest adjustment failed; cleared by the next success
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One transaction signature returned by `getSignaturesForAddress`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignatureEntry {
+    pub signature: String,
+    pub slot: u64,
+    /// Unix seconds, when the node knows it
+    pub block_time: Option<i64>,
+    pub failed: bool,
+}
+
+/// Unprocessed range between the last signature before it and the oldest after it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexerGapRange {
+    pub from_slot: u64,
+    pub to_slot: u64,
+    pub until_signature: String,
+    pub before_signature: String,
+}
+
+/// Indexer position for one program
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexerCursor {
+    pub program_id: String,
+    /// Newest processed signature; `None` until the program has any
+    pub last_slot: Option<i64>,
+    pub last_signature: Option<String>,
+    /// Cluster slot when the last successful poll ran
+    pub polled_head_slot: i64,
+    pub processed_count: i64,
+    pub polled_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Slot range the indexer has not yet processed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexerGap {
+    pub id: Uuid,
+    pub program_id: String,
+    /// Slot of the last signature processed before the gap
+    pub from_slot: i64,
+    /// Slot of the oldest signature processed after the gap; lowered as the re-scan proceeds
+    pub to_slot: i64,
+    pub until_signature: String,
+    pub before_signature: String,
+    /// `open` or `resolved`
+    pub status: String,
+    /// Signatures the re-scan found that had not been processed
+    pub recovered_count: i32,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub detected_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+/// Announced maintenance period
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaintenanceWindow {
+    pub id: Uuid,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub reason: String,
+    /// Writes are rejected while the window is active
+    pub read_only: bool,
+    pub created_at: DateTime<Utc>,
+    pub cancelled_at: Option<DateTime<Utc>>,
+}
+
+impl MaintenanceWindow {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.cancelled_at.is_none() && self.starts_at <= now && now < self.ends_at
+    }
+
+    pub fn is_upcoming(&self, now: DateTime<Utc>) -> bool {
+        self.cancelled_at.is_none() && now < self.starts_at
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuditLog {
     pub id: Uuid,
@@ -138,6 +1087,137 @@ pub struct RateLimitResult {
     pub allowed: bool,
     pub remaining_tokens: i32,
     pub reset_at: Option<DateTime<Utc>>,
+    /// Set while the client is banned; the request is refused whatever its tokens
+    pub banned_until: Option<DateTime<Utc>>,
+}
+
+/// One client's rate-limited traffic over a window
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientUsage {
+    pub client_id: String,
+    pub requests: i64,
+    /// 429s from an empty token bucket
+    pub rejected: i64,
+    /// 429s from a ban
+    pub banned: i64,
+    /// Minutes with any request
+    pub active_minutes: i64,
+    /// Minutes with at least one rejection
+    pub limited_minutes: i64,
+    pub first_seen_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    /// Bans placed on the client in the last week
+    pub recent_bans: i64,
+}
+
+/// Temporary ban of a rate-limited client
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimitBan {
+    pub id: Uuid,
+    pub client_id: String,
+    pub reason: String,
+    /// Operator that placed the ban; `None` for escalation rules
+    pub created_by: Option<String>,
+    pub rule_id: Option<Uuid>,
+    pub expires_at: DateTime<Utc>,
+    pub lifted_at: Option<DateTime<Utc>>,
+    pub lifted_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Bans clients rejected too often within a window
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EscalationRule {
+    pub id: Uuid,
+    pub name: String,
+    pub min_rejections: i64,
+    pub window_seconds: i32,
+    /// First ban length; doubled for each earlier ban of the client in the last week
+    pub ban_seconds: i32,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Identity verification of a user_pubkey as last reported by its provider
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Identity {
+    pub user_pubkey: String,
+    pub provider: String,
+    /// Provider's reference for the applicant
+    pub external_id: String,
+    pub level: String,
+    pub status: String,
+    pub verified_at: Option<DateTime<Utc>>,
+    /// Verification no longer counts after this
+    pub expires_at: Option<DateTime<Utc>>,
+    pub reported_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Alert rule a vault owner set on their vault
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VaultAlertRule {
+    pub id: Uuid,
+    pub vault_id: Uuid,
+    pub kind: String,
+    /// Base units for `balance_below`, percent for `lock_utilization_above`
+    pub threshold: Option<i64>,
+    pub label: Option<String>,
+    pub enabled: bool,
+    /// The condition held at the last evaluation; the rule fires again only after it clears
+    pub triggered: bool,
+    pub trigger_count: i64,
+    pub last_triggered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Asynchronous export of vaults, transactions and ledger for a time range
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataExport {
+    pub id: Uuid,
+    pub requested_by: String,
+    pub range_start: DateTime<Utc>,
+    pub range_end: DateTime<Utc>,
+    pub status: String,
+    /// Every file reflects the database as of this instant
+    pub snapshot_at: Option<DateTime<Utc>>,
+    pub objects: serde_json::Value,
+    pub error_message: Option<String>,
+    pub attempts: i32,
+    pub leased_by: Option<String>,
+    pub leased_until: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// Vault row as exported
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedVault {
+    pub id: Uuid,
+    pub user_pubkey: String,
+    pub vault_pubkey: String,
+    pub token_account_pubkey: String,
+    pub mint_pubkey: Option<String>,
+    pub total_balance: i64,
+    pub locked_balance: i64,
+    pub available_balance: i64,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Everything one export reads, from a single repeatable-read transaction
+#[derive(Debug, Clone)]
+pub struct ExportSnapshot {
+    pub snapshot_at: DateTime<Utc>,
+    pub vaults: Vec<ExportedVault>,
+    /// Records created within the range, any status
+    pub transactions: Vec<TransactionRecord>,
+    /// Records confirmed before the end of the range, by vault and confirmation time
+    pub confirmed: Vec<TransactionRecord>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -181,8 +1261,259 @@ impl Vault {
     }
 }
 
+impl MintConfig {
+    /// Render a base-unit amount as a decimal string, e.g. `1500000` -> `1.500000 USDT`
+    pub fn format_amount(&self, amount: i64) -> String {
+        format!("{} {}", self.format_units(amount), self.symbol)
+    }
+    
+    /// Decimal string without the symbol, e.g. `1500000` -> `1.500000`
+    pub fn format_units(&self, amount: i64) -> String {
+        let decimals = self.decimals.max(0) as u32;
+        let scale = 10i128.pow(decimals);
+        let amount = amount as i128;
+        let sign = if amount < 0 { "-" } else { "" };
+        let whole = amount.abs() / scale;
+        let frac = amount.abs() % scale;
+        
+        if decimals == 0 {
+            format!("{}{}", sign, whole)
+        } else {
+            format!("{}{}.{:0width$}", sign, whole, frac, width = decimals as usize)
+        }
+    }
+    
+    pub fn validate_pubkey(&self) -> Result<Pubkey, String> {
+        Pubkey::from_str(&self.mint_pubkey).map_err(|e| e.to_string())
+    }
+}
+
 impl VaultResponse {
     pub fn validate_balances(&self) -> bool {
         self.total_balance == (self.locked_balance + self.available_balance)
     }
-}
\ No newline at end of file
+}
+
+/// Response stored for a request made with an idempotency key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+/// Result of claiming an idempotency key for a request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdempotencyClaim {
+    /// First use of the key, or its earlier use expired or was abandoned; run the request
+    Claimed,
+    /// The request with this key already finished; replay its response
+    Completed(StoredResponse),
+    /// The request with this key is still running
+    InProgress,
+    /// The key was used for a different request
+    Mismatch,
+}
+
+/// Dormancy state of a vault flagged for inactivity
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VaultDormancy {
+    pub vault_id: Uuid,
+    pub user_pubkey: String,
+    /// `notified`, `swept`, `reclaimed` or `cleared`
+    pub status: String,
+    pub inactive_since: DateTime<Utc>,
+    pub notified_at: DateTime<Utc>,
+    /// The funds may be swept after this
+    pub grace_ends_at: DateTime<Utc>,
+    pub swept_at: Option<DateTime<Utc>>,
+    pub swept_amount: Option<i64>,
+    pub reclaimed_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// An active vault holding funds with no transactions since `inactive_since`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DormancyCandidate {
+    pub vault_id: Uuid,
+    pub user_pubkey: String,
+    pub total_balance: i64,
+    pub inactive_since: DateTime<Utc>,
+}
+
+/// One tier of a mint's withdrawal fee schedule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WithdrawalFeeTier {
+    pub mint_pubkey: String,
+    /// Smallest withdrawal, in base units, the tier applies to
+    pub min_amount: i64,
+    pub flat_fee: i64,
+    /// Basis points of the withdrawn amount
+    pub bps: i32,
+}
+
+/// A time-boxed promotional withdrawal fee
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WithdrawalFeePromotion {
+    pub id: Uuid,
+    pub mint_pubkey: String,
+    pub label: String,
+    /// Only this user qualifies; everyone when `None`
+    pub user_pubkey: Option<String>,
+    pub flat_fee: i64,
+    pub bps: i32,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One finished execution of a background monitor job
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MonitorJobRun {
+    pub id: i64,
+    pub job_type: String,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: i64,
+    /// `succeeded`, `failed` or `skipped`
+    pub outcome: String,
+    pub error: Option<String>,
+}
+
+/// An authentication or authorization failure, rate limit ban or operator write
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SecurityEvent {
+    pub id: i64,
+    pub event_type: String,
+    /// `info`, `warning` or `critical`
+    pub severity: String,
+    /// Client address, `ip:<address>`
+    pub source: String,
+    pub actor: Option<String>,
+    pub details: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Security events from one source within a window
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecurityEventCount {
+    pub source: String,
+    pub events: i64,
+}
+
+/// A deposit accepted but not yet credited, with what the indexer knows of its signature
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingDeposit {
+    pub transaction_id: Uuid,
+    pub vault_id: Uuid,
+    pub vault_pubkey: String,
+    pub token_account_pubkey: String,
+    /// Requested amount; less is credited when a transfer fee is withheld
+    pub amount: i64,
+    pub signature: String,
+    /// Slot of the signature once indexed
+    pub slot: Option<i64>,
+    /// Whether the transaction failed on-chain, once indexed
+    pub failed: Option<bool>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IncidentRunStatus {
+    Running,
+    /// A step failed; the run waits for an operator to resume or abort it
+    Failed,
+    Completed,
+    /// Stopped by an operator; steps not yet run never will be
+    Aborted,
+}
+
+impl IncidentRunStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IncidentRunStatus::Running => "running",
+            IncidentRunStatus::Failed => "failed",
+            IncidentRunStatus::Completed => "completed",
+            IncidentRunStatus::Aborted => "aborted",
+        }
+    }
+}
+
+/// One run of an incident runbook
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IncidentRun {
+    pub id: Uuid,
+    pub runbook: String,
+    pub reason: String,
+    pub status: String,
+    /// Vaults reconciled and resynced; every active vault when `None`
+    pub vault_ids: Option<Vec<Uuid>>,
+    /// Read-only window the run paused the API with, or took over from the run it follows
+    pub maintenance_window_id: Option<Uuid>,
+    pub follows: Option<Uuid>,
+    pub requested_by: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IncidentStepStatus {
+    Pending,
+    /// Started, or waiting on a condition such as a drain
+    Running,
+    Completed,
+    Failed,
+}
+
+impl IncidentStepStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IncidentStepStatus::Pending => "pending",
+            IncidentStepStatus::Running => "running",
+            IncidentStepStatus::Completed => "completed",
+            IncidentStepStatus::Failed => "failed",
+        }
+    }
+}
+
+/// A step of an incident run and what it did
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IncidentStepRecord {
+    pub run_id: Uuid,
+    pub position: i32,
+    pub step: String,
+    pub status: String,
+    pub attempts: i32,
+    pub output: Option<serde_json::Value>,
+    pub error_message: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// One committed change of a journaled table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub lsn: i64,
+    /// Table the row belongs to
+    pub entity: String,
+    /// Key columns joined with `:`
+    pub entity_key: String,
+    /// `insert`, `update` or `delete`
+    pub operation: String,
+    /// The row after the change; `None` for a delete
+    pub state: Option<serde_json::Value>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Journaled tables and the journal read in one consistent snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalRead {
+    /// Last LSN committed in the snapshot
+    pub lsn: i64,
+    /// Table name and row
+    pub rows: Vec<(String, serde_json::Value)>,
+    pub entries: Vec<JournalEntry>,
+}
@@ -0,0 +1,442 @@
+//! Squads v4 multisig support for vaults owned by a multisig wallet.
+//!
+//! A multisig-owned vault's `user` is a Squads vault PDA, which can only sign
+//! through the Squads program. Withdrawals are therefore built as the inner
+//! withdraw instruction wrapped in a Squads vault transaction plus proposal,
+//! handed to a member to sign, and tracked until the proposal executes.
+
+use crate::error::{Result, ChainError, DomainError};
+use crate::models::{MultisigProposal, MultisigProposalStatus, TransactionType, TransactionStatus, TransactionRecord};
+use crate::vault_manager::VaultManager;
+use crate::transaction_builder::{self, TransactionBuilder, SigningHints};
+use crate::database::MultisigProposalRepository;
+use crate::token_accounts::TokenAccountRole;
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+    transaction::Transaction,
+};
+use std::str::FromStr;
+use std::sync::Arc;
+use uuid::Uuid;
+use tracing::{info, warn, error};
+
+/// Squads v4 program
+pub const SQUADS_PROGRAM_ID: &str = "SQDS4ep65T869zMMBKyuUq6aD6EgTu8psMjkvj52pCf";
+
+const SEED_PREFIX: &[u8] = b"multisig";
+const SEED_VAULT: &[u8] = b"vault";
+const SEED_TRANSACTION: &[u8] = b"transaction";
+const SEED_PROPOSAL: &[u8] = b"proposal";
+
+/// Offset of `transaction_index: u64` in the Multisig account:
+/// discriminator(8) + create_key(32) + config_authority(32) + threshold(2) + time_lock(4)
+const MULTISIG_TRANSACTION_INDEX_OFFSET: usize = 78;
+
+/// Offset of the `status` enum tag in the Proposal account:
+/// discriminator(8) + multisig(32) + transaction_index(8)
+const PROPOSAL_STATUS_OFFSET: usize = 48;
+
+/// Proposals still awaiting a member's signature after this long are expired
+const AWAITING_SIGNATURE_TTL_HOURS: i64 = 24;
+
+pub fn squads_program_id() -> Pubkey {
+    Pubkey::from_str(SQUADS_PROGRAM_ID).expect("valid Squads program id")
+}
+
+/// Squads vault PDA that holds authority for `multisig`, seeds `["multisig", multisig, "vault", [index]]`
+pub fn squads_vault_pda(multisig: &Pubkey, vault_index: u8) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SEED_PREFIX, multisig.as_ref(), SEED_VAULT, &[vault_index]],
+        &squads_program_id(),
+    )
+}
+
+/// Vault transaction PDA, seeds `["multisig", multisig, "transaction", index_le]`
+pub fn squads_transaction_pda(multisig: &Pubkey, transaction_index: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SEED_PREFIX, multisig.as_ref(), SEED_TRANSACTION, &transaction_index.to_le_bytes()],
+        &squads_program_id(),
+    )
+}
+
+/// Proposal PDA, seeds `["multisig", multisig, "transaction", index_le, "proposal"]`
+pub fn squads_proposal_pda(multisig: &Pubkey, transaction_index: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SEED_PREFIX, multisig.as_ref(), SEED_TRANSACTION, &transaction_index.to_le_bytes(), SEED_PROPOSAL],
+        &squads_program_id(),
+    )
+}
+
+/// Anchor instruction discriminator: first 8 bytes of sha256("global:<name>")
+pub fn anchor_discriminator(name: &str) -> [u8; 8] {
+    let hash = Sha256::digest(format!("global:{}", name).as_bytes());
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash[..8]);
+    discriminator
+}
+
+/// Compile instructions into Squads' compact `TransactionMessage` encoding.
+///
+/// `vault` is the Squads vault PDA; it is always the first (writable, signer) key.
+pub fn compile_transaction_message(vault: &Pubkey, instructions: &[Instruction]) -> Result<Vec<u8>> {
+    // (key, is_signer, is_writable), vault first
+    let mut keys: Vec<(Pubkey, bool, bool)> = vec![(*vault, true, true)];
+    let mut merge = |key: Pubkey, is_signer: bool, is_writable: bool| {
+        match keys.iter_mut().find(|(k, _, _)| *k == key) {
+            Some(entry) => {
+                entry.1 |= is_signer;
+                entry.2 |= is_writable;
+            }
+            None => keys.push((key, is_signer, is_writable)),
+        }
+    };
+    for ix in instructions {
+        for meta in &ix.accounts {
+            merge(meta.pubkey, meta.is_signer, meta.is_writable);
+        }
+        merge(ix.program_id, false, false);
+    }
+
+    // Same ordering as a legacy message: writable signers, readonly signers, writable, readonly.
+    // The sort is stable, so the vault stays first among writable signers.
+    keys.sort_by_key(|(_, is_signer, is_writable)| match (*is_signer, *is_writable) {
+        (true, true) => 0,
+        (true, false) => 1,
+        (false, true) => 2,
+        (false, false) => 3,
+    });
+
+    let too_large = |what: &str| DomainError::Validation(format!("Multisig transaction has too many {}", what));
+    let count_u8 = |n: usize, what: &str| u8::try_from(n).map_err(|_| too_large(what));
+
+    let index_of = |key: &Pubkey| keys.iter().position(|(k, _, _)| k == key).expect("key was merged") as u8;
+
+    let mut out = Vec::new();
+    out.push(count_u8(keys.iter().filter(|k| k.1).count(), "signers")?);
+    out.push(count_u8(keys.iter().filter(|k| k.1 && k.2).count(), "signers")?);
+    out.push(count_u8(keys.iter().filter(|k| !k.1 && k.2).count(), "accounts")?);
+
+    out.push(count_u8(keys.len(), "accounts")?);
+    for (key, _, _) in &keys {
+        out.extend_from_slice(key.as_ref());
+    }
+
+    out.push(count_u8(instructions.len(), "instructions")?);
+    for ix in instructions {
+        out.push(index_of(&ix.program_id));
+        out.push(count_u8(ix.accounts.len(), "instruction accounts")?);
+        for meta in &ix.accounts {
+            out.push(index_of(&meta.pubkey));
+        }
+        let data_len = u16::try_from(ix.data.len()).map_err(|_| too_large("instruction data bytes"))?;
+        out.extend_from_slice(&data_len.to_le_bytes());
+        out.extend_from_slice(&ix.data);
+    }
+
+    // No address lookup tables
+    out.push(0);
+
+    Ok(out)
+}
+
+/// `vault_transaction_create`: store `message` as the multisig's next transaction
+pub fn vault_transaction_create_ix(
+    multisig: &Pubkey,
+    creator: &Pubkey,
+    transaction_index: u64,
+    vault_index: u8,
+    message: Vec<u8>,
+) -> Instruction {
+    let mut data = anchor_discriminator("vault_transaction_create").to_vec();
+    data.push(vault_index);
+    data.push(0); // ephemeral signers
+    data.extend_from_slice(&(message.len() as u32).to_le_bytes());
+    data.extend_from_slice(&message);
+    data.push(0); // memo: None
+
+    Instruction {
+        program_id: squads_program_id(),
+        accounts: vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(squads_transaction_pda(multisig, transaction_index).0, false),
+            AccountMeta::new_readonly(*creator, true),
+            AccountMeta::new(*creator, true), // rent payer
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    }
+}
+
+/// `proposal_create`: open the proposal for `transaction_index` for voting
+pub fn proposal_create_ix(multisig: &Pubkey, creator: &Pubkey, transaction_index: u64) -> Instruction {
+    let mut data = anchor_discriminator("proposal_create").to_vec();
+    data.extend_from_slice(&transaction_index.to_le_bytes());
+    data.push(0); // draft: false
+
+    Instruction {
+        program_id: squads_program_id(),
+        accounts: vec![
+            AccountMeta::new_readonly(*multisig, false),
+            AccountMeta::new(squads_proposal_pda(multisig, transaction_index).0, false),
+            AccountMeta::new_readonly(*creator, true),
+            AccountMeta::new(*creator, true), // rent payer
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    }
+}
+
+/// `proposal_approve`: the creating member's own approval
+pub fn proposal_approve_ix(multisig: &Pubkey, member: &Pubkey, transaction_index: u64) -> Instruction {
+    let mut data = anchor_discriminator("proposal_approve").to_vec();
+    data.push(0); // memo: None
+
+    Instruction {
+        program_id: squads_program_id(),
+        accounts: vec![
+            AccountMeta::new_readonly(*multisig, false),
+            AccountMeta::new(*member, true),
+            AccountMeta::new(squads_proposal_pda(multisig, transaction_index).0, false),
+        ],
+        data,
+    }
+}
+
+/// Current `transaction_index` from raw Multisig account data
+pub fn parse_multisig_transaction_index(data: &[u8]) -> Result<u64> {
+    let bytes = data
+        .get(MULTISIG_TRANSACTION_INDEX_OFFSET..MULTISIG_TRANSACTION_INDEX_OFFSET + 8)
+        .ok_or_else(|| ChainError::InvalidAccountData("Multisig account too short".to_string()))?;
+    Ok(u64::from_le_bytes(bytes.try_into().expect("8 bytes")))
+}
+
+/// Status from raw Proposal account data
+pub fn parse_proposal_status(data: &[u8]) -> Result<MultisigProposalStatus> {
+    let tag = data
+        .get(PROPOSAL_STATUS_OFFSET)
+        .ok_or_else(|| ChainError::InvalidAccountData("Proposal account too short".to_string()))?;
+    Ok(match tag {
+        0 => MultisigProposalStatus::Draft,
+        1 => MultisigProposalStatus::Active,
+        2 => MultisigProposalStatus::Rejected,
+        3 => MultisigProposalStatus::Approved,
+        4 => MultisigProposalStatus::Executing,
+        5 => MultisigProposalStatus::Executed,
+        6 => MultisigProposalStatus::Cancelled,
+        other => return Err(ChainError::InvalidAccountData(format!("Unknown proposal status {}", other)).into()),
+    })
+}
+
+/// Unsigned Squads transaction creating, proposing and approving a vault withdrawal
+#[derive(Debug, Clone)]
+pub struct MultisigProposalTx {
+    /// Fee payer is the creating member; no signatures attached
+    pub transaction: Transaction,
+    pub last_valid_block_height: u64,
+    pub squads_vault: Pubkey,
+    pub transaction_index: u64,
+    pub proposal: Pubkey,
+}
+
+/// Request to withdraw from a multisig-owned vault
+#[derive(Debug, Clone)]
+pub struct MultisigWithdrawalRequest {
+    pub vault_id: Uuid,
+    pub multisig: Pubkey,
+    pub vault_index: u8,
+    /// Multisig member who will sign and pay for the proposal
+    pub creator: Pubkey,
+    pub destination_token_account: Pubkey,
+    /// Mint the vault holds, which the destination must be for
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub idempotency_key: Option<String>,
+}
+
+/// A prepared multisig withdrawal, ready for a member to sign
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreparedMultisigWithdrawal {
+    pub transaction: TransactionRecord,
+    pub proposal: MultisigProposal,
+    /// Base64 bincode of the unsigned transaction
+    pub unsigned_transaction: String,
+    pub hints: SigningHints,
+}
+
+/// Builds multisig withdrawals and follows their proposals to execution
+pub struct MultisigManager {
+    proposal_repo: MultisigProposalRepository,
+    vault_manager: Arc<VaultManager>,
+    transaction_builder: Arc<TransactionBuilder>,
+}
+
+impl MultisigManager {
+    pub fn new(
+        pool: sqlx::PgPool,
+        vault_manager: Arc<VaultManager>,
+        transaction_builder: Arc<TransactionBuilder>,
+    ) -> Self {
+        Self {
+            proposal_repo: MultisigProposalRepository::new(pool),
+            vault_manager,
+            transaction_builder,
+        }
+    }
+
+    /// Record a pending withdrawal and build the proposal transaction for a member to sign
+    pub async fn prepare_withdrawal(&self, request: MultisigWithdrawalRequest) -> Result<PreparedMultisigWithdrawal> {
+        let vault = self.vault_manager.get_vault_by_id(request.vault_id).await?;
+
+        let (squads_vault, _) = squads_vault_pda(&request.multisig, request.vault_index);
+        if vault.user_pubkey != squads_vault.to_string() {
+            return Err(DomainError::Unauthorized(format!(
+                "Vault is not owned by multisig {} (vault index {})", request.multisig, request.vault_index
+            )).into());
+        }
+        if vault.available_balance < request.amount as i64 {
+            return Err(DomainError::InsufficientBalance {
+                available: vault.available_balance as u64,
+                required: request.amount,
+            }.into());
+        }
+
+        let vault_pubkey = Pubkey::from_str(&vault.vault_pubkey)
+            .map_err(|_| ChainError::InvalidAccountData(format!("Invalid vault pubkey: {}", vault.vault_pubkey)))?;
+        // The proposal executes long after it is signed, so a bad destination is caught now
+        let token_program = self.transaction_builder.fetch_token_program(request.mint).await?;
+        let destination_plan = self.transaction_builder.check_user_token_account(
+            request.destination_token_account,
+            request.mint,
+            token_program,
+            squads_vault,
+            TokenAccountRole::WithdrawalDestination,
+        ).await?;
+        let built = self.transaction_builder.build_multisig_withdraw_proposal(
+            request.multisig,
+            request.vault_index,
+            request.creator,
+            vault_pubkey,
+            request.amount,
+            request.destination_token_account,
+            &destination_plan,
+        ).await?;
+        let hints = self.transaction_builder.signing_hints(&built.transaction, built.last_valid_block_height).await?;
+
+        let transaction = self.vault_manager.queue_transaction(
+            vault.id,
+            TransactionType::Withdraw,
+            request.amount as i64,
+            None,
+            request.idempotency_key,
+        ).await?;
+
+        let proposal = self.proposal_repo.create_proposal(
+            transaction.id,
+            vault.id,
+            &request.multisig.to_string(),
+            &built.squads_vault.to_string(),
+            built.transaction_index as i64,
+            &built.proposal.to_string(),
+            request.amount as i64,
+        ).await?;
+
+        info!("Prepared multisig withdrawal {} for vault {} (proposal {})", transaction.id, vault.id, proposal.proposal_pubkey);
+
+        Ok(PreparedMultisigWithdrawal {
+            transaction,
+            proposal,
+            unsigned_transaction: transaction_builder::encode_transaction(&built.transaction)?,
+            hints,
+        })
+    }
+
+    /// Get a tracked proposal, refreshed from chain unless it is already final
+    pub async fn get_proposal(&self, transaction_id: Uuid) -> Result<MultisigProposal> {
+        let proposal = self.proposal_repo.get_by_transaction_id(transaction_id).await?;
+        self.refresh(proposal).await
+    }
+
+    /// Refresh every open proposal; errors are logged per proposal
+    pub async fn refresh_open_proposals(&self) -> Result<usize> {
+        let open = self.proposal_repo.list_open_proposals(100).await?;
+        let count = open.len();
+
+        for proposal in open {
+            let id = proposal.id;
+            if let Err(e) = self.refresh(proposal).await {
+                warn!("Failed to refresh multisig proposal {}: {}", id, e);
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Poll open proposals until the process exits
+    pub async fn run_proposal_tracker(&self, interval_seconds: u64) {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_seconds));
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.refresh_open_proposals().await {
+                error!("Multisig proposal tracking failed: {}", e);
+            }
+        }
+    }
+
+    /// Apply the on-chain proposal status, settling the withdrawal once it is final
+    async fn refresh(&self, proposal: MultisigProposal) -> Result<MultisigProposal> {
+        let current = MultisigProposalStatus::parse(&proposal.status)
+            .unwrap_or(MultisigProposalStatus::AwaitingSignature);
+        if current.is_terminal() {
+            return Ok(proposal);
+        }
+
+        let proposal_pubkey = Pubkey::from_str(&proposal.proposal_pubkey)
+            .map_err(|_| ChainError::InvalidAccountData(format!("Invalid proposal pubkey: {}", proposal.proposal_pubkey)))?;
+
+        let observed = match self.transaction_builder.fetch_multisig_proposal_status(proposal_pubkey).await? {
+            Some(status) => status,
+            None if Utc::now() - proposal.created_at > Duration::hours(AWAITING_SIGNATURE_TTL_HOURS) => {
+                MultisigProposalStatus::Expired
+            }
+            None => MultisigProposalStatus::AwaitingSignature,
+        };
+        if observed == current {
+            return Ok(proposal);
+        }
+
+        match observed {
+            MultisigProposalStatus::Executed => {
+                let vault = self.vault_manager.get_vault_by_id(proposal.vault_id).await?;
+                self.vault_manager.update_balances(
+                    vault.id,
+                    vault.total_balance - proposal.amount,
+                    vault.locked_balance,
+                    vault.available_balance - proposal.amount,
+                    Some(proposal.transaction_id),
+                    "multisig_proposal",
+                ).await?;
+                self.vault_manager.transaction_manager()
+                    .update_transaction_status(proposal.transaction_id, TransactionStatus::Confirmed, None)
+                    .await?;
+            }
+            MultisigProposalStatus::Rejected | MultisigProposalStatus::Cancelled | MultisigProposalStatus::Expired => {
+                self.vault_manager.transaction_manager()
+                    .update_transaction_status(
+                        proposal.transaction_id,
+                        TransactionStatus::Failed,
+                        Some(format!("Multisig proposal {}", observed.as_str())),
+                    )
+                    .await?;
+            }
+            _ => {}
+        }
+
+        info!("Multisig proposal {} moved {} -> {}", proposal.proposal_pubkey, current.as_str(), observed.as_str());
+        self.proposal_repo.update_status(proposal.id, observed.as_str()).await
+    }
+}
@@ -0,0 +1,232 @@
+//! Realized network fee analytics, for budgeting payer funding.
+//!
+//! Transactions the backend submits and pays for record their signature and
+//! send attempts on their transaction records. A monitor task reads each
+//! one's fee from the finalized transaction afterwards; transactions not
+//! finalized within `FEE_LOOKBACK_DAYS` are never counted. The priority fee is
+//! the part of the fee above the base fee for the transaction's signatures.
+//!
+//! `/analytics/fees` reports fees per day and operation, and projects a
+//! month's spend from the average daily spend of the last complete week.
+
+use crate::analytics::bucket_floor;
+use crate::database::NetworkFeeRepository;
+use crate::error::{Result, DomainError};
+use crate::models::{ActivityGranularity, NetworkFeeDayRow};
+use crate::rpc::RpcMethodClass;
+use crate::transaction_builder::TransactionBuilder;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Transactions whose fee is still unread this long after creation are given up on
+pub const FEE_LOOKBACK_DAYS: i64 = 7;
+
+/// Complete days the projection averages over
+pub const PROJECTION_WINDOW_DAYS: i64 = 7;
+
+/// Days in the projected month
+pub const PROJECTION_MONTH_DAYS: i64 = 30;
+
+/// Transactions whose fee is read per collection pass
+const FEE_COLLECTION_BATCH: i64 = 200;
+
+const MAX_RANGE_DAYS: i64 = 366;
+
+/// Realized fees of one operation class, or of all of them
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OperationFees {
+    pub transactions: i64,
+    pub fee_lamports: i64,
+    pub priority_fee_lamports: i64,
+    pub retries: i64,
+    pub compute_units_consumed: i64,
+    pub average_fee_lamports: Option<f64>,
+    pub retries_per_transaction: Option<f64>,
+}
+
+impl OperationFees {
+    fn add(&mut self, row: &NetworkFeeDayRow) {
+        self.transactions += row.transactions;
+        self.fee_lamports += row.fee_lamports;
+        self.priority_fee_lamports += row.priority_fee_lamports;
+        self.retries += row.retries;
+        self.compute_units_consumed += row.compute_units_consumed;
+        self.average_fee_lamports = (self.transactions > 0)
+            .then(|| self.fee_lamports as f64 / self.transactions as f64);
+        self.retries_per_transaction = (self.transactions > 0)
+            .then(|| self.retries as f64 / self.transactions as f64);
+    }
+}
+
+/// Fees summed over rows, per operation and in total
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeeSummary {
+    pub operations: BTreeMap<String, OperationFees>,
+    pub total: OperationFees,
+}
+
+impl FeeSummary {
+    pub fn from_rows<'a>(rows: impl IntoIterator<Item = &'a NetworkFeeDayRow>) -> Self {
+        let mut summary = Self::default();
+        for row in rows {
+            summary.operations.entry(row.operation_type.clone()).or_default().add(row);
+            summary.total.add(row);
+        }
+        summary
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeDay {
+    pub day: DateTime<Utc>,
+    #[serde(flatten)]
+    pub fees: FeeSummary,
+}
+
+/// Spend over a month at the recent rate
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeeProjection {
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    pub transactions_per_day: f64,
+    pub daily_average_lamports: f64,
+    pub projected_monthly_lamports: i64,
+    /// Per operation class
+    pub projected_monthly_by_operation: BTreeMap<String, i64>,
+    /// Change of the daily average from the window before; `None` without spend then
+    pub change_percent: Option<f64>,
+}
+
+/// Project a month's spend from the rows of `[window_start - window, window_start + window)`:
+/// the later window sets the rate, the earlier one the trend
+pub fn project_monthly_spend(rows: &[NetworkFeeDayRow], window_start: DateTime<Utc>, window: Duration) -> FeeProjection {
+    let window_end = window_start + window;
+    let days = window.num_days().max(1) as f64;
+    let recent = FeeSummary::from_rows(rows.iter().filter(|r| r.day >= window_start && r.day < window_end));
+    let previous = FeeSummary::from_rows(rows.iter().filter(|r| r.day >= window_start - window && r.day < window_start));
+
+    let monthly = |fee_lamports: i64| (fee_lamports as f64 / days * PROJECTION_MONTH_DAYS as f64).round() as i64;
+    let daily_average_lamports = recent.total.fee_lamports as f64 / days;
+    let previous_daily_average = previous.total.fee_lamports as f64 / days;
+
+    FeeProjection {
+        window_start,
+        window_end,
+        transactions_per_day: recent.total.transactions as f64 / days,
+        daily_average_lamports,
+        projected_monthly_lamports: monthly(recent.total.fee_lamports),
+        projected_monthly_by_operation: recent.operations
+            .iter()
+            .map(|(operation_type, fees)| (operation_type.clone(), monthly(fees.fee_lamports)))
+            .collect(),
+        change_percent: (previous_daily_average > 0.0)
+            .then(|| (daily_average_lamports - previous_daily_average) * 100.0 / previous_daily_average),
+    }
+}
+
+/// One entry per day of `[from, to)`, empty where no fees were recorded
+pub fn fill_days(rows: &[NetworkFeeDayRow], from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<FeeDay> {
+    let mut by_day: HashMap<DateTime<Utc>, Vec<&NetworkFeeDayRow>> = HashMap::new();
+    for row in rows {
+        by_day.entry(row.day).or_default().push(row);
+    }
+
+    let mut days = Vec::new();
+    let mut day = bucket_floor(from, ActivityGranularity::Day);
+    while day < to {
+        let fees = by_day.get(&day).map(|rows| FeeSummary::from_rows(rows.iter().copied())).unwrap_or_default();
+        days.push(FeeDay { day, fees });
+        day = day + Duration::days(1);
+    }
+    days
+}
+
+/// Response of `GET /analytics/fees`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeReport {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub days: Vec<FeeDay>,
+    pub totals: FeeSummary,
+    pub projection: FeeProjection,
+}
+
+/// Reads realized fees from chain and reports on them
+pub struct NetworkFeeAnalytics {
+    repo: NetworkFeeRepository,
+}
+
+impl NetworkFeeAnalytics {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self {
+            repo: NetworkFeeRepository::new(pool),
+        }
+    }
+
+    /// Record the fees of a batch of transactions submitted since the lookback; returns how many
+    pub async fn collect_realized_fees(&self, transaction_builder: &Arc<TransactionBuilder>, now: DateTime<Utc>) -> Result<usize> {
+        let signatures = self.repo
+            .get_unrecorded_signatures(now - Duration::days(FEE_LOOKBACK_DAYS), FEE_COLLECTION_BATCH)
+            .await?;
+
+        let mut recorded = 0;
+        for signature in &signatures {
+            // Not yet finalized or not served by the node; retried next pass
+            let fee = match transaction_builder.fetch_realized_fee(signature, RpcMethodClass::Snapshot).await {
+                Ok(Some(fee)) => fee,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("Failed to read fee of {}: {}", signature, e);
+                    continue;
+                }
+            };
+            self.repo.record_realized_fee(
+                signature,
+                fee.fee_lamports as i64,
+                fee.priority_fee_lamports as i64,
+                fee.compute_units_consumed.map(|units| units as i64),
+            ).await?;
+            recorded += 1;
+        }
+
+        if recorded > 0 {
+            info!("Recorded realized fees of {} transactions", recorded);
+        }
+        Ok(recorded)
+    }
+
+    /// Fees per day over `[from, to)`, aligned outward to whole days, defaulting to the last 30 days
+    pub async fn report(&self, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Result<FeeReport> {
+        let to = to.unwrap_or(now);
+        let from = from.unwrap_or_else(|| to - Duration::days(30));
+        if from >= to {
+            return Err(DomainError::Validation("`from` must be before `to`".to_string()).into());
+        }
+        if to - from > Duration::days(MAX_RANGE_DAYS) {
+            return Err(DomainError::Validation(format!("Period too long; at most {} days", MAX_RANGE_DAYS)).into());
+        }
+
+        let from = bucket_floor(from, ActivityGranularity::Day);
+        let to = match bucket_floor(to, ActivityGranularity::Day) {
+            floor if floor == to => to,
+            floor => floor + Duration::days(1),
+        };
+        let rows = self.repo.get_daily_fees(from, to).await?;
+
+        // The last complete days before today, and as many before them for the trend
+        let window = Duration::days(PROJECTION_WINDOW_DAYS);
+        let window_start = bucket_floor(now, ActivityGranularity::Day) - window;
+        let recent_rows = self.repo.get_daily_fees(window_start - window, window_start + window).await?;
+
+        Ok(FeeReport {
+            from,
+            to,
+            days: fill_days(&rows, from, to),
+            totals: FeeSummary::from_rows(&rows),
+            projection: project_monthly_spend(&recent_rows, window_start, window),
+        })
+    }
+}
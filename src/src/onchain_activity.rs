@@ -0,0 +1,160 @@
+//! Recent on-chain activity of a vault, decoded and matched to backend records.
+//!
+//! Signatures come from `getSignaturesForAddress` on the vault PDA, so they
+//! cover every transaction that passed the vault as an account, whoever
+//! submitted it. Each transaction's instructions addressed to the program,
+//! top-level and CPI alike, are named by their discriminator against the
+//! linked program build and listed with the signers they carried.
+//!
+//! A signature with no transaction record is external: the backend neither
+//! built nor tracked it. External activity is how wallets acting directly on
+//! the program, or a leaked authority key, show up.
+
+use crate::models::{SignatureEntry, TransactionRecord, TransactionStatus, TransactionType};
+use crate::program_info::instruction_discriminators;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{bs58, message::VersionedMessage, pubkey::Pubkey};
+use solana_transaction_status::{UiInnerInstructions, UiInstruction};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Signatures returned per page unless the caller asks for fewer
+pub const DEFAULT_ACTIVITY_LIMIT: usize = 20;
+
+/// Each signature costs a `getTransaction` call, so pages stay small
+pub const MAX_ACTIVITY_LIMIT: usize = 100;
+
+/// IDL name of the instruction whose discriminator starts `data`
+pub fn instruction_name(data: &[u8]) -> Option<&'static str> {
+    let discriminator = data.get(..8)?;
+    instruction_discriminators()
+        .into_iter()
+        .find(|(_, known)| known.as_slice() == discriminator)
+        .map(|(name, _)| name)
+}
+
+/// One program instruction in a transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodedInstruction {
+    /// `None` when the discriminator matches no instruction of the linked build
+    pub name: Option<String>,
+    /// Transaction signers among the instruction's accounts
+    pub signers: Vec<String>,
+    /// Invoked through CPI rather than directly by the transaction
+    pub inner: bool,
+}
+
+/// Instructions of `message` addressed to `program_id`, top-level ones first
+pub fn decode_program_instructions(
+    program_id: &Pubkey,
+    message: &VersionedMessage,
+    inner_instructions: &[UiInnerInstructions],
+) -> Vec<DecodedInstruction> {
+    let keys = message.static_account_keys();
+    // Signers are always static keys, and so are program ids
+    let decode = |program_id_index: u8, accounts: &[u8], data: &[u8], inner: bool| {
+        (keys.get(program_id_index as usize) == Some(program_id)).then(|| DecodedInstruction {
+            name: instruction_name(data).map(str::to_string),
+            signers: accounts.iter()
+                .map(|index| *index as usize)
+                .filter(|index| message.is_signer(*index))
+                .filter_map(|index| keys.get(index))
+                .map(|key| key.to_string())
+                .collect(),
+            inner,
+        })
+    };
+
+    let top_level = message.instructions()
+        .iter()
+        .filter_map(|ix| decode(ix.program_id_index, &ix.accounts, &ix.data, false));
+    let inner = inner_instructions.iter()
+        .flat_map(|group| &group.instructions)
+        .filter_map(|ix| match ix {
+            UiInstruction::Compiled(ix) => {
+                let data = bs58::decode(&ix.data).into_vec().ok()?;
+                decode(ix.program_id_index, &ix.accounts, &data, true)
+            }
+            UiInstruction::Parsed(_) => None,
+        });
+    top_level.chain(inner).collect()
+}
+
+/// A transaction that touched the vault, as read from chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OnchainTransaction {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<DateTime<Utc>>,
+    pub failed: bool,
+    pub fee_payer: Option<String>,
+    pub instructions: Vec<DecodedInstruction>,
+}
+
+impl OnchainTransaction {
+    /// A transaction known only from its signature entry, e.g. one the node no longer serves
+    pub fn from_entry(entry: &SignatureEntry) -> Self {
+        Self {
+            signature: entry.signature.clone(),
+            slot: entry.slot,
+            block_time: entry.block_time.and_then(|seconds| Utc.timestamp_opt(seconds, 0).single()),
+            failed: entry.failed,
+            fee_payer: None,
+            instructions: Vec::new(),
+        }
+    }
+}
+
+/// The backend record behind a signature
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedOperation {
+    pub transaction_id: Uuid,
+    pub transaction_type: TransactionType,
+    pub amount: i64,
+    pub status: TransactionStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityEntry {
+    #[serde(flatten)]
+    pub transaction: OnchainTransaction,
+    pub record: Option<RecordedOperation>,
+    /// No backend record carries this signature
+    pub external: bool,
+}
+
+/// Response of `GET /vaults/:user_pubkey/onchain-activity`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnchainActivity {
+    pub user_pubkey: String,
+    pub vault_pubkey: String,
+    /// Newest first
+    pub entries: Vec<ActivityEntry>,
+    pub external_count: usize,
+    /// `before` for the next page; `None` on the last one
+    pub next_before: Option<String>,
+}
+
+/// Pair each transaction with the record carrying its signature, keeping their order
+pub fn merge_activity(transactions: Vec<OnchainTransaction>, records: &[TransactionRecord]) -> Vec<ActivityEntry> {
+    let by_signature: HashMap<&str, &TransactionRecord> = records.iter()
+        .filter_map(|record| record.tx_signature.as_deref().map(|signature| (signature, record)))
+        .collect();
+
+    transactions.into_iter()
+        .map(|transaction| {
+            let record = by_signature.get(transaction.signature.as_str()).map(|record| RecordedOperation {
+                transaction_id: record.id,
+                transaction_type: record.transaction_type.clone(),
+                amount: record.amount,
+                status: record.status.clone(),
+            });
+            ActivityEntry {
+                external: record.is_none(),
+                transaction,
+                record,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,297 @@
+//! Durable delivery of balance and transaction events to downstream systems.
+//!
+//! Every balance write and transaction record change inserts its event into
+//! `event_outbox` in the same statement, so an event exists exactly when its
+//! change committed; inconsistent reconciliation results are written the same
+//! way as `reconciliation_discrepancy` events, fired vault alert rules as
+//! `vault_alert_triggered` events, finished bulk exports as
+//! `export_completed` / `export_failed` events and dormancy notices, sweeps
+//! and reclaims as `vault_dormancy_notice` / `vault_dormancy_swept` /
+//! `vault_dormancy_reclaimed` events. Sequence numbers are gap-free and increase in commit
+//! order (see migration 0017), which lets each consumer be a single cursor:
+//! `acked_seq` means every event up to it was delivered and acknowledged.
+//!
+//! - `webhook` consumers are pushed to by the delivery task, one event per
+//!   POST, in order. A 2xx response is the acknowledgement. Failures back off
+//!   exponentially and retry from the first unacknowledged event.
+//! - `pull` consumers fetch `GET /events` and acknowledge with `POST /events/ack`.
+//!
+//! A crash between a consumer receiving an event and its acknowledgement being
+//! stored redelivers that event, so consumers must drop any `seq` at or below
+//! the last one they processed; doing so gives exactly-once processing. The
+//! WebSocket stream stays a live view and still resets on gaps.
+
+use crate::database::OutboxRepository;
+use crate::error::{Result, DomainError, VaultError};
+use crate::models::{EventConsumer, OutboxEvent};
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+pub const EVENT_CONSUMER_REGISTERED_EVENT: &str = "event_consumer_registered";
+
+/// Events pushed to one webhook consumer per lease
+const DELIVERY_BATCH_SIZE: i64 = 100;
+
+/// How long a delivery worker holds a consumer
+const DELIVERY_LEASE_SECONDS: i64 = 120;
+
+const MAX_RETRY_BACKOFF_SECONDS: i64 = 3600;
+
+/// Most events returned by one pull
+pub const MAX_PULL_BATCH: i64 = 1000;
+
+/// Acknowledged events are kept this long before pruning
+pub const OUTBOX_RETENTION_DAYS: i64 = 7;
+
+pub const SEQ_HEADER: &str = "X-Event-Seq";
+pub const EVENT_TYPE_HEADER: &str = "X-Event-Type";
+pub const SIGNATURE_HEADER: &str = "X-Event-Signature";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventDelivery {
+    Webhook,
+    Pull,
+}
+
+impl EventDelivery {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventDelivery::Webhook => "webhook",
+            EventDelivery::Pull => "pull",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "webhook" => Some(EventDelivery::Webhook),
+            "pull" => Some(EventDelivery::Pull),
+            _ => None,
+        }
+    }
+}
+
+/// Delay before the next attempt after `failed_attempts` consecutive failures
+pub fn retry_backoff(failed_attempts: i32) -> Duration {
+    let exponent = failed_attempts.clamp(0, 12) as u32;
+    Duration::seconds((5i64 << exponent).min(MAX_RETRY_BACKOFF_SECONDS))
+}
+
+/// Hex HMAC-SHA256 of `<seq>.<body>` under the consumer's secret, sent in `X-Event-Signature`
+pub fn webhook_signature(secret: &str, seq: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(seq.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Consumer names appear in URLs and logs
+pub fn validate_consumer_name(name: &str) -> Result<()> {
+    let valid = (3..=64).contains(&name.len())
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if !valid {
+        return Err(DomainError::Validation(
+            "Consumer name must be 3-64 characters of lowercase letters, digits, '-' and '_'".to_string(),
+        ).into());
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisterConsumerRequest {
+    pub name: String,
+    pub delivery: EventDelivery,
+    /// Required for webhooks; must be https
+    pub endpoint_url: Option<String>,
+    /// Required for webhooks; signs each POST
+    pub signing_secret: Option<String>,
+    /// Treat events up to this sequence number as already delivered; defaults to the current head
+    pub start_after: Option<i64>,
+}
+
+/// A consumer with how far behind the outbox it is
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsumerStatus {
+    #[serde(flatten)]
+    pub consumer: EventConsumer,
+    pub lag: i64,
+}
+
+/// Events for a pull consumer after its cursor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventBatch {
+    pub consumer: String,
+    pub acked_seq: i64,
+    pub head_seq: i64,
+    pub events: Vec<OutboxEvent>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeliverySummary {
+    pub consumers: u32,
+    pub delivered: u32,
+    pub failed: u32,
+}
+
+/// Delivers outbox events to webhook consumers and serves pull consumers
+pub struct OutboxDispatcher {
+    repo: OutboxRepository,
+    client: reqwest::Client,
+    /// Identifies this process's leases
+    worker_id: String,
+}
+
+impl OutboxDispatcher {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self {
+            repo: OutboxRepository::new(pool),
+            client: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+            worker_id: format!("outbox-{}", Uuid::new_v4().simple()),
+        }
+    }
+
+    pub async fn register_consumer(&self, request: RegisterConsumerRequest) -> Result<EventConsumer> {
+        validate_consumer_name(&request.name)?;
+        if request.start_after.is_some_and(|seq| seq < 0) {
+            return Err(DomainError::Validation("start_after must not be negative".to_string()).into());
+        }
+        let (endpoint_url, signing_secret) = match request.delivery {
+            EventDelivery::Pull => (None, None),
+            EventDelivery::Webhook => {
+                let url = request.endpoint_url.as_deref().filter(|url| url.starts_with("https://"))
+                    .ok_or_else(|| DomainError::Validation("Webhook consumers need an https endpoint_url".to_string()))?;
+                let secret = request.signing_secret.as_deref().filter(|secret| secret.len() >= 32)
+                    .ok_or_else(|| DomainError::Validation("Webhook consumers need a signing_secret of at least 32 characters".to_string()))?;
+                (Some(url), Some(secret))
+            }
+        };
+
+        let consumer = self.repo
+            .register_consumer(
+                &request.name,
+                request.delivery.as_str(),
+                endpoint_url,
+                signing_secret,
+                request.start_after,
+                EVENT_CONSUMER_REGISTERED_EVENT,
+            )
+            .await?
+            .ok_or_else(|| DomainError::Validation(format!("Event consumer {} already exists", request.name)))?;
+        info!("Registered {} event consumer {} from seq {}", consumer.delivery, consumer.name, consumer.acked_seq);
+        Ok(consumer)
+    }
+
+    pub async fn consumers(&self) -> Result<Vec<ConsumerStatus>> {
+        let head = self.repo.head_seq().await?;
+        Ok(self.repo.get_consumers().await?
+            .into_iter()
+            .map(|consumer| ConsumerStatus { lag: head - consumer.acked_seq, consumer })
+            .collect())
+    }
+
+    /// Unacknowledged events for a pull consumer; the same events are returned until acknowledged
+    pub async fn poll(&self, name: &str, limit: i64) -> Result<EventBatch> {
+        let consumer = self.pull_consumer(name).await?;
+        let head_seq = self.repo.head_seq().await?;
+        let events = self.repo.events_after(consumer.acked_seq, limit.clamp(1, MAX_PULL_BATCH)).await?;
+        Ok(EventBatch { consumer: consumer.name, acked_seq: consumer.acked_seq, head_seq, events })
+    }
+
+    /// Record that a pull consumer processed every event up to `seq`.
+    ///
+    /// Acknowledging a position already passed is a no-op, so retried acks are safe.
+    pub async fn acknowledge(&self, name: &str, seq: i64) -> Result<EventConsumer> {
+        let consumer = self.pull_consumer(name).await?;
+        if seq <= consumer.acked_seq {
+            return Ok(consumer);
+        }
+        match self.repo.acknowledge(name, seq).await? {
+            Some(consumer) => Ok(consumer),
+            None => {
+                let current = self.repo.get_consumer(name).await?;
+                if seq <= current.acked_seq {
+                    return Ok(current);
+                }
+                Err(DomainError::Validation(format!("Cannot acknowledge seq {} beyond the latest event", seq)).into())
+            }
+        }
+    }
+
+    async fn pull_consumer(&self, name: &str) -> Result<EventConsumer> {
+        let consumer = self.repo.get_consumer(name).await?;
+        if consumer.delivery != EventDelivery::Pull.as_str() {
+            return Err(DomainError::Validation(format!("Event consumer {} receives webhooks", name)).into());
+        }
+        if !consumer.enabled {
+            return Err(DomainError::Validation(format!("Event consumer {} is disabled", name)).into());
+        }
+        Ok(consumer)
+    }
+
+    /// Push pending events to every webhook consumer that is due
+    pub async fn deliver_due(&self) -> Result<DeliverySummary> {
+        let mut summary = DeliverySummary::default();
+        while let Some(consumer) = self.repo.lease_due_webhook(&self.worker_id, DELIVERY_LEASE_SECONDS).await? {
+            summary.consumers += 1;
+            let (delivered, failed) = self.deliver_to(&consumer).await?;
+            summary.delivered += delivered;
+            summary.failed += failed as u32;
+        }
+        Ok(summary)
+    }
+
+    /// Deliver one batch in order, acknowledging each event as its POST succeeds
+    async fn deliver_to(&self, consumer: &EventConsumer) -> Result<(u32, bool)> {
+        let events = self.repo.events_after(consumer.acked_seq, DELIVERY_BATCH_SIZE).await?;
+        let mut delivered = 0;
+
+        for event in &events {
+            if let Err(e) = self.post(consumer, event).await {
+                let next_attempt_at = Utc::now() + retry_backoff(consumer.failed_attempts);
+                warn!("Delivery of event {} to {} failed (attempt {}): {}; retrying at {}",
+                      event.seq, consumer.name, consumer.failed_attempts + 1, e, next_attempt_at);
+                self.repo.record_failure(&consumer.name, &self.worker_id, &e.to_string(), next_attempt_at).await?;
+                return Ok((delivered, true));
+            }
+            self.repo.acknowledge(&consumer.name, event.seq).await?;
+            delivered += 1;
+        }
+
+        self.repo.release_lease(&consumer.name, &self.worker_id).await?;
+        Ok((delivered, false))
+    }
+
+    async fn post(&self, consumer: &EventConsumer, event: &OutboxEvent) -> Result<()> {
+        let (Some(url), Some(secret)) = (consumer.endpoint_url.as_deref(), consumer.signing_secret.as_deref()) else {
+            return Err(VaultError::Configuration(format!("Webhook consumer {} has no endpoint", consumer.name)));
+        };
+        let body = serde_json::to_vec(event)
+            .map_err(|e| VaultError::Internal(format!("Failed to serialize event {}: {}", event.seq, e)))?;
+
+        self.client
+            .post(url)
+            .header("Content-Type", "application/json")
+            .header(SEQ_HEADER, event.seq.to_string())
+            .header(EVENT_TYPE_HEADER, &event.event_type)
+            .header(SIGNATURE_HEADER, webhook_signature(secret, event.seq, &body))
+            .body(body)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| VaultError::Internal(format!("Webhook POST failed: {}", e)))?;
+        Ok(())
+    }
+
+    /// Delete acknowledged events past retention
+    pub async fn prune(&self, now: DateTime<Utc>) -> Result<u64> {
+        self.repo.prune(now - Duration::days(OUTBOX_RETENTION_DAYS)).await
+    }
+}
@@ -0,0 +1,179 @@
+//! Position-aware check of unlocks requested through the API.
+//!
+//! Locked collateral backs the owner's open positions on the trading program.
+//! Before an API caller's unlock is executed, every configured position source
+//! reports the margin the owner's open positions require; the unlock is refused
+//! when the collateral left locked would fall below it.
+//!
+//! Without a source nothing is checked. A source that cannot answer refuses the
+//! unlock unless the check is configured to fail open.
+
+use crate::error::{Result, DomainError, ChainError};
+use crate::models::Vault;
+use crate::rpc::RpcMethodClass;
+use crate::transaction_builder::TransactionBuilder;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Open positions of one owner as seen by one source
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PositionExposure {
+    pub open_positions: u32,
+    /// Collateral the positions need to stay margined, in mint base units
+    pub required_margin: u64,
+}
+
+/// Margin missing if `amount` is unlocked from `locked_balance` while
+/// `required_margin` must stay locked
+pub fn unlock_shortfall(locked_balance: u64, amount: u64, required_margin: u64) -> Option<u64> {
+    let remaining = locked_balance.saturating_sub(amount);
+    (remaining < required_margin).then(|| required_margin - remaining)
+}
+
+/// A source of an owner's open positions, e.g. the trading program's accounts
+#[async_trait]
+pub trait PositionSource: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn exposure(&self, owner: &Pubkey) -> Result<PositionExposure>;
+}
+
+/// Position accounts of the trading program, found by the owner pubkey stored
+/// at `owner_offset` and read for the little-endian `u64` margin at `margin_offset`.
+///
+/// Every matching account counts as an open position; closed positions are
+/// expected to have their accounts closed.
+pub struct TradingProgramPositions {
+    transaction_builder: Arc<TransactionBuilder>,
+    program_id: Pubkey,
+    owner_offset: usize,
+    margin_offset: usize,
+    account_size: Option<u64>,
+}
+
+impl TradingProgramPositions {
+    /// Right after the 8-byte Anchor discriminator
+    pub const DEFAULT_OWNER_OFFSET: usize = 8;
+
+    pub fn new(transaction_builder: Arc<TransactionBuilder>, program_id: Pubkey, owner_offset: usize, margin_offset: usize) -> Self {
+        Self {
+            transaction_builder,
+            program_id,
+            owner_offset,
+            margin_offset,
+            account_size: None,
+        }
+    }
+
+    /// Only consider accounts of exactly this size, i.e. of the position type
+    pub fn with_account_size(mut self, account_size: u64) -> Self {
+        self.account_size = Some(account_size);
+        self
+    }
+}
+
+#[async_trait]
+impl PositionSource for TradingProgramPositions {
+    fn name(&self) -> &str {
+        "trading_program"
+    }
+
+    async fn exposure(&self, owner: &Pubkey) -> Result<PositionExposure> {
+        let mut filters = vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(self.owner_offset, &owner.to_bytes()))];
+        if let Some(size) = self.account_size {
+            filters.push(RpcFilterType::DataSize(size));
+        }
+        let config = RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                // Only the margin field is needed
+                data_slice: Some(UiDataSliceConfig { offset: self.margin_offset, length: 8 }),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let program_id = self.program_id;
+        let accounts = self.transaction_builder.rpc()
+            .call(RpcMethodClass::Read, |c| c.get_program_accounts_with_config(&program_id, config))
+            .await?;
+
+        let mut exposure = PositionExposure::default();
+        for (address, account) in accounts {
+            let margin: [u8; 8] = account.data.as_slice().try_into()
+                .map_err(|_| ChainError::InvalidAccountData(format!("Position {} has no margin at offset {}", address, self.margin_offset)))?;
+            exposure.open_positions += 1;
+            exposure.required_margin = exposure.required_margin.saturating_add(u64::from_le_bytes(margin));
+        }
+        Ok(exposure)
+    }
+}
+
+/// Checks API unlocks against every configured position source
+pub struct UnlockGuard {
+    sources: Vec<Arc<dyn PositionSource>>,
+    fail_open: bool,
+}
+
+impl UnlockGuard {
+    pub fn new(fail_open: bool) -> Self {
+        Self { sources: Vec::new(), fail_open }
+    }
+
+    pub fn with_source(mut self, source: Arc<dyn PositionSource>) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.sources.is_empty()
+    }
+
+    /// Refuse unlocking `amount` from `vault` when its open positions would be left under-margined
+    pub async fn check_unlock(&self, vault: &Vault, amount: u64) -> Result<()> {
+        if self.sources.is_empty() {
+            return Ok(());
+        }
+        let owner = Pubkey::from_str(&vault.user_pubkey)
+            .map_err(|_| DomainError::Validation(format!("Invalid user pubkey {}", vault.user_pubkey)))?;
+
+        let mut total = PositionExposure::default();
+        for source in &self.sources {
+            match source.exposure(&owner).await {
+                Ok(exposure) => {
+                    total.open_positions += exposure.open_positions;
+                    total.required_margin = total.required_margin.saturating_add(exposure.required_margin);
+                }
+                Err(e) if self.fail_open => {
+                    warn!("Position source {} failed for {}; unlock check skipped it: {}", source.name(), owner, e);
+                }
+                Err(e) => {
+                    warn!("Position source {} failed for {}; refusing unlock: {}", source.name(), owner, e);
+                    return Err(e);
+                }
+            }
+        }
+
+        let locked = vault.locked_balance.max(0) as u64;
+        if let Some(shortfall) = unlock_shortfall(locked, amount, total.required_margin) {
+            warn!("Refused unlock of {} from vault {}: {} open positions need {} locked, {} would remain",
+                  amount, vault.id, total.open_positions, total.required_margin, locked.saturating_sub(amount));
+            return Err(DomainError::UnderMargined(format!(
+                "{} open positions require {} locked; unlocking {} leaves them {} short",
+                total.open_positions, total.required_margin, amount, shortfall,
+            )).into());
+        }
+        if total.open_positions > 0 {
+            info!("Unlock of {} from vault {} leaves its {} open positions margined", amount, vault.id, total.open_positions);
+        }
+        Ok(())
+    }
+}
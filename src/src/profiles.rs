@@ -0,0 +1,104 @@
+//! Named deployment profiles, selected with `PROFILE=`.
+//!
+//! A profile supplies the defaults of the settings that differ between
+//! environments: how strictly requests are validated, the commitment
+//! submissions are confirmed at, per-vault queue limits, approval thresholds,
+//! notice periods and feature flags. Any of them set explicitly in the
+//! environment still wins, so an environment only lists where it departs from
+//! its profile.
+//!
+//! `mainnet` is the default and keeps the values the service has always used;
+//! `devnet` relaxes them for test environments that should not need their own
+//! copy of every setting.
+
+use crate::screening::{ScreeningAction, ScreeningPolicy};
+use serde::{Deserialize, Serialize};
+use solana_sdk::commitment_config::CommitmentLevel;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileName {
+    Devnet,
+    Mainnet,
+}
+
+impl ProfileName {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProfileName::Devnet => "devnet",
+            ProfileName::Mainnet => "mainnet",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeploymentProfile {
+    pub name: ProfileName,
+    /// Commitment the submitter waits for before a transaction counts as landed
+    pub commitment: CommitmentLevel,
+    /// Let unlocks through when open positions cannot be read
+    pub position_check_fail_open: bool,
+    pub screening_policy: ScreeningPolicy,
+    /// Whether vault creation needs a verified identity
+    pub identity_required_for_vault_creation: bool,
+    pub max_concurrent_transactions: usize,
+    pub max_pending_operations_per_vault: i64,
+    /// In mint base units
+    pub max_pending_withdrawal_amount_per_vault: i64,
+    /// Notice given to owners of dormant vaults before a sweep
+    pub dormancy_grace_days: u32,
+    /// Apply pending migrations at startup
+    pub auto_migrate: bool,
+}
+
+impl DeploymentProfile {
+    pub fn mainnet() -> Self {
+        Self {
+            name: ProfileName::Mainnet,
+            commitment: CommitmentLevel::Finalized,
+            position_check_fail_open: false,
+            screening_policy: ScreeningPolicy::default(),
+            identity_required_for_vault_creation: false,
+            max_concurrent_transactions: 5,
+            max_pending_operations_per_vault: 20,
+            max_pending_withdrawal_amount_per_vault: 1_000_000_000_000, // 1M at 6 decimals
+            dormancy_grace_days: 30,
+            auto_migrate: true,
+        }
+    }
+
+    /// Faster confirmations, looser queues and screening that flags rather than holds
+    pub fn devnet() -> Self {
+        Self {
+            name: ProfileName::Devnet,
+            commitment: CommitmentLevel::Confirmed,
+            position_check_fail_open: true,
+            screening_policy: ScreeningPolicy {
+                on_elevated: ScreeningAction::AllowAndFlag,
+                on_severe: ScreeningAction::Block,
+                on_error: ScreeningAction::AllowAndFlag,
+            },
+            identity_required_for_vault_creation: false,
+            max_concurrent_transactions: 10,
+            max_pending_operations_per_vault: 100,
+            max_pending_withdrawal_amount_per_vault: i64::MAX,
+            dormancy_grace_days: 1,
+            auto_migrate: true,
+        }
+    }
+
+    /// The profile named `name`; `None` for an unknown one
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "mainnet" | "mainnet-beta" | "production" => Some(Self::mainnet()),
+            "devnet" | "development" => Some(Self::devnet()),
+            _ => None,
+        }
+    }
+}
+
+impl Default for DeploymentProfile {
+    fn default() -> Self {
+        Self::mainnet()
+    }
+}
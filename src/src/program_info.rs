@@ -0,0 +1,208 @@
+//! Description of the program build the backend is compiled against.
+//!
+//! Instruction and account discriminators and account sizes come from the
+//! linked program crate, so they always match what the backend builds and
+//! decodes. The IDL is the JSON `anchor build` emits for the same checkout;
+//! clients compare its hash with the one they bundle and fetch it again when
+//! it differs.
+
+use crate::error::{Result, VaultError};
+use anchor_lang::Discriminator;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeSet;
+
+/// Instructions of the linked program, by IDL name, with their discriminators
+pub fn instruction_discriminators() -> Vec<(&'static str, [u8; 8])> {
+    use collateral_vault::instruction as ix;
+    vec![
+        ("initialize_vault", ix::InitializeVault::DISCRIMINATOR),
+        ("deposit", ix::Deposit::DISCRIMINATOR),
+        ("deposit_for", ix::DepositFor::DISCRIMINATOR),
+        ("deposit_sol", ix::DepositSol::DISCRIMINATOR),
+        ("deposit_from_associated", ix::DepositFromAssociated::DISCRIMINATOR),
+        ("withdraw", ix::Withdraw::DISCRIMINATOR),
+        ("withdraw_with_memo", ix::WithdrawWithMemo::DISCRIMINATOR),
+        ("withdraw_all", ix::WithdrawAll::DISCRIMINATOR),
+        ("withdraw_sol", ix::WithdrawSol::DISCRIMINATOR),
+        ("withdraw_to_associated", ix::WithdrawToAssociated::DISCRIMINATOR),
+        ("withdraw_with_admin_approval", ix::WithdrawWithAdminApproval::DISCRIMINATOR),
+        ("set_withdrawal_cooldown", ix::SetWithdrawalCooldown::DISCRIMINATOR),
+        ("set_withdrawal_cooldown_with_admin_approval", ix::SetWithdrawalCooldownWithAdminApproval::DISCRIMINATOR),
+        ("request_withdrawal", ix::RequestWithdrawal::DISCRIMINATOR),
+        ("cancel_withdrawal", ix::CancelWithdrawal::DISCRIMINATOR),
+        ("execute_withdrawal", ix::ExecuteWithdrawal::DISCRIMINATOR),
+        ("freeze_vault", ix::FreezeVault::DISCRIMINATOR),
+        ("request_unfreeze", ix::RequestUnfreeze::DISCRIMINATOR),
+        ("unfreeze_vault", ix::UnfreezeVault::DISCRIMINATOR),
+        ("lock_collateral", ix::LockCollateral::DISCRIMINATOR),
+        ("unlock_collateral", ix::UnlockCollateral::DISCRIMINATOR),
+        ("transfer_collateral", ix::TransferCollateral::DISCRIMINATOR),
+        ("transfer_collateral_with_memo", ix::TransferCollateralWithMemo::DISCRIMINATOR),
+        ("transfer_available_collateral", ix::TransferAvailableCollateral::DISCRIMINATOR),
+        ("transfer_collateral_with_admin_approval", ix::TransferCollateralWithAdminApproval::DISCRIMINATOR),
+        ("initiate_transfer", ix::InitiateTransfer::DISCRIMINATOR),
+        ("accept_transfer", ix::AcceptTransfer::DISCRIMINATOR),
+        ("cancel_transfer", ix::CancelTransfer::DISCRIMINATOR),
+        ("open_lock", ix::OpenLock::DISCRIMINATOR),
+        ("release_lock", ix::ReleaseLock::DISCRIMINATOR),
+        ("expire_lock", ix::ExpireLock::DISCRIMINATOR),
+        ("announce_force_unlock", ix::AnnounceForceUnlock::DISCRIMINATOR),
+        ("execute_force_unlock", ix::ExecuteForceUnlock::DISCRIMINATOR),
+        ("cancel_force_unlock", ix::CancelForceUnlock::DISCRIMINATOR),
+        ("transfer_from_lock", ix::TransferFromLock::DISCRIMINATOR),
+        ("initialize_config", ix::InitializeConfig::DISCRIMINATOR),
+        ("set_collateral_mint", ix::SetCollateralMint::DISCRIMINATOR),
+        ("update_max_transaction_amount", ix::UpdateMaxTransactionAmount::DISCRIMINATOR),
+        ("update_max_lock_ratio", ix::UpdateMaxLockRatio::DISCRIMINATOR),
+        ("update_min_free_balance", ix::UpdateMinFreeBalance::DISCRIMINATOR),
+        ("update_minimum_amounts", ix::UpdateMinimumAmounts::DISCRIMINATOR),
+        ("initialize_asset_config", ix::InitializeAssetConfig::DISCRIMINATOR),
+        ("update_haircut", ix::UpdateHaircut::DISCRIMINATOR),
+        ("update_liquidation_penalty", ix::UpdateLiquidationPenalty::DISCRIMINATOR),
+        ("update_keeper_reward", ix::UpdateKeeperReward::DISCRIMINATOR),
+        ("update_withdrawal_fee", ix::UpdateWithdrawalFee::DISCRIMINATOR),
+        ("update_mint_policy", ix::UpdateMintPolicy::DISCRIMINATOR),
+        ("initialize_treasury", ix::InitializeTreasury::DISCRIMINATOR),
+        ("withdraw_treasury", ix::WithdrawTreasury::DISCRIMINATOR),
+        ("initialize_insurance_fund", ix::InitializeInsuranceFund::DISCRIMINATOR),
+        ("fund_insurance", ix::FundInsurance::DISCRIMINATOR),
+        ("draw_insurance", ix::DrawInsurance::DISCRIMINATOR),
+        ("set_pause", ix::SetPause::DISCRIMINATOR),
+        ("migrate_config_layout", ix::MigrateConfigLayout::DISCRIMINATOR),
+        ("recover_foreign_tokens", ix::RecoverForeignTokens::DISCRIMINATOR),
+        ("revoke_token_delegate", ix::RevokeTokenDelegate::DISCRIMINATOR),
+        ("rotate_authority", ix::RotateAuthority::DISCRIMINATOR),
+        ("add_authorized_authority", ix::AddAuthorizedAuthority::DISCRIMINATOR),
+        ("remove_authorized_authority", ix::RemoveAuthorizedAuthority::DISCRIMINATOR),
+        ("migrate_vault_layout", ix::MigrateVaultLayout::DISCRIMINATOR),
+        ("repair_vault_bump", ix::RepairVaultBump::DISCRIMINATOR),
+        ("sync_balance", ix::SyncBalance::DISCRIMINATOR),
+        ("sweep_dormant_vault", ix::SweepDormantVault::DISCRIMINATOR),
+        ("reclaim_dormant_funds", ix::ReclaimDormantFunds::DISCRIMINATOR),
+        ("close_vault", ix::CloseVault::DISCRIMINATOR),
+        ("liquidate", ix::Liquidate::DISCRIMINATOR),
+        ("settle_loss", ix::SettleLoss::DISCRIMINATOR),
+    ]
+}
+
+/// Program-owned accounts with their discriminators and allocated sizes
+pub fn account_layouts() -> Vec<AccountLayout> {
+    use collateral_vault_types::{Vault, ProgramConfig, DormantFunds, LockRecord, EscrowedTransfer, AssetConfig, ForceUnlock};
+    vec![
+        AccountLayout::new("Vault", Vault::DISCRIMINATOR, Vault::SIZE),
+        AccountLayout::new("ProgramConfig", ProgramConfig::DISCRIMINATOR, ProgramConfig::SIZE),
+        AccountLayout::new("DormantFunds", DormantFunds::DISCRIMINATOR, DormantFunds::SIZE),
+        AccountLayout::new("LockRecord", LockRecord::DISCRIMINATOR, LockRecord::SIZE),
+        AccountLayout::new("EscrowedTransfer", EscrowedTransfer::DISCRIMINATOR, EscrowedTransfer::SIZE),
+        AccountLayout::new("AssetConfig", AssetConfig::DISCRIMINATOR, AssetConfig::SIZE),
+        AccountLayout::new("ForceUnlock", ForceUnlock::DISCRIMINATOR, ForceUnlock::SIZE),
+    ]
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountLayout {
+    pub name: String,
+    /// Hex of the 8-byte account discriminator
+    pub discriminator: String,
+    /// Bytes the program allocates for the account
+    pub size: usize,
+}
+
+impl AccountLayout {
+    fn new(name: &str, discriminator: [u8; 8], size: usize) -> Self {
+        Self { name: name.to_string(), discriminator: hex::encode(discriminator), size }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstructionInfo {
+    pub name: String,
+    /// Hex of the 8-byte instruction discriminator
+    pub discriminator: String,
+}
+
+/// What `/program/info` reports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgramInfo {
+    pub program_id: String,
+    /// Version of the program crate the backend is built against
+    pub program_version: String,
+    /// SHA-256 of the served IDL; `None` when no IDL was loaded
+    pub idl_sha256: Option<String>,
+    /// Whether the hash the client sent matches `idl_sha256`; `None` when it sent none
+    pub client_idl_current: Option<bool>,
+    pub instructions: Vec<InstructionInfo>,
+    pub accounts: Vec<AccountLayout>,
+}
+
+impl ProgramInfo {
+    pub fn new(program_id: &Pubkey, idl: Option<&ProgramIdl>, client_idl_sha256: Option<&str>) -> Self {
+        let idl_sha256 = idl.map(|idl| idl.sha256.clone());
+        Self {
+            program_id: program_id.to_string(),
+            program_version: collateral_vault::VERSION.to_string(),
+            client_idl_current: client_idl_sha256.map(|hash| idl_sha256.as_deref() == Some(hash.to_lowercase().as_str())),
+            idl_sha256,
+            instructions: instruction_discriminators()
+                .into_iter()
+                .map(|(name, discriminator)| InstructionInfo { name: name.to_string(), discriminator: hex::encode(discriminator) })
+                .collect(),
+            accounts: account_layouts(),
+        }
+    }
+}
+
+/// IDL JSON as emitted by `anchor build`, with its hash
+#[derive(Debug, Clone)]
+pub struct ProgramIdl {
+    pub json: serde_json::Value,
+    /// Hex SHA-256 of the file bytes
+    pub sha256: String,
+}
+
+impl ProgramIdl {
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let json = serde_json::from_slice(bytes)
+            .map_err(|e| VaultError::Configuration(format!("IDL is not valid JSON: {}", e)))?;
+        Ok(Self { json, sha256: hex::encode(Sha256::digest(bytes)) })
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| VaultError::Configuration(format!("Failed to read IDL {}: {}", path, e)))?;
+        Self::parse(&bytes)
+    }
+
+    /// Instruction names listed in the IDL, in snake_case
+    pub fn instruction_names(&self) -> BTreeSet<String> {
+        self.json["instructions"]
+            .as_array()
+            .map(|ixs| ixs.iter().filter_map(|ix| ix["name"].as_str()).map(to_snake_case).collect())
+            .unwrap_or_default()
+    }
+
+    /// Instructions present on only one side of the IDL and the linked program
+    pub fn mismatches(&self) -> Vec<String> {
+        let idl = self.instruction_names();
+        let linked: BTreeSet<String> = instruction_discriminators().into_iter().map(|(name, _)| name.to_string()).collect();
+        idl.difference(&linked).map(|name| format!("{} is in the IDL but not the linked program", name))
+            .chain(linked.difference(&idl).map(|name| format!("{} is in the linked program but not the IDL", name)))
+            .collect()
+    }
+}
+
+/// Anchor 0.29 IDLs name instructions in camelCase
+fn to_snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            snake.push('_');
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
@@ -0,0 +1,299 @@
+//! Vault creation as a resumable saga.
+//!
+//! reserve row -> submit init tx -> confirm on chain -> activate
+//!
+//! Every step is persisted in `vault_provisioning` before the next one starts,
+//! so a crash or RPC failure at any point leaves a record the monitor (or the
+//! next startup) can pick up and drive forward. The on-chain vault account is
+//! the source of truth: if it exists, the init succeeded, whatever the
+//! transaction status says.
+
+use crate::error::{Result, DomainError};
+use crate::models::{Vault, MintConfig, VaultProvisioning, VaultProvisioningState};
+use crate::transaction_builder::{TransactionBuilder, TransactionSubmitter, TransactionStatus as ChainTransactionStatus};
+use crate::database::{VaultRepository, ProvisioningRepository, AuditRepository, MintRepository};
+use crate::derivation::{derive_vault_pda, derive_token_pda};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{info, warn, error};
+
+/// Init submissions before provisioning is marked failed
+pub const MAX_INIT_ATTEMPTS: i32 = 5;
+
+/// A submitted init that is neither confirmed nor failed after this long has
+/// an expired blockhash and is rebuilt
+const SUBMISSION_TIMEOUT_SECONDS: i64 = 120;
+
+/// Incomplete provisioning records repaired per pass
+const REPAIR_BATCH_SIZE: i32 = 100;
+
+/// A vault together with how far its provisioning has got
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProvisionedVault {
+    pub vault: Vault,
+    /// `None` for vaults created before provisioning was tracked
+    pub provisioning: Option<VaultProvisioning>,
+}
+
+impl ProvisionedVault {
+    pub fn state(&self) -> VaultProvisioningState {
+        self.provisioning
+            .as_ref()
+            .and_then(|p| VaultProvisioningState::parse(&p.state))
+            .unwrap_or(VaultProvisioningState::Active)
+    }
+}
+
+/// Totals for one repair pass
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProvisioningRepairSummary {
+    pub checked: usize,
+    pub activated: usize,
+    pub failed: usize,
+    pub still_pending: usize,
+    pub errors: usize,
+}
+
+/// Drives vault creation through reserve, submit, confirm and activate
+pub struct VaultProvisioner {
+    vault_repo: VaultRepository,
+    provisioning_repo: ProvisioningRepository,
+    audit_repo: AuditRepository,
+    mint_repo: MintRepository,
+    transaction_builder: Arc<TransactionBuilder>,
+    transaction_submitter: Arc<TransactionSubmitter>,
+}
+
+impl VaultProvisioner {
+    pub fn new(
+        pool: sqlx::PgPool,
+        transaction_builder: Arc<TransactionBuilder>,
+        transaction_submitter: Arc<TransactionSubmitter>,
+    ) -> Self {
+        Self {
+            vault_repo: VaultRepository::new(pool.clone()),
+            provisioning_repo: ProvisioningRepository::new(pool.clone()),
+            audit_repo: AuditRepository::new(pool.clone()),
+            mint_repo: MintRepository::new(pool),
+            transaction_builder,
+            transaction_submitter,
+        }
+    }
+
+    /// Create a vault, or resume creating it. Safe to call repeatedly with the
+    /// same arguments: each call picks up from the last persisted step.
+    pub async fn provision(&self, user_pubkey: &str, authority_pubkey: &str, mint: &MintConfig) -> Result<ProvisionedVault> {
+        let user = Pubkey::from_str(user_pubkey)
+            .map_err(|_| DomainError::Validation("Invalid user pubkey".to_string()))?;
+        Pubkey::from_str(authority_pubkey)
+            .map_err(|_| DomainError::Validation("Invalid authority pubkey".to_string()))?;
+        if !mint.enabled {
+            return Err(DomainError::Validation(format!("Mint {} is disabled", mint.symbol)).into());
+        }
+
+        let program_id = self.transaction_builder.program_id();
+        let (vault_pda, _) = derive_vault_pda(&program_id, &user);
+        let (token_pda, _) = derive_token_pda(&program_id, &vault_pda);
+
+        let reserved = self.provisioning_repo.reserve(
+            user_pubkey,
+            &vault_pda.to_string(),
+            &token_pda.to_string(),
+            authority_pubkey,
+            &mint.mint_pubkey,
+        ).await?;
+
+        let provisioning = match reserved {
+            Some(provisioning) => {
+                self.audit_repo.log_event(
+                    "vault_reserved",
+                    Some(user_pubkey),
+                    Some(provisioning.vault_id),
+                    Some(serde_json::json!({
+                        "vault_pubkey": vault_pda.to_string(),
+                        "token_account": token_pda.to_string(),
+                        "authority": authority_pubkey,
+                        "mint": mint.mint_pubkey,
+                    })),
+                    None,
+                ).await?;
+                provisioning
+            }
+            None => {
+                let vault = self.vault_repo.get_vault_by_user(user_pubkey).await?;
+                match self.provisioning_repo.get_by_vault_id(vault.id).await? {
+                    Some(provisioning) if provisioning.authority_pubkey != authority_pubkey => {
+                        return Err(DomainError::VaultAlreadyExists(user_pubkey.to_string()).into());
+                    }
+                    Some(provisioning) => provisioning,
+                    None => return Ok(ProvisionedVault { vault, provisioning: None }),
+                }
+            }
+        };
+
+        let provisioning = self.advance(provisioning).await?;
+        let vault = self.vault_repo.get_vault_by_id(provisioning.vault_id).await?;
+
+        Ok(ProvisionedVault { vault, provisioning: Some(provisioning) })
+    }
+
+    /// Drive every incomplete provisioning record as far as it will go
+    pub async fn repair_incomplete(&self) -> Result<ProvisioningRepairSummary> {
+        let incomplete = self.provisioning_repo.list_incomplete(REPAIR_BATCH_SIZE).await?;
+        let mut summary = ProvisioningRepairSummary::default();
+
+        for provisioning in incomplete {
+            summary.checked += 1;
+            let vault_id = provisioning.vault_id;
+            match self.advance(provisioning).await {
+                Ok(p) => match VaultProvisioningState::parse(&p.state) {
+                    Some(VaultProvisioningState::Active) => summary.activated += 1,
+                    Some(VaultProvisioningState::Failed) => summary.failed += 1,
+                    _ => summary.still_pending += 1,
+                },
+                Err(e) => {
+                    error!("Failed to repair provisioning of vault {}: {}", vault_id, e);
+                    summary.errors += 1;
+                }
+            }
+        }
+
+        if summary.checked > 0 {
+            info!("Vault provisioning repair: {} checked, {} activated, {} failed, {} pending, {} errors",
+                  summary.checked, summary.activated, summary.failed, summary.still_pending, summary.errors);
+        }
+        Ok(summary)
+    }
+
+    /// Run steps until provisioning is terminal or a step makes no progress
+    async fn advance(&self, mut provisioning: VaultProvisioning) -> Result<VaultProvisioning> {
+        loop {
+            let state = VaultProvisioningState::parse(&provisioning.state)
+                .ok_or_else(|| DomainError::InvalidVaultState(format!("Unknown provisioning state: {}", provisioning.state)))?;
+
+            let next = match state {
+                VaultProvisioningState::Active | VaultProvisioningState::Failed => return Ok(provisioning),
+                VaultProvisioningState::Reserved => self.submit_init(&provisioning).await?,
+                VaultProvisioningState::Submitted => self.check_submission(&provisioning).await?,
+                VaultProvisioningState::Confirmed => self.activate(&provisioning).await?,
+            };
+
+            if next.state == provisioning.state {
+                return Ok(next);
+            }
+            provisioning = next;
+        }
+    }
+
+    /// Reserved: send the init transaction, unless the account already exists
+    async fn submit_init(&self, provisioning: &VaultProvisioning) -> Result<VaultProvisioning> {
+        let (user, authority, vault_pda) = self.keys(provisioning)?;
+
+        if self.transaction_builder.account_exists(vault_pda).await? {
+            return self.provisioning_repo
+                .update_state(provisioning.vault_id, VaultProvisioningState::Confirmed.as_str(), None)
+                .await;
+        }
+
+        if provisioning.attempts >= MAX_INIT_ATTEMPTS {
+            warn!("Giving up on vault {} after {} init attempts", provisioning.vault_id, provisioning.attempts);
+            return self.provisioning_repo
+                .update_state(
+                    provisioning.vault_id,
+                    VaultProvisioningState::Failed.as_str(),
+                    Some(&format!("Init not confirmed after {} attempts", provisioning.attempts)),
+                )
+                .await;
+        }
+
+        let mint = self.mint_repo.get_mint(&provisioning.mint_pubkey).await?;
+        let built = self.transaction_builder.build_initialize_vault_tx(user, 0, authority, &mint).await?;
+
+        // Persist the signature first so a crash mid-send can still be traced on chain
+        let signature = built.transaction.signatures[0].to_string();
+        let submitted = self.provisioning_repo.record_submission(provisioning.vault_id, &signature).await?;
+
+        match self.transaction_submitter.submit_transaction(built.transaction, provisioning.vault_id).await {
+            Ok(_) => {
+                self.provisioning_repo
+                    .update_state(provisioning.vault_id, VaultProvisioningState::Confirmed.as_str(), None)
+                    .await
+            }
+            Err(e) => {
+                // The transaction may still land; the submitted step decides
+                warn!("Init transaction for vault {} not confirmed: {}", provisioning.vault_id, e);
+                self.provisioning_repo
+                    .update_state(submitted.vault_id, VaultProvisioningState::Submitted.as_str(), Some(&e.to_string()))
+                    .await
+            }
+        }
+    }
+
+    /// Submitted: resolve the recorded transaction to confirmed or back to reserved
+    async fn check_submission(&self, provisioning: &VaultProvisioning) -> Result<VaultProvisioning> {
+        let (_, _, vault_pda) = self.keys(provisioning)?;
+
+        if self.transaction_builder.account_exists(vault_pda).await? {
+            return self.provisioning_repo
+                .update_state(provisioning.vault_id, VaultProvisioningState::Confirmed.as_str(), None)
+                .await;
+        }
+
+        let status = match &provisioning.init_signature {
+            Some(signature) => self.transaction_submitter.check_transaction_status(signature).await?,
+            None => ChainTransactionStatus::Failed("No init signature recorded".to_string()),
+        };
+
+        let retry_reason = match status {
+            ChainTransactionStatus::Failed(reason) => reason,
+            ChainTransactionStatus::Pending
+                if Utc::now() - provisioning.updated_at > Duration::seconds(SUBMISSION_TIMEOUT_SECONDS) =>
+            {
+                "Init transaction expired before landing".to_string()
+            }
+            // Still in flight
+            ChainTransactionStatus::Pending => return Ok(provisioning.clone()),
+            // Landed but the account is missing; resubmitting will surface the real error
+            ChainTransactionStatus::Confirmed => "Init confirmed but vault account not found".to_string(),
+        };
+
+        self.provisioning_repo
+            .update_state(provisioning.vault_id, VaultProvisioningState::Reserved.as_str(), Some(&retry_reason))
+            .await
+    }
+
+    /// Confirmed: activate the vault row
+    async fn activate(&self, provisioning: &VaultProvisioning) -> Result<VaultProvisioning> {
+        let activated = self.provisioning_repo.activate(provisioning.vault_id).await?;
+        let vault = self.vault_repo.get_vault_by_id(provisioning.vault_id).await?;
+
+        self.audit_repo.log_event(
+            "vault_created",
+            Some(&provisioning.user_pubkey),
+            Some(vault.id),
+            Some(serde_json::json!({
+                "vault_pubkey": vault.vault_pubkey,
+                "token_account": vault.token_account_pubkey,
+                "init_signature": activated.init_signature,
+                "attempts": activated.attempts,
+            })),
+            None,
+        ).await?;
+
+        Ok(activated)
+    }
+
+    /// (user, authority, vault PDA) for a provisioning record
+    fn keys(&self, provisioning: &VaultProvisioning) -> Result<(Pubkey, Pubkey, Pubkey)> {
+        let user = Pubkey::from_str(&provisioning.user_pubkey)
+            .map_err(|_| DomainError::Validation(format!("Invalid user pubkey: {}", provisioning.user_pubkey)))?;
+        let authority = Pubkey::from_str(&provisioning.authority_pubkey)
+            .map_err(|_| DomainError::Validation(format!("Invalid authority pubkey: {}", provisioning.authority_pubkey)))?;
+        let (vault_pda, _) = derive_vault_pda(&self.transaction_builder.program_id(), &user);
+
+        Ok((user, authority, vault_pda))
+    }
+}
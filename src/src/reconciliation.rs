@@ -0,0 +1,728 @@
+use crate::error::{Result, ChainError, DomainError, VaultError};
+use crate::models::{Vault, TransactionRecord, TransactionType, ReconciliationMode, ReconciliationRecord, BalanceSnapshot};
+use crate::balance_tracker::{BalanceTracker, Discrepancy, DiscrepancyCode, DiscrepancySeverity, Remediation, ValueSource};
+use crate::transaction_builder::TransactionBuilder;
+use crate::rpc::RpcMethodClass;
+use crate::database::{VaultRepository, TransactionRepository, ReconciliationRepository, SnapshotRepository};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+use uuid::Uuid;
+use tracing::{info, warn, error};
+
+/// Outbox event written for every inconsistent reconciliation
+pub const RECONCILIATION_DISCREPANCY_EVENT: &str = "reconciliation_discrepancy";
+
+/// Snapshot history checked by the trajectory check; earlier records are
+/// still replayed, folded into the first interval
+const TRAJECTORY_LOOKBACK_DAYS: i64 = 30;
+
+/// Individual checks a reconciliation can run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReconciliationCheck {
+    /// Database vs balance cache
+    Cache,
+    /// total == locked + available in the database
+    Invariant,
+    /// Database vs the on-chain vault account
+    VaultAccount,
+    /// On-chain vault total vs the SPL token account holding the funds
+    TokenAccount,
+    /// Database balances vs a replay of confirmed transaction records
+    LedgerReplay,
+    /// Balance changes between snapshots vs the confirmed records in each interval;
+    /// `expected` is the change the records imply, `observed` the change seen
+    Trajectory,
+    /// Recent successful on-chain transactions touching the vault with no matching record
+    EventGaps,
+    /// On-chain deposit/withdrawal counters vs confirmed records since the counters started
+    Counters,
+}
+
+impl ReconciliationCheck {
+    /// Sources of a finding's `expected` and `observed` values
+    pub fn sources(&self) -> (ValueSource, ValueSource) {
+        use ValueSource::*;
+        match self {
+            ReconciliationCheck::Cache => (Database, Cache),
+            ReconciliationCheck::Invariant => (Database, Database),
+            ReconciliationCheck::VaultAccount => (Database, Chain),
+            ReconciliationCheck::TokenAccount => (Chain, TokenAccount),
+            ReconciliationCheck::LedgerReplay => (Database, Ledger),
+            ReconciliationCheck::Trajectory => (Ledger, Snapshots),
+            ReconciliationCheck::EventGaps => (Ledger, Chain),
+            ReconciliationCheck::Counters => (Ledger, Chain),
+        }
+    }
+}
+
+impl ReconciliationMode {
+    /// Checks run by this mode, cheapest first
+    pub fn checks(&self) -> &'static [ReconciliationCheck] {
+        use ReconciliationCheck::*;
+        match self {
+            ReconciliationMode::Quick => &[Cache, Invariant],
+            ReconciliationMode::Standard => &[Cache, Invariant, VaultAccount],
+            ReconciliationMode::Deep => &[Cache, Invariant, VaultAccount, TokenAccount, LedgerReplay, Trajectory, EventGaps, Counters],
+            ReconciliationMode::Ledger => &[Invariant, LedgerReplay, Trajectory],
+        }
+    }
+}
+
+/// One disagreement found by a check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconciliationFinding {
+    pub check: ReconciliationCheck,
+    pub code: DiscrepancyCode,
+    pub field: String,
+    /// Value the reference source holds
+    pub expected: i64,
+    pub expected_source: ValueSource,
+    /// Value the check observed
+    pub observed: i64,
+    pub observed_source: ValueSource,
+    pub severity: DiscrepancySeverity,
+    pub remediation: Remediation,
+    /// Human-readable summary; automation should use `code` and the values instead
+    pub issue: String,
+    /// Transaction records implicated by the finding
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub record_ids: Vec<Uuid>,
+}
+
+impl ReconciliationFinding {
+    /// Sources follow from the check; severity and remediation from the code
+    pub fn new(check: ReconciliationCheck, code: DiscrepancyCode, field: &str, expected: i64, observed: i64, issue: String) -> Self {
+        let (expected_source, observed_source) = check.sources();
+        Self {
+            check,
+            code,
+            field: field.to_string(),
+            expected,
+            expected_source,
+            observed,
+            observed_source,
+            severity: code.severity(),
+            remediation: code.remediation(),
+            issue,
+            record_ids: Vec::new(),
+        }
+    }
+
+    pub fn with_records(mut self, record_ids: Vec<Uuid>) -> Self {
+        self.record_ids = record_ids;
+        self
+    }
+}
+
+/// Outcome of reconciling one vault
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconciliationReport {
+    pub vault_id: Uuid,
+    pub mode: ReconciliationMode,
+    pub is_consistent: bool,
+    pub findings: Vec<ReconciliationFinding>,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+}
+
+impl ReconciliationReport {
+    /// Findings a scheduled pass alerts on
+    pub fn alerts(&self) -> impl Iterator<Item = &ReconciliationFinding> {
+        self.findings.iter().filter(|f| f.severity == DiscrepancySeverity::Critical)
+    }
+}
+
+/// Totals for a scheduled pass over many vaults
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReconciliationRunSummary {
+    pub vaults_checked: usize,
+    pub inconsistent_vaults: usize,
+    pub total_findings: usize,
+    pub errors: usize,
+}
+
+impl ReconciliationRunSummary {
+    /// Count one vault's outcome, logging its alerts or failure
+    pub fn record(&mut self, vault_id: Uuid, mode: ReconciliationMode, outcome: std::result::Result<&ReconciliationReport, &VaultError>) {
+        self.vaults_checked += 1;
+        match outcome {
+            Ok(report) => {
+                if !report.is_consistent {
+                    self.inconsistent_vaults += 1;
+                    self.total_findings += report.findings.len();
+                    for finding in report.alerts() {
+                        error!(vault_id = %vault_id, code = finding.code.as_str(), remediation = finding.remediation.as_str(),
+                               expected = finding.expected, observed = finding.observed,
+                               "CRITICAL: Vault {} has critical discrepancy: {}", vault_id, finding.issue);
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Failed {} reconciliation of vault {}: {}", mode.as_str(), vault_id, e);
+                self.errors += 1;
+            }
+        }
+    }
+}
+
+/// Compare database balances with balances observed elsewhere, field by field
+pub fn compare_balances(
+    check: ReconciliationCheck,
+    code: DiscrepancyCode,
+    database: (i64, i64, i64),
+    observed: (i64, i64, i64),
+) -> Vec<ReconciliationFinding> {
+    [
+        ("total_balance", database.0, observed.0),
+        ("locked_balance", database.1, observed.1),
+        ("available_balance", database.2, observed.2),
+    ]
+    .into_iter()
+    .filter(|(_, expected, observed)| expected != observed)
+    .map(|(field, expected, observed)| ReconciliationFinding::new(
+        check,
+        code,
+        field,
+        expected,
+        observed,
+        format!("{:?} {} mismatch: DB={}, observed={}", check, field, expected, observed),
+    ))
+    .collect()
+}
+
+/// Change one confirmed record makes to (total, locked).
+///
+/// Transfers carry a signed amount: outgoing (negative) transfers leave the
+/// source's locked balance, incoming ones land in the destination's available balance.
+pub fn record_effect(record: &TransactionRecord) -> (i64, i64) {
+    match record.transaction_type {
+        TransactionType::Deposit => (record.amount, 0),
+        TransactionType::Withdraw => (-record.amount, 0),
+        TransactionType::Lock => (0, record.amount),
+        TransactionType::Unlock => (0, -record.amount),
+        TransactionType::Transfer if record.amount < 0 => (record.amount, record.amount),
+        TransactionType::Transfer => (record.amount, 0),
+        TransactionType::Initialize => (0, 0),
+    }
+}
+
+/// Replay confirmed records into (total, locked)
+pub fn replay_ledger(records: &[TransactionRecord]) -> (i64, i64) {
+    records.iter().map(record_effect).fold((0, 0), |(total, locked), (dt, dl)| (total + dt, locked + dl))
+}
+
+/// Most intermediate states returned by one ledger replay
+pub const MAX_REPLAY_STEPS: u64 = 1000;
+
+/// A vault's balances right after one confirmed record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayStep {
+    /// 1-based position of the record in the vault's ledger, in confirmation order
+    pub seq: u64,
+    pub transaction_id: Uuid,
+    pub transaction_type: TransactionType,
+    pub amount: i64,
+    pub tx_signature: Option<String>,
+    pub confirmed_at: DateTime<Utc>,
+    pub total_change: i64,
+    pub locked_change: i64,
+    pub total_balance: i64,
+    pub locked_balance: i64,
+    pub available_balance: i64,
+    /// Impossible states this record produced, e.g. a negative balance
+    pub anomalies: Vec<String>,
+}
+
+/// The vault's ledger replayed up to a sequence number
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerReplay {
+    pub vault_id: Uuid,
+    /// Confirmed records in the whole ledger
+    pub ledger_length: u64,
+    pub from: u64,
+    pub until: u64,
+    /// States after each record in `from..=until`
+    pub steps: Vec<ReplayStep>,
+    /// First record, anywhere up to `until`, that produced an impossible state
+    pub first_anomaly_seq: Option<u64>,
+    /// The vault's current balances, for comparison with the last step
+    pub vault_total_balance: i64,
+    pub vault_locked_balance: i64,
+    pub vault_available_balance: i64,
+    /// Whether the full replay reproduces the vault's balances; `None` when stopped early
+    pub matches_vault: Option<bool>,
+}
+
+fn replay_anomalies(total: i64, locked: i64) -> Vec<String> {
+    let mut anomalies = Vec::new();
+    if total < 0 {
+        anomalies.push(format!("total balance {} is negative", total));
+    }
+    if locked < 0 {
+        anomalies.push(format!("locked balance {} is negative", locked));
+    }
+    if locked > total {
+        anomalies.push(format!("locked balance {} exceeds total {}", locked, total));
+    }
+    anomalies
+}
+
+/// Replay a vault's confirmed records up to `until` (the whole ledger when
+/// `None`), returning the state after each record from `from`.
+///
+/// Records are ordered by confirmation time (`updated_at`). The window holds
+/// at most `MAX_REPLAY_STEPS` states; without `from` it ends at `until`.
+pub fn build_ledger_replay(vault: &Vault, records: &[TransactionRecord], from: Option<u64>, until: Option<u64>) -> Result<LedgerReplay> {
+    let mut records: Vec<&TransactionRecord> = records.iter().collect();
+    records.sort_by_key(|r| r.updated_at);
+
+    let ledger_length = records.len() as u64;
+    let until = until.unwrap_or(ledger_length);
+    if until > ledger_length {
+        return Err(DomainError::Validation(format!("until {} is past the end of the ledger ({} records)", until, ledger_length)).into());
+    }
+    let from = from.unwrap_or_else(|| until.saturating_sub(MAX_REPLAY_STEPS - 1).max(1));
+    if from == 0 || (from > until && until > 0) {
+        return Err(DomainError::Validation("from must be between 1 and until".to_string()).into());
+    }
+    if until >= from && until - from >= MAX_REPLAY_STEPS {
+        return Err(DomainError::Validation(format!("At most {} steps per replay", MAX_REPLAY_STEPS)).into());
+    }
+
+    let (mut total, mut locked) = (0i64, 0i64);
+    let mut steps = Vec::new();
+    let mut first_anomaly_seq = None;
+    for (index, record) in records.iter().take(until as usize).enumerate() {
+        let seq = index as u64 + 1;
+        let (total_change, locked_change) = record_effect(record);
+        total += total_change;
+        locked += locked_change;
+
+        let anomalies = replay_anomalies(total, locked);
+        if !anomalies.is_empty() && first_anomaly_seq.is_none() {
+            first_anomaly_seq = Some(seq);
+        }
+        if seq >= from {
+            steps.push(ReplayStep {
+                seq,
+                transaction_id: record.id,
+                transaction_type: record.transaction_type.clone(),
+                amount: record.amount,
+                tx_signature: record.tx_signature.clone(),
+                confirmed_at: record.updated_at,
+                total_change,
+                locked_change,
+                total_balance: total,
+                locked_balance: locked,
+                available_balance: total - locked,
+                anomalies,
+            });
+        }
+    }
+
+    Ok(LedgerReplay {
+        vault_id: vault.id,
+        ledger_length,
+        from,
+        until,
+        steps,
+        first_anomaly_seq,
+        vault_total_balance: vault.total_balance,
+        vault_locked_balance: vault.locked_balance,
+        vault_available_balance: vault.available_balance,
+        matches_vault: (until == ledger_length)
+            .then(|| total == vault.total_balance && locked == vault.locked_balance && total - locked == vault.available_balance),
+    })
+}
+
+/// Balances observed at a point in time: a snapshot, or the vault row itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceCheckpoint {
+    pub at: DateTime<Utc>,
+    pub total_balance: i64,
+    pub locked_balance: i64,
+}
+
+impl From<&BalanceSnapshot> for BalanceCheckpoint {
+    fn from(snapshot: &BalanceSnapshot) -> Self {
+        Self {
+            at: snapshot.snapshot_time,
+            total_balance: snapshot.total_balance,
+            locked_balance: snapshot.locked_balance,
+        }
+    }
+}
+
+/// Walk the checkpoints oldest first, replaying the records confirmed in each
+/// interval, and flag every interval whose observed balance change the records
+/// do not explain.
+///
+/// Records are placed by confirmation time (`updated_at`). Only changes in the
+/// gap between ledger and balances are reported, so one bad record is flagged
+/// once, in the interval where it landed, rather than at every later checkpoint.
+pub fn trace_balance_trajectory(records: &[TransactionRecord], checkpoints: &[BalanceCheckpoint]) -> Vec<ReconciliationFinding> {
+    let mut records: Vec<&TransactionRecord> = records.iter().collect();
+    records.sort_by_key(|r| r.updated_at);
+
+    let mut findings = Vec::new();
+    let mut next = 0;
+    let mut replayed = (0i64, 0i64);
+    let mut gap = (0i64, 0i64);
+    let mut interval_start: Option<DateTime<Utc>> = None;
+
+    for checkpoint in checkpoints {
+        let first = next;
+        while next < records.len() && records[next].updated_at <= checkpoint.at {
+            next += 1;
+        }
+        let interval = &records[first..next];
+
+        let delta = interval.iter().map(|r| record_effect(r)).fold((0, 0), |(t, l), (dt, dl)| (t + dt, l + dl));
+        replayed = (replayed.0 + delta.0, replayed.1 + delta.1);
+        let current_gap = (checkpoint.total_balance - replayed.0, checkpoint.locked_balance - replayed.1);
+
+        for (field, before, after, ledger_change) in [
+            ("total_balance", gap.0, current_gap.0, delta.0),
+            ("locked_balance", gap.1, current_gap.1, delta.1),
+        ] {
+            if before == after {
+                continue;
+            }
+            let observed_change = ledger_change + (after - before);
+            let since = interval_start.map(|at| at.to_rfc3339()).unwrap_or_else(|| "vault creation".to_string());
+            findings.push(ReconciliationFinding::new(
+                ReconciliationCheck::Trajectory,
+                DiscrepancyCode::UnexplainedBalanceChange,
+                field,
+                ledger_change,
+                observed_change,
+                format!("{} changed by {} between {} and {}, but the {} confirmed records in that interval account for {}",
+                        field, observed_change, since, checkpoint.at.to_rfc3339(), interval.len(), ledger_change),
+            ).with_records(interval.iter().map(|r| r.id).collect()));
+        }
+
+        gap = current_gap;
+        interval_start = Some(checkpoint.at);
+    }
+
+    findings
+}
+
+/// Compare the vault account's counters with the confirmed deposits and
+/// withdrawals since `counters_since`.
+///
+/// Records are placed by confirmation time, which trails the on-chain
+/// timestamp, so an operation landing in the same moments as a layout
+/// migration can show as off by one. A legacy vault has no counters and
+/// yields a single finding suggesting migration.
+pub fn compare_counters(account: &collateral_vault_types::Vault, records: &[TransactionRecord]) -> Vec<ReconciliationFinding> {
+    let check = ReconciliationCheck::Counters;
+    if !account.counters_tracked() {
+        return vec![ReconciliationFinding::new(
+            check,
+            DiscrepancyCode::LegacyLayout,
+            "counters_since",
+            1,
+            0,
+            "Vault account has the legacy layout without counters; migrate it to enable this check".to_string(),
+        )];
+    }
+
+    let since = DateTime::<Utc>::from_timestamp(account.counters_since, 0).unwrap_or_default();
+    let counted: Vec<&TransactionRecord> = records.iter().filter(|r| r.updated_at >= since).collect();
+    let deposits: Vec<&TransactionRecord> = counted.iter().copied()
+        .filter(|r| matches!(r.transaction_type, TransactionType::Deposit))
+        .collect();
+    let withdrawals: Vec<&TransactionRecord> = counted.iter().copied()
+        .filter(|r| matches!(r.transaction_type, TransactionType::Withdraw))
+        .collect();
+    let volume = |records: &[&TransactionRecord]| records.iter().map(|r| r.amount).sum::<i64>();
+
+    [
+        ("deposit_count", deposits.len() as i64, account.deposit_count as i64),
+        ("total_deposited", volume(&deposits), account.total_deposited as i64),
+        ("withdraw_count", withdrawals.len() as i64, account.withdraw_count as i64),
+        ("total_withdrawn", volume(&withdrawals), account.total_withdrawn as i64),
+    ]
+    .into_iter()
+    .filter(|(_, expected, observed)| expected != observed)
+    .map(|(field, expected, observed)| ReconciliationFinding::new(
+        check,
+        DiscrepancyCode::CounterMismatch,
+        field,
+        expected,
+        observed,
+        format!("{} mismatch: confirmed records imply {}, vault account holds {}", field, expected, observed),
+    ))
+    .collect()
+}
+
+/// On-chain signatures with no matching transaction record
+pub fn find_event_gaps(chain_signatures: &[String], recorded_signatures: &[String]) -> Vec<String> {
+    let recorded: HashSet<&str> = recorded_signatures.iter().map(String::as_str).collect();
+    chain_signatures
+        .iter()
+        .filter(|sig| !recorded.contains(sig.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// Runs reconciliation at a chosen depth and persists the outcome
+pub struct Reconciler {
+    vault_repo: VaultRepository,
+    transaction_repo: TransactionRepository,
+    reconciliation_repo: ReconciliationRepository,
+    snapshot_repo: SnapshotRepository,
+    balance_tracker: Arc<BalanceTracker>,
+    transaction_builder: Arc<TransactionBuilder>,
+}
+
+impl Reconciler {
+    pub fn new(
+        pool: sqlx::PgPool,
+        balance_tracker: Arc<BalanceTracker>,
+        transaction_builder: Arc<TransactionBuilder>,
+    ) -> Self {
+        Self {
+            vault_repo: VaultRepository::new(pool.clone()),
+            transaction_repo: TransactionRepository::new(pool.clone()),
+            reconciliation_repo: ReconciliationRepository::new(pool.clone()),
+            snapshot_repo: SnapshotRepository::new(pool),
+            balance_tracker,
+            transaction_builder,
+        }
+    }
+
+    /// Reconcile one vault and persist the result
+    pub async fn reconcile(&self, vault_id: Uuid, mode: ReconciliationMode) -> Result<ReconciliationReport> {
+        let started_at = Utc::now();
+        let vault = self.vault_repo.get_vault_by_id(vault_id).await?;
+
+        let findings = run_checks(self, &vault, mode).await?;
+
+        let report = ReconciliationReport {
+            vault_id,
+            mode,
+            is_consistent: findings.is_empty(),
+            findings,
+            started_at,
+            completed_at: Utc::now(),
+        };
+
+        self.reconciliation_repo.record_result(
+            vault_id,
+            mode.as_str(),
+            report.is_consistent,
+            serde_json::to_value(&report.findings).unwrap_or_else(|_| serde_json::json!([])),
+            report.started_at,
+            report.completed_at,
+            RECONCILIATION_DISCREPANCY_EVENT,
+        ).await?;
+
+        if !report.is_consistent {
+            warn!("{} reconciliation found {} findings for vault {}", mode.as_str(), report.findings.len(), vault_id);
+        }
+
+        Ok(report)
+    }
+
+    /// Reconcile the vault owning an on-chain address, its PDA or its token account
+    pub async fn reconcile_address(&self, address: &str, mode: ReconciliationMode) -> Result<ReconciliationReport> {
+        let vault = match self.vault_repo.get_vault_by_vault_pubkey(address).await {
+            Err(e) if e.is_not_found() => self.vault_repo.get_vault_by_token_account(address).await?,
+            found => found?,
+        };
+        self.reconcile(vault.id, mode).await
+    }
+
+    /// Reconcile a set of vaults; per-vault failures are counted, not fatal
+    pub async fn reconcile_many(&self, vaults: &[Vault], mode: ReconciliationMode) -> ReconciliationRunSummary {
+        let mut summary = ReconciliationRunSummary::default();
+
+        for vault in vaults {
+            let outcome = self.reconcile(vault.id, mode).await;
+            summary.record(vault.id, mode, outcome.as_ref());
+        }
+
+        info!("{} reconciliation: {} vaults, {} inconsistent, {} findings, {} errors",
+              mode.as_str(), summary.vaults_checked, summary.inconsistent_vaults, summary.total_findings, summary.errors);
+        summary
+    }
+
+    /// Persisted results for a vault, newest first
+    pub async fn recent_results(&self, vault_id: Uuid, limit: i32) -> Result<Vec<ReconciliationRecord>> {
+        self.reconciliation_repo.get_vault_results(vault_id, limit).await
+    }
+}
+
+/// Everything the checks read, from the database, the balance cache and the
+/// cluster. `Reconciler` reads the live ones; `crate::simulation` scripts them.
+#[async_trait]
+pub trait ReconciliationSource: Send + Sync {
+    /// Time the trajectory check closes on
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Database vs cache, dropping a cached entry that disagrees
+    async fn cache_discrepancies(&self, vault: &Vault) -> Result<Vec<Discrepancy>>;
+
+    async fn vault_account(&self, vault: &Vault) -> Result<collateral_vault_types::Vault>;
+
+    async fn token_account_balance(&self, vault: &Vault) -> Result<u64>;
+
+    /// Recent successful transactions touching the vault account
+    async fn recent_signatures(&self, vault: &Vault) -> Result<Vec<String>>;
+
+    async fn confirmed_ledger(&self, vault_id: Uuid) -> Result<Vec<TransactionRecord>>;
+
+    async fn snapshot_history(&self, vault_id: Uuid, since: DateTime<Utc>) -> Result<Vec<BalanceSnapshot>>;
+}
+
+#[async_trait]
+impl ReconciliationSource for Reconciler {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    async fn cache_discrepancies(&self, vault: &Vault) -> Result<Vec<Discrepancy>> {
+        Ok(self.balance_tracker.reconcile_balances(vault.id).await?.discrepancies)
+    }
+
+    async fn vault_account(&self, vault: &Vault) -> Result<collateral_vault_types::Vault> {
+        self.transaction_builder.fetch_vault_account(parse_pubkey(&vault.vault_pubkey)?, RpcMethodClass::Snapshot).await
+    }
+
+    async fn token_account_balance(&self, vault: &Vault) -> Result<u64> {
+        self.transaction_builder
+            .fetch_token_account_balance(parse_pubkey(&vault.token_account_pubkey)?, RpcMethodClass::Snapshot).await
+    }
+
+    async fn recent_signatures(&self, vault: &Vault) -> Result<Vec<String>> {
+        self.transaction_builder
+            .fetch_recent_signatures(parse_pubkey(&vault.vault_pubkey)?, RpcMethodClass::Snapshot).await
+    }
+
+    async fn confirmed_ledger(&self, vault_id: Uuid) -> Result<Vec<TransactionRecord>> {
+        self.transaction_repo.get_confirmed_ledger(vault_id).await
+    }
+
+    async fn snapshot_history(&self, vault_id: Uuid, since: DateTime<Utc>) -> Result<Vec<BalanceSnapshot>> {
+        self.snapshot_repo.get_snapshot_history(vault_id, since).await
+    }
+}
+
+/// Run every check of `mode` against `vault`, cheapest first
+pub async fn run_checks(source: &dyn ReconciliationSource, vault: &Vault, mode: ReconciliationMode) -> Result<Vec<ReconciliationFinding>> {
+    let mut findings = Vec::new();
+    for check in mode.checks() {
+        findings.extend(run_check(source, *check, vault).await?);
+    }
+    Ok(findings)
+}
+
+async fn run_check(source: &dyn ReconciliationSource, check: ReconciliationCheck, vault: &Vault) -> Result<Vec<ReconciliationFinding>> {
+    let database = (vault.total_balance, vault.locked_balance, vault.available_balance);
+
+    match check {
+        ReconciliationCheck::Cache => {
+            // The invariant is reported by its own check
+            Ok(source.cache_discrepancies(vault).await?
+                .into_iter()
+                .filter(|d| d.field != "balance_invariant")
+                .map(|d| ReconciliationFinding::new(check, d.code, &d.field, d.database_value, d.cached_value, d.issue))
+                .collect())
+        }
+        ReconciliationCheck::Invariant => {
+            let sum = vault.locked_balance + vault.available_balance;
+            if vault.total_balance == sum {
+                return Ok(Vec::new());
+            }
+            Ok(vec![ReconciliationFinding::new(
+                check,
+                DiscrepancyCode::InvariantViolation,
+                "balance_invariant",
+                vault.total_balance,
+                sum,
+                format!("Balance invariant violated: total={} != locked={} + available={}",
+                        vault.total_balance, vault.locked_balance, vault.available_balance),
+            )])
+        }
+        ReconciliationCheck::VaultAccount => {
+            let account = source.vault_account(vault).await?;
+            Ok(compare_balances(check, DiscrepancyCode::ChainBalanceMismatch, database, account.balances().as_signed()))
+        }
+        ReconciliationCheck::TokenAccount => {
+            let account = source.vault_account(vault).await?;
+            let held = source.token_account_balance(vault).await? as i64;
+
+            // Extra tokens (e.g. stray transfers) are recoverable; a shortfall is not
+            if held >= account.total_balance as i64 {
+                return Ok(Vec::new());
+            }
+            Ok(vec![ReconciliationFinding::new(
+                check,
+                DiscrepancyCode::TokenAccountShortfall,
+                "token_account_balance",
+                account.total_balance as i64,
+                held,
+                format!("Token account holds {} but vault records {}", held, account.total_balance),
+            )])
+        }
+        ReconciliationCheck::LedgerReplay => {
+            let ledger = source.confirmed_ledger(vault.id).await?;
+            let (total, locked) = replay_ledger(&ledger);
+            Ok(compare_balances(check, DiscrepancyCode::LedgerMismatch, database, (total, locked, total - locked)))
+        }
+        ReconciliationCheck::Trajectory => {
+            let ledger = source.confirmed_ledger(vault.id).await?;
+            let now = source.now();
+            let since = now - Duration::days(TRAJECTORY_LOOKBACK_DAYS);
+            let mut checkpoints: Vec<BalanceCheckpoint> = source
+                .snapshot_history(vault.id, since).await?
+                .iter()
+                .map(BalanceCheckpoint::from)
+                .collect();
+            // The vault row closes the trajectory, covering records confirmed after the last snapshot
+            checkpoints.push(BalanceCheckpoint {
+                at: now,
+                total_balance: vault.total_balance,
+                locked_balance: vault.locked_balance,
+            });
+            Ok(trace_balance_trajectory(&ledger, &checkpoints))
+        }
+        ReconciliationCheck::EventGaps => {
+            let chain_signatures = source.recent_signatures(vault).await?;
+            let recorded: Vec<String> = source.confirmed_ledger(vault.id).await?
+                .into_iter()
+                .filter_map(|r| r.tx_signature)
+                .collect();
+
+            Ok(find_event_gaps(&chain_signatures, &recorded)
+                .into_iter()
+                .map(|signature| ReconciliationFinding::new(
+                    check,
+                    DiscrepancyCode::UnrecordedTransaction,
+                    "signature",
+                    0,
+                    1,
+                    format!("On-chain transaction {} has no confirmed record", signature),
+                ))
+                .collect())
+        }
+        ReconciliationCheck::Counters => {
+            let account = source.vault_account(vault).await?;
+            let ledger = source.confirmed_ledger(vault.id).await?;
+            Ok(compare_counters(&account, &ledger))
+        }
+    }
+}
+
+fn parse_pubkey(value: &str) -> Result<Pubkey> {
+    Pubkey::from_str(value)
+        .map_err(|_| ChainError::InvalidAccountData(format!("Invalid pubkey: {}", value)).into())
+}
@@ -0,0 +1,390 @@
+//! Request budgeting for Solana RPC endpoints.
+//!
+//! Every RPC call goes through a `BudgetedRpcClient`, which takes a token from
+//! the endpoint's bucket and from the bucket of the call's method class before
+//! sending. Submissions may drain the endpoint bucket; reads and snapshots have
+//! to leave a reserve behind for them. When the provider answers 429 the
+//! endpoint rate is halved and calls pause for a backoff, then the rate climbs
+//! back as calls succeed. Snapshot calls never queue behind a throttled
+//! endpoint: they are shed with `ChainError::RateLimited` and the caller skips
+//! that round.
+
+use crate::error::{ChainError, Result};
+use solana_client::client_error::{ClientError, Result as ClientResult};
+use solana_client::rpc_client::RpcClient;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// Hosts of the Solana Foundation's public endpoints
+const PUBLIC_ENDPOINT_HOSTS: [&str; 3] = [
+    "api.mainnet-beta.solana.com",
+    "api.devnet.solana.com",
+    "api.testnet.solana.com",
+];
+
+/// Backoff after the first 429, doubled for each consecutive one
+const INITIAL_THROTTLE_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_THROTTLE_BACKOFF: Duration = Duration::from_secs(30);
+
+/// The throttled rate never drops below this fraction of the configured one
+const MIN_RATE_FRACTION: f64 = 0.1;
+
+/// Fraction of the configured rate regained per successful call
+const RATE_RECOVERY_STEP: f64 = 0.05;
+
+/// What a call is for, which decides its share of the budget and whether it may queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RpcMethodClass {
+    /// Getting a transaction landed: blockhash, send, confirm, signature status
+    Submit,
+    /// Account reads serving requests and background jobs
+    Read,
+    /// Periodic snapshot and reconciliation reads; shed first
+    Snapshot,
+}
+
+impl RpcMethodClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RpcMethodClass::Submit => "submit",
+            RpcMethodClass::Read => "read",
+            RpcMethodClass::Snapshot => "snapshot",
+        }
+    }
+}
+
+/// Request limits of one RPC endpoint
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcLimits {
+    pub requests_per_second: f64,
+    pub burst: u32,
+    /// Fraction of the endpoint rate reads may use on their own
+    pub read_share: f64,
+    /// Fraction of the endpoint rate snapshots may use on their own
+    pub snapshot_share: f64,
+    /// Endpoint tokens snapshots must leave for submissions; reads leave half
+    pub submission_reserve: f64,
+    /// Longest a snapshot call waits for budget before it is shed
+    pub max_snapshot_wait: Duration,
+}
+
+impl RpcLimits {
+    /// Published limits of the public endpoints (100 requests per 10 seconds
+    /// per IP), or a private-provider default for anything else
+    pub fn for_endpoint(url: &str) -> Self {
+        if Self::is_public_endpoint(url) {
+            Self {
+                requests_per_second: 10.0,
+                burst: 40,
+                submission_reserve: 10.0,
+                ..Self::default()
+            }
+        } else {
+            Self::default()
+        }
+    }
+
+    pub fn is_public_endpoint(url: &str) -> bool {
+        let host = url
+            .split("://")
+            .last()
+            .unwrap_or(url)
+            .split(['/', ':'])
+            .next()
+            .unwrap_or_default();
+        PUBLIC_ENDPOINT_HOSTS.contains(&host)
+    }
+
+    /// Override the rate and burst, e.g. for a paid plan
+    pub fn with_rate(self, requests_per_second: f64, burst: u32) -> Self {
+        Self {
+            requests_per_second,
+            burst,
+            submission_reserve: self.submission_reserve.min(burst as f64 / 2.0),
+            ..self
+        }
+    }
+
+    fn share(&self, class: RpcMethodClass) -> f64 {
+        match class {
+            RpcMethodClass::Submit => 1.0,
+            RpcMethodClass::Read => self.read_share,
+            RpcMethodClass::Snapshot => self.snapshot_share,
+        }
+    }
+
+    fn reserve(&self, class: RpcMethodClass) -> f64 {
+        match class {
+            RpcMethodClass::Submit => 0.0,
+            RpcMethodClass::Read => self.submission_reserve / 2.0,
+            RpcMethodClass::Snapshot => self.submission_reserve,
+        }
+    }
+}
+
+impl Default for RpcLimits {
+    fn default() -> Self {
+        Self {
+            requests_per_second: 50.0,
+            burst: 100,
+            read_share: 0.7,
+            snapshot_share: 0.3,
+            submission_reserve: 20.0,
+            max_snapshot_wait: Duration::from_secs(2),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_second: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: f64, refill_per_second: f64, now: Instant) -> Self {
+        Self { capacity, tokens: capacity, refill_per_second, refilled_at: now }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_second).min(self.capacity);
+        self.refilled_at = now;
+    }
+
+    /// Time until one token is available above `reserve`
+    fn wait_for(&self, reserve: f64) -> Duration {
+        let missing = reserve + 1.0 - self.tokens;
+        if missing <= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(missing / self.refill_per_second.max(f64::EPSILON))
+        }
+    }
+}
+
+/// Outcome of asking the budget for one call
+#[derive(Debug, Clone, PartialEq)]
+pub enum Admission {
+    Granted,
+    /// Try again after this long
+    Wait(Duration),
+    /// Dropped to protect higher-priority calls
+    Shed(String),
+}
+
+/// Token buckets and throttle state of one endpoint; all decisions are made
+/// against an explicit `now`
+#[derive(Debug, Clone)]
+pub struct RpcBudgetState {
+    limits: RpcLimits,
+    endpoint: TokenBucket,
+    read: TokenBucket,
+    snapshot: TokenBucket,
+    /// Current rate, lowered on 429s and recovered on success
+    rate: f64,
+    cooldown_until: Option<Instant>,
+    consecutive_throttles: u32,
+}
+
+impl RpcBudgetState {
+    pub fn new(limits: RpcLimits, now: Instant) -> Self {
+        let rate = limits.requests_per_second;
+        let burst = limits.burst as f64;
+        Self {
+            endpoint: TokenBucket::new(burst, rate, now),
+            read: TokenBucket::new(burst * limits.read_share, rate * limits.read_share, now),
+            snapshot: TokenBucket::new(burst * limits.snapshot_share, rate * limits.snapshot_share, now),
+            rate,
+            cooldown_until: None,
+            consecutive_throttles: 0,
+            limits,
+        }
+    }
+
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    pub fn in_cooldown(&self, now: Instant) -> bool {
+        self.cooldown_until.map_or(false, |until| now < until)
+    }
+
+    fn class_bucket(&mut self, class: RpcMethodClass) -> Option<&mut TokenBucket> {
+        match class {
+            RpcMethodClass::Submit => None,
+            RpcMethodClass::Read => Some(&mut self.read),
+            RpcMethodClass::Snapshot => Some(&mut self.snapshot),
+        }
+    }
+
+    /// Take budget for one call of `class` that has already waited `waited`
+    pub fn admit(&mut self, class: RpcMethodClass, now: Instant, waited: Duration) -> Admission {
+        if let Some(until) = self.cooldown_until.filter(|until| now < *until) {
+            if class == RpcMethodClass::Snapshot {
+                return Admission::Shed("endpoint is throttled".to_string());
+            }
+            return Admission::Wait(until - now);
+        }
+
+        self.endpoint.refill(now);
+        let reserve = self.limits.reserve(class);
+        let mut wait = self.endpoint.wait_for(reserve);
+        if let Some(bucket) = self.class_bucket(class) {
+            bucket.refill(now);
+            wait = wait.max(bucket.wait_for(0.0));
+        }
+
+        if wait.is_zero() {
+            self.endpoint.tokens -= 1.0;
+            if let Some(bucket) = self.class_bucket(class) {
+                bucket.tokens -= 1.0;
+            }
+            return Admission::Granted;
+        }
+
+        if class == RpcMethodClass::Snapshot && waited + wait > self.limits.max_snapshot_wait {
+            return Admission::Shed("request budget exhausted".to_string());
+        }
+        Admission::Wait(wait)
+    }
+
+    /// The provider answered 429: halve the rate and pause until the backoff ends
+    pub fn record_throttled(&mut self, now: Instant) -> Duration {
+        let backoff = INITIAL_THROTTLE_BACKOFF
+            .saturating_mul(1 << self.consecutive_throttles.min(5))
+            .min(MAX_THROTTLE_BACKOFF);
+        self.consecutive_throttles += 1;
+        self.cooldown_until = Some(now + backoff);
+        self.set_rate((self.rate / 2.0).max(self.limits.requests_per_second * MIN_RATE_FRACTION));
+        self.endpoint.tokens = 0.0;
+        backoff
+    }
+
+    /// A call went through: step the rate back towards the configured one
+    pub fn record_success(&mut self) {
+        self.consecutive_throttles = 0;
+        if self.rate < self.limits.requests_per_second {
+            let step = self.limits.requests_per_second * RATE_RECOVERY_STEP;
+            self.set_rate((self.rate + step).min(self.limits.requests_per_second));
+        }
+    }
+
+    fn set_rate(&mut self, rate: f64) {
+        self.rate = rate;
+        self.endpoint.refill_per_second = rate;
+        self.read.refill_per_second = rate * self.limits.read_share;
+        self.snapshot.refill_per_second = rate * self.limits.snapshot_share;
+    }
+}
+
+/// Whether the provider rejected the call for exceeding its rate limit.
+///
+/// The client already retries 429s a few times before surfacing them, so an
+/// error here means the provider is still throttling.
+pub fn is_rate_limited(err: &ClientError) -> bool {
+    let message = err.to_string();
+    message.contains("429") || message.contains("Too Many Requests")
+}
+
+/// Shared budget of one endpoint; clients using the same endpoint must share it
+pub struct RpcBudget {
+    endpoint: String,
+    state: Mutex<RpcBudgetState>,
+}
+
+impl RpcBudget {
+    pub fn new(endpoint: &str, limits: RpcLimits) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            state: Mutex::new(RpcBudgetState::new(limits, Instant::now())),
+        }
+    }
+
+    /// Budget with the provider's known limits
+    pub fn for_endpoint(endpoint: &str) -> Self {
+        Self::new(endpoint, RpcLimits::for_endpoint(endpoint))
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Wait for budget; snapshot calls fail with `ChainError::RateLimited` instead of queueing long
+    pub async fn acquire(&self, class: RpcMethodClass) -> Result<()> {
+        let started = Instant::now();
+        loop {
+            let admission = self.state.lock().unwrap().admit(class, Instant::now(), started.elapsed());
+            match admission {
+                Admission::Granted => return Ok(()),
+                Admission::Wait(wait) => {
+                    debug!("RPC {} call to {} queued for {:?}", class.as_str(), self.endpoint, wait);
+                    tokio::time::sleep(wait).await;
+                }
+                Admission::Shed(reason) => {
+                    return Err(ChainError::RateLimited(format!(
+                        "{} call to {} shed: {}",
+                        class.as_str(),
+                        self.endpoint,
+                        reason
+                    )).into());
+                }
+            }
+        }
+    }
+
+    pub fn record_throttled(&self) {
+        let mut state = self.state.lock().unwrap();
+        let backoff = state.record_throttled(Instant::now());
+        warn!("RPC endpoint {} returned 429; rate lowered to {:.1}/s, pausing {:?}", self.endpoint, state.rate(), backoff);
+    }
+
+    pub fn record_success(&self) {
+        self.state.lock().unwrap().record_success();
+    }
+}
+
+/// `RpcClient` whose calls are charged against an endpoint budget
+pub struct BudgetedRpcClient {
+    client: Arc<RpcClient>,
+    budget: Arc<RpcBudget>,
+}
+
+impl BudgetedRpcClient {
+    pub fn new(client: RpcClient, budget: Arc<RpcBudget>) -> Self {
+        Self { client: Arc::new(client), budget }
+    }
+
+    /// Same underlying client, charged against another budget
+    pub fn with_budget(&self, budget: Arc<RpcBudget>) -> Self {
+        Self { client: self.client.clone(), budget }
+    }
+
+    pub fn budget(&self) -> Arc<RpcBudget> {
+        self.budget.clone()
+    }
+
+    /// Run one RPC call once the budget allows it
+    pub async fn call<T>(
+        &self,
+        class: RpcMethodClass,
+        request: impl FnOnce(&RpcClient) -> ClientResult<T>,
+    ) -> Result<T> {
+        self.budget.acquire(class).await?;
+
+        match request(&self.client) {
+            Ok(value) => {
+                self.budget.record_success();
+                Ok(value)
+            }
+            Err(e) if is_rate_limited(&e) => {
+                self.budget.record_throttled();
+                Err(ChainError::RateLimited(format!("{} rejected {} call: {}", self.budget.endpoint(), class.as_str(), e)).into())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use crate::error::{Result, VaultError, StorageError};
+use crate::models::{AppliedMigration, SchemaColumn};
+use crate::database::SchemaRepository;
+use serde::{Deserialize, Serialize};
+use sqlx::migrate::Migrator;
+use std::collections::{BTreeSet, HashMap};
+use tracing::{info, warn};
+
+/// Migrations compiled into this binary
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Tables and columns referenced by the compiled queries in `database.rs`.
+/// Keep in step with those queries; anything listed here must exist live.
+pub const EXPECTED_COLUMNS: &[(&str, &[&str])] = &[
+    ("vaults", &[
+        "id", "user_pubkey", "vault_pubkey", "token_account_pubkey", "total_balance", "locked_balance",
+        "available_balance", "is_active", "created_at", "updated_at", "mint_pubkey", "label", "version",
+    ]),
+    ("transaction_records", &[
+        "id", "vault_id", "operation_type", "amount", "signature", "status", "error_message",
+        "idempotency_key", "created_at", "updated_at", "stage_timings", "submission_attempts", "fee_lamports",
+        "priority_fee_lamports", "compute_units_consumed", "fee_recorded_at",
+    ]),
+    ("balance_snapshots", &[
+        "id", "vault_id", "total_balance", "locked_balance", "available_balance", "block_height", "created_at",
+    ]),
+    ("mints", &[
+        "mint_pubkey", "symbol", "decimals", "enabled", "min_deposit", "withdrawal_fee", "oracle_feed_id",
+        "created_at", "updated_at",
+    ]),
+    ("audit_logs", &[
+        "id", "event_type", "user_pubkey", "vault_id", "details", "metadata", "created_at",
+    ]),
+    ("support_credentials", &[
+        "id", "name", "token_hash", "role", "created_at", "revoked_at",
+    ]),
+    ("reconciliation_results", &[
+        "id", "vault_id", "mode", "is_consistent", "findings", "started_at", "completed_at",
+    ]),
+    ("multisig_proposals", &[
+        "id", "transaction_id", "vault_id", "multisig_pubkey", "squads_vault_pubkey", "transaction_index",
+        "proposal_pubkey", "amount", "status", "created_at", "updated_at",
+    ]),
+    ("vault_provisioning", &[
+        "vault_id", "user_pubkey", "authority_pubkey", "mint_pubkey", "state", "init_signature", "attempts",
+        "last_error", "created_at", "updated_at",
+    ]),
+    ("authority_rotations", &[
+        "id", "old_authority", "new_authority", "new_key_path", "status", "batch_size", "total_vaults",
+        "created_at", "updated_at", "completed_at",
+    ]),
+    ("authority_rotation_vaults", &[
+        "rotation_id", "vault_id", "vault_pubkey", "status", "signature", "error_message", "updated_at",
+    ]),
+    ("vault_activity_hourly", &[
+        "bucket_start", "vault_id", "operation_type", "tx_count", "volume",
+    ]),
+    ("activity_rollup_state", &[
+        "id", "rolled_up_through", "refreshed_at",
+    ]),
+    ("vault_cases", &[
+        "id", "vault_id", "title", "status", "opened_by", "opened_at", "closed_by", "closed_at", "resolution",
+    ]),
+    ("vault_notes", &[
+        "id", "vault_id", "case_id", "body", "author", "created_at",
+    ]),
+    ("vault_tags", &[
+        "vault_id", "tag", "added_by", "created_at",
+    ]),
+    ("maintenance_windows", &[
+        "id", "starts_at", "ends_at", "reason", "read_only", "created_at", "cancelled_at",
+    ]),
+    ("balance_holds", &[
+        "id", "vault_id", "transaction_id", "balance", "amount", "status", "created_at", "settled_at",
+    ]),
+    ("bulk_jobs", &[
+        "id", "operation", "parameters", "reason", "status", "batch_size", "total_targets", "requested_by",
+        "created_at", "updated_at", "started_at", "completed_at",
+    ]),
+    ("bulk_job_targets", &[
+        "job_id", "target_id", "vault_id", "description", "status", "error_message", "updated_at",
+    ]),
+    ("token_authority_findings", &[
+        "id", "vault_id", "token_account_pubkey", "delegate", "delegated_amount", "close_authority",
+        "detected_at", "last_seen_at", "resolved_at",
+    ]),
+    ("screening_decisions", &[
+        "id", "vault_id", "direction", "address", "amount", "risk", "action", "findings", "review_status",
+        "reviewed_by", "review_note", "reviewed_at", "clearance_used_at", "created_at",
+    ]),
+    ("event_outbox_head", &["id", "seq"]),
+    ("event_outbox", &["seq", "event_type", "vault_id", "payload", "created_at"]),
+    ("event_consumers", &[
+        "name", "delivery", "endpoint_url", "signing_secret", "acked_seq", "acked_at", "failed_attempts",
+        "last_error", "next_attempt_at", "leased_by", "leased_until", "enabled", "created_at",
+    ]),
+    ("vault_auto_lock", &[
+        "vault_id", "target_locked", "enabled", "last_adjustment", "last_adjusted_at", "last_error",
+        "created_at", "updated_at",
+    ]),
+    ("indexer_cursors", &[
+        "program_id", "last_slot", "last_signature", "polled_head_slot", "processed_count", "polled_at", "created_at",
+    ]),
+    ("indexed_signatures", &["signature", "program_id", "slot", "block_time", "failed", "source", "indexed_at"]),
+    ("indexer_gaps", &[
+        "id", "program_id", "from_slot", "to_slot", "until_signature", "before_signature", "status",
+        "recovered_count", "attempts", "last_error", "detected_at", "resolved_at",
+    ]),
+    ("rate_limit_usage", &[
+        "client_id", "window_start", "requests", "rejected", "banned", "last_seen_at",
+    ]),
+    ("rate_limit_bans", &[
+        "id", "client_id", "reason", "created_by", "rule_id", "expires_at", "lifted_at", "lifted_by", "created_at",
+    ]),
+    ("rate_limit_escalation_rules", &[
+        "id", "name", "min_rejections", "window_seconds", "ban_seconds", "enabled", "created_at",
+    ]),
+    ("identities", &[
+        "user_pubkey", "provider", "external_id", "level", "status", "verified_at", "expires_at", "reported_at",
+        "created_at", "updated_at",
+    ]),
+    ("identity_verification_events", &[
+        "provider", "event_id", "user_pubkey", "level", "status", "reported_at", "received_at",
+    ]),
+    ("vault_alert_rules", &[
+        "id", "vault_id", "kind", "threshold", "label", "enabled", "triggered", "trigger_count", "last_triggered_at",
+        "created_at", "updated_at",
+    ]),
+    ("data_exports", &[
+        "id", "requested_by", "range_start", "range_end", "status", "snapshot_at", "objects", "error_message",
+        "attempts", "leased_by", "leased_until", "created_at", "started_at", "completed_at",
+    ]),
+    ("idempotency_keys", &[
+        "client_id", "idempotency_key", "method", "path", "request_hash", "status", "response_status",
+        "response_content_type", "response_body", "locked_until", "created_at", "completed_at", "expires_at",
+    ]),
+    ("vault_dormancy", &[
+        "vault_id", "status", "inactive_since", "notified_at", "grace_ends_at", "swept_at", "swept_amount",
+        "reclaimed_at", "updated_at",
+    ]),
+    ("withdrawal_fee_tiers", &["mint_pubkey", "min_amount", "flat_fee", "bps"]),
+    ("withdrawal_fee_promotions", &[
+        "id", "mint_pubkey", "label", "user_pubkey", "flat_fee", "bps", "starts_at", "ends_at", "created_by",
+        "created_at",
+    ]),
+    ("monitor_job_runs", &["id", "job_type", "started_at", "duration_ms", "outcome", "error"]),
+    ("security_events", &["id", "event_type", "severity", "source", "actor", "details", "created_at"]),
+    ("incident_runs", &[
+        "id", "runbook", "reason", "status", "vault_ids", "maintenance_window_id", "follows", "requested_by",
+        "created_at", "updated_at", "completed_at",
+    ]),
+    ("incident_steps", &[
+        "run_id", "position", "step", "status", "attempts", "output", "error_message", "started_at", "completed_at",
+    ]),
+    ("state_journal_head", &["id", "lsn"]),
+    ("state_journal", &["lsn", "entity", "entity_key", "operation", "state", "recorded_at"]),
+];
+
+/// A migration known to this binary
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KnownMigration {
+    pub version: i64,
+    pub description: String,
+    #[serde(skip)]
+    pub checksum: Vec<u8>,
+}
+
+/// How the database's migration history compares to this binary's
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationStatus {
+    /// Latest migration compiled into this binary
+    pub expected_version: Option<i64>,
+    /// Latest migration recorded in the database
+    pub applied_version: Option<i64>,
+    /// Known to the binary but not yet applied
+    pub pending: Vec<KnownMigration>,
+    /// Recorded as started but never finished
+    pub failed: Vec<i64>,
+    /// Applied, but the file has since been edited
+    pub checksum_mismatches: Vec<i64>,
+    /// Applied by a newer binary
+    pub unknown: Vec<i64>,
+}
+
+impl MigrationStatus {
+    /// True when the database is exactly at the version this binary expects
+    pub fn is_current(&self) -> bool {
+        self.pending.is_empty()
+            && self.failed.is_empty()
+            && self.checksum_mismatches.is_empty()
+            && self.unknown.is_empty()
+    }
+
+    /// Human-readable reasons the schema is not current
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !self.pending.is_empty() {
+            let versions: Vec<String> = self.pending.iter().map(|m| m.version.to_string()).collect();
+            problems.push(format!("pending migrations: {}", versions.join(", ")));
+        }
+        if !self.failed.is_empty() {
+            problems.push(format!("failed migrations: {:?}", self.failed));
+        }
+        if !self.checksum_mismatches.is_empty() {
+            problems.push(format!("migrations modified after being applied: {:?}", self.checksum_mismatches));
+        }
+        if !self.unknown.is_empty() {
+            problems.push(format!("migrations applied by a newer release: {:?}", self.unknown));
+        }
+        problems
+    }
+}
+
+/// Columns the compiled queries need that the live database lacks
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SchemaDrift {
+    pub missing_tables: Vec<String>,
+    /// `table.column`
+    pub missing_columns: Vec<String>,
+}
+
+impl SchemaDrift {
+    pub fn is_empty(&self) -> bool {
+        self.missing_tables.is_empty() && self.missing_columns.is_empty()
+    }
+}
+
+/// Migrations compiled into this binary, oldest first
+pub fn known_migrations() -> Vec<KnownMigration> {
+    MIGRATOR
+        .iter()
+        .map(|m| KnownMigration {
+            version: m.version,
+            description: m.description.to_string(),
+            checksum: m.checksum.to_vec(),
+        })
+        .collect()
+}
+
+/// Compare the binary's migrations with what the database has recorded
+pub fn compute_status(known: &[KnownMigration], applied: &[AppliedMigration]) -> MigrationStatus {
+    let applied_by_version: HashMap<i64, &AppliedMigration> = applied.iter().map(|m| (m.version, m)).collect();
+    let known_by_version: HashMap<i64, &KnownMigration> = known.iter().map(|m| (m.version, m)).collect();
+
+    let pending = known
+        .iter()
+        .filter(|m| !applied_by_version.contains_key(&m.version))
+        .cloned()
+        .collect();
+
+    let checksum_mismatches = known
+        .iter()
+        .filter(|m| matches!(applied_by_version.get(&m.version), Some(a) if a.success && a.checksum != m.checksum))
+        .map(|m| m.version)
+        .collect();
+
+    MigrationStatus {
+        expected_version: known.iter().map(|m| m.version).max(),
+        applied_version: applied.iter().filter(|m| m.success).map(|m| m.version).max(),
+        pending,
+        failed: applied.iter().filter(|m| !m.success).map(|m| m.version).collect(),
+        checksum_mismatches,
+        unknown: applied.iter().filter(|m| !known_by_version.contains_key(&m.version)).map(|m| m.version).collect(),
+    }
+}
+
+/// Compare the expected tables/columns with those present live
+pub fn compute_drift(expected: &[(&str, &[&str])], live: &[SchemaColumn]) -> SchemaDrift {
+    let live_columns: BTreeSet<(&str, &str)> = live
+        .iter()
+        .map(|c| (c.table_name.as_str(), c.column_name.as_str()))
+        .collect();
+    let live_tables: BTreeSet<&str> = live.iter().map(|c| c.table_name.as_str()).collect();
+
+    let mut drift = SchemaDrift::default();
+    for (table, columns) in expected {
+        if !live_tables.contains(table) {
+            drift.missing_tables.push(table.to_string());
+            continue;
+        }
+        for column in columns.iter() {
+            if !live_columns.contains(&(*table, *column)) {
+                drift.missing_columns.push(format!("{}.{}", table, column));
+            }
+        }
+    }
+    drift
+}
+
+/// Migration status, drift detection and controlled migration runs
+pub struct SchemaManager {
+    schema_repo: SchemaRepository,
+    pool: sqlx::PgPool,
+}
+
+impl SchemaManager {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self {
+            schema_repo: SchemaRepository::new(pool.clone()),
+            pool,
+        }
+    }
+
+    /// Compare the database's migration history with this binary's
+    pub async fn status(&self) -> Result<MigrationStatus> {
+        let applied = self.schema_repo.list_applied_migrations().await?;
+        Ok(compute_status(&known_migrations(), &applied))
+    }
+
+    /// Check the live schema has every column the compiled queries use
+    pub async fn detect_drift(&self) -> Result<SchemaDrift> {
+        let tables: Vec<String> = EXPECTED_COLUMNS.iter().map(|(table, _)| table.to_string()).collect();
+        let live = self.schema_repo.list_columns(&tables).await?;
+        Ok(compute_drift(EXPECTED_COLUMNS, &live))
+    }
+
+    /// Apply pending migrations; returns the versions that were applied
+    pub async fn run_pending(&self) -> Result<Vec<i64>> {
+        let before = self.status().await?;
+        MIGRATOR
+            .run(&self.pool)
+            .await
+            .map_err(|e| StorageError::Query(format!("Failed to run migrations: {}", e)))?;
+
+        let applied: Vec<i64> = before.pending.iter().map(|m| m.version).collect();
+        if !applied.is_empty() {
+            info!("Applied migrations: {:?}", applied);
+        }
+        Ok(applied)
+    }
+
+    /// Refuse to continue unless the database is at this binary's version
+    pub async fn ensure_current(&self) -> Result<MigrationStatus> {
+        let status = self.status().await?;
+        if !status.is_current() {
+            return Err(VaultError::Configuration(format!(
+                "Database schema is not at expected version {:?}: {}",
+                status.expected_version,
+                status.problems().join("; ")
+            )));
+        }
+
+        let drift = self.detect_drift().await?;
+        if !drift.is_empty() {
+            warn!(
+                "Schema drift detected: missing tables {:?}, missing columns {:?}",
+                drift.missing_tables, drift.missing_columns
+            );
+        }
+
+        Ok(status)
+    }
+}
@@ -0,0 +1,429 @@
+//! Sanctions and denylist screening of deposits and withdrawals.
+//!
+//! Each configured provider rates the counterparty address; the worst rating
+//! decides the action through `ScreeningPolicy`. Every screening is stored in
+//! `screening_decisions` with what each provider said, and audited.
+//!
+//! - `allow` / `allow_and_flag`: the operation proceeds; flagged decisions are
+//!   kept for later review but need none.
+//! - `hold_for_review`: the operation is refused with 409 and the decision is
+//!   pending. An operator clears or rejects it; a cleared hold lets one retry
+//!   of the same operation (same vault, address and direction, up to the held
+//!   amount) through without screening again.
+//! - `block`: the operation is refused with 403.
+//!
+//! Provider failures are treated as configured by `ScreeningPolicy::on_error`,
+//! so a screening outage can fail closed.
+
+use crate::error::{Result, DomainError, VaultError};
+use crate::models::ScreeningDecision;
+use crate::database::ScreeningRepository;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+pub const SCREENING_DECISION_EVENT: &str = "screening_decision";
+pub const SCREENING_REVIEW_EVENT: &str = "screening_review";
+
+/// How long a cleared hold can be used by a retry
+pub const CLEARANCE_VALIDITY_HOURS: i64 = 24;
+
+const MAX_REVIEW_NOTE_LENGTH: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreeningDirection {
+    Deposit,
+    Withdrawal,
+}
+
+impl ScreeningDirection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScreeningDirection::Deposit => "deposit",
+            ScreeningDirection::Withdrawal => "withdrawal",
+        }
+    }
+}
+
+/// Rating of an address, least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreeningRisk {
+    Clear,
+    Elevated,
+    Severe,
+}
+
+impl ScreeningRisk {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScreeningRisk::Clear => "clear",
+            ScreeningRisk::Elevated => "elevated",
+            ScreeningRisk::Severe => "severe",
+        }
+    }
+}
+
+/// What happens to the operation, least to most restrictive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreeningAction {
+    Allow,
+    AllowAndFlag,
+    HoldForReview,
+    Block,
+}
+
+impl ScreeningAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScreeningAction::Allow => "allow",
+            ScreeningAction::AllowAndFlag => "allow_and_flag",
+            ScreeningAction::HoldForReview => "hold_for_review",
+            ScreeningAction::Block => "block",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "allow" => Some(ScreeningAction::Allow),
+            "allow_and_flag" => Some(ScreeningAction::AllowAndFlag),
+            "hold_for_review" => Some(ScreeningAction::HoldForReview),
+            "block" => Some(ScreeningAction::Block),
+            _ => None,
+        }
+    }
+}
+
+/// Action taken for each rating, and when a provider could not answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScreeningPolicy {
+    pub on_elevated: ScreeningAction,
+    pub on_severe: ScreeningAction,
+    pub on_error: ScreeningAction,
+}
+
+impl Default for ScreeningPolicy {
+    fn default() -> Self {
+        Self {
+            on_elevated: ScreeningAction::HoldForReview,
+            on_severe: ScreeningAction::Block,
+            on_error: ScreeningAction::HoldForReview,
+        }
+    }
+}
+
+impl ScreeningPolicy {
+    /// Worst rating among the answers, and the action it and any failures call for
+    pub fn decide(&self, outcomes: &[ProviderOutcome]) -> (ScreeningRisk, ScreeningAction) {
+        let risk = outcomes.iter()
+            .filter_map(|o| o.verdict.as_ref().map(|v| v.risk))
+            .max()
+            .unwrap_or(ScreeningRisk::Clear);
+        let mut action = match risk {
+            ScreeningRisk::Clear => ScreeningAction::Allow,
+            ScreeningRisk::Elevated => self.on_elevated,
+            ScreeningRisk::Severe => self.on_severe,
+        };
+        if outcomes.iter().any(|o| o.error.is_some()) {
+            action = action.max(self.on_error);
+        }
+        (risk, action)
+    }
+}
+
+/// The address being screened and the operation it takes part in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScreeningSubject {
+    pub vault_id: Uuid,
+    pub direction: ScreeningDirection,
+    /// Counterparty wallet or token account, base58
+    pub address: String,
+    pub amount: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProviderVerdict {
+    pub risk: ScreeningRisk,
+    pub reason: Option<String>,
+    /// Provider's identifier for the match, for reviewers
+    pub reference: Option<String>,
+}
+
+impl ProviderVerdict {
+    pub fn clear() -> Self {
+        Self { risk: ScreeningRisk::Clear, reason: None, reference: None }
+    }
+}
+
+/// One provider's answer as stored with the decision
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProviderOutcome {
+    pub provider: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verdict: Option<ProviderVerdict>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A source of address ratings, e.g. a sanctions API or a local list
+#[async_trait]
+pub trait ScreeningProvider: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn screen(&self, subject: &ScreeningSubject) -> Result<ProviderVerdict>;
+}
+
+/// Addresses rated severe by local decision, one per line in the source file
+pub struct StaticDenylist {
+    addresses: HashSet<String>,
+}
+
+impl StaticDenylist {
+    pub fn new(addresses: impl IntoIterator<Item = String>) -> Self {
+        Self { addresses: addresses.into_iter().map(|a| a.trim().to_string()).filter(|a| !a.is_empty()).collect() }
+    }
+
+    /// Blank lines and `#` comments are ignored
+    pub fn parse(contents: &str) -> Self {
+        Self::new(contents.lines().filter(|l| !l.trim_start().starts_with('#')).map(str::to_string))
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| VaultError::Configuration(format!("Failed to read denylist {}: {}", path, e)))?;
+        Ok(Self::parse(&contents))
+    }
+
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+}
+
+#[async_trait]
+impl ScreeningProvider for StaticDenylist {
+    fn name(&self) -> &str {
+        "static_denylist"
+    }
+
+    async fn screen(&self, subject: &ScreeningSubject) -> Result<ProviderVerdict> {
+        if self.addresses.contains(&subject.address) {
+            return Ok(ProviderVerdict {
+                risk: ScreeningRisk::Severe,
+                reason: Some("Address is on the local denylist".to_string()),
+                reference: None,
+            });
+        }
+        Ok(ProviderVerdict::clear())
+    }
+}
+
+/// Chainalysis sanctions screening API; any identification is rated severe
+pub struct ChainalysisSanctions {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: String,
+}
+
+impl ChainalysisSanctions {
+    pub const DEFAULT_BASE_URL: &'static str = "https://public.chainalysis.com/api/v1";
+
+    pub fn new(api_key: String) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(5))
+                .build()
+                .unwrap_or_default(),
+            base_url: Self::DEFAULT_BASE_URL.to_string(),
+            api_key,
+        }
+    }
+
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = base_url;
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ChainalysisResponse {
+    #[serde(default)]
+    identifications: Vec<ChainalysisIdentification>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChainalysisIdentification {
+    category: Option<String>,
+    name: Option<String>,
+    url: Option<String>,
+}
+
+#[async_trait]
+impl ScreeningProvider for ChainalysisSanctions {
+    fn name(&self) -> &str {
+        "chainalysis"
+    }
+
+    async fn screen(&self, subject: &ScreeningSubject) -> Result<ProviderVerdict> {
+        let response = self.client
+            .get(format!("{}/address/{}", self.base_url, subject.address))
+            .header("X-API-Key", &self.api_key)
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| VaultError::Internal(format!("Chainalysis request failed: {}", e)))?
+            .json::<ChainalysisResponse>()
+            .await
+            .map_err(|e| VaultError::Internal(format!("Chainalysis response unreadable: {}", e)))?;
+
+        Ok(match response.identifications.first() {
+            None => ProviderVerdict::clear(),
+            Some(hit) => ProviderVerdict {
+                risk: ScreeningRisk::Severe,
+                reason: Some(format!("{}: {}",
+                                     hit.category.as_deref().unwrap_or("identification"),
+                                     hit.name.as_deref().unwrap_or("unnamed"))),
+                reference: hit.url.clone(),
+            },
+        })
+    }
+}
+
+/// Screens operations with every configured provider and records each decision
+pub struct ScreeningService {
+    repo: ScreeningRepository,
+    providers: Vec<Arc<dyn ScreeningProvider>>,
+    policy: ScreeningPolicy,
+}
+
+impl ScreeningService {
+    pub fn new(pool: sqlx::PgPool, policy: ScreeningPolicy) -> Self {
+        Self {
+            repo: ScreeningRepository::new(pool),
+            providers: Vec::new(),
+            policy,
+        }
+    }
+
+    pub fn with_provider(mut self, provider: Arc<dyn ScreeningProvider>) -> Self {
+        self.providers.push(provider);
+        self
+    }
+
+    pub fn policy(&self) -> &ScreeningPolicy {
+        &self.policy
+    }
+
+    /// Screen before an operation is accepted; `Ok` means it may proceed.
+    ///
+    /// Without providers nothing is screened or recorded and `None` is returned.
+    pub async fn screen(&self, subject: ScreeningSubject) -> Result<Option<ScreeningDecision>> {
+        if self.providers.is_empty() {
+            return Ok(None);
+        }
+
+        let since = Utc::now() - Duration::hours(CLEARANCE_VALIDITY_HOURS);
+        if let Some(cleared) = self.repo
+            .take_clearance(subject.vault_id, subject.direction.as_str(), &subject.address, subject.amount, since)
+            .await? {
+            info!("Screening of {} for vault {} passed by review of decision {}", subject.address, subject.vault_id, cleared.id);
+            let findings = serde_json::json!([{ "provider": "review", "cleared_decision_id": cleared.id }]);
+            return self.record(&subject, ScreeningRisk::Clear, ScreeningAction::Allow, findings).await.map(Some);
+        }
+
+        let mut outcomes = Vec::with_capacity(self.providers.len());
+        for provider in &self.providers {
+            let outcome = match provider.screen(&subject).await {
+                Ok(verdict) => ProviderOutcome { provider: provider.name().to_string(), verdict: Some(verdict), error: None },
+                Err(e) => {
+                    warn!("Screening provider {} failed for {}: {}", provider.name(), subject.address, e);
+                    ProviderOutcome { provider: provider.name().to_string(), verdict: None, error: Some(e.to_string()) }
+                }
+            };
+            outcomes.push(outcome);
+        }
+
+        let (risk, action) = self.policy.decide(&outcomes);
+        let findings = serde_json::to_value(&outcomes)
+            .map_err(|e| VaultError::Internal(format!("Failed to serialize screening findings: {}", e)))?;
+        let decision = self.record(&subject, risk, action, findings).await?;
+
+        match action {
+            ScreeningAction::Allow => Ok(Some(decision)),
+            ScreeningAction::AllowAndFlag => {
+                warn!("Screening flagged {} {} for vault {} ({}); allowed, decision {}",
+                      subject.direction.as_str(), subject.address, subject.vault_id, risk.as_str(), decision.id);
+                Ok(Some(decision))
+            }
+            ScreeningAction::HoldForReview => {
+                warn!("Screening held {} {} for vault {} ({}); decision {} awaits review",
+                      subject.direction.as_str(), subject.address, subject.vault_id, risk.as_str(), decision.id);
+                Err(DomainError::ScreeningHeld(format!(
+                    "{} is under review (decision {}); retry once it is cleared", subject.direction.as_str(), decision.id
+                )).into())
+            }
+            ScreeningAction::Block => {
+                warn!("Screening blocked {} {} for vault {} ({}); decision {}",
+                      subject.direction.as_str(), subject.address, subject.vault_id, risk.as_str(), decision.id);
+                Err(DomainError::ScreeningBlocked(format!("decision {}", decision.id)).into())
+            }
+        }
+    }
+
+    async fn record(
+        &self,
+        subject: &ScreeningSubject,
+        risk: ScreeningRisk,
+        action: ScreeningAction,
+        findings: serde_json::Value,
+    ) -> Result<ScreeningDecision> {
+        let review_status = (action == ScreeningAction::HoldForReview).then_some("pending");
+        self.repo.record_decision(
+            Uuid::new_v4(),
+            subject.vault_id,
+            subject.direction.as_str(),
+            &subject.address,
+            subject.amount,
+            risk.as_str(),
+            action.as_str(),
+            findings,
+            review_status,
+            SCREENING_DECISION_EVENT,
+        ).await
+    }
+
+    /// Holds awaiting review, oldest first
+    pub async fn pending_reviews(&self, limit: i64) -> Result<Vec<ScreeningDecision>> {
+        self.repo.get_pending_reviews(limit).await
+    }
+
+    pub async fn vault_decisions(&self, vault_id: Uuid, limit: i64) -> Result<Vec<ScreeningDecision>> {
+        self.repo.get_vault_decisions(vault_id, limit).await
+    }
+
+    /// Clear (letting one retry through) or reject a held decision
+    pub async fn review(&self, decision_id: Uuid, clear: bool, reviewer: &str, note: &str) -> Result<ScreeningDecision> {
+        let note = note.trim();
+        if note.is_empty() || note.chars().count() > MAX_REVIEW_NOTE_LENGTH {
+            return Err(DomainError::Validation(format!("Review note must be 1-{} characters", MAX_REVIEW_NOTE_LENGTH)).into());
+        }
+        let status = if clear { "cleared" } else { "rejected" };
+
+        let decision = self.repo
+            .review(decision_id, status, reviewer, note, SCREENING_REVIEW_EVENT)
+            .await?
+            .ok_or_else(|| DomainError::NotFound(format!("Pending screening decision {}", decision_id)))?;
+        info!("{} {} screening decision {}", reviewer, status, decision.id);
+        Ok(decision)
+    }
+}
@@ -0,0 +1,269 @@
+//! Rust client for the vault HTTP API.
+//!
+//! Every mutating call is sent with a fresh `Idempotency-Key` that is reused
+//! for all of its retries, so a retry after a dropped connection or timeout
+//! replays the server's first response instead of executing a second lock or
+//! transfer (see `crate::idempotency`). Calls are retried on transport
+//! failures, `429`, `502`, `503`, `504` and the server's in-progress `409`,
+//! with exponential backoff that honours `Retry-After`.
+//!
+//! ```ignore
+//! let client = VaultClient::new("https://vaults.internal")?.with_api_key(api_key);
+//! let locked = client.lock(&user_pubkey, 1_000_000).await?;
+//! ```
+
+use crate::api::{
+    BalanceResponse, CreateVaultRequest, CreateVaultResponse, DepositRequest, ErrorResponse, TransactionRequest, TransactionResponse,
+    TransferRequest, VaultResponse,
+};
+use crate::idempotency::{IDEMPOTENCY_KEY_HEADER, IDEMPOTENT_REPLAYED_HEADER, IN_PROGRESS_ERROR};
+use reqwest::{Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::time::Duration;
+use thiserror::Error;
+use tracing::warn;
+use uuid::Uuid;
+
+#[derive(Error, Debug)]
+pub enum SdkError {
+    #[error("Invalid base URL: {0}")]
+    InvalidUrl(String),
+
+    /// The request may or may not have reached the server
+    #[error("Transport error: {0}")]
+    Transport(#[from] reqwest::Error),
+
+    /// The server answered with an error
+    #[error("{status} {error}: {message}")]
+    Api { status: u16, error: String, message: String },
+
+    #[error("Unexpected response: {0}")]
+    Decode(String),
+}
+
+pub type SdkResult<T> = std::result::Result<T, SdkError>;
+
+/// When and how often calls are retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts including the first; 1 disables retries
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before the retry following failed attempt `attempt` (1-based)
+    pub fn backoff(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        let exponential = self.initial_backoff.saturating_mul(1 << attempt.saturating_sub(1).min(16));
+        retry_after.unwrap_or(exponential).min(self.max_backoff)
+    }
+}
+
+/// Whether a response is worth retrying with the same idempotency key
+pub fn is_retryable(status: StatusCode, error: Option<&str>) -> bool {
+    match status {
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT => true,
+        StatusCode::CONFLICT => error == Some(IN_PROGRESS_ERROR),
+        _ => false,
+    }
+}
+
+/// A mutating call's result
+#[derive(Debug, Clone)]
+pub struct Mutation<T> {
+    pub value: T,
+    pub idempotency_key: String,
+    /// The server replayed the response of an earlier attempt
+    pub replayed: bool,
+    pub attempts: u32,
+}
+
+pub struct VaultClient {
+    http: reqwest::Client,
+    base_url: reqwest::Url,
+    api_key: Option<String>,
+    retry: RetryPolicy,
+}
+
+impl VaultClient {
+    pub fn new(base_url: &str) -> SdkResult<Self> {
+        let mut base_url = reqwest::Url::parse(base_url).map_err(|e| SdkError::InvalidUrl(format!("{}: {}", base_url, e)))?;
+        // Paths are joined onto the base, which must end in a slash to keep its last segment
+        if !base_url.path().ends_with('/') {
+            let path = format!("{}/", base_url.path());
+            base_url.set_path(&path);
+        }
+        Ok(Self {
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()?,
+            base_url,
+            api_key: None,
+            retry: RetryPolicy::default(),
+        })
+    }
+
+    /// Sent as a bearer token
+    pub fn with_api_key(mut self, api_key: String) -> Self {
+        self.api_key = Some(api_key);
+        self
+    }
+
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub async fn get_vault(&self, user_pubkey: &str) -> SdkResult<VaultResponse> {
+        self.get(&format!("vaults/{}", user_pubkey)).await
+    }
+
+    pub async fn get_balance(&self, user_pubkey: &str) -> SdkResult<BalanceResponse> {
+        self.get(&format!("vaults/{}/balance", user_pubkey)).await
+    }
+
+    pub async fn create_vault(&self, request: &CreateVaultRequest) -> SdkResult<CreateVaultResponse> {
+        Ok(self.mutate(Method::POST, "vaults", request).await?.value)
+    }
+
+    /// Report a deposit transaction the user has submitted; it is credited once finalized
+    pub async fn deposit(&self, user_pubkey: &str, amount: u64, signature: &str) -> SdkResult<TransactionResponse> {
+        let key = Uuid::new_v4().to_string();
+        let request = DepositRequest {
+            amount,
+            signature: signature.to_string(),
+            idempotency_key: Some(key.clone()),
+        };
+        let path = format!("vaults/{}/deposit", user_pubkey);
+        Ok(self.mutate_with_key(Method::POST, &path, &request, key).await?.value)
+    }
+
+    pub async fn withdraw(&self, user_pubkey: &str, amount: u64) -> SdkResult<TransactionResponse> {
+        self.transaction(user_pubkey, "withdraw", amount).await
+    }
+
+    pub async fn lock(&self, user_pubkey: &str, amount: u64) -> SdkResult<TransactionResponse> {
+        self.transaction(user_pubkey, "lock", amount).await
+    }
+
+    pub async fn unlock(&self, user_pubkey: &str, amount: u64) -> SdkResult<TransactionResponse> {
+        self.transaction(user_pubkey, "unlock", amount).await
+    }
+
+    pub async fn transfer(&self, user_pubkey: &str, destination_user_pubkey: &str, amount: u64) -> SdkResult<TransactionResponse> {
+        let key = Uuid::new_v4().to_string();
+        let request = TransferRequest {
+            amount,
+            destination_user_pubkey: destination_user_pubkey.to_string(),
+            idempotency_key: Some(key.clone()),
+            metadata: None,
+        };
+        let path = format!("vaults/{}/transfer", user_pubkey);
+        Ok(self.mutate_with_key(Method::POST, &path, &request, key).await?.value)
+    }
+
+    async fn transaction(&self, user_pubkey: &str, operation: &str, amount: u64) -> SdkResult<TransactionResponse> {
+        // The body key also dedupes at the transaction record
+        let key = Uuid::new_v4().to_string();
+        let request = TransactionRequest { amount, idempotency_key: Some(key.clone()), metadata: None };
+        let path = format!("vaults/{}/{}", user_pubkey, operation);
+        Ok(self.mutate_with_key(Method::POST, &path, &request, key).await?.value)
+    }
+
+    /// Send a read, retrying like a mutation; reads need no key
+    pub async fn get<T: DeserializeOwned>(&self, path: &str) -> SdkResult<T> {
+        Ok(self.send(Method::GET, path, None::<&()>, None).await?.value)
+    }
+
+    /// Send a mutating call under a fresh idempotency key
+    pub async fn mutate<B: Serialize, T: DeserializeOwned>(&self, method: Method, path: &str, body: &B) -> SdkResult<Mutation<T>> {
+        self.mutate_with_key(method, path, body, Uuid::new_v4().to_string()).await
+    }
+
+    /// Send a mutating call under `key`, e.g. one persisted to survive a client restart
+    pub async fn mutate_with_key<B: Serialize, T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: &B,
+        key: String,
+    ) -> SdkResult<Mutation<T>> {
+        self.send(method, path, Some(body), Some(key)).await
+    }
+
+    async fn send<B: Serialize, T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&B>,
+        key: Option<String>,
+    ) -> SdkResult<Mutation<T>> {
+        let url = self.base_url.join(path).map_err(|e| SdkError::InvalidUrl(format!("{}: {}", path, e)))?;
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let mut request = self.http.request(method.clone(), url.clone());
+            if let Some(api_key) = &self.api_key {
+                request = request.bearer_auth(api_key);
+            }
+            if let Some(key) = &key {
+                request = request.header(IDEMPOTENCY_KEY_HEADER, key);
+            }
+            if let Some(body) = body {
+                request = request.json(body);
+            }
+
+            let (retry_after, failure) = match request.send().await {
+                Ok(response) if response.status().is_success() => {
+                    let replayed = response.headers().get(IDEMPOTENT_REPLAYED_HEADER).map_or(false, |v| v == "true");
+                    let value = response.json().await.map_err(|e| SdkError::Decode(e.to_string()))?;
+                    return Ok(Mutation {
+                        value,
+                        idempotency_key: key.unwrap_or_default(),
+                        replayed,
+                        attempts: attempt,
+                    });
+                }
+                Ok(response) => {
+                    let status = response.status();
+                    let retry_after = response.headers().get(reqwest::header::RETRY_AFTER)
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.parse().ok())
+                        .map(Duration::from_secs);
+                    let error = response.json::<ErrorResponse>().await.ok();
+                    let retryable = is_retryable(status, error.as_ref().map(|e| e.error.as_str()));
+                    let failure = SdkError::Api {
+                        status: status.as_u16(),
+                        error: error.as_ref().map_or_else(|| status.to_string(), |e| e.error.clone()),
+                        message: error.map(|e| e.message).unwrap_or_default(),
+                    };
+                    if !retryable {
+                        return Err(failure);
+                    }
+                    (retry_after, failure)
+                }
+                // Safe to resend: reads change nothing and mutations carry their key
+                Err(e) => (None, SdkError::Transport(e)),
+            };
+
+            if attempt >= self.retry.max_attempts {
+                return Err(failure);
+            }
+            let wait = self.retry.backoff(attempt, retry_after);
+            warn!("{} {} attempt {} failed, retrying in {:?}: {}", method, path, attempt, wait, failure);
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
@@ -0,0 +1,348 @@
+//! Security event stream: authentication and authorization failures, rate
+//! limit bans and operator writes.
+//!
+//! Events are written to `security_events`, apart from the audit log, and,
+//! when a SIEM endpoint is configured, forwarded to it as JSON in the
+//! background; a failure to record or forward is logged and never fails the
+//! request that raised the event. Presented credentials are identified only by
+//! the hash prefix the rate limiter keys them by.
+//!
+//! The monitor's burst check applies `BURST_RULES` to recent events. A rule
+//! whose threshold is reached within its window, by one source or in total, is
+//! logged at error level and audited as `security_event_burst`, once until the
+//! count falls back below the threshold.
+
+use crate::abuse;
+use crate::database::SecurityEventRepository;
+use crate::error::{Result, VaultError};
+use crate::models::{SecurityEvent, SecurityEventCount};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn, error};
+
+pub const SECURITY_EVENT_BURST_EVENT: &str = "security_event_burst";
+
+/// Events are kept this long before pruning
+pub const SECURITY_EVENT_RETENTION_DAYS: i64 = 90;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecurityEventKind {
+    /// Missing, malformed or unknown staff credential
+    CredentialInvalid,
+    /// A revoked staff credential was presented
+    CredentialRevoked,
+    /// A valid credential used beyond its role
+    ScopeViolation,
+    /// Webhook whose HMAC signature is missing or does not match
+    WebhookSignatureInvalid,
+    /// A client banned by an operator or an escalation rule
+    RateLimitBan,
+    /// A successful write to an operator endpoint
+    AdminAction,
+}
+
+impl SecurityEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SecurityEventKind::CredentialInvalid => "credential_invalid",
+            SecurityEventKind::CredentialRevoked => "credential_revoked",
+            SecurityEventKind::ScopeViolation => "scope_violation",
+            SecurityEventKind::WebhookSignatureInvalid => "webhook_signature_invalid",
+            SecurityEventKind::RateLimitBan => "rate_limit_ban",
+            SecurityEventKind::AdminAction => "admin_action",
+        }
+    }
+
+    pub fn severity(&self) -> SecuritySeverity {
+        match self {
+            SecurityEventKind::AdminAction => SecuritySeverity::Info,
+            SecurityEventKind::CredentialInvalid
+            | SecurityEventKind::ScopeViolation
+            | SecurityEventKind::WebhookSignatureInvalid
+            | SecurityEventKind::RateLimitBan => SecuritySeverity::Warning,
+            // Someone holds a credential that was taken away
+            SecurityEventKind::CredentialRevoked => SecuritySeverity::Critical,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecuritySeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl SecuritySeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SecuritySeverity::Info => "info",
+            SecuritySeverity::Warning => "warning",
+            SecuritySeverity::Critical => "critical",
+        }
+    }
+}
+
+/// A security event to record
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewSecurityEvent {
+    pub kind: SecurityEventKind,
+    /// Client the event concerns, as the rate limiter identifies clients
+    pub source: String,
+    pub actor: Option<String>,
+    pub details: serde_json::Value,
+}
+
+impl NewSecurityEvent {
+    pub fn new(kind: SecurityEventKind, source: impl Into<String>) -> Self {
+        Self { kind, source: source.into(), actor: None, details: serde_json::json!({}) }
+    }
+
+    pub fn with_actor(mut self, actor: impl Into<String>) -> Self {
+        self.actor = Some(actor.into());
+        self
+    }
+
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = details;
+        self
+    }
+}
+
+/// Source of a request's events: its client address, whatever credential it
+/// carried, so a client cycling through tokens is still one source
+pub fn request_source(forwarded_for: Option<&str>) -> String {
+    abuse::client_identifier(None, forwarded_for)
+}
+
+/// Identifies a presented credential without storing it
+pub fn credential_fingerprint(token: &str) -> String {
+    abuse::client_identifier(Some(token), None)
+}
+
+/// An alerting rule over recent security events
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BurstRule {
+    pub name: &'static str,
+    pub kinds: &'static [SecurityEventKind],
+    pub threshold: i64,
+    pub window_seconds: i64,
+    /// Count per source rather than across all sources
+    pub per_source: bool,
+}
+
+pub const BURST_RULES: &[BurstRule] = &[
+    BurstRule {
+        name: "credential_guessing",
+        kinds: &[SecurityEventKind::CredentialInvalid],
+        threshold: 20,
+        window_seconds: 300,
+        per_source: true,
+    },
+    BurstRule {
+        name: "revoked_credential_use",
+        kinds: &[SecurityEventKind::CredentialRevoked],
+        threshold: 1,
+        window_seconds: 3600,
+        per_source: true,
+    },
+    BurstRule {
+        name: "scope_probing",
+        kinds: &[SecurityEventKind::ScopeViolation],
+        threshold: 5,
+        window_seconds: 600,
+        per_source: true,
+    },
+    BurstRule {
+        name: "webhook_forgery",
+        kinds: &[SecurityEventKind::WebhookSignatureInvalid],
+        threshold: 5,
+        window_seconds: 600,
+        per_source: true,
+    },
+    BurstRule {
+        name: "ban_wave",
+        kinds: &[SecurityEventKind::RateLimitBan],
+        threshold: 10,
+        window_seconds: 600,
+        per_source: false,
+    },
+    BurstRule {
+        name: "admin_write_surge",
+        kinds: &[SecurityEventKind::AdminAction],
+        threshold: 60,
+        window_seconds: 300,
+        per_source: true,
+    },
+];
+
+/// A rule's threshold reached by one source, or in total for rules that are not per source
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecurityBurst {
+    pub rule: String,
+    pub source: Option<String>,
+    pub events: i64,
+    pub window_seconds: i64,
+}
+
+/// Bursts of `rule` given the per-source event counts within its window
+pub fn detect_bursts(rule: &BurstRule, counts: &[SecurityEventCount]) -> Vec<SecurityBurst> {
+    let burst = |source: Option<String>, events: i64| SecurityBurst {
+        rule: rule.name.to_string(),
+        source,
+        events,
+        window_seconds: rule.window_seconds,
+    };
+
+    if rule.per_source {
+        counts.iter()
+            .filter(|count| count.events >= rule.threshold)
+            .map(|count| burst(Some(count.source.clone()), count.events))
+            .collect()
+    } else {
+        let total: i64 = counts.iter().map(|count| count.events).sum();
+        if total >= rule.threshold { vec![burst(None, total)] } else { Vec::new() }
+    }
+}
+
+/// Posts each recorded event as JSON to a SIEM collector
+pub struct SiemForwarder {
+    client: reqwest::Client,
+    endpoint: String,
+    token: Option<String>,
+}
+
+impl SiemForwarder {
+    pub fn new(endpoint: String, token: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(5))
+                .build()
+                .unwrap_or_default(),
+            endpoint,
+            token,
+        }
+    }
+
+    pub async fn forward(&self, event: &SecurityEvent) -> Result<()> {
+        let mut request = self.client.post(&self.endpoint).json(event);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request.send().await
+            .map_err(|e| VaultError::Internal(format!("SIEM forward failed: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(VaultError::Internal(format!("SIEM collector answered {}", response.status())));
+        }
+        Ok(())
+    }
+}
+
+/// Records security events, forwards them, and alerts on bursts
+pub struct SecurityEventLog {
+    repo: SecurityEventRepository,
+    forwarder: Option<Arc<SiemForwarder>>,
+    /// Rule and source of bursts already alerted, cleared when they subside
+    alerted: Mutex<HashSet<(String, Option<String>)>>,
+}
+
+impl SecurityEventLog {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self {
+            repo: SecurityEventRepository::new(pool),
+            forwarder: None,
+            alerted: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn with_forwarder(mut self, forwarder: SiemForwarder) -> Self {
+        self.forwarder = Some(Arc::new(forwarder));
+        self
+    }
+
+    /// Record an event; failures are logged, never propagated to the caller
+    pub async fn record(&self, event: NewSecurityEvent) {
+        let severity = event.kind.severity();
+        warn!("Security event {} ({}) from {}", event.kind.as_str(), severity.as_str(), event.source);
+
+        let stored = match self.repo
+            .insert(event.kind.as_str(), severity.as_str(), &event.source, event.actor.as_deref(), &event.details)
+            .await
+        {
+            Ok(stored) => stored,
+            Err(e) => {
+                error!("Failed to record {} security event from {}: {}", event.kind.as_str(), event.source, e);
+                return;
+            }
+        };
+
+        if let Some(forwarder) = &self.forwarder {
+            let forwarder = forwarder.clone();
+            tokio::spawn(async move {
+                if let Err(e) = forwarder.forward(&stored).await {
+                    warn!("Failed to forward security event {}: {}", stored.id, e);
+                }
+            });
+        }
+    }
+
+    /// Events newest first, optionally of one type and/or source, since a time
+    pub async fn list(
+        &self,
+        event_type: Option<&str>,
+        source: Option<&str>,
+        since: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> Result<Vec<SecurityEvent>> {
+        self.repo.list(event_type, source, since, limit).await
+    }
+
+    /// Apply every burst rule at `now`; returns the bursts newly alerted
+    pub async fn check_bursts(&self, now: DateTime<Utc>) -> Result<Vec<SecurityBurst>> {
+        let mut bursts = Vec::new();
+        for rule in BURST_RULES {
+            let kinds: Vec<String> = rule.kinds.iter().map(|kind| kind.as_str().to_string()).collect();
+            let counts = self.repo.count_by_source(&kinds, now - Duration::seconds(rule.window_seconds)).await?;
+            bursts.push((rule.name, detect_bursts(rule, &counts)));
+        }
+
+        let mut newly_alerted = Vec::new();
+        {
+            let mut alerted = self.alerted.lock().unwrap_or_else(|e| e.into_inner());
+            for (rule, rule_bursts) in &bursts {
+                let active: HashSet<_> = rule_bursts.iter().map(|b| (b.rule.clone(), b.source.clone())).collect();
+                alerted.retain(|key| key.0 != *rule || active.contains(key));
+                for burst in rule_bursts {
+                    if alerted.insert((burst.rule.clone(), burst.source.clone())) {
+                        newly_alerted.push(burst.clone());
+                    }
+                }
+            }
+        }
+
+        for burst in &newly_alerted {
+            error!("Security event burst {}: {} events from {} within {}s",
+                   burst.rule, burst.events, burst.source.as_deref().unwrap_or("all sources"), burst.window_seconds);
+            if let Err(e) = self.repo.record_burst(&burst.rule, burst.source.as_deref(), burst.events,
+                                                   burst.window_seconds, SECURITY_EVENT_BURST_EVENT).await {
+                warn!("Failed to audit security event burst {}: {}", burst.rule, e);
+            }
+        }
+
+        Ok(newly_alerted)
+    }
+
+    /// Delete events past retention
+    pub async fn prune(&self, now: DateTime<Utc>) -> Result<u64> {
+        let pruned = self.repo.prune(now - Duration::days(SECURITY_EVENT_RETENTION_DAYS)).await?;
+        if pruned > 0 {
+            info!("Pruned {} security events", pruned);
+        }
+        Ok(pruned)
+    }
+}
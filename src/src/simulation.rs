@@ -0,0 +1,522 @@
+//! Deterministic simulation of reconciliation, remediation and alerting.
+//!
+//! A `Simulation` stands in for the cluster, the database and the balance
+//! cache of one vault, all in memory and driven by a scripted clock. A
+//! scenario is a list of `SimStep`s: operations that land on-chain and are
+//! then indexed, optionally with a `SimFault` between the two, snapshots,
+//! monitor reconciliation passes, and the remediations the monitor or an
+//! operator would apply.
+//!
+//! Passes run the checks `Reconciler` runs (`reconciliation::run_checks`) and
+//! count and alert through the same `ReconciliationRunSummary`; cache pushes
+//! go through the balance tracker's ordering rule. Ids, signatures and
+//! timestamps derive from counters and the clock, so a scenario has the same
+//! outcome on every run and needs no cluster or database.
+
+use crate::balance_tracker::{apply_to_cache, compare_cached, BalanceCache, Discrepancy, DiscrepancyCode, Remediation};
+use crate::error::{DomainError, Result};
+use crate::models::{
+    BalanceSnapshot, BalanceUpdate, BalanceUpdateSource, ReconciliationMode, TransactionRecord, TransactionStatus,
+    TransactionType, Vault,
+};
+use crate::reconciliation::{record_effect, run_checks, ReconciliationReport, ReconciliationRunSummary, ReconciliationSource};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Clock start of every simulation, 2024-01-01T00:00:00Z
+pub const SIMULATION_EPOCH: i64 = 1_704_067_200;
+
+/// A vault operation as submitted on-chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SimOperation {
+    Deposit { amount: i64 },
+    Withdraw { amount: i64 },
+    Lock { amount: i64 },
+    Unlock { amount: i64 },
+}
+
+impl SimOperation {
+    fn transaction_type(&self) -> TransactionType {
+        match self {
+            SimOperation::Deposit { .. } => TransactionType::Deposit,
+            SimOperation::Withdraw { .. } => TransactionType::Withdraw,
+            SimOperation::Lock { .. } => TransactionType::Lock,
+            SimOperation::Unlock { .. } => TransactionType::Unlock,
+        }
+    }
+
+    fn amount(&self) -> i64 {
+        match self {
+            SimOperation::Deposit { amount }
+            | SimOperation::Withdraw { amount }
+            | SimOperation::Lock { amount }
+            | SimOperation::Unlock { amount } => *amount,
+        }
+    }
+}
+
+/// What goes wrong between an operation landing on-chain and the backend recording it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SimFault {
+    /// The indexer never sees the transaction: no record, no balance change
+    MissedEvent,
+    /// The record is confirmed, but the vault row update and the cache push are lost
+    PartialWrite,
+    /// The database is written, the cache push is dropped
+    DroppedCacheUpdate,
+    /// The confirmation waits for `SimStep::ReleaseConfirmations`
+    HeldConfirmation,
+}
+
+/// One step of a scenario
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum SimStep {
+    /// Land an operation on-chain and index it, unless `fault` intervenes
+    Submit {
+        operation: SimOperation,
+        #[serde(default)]
+        fault: Option<SimFault>,
+    },
+    /// Confirm held operations one second apart; `reversed` delivers the newest first
+    ReleaseConfirmations { reversed: bool },
+    Advance { seconds: i64 },
+    /// Snapshot the vault row
+    Snapshot,
+    /// A monitor reconciliation pass
+    Reconcile { mode: ReconciliationMode },
+    /// Apply the automatable remediations of the last pass's findings
+    Remediate,
+    /// Operator backfill of records for on-chain transactions that have none
+    Backfill,
+}
+
+/// A critical finding the pass alerted on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimAlert {
+    pub at: DateTime<Utc>,
+    pub mode: ReconciliationMode,
+    pub code: DiscrepancyCode,
+    pub field: String,
+    pub expected: i64,
+    pub observed: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimRemediation {
+    pub at: DateTime<Utc>,
+    pub remediation: Remediation,
+    /// False for remediations that need review, which the simulation leaves alone
+    pub applied: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimPass {
+    pub report: ReconciliationReport,
+    pub summary: ReconciliationRunSummary,
+}
+
+/// Everything a scenario produced, in step order
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SimulationOutcome {
+    pub passes: Vec<SimPass>,
+    pub alerts: Vec<SimAlert>,
+    pub remediations: Vec<SimRemediation>,
+}
+
+/// A transaction that landed on-chain
+#[derive(Debug, Clone)]
+struct Landed {
+    signature: String,
+    operation: SimOperation,
+    landed_at: DateTime<Utc>,
+    confirmed: bool,
+}
+
+/// In-memory cluster, database and balance cache of one vault
+pub struct Simulation {
+    clock: DateTime<Utc>,
+    next_id: u128,
+    /// Database row
+    vault: Vault,
+    /// On-chain vault account
+    account: collateral_vault_types::Vault,
+    token_balance: u64,
+    /// On-chain history, oldest first
+    chain: Vec<Landed>,
+    /// Indices into `chain` awaiting confirmation
+    held: Vec<usize>,
+    ledger: Vec<TransactionRecord>,
+    snapshots: Vec<BalanceSnapshot>,
+    cache: Mutex<HashMap<Uuid, BalanceCache>>,
+    last_report: Option<ReconciliationReport>,
+    outcome: SimulationOutcome,
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Simulation {
+    /// An empty, active vault created at `SIMULATION_EPOCH`
+    pub fn new() -> Self {
+        let epoch = DateTime::<Utc>::from_timestamp(SIMULATION_EPOCH, 0).unwrap_or_default();
+        let vault_id = Uuid::from_u128(1);
+        Self {
+            clock: epoch,
+            next_id: 2,
+            vault: Vault {
+                id: vault_id,
+                user_pubkey: Pubkey::new_from_array([1; 32]).to_string(),
+                vault_pubkey: Pubkey::new_from_array([2; 32]).to_string(),
+                token_account_pubkey: Pubkey::new_from_array([3; 32]).to_string(),
+                bump: 255,
+                total_balance: 0,
+                locked_balance: 0,
+                available_balance: 0,
+                last_updated: epoch,
+                is_active: true,
+                authority: Pubkey::new_from_array([4; 32]).to_string(),
+                created_at: epoch,
+                updated_at: epoch,
+            },
+            account: collateral_vault_types::Vault {
+                user: Pubkey::new_from_array([1; 32]),
+                token_account: Pubkey::new_from_array([3; 32]),
+                bump: 255,
+                total_balance: 0,
+                locked_balance: 0,
+                available_balance: 0,
+                last_updated: SIMULATION_EPOCH,
+                is_active: true,
+                authority: Pubkey::new_from_array([4; 32]),
+                created_at: SIMULATION_EPOCH,
+                counters_since: SIMULATION_EPOCH,
+                deposit_count: 0,
+                withdraw_count: 0,
+                total_deposited: 0,
+                total_withdrawn: 0,
+                authorized_authorities: Vec::new(),
+                withdrawal_cooldown: 0,
+                pending_withdrawal: 0,
+                withdrawal_unlocks_at: 0,
+                event_sequence: 0,
+                sub_account_id: 0,
+                frozen: false,
+                unfreeze_requested_at: 0,
+                processing: false,
+                recorded_locked_balance: 0,
+                open_records: 0,
+            },
+            token_balance: 0,
+            chain: Vec::new(),
+            held: Vec::new(),
+            ledger: Vec::new(),
+            snapshots: Vec::new(),
+            cache: Mutex::new(HashMap::new()),
+            last_report: None,
+            outcome: SimulationOutcome::default(),
+        }
+    }
+
+    /// Start from a vault account with the legacy layout, without counters
+    pub fn with_legacy_layout(mut self) -> Self {
+        self.account.created_at = 0;
+        self.account.counters_since = 0;
+        self
+    }
+
+    /// Run a scenario, stopping at the first step the chain rejects
+    pub async fn run(&mut self, steps: &[SimStep]) -> Result<&SimulationOutcome> {
+        for step in steps {
+            self.step(step).await?;
+        }
+        Ok(&self.outcome)
+    }
+
+    pub async fn step(&mut self, step: &SimStep) -> Result<()> {
+        match step {
+            SimStep::Submit { operation, fault } => self.submit(*operation, *fault)?,
+            SimStep::ReleaseConfirmations { reversed } => {
+                let mut held = std::mem::take(&mut self.held);
+                if *reversed {
+                    held.reverse();
+                }
+                for index in held {
+                    self.confirm(index, true, true);
+                    self.clock += Duration::seconds(1);
+                }
+            }
+            SimStep::Advance { seconds } => self.clock += Duration::seconds(*seconds),
+            SimStep::Snapshot => {
+                let id = self.next_uuid();
+                self.snapshots.push(BalanceSnapshot {
+                    id,
+                    vault_id: self.vault.id,
+                    total_balance: self.vault.total_balance,
+                    locked_balance: self.vault.locked_balance,
+                    available_balance: self.vault.available_balance,
+                    snapshot_time: self.clock,
+                    block_height: None,
+                });
+            }
+            SimStep::Reconcile { mode } => self.reconcile(*mode).await?,
+            SimStep::Remediate => self.remediate(),
+            SimStep::Backfill => {
+                let unrecorded: Vec<usize> = (0..self.chain.len())
+                    .filter(|index| !self.chain[*index].confirmed && !self.held.contains(index))
+                    .collect();
+                for index in unrecorded {
+                    self.confirm(index, false, false);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn outcome(&self) -> &SimulationOutcome {
+        &self.outcome
+    }
+
+    pub fn clock(&self) -> DateTime<Utc> {
+        self.clock
+    }
+
+    /// The database row
+    pub fn vault(&self) -> &Vault {
+        &self.vault
+    }
+
+    /// The on-chain vault account
+    pub fn vault_account_state(&self) -> &collateral_vault_types::Vault {
+        &self.account
+    }
+
+    /// The cached (total, locked, available), if an entry is held
+    pub fn cached_balance(&self) -> Option<(u64, u64, u64)> {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner()).get(&self.vault.id).map(BalanceCache::balances)
+    }
+
+    fn next_uuid(&mut self) -> Uuid {
+        self.next_id += 1;
+        Uuid::from_u128(self.next_id)
+    }
+
+    fn record_for(&mut self, index: usize, confirmed_at: DateTime<Utc>) -> TransactionRecord {
+        let landed = self.chain[index].clone();
+        TransactionRecord {
+            id: self.next_uuid(),
+            vault_id: self.vault.id,
+            transaction_type: landed.operation.transaction_type(),
+            amount: landed.operation.amount(),
+            tx_signature: Some(landed.signature),
+            status: TransactionStatus::Confirmed,
+            error_message: None,
+            created_at: landed.landed_at,
+            updated_at: confirmed_at,
+        }
+    }
+
+    /// Land `operation` on-chain, then index it unless `fault` says otherwise
+    fn submit(&mut self, operation: SimOperation, fault: Option<SimFault>) -> Result<()> {
+        let landed = Landed {
+            signature: format!("sim-{}", self.chain.len() + 1),
+            operation,
+            landed_at: self.clock,
+            confirmed: false,
+        };
+        let (total_change, locked_change) = record_effect(&TransactionRecord {
+            id: Uuid::nil(),
+            vault_id: self.vault.id,
+            transaction_type: operation.transaction_type(),
+            amount: operation.amount(),
+            tx_signature: None,
+            status: TransactionStatus::Confirmed,
+            error_message: None,
+            created_at: self.clock,
+            updated_at: self.clock,
+        });
+
+        // The program refuses anything that would leave the account inconsistent
+        let total = self.account.total_balance as i64 + total_change;
+        let locked = self.account.locked_balance as i64 + locked_change;
+        if operation.amount() <= 0 || total < 0 || locked < 0 || locked > total {
+            return Err(DomainError::Validation(format!(
+                "{:?} rejected on-chain: total={} locked={}", operation, self.account.total_balance, self.account.locked_balance
+            )).into());
+        }
+
+        self.account.total_balance = total as u64;
+        self.account.locked_balance = locked as u64;
+        self.account.available_balance = (total - locked) as u64;
+        self.account.last_updated = self.clock.timestamp();
+        self.token_balance = (self.token_balance as i64 + total_change) as u64;
+        if self.account.counters_tracked() {
+            match operation {
+                SimOperation::Deposit { amount } => {
+                    self.account.deposit_count += 1;
+                    self.account.total_deposited += amount as u64;
+                }
+                SimOperation::Withdraw { amount } => {
+                    self.account.withdraw_count += 1;
+                    self.account.total_withdrawn += amount as u64;
+                }
+                SimOperation::Lock { .. } | SimOperation::Unlock { .. } => {}
+            }
+        }
+        self.chain.push(landed);
+
+        let index = self.chain.len() - 1;
+        match fault {
+            None => self.confirm(index, true, true),
+            Some(SimFault::MissedEvent) => {}
+            Some(SimFault::PartialWrite) => self.confirm(index, false, false),
+            Some(SimFault::DroppedCacheUpdate) => self.confirm(index, true, false),
+            Some(SimFault::HeldConfirmation) => self.held.push(index),
+        }
+        Ok(())
+    }
+
+    /// Write the confirmed record of `chain[index]` and, as asked, the vault row and the cache push
+    fn confirm(&mut self, index: usize, update_row: bool, push_cache: bool) {
+        let record = self.record_for(index, self.clock);
+        let (total_change, locked_change) = record_effect(&record);
+        self.ledger.push(record);
+        self.chain[index].confirmed = true;
+
+        if !update_row {
+            return;
+        }
+        self.vault.total_balance += total_change;
+        self.vault.locked_balance += locked_change;
+        self.vault.available_balance = self.vault.total_balance - self.vault.locked_balance;
+        self.vault.last_updated = self.clock;
+        self.vault.updated_at = self.clock;
+
+        if push_cache {
+            // The indexer's push carries the time the change was observed on-chain
+            let update = BalanceUpdate {
+                as_of: self.chain[index].landed_at,
+                ..BalanceUpdate::from_vault(&self.vault, BalanceUpdateSource::Chain)
+            };
+            apply_to_cache(&mut self.cache.lock().unwrap_or_else(|e| e.into_inner()), &update);
+        }
+    }
+
+    /// One monitor pass over the vault, as `Reconciler::reconcile_many` runs it
+    async fn reconcile(&mut self, mode: ReconciliationMode) -> Result<()> {
+        let vault = self.vault.clone();
+        let findings = run_checks(&*self, &vault, mode).await?;
+        let report = ReconciliationReport {
+            vault_id: vault.id,
+            mode,
+            is_consistent: findings.is_empty(),
+            findings,
+            started_at: self.clock,
+            completed_at: self.clock,
+        };
+
+        let mut summary = ReconciliationRunSummary::default();
+        summary.record(vault.id, mode, Ok(&report));
+        self.outcome.alerts.extend(report.alerts().map(|finding| SimAlert {
+            at: self.clock,
+            mode,
+            code: finding.code,
+            field: finding.field.clone(),
+            expected: finding.expected,
+            observed: finding.observed,
+        }));
+        self.outcome.passes.push(SimPass { report: report.clone(), summary });
+        self.last_report = Some(report);
+        Ok(())
+    }
+
+    /// Apply each distinct remediation of the last pass once
+    fn remediate(&mut self) {
+        let Some(report) = self.last_report.take() else { return };
+        let mut seen = HashSet::new();
+
+        for finding in &report.findings {
+            let remediation = finding.remediation;
+            if !seen.insert(remediation.as_str()) {
+                continue;
+            }
+            let applied = remediation.is_automatable();
+            match remediation {
+                Remediation::RefreshCache => {
+                    self.cache.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.vault.id);
+                }
+                Remediation::ResyncFromChain => {
+                    let (total, locked, available) = self.account.balances().as_signed();
+                    self.vault.total_balance = total;
+                    self.vault.locked_balance = locked;
+                    self.vault.available_balance = available;
+                    self.vault.last_updated = self.clock;
+                    self.vault.updated_at = self.clock;
+                    let update = BalanceUpdate::from_vault(&self.vault, BalanceUpdateSource::Database);
+                    apply_to_cache(&mut self.cache.lock().unwrap_or_else(|e| e.into_inner()), &update);
+                }
+                Remediation::MigrateLayout => {
+                    // Migration starts the counters at zero
+                    self.account.counters_since = self.clock.timestamp();
+                }
+                Remediation::BackfillRecords | Remediation::ReviewRecords | Remediation::ManualReview => {}
+            }
+            self.outcome.remediations.push(SimRemediation { at: self.clock, remediation, applied });
+        }
+    }
+}
+
+#[async_trait]
+impl ReconciliationSource for Simulation {
+    fn now(&self) -> DateTime<Utc> {
+        self.clock
+    }
+
+    async fn cache_discrepancies(&self, vault: &Vault) -> Result<Vec<Discrepancy>> {
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        let cached = match cache.get(&vault.id) {
+            Some(entry) => entry.balances(),
+            None => {
+                apply_to_cache(&mut cache, &BalanceUpdate::from_vault(vault, BalanceUpdateSource::Database));
+                (vault.total_balance as u64, vault.locked_balance as u64, vault.available_balance as u64)
+            }
+        };
+
+        let discrepancies = compare_cached(vault, cached);
+        if cached != (vault.total_balance as u64, vault.locked_balance as u64, vault.available_balance as u64) {
+            cache.remove(&vault.id);
+        }
+        Ok(discrepancies)
+    }
+
+    async fn vault_account(&self, _vault: &Vault) -> Result<collateral_vault_types::Vault> {
+        Ok(self.account.clone())
+    }
+
+    async fn token_account_balance(&self, _vault: &Vault) -> Result<u64> {
+        Ok(self.token_balance)
+    }
+
+    async fn recent_signatures(&self, _vault: &Vault) -> Result<Vec<String>> {
+        // Newest first, as the cluster answers
+        Ok(self.chain.iter().rev().map(|landed| landed.signature.clone()).collect())
+    }
+
+    async fn confirmed_ledger(&self, _vault_id: Uuid) -> Result<Vec<TransactionRecord>> {
+        Ok(self.ledger.clone())
+    }
+
+    async fn snapshot_history(&self, _vault_id: Uuid, since: DateTime<Utc>) -> Result<Vec<BalanceSnapshot>> {
+        Ok(self.snapshots.iter().filter(|snapshot| snapshot.snapshot_time >= since).cloned().collect())
+    }
+}
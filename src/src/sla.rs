@@ -0,0 +1,181 @@
+//! Per-operation latency SLAs, as reported to institutional clients.
+//!
+//! Every operation class has a target latency that `objective_percent` of its
+//! confirmed operations must meet. An operation's latency is the sum of its
+//! pipeline stage timers (`crate::latency`) when it ran through the timed
+//! pipeline, otherwise the time from its record's creation to its
+//! confirmation, queueing included. Attainment is computed over a rolling
+//! window of confirmed operations.
+//!
+//! The monitor's SLA check alerts on classes whose attainment is below the
+//! objective with at least `MIN_OPERATIONS_FOR_BREACH` operations in the
+//! window: logged at error level and audited as `sla_breach`, once until the
+//! class recovers.
+
+use crate::database::SlaRepository;
+use crate::error::Result;
+use crate::models::SlaLatencyCounts;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Mutex;
+use tracing::{info, warn, error};
+
+pub const SLA_BREACH_EVENT: &str = "sla_breach";
+
+/// Fewer operations in the window than this never count as a breach
+pub const MIN_OPERATIONS_FOR_BREACH: i64 = 20;
+
+pub const DEFAULT_SLA_OBJECTIVE_PERCENT: f64 = 99.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlaTarget {
+    pub operation_type: String,
+    /// Request to confirmation
+    pub target_ms: u64,
+    /// Share of operations that must meet the target
+    pub objective_percent: f64,
+}
+
+/// Targets used unless overridden, per `transaction_records.operation_type`
+pub fn default_sla_targets(objective_percent: f64) -> Vec<SlaTarget> {
+    [("deposit", 60_000), ("withdraw", 60_000), ("lock", 10_000), ("unlock", 10_000), ("transfer", 15_000)]
+        .into_iter()
+        .map(|(operation_type, target_ms)| SlaTarget {
+            operation_type: operation_type.to_string(),
+            target_ms,
+            objective_percent,
+        })
+        .collect()
+}
+
+/// Defaults with the targets in `spec` overridden, e.g. `withdraw=30000,lock=5000`;
+/// `None` for an unknown class, a malformed entry or an objective outside (0, 100]
+pub fn parse_sla_targets(spec: &str, objective_percent: f64) -> Option<Vec<SlaTarget>> {
+    if !(objective_percent > 0.0 && objective_percent <= 100.0) {
+        return None;
+    }
+
+    let mut targets = default_sla_targets(objective_percent);
+    for entry in spec.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+        let (operation_type, target_ms) = entry.split_once('=')?;
+        let target_ms: u64 = target_ms.trim().parse().ok().filter(|ms| *ms > 0)?;
+        let target = targets.iter_mut().find(|t| t.operation_type == operation_type.trim())?;
+        target.target_ms = target_ms;
+    }
+    Some(targets)
+}
+
+/// One class's attainment over a window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlaAttainment {
+    pub operation_type: String,
+    pub target_ms: u64,
+    pub objective_percent: f64,
+    pub operations: i64,
+    pub within_target: i64,
+    /// `None` without operations in the window
+    pub attainment_percent: Option<f64>,
+    pub p95_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+    /// Below the objective with enough operations to tell
+    pub breached: bool,
+}
+
+pub fn compute_attainment(target: &SlaTarget, counts: &SlaLatencyCounts) -> SlaAttainment {
+    let attainment_percent = (counts.operations > 0)
+        .then(|| counts.within_target as f64 * 100.0 / counts.operations as f64);
+
+    SlaAttainment {
+        operation_type: target.operation_type.clone(),
+        target_ms: target.target_ms,
+        objective_percent: target.objective_percent,
+        operations: counts.operations,
+        within_target: counts.within_target,
+        attainment_percent,
+        p95_ms: counts.p95_ms,
+        max_ms: counts.max_ms,
+        breached: counts.operations >= MIN_OPERATIONS_FOR_BREACH
+            && attainment_percent.map_or(false, |percent| percent < target.objective_percent),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlaReport {
+    pub window_minutes: i64,
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    pub operations: Vec<SlaAttainment>,
+}
+
+/// Measures attainment against the configured targets and alerts on breaches
+pub struct SlaTracker {
+    repo: SlaRepository,
+    targets: Vec<SlaTarget>,
+    /// Window of the breach check
+    window_minutes: i64,
+    /// Classes already alerted, cleared when they recover
+    alerted: Mutex<HashSet<String>>,
+}
+
+impl SlaTracker {
+    pub fn new(pool: sqlx::PgPool, targets: Vec<SlaTarget>, window_minutes: i64) -> Self {
+        Self {
+            repo: SlaRepository::new(pool),
+            targets,
+            window_minutes,
+            alerted: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn targets(&self) -> &[SlaTarget] {
+        &self.targets
+    }
+
+    /// Attainment of every class over the `window_minutes` before `now`
+    /// (the breach check's window when `None`)
+    pub async fn report(&self, now: DateTime<Utc>, window_minutes: Option<i64>) -> Result<SlaReport> {
+        let window_minutes = window_minutes.unwrap_or(self.window_minutes);
+        let since = now - Duration::minutes(window_minutes);
+
+        let mut operations = Vec::with_capacity(self.targets.len());
+        for target in &self.targets {
+            let counts = self.repo.latency_counts(&target.operation_type, target.target_ms, since).await?;
+            operations.push(compute_attainment(target, &counts));
+        }
+
+        Ok(SlaReport { window_minutes, since, until: now, operations })
+    }
+
+    /// Alert on classes newly in breach at `now`; returns them
+    pub async fn check_breaches(&self, now: DateTime<Utc>) -> Result<Vec<SlaAttainment>> {
+        let report = self.report(now, None).await?;
+
+        let mut newly_breached = Vec::new();
+        {
+            let mut alerted = self.alerted.lock().unwrap_or_else(|e| e.into_inner());
+            for attainment in report.operations {
+                if attainment.breached {
+                    if alerted.insert(attainment.operation_type.clone()) {
+                        newly_breached.push(attainment);
+                    }
+                } else if alerted.remove(&attainment.operation_type) {
+                    info!("{} SLA recovered", attainment.operation_type);
+                }
+            }
+        }
+
+        for breach in &newly_breached {
+            error!("{} SLA breached: {:.2}% of {} operations within {}ms over {} minutes (objective {}%)",
+                   breach.operation_type, breach.attainment_percent.unwrap_or(0.0), breach.operations,
+                   breach.target_ms, report.window_minutes, breach.objective_percent);
+            if let Err(e) = self.repo.record_breach(&breach.operation_type, breach.target_ms as i64, breach.objective_percent,
+                                                    breach.attainment_percent, breach.operations, report.window_minutes,
+                                                    SLA_BREACH_EVENT).await {
+                warn!("Failed to audit {} SLA breach: {}", breach.operation_type, e);
+            }
+        }
+
+        Ok(newly_breached)
+    }
+}
@@ -0,0 +1,402 @@
+//! Sequenced event stream behind the WebSocket API.
+//!
+//! Every event published in this process gets the next sequence number and is
+//! kept in a bounded in-memory buffer, so a client that reconnects can resume
+//! from the last event it processed instead of refetching everything.
+//!
+//! Protocol (JSON text frames tagged by `type`):
+//!
+//! ```text
+//! client -> server
+//!   {"type":"subscribe","id":"1","channels":["balances","transactions"],"vault":"<user_pubkey>","resume_token":"<token>"}
+//!   {"type":"unsubscribe","id":"2","channels":["transactions"]}
+//!   {"type":"ack","seq":42}
+//!
+//! server -> client
+//!   {"type":"hello","protocol_version":1,"channels":[],"resume_token":"<token>"}
+//!   {"type":"subscribed","id":"1","channels":["balances","transactions"],"resumed":true}
+//!   {"type":"event","seq":43,"channel":"balances","resume_token":"<token>","published_at":"...","event":{"kind":"balance_updated",...}}
+//!   {"type":"reset","reason":"...","resume_token":"<token>"}
+//!   {"type":"error","id":"1","message":"..."}
+//! ```
+//!
+//! - `balances` and `transactions` are per vault and need `vault`; `system` is global
+//!   and carries periodic metrics and maintenance announcements.
+//! - Sequence numbers increase monotonically but a connection only sees the
+//!   events matching its subscriptions, so they are not contiguous.
+//! - Clients ack the last `seq` they processed. At most `MAX_UNACKED_EVENTS`
+//!   are in flight per connection; delivery pauses until the client acks.
+//! - A resume token is only valid in the process that issued it and while its
+//!   events are still buffered. Otherwise the server sends `reset` and the
+//!   client should refetch state over REST before relying on the stream again.
+
+use crate::models::{BalanceUpdate, TransactionRecord, WithdrawalQueueStatus};
+use crate::maintenance::MaintenanceNotice;
+use crate::vault_monitor::VaultMonitor;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use tokio::sync::{broadcast, watch, RwLock};
+use tracing::{warn, error};
+use uuid::Uuid;
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Events kept for resumption
+const EVENT_BUFFER_CAPACITY: usize = 10_000;
+
+/// Delivered but unacknowledged events allowed per connection
+pub const MAX_UNACKED_EVENTS: usize = 512;
+
+/// Cadence of `system` channel metrics
+const SYSTEM_METRICS_INTERVAL_SECONDS: u64 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Channel {
+    Balances,
+    Transactions,
+    System,
+}
+
+impl Channel {
+    /// Channel carries one vault's events and needs a vault filter
+    pub fn is_per_vault(&self) -> bool {
+        matches!(self, Channel::Balances | Channel::Transactions)
+    }
+}
+
+/// System-wide figures published periodically on the `system` channel
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SystemMetrics {
+    pub vault_count: i64,
+    pub pending_transactions: i64,
+    pub failed_transactions_24h: i64,
+    pub total_value_locked: i64,
+    pub is_healthy: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StreamEvent {
+    BalanceUpdated(BalanceUpdate),
+    TransactionUpdated(TransactionRecord),
+    SystemMetrics(SystemMetrics),
+    Maintenance(MaintenanceNotice),
+}
+
+impl StreamEvent {
+    pub fn channel(&self) -> Channel {
+        match self {
+            StreamEvent::BalanceUpdated(_) => Channel::Balances,
+            StreamEvent::TransactionUpdated(_) => Channel::Transactions,
+            StreamEvent::SystemMetrics(_) | StreamEvent::Maintenance(_) => Channel::System,
+        }
+    }
+
+    pub fn vault_id(&self) -> Option<Uuid> {
+        match self {
+            StreamEvent::BalanceUpdated(update) => Some(update.vault_id),
+            StreamEvent::TransactionUpdated(record) => Some(record.vault_id),
+            StreamEvent::SystemMetrics(_) | StreamEvent::Maintenance(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequencedEvent {
+    pub seq: u64,
+    pub published_at: DateTime<Utc>,
+    pub event: StreamEvent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Subscribe {
+        id: Option<String>,
+        channels: Vec<Channel>,
+        /// User pubkey whose vault the per-vault channels follow
+        vault: Option<String>,
+        resume_token: Option<String>,
+    },
+    Unsubscribe {
+        id: Option<String>,
+        channels: Vec<Channel>,
+    },
+    Ack {
+        seq: u64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Hello {
+        protocol_version: u32,
+        channels: Vec<Channel>,
+        resume_token: String,
+    },
+    Subscribed {
+        id: Option<String>,
+        channels: Vec<Channel>,
+        /// Buffered events after the resume token will follow
+        resumed: bool,
+    },
+    Event {
+        seq: u64,
+        channel: Channel,
+        resume_token: String,
+        published_at: DateTime<Utc>,
+        event: StreamEvent,
+    },
+    /// Events were missed; refetch state before trusting the stream again
+    Reset {
+        reason: String,
+        resume_token: String,
+    },
+    Error {
+        id: Option<String>,
+        message: String,
+    },
+    /// Single-withdrawal feed; not sequenced
+    WithdrawalStatus(WithdrawalQueueStatus),
+}
+
+/// Position in the stream of one process: `<epoch>:<seq>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResumeToken {
+    pub epoch: Uuid,
+    pub seq: u64,
+}
+
+impl ResumeToken {
+    pub fn encode(&self) -> String {
+        format!("{}:{}", self.epoch.simple(), self.seq)
+    }
+
+    pub fn parse(token: &str) -> Option<Self> {
+        let (epoch, seq) = token.split_once(':')?;
+        Some(Self {
+            epoch: Uuid::parse_str(epoch).ok()?,
+            seq: seq.parse().ok()?,
+        })
+    }
+}
+
+/// What one connection receives
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Subscription {
+    pub channels: HashSet<Channel>,
+    pub vault_id: Option<Uuid>,
+}
+
+impl Subscription {
+    pub fn matches(&self, event: &StreamEvent) -> bool {
+        let channel = event.channel();
+        if !self.channels.contains(&channel) {
+            return false;
+        }
+        !channel.is_per_vault() || (self.vault_id.is_some() && event.vault_id() == self.vault_id)
+    }
+
+    pub fn sorted_channels(&self) -> Vec<Channel> {
+        let mut channels: Vec<Channel> = self.channels.iter().copied().collect();
+        channels.sort();
+        channels
+    }
+}
+
+/// The requested position is no longer buffered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamGap {
+    pub oldest_buffered: u64,
+}
+
+/// Matching events after `cursor`, at most `limit`, and the cursor to continue from.
+///
+/// `buffer` holds contiguous sequence numbers, oldest first. The returned cursor
+/// also moves past events the subscription skips.
+pub fn events_after(
+    buffer: &VecDeque<SequencedEvent>,
+    cursor: u64,
+    subscription: &Subscription,
+    limit: usize,
+) -> Result<(Vec<SequencedEvent>, u64), StreamGap> {
+    filtered_events_after(buffer, cursor, limit, |event| subscription.matches(event))
+}
+
+fn filtered_events_after(
+    buffer: &VecDeque<SequencedEvent>,
+    cursor: u64,
+    limit: usize,
+    matches: impl Fn(&StreamEvent) -> bool,
+) -> Result<(Vec<SequencedEvent>, u64), StreamGap> {
+    let Some(oldest) = buffer.front().map(|e| e.seq) else {
+        return Ok((Vec::new(), cursor));
+    };
+    if cursor + 1 < oldest {
+        return Err(StreamGap { oldest_buffered: oldest });
+    }
+
+    let mut events = Vec::new();
+    let mut next_cursor = cursor;
+    let start = (cursor + 1 - oldest) as usize;
+
+    for event in buffer.iter().skip(start) {
+        if events.len() >= limit {
+            break;
+        }
+        if matches(&event.event) {
+            events.push(event.clone());
+        }
+        next_cursor = event.seq;
+    }
+
+    Ok((events, next_cursor))
+}
+
+/// In-process publisher for the WebSocket API
+pub struct EventStream {
+    epoch: Uuid,
+    buffer: RwLock<VecDeque<SequencedEvent>>,
+    head: watch::Sender<u64>,
+}
+
+impl EventStream {
+    pub fn new() -> Self {
+        Self {
+            epoch: Uuid::new_v4(),
+            buffer: RwLock::new(VecDeque::with_capacity(EVENT_BUFFER_CAPACITY)),
+            head: watch::channel(0).0,
+        }
+    }
+
+    /// Sequence number of the latest event, 0 before the first
+    pub fn head(&self) -> u64 {
+        *self.head.borrow()
+    }
+
+    /// Notified whenever a new event is published
+    pub fn watch_head(&self) -> watch::Receiver<u64> {
+        self.head.subscribe()
+    }
+
+    pub fn token_at(&self, seq: u64) -> String {
+        ResumeToken { epoch: self.epoch, seq }.encode()
+    }
+
+    /// Cursor to start from for a resume token; `Err` explains why it can't be honored
+    pub fn resume_cursor(&self, token: &str) -> Result<u64, String> {
+        let token = ResumeToken::parse(token).ok_or_else(|| "Malformed resume token".to_string())?;
+        if token.epoch != self.epoch {
+            return Err("Resume token was issued before a server restart".to_string());
+        }
+        if token.seq > self.head() {
+            return Err("Resume token is ahead of the stream".to_string());
+        }
+        Ok(token.seq)
+    }
+
+    pub async fn publish(&self, event: StreamEvent) -> u64 {
+        let mut buffer = self.buffer.write().await;
+        let seq = self.head() + 1;
+        buffer.push_back(SequencedEvent { seq, published_at: Utc::now(), event });
+        while buffer.len() > EVENT_BUFFER_CAPACITY {
+            buffer.pop_front();
+        }
+        self.head.send_replace(seq);
+        seq
+    }
+
+    pub async fn events_after(
+        &self,
+        cursor: u64,
+        subscription: &Subscription,
+        limit: usize,
+    ) -> Result<(Vec<SequencedEvent>, u64), StreamGap> {
+        events_after(&*self.buffer.read().await, cursor, subscription, limit)
+    }
+
+    /// Events on `channels` of every vault after `cursor`, for in-process consumers
+    pub async fn channel_events_after(
+        &self,
+        cursor: u64,
+        channels: &[Channel],
+        limit: usize,
+    ) -> Result<(Vec<SequencedEvent>, u64), StreamGap> {
+        filtered_events_after(&*self.buffer.read().await, cursor, limit, |event| channels.contains(&event.channel()))
+    }
+
+    /// Publish every balance change from a `VaultManager`
+    pub async fn run_balance_listener(&self, mut updates: broadcast::Receiver<BalanceUpdate>) {
+        loop {
+            match updates.recv().await {
+                Ok(update) => {
+                    self.publish(StreamEvent::BalanceUpdated(update)).await;
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Event stream missed {} balance updates", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+
+    /// Publish maintenance announcements, starts, ends and cancellations
+    pub async fn run_maintenance_listener(&self, mut notices: broadcast::Receiver<MaintenanceNotice>) {
+        loop {
+            match notices.recv().await {
+                Ok(notice) => {
+                    self.publish(StreamEvent::Maintenance(notice)).await;
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Event stream missed {} maintenance notices", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+
+    /// Publish every transaction record change from a `TransactionManager`
+    pub async fn run_transaction_listener(&self, mut updates: broadcast::Receiver<TransactionRecord>) {
+        loop {
+            match updates.recv().await {
+                Ok(record) => {
+                    self.publish(StreamEvent::TransactionUpdated(record)).await;
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Event stream missed {} transaction updates", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+
+    /// Publish monitor stats on the `system` channel
+    pub async fn run_system_publisher(&self, monitor: Arc<VaultMonitor>) {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(SYSTEM_METRICS_INTERVAL_SECONDS));
+        loop {
+            interval.tick().await;
+            match monitor.get_stats().await {
+                Ok(stats) => {
+                    self.publish(StreamEvent::SystemMetrics(SystemMetrics {
+                        vault_count: stats.vault_count,
+                        pending_transactions: stats.pending_transactions,
+                        failed_transactions_24h: stats.failed_transactions_24h,
+                        total_value_locked: stats.total_value_locked,
+                        is_healthy: stats.is_healthy,
+                    })).await;
+                }
+                Err(e) => error!("Failed to get stats for event stream: {}", e),
+            }
+        }
+    }
+}
+
+impl Default for EventStream {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,266 @@
+use crate::error::{Result, DomainError};
+use crate::models::{Vault, TransactionRecord, AuditLog, SupportCredential, VaultCase, VaultNote, VaultTag};
+use crate::database::{VaultRepository, TransactionRepository, AuditRepository, SupportRepository, CaseRepository};
+use crate::security_events::SecurityEventKind;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use tracing::info;
+
+/// Every support token starts with this, so read-only enforcement needs no lookup
+pub const SUPPORT_TOKEN_PREFIX: &str = "sup_";
+
+/// Prefix of operations tokens, which may also write notes, tags and cases
+pub const OPERATIONS_TOKEN_PREFIX: &str = "ops_";
+
+/// Audit event type written for each support read
+pub const SUPPORT_ACCESS_EVENT: &str = "support_access";
+
+/// Why a support engineer is looking at a vault; required on every request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SupportReason {
+    CustomerTicket,
+    IncidentInvestigation,
+    ComplianceReview,
+    Reconciliation,
+}
+
+impl SupportReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SupportReason::CustomerTicket => "customer_ticket",
+            SupportReason::IncidentInvestigation => "incident_investigation",
+            SupportReason::ComplianceReview => "compliance_review",
+            SupportReason::Reconciliation => "reconciliation",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "customer_ticket" => Ok(SupportReason::CustomerTicket),
+            "incident_investigation" => Ok(SupportReason::IncidentInvestigation),
+            "compliance_review" => Ok(SupportReason::ComplianceReview),
+            "reconciliation" => Ok(SupportReason::Reconciliation),
+            other => Err(DomainError::Validation(format!("Unknown support reason code: {}", other)).into()),
+        }
+    }
+}
+
+/// What a staff credential may do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StaffRole {
+    /// Read-only vault views
+    Support,
+    /// Support views plus notes, tags and cases
+    Operations,
+}
+
+impl StaffRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StaffRole::Support => "support",
+            StaffRole::Operations => "operations",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "support" => Ok(StaffRole::Support),
+            "operations" => Ok(StaffRole::Operations),
+            other => Err(DomainError::Validation(format!("Unknown staff role: {}", other)).into()),
+        }
+    }
+
+    pub fn token_prefix(&self) -> &'static str {
+        match self {
+            StaffRole::Support => SUPPORT_TOKEN_PREFIX,
+            StaffRole::Operations => OPERATIONS_TOKEN_PREFIX,
+        }
+    }
+}
+
+/// An authenticated support request
+#[derive(Debug, Clone)]
+pub struct SupportAccess {
+    pub credential_id: Uuid,
+    pub credential_name: String,
+    pub role: StaffRole,
+    pub reason: SupportReason,
+    /// Ticket or incident reference, when the reason has one
+    pub reference: Option<String>,
+}
+
+/// Everything support may see about one vault
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupportVaultDetail {
+    pub vault: Vault,
+    pub recent_transactions: Vec<TransactionRecord>,
+    /// Audit events for the vault, newest first, including ops case activity
+    pub timeline: Vec<AuditLog>,
+    pub tags: Vec<VaultTag>,
+    /// Open cases first
+    pub cases: Vec<VaultCase>,
+    pub notes: Vec<VaultNote>,
+}
+
+/// True when a bearer token is a (read-only) support credential
+pub fn is_support_token(token: &str) -> bool {
+    token.starts_with(SUPPORT_TOKEN_PREFIX)
+}
+
+/// True when a bearer token is any staff credential
+pub fn is_staff_token(token: &str) -> bool {
+    is_support_token(token) || token.starts_with(OPERATIONS_TOKEN_PREFIX)
+}
+
+/// Hex SHA-256 of a token, as stored in `support_credentials`
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// New random token for `role`; shown once, never stored
+pub fn generate_token(role: StaffRole) -> String {
+    format!("{}{}{}", role.token_prefix(), Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// Read-only vault access for support staff; every read is written to the audit trail
+pub struct SupportService {
+    support_repo: SupportRepository,
+    vault_repo: VaultRepository,
+    transaction_repo: TransactionRepository,
+    audit_repo: AuditRepository,
+    case_repo: CaseRepository,
+}
+
+impl SupportService {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self {
+            support_repo: SupportRepository::new(pool.clone()),
+            vault_repo: VaultRepository::new(pool.clone()),
+            transaction_repo: TransactionRepository::new(pool.clone()),
+            audit_repo: AuditRepository::new(pool.clone()),
+            case_repo: CaseRepository::new(pool),
+        }
+    }
+
+    /// Issue a credential; returns it together with the plaintext token
+    pub async fn issue_credential(&self, name: &str, role: StaffRole) -> Result<(SupportCredential, String)> {
+        if name.trim().is_empty() {
+            return Err(DomainError::Validation("Support credential name is required".to_string()).into());
+        }
+
+        let token = generate_token(role);
+        let credential = self.support_repo.create_credential(name.trim(), role.as_str(), &hash_token(&token)).await?;
+
+        self.audit_repo.log_event(
+            "support_credential_issued",
+            None,
+            None,
+            Some(serde_json::json!({ "credential_id": credential.id, "name": credential.name, "role": credential.role })),
+            None,
+        ).await?;
+
+        info!("Issued {} credential {} ({})", credential.role, credential.id, credential.name);
+        Ok((credential, token))
+    }
+
+    pub async fn revoke_credential(&self, credential_id: Uuid) -> Result<SupportCredential> {
+        let credential = self.support_repo.revoke_credential(credential_id).await?;
+
+        self.audit_repo.log_event(
+            "support_credential_revoked",
+            None,
+            None,
+            Some(serde_json::json!({ "credential_id": credential.id, "name": credential.name })),
+            None,
+        ).await?;
+
+        info!("Revoked support credential {} ({})", credential.id, credential.name);
+        Ok(credential)
+    }
+
+    pub async fn list_credentials(&self) -> Result<Vec<SupportCredential>> {
+        self.support_repo.list_credentials().await
+    }
+
+    /// Resolve a bearer token and reason code into an access grant
+    pub async fn authenticate(&self, token: &str, reason: &str, reference: Option<String>) -> Result<SupportAccess> {
+        let reason = SupportReason::parse(reason)?;
+        let credential = self.active_credential(token).await?;
+
+        Ok(SupportAccess {
+            role: StaffRole::parse(&credential.role)?,
+            credential_id: credential.id,
+            credential_name: credential.name,
+            reason,
+            reference,
+        })
+    }
+
+    /// Resolve a bearer token into an operations credential, refusing support-only ones
+    pub async fn authorize_operations(&self, token: &str) -> Result<SupportCredential> {
+        let credential = self.active_credential(token).await?;
+        if StaffRole::parse(&credential.role)? != StaffRole::Operations {
+            return Err(DomainError::Unauthorized("Operations credential required".to_string()).into());
+        }
+        Ok(credential)
+    }
+
+    /// Which security event a token refused by `authenticate` or
+    /// `authorize_operations` amounts to; a missing token counts as invalid
+    pub async fn rejection_kind(&self, token: Option<&str>) -> Result<SecurityEventKind> {
+        let Some(token) = token.filter(|token| is_staff_token(token)) else {
+            return Ok(SecurityEventKind::CredentialInvalid);
+        };
+
+        Ok(match self.support_repo.get_credential_by_hash(&hash_token(token)).await? {
+            None => SecurityEventKind::CredentialInvalid,
+            Some(credential) if credential.revoked_at.is_some() => SecurityEventKind::CredentialRevoked,
+            // A live credential is only refused for its role
+            Some(_) => SecurityEventKind::ScopeViolation,
+        })
+    }
+
+    async fn active_credential(&self, token: &str) -> Result<SupportCredential> {
+        if !is_staff_token(token) {
+            return Err(DomainError::Unauthorized("Not a support credential".to_string()).into());
+        }
+
+        self.support_repo.get_active_credential(&hash_token(token)).await?
+            .ok_or_else(|| DomainError::Unauthorized("Unknown or revoked support credential".to_string()).into())
+    }
+
+    /// Full vault detail. The access is logged before any data is read; if the
+    /// audit write fails, nothing is returned.
+    pub async fn vault_detail(&self, access: &SupportAccess, user_pubkey: &str, limit: i32) -> Result<SupportVaultDetail> {
+        let vault = self.vault_repo.get_vault_by_user(user_pubkey).await;
+        self.log_access(access, user_pubkey, vault.as_ref().ok().map(|v| v.id)).await?;
+        let vault = vault?;
+
+        let recent_transactions = self.transaction_repo.get_vault_transactions(vault.id, limit).await?;
+        let timeline = self.audit_repo.get_vault_events(vault.id, limit).await?;
+        let tags = self.case_repo.get_vault_tags(vault.id).await?;
+        let cases = self.case_repo.get_vault_cases(vault.id, limit).await?;
+        let notes = self.case_repo.get_vault_notes(vault.id, limit).await?;
+
+        Ok(SupportVaultDetail { vault, recent_transactions, timeline, tags, cases, notes })
+    }
+
+    async fn log_access(&self, access: &SupportAccess, user_pubkey: &str, vault_id: Option<Uuid>) -> Result<()> {
+        self.audit_repo.log_event(
+            SUPPORT_ACCESS_EVENT,
+            Some(user_pubkey),
+            vault_id,
+            Some(serde_json::json!({
+                "credential_id": access.credential_id,
+                "credential_name": access.credential_name,
+                "role": access.role.as_str(),
+                "reason": access.reason.as_str(),
+                "reference": access.reference,
+            })),
+            None,
+        ).await
+    }
+}
@@ -0,0 +1,244 @@
+//! Per-user tax reporting over a vault's confirmed ledger.
+//!
+//! Events are classified into deposits, withdrawals, withdrawal fees and PnL
+//! transfers, and a running average cost basis is carried from the vault's
+//! first record so a year's figures don't depend on where the year starts.
+//! Collateral is valued at face value in the mint's units (the supported
+//! mints are USD stablecoins); amounts stay in base units until rendered.
+//!
+//! Only disposals for no consideration reach Form 8949: withdrawal fees and
+//! outgoing PnL transfers. Withdrawals move the user's own funds and incoming
+//! PnL transfers are income, so both only adjust the basis.
+
+use crate::models::{MintConfig, TransactionRecord, TransactionType, Vault};
+use chrono::{DateTime, Datelike, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Date acquired for disposals out of an average-cost pool
+const VARIOUS: &str = "VARIOUS";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaxCategory {
+    Deposit,
+    Withdrawal,
+    /// Withdrawal fee retained by the protocol
+    Fee,
+    /// Settlement between vaults; negative when paid out
+    PnlTransfer,
+}
+
+/// One classified ledger event with the pool state after it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaxEvent {
+    pub transaction_id: Uuid,
+    pub date: DateTime<Utc>,
+    pub category: TaxCategory,
+    /// Signed change to holdings, in base units
+    pub amount: i64,
+    pub holdings_after: i64,
+    pub cost_basis_after: i64,
+    /// Basis released by a disposal
+    pub basis_disposed: i64,
+}
+
+/// A Form 8949 line; amounts in base units
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Form8949Row {
+    pub transaction_id: Uuid,
+    pub description: String,
+    pub date_acquired: String,
+    pub date_sold: String,
+    pub proceeds: i64,
+    pub cost_basis: i64,
+    pub adjustment_code: Option<String>,
+    pub adjustment_amount: i64,
+    pub gain_or_loss: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaxReport {
+    pub user_pubkey: String,
+    pub vault_id: Uuid,
+    pub tax_year: i32,
+    pub mint: String,
+    pub decimals: i16,
+    /// Events dated in the tax year
+    pub events: Vec<TaxEvent>,
+    pub form_8949: Vec<Form8949Row>,
+    pub total_proceeds: i64,
+    pub total_cost_basis: i64,
+    pub total_gain_or_loss: i64,
+    /// Incoming PnL transfers, reportable as income rather than on Form 8949
+    pub pnl_income: i64,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Average-cost pool of the vault's holdings
+#[derive(Debug, Clone, Copy, Default)]
+struct CostBasisPool {
+    holdings: i128,
+    basis: i128,
+}
+
+impl CostBasisPool {
+    fn acquire(&mut self, amount: i64) {
+        self.holdings += amount as i128;
+        self.basis += amount as i128;
+    }
+
+    /// Remove `amount` and return the basis it carried
+    fn dispose(&mut self, amount: i64) -> i64 {
+        let amount = (amount as i128).min(self.holdings.max(0));
+        let released = if amount == self.holdings {
+            self.basis
+        } else if self.holdings > 0 {
+            self.basis * amount / self.holdings
+        } else {
+            0
+        };
+        self.holdings -= amount;
+        self.basis -= released;
+        released as i64
+    }
+}
+
+/// Classify confirmed records (oldest first) into tax events.
+///
+/// Withdrawal records carry the gross amount; `withdrawal_fee` of it is split
+/// out as a fee event. Locks and unlocks move nothing in or out of the vault
+/// and are skipped.
+pub fn classify_ledger(records: &[TransactionRecord], withdrawal_fee: i64) -> Vec<TaxEvent> {
+    let mut pool = CostBasisPool::default();
+    let mut events = Vec::new();
+
+    for record in records {
+        let mut push = |category, amount, basis_disposed, pool: &CostBasisPool| {
+            events.push(TaxEvent {
+                transaction_id: record.id,
+                date: record.updated_at,
+                category,
+                amount,
+                holdings_after: pool.holdings as i64,
+                cost_basis_after: pool.basis as i64,
+                basis_disposed,
+            });
+        };
+
+        match record.transaction_type {
+            TransactionType::Deposit => {
+                pool.acquire(record.amount);
+                push(TaxCategory::Deposit, record.amount, 0, &pool);
+            }
+            TransactionType::Withdraw => {
+                let fee = withdrawal_fee.clamp(0, record.amount.max(0));
+                let net = record.amount - fee;
+                let released = pool.dispose(net);
+                push(TaxCategory::Withdrawal, -net, released, &pool);
+                if fee > 0 {
+                    let released = pool.dispose(fee);
+                    push(TaxCategory::Fee, -fee, released, &pool);
+                }
+            }
+            TransactionType::Transfer if record.amount < 0 => {
+                let released = pool.dispose(-record.amount);
+                push(TaxCategory::PnlTransfer, record.amount, released, &pool);
+            }
+            TransactionType::Transfer => {
+                pool.acquire(record.amount);
+                push(TaxCategory::PnlTransfer, record.amount, 0, &pool);
+            }
+            TransactionType::Lock | TransactionType::Unlock | TransactionType::Initialize => {}
+        }
+    }
+
+    events
+}
+
+/// Form 8949 lines for the taxable disposals among `events`
+pub fn form_8949_rows(events: &[TaxEvent], mint: &MintConfig) -> Vec<Form8949Row> {
+    events
+        .iter()
+        .filter(|e| e.amount < 0 && matches!(e.category, TaxCategory::Fee | TaxCategory::PnlTransfer))
+        .map(|e| {
+            let what = match e.category {
+                TaxCategory::Fee => "withdrawal fee",
+                _ => "PnL settlement paid",
+            };
+            Form8949Row {
+                transaction_id: e.transaction_id,
+                description: format!("{} ({})", mint.format_amount(-e.amount), what),
+                date_acquired: VARIOUS.to_string(),
+                date_sold: e.date.format("%m/%d/%Y").to_string(),
+                proceeds: 0,
+                cost_basis: e.basis_disposed,
+                adjustment_code: None,
+                adjustment_amount: 0,
+                gain_or_loss: -e.basis_disposed,
+            }
+        })
+        .collect()
+}
+
+/// Build the report for `tax_year` from the vault's full confirmed ledger
+pub fn build_tax_report(vault: &Vault, records: &[TransactionRecord], mint: &MintConfig, tax_year: i32) -> TaxReport {
+    let events: Vec<TaxEvent> = classify_ledger(records, mint.withdrawal_fee)
+        .into_iter()
+        .filter(|e| e.date.year() == tax_year)
+        .collect();
+    let form_8949 = form_8949_rows(&events, mint);
+
+    TaxReport {
+        user_pubkey: vault.user_pubkey.clone(),
+        vault_id: vault.id,
+        tax_year,
+        mint: mint.symbol.clone(),
+        decimals: mint.decimals,
+        total_proceeds: form_8949.iter().map(|r| r.proceeds).sum(),
+        total_cost_basis: form_8949.iter().map(|r| r.cost_basis).sum(),
+        total_gain_or_loss: form_8949.iter().map(|r| r.gain_or_loss).sum(),
+        pnl_income: events
+            .iter()
+            .filter(|e| e.category == TaxCategory::PnlTransfer && e.amount > 0)
+            .map(|e| e.amount)
+            .sum(),
+        events,
+        form_8949,
+        generated_at: Utc::now(),
+    }
+}
+
+/// Quote a CSV field when it contains a delimiter, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Form 8949 columns (a) through (h), amounts in the mint's units
+pub fn form_8949_csv(report: &TaxReport, mint: &MintConfig) -> String {
+    let mut csv = String::from(
+        "Description of property,Date acquired,Date sold or disposed of,Proceeds,Cost or other basis,\
+         Adjustment code,Amount of adjustment,Gain or (loss)\n",
+    );
+
+    for row in &report.form_8949 {
+        let fields = [
+            csv_field(&row.description),
+            csv_field(&row.date_acquired),
+            csv_field(&row.date_sold),
+            mint.format_units(row.proceeds),
+            mint.format_units(row.cost_basis),
+            csv_field(row.adjustment_code.as_deref().unwrap_or("")),
+            mint.format_units(row.adjustment_amount),
+            mint.format_units(row.gain_or_loss),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+
+    csv
+}
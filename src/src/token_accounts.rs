@@ -0,0 +1,172 @@
+//! Prepare-time checks of the user token accounts a transaction moves tokens through.
+//!
+//! A deposit from a missing, empty or wrong-mint account, or a withdrawal to
+//! a destination that does not exist, fails on-chain with a generic token
+//! program error after the user has already signed and paid for it. Prepare
+//! flows check the account first and answer with what is wrong and how to fix
+//! it. A missing withdrawal destination that is the owner's associated token
+//! account (ATA) is instead created in the prepared transaction itself, with
+//! the idempotent ATA instruction paid by the signer.
+//!
+//! Vaults may hold mints of the legacy token program or of Token-2022; a user
+//! account must belong to the same token program as the mint, which is also
+//! part of its ATA address.
+
+use crate::error::{DomainError, Result};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
+
+/// `CreateIdempotent` instruction of the associated token account program
+const CREATE_IDEMPOTENT: u8 = 1;
+
+pub fn associated_token_program_id() -> Pubkey {
+    Pubkey::from_str(ASSOCIATED_TOKEN_PROGRAM_ID).expect("valid associated token program id")
+}
+
+/// Whether `program` is the legacy token program or Token-2022
+pub fn is_token_program(program: &Pubkey) -> bool {
+    *program == anchor_spl::token::ID || *program == anchor_spl::token_2022::ID
+}
+
+/// Associated token account of `wallet` for `mint`, seeds `[wallet, token_program, mint]`
+pub fn associated_token_address(wallet: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &associated_token_program_id(),
+    ).0
+}
+
+/// Create `wallet`'s associated token account for `mint` unless it already exists, paid by `payer`
+pub fn create_associated_token_account_idempotent_ix(
+    payer: &Pubkey,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: associated_token_program_id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(associated_token_address(wallet, mint, token_program), false),
+            AccountMeta::new_readonly(*wallet, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+        data: vec![CREATE_IDEMPOTENT],
+    }
+}
+
+/// The token account holding a vault's collateral, with its mint and the
+/// token program both belong to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CollateralToken {
+    pub token_account: Pubkey,
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+}
+
+/// The fields of a token account the checks look at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenAccountInfo {
+    /// Program owning the account
+    pub token_program: Pubkey,
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub frozen: bool,
+}
+
+/// How the prepared transaction uses a user token account
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenAccountRole {
+    /// Tokens are moved out of it by the vault owner
+    DepositSource { amount: u64 },
+    /// Withdrawn tokens are moved into it
+    WithdrawalDestination,
+}
+
+/// What the prepared transaction has to do before it can use the account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenAccountPlan {
+    Ready,
+    /// The account is `wallet`'s missing ATA for `mint`, created in the same transaction
+    CreateAssociated { wallet: Pubkey, mint: Pubkey, token_program: Pubkey },
+}
+
+impl TokenAccountPlan {
+    /// Instructions to put ahead of the ones using the account
+    pub fn setup_instructions(&self, payer: &Pubkey) -> Vec<Instruction> {
+        match self {
+            TokenAccountPlan::Ready => Vec::new(),
+            TokenAccountPlan::CreateAssociated { wallet, mint, token_program } => {
+                vec![create_associated_token_account_idempotent_ix(payer, wallet, mint, token_program)]
+            }
+        }
+    }
+}
+
+/// Check the token account at `address` (`None` if it does not exist) before
+/// `owner` moves `mint` tokens, of `token_program`, through it in `role`
+pub fn check_user_token_account(
+    address: &Pubkey,
+    account: Option<&TokenAccountInfo>,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    owner: &Pubkey,
+    role: TokenAccountRole,
+) -> Result<TokenAccountPlan> {
+    let associated = associated_token_address(owner, mint, token_program);
+
+    let Some(account) = account else {
+        return match role {
+            TokenAccountRole::WithdrawalDestination if *address == associated => {
+                Ok(TokenAccountPlan::CreateAssociated { wallet: *owner, mint: *mint, token_program: *token_program })
+            }
+            TokenAccountRole::WithdrawalDestination => Err(DomainError::Validation(format!(
+                "Destination token account {} does not exist; create it first, or withdraw to the associated token account {}, which is created with the withdrawal",
+                address, associated
+            )).into()),
+            TokenAccountRole::DepositSource { .. } => Err(DomainError::Validation(format!(
+                "Token account {} does not exist; fund the associated token account {} of {} for mint {} first",
+                address, associated, owner, mint
+            )).into()),
+        };
+    };
+
+    if account.token_program != *token_program {
+        return Err(DomainError::Validation(format!(
+            "Token account {} belongs to token program {}, but mint {} is of {}",
+            address, account.token_program, mint, token_program
+        )).into());
+    }
+    if account.mint != *mint {
+        return Err(DomainError::Validation(format!(
+            "Token account {} holds mint {}, not the vault's mint {}", address, account.mint, mint
+        )).into());
+    }
+    if account.frozen {
+        return Err(DomainError::Validation(format!(
+            "Token account {} is frozen by the freeze authority of mint {}", address, mint
+        )).into());
+    }
+
+    if let TokenAccountRole::DepositSource { amount } = role {
+        if account.owner != *owner {
+            return Err(DomainError::Validation(format!(
+                "Token account {} is owned by {}; deposits must come from an account owned by {}",
+                address, account.owner, owner
+            )).into());
+        }
+        if account.amount < amount {
+            return Err(DomainError::Validation(format!(
+                "Token account {} holds {} base units; the deposit needs {}", address, account.amount, amount
+            )).into());
+        }
+    }
+
+    Ok(TokenAccountPlan::Ready)
+}
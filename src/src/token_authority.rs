@@ -0,0 +1,208 @@
+//! Sweep for delegates and close authorities on vault token accounts.
+//!
+//! The program never approves a delegate or sets a close authority on the
+//! token accounts it owns, and deposits are refused on-chain while either is
+//! set. Finding one means collateral can leave (or the account can be closed)
+//! outside the vault's accounting, so every detection is logged as critical
+//! and recorded as a finding until a later sweep sees the account clean.
+//!
+//! A delegate can be revoked by the vault PDA through `revoke_token_delegate`;
+//! the backend builds that transaction unsigned, for the config admin to
+//! approve and sign. A close authority can only be cleared by itself, so for
+//! those the finding is the alert and remediation is manual.
+
+use crate::error::{Result, DomainError, ChainError};
+use crate::models::{Vault, TokenAuthorityFinding};
+use crate::transaction_builder::{self, TransactionBuilder, SigningHints};
+use crate::rpc::RpcMethodClass;
+use crate::database::{VaultRepository, TokenAuthorityRepository};
+use anchor_spl::token_2022::spl_token_2022;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{info, error};
+use uuid::Uuid;
+
+pub const TOKEN_AUTHORITY_EXPOSED_EVENT: &str = "token_authority_exposed";
+pub const TOKEN_AUTHORITY_CLEARED_EVENT: &str = "token_authority_cleared";
+
+/// Vaults loaded per page while sweeping
+const SWEEP_PAGE_SIZE: i32 = 500;
+
+/// Authorities set on a vault token account besides the vault PDA
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenAuthorityExposure {
+    pub delegate: Option<Pubkey>,
+    pub delegated_amount: u64,
+    pub close_authority: Option<Pubkey>,
+}
+
+impl TokenAuthorityExposure {
+    /// `None` when neither a delegate nor a close authority is set
+    pub fn detect(delegate: Option<Pubkey>, delegated_amount: u64, close_authority: Option<Pubkey>) -> Option<Self> {
+        if delegate.is_none() && close_authority.is_none() {
+            return None;
+        }
+        Some(Self {
+            delegate,
+            delegated_amount: if delegate.is_some() { delegated_amount } else { 0 },
+            close_authority,
+        })
+    }
+
+    pub fn from_account(account: &spl_token_2022::state::Account) -> Option<Self> {
+        Self::detect(account.delegate.into(), account.delegated_amount, account.close_authority.into())
+    }
+
+    /// Whether the vault PDA can undo it; only delegates can be revoked by the owner
+    pub fn revocable(&self) -> bool {
+        self.delegate.is_some()
+    }
+}
+
+/// Counts from one sweep over every active vault
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenAuthoritySweepSummary {
+    pub checked: usize,
+    pub exposed: usize,
+    pub resolved: usize,
+    pub errors: usize,
+}
+
+/// A revoke transaction awaiting the config admin's signature
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreparedTokenRevocation {
+    pub finding: TokenAuthorityFinding,
+    /// Fee payer and required signer
+    pub admin: String,
+    /// Base64 bincode of the unsigned transaction
+    pub unsigned_transaction: String,
+    pub hints: SigningHints,
+}
+
+/// Checks vault token accounts and prepares revocations for admin approval
+pub struct TokenAuthorityGuard {
+    vault_repo: VaultRepository,
+    finding_repo: TokenAuthorityRepository,
+    transaction_builder: Arc<TransactionBuilder>,
+}
+
+impl TokenAuthorityGuard {
+    pub fn new(pool: sqlx::PgPool, transaction_builder: Arc<TransactionBuilder>) -> Self {
+        Self {
+            vault_repo: VaultRepository::new(pool.clone()),
+            finding_repo: TokenAuthorityRepository::new(pool),
+            transaction_builder,
+        }
+    }
+
+    /// Check every active vault; failures are logged per vault
+    pub async fn sweep(&self) -> Result<TokenAuthoritySweepSummary> {
+        let mut summary = TokenAuthoritySweepSummary::default();
+        let mut offset = 0;
+        loop {
+            let vaults = self.vault_repo.get_active_vaults(SWEEP_PAGE_SIZE, offset).await?;
+            for vault in &vaults {
+                summary.checked += 1;
+                match self.check_vault(vault).await {
+                    Ok(VaultCheck::Exposed) => summary.exposed += 1,
+                    Ok(VaultCheck::Resolved) => summary.resolved += 1,
+                    Ok(VaultCheck::Clean) => {}
+                    Err(e) => {
+                        summary.errors += 1;
+                        error!("Token authority check failed for vault {}: {}", vault.id, e);
+                    }
+                }
+            }
+            if (vaults.len() as i32) < SWEEP_PAGE_SIZE {
+                break;
+            }
+            offset += SWEEP_PAGE_SIZE;
+        }
+        Ok(summary)
+    }
+
+    async fn check_vault(&self, vault: &Vault) -> Result<VaultCheck> {
+        let token_account = Pubkey::from_str(&vault.token_account_pubkey)
+            .map_err(|_| ChainError::InvalidAccountData(format!("Invalid token account: {}", vault.token_account_pubkey)))?;
+        let account = self.transaction_builder.fetch_token_account(token_account, RpcMethodClass::Read).await?;
+
+        let Some(exposure) = TokenAuthorityExposure::from_account(&account) else {
+            let details = serde_json::json!({ "token_account": vault.token_account_pubkey });
+            let resolved = self.finding_repo.resolve(vault.id, TOKEN_AUTHORITY_CLEARED_EVENT, details).await?;
+            return Ok(match resolved {
+                Some(finding) => {
+                    info!("Token account {} of vault {} is clean again (finding {})",
+                          vault.token_account_pubkey, vault.id, finding.id);
+                    VaultCheck::Resolved
+                }
+                None => VaultCheck::Clean,
+            });
+        };
+
+        let delegate = exposure.delegate.map(|k| k.to_string());
+        let close_authority = exposure.close_authority.map(|k| k.to_string());
+        let details = serde_json::json!({
+            "token_account": vault.token_account_pubkey,
+            "delegate": delegate,
+            "delegated_amount": exposure.delegated_amount,
+            "close_authority": close_authority,
+        });
+        let finding = self.finding_repo.record_exposure(
+            vault.id,
+            &vault.token_account_pubkey,
+            delegate.as_deref(),
+            exposure.delegated_amount as i64,
+            close_authority.as_deref(),
+            TOKEN_AUTHORITY_EXPOSED_EVENT,
+            details,
+        ).await?;
+
+        error!("CRITICAL: token account {} of vault {} has delegate {:?} (amount {}) and close authority {:?}; finding {}{}",
+               vault.token_account_pubkey, vault.id, delegate, exposure.delegated_amount, close_authority, finding.id,
+               if exposure.revocable() { ", revoke transaction awaiting admin approval" } else { ", needs manual remediation" });
+        Ok(VaultCheck::Exposed)
+    }
+
+    /// Findings not yet resolved
+    pub async fn open_findings(&self) -> Result<Vec<TokenAuthorityFinding>> {
+        self.finding_repo.get_open_findings().await
+    }
+
+    /// Build a fresh unsigned revoke transaction for an open finding with a delegate
+    pub async fn prepare_revocation(&self, finding_id: Uuid) -> Result<PreparedTokenRevocation> {
+        let finding = self.finding_repo.get_finding(finding_id).await?;
+        if finding.resolved_at.is_some() {
+            return Err(DomainError::Validation(format!("Finding {} is already resolved", finding_id)).into());
+        }
+        if finding.delegate.is_none() {
+            return Err(DomainError::Validation(
+                "Only the close authority can clear itself; move the collateral to a new token account".to_string()
+            ).into());
+        }
+
+        let vault = self.vault_repo.get_vault_by_id(finding.vault_id).await?;
+        let vault_pubkey = Pubkey::from_str(&vault.vault_pubkey)
+            .map_err(|_| ChainError::InvalidAccountData(format!("Invalid vault pubkey: {}", vault.vault_pubkey)))?;
+        let admin = self.transaction_builder.fetch_program_config(RpcMethodClass::Read).await?.admin;
+
+        let built = self.transaction_builder.build_revoke_token_delegate_tx(vault_pubkey, admin).await?;
+        let hints = self.transaction_builder.signing_hints(&built.transaction, built.last_valid_block_height).await?;
+
+        info!("Prepared delegate revocation for vault {} (finding {})", vault.id, finding.id);
+
+        Ok(PreparedTokenRevocation {
+            finding,
+            admin: admin.to_string(),
+            unsigned_transaction: transaction_builder::encode_transaction(&built.transaction)?,
+            hints,
+        })
+    }
+}
+
+enum VaultCheck {
+    Clean,
+    Exposed,
+    Resolved,
+}
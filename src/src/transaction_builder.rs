@@ -1,9 +1,20 @@
-use crate::error::{Result, VaultError};
-use solana_client::rpc_client::RpcClient;
+use crate::error::{Result, ChainError, DomainError, VaultError};
+use crate::models::{MintConfig, MultisigProposalStatus, SignatureEntry};
+use crate::multisig::{self, MultisigProposalTx};
+use crate::latency::{PipelineStage, StageTimings};
+use crate::derivation::{derive_vault_pda, derive_sub_account_vault_pda, derive_token_pda, derive_config_pda, derive_dormant_funds_pda, derive_dormant_token_pda, derive_treasury_token_pda, derive_asset_config_pda};
+use crate::rpc::{BudgetedRpcClient, RpcBudget, RpcMethodClass};
+use crate::cluster::{ClusterTiming, NOMINAL_SLOT_TIME_MS};
+use crate::token_accounts::{self, CollateralToken, TokenAccountInfo, TokenAccountPlan, TokenAccountRole};
+use crate::onchain_activity::{self, OnchainTransaction};
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_transaction_status::UiTransactionEncoding;
 use solana_sdk::{
     pubkey::Pubkey,
-    signature::{Keypair, Signer},
+    signature::{Keypair, Signature, Signer},
     transaction::Transaction,
+    hash::Hash,
     instruction::{Instruction, AccountMeta},
     commitment_config::CommitmentConfig,
     system_instruction,
@@ -15,6 +26,10 @@ use anchor_client::{
     Cluster,
     Program,
 };
+use anchor_lang::AccountDeserialize;
+use anchor_spl::token_2022::spl_token_2022::{self, extension::StateWithExtensions};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 use std::sync::Arc;
 use tokio::sync::Semaphore;
@@ -22,7 +37,7 @@ use uuid::Uuid;
 use tracing::{info, warn, error};
 
 pub struct TransactionBuilder {
-    rpc_client: Arc<RpcClient>,
+    rpc: BudgetedRpcClient,
     anchor_client: Arc<AnchorClient>,
     program: Program,
     rate_limiter: Arc<Semaphore>,
@@ -37,10 +52,10 @@ impl TransactionBuilder {
         program_id: Pubkey,
         max_concurrent_tx: usize,
     ) -> Result<Self> {
-        let rpc_client = Arc::new(RpcClient::new_with_commitment(
-            rpc_url.to_string(),
-            CommitmentConfig::confirmed(),
-        ));
+        let rpc = BudgetedRpcClient::new(
+            RpcClient::new_with_commitment(rpc_url.to_string(), CommitmentConfig::confirmed()),
+            Arc::new(RpcBudget::for_endpoint(rpc_url)),
+        );
         
         let anchor_client = Arc::new(AnchorClient::new_with_options(
             Cluster::Custom(rpc_url.to_string(), rpc_url.to_string()),
@@ -51,7 +66,7 @@ impl TransactionBuilder {
         let program = anchor_client.program(program_id)?;
         
         Ok(Self {
-            rpc_client,
+            rpc,
             anchor_client,
             program,
             rate_limiter: Arc::new(Semaphore::new(max_concurrent_tx)),
@@ -60,42 +75,58 @@ impl TransactionBuilder {
         })
     }
     
-    /// Build initialize vault transaction
+    /// Charge RPC calls against `budget`, shared with other clients of the same endpoint
+    pub fn with_rpc_budget(mut self, budget: Arc<RpcBudget>) -> Self {
+        self.rpc = self.rpc.with_budget(budget);
+        self
+    }
+    
+    /// Budgeted client for calls not wrapped by the builder
+    pub fn rpc(&self) -> &BudgetedRpcClient {
+        &self.rpc
+    }
+    
+    /// Build initialize vault transaction for one of the user's isolated
+    /// vaults; sub-account 0 is the user's original vault
     pub async fn build_initialize_vault_tx(
         &self,
         user_pubkey: Pubkey,
+        sub_account_id: u16,
         authority_pubkey: Pubkey,
-        usdt_mint: Pubkey,
+        mint: &MintConfig,
     ) -> Result<BuiltTransaction> {
+        if !mint.enabled {
+            return Err(DomainError::Validation(format!("Mint {} is disabled", mint.symbol)).into());
+        }
+        let mint_pubkey = mint.validate_pubkey().map_err(DomainError::Validation)?;
+        
         let _permit = self.rate_limiter.acquire().await.unwrap();
         
-        // Derive PDAs
-        let (vault_pda, vault_bump) = Pubkey::find_program_address(
-            &[b"vault", user_pubkey.as_ref()],
-            &self.program_id,
-        );
+        // The vault token account is created under the mint's token program
+        let token_program = self.fetch_token_program(mint_pubkey).await?;
         
-        let (token_pda, _) = Pubkey::find_program_address(
-            &[b"token", vault_pda.as_ref()],
-            &self.program_id,
-        );
+        // Derive PDAs
+        let (vault_pda, vault_bump) = derive_sub_account_vault_pda(&self.program_id, &user_pubkey, sub_account_id);
+        let (token_pda, _) = derive_token_pda(&self.program_id, &vault_pda);
         
         // Get recent blockhash
-        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let recent_blockhash = self.rpc.call(RpcMethodClass::Submit, |c| c.get_latest_blockhash()).await?;
         
         // Build instruction
         let accounts = collateral_vault::accounts::InitializeVault {
             vault: vault_pda,
             vault_token_account: token_pda,
             user: user_pubkey,
+            payer: user_pubkey,
             authority: authority_pubkey,
-            usdt_mint,
-            token_program: spl_token::id(),
+            config: self.get_config_pda(),
+            usdt_mint: mint_pubkey,
+            token_program,
             system_program: system_program::id(),
             rent: solana_sdk::sysvar::rent::id(),
         };
         
-        let data = collateral_vault::instruction::InitializeVault { bump: vault_bump };
+        let data = collateral_vault::instruction::InitializeVault { sub_account_id };
         
         let ix = Instruction {
             program_id: self.program_id,
@@ -133,10 +164,11 @@ impl TransactionBuilder {
         let _permit = self.rate_limiter.acquire().await.unwrap();
         
         // Get vault token account
-        let vault_token_account = self.get_vault_token_account(vault_pubkey).await?;
+        let collateral = self.fetch_collateral_token(vault_pubkey, RpcMethodClass::Read).await?;
+        let vault_token_account = collateral.token_account;
         
         // Get recent blockhash
-        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let recent_blockhash = self.rpc.call(RpcMethodClass::Submit, |c| c.get_latest_blockhash()).await?;
         
         // Build instruction
         let accounts = collateral_vault::accounts::Deposit {
@@ -144,7 +176,9 @@ impl TransactionBuilder {
             vault_token_account,
             user_token_account,
             user: user_pubkey,
-            token_program: spl_token::id(),
+            config: self.get_config_pda(),
+            mint: collateral.mint,
+            token_program: collateral.token_program,
         };
         
         let data = collateral_vault::instruction::Deposit { amount };
@@ -171,37 +205,47 @@ impl TransactionBuilder {
         })
     }
     
-    /// Build withdraw transaction
+    /// Build withdraw transaction; a `memo` travels with it through the SPL
+    /// Memo program, using `withdraw_with_memo`
     pub async fn build_withdraw_tx(
         &self,
         user_pubkey: Pubkey,
         vault_pubkey: Pubkey,
         amount: u64,
         user_token_account: Pubkey,
+        memo: Option<String>,
     ) -> Result<BuiltTransaction> {
         let _permit = self.rate_limiter.acquire().await.unwrap();
         
         // Get vault token account
-        let vault_token_account = self.get_vault_token_account(vault_pubkey).await?;
+        let collateral = self.fetch_collateral_token(vault_pubkey, RpcMethodClass::Read).await?;
+        let vault_token_account = collateral.token_account;
         
         // Get recent blockhash
-        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let recent_blockhash = self.rpc.call(RpcMethodClass::Submit, |c| c.get_latest_blockhash()).await?;
         
         // Build instruction
         let accounts = collateral_vault::accounts::Withdraw {
             vault: vault_pubkey,
             vault_token_account,
             user_token_account,
+            treasury_token_account: self.fetch_treasury(collateral.mint).await?,
             user: user_pubkey,
-            token_program: spl_token::id(),
+            config: self.get_config_pda(),
+            mint: collateral.mint,
+            token_program: collateral.token_program,
+            memo_program: memo.is_some().then_some(anchor_spl::memo::ID),
         };
         
-        let data = collateral_vault::instruction::Withdraw { amount };
+        let data = match memo {
+            Some(memo) => collateral_vault::instruction::WithdrawWithMemo { amount, memo }.data(),
+            None => collateral_vault::instruction::Withdraw { amount }.data(),
+        };
         
         let ix = Instruction {
             program_id: self.program_id,
             accounts: accounts.to_account_metas(None),
-            data: data.data(),
+            data,
         };
         
         let transaction = Transaction::new_signed_with_payer(
@@ -216,7 +260,7 @@ impl TransactionBuilder {
             vault_pubkey,
             token_account_pubkey: vault_token_account,
             bump: 0, // Not used for withdraw
-            estimated_compute_units: 120_000,
+            estimated_compute_units: WITHDRAW_COMPUTE_UNITS,
         })
     }
     
@@ -229,12 +273,18 @@ impl TransactionBuilder {
     ) -> Result<BuiltTransaction> {
         let _permit = self.rate_limiter.acquire().await.unwrap();
         
+        // The lock is checked against the haircut of the vault's mint
+        let collateral = self.fetch_collateral_token(vault_pubkey, RpcMethodClass::Read).await?;
+        
         // Get recent blockhash
-        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let recent_blockhash = self.rpc.call(RpcMethodClass::Submit, |c| c.get_latest_blockhash()).await?;
         
         // Build instruction
         let accounts = collateral_vault::accounts::LockCollateral {
             vault: vault_pubkey,
+            vault_token_account: collateral.token_account,
+            asset_config: derive_asset_config_pda(&self.program_id, &collateral.mint).0,
+            config: self.get_config_pda(),
             authority: authority_keypair.pubkey(),
         };
         
@@ -272,11 +322,12 @@ impl TransactionBuilder {
         let _permit = self.rate_limiter.acquire().await.unwrap();
         
         // Get recent blockhash
-        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let recent_blockhash = self.rpc.call(RpcMethodClass::Submit, |c| c.get_latest_blockhash()).await?;
         
         // Build instruction
         let accounts = collateral_vault::accounts::UnlockCollateral {
             vault: vault_pubkey,
+            config: self.get_config_pda(),
             authority: authority_keypair.pubkey(),
         };
         
@@ -304,22 +355,25 @@ impl TransactionBuilder {
         })
     }
     
-    /// Build transfer collateral transaction
+    /// Build transfer collateral transaction; a `memo` travels with it through
+    /// the SPL Memo program, using `transfer_collateral_with_memo`
     pub async fn build_transfer_collateral_tx(
         &self,
         source_vault_pubkey: Pubkey,
         destination_vault_pubkey: Pubkey,
         amount: u64,
         authority_keypair: &Keypair,
+        memo: Option<String>,
     ) -> Result<BuiltTransaction> {
         let _permit = self.rate_limiter.acquire().await.unwrap();
         
         // Get token accounts
-        let source_token_account = self.get_vault_token_account(source_vault_pubkey).await?;
+        let source = self.fetch_collateral_token(source_vault_pubkey, RpcMethodClass::Read).await?;
+        let source_token_account = source.token_account;
         let destination_token_account = self.get_vault_token_account(destination_vault_pubkey).await?;
         
         // Get recent blockhash
-        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let recent_blockhash = self.rpc.call(RpcMethodClass::Submit, |c| c.get_latest_blockhash()).await?;
         
         // Build instruction
         let accounts = collateral_vault::accounts::TransferCollateral {
@@ -328,15 +382,21 @@ impl TransactionBuilder {
             source_token_account,
             destination_token_account,
             authority: authority_keypair.pubkey(),
-            token_program: spl_token::id(),
+            config: self.get_config_pda(),
+            mint: source.mint,
+            token_program: source.token_program,
+            memo_program: memo.is_some().then_some(anchor_spl::memo::ID),
         };
         
-        let data = collateral_vault::instruction::TransferCollateral { amount };
+        let data = match memo {
+            Some(memo) => collateral_vault::instruction::TransferCollateralWithMemo { amount, memo }.data(),
+            None => collateral_vault::instruction::TransferCollateral { amount }.data(),
+        };
         
         let ix = Instruction {
             program_id: self.program_id,
             accounts: accounts.to_account_metas(None),
-            data: data.data(),
+            data,
         };
         
         let transaction = Transaction::new_signed_with_payer(
@@ -355,15 +415,629 @@ impl TransactionBuilder {
         })
     }
     
+    /// Build rotate authority transaction; both the current and the new key sign
+    pub async fn build_rotate_authority_tx(
+        &self,
+        vault_pubkey: Pubkey,
+        current_authority: &Keypair,
+        new_authority: &Keypair,
+    ) -> Result<BuiltTransaction> {
+        let _permit = self.rate_limiter.acquire().await.unwrap();
+        
+        // Get recent blockhash
+        let recent_blockhash = self.rpc.call(RpcMethodClass::Submit, |c| c.get_latest_blockhash()).await?;
+        
+        // Build instruction
+        let accounts = collateral_vault::accounts::RotateAuthority {
+            vault: vault_pubkey,
+            authority: current_authority.pubkey(),
+            new_authority: new_authority.pubkey(),
+        };
+        
+        let data = collateral_vault::instruction::RotateAuthority {};
+        
+        let ix = Instruction {
+            program_id: self.program_id,
+            accounts: accounts.to_account_metas(None),
+            data: data.data(),
+        };
+        
+        let transaction = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&self.payer.pubkey()),
+            &[&self.payer, current_authority, new_authority],
+            recent_blockhash,
+        );
+        
+        Ok(BuiltTransaction {
+            transaction,
+            vault_pubkey,
+            token_account_pubkey: self.get_vault_token_account(vault_pubkey).await?,
+            bump: 0, // Not used for rotation
+            estimated_compute_units: 30_000,
+        })
+    }
+    
+    /// Build an unsigned transaction, paid and signed by the config admin, that
+    /// revokes the SPL delegate on a vault's token account
+    pub async fn build_revoke_token_delegate_tx(&self, vault_pubkey: Pubkey, admin: Pubkey) -> Result<UnsignedTransaction> {
+        let _permit = self.rate_limiter.acquire().await.unwrap();
+        
+        let collateral = self.fetch_collateral_token(vault_pubkey, RpcMethodClass::Read).await?;
+        let accounts = collateral_vault::accounts::RevokeTokenDelegate {
+            config: self.get_config_pda(),
+            admin,
+            vault: vault_pubkey,
+            vault_token_account: collateral.token_account,
+            token_program: collateral.token_program,
+        };
+        
+        let data = collateral_vault::instruction::RevokeTokenDelegate {};
+        
+        let ix = Instruction {
+            program_id: self.program_id,
+            accounts: accounts.to_account_metas(None),
+            data: data.data(),
+        };
+        
+        let (recent_blockhash, last_valid_block_height) = self.latest_blockhash().await?;
+        
+        let mut transaction = Transaction::new_with_payer(&[ix], Some(&admin));
+        transaction.message.recent_blockhash = recent_blockhash;
+        
+        Ok(UnsignedTransaction { transaction, last_valid_block_height })
+    }
+    
+    /// Build an unsigned transaction, paid and signed by the config admin, that
+    /// sweeps a dormant vault's funds into its dormant-funds account
+    pub async fn build_sweep_dormant_vault_tx(&self, vault_pubkey: Pubkey, admin: Pubkey) -> Result<UnsignedTransaction> {
+        let _permit = self.rate_limiter.acquire().await.unwrap();
+        
+        let collateral = self.fetch_collateral_token(vault_pubkey, RpcMethodClass::Read).await?;
+        let (dormant_funds, _) = derive_dormant_funds_pda(&self.program_id, &vault_pubkey);
+        
+        let accounts = collateral_vault::accounts::SweepDormantVault {
+            config: self.get_config_pda(),
+            admin,
+            vault: vault_pubkey,
+            vault_token_account: collateral.token_account,
+            dormant_funds,
+            dormant_token_account: derive_dormant_token_pda(&self.program_id, &dormant_funds).0,
+            mint: collateral.mint,
+            token_program: collateral.token_program,
+            system_program: system_program::id(),
+            rent: solana_sdk::sysvar::rent::id(),
+        };
+        
+        let data = collateral_vault::instruction::SweepDormantVault {};
+        
+        let ix = Instruction {
+            program_id: self.program_id,
+            accounts: accounts.to_account_metas(None),
+            data: data.data(),
+        };
+        
+        let (recent_blockhash, last_valid_block_height) = self.latest_blockhash().await?;
+        
+        let mut transaction = Transaction::new_with_payer(&[ix], Some(&admin));
+        transaction.message.recent_blockhash = recent_blockhash;
+        
+        Ok(UnsignedTransaction { transaction, last_valid_block_height })
+    }
+    
+    /// Build an unsigned transaction, paid and signed by the vault owner, that
+    /// returns swept funds to the vault
+    pub async fn build_reclaim_dormant_funds_tx(&self, user_pubkey: Pubkey) -> Result<UnsignedTransaction> {
+        let _permit = self.rate_limiter.acquire().await.unwrap();
+        
+        let (vault_pda, _) = derive_vault_pda(&self.program_id, &user_pubkey);
+        let dormant = self.fetch_dormant_funds(vault_pda).await?
+            .ok_or_else(|| DomainError::NotFound(format!("Dormant funds of vault {}", vault_pda)))?;
+        let (dormant_funds, _) = derive_dormant_funds_pda(&self.program_id, &vault_pda);
+        let collateral = self.fetch_collateral_token(vault_pda, RpcMethodClass::Read).await?;
+        
+        let accounts = collateral_vault::accounts::ReclaimDormantFunds {
+            vault: vault_pda,
+            user: user_pubkey,
+            vault_token_account: collateral.token_account,
+            dormant_funds,
+            dormant_token_account: derive_dormant_token_pda(&self.program_id, &dormant_funds).0,
+            payer: dormant.payer,
+            config: self.get_config_pda(),
+            mint: collateral.mint,
+            token_program: collateral.token_program,
+        };
+        
+        let data = collateral_vault::instruction::ReclaimDormantFunds {};
+        
+        let ix = Instruction {
+            program_id: self.program_id,
+            accounts: accounts.to_account_metas(None),
+            data: data.data(),
+        };
+        
+        let (recent_blockhash, last_valid_block_height) = self.latest_blockhash().await?;
+        
+        let mut transaction = Transaction::new_with_payer(&[ix], Some(&user_pubkey));
+        transaction.message.recent_blockhash = recent_blockhash;
+        
+        Ok(UnsignedTransaction { transaction, last_valid_block_height })
+    }
+    
+    /// Deposit for the owner to sign and pay for, from `user_token_account` into their vault
+    pub async fn build_user_deposit_tx(
+        &self,
+        user_pubkey: Pubkey,
+        amount: u64,
+        user_token_account: Pubkey,
+    ) -> Result<UnsignedTransaction> {
+        let _permit = self.rate_limiter.acquire().await.unwrap();
+        
+        let (vault_pda, _) = derive_vault_pda(&self.program_id, &user_pubkey);
+        let collateral = self.fetch_collateral_token(vault_pda, RpcMethodClass::Read).await?;
+        
+        let accounts = collateral_vault::accounts::Deposit {
+            vault: vault_pda,
+            vault_token_account: collateral.token_account,
+            user_token_account,
+            user: user_pubkey,
+            config: self.get_config_pda(),
+            mint: collateral.mint,
+            token_program: collateral.token_program,
+        };
+        
+        let data = collateral_vault::instruction::Deposit { amount };
+        
+        let ix = Instruction {
+            program_id: self.program_id,
+            accounts: accounts.to_account_metas(None),
+            data: data.data(),
+        };
+        
+        let (recent_blockhash, last_valid_block_height) = self.latest_blockhash().await?;
+        
+        let mut transaction = Transaction::new_with_payer(&[ix], Some(&user_pubkey));
+        transaction.message.recent_blockhash = recent_blockhash;
+        
+        Ok(UnsignedTransaction { transaction, last_valid_block_height })
+    }
+    
+    /// Grow a legacy vault account to the current layout, paid by the backend payer
+    pub async fn build_migrate_vault_layout_tx(&self, user_pubkey: Pubkey, sub_account_id: u16) -> Result<BuiltTransaction> {
+        let _permit = self.rate_limiter.acquire().await.unwrap();
+        
+        let (vault_pda, vault_bump) = derive_sub_account_vault_pda(&self.program_id, &user_pubkey, sub_account_id);
+        let recent_blockhash = self.rpc.call(RpcMethodClass::Submit, |c| c.get_latest_blockhash()).await?;
+        
+        let accounts = collateral_vault::accounts::MigrateVaultLayout {
+            vault: vault_pda,
+            user: user_pubkey,
+            payer: self.payer.pubkey(),
+            system_program: system_program::id(),
+        };
+        
+        let data = collateral_vault::instruction::MigrateVaultLayout { sub_account_id };
+        
+        let ix = Instruction {
+            program_id: self.program_id,
+            accounts: accounts.to_account_metas(None),
+            data: data.data(),
+        };
+        
+        let transaction = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&self.payer.pubkey()),
+            &[&self.payer],
+            recent_blockhash,
+        );
+        
+        Ok(BuiltTransaction {
+            transaction,
+            vault_pubkey: vault_pda,
+            token_account_pubkey: self.get_vault_token_account(vault_pda).await?,
+            bump: vault_bump,
+            estimated_compute_units: 20_000,
+        })
+    }
+    
+    /// Fee, priority fee and blockhash expiry for an unsigned transaction handed to a client
+    pub async fn signing_hints(&self, transaction: &Transaction, last_valid_block_height: u64) -> Result<SigningHints> {
+        let message = &transaction.message;
+        let fee_lamports = self.rpc.call(RpcMethodClass::Read, |c| c.get_fee_for_message(message)).await?;
+        
+        let writable: Vec<Pubkey> = message.account_keys.iter()
+            .enumerate()
+            .filter(|(i, _)| message.is_writable(*i))
+            .map(|(_, key)| *key)
+            .collect();
+        let recent_fees = self.rpc
+            .call(RpcMethodClass::Read, |c| c.get_recent_prioritization_fees(&writable))
+            .await?;
+        let recent_fees: Vec<u64> = recent_fees.into_iter().map(|f| f.prioritization_fee).collect();
+        
+        let slot = self.rpc.call(RpcMethodClass::Read, |c| c.get_slot()).await?;
+        let block_height = self.rpc.call(RpcMethodClass::Read, |c| c.get_block_height()).await?;
+        let (expiry_slot, estimated_expiry_at) = blockhash_expiry(slot, block_height, last_valid_block_height, Utc::now());
+        
+        Ok(SigningHints {
+            recent_blockhash: message.recent_blockhash.to_string(),
+            last_valid_block_height,
+            expiry_slot,
+            estimated_expiry_at,
+            fee_lamports,
+            recommended_priority_fee: recommended_priority_fee(&recent_fees),
+        })
+    }
+    
+    /// Swap a stale blockhash in a prepared, still unsigned transaction for a fresh one
+    pub async fn refresh_unsigned(&self, mut transaction: Transaction) -> Result<(Transaction, SigningHints)> {
+        if transaction.signatures.iter().any(|s| *s != Signature::default()) {
+            return Err(DomainError::Validation("Transaction is already signed; refresh it before signing".to_string()).into());
+        }
+        let targets_known_program = transaction.message.instructions.iter().any(|ix| {
+            let program = transaction.message.account_keys.get(ix.program_id_index as usize);
+            program == Some(&self.program_id) || program == Some(&multisig::squads_program_id())
+        });
+        if !targets_known_program {
+            return Err(DomainError::Validation("Transaction was not prepared by this service".to_string()).into());
+        }
+        
+        let (recent_blockhash, last_valid_block_height) = self.latest_blockhash().await?;
+        transaction.message.recent_blockhash = recent_blockhash;
+        
+        let hints = self.signing_hints(&transaction, last_valid_block_height).await?;
+        Ok((transaction, hints))
+    }
+    
+    /// Latest confirmed blockhash and the last block height at which it is accepted
+    async fn latest_blockhash(&self) -> Result<(Hash, u64)> {
+        self.rpc
+            .call(RpcMethodClass::Submit, |c| c.get_latest_blockhash_with_commitment(CommitmentConfig::confirmed()))
+            .await
+    }
+    
+    /// Program the built transactions target
+    pub fn program_id(&self) -> Pubkey {
+        self.program_id
+    }
+    
+    /// Whether an account exists at `address`; distinguishes "absent" from RPC failures
+    pub async fn account_exists(&self, address: Pubkey) -> Result<bool> {
+        let account = self.rpc
+            .call(RpcMethodClass::Read, |c| c.get_account_with_commitment(&address, CommitmentConfig::confirmed()))
+            .await?
+            .value;
+        
+        Ok(account.is_some())
+    }
+    
     /// Get vault token account PDA
     async fn get_vault_token_account(&self, vault_pubkey: Pubkey) -> Result<Pubkey> {
-        let (token_pda, _) = Pubkey::find_program_address(
-            &[b"token", vault_pubkey.as_ref()],
-            &self.program_id,
-        );
+        let (token_pda, _) = derive_token_pda(&self.program_id, &vault_pubkey);
         Ok(token_pda)
     }
     
+    /// Global program config PDA (holds the per-transaction cap)
+    fn get_config_pda(&self) -> Pubkey {
+        derive_config_pda(&self.program_id).0
+    }
+    
+    /// Fetch and decode the on-chain Vault account, the source of truth for balances
+    /// Fetch and decode a vault account; legacy vaults decode with untracked counters
+    pub async fn fetch_vault_account(&self, vault_pubkey: Pubkey, class: RpcMethodClass) -> Result<collateral_vault_types::Vault> {
+        let account = self.rpc.call(class, |c| c.get_account(&vault_pubkey)).await?;
+        
+        collateral_vault_types::Vault::from_account_data(&account.data)
+            .map_err(|e| ChainError::InvalidAccountData(format!("Vault {}: {}", vault_pubkey, e)).into())
+    }
+    
+    /// Raw amount held by an SPL token account
+    pub async fn fetch_token_account_balance(&self, token_account: Pubkey, class: RpcMethodClass) -> Result<u64> {
+        let balance = self.rpc.call(class, |c| c.get_token_account_balance(&token_account)).await?;
+        
+        balance.amount.parse()
+            .map_err(|_| ChainError::InvalidAccountData(format!("Token account {}: bad amount {}", token_account, balance.amount)).into())
+    }
+    
+    /// Fetch and decode the dormant-funds record of a swept vault; `None` when it was never swept or was reclaimed
+    pub async fn fetch_dormant_funds(&self, vault_pubkey: Pubkey) -> Result<Option<collateral_vault_types::DormantFunds>> {
+        let (dormant_funds, _) = derive_dormant_funds_pda(&self.program_id, &vault_pubkey);
+        let account = self.rpc
+            .call(RpcMethodClass::Read, |c| c.get_account_with_commitment(&dormant_funds, CommitmentConfig::confirmed()))
+            .await?
+            .value;
+        
+        account
+            .map(|account| collateral_vault_types::DormantFunds::try_deserialize(&mut account.data.as_slice()))
+            .transpose()
+            .map_err(|e| ChainError::InvalidAccountData(format!("Dormant funds {}: {}", dormant_funds, e)).into())
+    }
+    
+    /// Fetch and decode the global program config
+    pub async fn fetch_program_config(&self, class: RpcMethodClass) -> Result<collateral_vault_types::ProgramConfig> {
+        let config_pda = self.get_config_pda();
+        let account = self.rpc.call(class, |c| c.get_account(&config_pda)).await?;
+        
+        collateral_vault_types::ProgramConfig::from_account_data(&account.data)
+            .map_err(|e| ChainError::InvalidAccountData(format!("Program config {}: {}", config_pda, e)).into())
+    }
+    
+    /// Fetch and unpack an SPL token account, including its delegate and close authority
+    pub async fn fetch_token_account(&self, token_account: Pubkey, class: RpcMethodClass) -> Result<spl_token_2022::state::Account> {
+        let account = self.rpc.call(class, |c| c.get_account(&token_account)).await?;
+        
+        unpack_token_account(&token_account, &account)
+    }
+    
+    /// A vault's collateral token account with its mint and token program
+    pub async fn fetch_collateral_token(&self, vault_pubkey: Pubkey, class: RpcMethodClass) -> Result<CollateralToken> {
+        let token_account = self.get_vault_token_account(vault_pubkey).await?;
+        let account = self.rpc.call(class, |c| c.get_account(&token_account)).await?;
+        let unpacked = unpack_token_account(&token_account, &account)?;
+        
+        Ok(CollateralToken {
+            token_account,
+            mint: unpacked.mint,
+            token_program: account.owner,
+        })
+    }
+    
+    /// Treasury token account of `mint`; `None` until the admin creates it,
+    /// which withdrawals only need while a fee is charged
+    pub async fn fetch_treasury(&self, mint: Pubkey) -> Result<Option<Pubkey>> {
+        let treasury = derive_treasury_token_pda(&self.program_id, &mint).0;
+        let account = self.rpc
+            .call(RpcMethodClass::Read, |c| c.get_account_with_commitment(&treasury, CommitmentConfig::confirmed()))
+            .await?
+            .value;
+        
+        Ok(account.map(|_| treasury))
+    }
+    
+    /// Token program owning `mint`, the legacy one or Token-2022
+    pub async fn fetch_token_program(&self, mint: Pubkey) -> Result<Pubkey> {
+        let account = self.rpc.call(RpcMethodClass::Read, |c| c.get_account(&mint)).await?;
+        
+        if !token_accounts::is_token_program(&account.owner) {
+            return Err(DomainError::Validation(format!(
+                "Mint {} is not owned by a token program (owned by {})", mint, account.owner
+            )).into());
+        }
+        Ok(account.owner)
+    }
+    
+    /// Fetch the fields of a user token account the prepare checks need; `None` when it does not exist
+    pub async fn fetch_token_account_info(&self, token_account: Pubkey, class: RpcMethodClass) -> Result<Option<TokenAccountInfo>> {
+        let account = self.rpc
+            .call(class, |c| c.get_account_with_commitment(&token_account, CommitmentConfig::confirmed()))
+            .await?
+            .value;
+        let Some(account) = account else { return Ok(None) };
+        
+        if !token_accounts::is_token_program(&account.owner) {
+            return Err(DomainError::Validation(format!(
+                "Account {} is not an SPL token account (owned by {})", token_account, account.owner
+            )).into());
+        }
+        let unpacked = unpack_token_account(&token_account, &account)?;
+        
+        Ok(Some(TokenAccountInfo {
+            token_program: account.owner,
+            mint: unpacked.mint,
+            owner: unpacked.owner,
+            amount: unpacked.amount,
+            frozen: unpacked.is_frozen(),
+        }))
+    }
+    
+    /// Check a user token account before the prepared transaction moves `mint` tokens, of `token_program`, through it
+    pub async fn check_user_token_account(
+        &self,
+        token_account: Pubkey,
+        mint: Pubkey,
+        token_program: Pubkey,
+        owner: Pubkey,
+        role: TokenAccountRole,
+    ) -> Result<TokenAccountPlan> {
+        let account = self.fetch_token_account_info(token_account, RpcMethodClass::Read).await?;
+        token_accounts::check_user_token_account(&token_account, account.as_ref(), &mint, &token_program, &owner, role)
+    }
+    
+    /// Signatures of recent successful transactions that touched `address`, newest first
+    pub async fn fetch_recent_signatures(&self, address: Pubkey, class: RpcMethodClass) -> Result<Vec<String>> {
+        let signatures = self.rpc.call(class, |c| c.get_signatures_for_address(&address)).await?;
+        
+        Ok(signatures.into_iter().filter(|s| s.err.is_none()).map(|s| s.signature).collect())
+    }
+    
+    /// One page of signatures for `address`, newest first, older than `before` and
+    /// newer than `until`; failed transactions are included and marked
+    pub async fn fetch_signature_page(
+        &self,
+        address: Pubkey,
+        before: Option<&str>,
+        until: Option<&str>,
+        limit: usize,
+        class: RpcMethodClass,
+    ) -> Result<Vec<SignatureEntry>> {
+        let parse = |signature: &str| Signature::from_str(signature)
+            .map_err(|_| DomainError::Validation(format!("Invalid signature: {}", signature)));
+        let config = GetConfirmedSignaturesForAddress2Config {
+            before: before.map(parse).transpose()?,
+            until: until.map(parse).transpose()?,
+            limit: Some(limit),
+            commitment: Some(CommitmentConfig::confirmed()),
+        };
+        let signatures = self.rpc.call(class, |c| c.get_signatures_for_address_with_config(&address, config)).await?;
+        
+        Ok(signatures.into_iter().map(|s| SignatureEntry {
+            signature: s.signature,
+            slot: s.slot,
+            block_time: s.block_time,
+            failed: s.err.is_some(),
+        }).collect())
+    }
+    
+    /// Highest slot the cluster has finalized
+    pub async fn fetch_finalized_slot(&self, class: RpcMethodClass) -> Result<u64> {
+        self.rpc.call(class, |c| c.get_slot_with_commitment(CommitmentConfig::finalized())).await
+    }
+    
+    /// Log messages of a finalized transaction
+    pub async fn fetch_finalized_logs(&self, signature: &str, class: RpcMethodClass) -> Result<Vec<String>> {
+        let signature = Signature::from_str(signature)
+            .map_err(|_| DomainError::Validation(format!("Invalid signature: {}", signature)))?;
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Json),
+            commitment: Some(CommitmentConfig::finalized()),
+            max_supported_transaction_version: Some(0),
+        };
+        let transaction = self.rpc.call(class, |c| c.get_transaction_with_config(&signature, config)).await?;
+        
+        Ok(transaction.transaction.meta
+            .and_then(|meta| Option::<Vec<String>>::from(meta.log_messages))
+            .unwrap_or_default())
+    }
+    
+    /// The transaction behind `entry` with its instructions to the program decoded;
+    /// only what the entry itself says when the node no longer serves it
+    pub async fn fetch_onchain_transaction(&self, entry: &SignatureEntry, class: RpcMethodClass) -> Result<OnchainTransaction> {
+        let signature = Signature::from_str(&entry.signature)
+            .map_err(|_| DomainError::Validation(format!("Invalid signature: {}", entry.signature)))?;
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        };
+        let mut onchain = OnchainTransaction::from_entry(entry);
+        let transaction = match self.rpc.call(class, |c| c.get_transaction_with_config(&signature, config)).await {
+            Ok(transaction) => transaction,
+            Err(e) => {
+                warn!("Failed to fetch transaction {}: {}", entry.signature, e);
+                return Ok(onchain);
+            }
+        };
+        
+        let inner_instructions = transaction.transaction.meta.as_ref()
+            .and_then(|meta| Option::<Vec<_>>::from(meta.inner_instructions.clone()))
+            .unwrap_or_default();
+        if let Some(decoded) = transaction.transaction.transaction.decode() {
+            onchain.fee_payer = decoded.message.static_account_keys().first().map(|key| key.to_string());
+            onchain.instructions = onchain_activity::decode_program_instructions(&self.program_id, &decoded.message, &inner_instructions);
+        }
+        Ok(onchain)
+    }
+    
+    /// Fee a finalized transaction paid; `None` when the node kept no status meta for it
+    pub async fn fetch_realized_fee(&self, signature: &str, class: RpcMethodClass) -> Result<Option<RealizedFee>> {
+        let signature = Signature::from_str(signature)
+            .map_err(|_| DomainError::Validation(format!("Invalid signature: {}", signature)))?;
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(CommitmentConfig::finalized()),
+            max_supported_transaction_version: Some(0),
+        };
+        let transaction = self.rpc.call(class, |c| c.get_transaction_with_config(&signature, config)).await?;
+        
+        let signatures = transaction.transaction.transaction.decode()
+            .map_or(1, |decoded| decoded.signatures.len() as u64);
+        Ok(transaction.transaction.meta.map(|meta| {
+            RealizedFee::new(meta.fee, signatures, Option::<u64>::from(meta.compute_units_consumed))
+        }))
+    }
+    
+    /// Build an unsigned Squads transaction that proposes (and approves, as `creator`)
+    /// a withdraw from a vault whose `user` is the multisig's vault PDA
+    pub async fn build_multisig_withdraw_proposal(
+        &self,
+        multisig: Pubkey,
+        vault_index: u8,
+        creator: Pubkey,
+        vault_pubkey: Pubkey,
+        amount: u64,
+        destination_token_account: Pubkey,
+        destination_plan: &TokenAccountPlan,
+    ) -> Result<MultisigProposalTx> {
+        let _permit = self.rate_limiter.acquire().await.unwrap();
+        
+        let (squads_vault, _) = multisig::squads_vault_pda(&multisig, vault_index);
+        let collateral = self.fetch_collateral_token(vault_pubkey, RpcMethodClass::Read).await?;
+        
+        // Inner withdraw, signed by the Squads vault PDA when the proposal executes
+        let accounts = collateral_vault::accounts::Withdraw {
+            vault: vault_pubkey,
+            vault_token_account: collateral.token_account,
+            user_token_account: destination_token_account,
+            treasury_token_account: self.fetch_treasury(collateral.mint).await?,
+            user: squads_vault,
+            config: self.get_config_pda(),
+            mint: collateral.mint,
+            token_program: collateral.token_program,
+            memo_program: None,
+        };
+        
+        let data = collateral_vault::instruction::Withdraw { amount };
+        
+        let withdraw_ix = Instruction {
+            program_id: self.program_id,
+            accounts: accounts.to_account_metas(None),
+            data: data.data(),
+        };
+        
+        let message = multisig::compile_transaction_message(&squads_vault, &[withdraw_ix])?;
+        let transaction_index = self.fetch_multisig_transaction_index(multisig).await? + 1;
+        
+        let (recent_blockhash, last_valid_block_height) = self.latest_blockhash().await?;
+        
+        // A missing destination ATA is created up front, paid by the creator, so the
+        // proposal can execute once approved
+        let mut instructions = destination_plan.setup_instructions(&creator);
+        instructions.extend([
+            multisig::vault_transaction_create_ix(&multisig, &creator, transaction_index, vault_index, message),
+            multisig::proposal_create_ix(&multisig, &creator, transaction_index),
+            multisig::proposal_approve_ix(&multisig, &creator, transaction_index),
+        ]);
+        
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(&creator));
+        transaction.message.recent_blockhash = recent_blockhash;
+        
+        Ok(MultisigProposalTx {
+            transaction,
+            last_valid_block_height,
+            squads_vault,
+            transaction_index,
+            proposal: multisig::squads_proposal_pda(&multisig, transaction_index).0,
+        })
+    }
+    
+    /// Index of the last transaction created on a Squads multisig
+    pub async fn fetch_multisig_transaction_index(&self, multisig: Pubkey) -> Result<u64> {
+        let account = self.rpc.call(RpcMethodClass::Read, |c| c.get_account(&multisig)).await?;
+        
+        multisig::parse_multisig_transaction_index(&account.data)
+    }
+    
+    /// Status of a Squads proposal; `None` until the proposal account exists
+    pub async fn fetch_multisig_proposal_status(&self, proposal: Pubkey) -> Result<Option<MultisigProposalStatus>> {
+        let account = self.rpc
+            .call(RpcMethodClass::Read, |c| c.get_account_with_commitment(&proposal, CommitmentConfig::confirmed()))
+            .await?
+            .value;
+        
+        account.map(|a| multisig::parse_proposal_status(&a.data)).transpose()
+    }
+    
+    /// Network cost of a withdrawal from the vault at current priority fees, without building it
+    pub async fn estimate_withdraw_network_fee(&self, vault_pubkey: Pubkey) -> Result<NetworkFeeEstimate> {
+        let vault_token_account = self.get_vault_token_account(vault_pubkey).await?;
+        let recent_fees = self.rpc
+            .call(RpcMethodClass::Read, |c| c.get_recent_prioritization_fees(&[vault_pubkey, vault_token_account]))
+            .await?;
+        let recent_fees: Vec<u64> = recent_fees.into_iter().map(|f| f.prioritization_fee).collect();
+        
+        Ok(NetworkFeeEstimate::new(1, WITHDRAW_COMPUTE_UNITS, recommended_priority_fee(&recent_fees)))
+    }
+    
     /// Estimate transaction cost
     pub fn estimate_transaction_cost(&self, built_tx: &BuiltTransaction) -> u64 {
         // Base fee + compute unit cost
@@ -373,6 +1047,131 @@ impl TransactionBuilder {
     }
 }
 
+/// Transaction for a client to sign, with the height its blockhash is good until
+#[derive(Debug, Clone)]
+pub struct UnsignedTransaction {
+    pub transaction: Transaction,
+    pub last_valid_block_height: u64,
+}
+
+/// What a wallet needs to price an unsigned transaction and tell when it goes stale
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SigningHints {
+    pub recent_blockhash: String,
+    /// Last block height at which the blockhash is accepted
+    pub last_valid_block_height: u64,
+    /// Estimated slot the blockhash expires at; skipped slots only push it later
+    pub expiry_slot: u64,
+    pub estimated_expiry_at: DateTime<Utc>,
+    /// Base fee for the transaction's signatures, in lamports
+    pub fee_lamports: u64,
+    /// Compute unit price to set, in micro-lamports
+    pub recommended_priority_fee: u64,
+}
+
+/// Compute budget a withdraw instruction is estimated to use
+pub const WITHDRAW_COMPUTE_UNITS: u32 = 120_000;
+
+/// Base fee charged per signature, in lamports
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+/// Estimated network cost of a transaction, in lamports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkFeeEstimate {
+    pub base_fee_lamports: u64,
+    /// Compute unit price, in micro-lamports
+    pub priority_fee_micro_lamports: u64,
+    pub compute_units: u32,
+    pub priority_fee_lamports: u64,
+    pub total_lamports: u64,
+}
+
+impl NetworkFeeEstimate {
+    pub fn new(signatures: u64, compute_units: u32, priority_fee_micro_lamports: u64) -> Self {
+        let base_fee_lamports = signatures * LAMPORTS_PER_SIGNATURE;
+        // Micro-lamports per unit, rounded up as the runtime does
+        let priority_fee_lamports = ((compute_units as u128 * priority_fee_micro_lamports as u128 + 999_999) / 1_000_000) as u64;
+        Self {
+            base_fee_lamports,
+            priority_fee_micro_lamports,
+            compute_units,
+            priority_fee_lamports,
+            total_lamports: base_fee_lamports + priority_fee_lamports,
+        }
+    }
+}
+
+/// Fee a landed transaction paid, in lamports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RealizedFee {
+    pub fee_lamports: u64,
+    /// Part of the fee above the base fee for the transaction's signatures
+    pub priority_fee_lamports: u64,
+    pub compute_units_consumed: Option<u64>,
+}
+
+impl RealizedFee {
+    pub fn new(fee_lamports: u64, signatures: u64, compute_units_consumed: Option<u64>) -> Self {
+        Self {
+            fee_lamports,
+            priority_fee_lamports: fee_lamports.saturating_sub(signatures * LAMPORTS_PER_SIGNATURE),
+            compute_units_consumed,
+        }
+    }
+}
+
+/// Percentile of recent prioritization fees that is recommended
+pub const PRIORITY_FEE_PERCENTILE: usize = 75;
+
+/// Priority fee paid by `PRIORITY_FEE_PERCENTILE`% of recent transactions on the same accounts
+pub fn recommended_priority_fee(recent_fees: &[u64]) -> u64 {
+    if recent_fees.is_empty() {
+        return 0;
+    }
+    let mut fees = recent_fees.to_vec();
+    fees.sort_unstable();
+    fees[(fees.len() - 1) * PRIORITY_FEE_PERCENTILE / 100]
+}
+
+/// Slot and time at which a blockhash valid until `last_valid_block_height` expires,
+/// assuming a block every slot from here on
+pub fn blockhash_expiry(
+    current_slot: u64,
+    current_block_height: u64,
+    last_valid_block_height: u64,
+    now: DateTime<Utc>,
+) -> (u64, DateTime<Utc>) {
+    let remaining = last_valid_block_height.saturating_sub(current_block_height);
+    let expires_at = now + Duration::milliseconds((remaining as f64 * NOMINAL_SLOT_TIME_MS) as i64);
+    (current_slot + remaining, expires_at)
+}
+
+/// Unpack a token account of either token program; extensions are skipped
+fn unpack_token_account(address: &Pubkey, account: &solana_sdk::account::Account) -> Result<spl_token_2022::state::Account> {
+    if !token_accounts::is_token_program(&account.owner) {
+        return Err(ChainError::InvalidAccountData(format!(
+            "Token account {}: owned by {}, not a token program", address, account.owner
+        )).into());
+    }
+    StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+        .map(|state| state.base)
+        .map_err(|e| ChainError::InvalidAccountData(format!("Token account {}: {}", address, e)).into())
+}
+
+/// Base64 bincode, the encoding unsigned transactions are handed to clients in
+pub fn encode_transaction(transaction: &Transaction) -> Result<String> {
+    let serialized = bincode::serialize(transaction)
+        .map_err(|e| VaultError::Internal(format!("Failed to serialize transaction: {}", e)))?;
+    Ok(base64::Engine::encode(&base64::engine::general_purpose::STANDARD, serialized))
+}
+
+pub fn decode_transaction(encoded: &str) -> Result<Transaction> {
+    let bytes = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, encoded)
+        .map_err(|_| DomainError::Validation("Transaction is not valid base64".to_string()))?;
+    bincode::deserialize(&bytes)
+        .map_err(|_| DomainError::Validation("Transaction could not be decoded".to_string()).into())
+}
+
 #[derive(Debug, Clone)]
 pub struct BuiltTransaction {
     pub transaction: Transaction,
@@ -384,30 +1183,59 @@ pub struct BuiltTransaction {
 
 /// Transaction submission manager
 pub struct TransactionSubmitter {
-    rpc_client: Arc<RpcClient>,
+    rpc: Arc<BudgetedRpcClient>,
     max_retries: u32,
     retry_delay_ms: u64,
+    cluster_timing: Option<Arc<ClusterTiming>>,
 }
 
 impl TransactionSubmitter {
-    pub fn new(rpc_client: Arc<RpcClient>, max_retries: u32, retry_delay_ms: u64) -> Self {
+    pub fn new(rpc: Arc<BudgetedRpcClient>, max_retries: u32, retry_delay_ms: u64) -> Self {
         Self {
-            rpc_client,
+            rpc,
             max_retries,
             retry_delay_ms,
+            cluster_timing: None,
         }
     }
     
+    /// Hold submissions while the cluster is producing slots well below nominal speed
+    pub fn with_cluster_timing(mut self, cluster_timing: Arc<ClusterTiming>) -> Self {
+        self.cluster_timing = Some(cluster_timing);
+        self
+    }
+    
     /// Submit transaction with retry logic
     pub async fn submit_transaction(&self, transaction: Transaction, tx_id: Uuid) -> Result<String> {
+        let submitted = self.submit_transaction_with_timings(transaction, tx_id, &mut StageTimings::new()).await?;
+        Ok(submitted.signature)
+    }
+    
+    /// Submit transaction with retry logic, charging send and confirmation time
+    /// (across all attempts) to the Submit and Confirm stages. Time held back for
+    /// a degraded cluster counts towards Submit.
+    pub async fn submit_transaction_with_timings(
+        &self,
+        transaction: Transaction,
+        tx_id: Uuid,
+        timings: &mut StageTimings,
+    ) -> Result<SubmittedTransaction> {
+        if let Some(cluster_timing) = &self.cluster_timing {
+            let deferred = cluster_timing.wait_for_submission_window().await;
+            if !deferred.is_zero() {
+                info!("Deferred submission of {} by {:?} for degraded cluster", tx_id, deferred);
+                timings.record(PipelineStage::Submit, deferred);
+            }
+        }
+        
         let mut retry_count = 0;
         let mut last_error = None;
         
         loop {
-            match self.send_transaction(&transaction).await {
+            match self.send_transaction(&transaction, timings).await {
                 Ok(signature) => {
                     info!("Transaction submitted successfully: {}", signature);
-                    return Ok(signature);
+                    return Ok(SubmittedTransaction { signature, attempts: retry_count + 1 });
                 }
                 Err(e) => {
                     error!("Transaction submission failed (attempt {}): {}", retry_count + 1, e);
@@ -423,33 +1251,39 @@ impl TransactionSubmitter {
             }
         }
         
-        Err(VaultError::TransactionFailed(format!(
+        Err(ChainError::TransactionFailed(format!(
             "Transaction failed after {} retries: {:?}",
             self.max_retries,
             last_error
-        )))
+        )).into())
     }
     
     /// Send transaction to Solana
-    async fn send_transaction(&self, transaction: &Transaction) -> Result<String> {
-        let signature = self.rpc_client.send_transaction(transaction)?;
+    async fn send_transaction(&self, transaction: &Transaction, timings: &mut StageTimings) -> Result<String> {
+        let started = std::time::Instant::now();
+        let signature = self.rpc.call(RpcMethodClass::Submit, |c| c.send_transaction(transaction)).await;
+        timings.record(PipelineStage::Submit, started.elapsed());
+        let signature = signature?;
         
         // Wait for confirmation
-        let confirmation = self.rpc_client.confirm_transaction(&signature)?;
+        let started = std::time::Instant::now();
+        let confirmation = self.rpc.call(RpcMethodClass::Submit, |c| c.confirm_transaction(&signature)).await;
+        timings.record(PipelineStage::Confirm, started.elapsed());
+        let confirmation = confirmation?;
         
         if confirmation {
             Ok(signature.to_string())
         } else {
-            Err(VaultError::TransactionFailed("Transaction not confirmed".to_string()))
+            Err(ChainError::TransactionFailed("Transaction not confirmed".to_string()).into())
         }
     }
     
     /// Check transaction status
     pub async fn check_transaction_status(&self, signature: &str) -> Result<TransactionStatus> {
         let sig = signature.parse()
-            .map_err(|_| VaultError::ValidationError("Invalid signature".to_string()))?;
+            .map_err(|_| DomainError::Validation("Invalid signature".to_string()))?;
         
-        match self.rpc_client.get_signature_status(&sig)? {
+        match self.rpc.call(RpcMethodClass::Submit, |c| c.get_signature_status(&sig)).await? {
             Some(Ok(_)) => Ok(TransactionStatus::Confirmed),
             Some(Err(e)) => Ok(TransactionStatus::Failed(e.to_string())),
             None => Ok(TransactionStatus::Pending),
@@ -457,6 +1291,13 @@ impl TransactionSubmitter {
     }
 }
 
+/// A transaction that landed, and how many sends it took
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubmittedTransaction {
+    pub signature: String,
+    pub attempts: u32,
+}
+
 #[derive(Debug, Clone)]
 pub enum TransactionStatus {
     Pending,
@@ -0,0 +1,149 @@
+//! Time-weighted average balances over a vault's confirmed ledger.
+//!
+//! Rewards programs pay for how long collateral stayed in a vault, which a
+//! point-in-time snapshot cannot tell. Balances are rebuilt by replaying the
+//! confirmed records at their confirmation time (`updated_at`), the same
+//! placement ledger reconciliation uses, and integrated over fixed-length
+//! epochs aligned to `epoch_origin()`.
+//!
+//! The epoch containing `now` is averaged only up to `now` and reported as
+//! incomplete, so its figure is final only once the epoch has ended.
+
+use crate::models::TransactionRecord;
+use crate::reconciliation::record_effect;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Epochs are counted from Monday 1970-01-05 00:00 UTC, so weekly epochs start on Mondays
+pub fn epoch_origin() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(1970, 1, 5, 0, 0, 0).unwrap()
+}
+
+pub const DEFAULT_EPOCH_SECONDS: i64 = 7 * 24 * 3600;
+
+/// Shortest epoch accepted; finer averages belong to snapshots
+pub const MIN_EPOCH_SECONDS: i64 = 3600;
+
+/// Most epochs computed in one report
+pub const MAX_EPOCHS: i64 = 400;
+
+/// Time-weighted balances of one epoch, in base units
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpochTwab {
+    /// Index counted from the epoch origin
+    pub epoch: i64,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    /// The epoch has ended; an incomplete epoch is averaged up to the report time
+    pub complete: bool,
+    pub twab_total_balance: i64,
+    pub twab_locked_balance: i64,
+    pub twab_available_balance: i64,
+    pub closing_total_balance: i64,
+    pub closing_locked_balance: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TwabReport {
+    pub vault_id: Uuid,
+    pub epoch_seconds: i64,
+    pub epochs: Vec<EpochTwab>,
+    pub generated_at: DateTime<Utc>,
+}
+
+pub fn build_twab_report(
+    vault_id: Uuid,
+    records: &[TransactionRecord],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    epoch_seconds: i64,
+    now: DateTime<Utc>,
+) -> TwabReport {
+    TwabReport {
+        vault_id,
+        epoch_seconds,
+        epochs: time_weighted_balances(records, from, to, epoch_seconds, now),
+        generated_at: now,
+    }
+}
+
+/// Index of the epoch containing `at`
+pub fn epoch_index(at: DateTime<Utc>, epoch_seconds: i64) -> i64 {
+    (at - epoch_origin()).num_seconds().div_euclid(epoch_seconds)
+}
+
+pub fn epoch_start(epoch: i64, epoch_seconds: i64) -> DateTime<Utc> {
+    epoch_origin() + Duration::seconds(epoch * epoch_seconds)
+}
+
+/// Time-weighted balances for every epoch from the one containing `from` to the
+/// one containing `to`, neither averaged past `now`
+pub fn time_weighted_balances(
+    records: &[TransactionRecord],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    epoch_seconds: i64,
+    now: DateTime<Utc>,
+) -> Vec<EpochTwab> {
+    let mut changes: Vec<(DateTime<Utc>, i64, i64)> = records
+        .iter()
+        .map(|r| {
+            let (total, locked) = record_effect(r);
+            (r.updated_at, total, locked)
+        })
+        .collect();
+    changes.sort_by_key(|(at, _, _)| *at);
+
+    let first = epoch_index(from, epoch_seconds);
+    let last = epoch_index(to.min(now), epoch_seconds);
+
+    // Balances at the start of the first epoch
+    let mut next = 0;
+    let (mut total, mut locked) = (0i64, 0i64);
+    let first_start = epoch_start(first, epoch_seconds);
+    while next < changes.len() && changes[next].0 < first_start {
+        total += changes[next].1;
+        locked += changes[next].2;
+        next += 1;
+    }
+
+    let mut epochs = Vec::new();
+    for epoch in first..=last {
+        let starts_at = epoch_start(epoch, epoch_seconds);
+        let ends_at = epoch_start(epoch + 1, epoch_seconds);
+        let until = ends_at.min(now);
+
+        let (mut total_area, mut locked_area) = (0i128, 0i128);
+        let mut cursor = starts_at;
+        while next < changes.len() && changes[next].0 < until {
+            let elapsed = (changes[next].0 - cursor).num_milliseconds() as i128;
+            total_area += total as i128 * elapsed;
+            locked_area += locked as i128 * elapsed;
+            cursor = changes[next].0;
+            total += changes[next].1;
+            locked += changes[next].2;
+            next += 1;
+        }
+        let elapsed = (until - cursor).num_milliseconds() as i128;
+        total_area += total as i128 * elapsed;
+        locked_area += locked as i128 * elapsed;
+
+        let span = (until - starts_at).num_milliseconds() as i128;
+        let average = |area: i128| if span > 0 { (area / span) as i64 } else { 0 };
+        let (twab_total, twab_locked) = (average(total_area), average(locked_area));
+
+        epochs.push(EpochTwab {
+            epoch,
+            starts_at,
+            ends_at,
+            complete: ends_at <= now,
+            twab_total_balance: twab_total,
+            twab_locked_balance: twab_locked,
+            twab_available_balance: twab_total - twab_locked,
+            closing_total_balance: total,
+            closing_locked_balance: locked,
+        });
+    }
+    epochs
+}
@@ -1,30 +1,178 @@
-use crate::error::{Result, VaultError};
-use crate::models::{Vault, VaultCreateRequest, VaultDepositRequest, VaultWithdrawRequest, 
+use crate::error::{Result, DomainError, StorageError};
+use crate::models::{Vault, VaultVersion, VaultListFilter, VaultCreateRequest, VaultDepositRequest, VaultWithdrawRequest, 
                     VaultLockRequest, VaultUnlockRequest, VaultTransferRequest, TransactionRecord,
-                    TransactionType, TransactionStatus, BalanceSnapshot, AuditLog};
-use crate::database::{VaultRepository, TransactionRepository, AuditRepository};
+                    TransactionType, TransactionStatus, BalanceSnapshot, AuditLog,
+                    BalanceUpdate, BalanceUpdateSource, WithdrawalQueueStatus, StageLatencyRow,
+                    PendingQuota, PendingUsage, BalanceHold, HoldBalance, HeldAmounts, SpendableBalance, QueueOutcome,
+                    PendingDeposit};
+use crate::deposits::DEPOSIT_CREDITED_EVENT;
+use crate::latency::StageTimings;
+use crate::transaction_builder::SubmittedTransaction;
+use crate::database::{VaultRepository, TransactionRepository, AuditRepository, HoldRepository};
 use sqlx::PgPool;
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 use std::str::FromStr;
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use tokio::sync::broadcast;
 use tracing::{info, warn, error};
 
 pub struct VaultManager {
     vault_repo: VaultRepository,
     transaction_repo: TransactionRepository,
     audit_repo: AuditRepository,
+    hold_repo: HoldRepository,
+    transaction_manager: TransactionManager,
+    balance_updates: broadcast::Sender<BalanceUpdate>,
+    pending_quota: PendingQuota,
 }
 
+/// Buffered balance updates per subscriber before it is considered lagged
+const BALANCE_UPDATE_CHANNEL_CAPACITY: usize = 1024;
+
 impl VaultManager {
     pub fn new(pool: PgPool) -> Self {
         Self {
             vault_repo: VaultRepository::new(pool.clone()),
             transaction_repo: TransactionRepository::new(pool.clone()),
+            transaction_manager: TransactionManager::new(pool.clone()),
+            hold_repo: HoldRepository::new(pool.clone()),
             audit_repo: AuditRepository::new(pool),
+            balance_updates: broadcast::channel(BALANCE_UPDATE_CHANNEL_CAPACITY).0,
+            pending_quota: PendingQuota::default(),
+        }
+    }
+    
+    /// Cap how much work may be queued per vault
+    pub fn with_pending_quota(mut self, pending_quota: PendingQuota) -> Self {
+        self.pending_quota = pending_quota;
+        self
+    }
+    
+    pub fn pending_quota(&self) -> &PendingQuota {
+        &self.pending_quota
+    }
+    
+    /// Queue an operation for a vault and hold the balance it draws on until
+    /// it settles, refusing it when the vault already has as much pending work
+    /// as its quota allows or too little unheld balance
+    pub async fn queue_transaction(&self,
+                                   vault_id: Uuid,
+                                   tx_type: TransactionType,
+                                   amount: i64,
+                                   tx_signature: Option<String>,
+                                   idempotency_key: Option<String>) -> Result<TransactionRecord> {
+        let hold = HoldBalance::for_operation(&tx_type, amount);
+        let outcome = self.transaction_manager.create_transaction_within_quota(
+            vault_id, tx_type.clone(), amount, tx_signature, idempotency_key, &self.pending_quota, hold,
+        ).await?;
+        
+        match outcome {
+            QueueOutcome::Queued(tx) => Ok(tx),
+            QueueOutcome::QuotaExceeded(usage) => {
+                let withdrawal_amount = if matches!(tx_type, TransactionType::Withdraw) { amount } else { 0 };
+                let reason = self.pending_quota.violation(&usage, withdrawal_amount).unwrap_or_default();
+                warn!("Refused {:?} of {} for vault {}: {}", tx_type, amount, vault_id, reason);
+                Err(DomainError::QuotaExceeded(format!("Vault {}: {}", vault_id, reason)).into())
+            }
+            QueueOutcome::InsufficientSpendable { balance, spendable } => {
+                let required = hold.map_or(0, |(_, held)| held) as u64;
+                warn!("Refused {:?} of {} for vault {}: {} {} balance not held by pending operations",
+                      tx_type, amount, vault_id, spendable, balance.as_str());
+                Err(match balance {
+                    HoldBalance::Available => DomainError::InsufficientBalance { available: spendable as u64, required },
+                    HoldBalance::Locked => DomainError::InsufficientLockedBalance { locked: spendable as u64, required },
+                }.into())
+            }
         }
     }
     
+    /// Record a deposit the user submitted on-chain as a provisional credit.
+    ///
+    /// No balance changes here: the indexer credits the vault once `signature` is
+    /// finalized with a matching `DepositEvent` (see `crate::deposits`). A
+    /// signature already backing the same deposit returns that record, also
+    /// once credited at less than `amount` after a transfer fee.
+    pub async fn deposit(&self,
+                         vault_id: Uuid,
+                         amount: u64,
+                         signature: String,
+                         idempotency_key: Option<String>) -> Result<TransactionRecord> {
+        Signature::from_str(&signature)
+            .map_err(|_| DomainError::Validation(format!("Invalid signature: {}", signature)))?;
+        
+        if let Some(existing) = self.transaction_repo.get_deposit_by_signature(&signature).await? {
+            let credited_after_fee = matches!(existing.status, TransactionStatus::Confirmed) && existing.amount < amount as i64;
+            if existing.vault_id == vault_id && (existing.amount == amount as i64 || credited_after_fee) {
+                return Ok(existing);
+            }
+            return Err(DomainError::Validation(format!(
+                "Signature {} already backs deposit {}", signature, existing.id
+            )).into());
+        }
+        
+        self.queue_transaction(vault_id, TransactionType::Deposit, amount as i64, Some(signature), idempotency_key).await
+    }
+    
+    /// Credit `amount` of a pending deposit confirmed on-chain in `slot`; `None` if it was no longer pending
+    pub async fn credit_deposit(&self, tx_id: Uuid, slot: i64, amount: i64) -> Result<Option<Vault>> {
+        let Some(vault) = self.vault_repo.credit_deposit(tx_id, slot, amount, DEPOSIT_CREDITED_EVENT).await? else {
+            return Ok(None);
+        };
+        
+        let _ = self.balance_updates.send(BalanceUpdate::from_vault(&vault, BalanceUpdateSource::Database));
+        match self.transaction_repo.get_transaction_by_id(tx_id).await {
+            Ok(tx) => {
+                let _ = self.transaction_manager.transaction_updates.send(tx);
+            }
+            Err(e) => warn!("Deposit {} credited but its record could not be re-read: {}", tx_id, e),
+        }
+        Ok(Some(vault))
+    }
+    
+    /// Pending deposits with their indexed signatures, oldest first
+    pub async fn get_pending_deposits(&self, limit: i64) -> Result<Vec<PendingDeposit>> {
+        self.transaction_repo.get_pending_deposits(limit).await
+    }
+    
+    /// Deposits awaiting on-chain confirmation; not part of any balance
+    pub async fn get_pending_credit(&self, vault_id: Uuid) -> Result<i64> {
+        self.transaction_repo.get_pending_credit(vault_id).await
+    }
+    
+    /// Balance held by a vault's operations not yet settled
+    pub async fn get_held_amounts(&self, vault_id: Uuid) -> Result<HeldAmounts> {
+        self.hold_repo.get_held_amounts(vault_id).await
+    }
+    
+    /// Stored balances net of the holds of operations not yet settled
+    pub async fn get_spendable_balance(&self, vault_id: Uuid) -> Result<SpendableBalance> {
+        let vault = self.vault_repo.get_vault_by_id(vault_id).await?;
+        let held = self.hold_repo.get_held_amounts(vault_id).await?;
+        Ok(SpendableBalance::new(vault.available_balance, vault.locked_balance, held))
+    }
+    
+    /// Holds of a vault's unsettled operations, oldest first
+    pub async fn get_active_holds(&self, vault_id: Uuid) -> Result<Vec<BalanceHold>> {
+        self.hold_repo.get_active_holds(vault_id).await
+    }
+    
+    /// Work currently queued for a vault
+    pub async fn get_pending_usage(&self, vault_id: Uuid) -> Result<PendingUsage> {
+        self.transaction_repo.get_pending_usage(vault_id).await
+    }
+    
+    /// Receive every balance change written through this manager
+    pub fn subscribe_balance_updates(&self) -> broadcast::Receiver<BalanceUpdate> {
+        self.balance_updates.subscribe()
+    }
+    
+    /// Transaction record tracking for operations driven through this manager
+    pub fn transaction_manager(&self) -> &TransactionManager {
+        &self.transaction_manager
+    }
+    
     /// Initialize a new vault in the database
     pub async fn create_vault(&self, request: VaultCreateRequest, 
                               vault_pubkey: Pubkey, 
@@ -33,14 +181,14 @@ impl VaultManager {
         
         // Validate pubkeys
         let user_pubkey = Pubkey::from_str(&request.user_pubkey)
-            .map_err(|_| VaultError::ValidationError("Invalid user pubkey".to_string()))?;
+            .map_err(|_| DomainError::Validation("Invalid user pubkey".to_string()))?;
         let authority = Pubkey::from_str(&request.authority)
-            .map_err(|_| VaultError::ValidationError("Invalid authority pubkey".to_string()))?;
+            .map_err(|_| DomainError::Validation("Invalid authority pubkey".to_string()))?;
         
         // Check if vault already exists
         match self.vault_repo.get_vault_by_user(&request.user_pubkey).await {
-            Ok(_) => return Err(VaultError::VaultAlreadyExists(request.user_pubkey)),
-            Err(VaultError::NotFound(_)) => {}, // This is expected - vault doesn't exist
+            Ok(_) => return Err(DomainError::VaultAlreadyExists(request.user_pubkey).into()),
+            Err(e) if e.is_not_found() => {}, // This is expected - vault doesn't exist
             Err(e) => return Err(e), // Propagate other errors
         }
         
@@ -71,17 +219,32 @@ impl VaultManager {
     pub async fn get_vault_by_user(&self, user_pubkey: &str) -> Result<Option<Vault>> {
         match self.vault_repo.get_vault_by_user(user_pubkey).await {
             Ok(vault) => Ok(Some(vault)),
-            Err(VaultError::NotFound(_)) => Ok(None),
+            Err(e) if e.is_not_found() => Ok(None),
             Err(e) => Err(e),
         }
     }
     
-    /// Get vault by vault pubkey
+    /// Get vault by vault pubkey, active or not
     pub async fn get_vault_by_pubkey(&self, vault_pubkey: &str) -> Result<Option<Vault>> {
-        // This would need to be implemented in VaultRepository
-        // For now, we'll use a workaround
-        let vaults = self.vault_repo.get_active_vaults(1000, 0).await?;
-        Ok(vaults.into_iter().find(|v| v.vault_pubkey == vault_pubkey))
+        match self.vault_repo.get_vault_by_vault_pubkey(vault_pubkey).await {
+            Ok(vault) => Ok(Some(vault)),
+            Err(e) if e.is_not_found() => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+    
+    /// Get vault by the address of its token account, active or not
+    pub async fn get_vault_by_token_account(&self, token_account_pubkey: &str) -> Result<Option<Vault>> {
+        match self.vault_repo.get_vault_by_token_account(token_account_pubkey).await {
+            Ok(vault) => Ok(Some(vault)),
+            Err(e) if e.is_not_found() => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+    
+    /// Version of a user's active vault, for conditional reads
+    pub async fn get_vault_version(&self, user_pubkey: &str) -> Result<VaultVersion> {
+        self.vault_repo.get_vault_version(user_pubkey).await
     }
     
     /// Get vault by ID
@@ -101,12 +264,13 @@ impl VaultManager {
         // Get current state for audit log
         let current = self.vault_repo.get_vault_by_id(vault_id).await?;
         
-        // Update vault balances
-        let updated_vault = self.vault_repo.update_vault_balances(
+        // Update vault balances, settling the holds they now account for
+        let updated_vault = self.vault_repo.apply_balances(
             vault_id,
             new_total,
             new_locked,
-            new_available
+            new_available,
+            tx_id,
         ).await?;
         
         // Log audit event
@@ -126,6 +290,9 @@ impl VaultManager {
             Some(serde_json::json!({"performed_by": performed_by}))
         ).await?;
         
+        // No subscribers is fine; send only fails when nobody is listening
+        let _ = self.balance_updates.send(BalanceUpdate::from_vault(&updated_vault, BalanceUpdateSource::Database));
+        
         Ok(updated_vault)
     }
     
@@ -150,6 +317,21 @@ impl VaultManager {
         self.vault_repo.get_active_vaults(limit as i32, offset as i32).await
     }
     
+    /// List vaults matching operational filters (balances, utilization, activity, mint)
+    pub async fn list_vaults(&self, filter: &VaultListFilter, limit: i64, offset: i64) -> Result<Vec<Vault>> {
+        self.vault_repo.list_vaults(filter, limit as i32, offset as i32).await
+    }
+    
+    /// Search vaults by pubkey prefix or label
+    pub async fn search_vaults(&self, query: &str, limit: i64) -> Result<Vec<Vault>> {
+        let query = query.trim();
+        if query.len() < 3 {
+            return Err(DomainError::Validation("Search query must be at least 3 characters".to_string()).into());
+        }
+        
+        self.vault_repo.search_vaults(query, limit as i32).await
+    }
+    
     /// Get total value locked across all vaults
     pub async fn get_total_value_locked(&self) -> Result<i64> {
         let stats = self.vault_repo.get_active_vaults(10000, 0).await?;
@@ -179,7 +361,7 @@ impl VaultManager {
         )
         .fetch_one(&self.vault_repo.pool)
         .await
-        .map_err(|e| VaultError::DatabaseError(format!("Failed to create balance snapshot: {}", e)))?;
+        .map_err(|e| StorageError::Query(format!("Failed to create balance snapshot: {}", e)))?;
         
         Ok(snapshot)
     }
@@ -189,16 +371,26 @@ impl VaultManager {
 pub struct TransactionManager {
     transaction_repo: TransactionRepository,
     audit_repo: AuditRepository,
+    transaction_updates: broadcast::Sender<TransactionRecord>,
 }
 
+/// Buffered transaction record changes per subscriber before it is considered lagged
+const TRANSACTION_UPDATE_CHANNEL_CAPACITY: usize = 1024;
+
 impl TransactionManager {
     pub fn new(pool: PgPool) -> Self {
         Self {
             transaction_repo: TransactionRepository::new(pool.clone()),
             audit_repo: AuditRepository::new(pool),
+            transaction_updates: broadcast::channel(TRANSACTION_UPDATE_CHANNEL_CAPACITY).0,
         }
     }
     
+    /// Receive every record created or updated through this manager
+    pub fn subscribe_transaction_updates(&self) -> broadcast::Receiver<TransactionRecord> {
+        self.transaction_updates.subscribe()
+    }
+    
     /// Create transaction record
     pub async fn create_transaction(&self, 
                                   vault_id: Uuid,
@@ -215,6 +407,44 @@ impl TransactionManager {
             idempotency_key.as_deref()
         ).await?;
         
+        self.record_created(tx, tx_type, tx_signature).await
+    }
+    
+    /// Create transaction record and place `hold`, unless `quota` is already used up
+    /// or the vault lacks the balance to hold
+    pub async fn create_transaction_within_quota(&self,
+                                                 vault_id: Uuid,
+                                                 tx_type: TransactionType,
+                                                 amount: i64,
+                                                 tx_signature: Option<String>,
+                                                 idempotency_key: Option<String>,
+                                                 quota: &PendingQuota,
+                                                 hold: Option<(HoldBalance, i64)>) -> Result<QueueOutcome> {
+        
+        let outcome = self.transaction_repo.create_transaction_within_quota(
+            vault_id,
+            &format!("{:?}", tx_type).to_lowercase(),
+            amount,
+            tx_signature.as_deref(),
+            idempotency_key.as_deref(),
+            quota,
+            hold,
+        ).await?;
+        
+        match outcome {
+            QueueOutcome::Queued(tx) => Ok(QueueOutcome::Queued(self.record_created(tx, tx_type, tx_signature).await?)),
+            refused => Ok(refused),
+        }
+    }
+    
+    /// Audit and broadcast a newly created record
+    async fn record_created(&self,
+                            tx: TransactionRecord,
+                            tx_type: TransactionType,
+                            tx_signature: Option<String>) -> Result<TransactionRecord> {
+        let vault_id = tx.vault_id;
+        let amount = tx.amount;
+        
         // Log audit event
         self.audit_repo.log_event(
             "transaction_created",
@@ -228,6 +458,7 @@ impl TransactionManager {
             None
         ).await?;
         
+        let _ = self.transaction_updates.send(tx.clone());
         Ok(tx)
     }
     
@@ -259,6 +490,7 @@ impl TransactionManager {
             ).await?;
         }
         
+        let _ = self.transaction_updates.send(tx.clone());
         Ok(tx)
     }
     
@@ -278,7 +510,7 @@ impl TransactionManager {
         )
         .fetch_all(&self.transaction_repo.pool)
         .await
-        .map_err(|e| VaultError::DatabaseError(format!("Failed to get pending transactions: {}", e)))?;
+        .map_err(|e| StorageError::Query(format!("Failed to get pending transactions: {}", e)))?;
         
         Ok(transactions)
     }
@@ -292,4 +524,91 @@ impl TransactionManager {
     pub async fn get_transaction_by_idempotency_key(&self, idempotency_key: &str) -> Result<Option<TransactionRecord>> {
         self.transaction_repo.get_transaction_by_idempotency_key(idempotency_key).await
     }
+    
+    /// Store stage timings on a transaction record
+    pub async fn record_stage_timings(&self, tx_id: Uuid, timings: &StageTimings) -> Result<()> {
+        self.transaction_repo.record_stage_timings(tx_id, timings.to_json()).await
+    }
+    
+    /// Store the signature and send attempts of a transaction the backend submitted
+    pub async fn record_submission(&self, tx_id: Uuid, submitted: &SubmittedTransaction) -> Result<()> {
+        self.transaction_repo.record_submission(tx_id, &submitted.signature, submitted.attempts as i32).await
+    }
+    
+    /// Per-stage latency percentiles over recent transaction records
+    pub async fn get_stage_latency(&self, since: DateTime<Utc>) -> Result<Vec<StageLatencyRow>> {
+        self.transaction_repo.get_stage_latency_percentiles(since).await
+    }
+    
+    /// Get transaction by ID
+    pub async fn get_transaction_by_id(&self, tx_id: Uuid) -> Result<TransactionRecord> {
+        self.transaction_repo.get_transaction_by_id(tx_id).await
+    }
+    
+    /// Confirmed records of a vault, oldest first
+    pub async fn get_confirmed_ledger(&self, vault_id: Uuid) -> Result<Vec<TransactionRecord>> {
+        self.transaction_repo.get_confirmed_ledger(vault_id).await
+    }
+    
+    /// Records of a vault carrying any of `signatures`
+    pub async fn get_vault_transactions_by_signatures(&self, vault_id: Uuid, signatures: &[String]) -> Result<Vec<TransactionRecord>> {
+        self.transaction_repo.get_vault_transactions_by_signatures(vault_id, signatures).await
+    }
+    
+    /// Queue position and confirmation estimate for a withdrawal belonging to `vault_id`
+    pub async fn get_withdrawal_status(&self, vault_id: Uuid, tx_id: Uuid) -> Result<WithdrawalQueueStatus> {
+        let tx = self.transaction_repo.get_transaction_by_id(tx_id).await?;
+        
+        // Don't reveal whether another vault's transaction exists
+        if tx.vault_id != vault_id || !matches!(tx.transaction_type, TransactionType::Withdraw) {
+            return Err(DomainError::NotFound(format!("withdrawal {}", tx_id)).into());
+        }
+        
+        let in_queue = matches!(tx.status, TransactionStatus::Pending | TransactionStatus::Processing);
+        let (queue_position, estimated_confirmation_at) = if in_queue {
+            let position = self.transaction_repo.get_queue_position(tx_id).await?;
+            
+            // Recent confirmation latency reflects current congestion and the fee we pay
+            let since = Utc::now() - chrono::Duration::seconds(ETA_LATENCY_WINDOW_SECONDS);
+            let avg_latency = self.transaction_repo
+                .get_average_confirmation_seconds("withdraw", since)
+                .await?
+                .unwrap_or(DEFAULT_CONFIRMATION_SECONDS);
+            
+            let eta = estimate_confirmation_seconds(position, avg_latency, SUBMISSION_CONCURRENCY);
+            (Some(position), Some(Utc::now() + chrono::Duration::seconds(eta)))
+        } else {
+            (None, None)
+        };
+        
+        Ok(WithdrawalQueueStatus {
+            transaction_id: tx.id,
+            vault_id: tx.vault_id,
+            amount: tx.amount,
+            status: tx.status,
+            queue_position,
+            estimated_confirmation_at,
+            signature: tx.tx_signature,
+            error_message: tx.error_message,
+            created_at: tx.created_at,
+            updated_at: tx.updated_at,
+        })
+    }
+}
+
+/// Lookback used to measure recent withdrawal confirmation latency
+const ETA_LATENCY_WINDOW_SECONDS: i64 = 900;
+
+/// Assumed confirmation latency when no withdrawal confirmed recently
+const DEFAULT_CONFIRMATION_SECONDS: f64 = 30.0;
+
+/// Withdrawals submitted in parallel (matches the default MAX_CONCURRENT_TRANSACTIONS)
+const SUBMISSION_CONCURRENCY: i64 = 5;
+
+/// Seconds until a withdrawal with `queue_position` others ahead of it should confirm.
+///
+/// The queue drains `concurrency` at a time, each batch taking roughly `avg_latency_secs`.
+pub fn estimate_confirmation_seconds(queue_position: i64, avg_latency_secs: f64, concurrency: i64) -> i64 {
+    let batches_ahead = queue_position.max(0) / concurrency.max(1);
+    ((batches_ahead + 1) as f64 * avg_latency_secs.max(0.0)).ceil() as i64
 }
\ No newline at end of file
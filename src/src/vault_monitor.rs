@@ -1,15 +1,33 @@
 use crate::error::{Result, VaultError};
-use crate::models::{Vault, BalanceSnapshot, SystemBalanceStats};
+use crate::models::{Vault, BalanceSnapshot, SystemBalanceStats, ReconciliationMode};
 use crate::vault_manager::VaultManager;
 use crate::balance_tracker::BalanceTracker;
+use crate::reconciliation::Reconciler;
+use crate::provisioning::VaultProvisioner;
+use crate::analytics::ActivityAnalytics;
+use crate::network_fees::NetworkFeeAnalytics;
+use crate::token_authority::TokenAuthorityGuard;
+use crate::outbox::OutboxDispatcher;
+use crate::indexer::{IndexerStatus, ProgramIndexer};
+use crate::abuse::AbuseGuard;
+use crate::security_events::{NewSecurityEvent, SecurityEventKind, SecurityEventLog};
+use crate::alerts::VaultAlerts;
+use crate::sla::{SlaTarget, SlaTracker};
 use crate::transaction_builder::{TransactionBuilder, TransactionSubmitter};
+use crate::rpc::RpcMethodClass;
+use crate::cluster::ClusterTiming;
+use crate::jobs::{self, ExpectedJob, JobOutcome, MonitorJobs};
 use crate::database::{VaultRepository, TransactionRepository, SnapshotRepository, AuditRepository};
 use chrono::{DateTime, Utc, Duration};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, Ordering};
 use tokio::time::interval;
 use tracing::{info, warn, error};
 use uuid::Uuid;
 
+/// Interval of the stale transaction cleanup pass
+const STALE_TRANSACTION_CLEANUP_INTERVAL_SECONDS: u64 = 300; // 5 minutes
+
 /// VaultMonitor provides real-time monitoring and alerting for the vault system
 pub struct VaultMonitor {
     pool: sqlx::PgPool,
@@ -21,15 +39,46 @@ pub struct VaultMonitor {
     balance_tracker: Arc<BalanceTracker>,
     transaction_builder: Arc<TransactionBuilder>,
     transaction_submitter: Arc<TransactionSubmitter>,
+    reconciler: Arc<Reconciler>,
+    provisioner: Arc<VaultProvisioner>,
+    analytics: Arc<ActivityAnalytics>,
+    network_fees: Arc<NetworkFeeAnalytics>,
+    token_authority_guard: Arc<TokenAuthorityGuard>,
+    outbox: Arc<OutboxDispatcher>,
+    indexer: Arc<ProgramIndexer>,
+    abuse: Arc<AbuseGuard>,
+    security_events: Arc<SecurityEventLog>,
+    alerts: Arc<VaultAlerts>,
+    sla: Arc<SlaTracker>,
+    cluster_timing: Arc<ClusterTiming>,
+    jobs: Arc<MonitorJobs>,
     
     // Configuration
     reconciliation_interval_seconds: u64,
+    standard_reconciliation_interval_seconds: u64,
+    deep_reconciliation_interval_seconds: u64,
+    deep_reconciliation_batch_size: i32,
+    ledger_reconciliation_interval_seconds: u64,
     health_check_interval_seconds: u64,
     stale_transaction_threshold_seconds: i64,
     max_pending_transactions: i64,
+    provisioning_repair_interval_seconds: u64,
+    activity_rollup_interval_seconds: u64,
+    cluster_poll_interval_seconds: u64,
+    token_authority_sweep_interval_seconds: u64,
+    outbox_delivery_interval_seconds: u64,
+    indexer_poll_interval_seconds: u64,
+    indexer_lag_alert_slots: u64,
+    snapshot_baseline_interval_seconds: u64,
+    abuse_escalation_interval_seconds: u64,
+    security_burst_interval_seconds: u64,
+    sla_check_interval_seconds: u64,
+    fee_collection_interval_seconds: u64,
     
     // Monitoring state
     last_reconciliation: Option<DateTime<Utc>>,
+    /// Offset of the next deep reconciliation batch; deep passes rotate through vaults
+    deep_reconciliation_cursor: AtomicI64,
     consecutive_failures: u32,
     is_healthy: Arc<tokio::sync::RwLock<bool>>,
 }
@@ -43,32 +92,100 @@ impl VaultMonitor {
         transaction_submitter: Arc<TransactionSubmitter>,
         config: MonitorConfig,
     ) -> Self {
+        let expected_jobs = vec![
+            ExpectedJob::new(jobs::reconciliation_job(ReconciliationMode::Quick), config.reconciliation_interval_seconds),
+            ExpectedJob::new(jobs::reconciliation_job(ReconciliationMode::Standard), config.standard_reconciliation_interval_seconds),
+            ExpectedJob::new(jobs::reconciliation_job(ReconciliationMode::Deep), config.deep_reconciliation_interval_seconds),
+            ExpectedJob::new(jobs::reconciliation_job(ReconciliationMode::Ledger), config.ledger_reconciliation_interval_seconds),
+            ExpectedJob::new(jobs::HEALTH_CHECK_JOB, config.health_check_interval_seconds),
+            ExpectedJob::new(jobs::STALE_TRANSACTION_CLEANUP_JOB, STALE_TRANSACTION_CLEANUP_INTERVAL_SECONDS),
+            ExpectedJob::new(jobs::BALANCE_SNAPSHOT_JOB, config.snapshot_baseline_interval_seconds),
+            ExpectedJob::new(jobs::PROVISIONING_REPAIR_JOB, config.provisioning_repair_interval_seconds),
+            ExpectedJob::new(jobs::ACTIVITY_ROLLUP_JOB, config.activity_rollup_interval_seconds),
+            ExpectedJob::new(jobs::CLUSTER_TIMING_JOB, config.cluster_poll_interval_seconds),
+            ExpectedJob::new(jobs::TOKEN_AUTHORITY_SWEEP_JOB, config.token_authority_sweep_interval_seconds),
+            ExpectedJob::new(jobs::OUTBOX_DELIVERY_JOB, config.outbox_delivery_interval_seconds),
+            ExpectedJob::new(jobs::INDEXER_JOB, config.indexer_poll_interval_seconds),
+            ExpectedJob::new(jobs::ABUSE_ESCALATION_JOB, config.abuse_escalation_interval_seconds),
+            ExpectedJob::new(jobs::SECURITY_BURST_JOB, config.security_burst_interval_seconds),
+            ExpectedJob::new(jobs::SLA_CHECK_JOB, config.sla_check_interval_seconds),
+            ExpectedJob::new(jobs::FEE_COLLECTION_JOB, config.fee_collection_interval_seconds),
+        ];
+        
         Self {
             pool: pool.clone(),
             vault_repo: VaultRepository::new(pool.clone()),
             transaction_repo: TransactionRepository::new(pool.clone()),
             snapshot_repo: SnapshotRepository::new(pool.clone()),
-            audit_repo: AuditRepository::new(pool),
+            audit_repo: AuditRepository::new(pool.clone()),
+            reconciler: Arc::new(Reconciler::new(pool.clone(), balance_tracker.clone(), transaction_builder.clone())),
+            provisioner: Arc::new(VaultProvisioner::new(pool.clone(), transaction_builder.clone(), transaction_submitter.clone())),
+            token_authority_guard: Arc::new(TokenAuthorityGuard::new(pool.clone(), transaction_builder.clone())),
+            outbox: Arc::new(OutboxDispatcher::new(pool.clone())),
+            indexer: Arc::new(ProgramIndexer::new(pool.clone(), transaction_builder.clone(), vault_manager.clone())),
+            abuse: Arc::new(AbuseGuard::new(pool.clone())),
+            security_events: Arc::new(SecurityEventLog::new(pool.clone())),
+            alerts: Arc::new(VaultAlerts::new(pool.clone())),
+            sla: Arc::new(SlaTracker::new(pool.clone(), config.sla_targets, config.sla_window_minutes)),
+            jobs: Arc::new(MonitorJobs::new(pool.clone(), expected_jobs)),
+            network_fees: Arc::new(NetworkFeeAnalytics::new(pool.clone())),
+            analytics: Arc::new(ActivityAnalytics::new(pool)),
+            cluster_timing: Arc::new(ClusterTiming::default()),
             vault_manager,
             balance_tracker,
             transaction_builder,
             transaction_submitter,
             reconciliation_interval_seconds: config.reconciliation_interval_seconds,
+            standard_reconciliation_interval_seconds: config.standard_reconciliation_interval_seconds,
+            deep_reconciliation_interval_seconds: config.deep_reconciliation_interval_seconds,
+            deep_reconciliation_batch_size: config.deep_reconciliation_batch_size,
+            ledger_reconciliation_interval_seconds: config.ledger_reconciliation_interval_seconds,
             health_check_interval_seconds: config.health_check_interval_seconds,
             stale_transaction_threshold_seconds: config.stale_transaction_threshold_seconds,
             max_pending_transactions: config.max_pending_transactions,
+            provisioning_repair_interval_seconds: config.provisioning_repair_interval_seconds,
+            activity_rollup_interval_seconds: config.activity_rollup_interval_seconds,
+            cluster_poll_interval_seconds: config.cluster_poll_interval_seconds,
+            token_authority_sweep_interval_seconds: config.token_authority_sweep_interval_seconds,
+            outbox_delivery_interval_seconds: config.outbox_delivery_interval_seconds,
+            indexer_poll_interval_seconds: config.indexer_poll_interval_seconds,
+            indexer_lag_alert_slots: config.indexer_lag_alert_slots,
+            snapshot_baseline_interval_seconds: config.snapshot_baseline_interval_seconds,
+            abuse_escalation_interval_seconds: config.abuse_escalation_interval_seconds,
+            security_burst_interval_seconds: config.security_burst_interval_seconds,
+            sla_check_interval_seconds: config.sla_check_interval_seconds,
+            fee_collection_interval_seconds: config.fee_collection_interval_seconds,
             last_reconciliation: None,
+            deep_reconciliation_cursor: AtomicI64::new(0),
             consecutive_failures: 0,
             is_healthy: Arc::new(tokio::sync::RwLock::new(true)),
         }
     }
     
+    /// Share cluster timing with the transaction submitter so both act on the same observations
+    pub fn with_cluster_timing(mut self, cluster_timing: Arc<ClusterTiming>) -> Self {
+        self.cluster_timing = cluster_timing;
+        self
+    }
+    
+    /// Share the security event log with the API so bans and bursts land in the same stream
+    pub fn with_security_events(mut self, security_events: Arc<SecurityEventLog>) -> Self {
+        self.security_events = security_events;
+        self
+    }
+    
     /// Start monitoring tasks
     pub async fn start_monitoring(&self) {
         info!("Starting vault monitoring services");
         
-        // Start reconciliation task
-        let reconciliation_handle = self.start_reconciliation_task();
+        // Start reconciliation tasks, one per mode
+        let reconciliation_handle = self.start_reconciliation_task(ReconciliationMode::Quick, self.reconciliation_interval_seconds);
+        let standard_reconciliation_handle = self.start_reconciliation_task(
+            ReconciliationMode::Standard, self.standard_reconciliation_interval_seconds);
+        let deep_reconciliation_handle = self.start_reconciliation_task(
+            ReconciliationMode::Deep, self.deep_reconciliation_interval_seconds);
+        let ledger_reconciliation_handle = self.start_reconciliation_task(
+            ReconciliationMode::Ledger, self.ledger_reconciliation_interval_seconds);
         
         // Start health check task
         let health_check_handle = self.start_health_check_task();
@@ -76,28 +193,72 @@ impl VaultMonitor {
         // Start stale transaction cleanup task
         let cleanup_handle = self.start_cleanup_task();
         
-        // Start balance snapshot task
+        // Start baseline balance snapshot task; changes are snapshotted as they happen
         let snapshot_handle = self.start_snapshot_task();
         
+        // Start repair of half-created vaults
+        let provisioning_handle = self.start_provisioning_repair_task();
+        
+        // Start activity rollup refresh
+        let activity_rollup_handle = self.start_activity_rollup_task();
+        
+        // Start epoch and slot timing observation
+        let cluster_timing_handle = self.start_cluster_timing_task();
+        
+        // Start sweep for delegates and close authorities on vault token accounts
+        let token_authority_handle = self.start_token_authority_sweep_task();
+        
+        // Start outbox delivery to webhook consumers
+        let outbox_handle = self.start_outbox_delivery_task();
+        
+        // Start program indexing with gap re-scans
+        let indexer_handle = self.start_indexer_task();
+        
+        // Start rate limit escalation rules and usage pruning
+        let abuse_handle = self.start_abuse_escalation_task();
+        
+        // Start alerting on bursts of security events
+        let security_burst_handle = self.start_security_burst_task();
+        
+        // Start alerting on operation classes below their latency SLA
+        let sla_handle = self.start_sla_check_task();
+        
+        // Start reading realized fees of submitted transactions
+        let fee_collection_handle = self.start_fee_collection_task();
+        
         // Wait for all tasks
         tokio::select! {
             _ = reconciliation_handle => warn!("Reconciliation task ended"),
+            _ = standard_reconciliation_handle => warn!("Standard reconciliation task ended"),
+            _ = deep_reconciliation_handle => warn!("Deep reconciliation task ended"),
+            _ = ledger_reconciliation_handle => warn!("Ledger reconciliation task ended"),
             _ = health_check_handle => warn!("Health check task ended"),
             _ = cleanup_handle => warn!("Cleanup task ended"),
             _ = snapshot_handle => warn!("Snapshot task ended"),
+            _ = provisioning_handle => warn!("Provisioning repair task ended"),
+            _ = activity_rollup_handle => warn!("Activity rollup task ended"),
+            _ = cluster_timing_handle => warn!("Cluster timing task ended"),
+            _ = token_authority_handle => warn!("Token authority sweep task ended"),
+            _ = outbox_handle => warn!("Outbox delivery task ended"),
+            _ = indexer_handle => warn!("Indexer task ended"),
+            _ = abuse_handle => warn!("Abuse escalation task ended"),
+            _ = security_burst_handle => warn!("Security burst check task ended"),
+            _ = sla_handle => warn!("SLA check task ended"),
+            _ = fee_collection_handle => warn!("Fee collection task ended"),
         }
     }
     
-    /// Start reconciliation task
-    fn start_reconciliation_task(&self) -> tokio::task::JoinHandle<()> {
+    /// Start reconciliation task for one mode
+    fn start_reconciliation_task(&self, mode: ReconciliationMode, interval_seconds: u64) -> tokio::task::JoinHandle<()> {
         let monitor = Arc::new(self);
-        let mut interval = interval(tokio::time::Duration::from_secs(self.reconciliation_interval_seconds));
+        let job_type = jobs::reconciliation_job(mode);
+        let mut interval = interval(tokio::time::Duration::from_secs(interval_seconds));
         
         tokio::spawn(async move {
             loop {
                 interval.tick().await;
                 
-                if let Err(e) = monitor.run_reconciliation().await {
+                if let Err(e) = monitor.jobs.track(&job_type, monitor.run_reconciliation(mode)).await {
                     error!("Reconciliation failed: {}", e);
                     monitor.increment_failures().await;
                 } else {
@@ -116,7 +277,7 @@ impl VaultMonitor {
             loop {
                 interval.tick().await;
                 
-                match monitor.run_health_check().await {
+                match monitor.jobs.track(jobs::HEALTH_CHECK_JOB, monitor.run_health_check()).await {
                     Ok(healthy) => {
                         monitor.set_health_status(healthy).await;
                         if !healthy {
@@ -135,72 +296,313 @@ impl VaultMonitor {
     /// Start cleanup task
     fn start_cleanup_task(&self) -> tokio::task::JoinHandle<()> {
         let monitor = Arc::new(self);
-        let mut interval = interval(tokio::time::Duration::from_secs(300)); // Every 5 minutes
+        let mut interval = interval(tokio::time::Duration::from_secs(STALE_TRANSACTION_CLEANUP_INTERVAL_SECONDS));
         
         tokio::spawn(async move {
             loop {
                 interval.tick().await;
                 
-                if let Err(e) = monitor.cleanup_stale_transactions().await {
+                if let Err(e) = monitor.jobs.track(jobs::STALE_TRANSACTION_CLEANUP_JOB, monitor.cleanup_stale_transactions()).await {
                     error!("Cleanup failed: {}", e);
                 }
             }
         })
     }
     
-    /// Start snapshot task
+    /// Start baseline snapshot task
     fn start_snapshot_task(&self) -> tokio::task::JoinHandle<()> {
         let monitor = Arc::new(self);
-        let mut interval = interval(tokio::time::Duration::from_secs(60)); // Every minute
+        let mut interval = interval(tokio::time::Duration::from_secs(self.snapshot_baseline_interval_seconds));
         
         tokio::spawn(async move {
             loop {
                 interval.tick().await;
                 
-                if let Err(e) = monitor.create_balance_snapshots().await {
+                if let Err(e) = monitor.jobs.track(jobs::BALANCE_SNAPSHOT_JOB, monitor.create_balance_snapshots()).await {
                     error!("Snapshot creation failed: {}", e);
                 }
             }
         })
     }
     
-    /// Run balance reconciliation
-    async fn run_reconciliation(&self) -> Result<()> {
-        info!("Running balance reconciliation");
-        
-        // Get all active vaults
-        let vaults = self.vault_repo.get_active_vaults(1000, 0).await?;
-        
-        let mut inconsistent_vaults = Vec::new();
-        let mut total_discrepancies = 0;
-        
-        for vault in vaults {
-            match self.balance_tracker.reconcile_balances(vault.id).await {
-                Ok(result) => {
-                    if !result.is_consistent {
-                        inconsistent_vaults.push((vault.id, result.discrepancies.len()));
-                        total_discrepancies += result.discrepancies.len();
-                        
-                        // Log critical discrepancies
-                        for discrepancy in &result.discrepancies {
-                            if discrepancy.severity == crate::balance_tracker::DiscrepancySeverity::Critical {
-                                error!("CRITICAL: Vault {} has critical discrepancy: {}", 
-                                       vault.id, discrepancy.issue);
-                            }
+    /// Start provisioning repair task
+    fn start_provisioning_repair_task(&self) -> tokio::task::JoinHandle<()> {
+        let provisioner = self.provisioner.clone();
+        let monitor_jobs = self.jobs.clone();
+        let mut interval = interval(tokio::time::Duration::from_secs(self.provisioning_repair_interval_seconds));
+        
+        tokio::spawn(async move {
+            loop {
+                interval.tick().await;
+                
+                if let Err(e) = monitor_jobs.track(jobs::PROVISIONING_REPAIR_JOB, provisioner.repair_incomplete()).await {
+                    error!("Vault provisioning repair failed: {}", e);
+                }
+            }
+        })
+    }
+    
+    /// Start activity rollup refresh task
+    fn start_activity_rollup_task(&self) -> tokio::task::JoinHandle<()> {
+        let analytics = self.analytics.clone();
+        let cluster_timing = self.cluster_timing.clone();
+        let monitor_jobs = self.jobs.clone();
+        let mut interval = interval(tokio::time::Duration::from_secs(self.activity_rollup_interval_seconds));
+        
+        tokio::spawn(async move {
+            loop {
+                interval.tick().await;
+                
+                if let Some(reason) = cluster_timing.maintenance_deferral() {
+                    info!("Deferring activity rollup refresh: {}", reason);
+                    monitor_jobs.record(jobs::ACTIVITY_ROLLUP_JOB, Utc::now(), std::time::Duration::ZERO, JobOutcome::Skipped, None).await;
+                    continue;
+                }
+                
+                if let Err(e) = monitor_jobs.track(jobs::ACTIVITY_ROLLUP_JOB, analytics.refresh_rollups()).await {
+                    error!("Activity rollup refresh failed: {}", e);
+                }
+            }
+        })
+    }
+    
+    /// Start cluster timing task, refreshing epoch position and slot times
+    fn start_cluster_timing_task(&self) -> tokio::task::JoinHandle<()> {
+        let cluster_timing = self.cluster_timing.clone();
+        let transaction_builder = self.transaction_builder.clone();
+        let monitor_jobs = self.jobs.clone();
+        let mut interval = interval(tokio::time::Duration::from_secs(self.cluster_poll_interval_seconds));
+        
+        tokio::spawn(async move {
+            loop {
+                interval.tick().await;
+                
+                if let Err(e) = monitor_jobs.track(jobs::CLUSTER_TIMING_JOB, cluster_timing.observe(transaction_builder.rpc())).await {
+                    warn!("Cluster timing observation failed: {}", e);
+                }
+            }
+        })
+    }
+    
+    /// Start token authority sweep task
+    fn start_token_authority_sweep_task(&self) -> tokio::task::JoinHandle<()> {
+        let guard = self.token_authority_guard.clone();
+        let monitor_jobs = self.jobs.clone();
+        let mut interval = interval(tokio::time::Duration::from_secs(self.token_authority_sweep_interval_seconds));
+        
+        tokio::spawn(async move {
+            loop {
+                interval.tick().await;
+                
+                match monitor_jobs.track(jobs::TOKEN_AUTHORITY_SWEEP_JOB, guard.sweep()).await {
+                    Ok(summary) if summary.exposed > 0 => {
+                        error!("Token authority sweep: {} of {} vault token accounts have a delegate or close authority",
+                               summary.exposed, summary.checked);
+                    }
+                    Ok(summary) => info!("Token authority sweep checked {} vaults ({} errors)", summary.checked, summary.errors),
+                    Err(e) => error!("Token authority sweep failed: {}", e),
+                }
+            }
+        })
+    }
+    
+    /// Start outbox delivery task; acknowledged events past retention are pruned hourly
+    fn start_outbox_delivery_task(&self) -> tokio::task::JoinHandle<()> {
+        let outbox = self.outbox.clone();
+        let monitor_jobs = self.jobs.clone();
+        let mut interval = interval(tokio::time::Duration::from_secs(self.outbox_delivery_interval_seconds));
+        
+        tokio::spawn(async move {
+            let mut last_pruned: Option<DateTime<Utc>> = None;
+            loop {
+                interval.tick().await;
+                
+                match monitor_jobs.track(jobs::OUTBOX_DELIVERY_JOB, outbox.deliver_due()).await {
+                    Ok(summary) if summary.failed > 0 => {
+                        warn!("Outbox delivered {} events to {} consumers; {} consumers failing",
+                              summary.delivered, summary.consumers, summary.failed);
+                    }
+                    Ok(summary) if summary.delivered > 0 => {
+                        info!("Outbox delivered {} events to {} consumers", summary.delivered, summary.consumers);
+                    }
+                    Ok(_) => {}
+                    Err(e) => error!("Outbox delivery failed: {}", e),
+                }
+                
+                let now = Utc::now();
+                if last_pruned.map_or(true, |at| now - at >= Duration::hours(1)) {
+                    match outbox.prune(now).await {
+                        Ok(pruned) if pruned > 0 => info!("Pruned {} delivered outbox events", pruned),
+                        Ok(_) => {}
+                        Err(e) => error!("Outbox pruning failed: {}", e),
+                    }
+                    last_pruned = Some(now);
+                }
+            }
+        })
+    }
+    
+    /// Start indexer task: poll program signatures, then re-scan open gaps
+    fn start_indexer_task(&self) -> tokio::task::JoinHandle<()> {
+        let indexer = self.indexer.clone();
+        let monitor_jobs = self.jobs.clone();
+        let mut interval = interval(tokio::time::Duration::from_secs(self.indexer_poll_interval_seconds));
+        
+        tokio::spawn(async move {
+            loop {
+                interval.tick().await;
+                
+                match monitor_jobs.track(jobs::INDEXER_JOB, indexer.run_pass()).await {
+                    Ok(summary) if summary.gaps_detected > 0 || summary.recovered > 0 => {
+                        warn!("Indexer: {} signatures, {} gaps detected, {} resolved, {} signatures recovered",
+                              summary.indexed, summary.gaps_detected, summary.gaps_resolved, summary.recovered);
+                    }
+                    Ok(summary) if summary.deposits_credited > 0 || summary.deposits_rejected > 0 => {
+                        info!("Indexer: {} deposits credited, {} rejected", summary.deposits_credited, summary.deposits_rejected);
+                    }
+                    Ok(_) => {}
+                    Err(e) => error!("Indexer pass failed: {}", e),
+                }
+            }
+        })
+    }
+    
+    /// Start abuse escalation task; rate limit usage past retention is pruned hourly
+    fn start_abuse_escalation_task(&self) -> tokio::task::JoinHandle<()> {
+        let abuse = self.abuse.clone();
+        let security_events = self.security_events.clone();
+        let monitor_jobs = self.jobs.clone();
+        let mut interval = interval(tokio::time::Duration::from_secs(self.abuse_escalation_interval_seconds));
+        
+        tokio::spawn(async move {
+            let mut last_pruned: Option<DateTime<Utc>> = None;
+            loop {
+                interval.tick().await;
+                
+                match monitor_jobs.track(jobs::ABUSE_ESCALATION_JOB, abuse.escalate()).await {
+                    Ok(bans) => {
+                        for ban in bans {
+                            security_events.record(
+                                NewSecurityEvent::new(SecurityEventKind::RateLimitBan, ban.client_id.clone())
+                                    .with_details(serde_json::json!({
+                                        "ban_id": ban.id,
+                                        "rule_id": ban.rule_id,
+                                        "reason": ban.reason,
+                                        "expires_at": ban.expires_at,
+                                    })),
+                            ).await;
                         }
                     }
+                    Err(e) => error!("Rate limit escalation failed: {}", e),
                 }
-                Err(e) => {
-                    error!("Failed to reconcile vault {}: {}", vault.id, e);
-                    inconsistent_vaults.push((vault.id, 1));
-                    total_discrepancies += 1;
+                
+                let now = Utc::now();
+                if last_pruned.map_or(true, |at| now - at >= Duration::hours(1)) {
+                    match abuse.prune(now).await {
+                        Ok(pruned) if pruned > 0 => info!("Pruned {} rate limit usage windows", pruned),
+                        Ok(_) => {}
+                        Err(e) => error!("Rate limit usage pruning failed: {}", e),
+                    }
+                    last_pruned = Some(now);
                 }
             }
+        })
+    }
+    
+    /// Start the security event burst check; events past retention are pruned hourly
+    fn start_security_burst_task(&self) -> tokio::task::JoinHandle<()> {
+        let security_events = self.security_events.clone();
+        let monitor_jobs = self.jobs.clone();
+        let mut interval = interval(tokio::time::Duration::from_secs(self.security_burst_interval_seconds));
+        
+        tokio::spawn(async move {
+            let mut last_pruned: Option<DateTime<Utc>> = None;
+            loop {
+                interval.tick().await;
+                
+                let now = Utc::now();
+                if let Err(e) = monitor_jobs.track(jobs::SECURITY_BURST_JOB, security_events.check_bursts(now)).await {
+                    error!("Security event burst check failed: {}", e);
+                }
+                
+                if last_pruned.map_or(true, |at| now - at >= Duration::hours(1)) {
+                    if let Err(e) = security_events.prune(now).await {
+                        error!("Security event pruning failed: {}", e);
+                    }
+                    last_pruned = Some(now);
+                }
+            }
+        })
+    }
+    
+    /// Start the SLA attainment check
+    fn start_sla_check_task(&self) -> tokio::task::JoinHandle<()> {
+        let sla = self.sla.clone();
+        let monitor_jobs = self.jobs.clone();
+        let mut interval = interval(tokio::time::Duration::from_secs(self.sla_check_interval_seconds));
+        
+        tokio::spawn(async move {
+            loop {
+                interval.tick().await;
+                
+                if let Err(e) = monitor_jobs.track(jobs::SLA_CHECK_JOB, sla.check_breaches(Utc::now())).await {
+                    error!("SLA check failed: {}", e);
+                }
+            }
+        })
+    }
+    
+    /// Start reading realized fees of finalized transactions
+    fn start_fee_collection_task(&self) -> tokio::task::JoinHandle<()> {
+        let network_fees = self.network_fees.clone();
+        let transaction_builder = self.transaction_builder.clone();
+        let cluster_timing = self.cluster_timing.clone();
+        let monitor_jobs = self.jobs.clone();
+        let mut interval = interval(tokio::time::Duration::from_secs(self.fee_collection_interval_seconds));
+        
+        tokio::spawn(async move {
+            loop {
+                interval.tick().await;
+                
+                if let Some(reason) = cluster_timing.maintenance_deferral() {
+                    info!("Deferring fee collection: {}", reason);
+                    monitor_jobs.record(jobs::FEE_COLLECTION_JOB, Utc::now(), std::time::Duration::ZERO, JobOutcome::Skipped, None).await;
+                    continue;
+                }
+                
+                let collection = network_fees.collect_realized_fees(&transaction_builder, Utc::now());
+                if let Err(e) = monitor_jobs.track(jobs::FEE_COLLECTION_JOB, collection).await {
+                    error!("Fee collection failed: {}", e);
+                }
+            }
+        })
+    }
+    
+    /// Run balance reconciliation at the given depth.
+    ///
+    /// Quick, standard and ledger passes cover every active vault; deep passes cover
+    /// one batch per run, rotating through the vault set.
+    async fn run_reconciliation(&self, mode: ReconciliationMode) -> Result<()> {
+        // Quick passes only compare the database with the cache; the rest load the RPC node
+        if mode != ReconciliationMode::Quick {
+            if let Some(reason) = self.cluster_timing.maintenance_deferral() {
+                info!("Deferring {} balance reconciliation: {}", mode.as_str(), reason);
+                return Ok(());
+            }
         }
         
-        if !inconsistent_vaults.is_empty() {
-            warn!("Balance reconciliation found {} inconsistent vaults with {} total discrepancies",
-                  inconsistent_vaults.len(), total_discrepancies);
+        info!("Running {} balance reconciliation", mode.as_str());
+        
+        let vaults = match mode {
+            ReconciliationMode::Deep => self.next_deep_reconciliation_batch().await?,
+            _ => self.vault_repo.get_active_vaults(1000, 0).await?,
+        };
+        
+        let summary = self.reconciler.reconcile_many(&vaults, mode).await;
+        
+        if summary.inconsistent_vaults > 0 || summary.errors > 0 {
+            warn!("Balance reconciliation found {} inconsistent vaults with {} total discrepancies ({} errors)",
+                  summary.inconsistent_vaults, summary.total_findings, summary.errors);
         } else {
             info!("Balance reconciliation completed successfully - all vaults consistent");
         }
@@ -209,6 +611,95 @@ impl VaultMonitor {
         Ok(())
     }
     
+    /// Next batch of vaults for deep reconciliation, wrapping to the start when exhausted
+    async fn next_deep_reconciliation_batch(&self) -> Result<Vec<Vault>> {
+        let batch_size = self.deep_reconciliation_batch_size;
+        let offset = self.deep_reconciliation_cursor.load(Ordering::SeqCst) as i32;
+        
+        let mut vaults = self.vault_repo.get_active_vaults(batch_size, offset).await?;
+        if vaults.is_empty() && offset > 0 {
+            vaults = self.vault_repo.get_active_vaults(batch_size, 0).await?;
+            self.deep_reconciliation_cursor.store(vaults.len() as i64, Ordering::SeqCst);
+        } else {
+            self.deep_reconciliation_cursor.fetch_add(vaults.len() as i64, Ordering::SeqCst);
+        }
+        
+        Ok(vaults)
+    }
+    
+    /// Reconciler used by scheduled passes, shared with on-demand API requests
+    pub fn reconciler(&self) -> Arc<Reconciler> {
+        self.reconciler.clone()
+    }
+    
+    /// Vault provisioner repaired by the monitor, shared with vault creation requests
+    pub fn provisioner(&self) -> Arc<VaultProvisioner> {
+        self.provisioner.clone()
+    }
+    
+    /// Activity analytics whose rollup the monitor keeps current
+    pub fn analytics(&self) -> Arc<ActivityAnalytics> {
+        self.analytics.clone()
+    }
+    
+    /// Realized fee analytics whose fees the monitor collects
+    pub fn network_fees(&self) -> Arc<NetworkFeeAnalytics> {
+        self.network_fees.clone()
+    }
+    
+    /// Token authority guard swept by the monitor, shared with admin requests
+    pub fn token_authority_guard(&self) -> Arc<TokenAuthorityGuard> {
+        self.token_authority_guard.clone()
+    }
+    
+    /// Outbox dispatcher driven by the monitor, shared with consumer requests
+    pub fn outbox(&self) -> Arc<OutboxDispatcher> {
+        self.outbox.clone()
+    }
+    
+    /// Rate limit analytics and bans; escalation rules are applied by the monitor
+    pub fn abuse(&self) -> Arc<AbuseGuard> {
+        self.abuse.clone()
+    }
+    
+    /// Security event log whose bursts the monitor alerts on
+    pub fn security_events(&self) -> Arc<SecurityEventLog> {
+        self.security_events.clone()
+    }
+    
+    /// Latency SLA targets and attainment; breaches are alerted by the monitor
+    pub fn sla(&self) -> Arc<SlaTracker> {
+        self.sla.clone()
+    }
+    
+    /// Vault alert rules; evaluated against the event stream by `VaultAlerts::run_stream_evaluator`
+    pub fn alerts(&self) -> Arc<VaultAlerts> {
+        self.alerts.clone()
+    }
+    
+    /// Run history of the monitor's jobs; its watchdog is started separately so it
+    /// keeps running if the monitor's own tasks stop
+    pub fn jobs(&self) -> Arc<MonitorJobs> {
+        self.jobs.clone()
+    }
+    
+    /// Program indexer driven by the monitor
+    pub fn indexer(&self) -> Arc<ProgramIndexer> {
+        self.indexer.clone()
+    }
+    
+    /// Indexer status with lag measured against the estimated cluster slot
+    pub async fn indexer_status(&self) -> Result<IndexerStatus> {
+        let now = Utc::now();
+        let head_slot = self.cluster_timing.current().map(|c| c.estimated_absolute_slot(now));
+        self.indexer.status(head_slot, now).await
+    }
+    
+    /// Cluster timing observed by the monitor
+    pub fn cluster_timing(&self) -> Arc<ClusterTiming> {
+        self.cluster_timing.clone()
+    }
+    
     /// Run health check
     async fn run_health_check(&self) -> Result<bool> {
         // Check database connection
@@ -223,11 +714,14 @@ impl VaultMonitor {
         // Check for critical issues
         let critical_issues = self.check_critical_issues().await?;
         
-        let overall_healthy = db_healthy && solana_healthy && pending_healthy && !critical_issues;
+        // Check the indexer is current and complete
+        let indexer_healthy = self.check_indexer().await?;
+        
+        let overall_healthy = db_healthy && solana_healthy && pending_healthy && !critical_issues && indexer_healthy;
         
         if !overall_healthy {
-            warn!("Health check failed: db={}, solana={}, pending={}, critical={}", 
-                  db_healthy, solana_healthy, pending_healthy, critical_issues);
+            warn!("Health check failed: db={}, solana={}, pending={}, critical={}, indexer={}", 
+                  db_healthy, solana_healthy, pending_healthy, critical_issues, indexer_healthy);
         }
         
         Ok(overall_healthy)
@@ -246,7 +740,7 @@ impl VaultMonitor {
     
     /// Check Solana connection
     async fn check_solana_connection(&self) -> Result<bool> {
-        match self.transaction_builder.rpc_client.get_health() {
+        match self.transaction_builder.rpc().call(RpcMethodClass::Read, |c| c.get_health()).await {
             Ok(_) => Ok(true),
             Err(e) => {
                 error!("Solana connection check failed: {}", e);
@@ -267,6 +761,15 @@ impl VaultMonitor {
         Ok(healthy)
     }
     
+    /// Check indexer lag, open gaps and never-indexed transactions
+    async fn check_indexer(&self) -> Result<bool> {
+        let alerts = self.indexer_status().await?.alerts(self.indexer_lag_alert_slots);
+        for alert in &alerts {
+            error!("Indexer alert: {}", alert);
+        }
+        Ok(alerts.is_empty())
+    }
+    
     /// Check for critical issues
     async fn check_critical_issues(&self) -> Result<bool> {
         // Check for vaults with negative balances
@@ -309,10 +812,16 @@ impl VaultMonitor {
         Ok(())
     }
     
-    /// Create balance snapshots for all active vaults
+    /// Baseline snapshot of all active vaults: rows for vaults that changed without
+    /// a snapshot being recorded, and for vaults not snapshotted within the interval
     async fn create_balance_snapshots(&self) -> Result<()> {
+        if let Some(reason) = self.cluster_timing.maintenance_deferral() {
+            info!("Deferring balance snapshots: {}", reason);
+            return Ok(());
+        }
+        
         // Get current block height
-        let block_height = match self.transaction_builder.rpc_client.get_block_height() {
+        let block_height = match self.transaction_builder.rpc().call(RpcMethodClass::Snapshot, |c| c.get_block_height()).await {
             Ok(height) => Some(height as i64),
             Err(e) => {
                 warn!("Failed to get block height: {}", e);
@@ -320,14 +829,8 @@ impl VaultMonitor {
             }
         };
         
-        // Get all active vaults
-        let vaults = self.vault_repo.get_active_vaults(1000, 0).await?;
-        
-        for vault in vaults {
-            if let Err(e) = self.balance_tracker.create_snapshot(vault.id, block_height).await {
-                error!("Failed to create snapshot for vault {}: {}", vault.id, e);
-            }
-        }
+        let baseline_after = Utc::now() - Duration::seconds(self.snapshot_baseline_interval_seconds as i64);
+        self.balance_tracker.snapshot_active_vaults(block_height, baseline_after).await?;
         
         Ok(())
     }
@@ -350,6 +853,8 @@ impl VaultMonitor {
         .fetch_one(&self.pool)
         .await?;
         
+        let indexer = self.indexer_status().await?;
+        
         Ok(MonitoringStats {
             vault_count: system_stats.vault_count,
             pending_transactions: pending_count,
@@ -358,6 +863,8 @@ impl VaultMonitor {
             is_healthy: self.get_health_status().await,
             last_reconciliation: self.last_reconciliation,
             consecutive_failures: self.consecutive_failures,
+            indexer_lag_slots: indexer.max_lag_slots,
+            indexer_open_gaps: indexer.open_gaps.len(),
         })
     }
     
@@ -387,19 +894,73 @@ impl VaultMonitor {
 
 #[derive(Debug, Clone)]
 pub struct MonitorConfig {
+    /// Interval of quick (DB vs cache) reconciliation
     pub reconciliation_interval_seconds: u64,
+    /// Interval of standard (plus on-chain vault account) reconciliation
+    pub standard_reconciliation_interval_seconds: u64,
+    /// Interval of deep reconciliation; each run covers `deep_reconciliation_batch_size` vaults
+    pub deep_reconciliation_interval_seconds: u64,
+    pub deep_reconciliation_batch_size: i32,
+    /// Interval of ledger reconciliation (vault row and snapshots vs confirmed records)
+    pub ledger_reconciliation_interval_seconds: u64,
     pub health_check_interval_seconds: u64,
     pub stale_transaction_threshold_seconds: i64,
     pub max_pending_transactions: i64,
+    /// Interval of the pass that resumes half-created vaults
+    pub provisioning_repair_interval_seconds: u64,
+    /// Interval at which new ledger activity is folded into the analytics rollup
+    pub activity_rollup_interval_seconds: u64,
+    /// Interval at which epoch info and performance samples are fetched
+    pub cluster_poll_interval_seconds: u64,
+    /// Interval of the check for delegates and close authorities on vault token accounts
+    pub token_authority_sweep_interval_seconds: u64,
+    /// Interval at which due webhook consumers are sent their pending events
+    pub outbox_delivery_interval_seconds: u64,
+    /// Interval of the indexer's poll and gap re-scan
+    pub indexer_poll_interval_seconds: u64,
+    /// Indexer lag, in slots, past which the health check alerts
+    pub indexer_lag_alert_slots: u64,
+    /// Interval of the baseline snapshot pass; unchanged vaults get one snapshot per interval
+    pub snapshot_baseline_interval_seconds: u64,
+    /// Interval at which rate limit escalation rules are applied
+    pub abuse_escalation_interval_seconds: u64,
+    /// Interval at which security event burst rules are applied
+    pub security_burst_interval_seconds: u64,
+    /// Target latency per operation class
+    pub sla_targets: Vec<SlaTarget>,
+    /// Rolling window SLA breaches are judged over
+    pub sla_window_minutes: i64,
+    /// Interval of the SLA breach check
+    pub sla_check_interval_seconds: u64,
+    /// Interval at which realized fees of finalized transactions are read
+    pub fee_collection_interval_seconds: u64,
 }
 
 impl Default for MonitorConfig {
     fn default() -> Self {
         Self {
             reconciliation_interval_seconds: 300, // 5 minutes
+            standard_reconciliation_interval_seconds: 3600, // 1 hour
+            deep_reconciliation_interval_seconds: 900, // 15 minutes, one batch each
+            deep_reconciliation_batch_size: 50,
+            ledger_reconciliation_interval_seconds: 3600, // 1 hour
             health_check_interval_seconds: 30,    // 30 seconds
             stale_transaction_threshold_seconds: 3600, // 1 hour
             max_pending_transactions: 100,
+            provisioning_repair_interval_seconds: 60,
+            activity_rollup_interval_seconds: 300, // 5 minutes
+            cluster_poll_interval_seconds: 30,
+            token_authority_sweep_interval_seconds: 600, // 10 minutes
+            outbox_delivery_interval_seconds: 5,
+            indexer_poll_interval_seconds: 10,
+            indexer_lag_alert_slots: 150, // ~1 minute
+            snapshot_baseline_interval_seconds: 21600, // 6 hours
+            abuse_escalation_interval_seconds: 60,
+            security_burst_interval_seconds: 60,
+            sla_targets: crate::sla::default_sla_targets(crate::sla::DEFAULT_SLA_OBJECTIVE_PERCENT),
+            sla_window_minutes: 60,
+            sla_check_interval_seconds: 300, // 5 minutes
+            fee_collection_interval_seconds: 60,
         }
     }
 }
@@ -413,4 +974,7 @@ pub struct MonitoringStats {
     pub is_healthy: bool,
     pub last_reconciliation: Option<DateTime<Utc>>,
     pub consecutive_failures: u32,
+    /// Largest lag of any indexed program; `None` before the cluster slot is observed
+    pub indexer_lag_slots: Option<u64>,
+    pub indexer_open_gaps: usize,
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 use collateral_vault_backend::{
     VaultManager, TransactionManager, BalanceTracker, TransactionBuilder, TransactionSubmitter,
-    CPIManager, VaultMonitor, MonitorConfig, models::*, error::*, database::*, api,
+    CPIManager, VaultMonitor, MonitorConfig, MintRegistry, SchemaManager, SupportService, CaseService, MultisigManager, AuthorityKeyRing, AuthorityRotationManager, EventStream, MaintenanceService, BulkOperationManager, screening::{ScreeningService, ScreeningPolicy}, auto_lock::AutoLockService, positions::UnlockGuard, identities::{IdentityService, IdentityPolicy}, exports::ExportService, idempotency::IdempotencyService, dormancy::{DormancyService, DormancyPolicy}, fees::FeeService, security_events::SecurityEventLog, incident::{IncidentManager, IncidentPolicy}, rpc::BudgetedRpcClient, models::*, error::*, database::*, api,
 };
 use axum::{
     body::Body,
@@ -35,31 +35,44 @@ mod api_integration_tests {
         let payer_keypair = Arc::new(solana_sdk::signature::Keypair::new());
         let authority_keypair = Arc::new(solana_sdk::signature::Keypair::new());
         
+        let program_id = solana_sdk::pubkey::Pubkey::new_unique();
         let transaction_builder = Arc::new(TransactionBuilder::new(
             "https://api.testnet.solana.com",
             payer_keypair,
-            solana_sdk::pubkey::Pubkey::new_unique(),
+            program_id,
             5,
         ).expect("Failed to create transaction builder"));
         
         let transaction_submitter = Arc::new(TransactionSubmitter::new(
-            std::sync::Arc::new(solana_client::rpc_client::RpcClient::new("https://api.testnet.solana.com")),
+            std::sync::Arc::new(BudgetedRpcClient::new(
+                solana_client::rpc_client::RpcClient::new("https://api.testnet.solana.com"),
+                transaction_builder.rpc().budget(),
+            )),
             3,
             1000,
         ));
         
+        let authority_rotation = Arc::new(AuthorityRotationManager::new(
+            pool.clone(),
+            Arc::new(AuthorityKeyRing::new(authority_keypair.clone())),
+            transaction_builder.clone(),
+            transaction_submitter.clone(),
+        ));
+        
         let cpi_manager = Arc::new(CPIManager::new(
             vault_manager.clone(),
             transaction_builder.clone(),
             transaction_submitter.clone(),
             authority_keypair,
-        ));
+        ).with_authority_rotation(authority_rotation.clone()));
+        let auto_lock = Arc::new(AutoLockService::new(pool.clone(), vault_manager.clone(), cpi_manager.clone()));
         
         let monitor_config = MonitorConfig {
             reconciliation_interval_seconds: 60,
             health_check_interval_seconds: 30,
             stale_transaction_threshold_seconds: 3600,
             max_pending_transactions: 100,
+            ..MonitorConfig::default()
         };
         
         let monitor = Arc::new(VaultMonitor::new(
@@ -72,6 +85,25 @@ mod api_integration_tests {
         ));
         
         let rate_limit_repo = Arc::new(RateLimitRepository::new(pool.clone()));
+        let mint_registry = Arc::new(MintRegistry::new(pool.clone(), "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB".to_string()));
+        let schema_manager = Arc::new(SchemaManager::new(pool.clone()));
+        let support_service = Arc::new(SupportService::new(pool.clone()));
+        let case_service = Arc::new(CaseService::new(pool.clone()));
+        let multisig_manager = Arc::new(MultisigManager::new(
+            pool.clone(),
+            vault_manager.clone(),
+            transaction_builder.clone(),
+        ));
+        let bulk_operations = Arc::new(BulkOperationManager::new(pool.clone(), vault_manager.clone()));
+        let maintenance = Arc::new(MaintenanceService::new(pool.clone()));
+        let incidents = Arc::new(IncidentManager::new(
+            pool.clone(),
+            vault_manager.clone(),
+            maintenance.clone(),
+            monitor.reconciler(),
+            cpi_manager.clone(),
+            IncidentPolicy::default(),
+        ));
         
         // Create app state
         let app_state = api::AppState {
@@ -81,6 +113,28 @@ mod api_integration_tests {
             cpi_manager,
             monitor,
             rate_limit_repo,
+            mint_registry: mint_registry.clone(),
+            schema_manager,
+            support_service,
+            case_service,
+            multisig_manager,
+            authority_rotation,
+            event_stream: Arc::new(EventStream::new()),
+            maintenance,
+            bulk_operations,
+            incidents,
+            transaction_builder: transaction_builder.clone(),
+            program_id,
+            program_idl: None,
+            screening: Arc::new(ScreeningService::new(pool.clone(), ScreeningPolicy::default())),
+            auto_lock,
+            unlock_guard: Arc::new(UnlockGuard::new(false)),
+            identities: Arc::new(IdentityService::new(pool.clone(), IdentityPolicy::default())),
+            exports: Arc::new(ExportService::new(pool.clone(), "exports".to_string(), chrono::Duration::hours(1))),
+            idempotency: Arc::new(IdempotencyService::new(pool.clone())),
+            dormancy: Arc::new(DormancyService::new(pool.clone(), transaction_builder, DormancyPolicy::new(12, 30).unwrap())),
+            fees: Arc::new(FeeService::new(pool.clone(), mint_registry)),
+            security_events: Arc::new(SecurityEventLog::new(pool.clone())),
         };
         
         (api::create_router(app_state), pool)
@@ -247,7 +301,7 @@ mod api_integration_tests {
         // Now test deposit
         let deposit_request = json!({
             "amount": 1000000,
-            "user_token_account": "test_user_token_account",
+            "signature": "1".repeat(64),
             "idempotency_key": "test_deposit_idempotent"
         });
         
@@ -425,4 +479,121 @@ mod api_integration_tests {
         assert!(response.status() == StatusCode::SWITCHING_PROTOCOLS || 
                 response.status() == StatusCode::BAD_REQUEST);
     }
-}
\ No newline at end of file
+    
+    #[tokio::test]
+    async fn test_list_vaults_with_filters() {
+        let (app, _pool) = setup_test_app().await;
+        
+        let response = app
+            .oneshot(Request::builder()
+                .uri("/vaults?is_active=true&min_locked_balance=1&last_activity_before=2020-01-01T00:00:00Z")
+                .body(Body::empty())
+                .unwrap())
+            .await
+            .unwrap();
+        
+        assert_eq!(response.status(), StatusCode::OK);
+        
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body_json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        
+        assert!(body_json.is_array());
+    }
+    
+    #[tokio::test]
+    async fn test_search_vaults_rejects_short_query() {
+        let (app, _pool) = setup_test_app().await;
+        
+        let response = app
+            .oneshot(Request::builder()
+                .uri("/vaults/search?q=ab")
+                .body(Body::empty())
+                .unwrap())
+            .await
+            .unwrap();
+        
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+    
+    #[tokio::test]
+    async fn test_list_mints_includes_default_mint() {
+        let (app, _pool) = setup_test_app().await;
+        
+        let response = app
+            .oneshot(Request::builder()
+                .uri("/mints")
+                .body(Body::empty())
+                .unwrap())
+            .await
+            .unwrap();
+        
+        assert_eq!(response.status(), StatusCode::OK);
+        
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body_json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        
+        let mints = body_json.as_array().unwrap();
+        assert!(mints.iter().any(|m| m["symbol"] == "USDT" && m["decimals"] == 6));
+    }
+    
+    #[tokio::test]
+    async fn test_withdrawal_status_unknown_transaction() {
+        let (app, _pool) = setup_test_app().await;
+        
+        // Create a vault so the lookup reaches the withdrawal itself
+        let _ = app.clone()
+            .oneshot(Request::builder()
+                .method("POST")
+                .uri("/vaults")
+                .header("content-type", "application/json")
+                .body(Body::from(json!({
+                    "user_pubkey": "11111111111111111111111111111111",
+                    "authority_pubkey": "11111111111111111111111111111111"
+                }).to_string()))
+                .unwrap())
+            .await
+            .unwrap();
+        
+        let response = app
+            .oneshot(Request::builder()
+                .uri(format!("/vaults/11111111111111111111111111111111/withdrawals/{}", uuid::Uuid::new_v4()))
+                .body(Body::empty())
+                .unwrap())
+            .await
+            .unwrap();
+        
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+    
+    #[tokio::test]
+    async fn test_derive_vault_endpoint() {
+        let (app, _pool) = setup_test_app().await;
+        
+        let response = app.clone()
+            .oneshot(Request::builder()
+                .uri("/derive/vault/11111111111111111111111111111111")
+                .body(Body::empty())
+                .unwrap())
+            .await
+            .unwrap();
+        
+        assert_eq!(response.status(), StatusCode::OK);
+        
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body_json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        
+        assert!(body_json["vault_pda"].is_string());
+        assert!(body_json["token_pda"].is_string());
+        assert!(body_json["vault_bump"].is_number());
+        
+        let response = app
+            .oneshot(Request::builder()
+                .uri("/derive/vault/not-a-pubkey")
+                .body(Body::empty())
+                .unwrap())
+            .await
+            .unwrap();
+        
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
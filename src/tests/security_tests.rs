@@ -1,6 +1,6 @@
 use collateral_vault_backend::{
     VaultManager, TransactionManager, BalanceTracker, TransactionBuilder, TransactionSubmitter,
-    CPIManager, VaultMonitor, MonitorConfig, models::*, error::*, database::*, api,
+    CPIManager, VaultMonitor, MonitorConfig, MintRegistry, SchemaManager, SupportService, CaseService, MultisigManager, AuthorityKeyRing, AuthorityRotationManager, EventStream, MaintenanceService, BulkOperationManager, screening::{ScreeningService, ScreeningPolicy}, auto_lock::AutoLockService, positions::UnlockGuard, identities::{IdentityService, IdentityPolicy}, exports::ExportService, idempotency::IdempotencyService, dormancy::{DormancyService, DormancyPolicy}, fees::FeeService, security_events::SecurityEventLog, incident::{IncidentManager, IncidentPolicy}, rpc::BudgetedRpcClient, support::StaffRole, models::*, error::*, database::*, api,
 };
 use axum::{
     body::Body,
@@ -35,31 +35,44 @@ mod security_tests {
         let payer_keypair = Arc::new(solana_sdk::signature::Keypair::new());
         let authority_keypair = Arc::new(solana_sdk::signature::Keypair::new());
         
+        let program_id = solana_sdk::pubkey::Pubkey::new_unique();
         let transaction_builder = Arc::new(TransactionBuilder::new(
             "https://api.testnet.solana.com",
             payer_keypair,
-            solana_sdk::pubkey::Pubkey::new_unique(),
+            program_id,
             5,
         ).expect("Failed to create transaction builder"));
         
         let transaction_submitter = Arc::new(TransactionSubmitter::new(
-            std::sync::Arc::new(solana_client::rpc_client::RpcClient::new("https://api.testnet.solana.com")),
+            std::sync::Arc::new(BudgetedRpcClient::new(
+                solana_client::rpc_client::RpcClient::new("https://api.testnet.solana.com"),
+                transaction_builder.rpc().budget(),
+            )),
             3,
             1000,
         ));
         
+        let authority_rotation = Arc::new(AuthorityRotationManager::new(
+            pool.clone(),
+            Arc::new(AuthorityKeyRing::new(authority_keypair.clone())),
+            transaction_builder.clone(),
+            transaction_submitter.clone(),
+        ));
+        
         let cpi_manager = Arc::new(CPIManager::new(
             vault_manager.clone(),
             transaction_builder.clone(),
             transaction_submitter.clone(),
             authority_keypair,
-        ));
+        ).with_authority_rotation(authority_rotation.clone()));
+        let auto_lock = Arc::new(AutoLockService::new(pool.clone(), vault_manager.clone(), cpi_manager.clone()));
         
         let monitor_config = MonitorConfig {
             reconciliation_interval_seconds: 60,
             health_check_interval_seconds: 30,
             stale_transaction_threshold_seconds: 3600,
             max_pending_transactions: 100,
+            ..MonitorConfig::default()
         };
         
         let monitor = Arc::new(VaultMonitor::new(
@@ -72,6 +85,25 @@ mod security_tests {
         ));
         
         let rate_limit_repo = Arc::new(RateLimitRepository::new(pool.clone()));
+        let mint_registry = Arc::new(MintRegistry::new(pool.clone(), "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB".to_string()));
+        let schema_manager = Arc::new(SchemaManager::new(pool.clone()));
+        let support_service = Arc::new(SupportService::new(pool.clone()));
+        let case_service = Arc::new(CaseService::new(pool.clone()));
+        let multisig_manager = Arc::new(MultisigManager::new(
+            pool.clone(),
+            vault_manager.clone(),
+            transaction_builder.clone(),
+        ));
+        let bulk_operations = Arc::new(BulkOperationManager::new(pool.clone(), vault_manager.clone()));
+        let maintenance = Arc::new(MaintenanceService::new(pool.clone()));
+        let incidents = Arc::new(IncidentManager::new(
+            pool.clone(),
+            vault_manager.clone(),
+            maintenance.clone(),
+            monitor.reconciler(),
+            cpi_manager.clone(),
+            IncidentPolicy::default(),
+        ));
         
         // Create app state
         let app_state = api::AppState {
@@ -81,6 +113,28 @@ mod security_tests {
             cpi_manager,
             monitor,
             rate_limit_repo,
+            mint_registry: mint_registry.clone(),
+            schema_manager,
+            support_service,
+            case_service,
+            multisig_manager,
+            authority_rotation,
+            event_stream: Arc::new(EventStream::new()),
+            maintenance,
+            bulk_operations,
+            incidents,
+            transaction_builder: transaction_builder.clone(),
+            program_id,
+            program_idl: None,
+            screening: Arc::new(ScreeningService::new(pool.clone(), ScreeningPolicy::default())),
+            auto_lock,
+            unlock_guard: Arc::new(UnlockGuard::new(false)),
+            identities: Arc::new(IdentityService::new(pool.clone(), IdentityPolicy::default())),
+            exports: Arc::new(ExportService::new(pool.clone(), "exports".to_string(), chrono::Duration::hours(1))),
+            idempotency: Arc::new(IdempotencyService::new(pool.clone())),
+            dormancy: Arc::new(DormancyService::new(pool.clone(), transaction_builder, DormancyPolicy::new(12, 30).unwrap())),
+            fees: Arc::new(FeeService::new(pool.clone(), mint_registry)),
+            security_events: Arc::new(SecurityEventLog::new(pool.clone())),
         };
         
         (api::create_router(app_state), pool)
@@ -298,7 +352,7 @@ mod security_tests {
         let idempotency_key = "test_idempotency_security";
         let request_body = json!({
             "amount": 1000000,
-            "user_token_account": "test_token_account",
+            "signature": "1".repeat(64),
             "idempotency_key": idempotency_key
         });
         
@@ -369,6 +423,142 @@ mod security_tests {
                     response.status() == StatusCode::TOO_MANY_REQUESTS);
         }
     }
+    
+    #[tokio::test]
+    async fn test_support_credentials_cannot_mutate() {
+        let (app, pool) = setup_test_app().await;
+        let (_, token) = SupportService::new(pool).issue_credential("support-test", StaffRole::Support).await.unwrap();
+        
+        let response = app
+            .oneshot(Request::builder()
+                .method("POST")
+                .uri("/vaults/any_user/withdraw")
+                .header("Authorization", format!("Bearer {}", token))
+                .header("content-type", "application/json")
+                .body(Body::from(json!({"amount": 1}).to_string()))
+                .unwrap())
+            .await
+            .unwrap();
+        
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+    
+    #[tokio::test]
+    async fn test_support_view_requires_reason_and_logs_access() {
+        let (app, pool) = setup_test_app().await;
+        let support_service = SupportService::new(pool.clone());
+        let (_, token) = support_service.issue_credential("support-audit-test", StaffRole::Support).await.unwrap();
+        VaultRepository::new(pool.clone())
+            .create_vault("support_view_user", "support_view_vault", "support_view_token")
+            .await
+            .unwrap();
+        
+        // No reason code: refused
+        let response = app
+            .clone()
+            .oneshot(Request::builder()
+                .uri("/support/vaults/support_view_user")
+                .header("Authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        
+        let response = app
+            .oneshot(Request::builder()
+                .uri("/support/vaults/support_view_user")
+                .header("Authorization", format!("Bearer {}", token))
+                .header("X-Support-Reason", "customer_ticket")
+                .header("X-Support-Reference", "TICKET-42")
+                .body(Body::empty())
+                .unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        
+        let vault = VaultRepository::new(pool.clone()).get_vault_by_user("support_view_user").await.unwrap();
+        let events = AuditRepository::new(pool).get_vault_events(vault.id, 10).await.unwrap();
+        let access = events.iter().find(|e| e.event_type == "support_access").expect("support access logged");
+        assert_eq!(access.details.as_ref().unwrap()["reason"], "customer_ticket");
+        assert_eq!(access.details.as_ref().unwrap()["reference"], "TICKET-42");
+    }
+    
+    #[tokio::test]
+    async fn test_case_writes_require_operations_role_and_are_audited() {
+        let (app, pool) = setup_test_app().await;
+        let support_service = SupportService::new(pool.clone());
+        let (_, support_token) = support_service.issue_credential("case-support", StaffRole::Support).await.unwrap();
+        let (_, ops_token) = support_service.issue_credential("case-ops", StaffRole::Operations).await.unwrap();
+        VaultRepository::new(pool.clone())
+            .create_vault("case_user", "case_vault", "case_token")
+            .await
+            .unwrap();
+        
+        let tag_request = |token: &str| Request::builder()
+            .method("PUT")
+            .uri("/ops/vaults/case_user/tags/VIP")
+            .header("Authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+        
+        let response = app.clone().oneshot(tag_request(&support_token)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        
+        let response = app.clone().oneshot(tag_request(&ops_token)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        
+        let response = app
+            .oneshot(Request::builder()
+                .uri("/vaults?tag=vip")
+                .body(Body::empty())
+                .unwrap())
+            .await
+            .unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let vaults: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(vaults.as_array().unwrap().iter().any(|v| v["user_pubkey"] == "case_user"));
+        
+        let vault = VaultRepository::new(pool.clone()).get_vault_by_user("case_user").await.unwrap();
+        let events = AuditRepository::new(pool).get_vault_events(vault.id, 10).await.unwrap();
+        let tagged = events.iter().find(|e| e.event_type == "vault_tag_added").expect("tag write audited");
+        assert_eq!(tagged.details.as_ref().unwrap()["credential_name"], "case-ops");
+    }
+    
+    #[tokio::test]
+    async fn test_refused_credentials_become_security_events() {
+        let (app, pool) = setup_test_app().await;
+        let support_service = SupportService::new(pool.clone());
+        let (_, support_token) = support_service.issue_credential("events-support", StaffRole::Support).await.unwrap();
+        let (revoked, revoked_token) = support_service.issue_credential("events-revoked", StaffRole::Operations).await.unwrap();
+        support_service.revoke_credential(revoked.id).await.unwrap();
+        let (_, ops_token) = support_service.issue_credential("events-ops", StaffRole::Operations).await.unwrap();
+        // A fresh client address keeps other runs' events out of the listing
+        let address = format!("2001:db8::{:x}", Uuid::new_v4().as_u128() as u16);
+        
+        let list_request = |token: &str| Request::builder()
+            .uri(format!("/admin/security-events?source=ip:{}", address))
+            .header("Authorization", format!("Bearer {}", token))
+            .header("X-Forwarded-For", address.as_str())
+            .body(Body::empty())
+            .unwrap();
+        
+        for token in [&support_token, &revoked_token] {
+            let response = app.clone().oneshot(list_request(token)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        }
+        
+        let response = app.oneshot(list_request(&ops_token)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let events: Vec<SecurityEvent> = serde_json::from_slice(&body).unwrap();
+        
+        let scope = events.iter().find(|e| e.event_type == "scope_violation").expect("support credential on ops endpoint recorded");
+        assert_eq!(scope.severity, "warning");
+        let revoked = events.iter().find(|e| e.event_type == "credential_revoked").expect("revoked credential use recorded");
+        assert_eq!(revoked.severity, "critical");
+        assert!(!revoked.details.to_string().contains(&revoked_token));
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,137 @@
+//! Program error codes, numbered from Anchor's custom error offset (6000)
+
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum VaultError {
+    #[msg("Vault is inactive")]
+    VaultInactive,
+    #[msg("Insufficient available balance")]
+    InsufficientAvailableBalance,
+    #[msg("Insufficient locked balance")]
+    InsufficientLockedBalance,
+    #[msg("Invalid amount")]
+    InvalidAmount,
+    #[msg("Unauthorized caller - only authorized programs can call this function")]
+    UnauthorizedCaller,
+    #[msg("Math overflow occurred")]
+    Overflow,
+    #[msg("Math underflow occurred")]
+    Underflow,
+    #[msg("Vault invariant violated - balances don't add up")]
+    InvariantViolated,
+    #[msg("Amount exceeds the per-transaction limit - admin approval required")]
+    AmountExceedsLimit,
+    #[msg("Signer is not the config admin")]
+    UnauthorizedAdmin,
+    #[msg("Collateral mint tokens can never be recovered")]
+    CollateralNotRecoverable,
+    #[msg("New authority is the same as the current authority")]
+    AuthorityUnchanged,
+    #[msg("Vault token account has a delegate or close authority set")]
+    TokenAccountDelegated,
+    #[msg("Vault token account has no delegate to revoke")]
+    NoDelegateToRevoke,
+    #[msg("Vault account already has the current layout")]
+    VaultLayoutCurrent,
+    #[msg("Vault has not been inactive long enough to be swept")]
+    VaultNotDormant,
+    #[msg("Vault has locked collateral")]
+    VaultHasLockedCollateral,
+    #[msg("Lock would exceed the configured ratio of locked to total balance")]
+    LockRatioExceeded,
+    #[msg("Lock ratio must be at most 10000 basis points")]
+    InvalidLockRatio,
+    #[msg("Program config already has the current layout")]
+    ConfigLayoutCurrent,
+    #[msg("Token account is not the vault's collateral account")]
+    TokenAccountMismatch,
+    #[msg("Source and destination vault must differ")]
+    SameVault,
+    #[msg("Vault still holds collateral")]
+    VaultNotEmpty,
+    #[msg("Program is paused")]
+    ProgramPaused,
+    #[msg("Key is already authorized for this vault")]
+    AuthorityAlreadyAuthorized,
+    #[msg("Key is not among the vault's authorized authorities")]
+    AuthorityNotAuthorized,
+    #[msg("Vault already authorizes the maximum number of authorities")]
+    TooManyAuthorities,
+    #[msg("Liquidation penalty exceeds the allowed maximum")]
+    InvalidLiquidationPenalty,
+    #[msg("Vault has a withdrawal cooldown; request the withdrawal first")]
+    WithdrawalCooldownActive,
+    #[msg("Withdrawal cooldown exceeds the allowed maximum")]
+    InvalidWithdrawalCooldown,
+    #[msg("Lowering the withdrawal cooldown requires admin approval")]
+    CooldownDecreaseNeedsApproval,
+    #[msg("Vault has no pending withdrawal")]
+    NoPendingWithdrawal,
+    #[msg("Pending withdrawal is still cooling down")]
+    WithdrawalStillCoolingDown,
+    #[msg("Lock record does not hold that much collateral")]
+    InsufficientLockRecordBalance,
+    #[msg("Lock expiry must be in the future")]
+    InvalidLockExpiry,
+    #[msg("Lock has no expiry or has not reached it")]
+    LockNotExpired,
+    #[msg("Withdrawal fee exceeds the allowed maximum")]
+    InvalidWithdrawalFee,
+    #[msg("An insurance draw must state its reason")]
+    MissingInsuranceDrawReason,
+    #[msg("Vault already stores its canonical bump")]
+    VaultBumpAlreadyCanonical,
+    #[msg("Escrow expiry must be in the future")]
+    InvalidEscrowExpiry,
+    #[msg("Escrowed transfer has expired")]
+    EscrowExpired,
+    #[msg("Vault token account holds nothing beyond the recorded balance")]
+    NoBalanceSurplus,
+    #[msg("Mint is not the configured collateral mint")]
+    InvalidCollateralMint,
+    #[msg("Collateral mint is already set")]
+    CollateralMintAlreadySet,
+    #[msg("Mint has a freeze authority and the mint policy does not allow one")]
+    MintFreezeAuthorityNotAllowed,
+    #[msg("Mint charges a transfer fee above the mint policy's maximum")]
+    MintTransferFeeNotAllowed,
+    #[msg("Mint carries an extension that could move, block or hook vault transfers")]
+    MintExtensionNotAllowed,
+    #[msg("Mint transfer fee cap exceeds 100%")]
+    InvalidMintPolicy,
+    #[msg("Lock would leave less than the configured minimum free balance")]
+    FreeBalanceBelowMinimum,
+    #[msg("Haircut must be at most 10000 basis points")]
+    InvalidHaircut,
+    #[msg("Lock would exceed the vault's collateral value after its mint's haircut")]
+    HaircutExceeded,
+    #[msg("SOL deposits and withdrawals need a wrapped SOL vault")]
+    NotWrappedSol,
+    #[msg("Deposit is below the configured minimum")]
+    DepositBelowMinimum,
+    #[msg("Withdrawal is below the configured minimum and leaves a balance behind")]
+    WithdrawalBelowMinimum,
+    #[msg("Vault is frozen by its owner")]
+    VaultFrozen,
+    #[msg("Vault is not frozen")]
+    VaultNotFrozen,
+    #[msg("Unfreeze has not been requested or its delay has not elapsed")]
+    UnfreezeNotDue,
+    #[msg("Force unlock is still within its announcement delay")]
+    ForceUnlockNotDue,
+    #[msg("Keeper reward exceeds the allowed maximum")]
+    InvalidKeeperReward,
+    #[msg("Vault is already mid-operation; re-entrant calls are refused")]
+    VaultBusy,
+    #[msg("Memo is empty or longer than MAX_MEMO_LEN")]
+    InvalidMemo,
+    #[msg("A memo needs the memo program account")]
+    MemoProgramMissing,
+    #[msg("A withdrawal fee needs the mint's treasury account")]
+    TreasuryMissing,
+    #[msg("Amount exceeds the locked collateral no lock record holds; move recorded collateral through its record")]
+    LockedUnderRecord,
+    #[msg("Vault still has open lock records, escrowed transfers or force unlocks")]
+    VaultHasOpenRecords,
+}
@@ -0,0 +1,710 @@
+//! Events emitted by the program
+//!
+//! Every event naming a vault carries `sequence`, the vault's
+//! `event_sequence` after the event: each event takes the vault's next number,
+//! so an indexer that sees a vault jump from N to N + 2 missed one, and one
+//! that sees N after N + 1 received them out of order.
+
+use anchor_lang::prelude::*;
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VaultInitialized {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub token_account: Pubkey,
+    pub sub_account_id: u16,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DepositEvent {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    /// Signed for the deposit's tokens: `user` itself, or another wallet through `deposit_for`
+    pub depositor: Pubkey,
+    pub amount: u64,
+    pub new_total_balance: u64,
+    pub new_available_balance: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WithdrawEvent {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub amount: u64,
+    pub new_total_balance: u64,
+    pub new_available_balance: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CollateralLocked {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub amount: u64,
+    pub new_available_balance: u64,
+    pub new_locked_balance: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CollateralUnlocked {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub amount: u64,
+    pub new_available_balance: u64,
+    pub new_locked_balance: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CollateralTransferred {
+    pub source_user: Pubkey,
+    pub destination_user: Pubkey,
+    pub source_vault: Pubkey,
+    pub destination_vault: Pubkey,
+    pub amount: u64,
+    /// Debited from the source's locked balance; otherwise from its available one
+    pub from_locked: bool,
+    pub source_sequence: u64,
+    pub destination_sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConfigUpdated {
+    pub admin: Pubkey,
+    pub max_transaction_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LockRatioUpdated {
+    pub admin: Pubkey,
+    /// 0 when locks are no longer capped
+    pub max_lock_ratio_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PauseUpdated {
+    pub admin: Pubkey,
+    pub paused: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LiquidationPenaltyUpdated {
+    pub admin: Pubkey,
+    pub liquidation_penalty_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConfigLayoutMigrated {
+    pub config: Pubkey,
+    pub payer: Pubkey,
+    pub old_size: u64,
+    pub new_size: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LimitOverrideApproved {
+    pub admin: Pubkey,
+    pub vault: Pubkey,
+    pub amount: u64,
+    pub max_transaction_amount: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ForeignTokensRecovered {
+    pub admin: Pubkey,
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+    pub source: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AuthorityRotated {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub old_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TokenDelegateRevoked {
+    pub admin: Pubkey,
+    pub vault: Pubkey,
+    pub token_account: Pubkey,
+    pub delegate: Pubkey,
+    pub delegated_amount: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VaultLayoutMigrated {
+    pub vault: Pubkey,
+    pub payer: Pubkey,
+    pub old_size: u64,
+    pub new_size: u64,
+    /// The vault's event sequence; see `Vault::next_sequence`
+    pub sequence: u64,
+    /// Counters on the vault count from here
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VaultSweptDormant {
+    pub admin: Pubkey,
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub dormant_funds: Pubkey,
+    pub amount: u64,
+    /// The vault's `last_updated` before the sweep
+    pub inactive_since: i64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DormantFundsReclaimed {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub dormant_funds: Pubkey,
+    pub amount: u64,
+    pub swept_at: i64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// A vault and its token account were closed; `rent_reclaimed` lamports went
+/// to `rent_destination`
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VaultClosed {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub token_account: Pubkey,
+    pub rent_destination: Pubkey,
+    pub rent_reclaimed: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// A Token-2022 transfer fee withheld from a transfer the program made; the
+/// destination received `amount - fee`
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransferFeeWithheld {
+    pub mint: Pubkey,
+    pub source: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub timestamp: i64,
+}
+
+/// A key was given the vault's CPI rights alongside `authority`
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AuthorityAuthorized {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub authorized_by: Pubkey,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// A key's CPI rights over the vault were withdrawn
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AuthorityDeauthorized {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub deauthorized_by: Pubkey,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Locked collateral was seized from a vault; `amount + penalty` left it for
+/// `destination`, which received `received` of it net of any transfer fee
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VaultLiquidated {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub liquidator: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub penalty: u64,
+    pub received: u64,
+    /// Token account paid the keeper reward; `None` when no keeper was paid
+    pub keeper: Option<Pubkey>,
+    /// What `keeper` received out of the seized collateral
+    pub keeper_reward: u64,
+    pub new_total_balance: u64,
+    pub new_locked_balance: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// A realized trading loss was paid out of a vault's locked collateral to
+/// `counterparty`, which received `received` of `amount` net of any transfer fee
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LossSettled {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub counterparty: Pubkey,
+    pub amount: u64,
+    pub received: u64,
+    pub new_total_balance: u64,
+    pub new_locked_balance: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// The vault's withdrawal cooldown changed; a decrease carries the approving admin
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WithdrawalCooldownUpdated {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub old_cooldown: u32,
+    pub new_cooldown: u32,
+    pub approved_by: Option<Pubkey>,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// A withdrawal was requested and may execute from `unlocks_at`; replaces
+/// any earlier request
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WithdrawalRequested {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub amount: u64,
+    pub unlocks_at: i64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WithdrawalCancelled {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub amount: u64,
+    pub cancelled_by: Pubkey,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Collateral was locked under a lock record
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LockOpened {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub lock: Pubkey,
+    pub lock_id: u64,
+    pub authority: Pubkey,
+    pub purpose: [u8; 32],
+    pub amount: u64,
+    pub expires_at: i64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Collateral left a lock record, back to available balance or, with
+/// `destination_vault`, transferred out; the record closes at `remaining == 0`
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LockReleased {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub lock: Pubkey,
+    pub lock_id: u64,
+    pub released_by: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+    pub destination_vault: Option<Pubkey>,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// An expired lock record was closed by `expired_by`, returning `amount` to
+/// the vault's available balance
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LockExpired {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub lock: Pubkey,
+    pub lock_id: u64,
+    pub amount: u64,
+    pub expires_at: i64,
+    pub expired_by: Pubkey,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WithdrawalFeeUpdated {
+    pub admin: Pubkey,
+    pub withdrawal_fee_bps: u16,
+    pub timestamp: i64,
+}
+
+/// The protocol fee kept from a withdrawal of `amount`; the user received the
+/// rest and `treasury` received `received` of `fee`, net of any transfer fee
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeeCollected {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub treasury: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub received: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// The admin moved collected fees out of the treasury
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TreasuryWithdrawn {
+    pub admin: Pubkey,
+    pub treasury: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InsuranceFunded {
+    pub funder: Pubkey,
+    pub fund: Pubkey,
+    pub amount: u64,
+    pub received: u64,
+    pub timestamp: i64,
+}
+
+/// The admin paid `amount` out of the insurance fund outside of any settlement
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InsuranceDrawn {
+    pub admin: Pubkey,
+    pub fund: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub reason: [u8; 32],
+    pub timestamp: i64,
+}
+
+/// A loss settlement or liquidation asked for more than the vault had locked
+/// and the insurance fund paid the `shortfall`
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InsuranceShortfallCovered {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub fund: Pubkey,
+    pub destination: Pubkey,
+    pub shortfall: u64,
+    pub received: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VaultBumpRepaired {
+    pub vault: Pubkey,
+    pub previous_bump: u8,
+    pub bump: u8,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Collateral left `source_vault` for an escrow awaiting the destination
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransferInitiated {
+    pub source_user: Pubkey,
+    pub source_vault: Pubkey,
+    pub destination_vault: Pubkey,
+    pub escrow: Pubkey,
+    pub transfer_id: u64,
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub escrowed: u64,
+    pub expires_at: i64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransferAccepted {
+    pub destination_user: Pubkey,
+    pub source_vault: Pubkey,
+    pub destination_vault: Pubkey,
+    pub escrow: Pubkey,
+    pub transfer_id: u64,
+    pub amount: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// The escrow went back to the source vault's available balance, at its
+/// authority's request or because it expired
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransferCancelled {
+    pub source_user: Pubkey,
+    pub source_vault: Pubkey,
+    pub destination_vault: Pubkey,
+    pub escrow: Pubkey,
+    pub transfer_id: u64,
+    pub amount: u64,
+    pub cancelled_by: Pubkey,
+    pub expired: bool,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Tokens found in the vault token account beyond its recorded balance were
+/// credited to the available balance
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BalanceSynced {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub caller: Pubkey,
+    pub surplus: u64,
+    pub new_total_balance: u64,
+    pub new_available_balance: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// The collateral mint every vault must hold was fixed in the config
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CollateralMintSet {
+    pub admin: Pubkey,
+    pub mint: Pubkey,
+    pub decimals: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MintPolicyUpdated {
+    pub admin: Pubkey,
+    pub allow_mint_freeze_authority: bool,
+    pub max_mint_transfer_fee_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MinFreeBalanceUpdated {
+    pub admin: Pubkey,
+    /// 0 when locks no longer need to leave anything free
+    pub min_free_balance: u64,
+    pub timestamp: i64,
+}
+
+/// The haircut of `mint` was set; locks now count its balances at the rest
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HaircutUpdated {
+    pub admin: Pubkey,
+    pub mint: Pubkey,
+    pub haircut_bps: u16,
+    pub timestamp: i64,
+}
+
+/// Dust thresholds for deposits and withdrawals were set
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MinimumAmountsUpdated {
+    pub admin: Pubkey,
+    pub min_deposit: u64,
+    pub min_withdrawal: u64,
+    pub timestamp: i64,
+}
+
+/// The owner froze the vault, cancelling any unfreeze they had requested
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VaultFrozen {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// The owner asked to unfreeze; `unfreeze_vault` may complete it from
+/// `unfreezes_at` unless the vault is frozen again first
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnfreezeRequested {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub unfreezes_at: i64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VaultUnfrozen {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// The admin announced a force unlock of the vault, executable from
+/// `executable_at` unless one of its authorities cancels it first
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ForceUnlockAnnounced {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub admin: Pubkey,
+    pub amount: u64,
+    pub executable_at: i64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// An announced force unlock ran; `amount` is what was still locked to return,
+/// at most the announced amount
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ForceUnlockExecuted {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub admin: Pubkey,
+    pub amount: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ForceUnlockCancelled {
+    pub user: Pubkey,
+    pub vault: Pubkey,
+    pub cancelled_by: Pubkey,
+    pub amount: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeeperRewardUpdated {
+    pub admin: Pubkey,
+    pub keeper_reward_bps: u16,
+    pub timestamp: i64,
+}
@@ -0,0 +1,21 @@
+//! Types shared by the on-chain program, the backend and tests.
+//!
+//! The program re-exports everything here, so account layouts, event
+//! payloads, error codes and PDA seeds are defined exactly once. Off-chain
+//! consumers deserialize program data with these types instead of keeping
+//! their own copies.
+
+use anchor_lang::prelude::*;
+
+pub mod error;
+pub mod events;
+pub mod seeds;
+pub mod state;
+
+pub use error::VaultError;
+pub use events::*;
+pub use seeds::*;
+pub use state::*;
+
+// Accounts defined here are owned by this program id
+declare_id!("CVault111111111111111111111111111111111111111");
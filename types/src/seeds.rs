@@ -0,0 +1,43 @@
+//! PDA seed prefixes
+
+/// Seed prefix of the per-user vault PDA: `[VAULT_SEED, user]`
+pub const VAULT_SEED: &[u8] = b"vault";
+
+/// Seed prefix of the vault's token account PDA: `[TOKEN_SEED, vault]`
+pub const TOKEN_SEED: &[u8] = b"token";
+
+/// Seed of the global program config PDA: `[CONFIG_SEED]`
+pub const CONFIG_SEED: &[u8] = b"config";
+
+/// Seed prefix of a swept vault's dormant-funds record: `[DORMANT_SEED, vault]`
+pub const DORMANT_SEED: &[u8] = b"dormant";
+
+/// Seed prefix of the token account holding a swept vault's funds: `[DORMANT_TOKEN_SEED, dormant_funds]`
+pub const DORMANT_TOKEN_SEED: &[u8] = b"dormant_token";
+
+/// Seed prefix of a lock record: `[LOCK_SEED, vault, lock_id as little-endian u64]`
+pub const LOCK_SEED: &[u8] = b"lock";
+
+/// Seed prefix of the protocol treasury's token account for a mint, owned by
+/// the config PDA: `[TREASURY_SEED, mint]`
+pub const TREASURY_SEED: &[u8] = b"treasury";
+
+/// Seed prefix of the insurance fund's token account for a mint, owned by the
+/// config PDA: `[INSURANCE_SEED, mint]`
+pub const INSURANCE_SEED: &[u8] = b"insurance";
+
+/// Seed prefix of an escrowed transfer: `[ESCROW_SEED, source_vault, transfer_id as little-endian u64]`
+pub const ESCROW_SEED: &[u8] = b"escrow";
+
+/// Seed prefix of the token account holding an escrowed transfer's funds: `[ESCROW_TOKEN_SEED, escrow]`
+pub const ESCROW_TOKEN_SEED: &[u8] = b"escrow_token";
+
+/// Seed prefix of a mint's collateral settings, such as its haircut: `[ASSET_CONFIG_SEED, mint]`
+pub const ASSET_CONFIG_SEED: &[u8] = b"asset_config";
+
+/// Seed prefix of the temporary wSOL account a SOL deposit or withdrawal wraps
+/// through, closed before the instruction returns: `[WRAPPED_SOL_SEED, vault]`
+pub const WRAPPED_SOL_SEED: &[u8] = b"wrapped_sol";
+
+/// Seed prefix of an admin's announced force unlock of a vault: `[FORCE_UNLOCK_SEED, vault]`
+pub const FORCE_UNLOCK_SEED: &[u8] = b"force_unlock";
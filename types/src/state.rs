@@ -0,0 +1,509 @@
+//! Program account layouts
+
+use anchor_lang::prelude::*;
+use crate::error::VaultError;
+
+#[account]
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Vault {
+    pub user: Pubkey,                    // Owner of the vault
+    pub token_account: Pubkey,          // Associated token account
+    pub bump: u8,                       // PDA bump seed
+    pub total_balance: u64,            // Total USDT balance
+    pub locked_balance: u64,           // Locked for positions
+    pub available_balance: u64,        // Available for withdrawal
+    pub last_updated: i64,             // Last update timestamp
+    pub is_active: bool,              // Vault status
+    pub authority: Pubkey,             // Authorized programs for CPI calls
+    pub created_at: i64,               // Creation timestamp; 0 for vaults migrated from the legacy layout
+    pub counters_since: i64,           // When the counters below started counting
+    pub deposit_count: u64,            // Deposits since counters_since
+    pub withdraw_count: u64,           // Withdrawals since counters_since
+    pub total_deposited: u64,          // Lifetime deposit volume since counters_since
+    pub total_withdrawn: u64,          // Lifetime withdrawal volume since counters_since
+    pub authorized_authorities: Vec<Pubkey>, // Further keys with the same CPI rights as authority
+    pub withdrawal_cooldown: u32,      // Seconds a withdrawal waits after its request; 0 for direct withdrawals
+    pub pending_withdrawal: u64,       // Requested withdrawal amount; 0 for none
+    pub withdrawal_unlocks_at: i64,    // When the pending withdrawal may execute
+    pub event_sequence: u64,           // Events emitted for this vault; see `next_sequence`
+    pub sub_account_id: u16,           // Which of the user's isolated vaults this is; 0 for the first
+    pub frozen: bool,                  // Frozen by the owner: no withdrawals or new locks
+    pub unfreeze_requested_at: i64,    // When the owner asked to unfreeze; 0 for no request
+    pub processing: bool,              // Set while an instruction has a token CPI in flight; see `begin_processing`
+    pub recorded_locked_balance: u64,  // Part of locked_balance held under lock records
+    pub open_records: u32,             // Lock records, escrowed transfers and force unlocks drawing on this vault
+}
+
+impl Vault {
+    /// Allocated account size: discriminator and fields with no authorized
+    /// authorities. Vaults allocated before `event_sequence`,
+    /// `sub_account_id`, the freeze fields, `processing`,
+    /// `recorded_locked_balance` or `open_records` are short and grow through
+    /// `migrate_vault_layout`.
+    pub const SIZE: usize = 8 + 32 + 32 + 1 + 8 + 8 + 8 + 8 + 1 + 32 + 6 * 8 + 4 + 4 + 8 + 8 + 8 + 2 + 1 + 8 + 1 + 8 + 4;
+    
+    /// Longest withdrawal cooldown a vault may set: 30 days
+    pub const MAX_WITHDRAWAL_COOLDOWN: u32 = 30 * 24 * 60 * 60;
+    
+    /// How long a self-frozen vault stays frozen after its owner asks to
+    /// unfreeze: 48 hours, for the owner to notice a request they did not make
+    pub const UNFREEZE_DELAY: i64 = 48 * 60 * 60;
+    
+    /// Keys that may be authorized besides `authority`
+    pub const MAX_AUTHORIZED_AUTHORITIES: usize = 4;
+    
+    /// Size of vaults created before the counters; they grow through `migrate_vault_layout`
+    pub const LEGACY_SIZE: usize = 8 + 32 + 32 + 1 + 8 + 8 + 8 + 8 + 1 + 32 + 24;
+    
+    /// Allocated size of a vault authorizing `authorities` keys besides
+    /// `authority`; the account grows and shrinks with the list
+    pub fn size_with_authorities(authorities: usize) -> usize {
+        Self::SIZE + authorities * 32
+    }
+    
+    /// Decode account data of any layout; fields a vault predates read as
+    /// zero, so a legacy vault reads with zeroed counters, `counters_since == 0`,
+    /// no authorized authorities, no withdrawal cooldown, no events counted,
+    /// as sub-account 0, unfrozen, not processing, with nothing locked under
+    /// lock records and no open records counted
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        // Trailing bytes are ignored, so padding by more than a layout lacks is harmless
+        let mut padded = data.to_vec();
+        padded.resize(data.len() + Self::SIZE, 0);
+        Self::try_deserialize(&mut padded.as_slice())
+    }
+    
+    /// Seed following the user in the vault PDA, `[VAULT_SEED, user, seed]`:
+    /// empty for sub-account 0, which keeps the address vaults had before
+    /// sub-accounts, and the id's little-endian bytes for any other
+    pub fn sub_account_seed(sub_account_id: u16) -> Vec<u8> {
+        if sub_account_id == 0 {
+            Vec::new()
+        } else {
+            sub_account_id.to_le_bytes().to_vec()
+        }
+    }
+    
+    /// Allocated size this vault's layout needs; smaller accounts need
+    /// `migrate_vault_layout`
+    pub fn required_size(&self) -> usize {
+        Self::size_with_authorities(self.authorized_authorities.len())
+    }
+    
+    /// Count an event for this vault, returning the `sequence` it carries
+    pub fn next_sequence(&mut self) -> Result<u64> {
+        self.event_sequence = self.event_sequence.checked_add(1).ok_or(VaultError::Overflow)?;
+        Ok(self.event_sequence)
+    }
+    
+    /// Whether `key` may lock, unlock and transfer this vault's collateral
+    pub fn is_authorized(&self, key: &Pubkey) -> bool {
+        *key == self.authority || self.authorized_authorities.contains(key)
+    }
+    
+    /// Locked collateral no lock record holds; unlocks, transfers,
+    /// liquidations and settlements that name no record move only this part
+    pub fn unrecorded_locked_balance(&self) -> u64 {
+        self.locked_balance.saturating_sub(self.recorded_locked_balance)
+    }
+    
+    /// Refuse moving `amount` of locked collateral without naming a lock
+    /// record when it would reach into what records hold
+    pub fn check_unrecorded_locked(&self, amount: u64) -> Result<()> {
+        require!(self.locked_balance >= amount, VaultError::InsufficientLockedBalance);
+        require!(self.unrecorded_locked_balance() >= amount, VaultError::LockedUnderRecord);
+        Ok(())
+    }
+    
+    /// Count `amount` of the locked balance as held under a lock record
+    pub fn record_lock(&mut self, amount: u64) -> Result<()> {
+        self.recorded_locked_balance = self.recorded_locked_balance.checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        Ok(())
+    }
+    
+    /// Stop counting `amount` as held under lock records, ahead of it
+    /// leaving the locked balance
+    pub fn release_recorded_lock(&mut self, amount: u64) -> Result<()> {
+        self.recorded_locked_balance = self.recorded_locked_balance.checked_sub(amount)
+            .ok_or(VaultError::InsufficientLockedBalance)?;
+        Ok(())
+    }
+    
+    /// Count a lock record, escrowed transfer or force unlock drawing on this
+    /// vault, which must close before the vault can
+    pub fn count_record_opened(&mut self) -> Result<()> {
+        self.open_records = self.open_records.checked_add(1).ok_or(VaultError::Overflow)?;
+        Ok(())
+    }
+    
+    /// Stop counting one that has closed; saturating, as records opened
+    /// before the vault counted them were never counted
+    pub fn count_record_closed(&mut self) {
+        self.open_records = self.open_records.saturating_sub(1);
+    }
+    
+    /// Whether withdrawals must be requested and wait out the cooldown
+    pub fn has_withdrawal_cooldown(&self) -> bool {
+        self.withdrawal_cooldown > 0
+    }
+    
+    /// Whether a requested withdrawal may execute at `now`
+    pub fn withdrawal_unlocked(&self, now: i64) -> bool {
+        self.pending_withdrawal > 0 && now >= self.withdrawal_unlocks_at
+    }
+    
+    /// Whether a requested unfreeze may complete at `now`
+    pub fn unfreeze_due(&self, now: i64) -> bool {
+        self.frozen
+            && self.unfreeze_requested_at != 0
+            && now >= self.unfreeze_requested_at.saturating_add(Self::UNFREEZE_DELAY)
+    }
+    
+    /// Whether the counters are kept; false for a legacy vault not yet migrated
+    pub fn counters_tracked(&self) -> bool {
+        self.counters_since != 0
+    }
+    
+    pub fn record_deposit(&mut self, amount: u64) -> Result<()> {
+        self.deposit_count = self.deposit_count.checked_add(1).ok_or(VaultError::Overflow)?;
+        self.total_deposited = self.total_deposited.checked_add(amount).ok_or(VaultError::Overflow)?;
+        Ok(())
+    }
+    
+    pub fn record_withdrawal(&mut self, amount: u64) -> Result<()> {
+        self.withdraw_count = self.withdraw_count.checked_add(1).ok_or(VaultError::Overflow)?;
+        self.total_withdrawn = self.total_withdrawn.checked_add(amount).ok_or(VaultError::Overflow)?;
+        Ok(())
+    }
+    
+    /// Critical invariant: available_balance + locked_balance == total_balance,
+    /// with recorded_locked_balance within locked_balance
+    /// 
+    /// Every instruction that changes balances checks it before returning, so
+    /// a math bug aborts the transaction instead of corrupting the books.
+    pub fn validate_invariant(&self) -> Result<()> {
+        let calculated_total = self.available_balance.checked_add(self.locked_balance)
+            .ok_or(VaultError::Overflow)?;
+        require!(calculated_total == self.total_balance, VaultError::InvariantViolated);
+        require!(self.recorded_locked_balance <= self.locked_balance, VaultError::InvariantViolated);
+        Ok(())
+    }
+    
+    pub fn balances(&self) -> VaultBalances {
+        VaultBalances::from(self)
+    }
+}
+
+/// Global settings shared by every vault, PDA seeds `[b"config"]`
+#[account]
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProgramConfig {
+    pub admin: Pubkey,                 // May change config and co-sign over-limit movements
+    pub max_transaction_amount: u64,   // Cap on a single withdrawal or transfer
+    pub bump: u8,
+    pub max_lock_ratio_bps: u16,       // Cap on locked / total balance after a lock; 0 for no cap
+    pub paused: bool,                  // Halts every instruction moving vault funds; see `set_pause`
+    pub liquidation_penalty_bps: u16,  // Seized on top of a liquidated amount, in basis points of it
+    pub withdrawal_fee_bps: u16,       // Kept from each withdrawal for the treasury, in basis points of it
+    pub collateral_mint: Pubkey,       // The only mint vaults, treasuries and insurance funds may hold; default until set
+    pub collateral_decimals: u8,       // Decimals of `collateral_mint`
+    pub allow_mint_freeze_authority: bool, // Accept a collateral mint that has a freeze authority
+    pub max_mint_transfer_fee_bps: u16,    // Highest Token-2022 transfer fee a collateral mint may charge
+    pub min_free_balance: u64,         // Available balance a lock must leave a vault, in base units; 0 for no floor
+    pub min_deposit: u64,              // Smallest deposit accepted, in base units; 0 for no minimum
+    pub min_withdrawal: u64,           // Smallest withdrawal accepted short of emptying the available balance; 0 for no minimum
+    pub keeper_reward_bps: u16,        // Paid out of each liquidation's seized collateral to its keeper, in basis points of it
+}
+
+impl ProgramConfig {
+    /// Allocated account size: discriminator and fields (118 bytes) plus 3 spare.
+    /// Existing configs were created at this size, so it must not shrink;
+    /// configs of any smaller size grow through `migrate_config_layout`.
+    pub const SIZE: usize = 8 + 32 + 8 + 1 + 2 + 1 + 2 + 2 + 32 + 1 + 1 + 2 + 8 + 8 + 8 + 2 + 3;
+    
+    /// Size of configs created before the lock ratio; they grow through `migrate_config_layout`
+    pub const LEGACY_SIZE: usize = 8 + 32 + 8 + 1;
+    
+    /// `max_lock_ratio_bps` of a vault that is entirely locked
+    pub const MAX_LOCK_RATIO_BPS: u16 = 10_000;
+    
+    /// Highest `liquidation_penalty_bps` the admin may set
+    pub const MAX_LIQUIDATION_PENALTY_BPS: u16 = 5_000;
+    
+    /// Highest `keeper_reward_bps` the admin may set
+    pub const MAX_KEEPER_REWARD_BPS: u16 = 1_000;
+    
+    /// Highest `withdrawal_fee_bps` the admin may set
+    pub const MAX_WITHDRAWAL_FEE_BPS: u16 = 1_000;
+    
+    /// Highest `max_mint_transfer_fee_bps` the admin may set; Token-2022 fees never exceed it
+    pub const MAX_MINT_TRANSFER_FEE_BPS: u16 = 10_000;
+    
+    /// Decode account data of any earlier layout; a legacy config reads with
+    /// no lock ratio cap, free balance floor or minimum amounts, unpaused,
+    /// without a liquidation penalty, keeper reward or withdrawal fee, with no
+    /// collateral mint set and under the strictest mint policy
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() >= Self::SIZE - 3 {
+            return Self::try_deserialize(&mut &data[..]);
+        }
+        let mut padded = data.to_vec();
+        padded.resize(Self::SIZE, 0);
+        Self::try_deserialize(&mut padded.as_slice())
+    }
+    
+    /// Whether a vault may hold `locked` of its `total` balance locked
+    pub fn lock_ratio_allows(&self, locked: u64, total: u64) -> bool {
+        self.max_lock_ratio_bps == 0
+            || locked as u128 * Self::MAX_LOCK_RATIO_BPS as u128 <= total as u128 * self.max_lock_ratio_bps as u128
+    }
+    
+    /// Whether a vault left with `available` after a lock keeps the free
+    /// balance floor; a vault holding less than the floor may lock nothing
+    pub fn free_balance_allows(&self, available: u64) -> bool {
+        available >= self.min_free_balance
+    }
+    
+    /// Whether a deposit of `amount` clears the dust threshold
+    pub fn deposit_allows(&self, amount: u64) -> bool {
+        amount >= self.min_deposit
+    }
+    
+    /// Whether withdrawing `amount` of `available` clears the dust threshold;
+    /// taking everything that is left always does, so dust is never stranded
+    pub fn withdrawal_allows(&self, amount: u64, available: u64) -> bool {
+        amount >= self.min_withdrawal || amount == available
+    }
+    
+    /// Penalty due on liquidating `amount`, rounded down
+    pub fn liquidation_penalty(&self, amount: u64) -> u64 {
+        (amount as u128 * self.liquidation_penalty_bps as u128 / 10_000) as u64
+    }
+    
+    /// Keeper's share of `seized` collateral, rounded down
+    pub fn keeper_reward(&self, seized: u64) -> u64 {
+        (seized as u128 * self.keeper_reward_bps as u128 / 10_000) as u64
+    }
+    
+    /// Treasury's share of withdrawing `amount`, rounded down in the user's favour
+    pub fn withdrawal_fee(&self, amount: u64) -> u64 {
+        (amount as u128 * self.withdrawal_fee_bps as u128 / 10_000) as u64
+    }
+    
+    /// Whether the admin has set the collateral mint; vaults cannot be opened before
+    pub fn has_collateral_mint(&self) -> bool {
+        self.collateral_mint != Pubkey::default()
+    }
+    
+    /// Whether `mint` with `decimals` is the configured collateral mint
+    pub fn is_collateral_mint(&self, mint: &Pubkey, decimals: u8) -> bool {
+        self.has_collateral_mint() && self.collateral_mint == *mint && self.collateral_decimals == decimals
+    }
+    
+    /// Refuse a mint the mint policy does not allow: one with a freeze
+    /// authority unless explicitly allowed, or one charging more than
+    /// `max_mint_transfer_fee_bps` on transfers
+    pub fn check_mint_policy(&self, has_freeze_authority: bool, transfer_fee_bps: u16) -> Result<()> {
+        require!(!has_freeze_authority || self.allow_mint_freeze_authority,
+                 VaultError::MintFreezeAuthorityNotAllowed);
+        require!(transfer_fee_bps <= self.max_mint_transfer_fee_bps, VaultError::MintTransferFeeNotAllowed);
+        Ok(())
+    }
+}
+
+/// Funds swept out of a dormant vault, held for its owner to reclaim,
+/// PDA seeds `[DORMANT_SEED, vault]`.
+///
+/// The funds sit in a token account of their own, `[DORMANT_TOKEN_SEED, dormant_funds]`,
+/// owned by this PDA. Both accounts are closed on reclaim, refunding their rent to `payer`.
+#[account]
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DormantFunds {
+    pub vault: Pubkey,
+    pub user: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub inactive_since: i64,           // The vault's last_updated when it was swept
+    pub swept_at: i64,
+    pub payer: Pubkey,                 // Paid the rent of both accounts and gets it back
+    pub bump: u8,
+}
+
+impl DormantFunds {
+    pub const SIZE: usize = 8 + 32 + 32 + 32 + 8 + 8 + 8 + 32 + 1; // Discriminator + fields
+    
+    /// A vault must have been untouched this long before it can be swept,
+    /// whatever inactivity period the backend's policy uses
+    pub const MIN_DORMANCY_SECONDS: i64 = 180 * 24 * 60 * 60;
+    
+    pub fn is_dormant(vault: &Vault, now: i64) -> bool {
+        now.saturating_sub(vault.last_updated) >= Self::MIN_DORMANCY_SECONDS
+    }
+}
+
+/// One lock of a vault's collateral, PDA seeds `[LOCK_SEED, vault, lock_id]`
+/// 
+/// Its amount is part of the vault's `locked_balance`; the record tells which
+/// authority holds it and for what, so several programs or positions locking
+/// the same vault stay apart. Closed once fully released or expired,
+/// refunding its rent to `payer`.
+#[account]
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LockRecord {
+    pub vault: Pubkey,
+    pub lock_id: u64,                  // Chosen by the locking authority, unique per vault
+    pub authority: Pubkey,             // The authority that opened the lock
+    pub purpose: [u8; 32],             // Caller-defined tag, e.g. a position or market id
+    pub amount: u64,                   // Still locked under this record
+    pub created_at: i64,
+    pub payer: Pubkey,                 // Paid the rent and gets it back on close
+    pub bump: u8,
+    pub expires_at: i64,               // From then anyone may return the amount to available; 0 for never
+}
+
+impl LockRecord {
+    pub const SIZE: usize = 8 + 32 + 8 + 32 + 32 + 8 + 8 + 32 + 1 + 8; // Discriminator + fields
+    
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at != 0 && now >= self.expires_at
+    }
+    
+    /// Whether `key` may release or transfer this lock: the authority that
+    /// opened it, or the vault's `authority` should that key lose its rights
+    pub fn can_release(&self, vault: &Vault, key: &Pubkey) -> bool {
+        (*key == self.authority && vault.is_authorized(key)) || *key == vault.authority
+    }
+}
+
+/// A transfer between vaults held in escrow until the destination accepts it,
+/// PDA seeds `[ESCROW_SEED, source_vault, transfer_id]`
+/// 
+/// The funds have left the source vault's books and sit in a token account of
+/// their own, `[ESCROW_TOKEN_SEED, escrow]`, owned by this PDA. Accepting
+/// credits them to the destination; cancelling, or anyone once `expires_at`
+/// passes, returns them to the source. Both accounts then close, refunding
+/// their rent to `payer`.
+#[account]
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EscrowedTransfer {
+    pub source_vault: Pubkey,
+    pub destination_vault: Pubkey,
+    pub transfer_id: u64,              // Chosen by the initiating authority, unique per source vault
+    pub authority: Pubkey,             // The authority that initiated the transfer
+    pub mint: Pubkey,
+    pub amount: u64,                   // What reached the escrow token account
+    pub created_at: i64,
+    pub expires_at: i64,               // Acceptance closes and the source may take the funds back
+    pub payer: Pubkey,                 // Paid the rent of both accounts and gets it back
+    pub bump: u8,
+}
+
+impl EscrowedTransfer {
+    pub const SIZE: usize = 8 + 32 + 32 + 8 + 32 + 32 + 8 + 8 + 8 + 32 + 1; // Discriminator + fields
+    
+    pub fn is_expired(&self, now: i64) -> bool {
+        now >= self.expires_at
+    }
+    
+    /// Whether `key` may cancel before expiry: the authority that initiated
+    /// the transfer, or the source vault's `authority` should that key lose
+    /// its rights
+    pub fn can_cancel(&self, source_vault: &Vault, key: &Pubkey) -> bool {
+        (*key == self.authority && source_vault.is_authorized(key)) || *key == source_vault.authority
+    }
+}
+
+/// The three balance fields of a vault account
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VaultBalances {
+    pub total_balance: u64,
+    pub locked_balance: u64,
+    pub available_balance: u64,
+}
+
+impl VaultBalances {
+    /// `(total, locked, available)` in the signed representation used off-chain
+    pub fn as_signed(&self) -> (i64, i64, i64) {
+        (self.total_balance as i64, self.locked_balance as i64, self.available_balance as i64)
+    }
+}
+
+impl From<&Vault> for VaultBalances {
+    fn from(vault: &Vault) -> Self {
+        Self {
+            total_balance: vault.total_balance,
+            locked_balance: vault.locked_balance,
+            available_balance: vault.available_balance,
+        }
+    }
+}
+
+/// Collateral settings of a mint, PDA seeds `[ASSET_CONFIG_SEED, mint]`
+/// 
+/// A vault's balance counts towards locks at its value after the haircut:
+/// 0 for a stable asset, 1_000 for one valued at 90%. Locks are refused once
+/// they would exceed that value, so a volatile asset keeps a buffer that
+/// absorbs price moves before the locked amount is under-collateralised.
+#[account]
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AssetConfig {
+    pub mint: Pubkey,
+    pub haircut_bps: u16,              // Discount on the mint's balances as collateral, in basis points
+    pub bump: u8,
+}
+
+impl AssetConfig {
+    pub const SIZE: usize = 8 + 32 + 2 + 1; // Discriminator + fields
+    
+    /// Highest `haircut_bps`; a mint at it counts for nothing
+    pub const MAX_HAIRCUT_BPS: u16 = 10_000;
+    
+    /// What `amount` of the mint is worth as collateral, rounded down
+    pub fn collateral_value(&self, amount: u64) -> u64 {
+        (amount as u128 * (Self::MAX_HAIRCUT_BPS - self.haircut_bps) as u128 / Self::MAX_HAIRCUT_BPS as u128) as u64
+    }
+    
+    /// Whether a vault may hold `locked` of its `total` balance locked
+    pub fn haircut_allows(&self, locked: u64, total: u64) -> bool {
+        locked <= self.collateral_value(total)
+    }
+}
+
+/// An admin's announced release of a vault's locked collateral, PDA seeds
+/// `[FORCE_UNLOCK_SEED, vault]`
+/// 
+/// The escape hatch for collateral stranded by a trading program that is
+/// bricked or has lost its authority key. It executes no earlier than
+/// `DELAY` after the announcement, and until then any of the vault's
+/// authorities may cancel it, which a program still in control will do.
+/// Closed on execution or cancellation, refunding its rent to `admin`.
+#[account]
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ForceUnlock {
+    pub vault: Pubkey,
+    pub admin: Pubkey,                 // Announced it and paid the rent, which it gets back
+    pub amount: u64,                   // Locked collateral to return to available
+    pub announced_at: i64,
+    pub executable_at: i64,
+    pub bump: u8,
+}
+
+impl ForceUnlock {
+    pub const SIZE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 1; // Discriminator + fields
+    
+    /// Time between announcing a force unlock and executing it: 7 days
+    pub const DELAY: i64 = 7 * 24 * 60 * 60;
+    
+    pub fn is_executable(&self, now: i64) -> bool {
+        now >= self.executable_at
+    }
+}